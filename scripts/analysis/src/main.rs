@@ -1,18 +1,33 @@
 use chrono::Local;
 use clap::Parser;
-use html_escape::encode_text;
-use lofty::config::ParseOptions;
+use dmp_common::musicbrainz as mb;
+use dmp_common::report::{self, html_text, FileRow, NavItem, PageBudget, Report, SearchTarget, StatCard};
+use dmp_common::{s3, FolderFilter};
+use lofty::config::{ParseOptions, WriteOptions};
+use lofty::file::FileType;
 use lofty::prelude::*;
 use lofty::probe::Probe;
+use lofty::tag::TagType;
+use md5::{Digest, Md5};
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs;
-use std::io::{BufWriter, Write};
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::time::Instant;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Condvar, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
 use walkdir::WalkDir;
 
+#[cfg(test)]
+mod test_support;
+#[cfg(test)]
+mod tests;
+
 // ---------------------------------------------------------------------------
 // CLI
 // ---------------------------------------------------------------------------
@@ -56,14 +71,78 @@ struct Args {
     #[arg(long)]
     quarantine_dry: bool,
 
-    /// Move all files from __QUARANTINE back to their original locations (reverses --quarantine)
+    /// Move all files from __QUARANTINE (and the other staging folders) back to their original locations (reverses --quarantine)
     #[arg(long)]
     end_quarantine: bool,
 
+    /// With --end-quarantine: only restore files moved for CATEGORY (critical, mb, discogs, ids, other, style, a rules file category, unreadable, duplicate, autofixed), per __QUARANTINE/manifest.json; combine with --only / --from / --to to pick artists
+    #[arg(long, value_name = "CATEGORY", requires = "end_quarantine")]
+    category: Option<String>,
+
+    /// Folder glob (relative to the scan root, case insensitive) that quarantine and autofix never touch; repeatable
+    #[arg(long)]
+    protect: Vec<String>,
+
+    /// Folder or file glob (relative to the scan root, case insensitive) the scan skips, on top of the scan root's .analysisignore; repeatable
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+
     /// Skip report generation entirely
     #[arg(long)]
     no_report: bool,
 
+    /// Also bundle the whole report (CSS, JS, every page) into one standalone HTML file next to the report folder, for attaching to a mail or a ticket
+    #[arg(long, conflicts_with = "no_report")]
+    single_file: bool,
+
+    /// Target size of each paginated report page, e.g. 2MB or 500KB (0 = no limit): pages end early so prolific artists don't make pages browsers choke on
+    #[arg(long, value_name = "SIZE", default_value = DEFAULT_PAGE_SIZE, value_parser = parse_page_size)]
+    page_size: usize,
+
+    /// At most N files per paginated report page (0 = no limit)
+    #[arg(long, value_name = "N", default_value = "0")]
+    page_files: usize,
+
+    /// Also write a small self-contained HTML digest (counts, deltas vs the previous digest at this path, worst artists) for emailing
+    #[arg(long)]
+    digest: Option<String>,
+
+    /// Append the run's per-category counts (timestamp, category, count, matched_count) to this CSV, e.g. for Grafana; also with --no-report
+    #[arg(long, value_name = "PATH")]
+    metrics_csv: Option<String>,
+
+    /// Don't record the run in <output-dir>/history.jsonl or chart the trends of earlier runs on index.html
+    #[arg(long)]
+    no_history: bool,
+
+    /// Also write per-category file lists and beets path queries to the report's beets/ folder
+    #[arg(long)]
+    beets_export: bool,
+
+    /// Upload the report directory to the S3 image bucket from web/.env under this key prefix and print its URL
+    #[arg(long, value_name = "PREFIX")]
+    publish_s3: Option<String>,
+
+    /// POST a JSON summary of the run (counts per category, elapsed time, report path) to this URL when the scan finishes
+    #[arg(long, value_name = "URL")]
+    notify_webhook: Option<String>,
+
+    /// Email the run's summary to this address when the scan finishes, through the SMTP server set in web/.env (SMTP_HOST, ...); repeatable
+    #[arg(long, value_name = "ADDRESS")]
+    notify_email: Vec<String>,
+
+    /// Run CMD when the scan finishes, with the path of the run's summary.json as its last argument
+    #[arg(long, value_name = "CMD", value_parser = HookCommand::parse)]
+    on_complete: Option<HookCommand>,
+
+    /// Run CMD (with the summary.json path as its last argument) when at least N files have issues; repeatable
+    #[arg(long, value_name = "N:CMD", value_parser = IssueHook::parse)]
+    on_issue_threshold: Vec<IssueHook>,
+
+    /// After the report is written, serve its folder over HTTP on PORT (default 8080, all interfaces) until Ctrl-C; with --watch, while watching
+    #[arg(long, value_name = "PORT", num_args = 0..=1, default_missing_value = "8080", conflicts_with = "no_report")]
+    serve: Option<u16>,
+
     /// Only generate critical.html + index.html
     #[arg(long)]
     only_critical: bool,
@@ -88,6 +167,58 @@ struct Args {
     #[arg(long)]
     only_other: bool,
 
+    /// Only generate style.html + index.html (needs --style)
+    #[arg(long)]
+    only_style: bool,
+
+    /// Only generate consistency.html + index.html
+    #[arg(long)]
+    only_consistency: bool,
+
+    /// Only generate albums.html + index.html
+    #[arg(long)]
+    only_albums: bool,
+
+    /// TOML file of issue rules: tag keys and requirement per field, the valid year range, and custom categories of required tags with their own pages
+    #[arg(long, value_name = "FILE")]
+    rules: Option<String>,
+
+    /// Style lints to run, comma-separated: case, explicit, spaces, feat (or "all")
+    #[arg(long, default_value = "")]
+    style: String,
+
+    /// Rewrite the artist/title tags flagged by --style (collapse spaces, drop "[Explicit]", Title Case, move "feat." to the artist)
+    #[arg(long)]
+    style_fix: bool,
+
+    /// Dry run of --style-fix: print the tag changes without writing anything
+    #[arg(long)]
+    style_fix_dry: bool,
+
+    /// Remove blank artist/title/year/genre items (key present, empty value) from every tag container
+    #[arg(long)]
+    strip_blank_tags: bool,
+
+    /// Dry run of --strip-blank-tags: print the files and fields without writing anything
+    #[arg(long)]
+    strip_blank_tags_dry: bool,
+
+    /// Repair what needs no lookup in every scanned file: remove blank tag items, cut YEAR values like "2019-05-03" down to the year, and fill YEAR from DATE (or DATE from YEAR) when a file has only one
+    #[arg(long)]
+    fix_trivial: bool,
+
+    /// Dry run of --fix-trivial: print the files and changes without writing anything
+    #[arg(long)]
+    fix_trivial_dry: bool,
+
+    /// With --fix-trivial: also write the year of the original release date (ORIGINALDATE, TDOR) into files with neither YEAR nor DATE
+    #[arg(long)]
+    normalize: bool,
+
+    /// Copy values from unusual tag containers (ID3v2 in FLAC, APEv2 on MP3, ...) into the format's canonical one
+    #[arg(long)]
+    migrate_tags: bool,
+
     /// Use beets to auto-fix missing metadata on files with issues (requires beet installed)
     #[arg(long)]
     autofix: bool,
@@ -95,6 +226,110 @@ struct Args {
     /// Dry run of --autofix: show what beets would tag without writing anything
     #[arg(long)]
     autofix_dry: bool,
+
+    /// Tag with CMD instead of beets during --autofix, e.g. "my-tagger --write {dir}": {dir} is the folder, {files} its files with issues (one argument each)
+    #[arg(long, value_name = "CMD")]
+    autofix_cmd: Option<String>,
+
+    /// TOML file describing the tagger --autofix runs instead of beets: command, dry_command, and the output patterns that mean skipped / matched
+    #[arg(long, value_name = "FILE")]
+    autofix_config: Option<String>,
+
+    /// Run N tagger processes (beet imports by default) at once during --autofix / --autofix-dry, each beet import against its own temporary library
+    #[arg(long, value_name = "N", default_value = "1", value_parser = clap::value_parser!(u16).range(1..=32))]
+    autofix_jobs: u16,
+
+    /// Tag with MusicBrainz lookups made by analysis itself instead of beets during --autofix: writes the missing MB IDs, year, artist and title with lofty, no Python needed
+    #[arg(long, conflicts_with_all = ["autofix_cmd", "autofix_config"])]
+    autofix_native: bool,
+
+    /// MusicBrainz web service --autofix-native queries instead of musicbrainz.org, e.g. a musicbrainz-docker mirror (overrides MB_URL)
+    #[arg(long, value_name = "URL")]
+    mb_url: Option<String>,
+
+    /// Exclude files ticked as handled in a previous report (JSON from its "Export handled" button)
+    #[arg(long)]
+    apply_handled: Option<String>,
+
+    /// Second library root (e.g. a portable lossy mirror) to compare against; writes mirror.html
+    #[arg(long)]
+    mirror: Option<String>,
+
+    /// Folder of .zip downloads (e.g. Bandcamp purchases) to vet before extracting them: tags are read in memory, issues are listed per archive on archives.html
+    #[arg(long, value_name = "DIR")]
+    archives: Option<String>,
+
+    /// Cluster files whose AcoustID fingerprint tags (beets chroma, Picard) match at SCORE or more (0-1, share of matching bits; flag alone = 0.9), whatever their tags and names; writes fingerprints.html
+    #[arg(long, value_name = "SCORE", num_args = 0..=1, default_missing_value = "0.9")]
+    dupe_by_fingerprint: Option<f64>,
+
+    /// Group files that are the same track by normalized artist + title (lengths within 5s), or also by AcoustID tag ("acoustid") or audio data ("audio"); writes duplicates.html
+    #[arg(long, value_name = "MODE", num_args = 0..=1, default_missing_value = "tags", value_parser = ["tags", "acoustid", "audio"])]
+    detect_duplicates: Option<String>,
+
+    /// Move every copy but the best (lossless, then bitrate, then size) of each --detect-duplicates group into a __DUPLICATES subfolder of the scan root
+    #[arg(long, requires = "detect_duplicates")]
+    quarantine_duplicates: bool,
+
+    /// Dry run of --quarantine-duplicates: print what would be moved without touching the filesystem
+    #[arg(long, requires = "detect_duplicates")]
+    quarantine_duplicates_dry: bool,
+
+    /// Also read audio properties and flag MP3s under 192 kbps, sample rates that differ within a folder, and tracks under 30s; writes quality.html
+    #[arg(long)]
+    quality: bool,
+
+    /// Don't list lone files (the only audio file in their folder) on issues.html; quarantine still routes them to __NEEDS_REVIEW
+    #[arg(long)]
+    no_lone_files: bool,
+
+    /// Scan only the files listed in FILE instead of walking the scan root: one path per line (absolute or relative to the root, e.g. a beets/*.txt export or `beet ls -p`), or the JSON of a report's "Export handled" button; "-" reads stdin
+    #[arg(long, value_name = "FILE")]
+    paths_from: Option<String>,
+
+    /// Reuse the results stored in FILE for files unchanged since (same size and mtime) and store this run's there: only new or modified files are parsed
+    #[arg(long, value_name = "FILE")]
+    cache: Option<String>,
+
+    /// Keep running after the report: watch the scan root, re-scan files as they are added, modified or removed and rewrite the report once changes settle (Ctrl-C to stop)
+    #[arg(long, conflicts_with_all = [
+        "autofix", "autofix_dry", "quarantine", "quarantine_dry", "quarantine_duplicates", "quarantine_duplicates_dry",
+        "style_fix", "style_fix_dry", "strip_blank_tags", "strip_blank_tags_dry", "fix_trivial", "fix_trivial_dry", "migrate_tags",
+        "mirror", "archives", "dupe_by_fingerprint", "detect_duplicates",
+        "paths_from", "limit", "no_report", "publish_s3", "metrics_csv",
+    ])]
+    watch: bool,
+
+    /// With --watch: seconds without further changes before re-scanning and rewriting the report
+    #[arg(long, value_name = "SECS", default_value = "5", requires = "watch")]
+    watch_debounce: u64,
+
+    /// Don't follow symlinks (symlinked files and folders are skipped); by default they are followed and files reached twice are scanned once
+    #[arg(long)]
+    no_follow_links: bool,
+
+    /// Cap read throughput across all scanner threads, in MB/s (0 = unlimited)
+    #[arg(long, default_value = "0")]
+    io_limit: f64,
+
+    /// Cap how many audio files are open at once across all scanner threads (0 = unlimited)
+    #[arg(long, default_value = "0")]
+    max_open_files: usize,
+}
+
+/// `analysis diff OLD NEW`, parsed on its own so the scan's flags stay as they are.
+#[derive(Parser, Debug)]
+#[command(name = "analysis diff", about = "Compare two scans: issues fixed, issues introduced, new unreadable files")]
+struct DiffArgs {
+    /// The earlier report folder, or its results.json (a summary.json only gives the count changes)
+    old: String,
+
+    /// The later report folder, or its results.json
+    new: String,
+
+    /// Also write the changes as a self-contained HTML page
+    #[arg(long, value_name = "PATH")]
+    html: Option<String>,
 }
 
 // ---------------------------------------------------------------------------
@@ -109,25 +344,14 @@ struct PageFlags {
     discogs: bool,
     ids: bool,
     other: bool,
+    style: bool,
+    consistency: bool,
+    albums: bool,
+    /// Custom categories from `--rules`
+    custom: bool,
 }
 
-/// Badge counts for the navigation bar.
-struct NavCounts {
-    issues: usize,
-    critical: usize,
-    mb: usize,
-    discogs: usize,
-    ids: usize,
-    other: usize,
-    // Fixed counts (for autofix delta display)
-    critical_matched: usize,
-    mb_matched: usize,
-    discogs_matched: usize,
-    ids_matched: usize,
-    other_matched: usize,
-}
-
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct FileIssue {
     path: PathBuf,
     file_size: u64,
@@ -153,12 +377,24 @@ struct FileIssue {
     missing_bpm: bool,
     missing_mood: bool,
     missing_album_art: bool,
+    unusual_tags: Option<String>,    // e.g. "APEv2 in MP3"
     // Inconsistencies
     invalid_year: Option<String>,    // the bad value
     blank_artist: bool,
     blank_title: bool,
     blank_year: bool,
     blank_genre: bool,
+    // Style (only with --style)
+    style_case: Option<String>,      // the ALL CAPS / all lowercase value(s)
+    style_explicit: bool,
+    style_spaces: Option<String>,    // which field(s): "Artist", "Title"
+    style_feat: Option<String>,      // the featured artist found in the title
+    // Custom categories (only with --rules): ids of the missing fields
+    custom_missing: Vec<String>,
+    // Audio properties (only with --quality); checked on quality.html, not an issue here
+    audio: Option<AudioInfo>,
+    // Encoded-by / encoder / encoder settings tags; shown on albums.html, not an issue
+    encoder: Option<String>,
 }
 
 /// A single field-level change made by beets autofix.
@@ -177,14 +413,6 @@ type MatchDiffs = HashMap<PathBuf, Vec<FieldMatch>>;
 /// Key = file path, value = human-readable reason extracted from beets output.
 type SkippedFiles = HashMap<PathBuf, String>;
 
-/// Fix status attached to each file entry in artist groups.
-#[derive(Debug, Clone)]
-enum FileFixStatus {
-    NoAutofix,           // autofix didn't run
-    Matched,               // all issues for this field were resolved
-    Skipped(String),     // beets attempted but found no confident match
-}
-
 impl FileIssue {
     fn has_critical(&self) -> bool {
         self.missing_artist
@@ -214,14 +442,33 @@ impl FileIssue {
             || self.missing_bpm
             || self.missing_mood
             || self.missing_album_art
+            || self.unusual_tags.is_some()
             || self.blank_genre
     }
+    fn has_style(&self) -> bool {
+        self.style_case.is_some()
+            || self.style_explicit
+            || self.style_spaces.is_some()
+            || self.style_feat.is_some()
+    }
+    fn has_custom(&self) -> bool {
+        !self.custom_missing.is_empty()
+    }
+    /// Whether the file is listed on category page `id` (built-in or `--rules`).
+    fn in_category(&self, id: &str) -> bool {
+        match CATEGORY_PAGES.iter().find(|c| c.id == id) {
+            Some(category) => (category.has_issue)(self),
+            None => rules().categories.iter().find(|c| c.id == id).is_some_and(|c| c.has_issue(self)),
+        }
+    }
     fn has_any_issue(&self) -> bool {
-        self.has_critical()
+        self.has_custom()
+            || self.has_critical()
             || self.has_mb()
             || self.has_discogs()
             || self.has_ids()
             || self.has_other()
+            || self.has_style()
     }
 }
 
@@ -237,6 +484,30 @@ fn has_tag(tags: &HashMap<String, String>, keys: &[&str]) -> bool {
     })
 }
 
+/// AcoustID (recording ID from the AcoustID lookup) tag keys.
+const ACOUSTID_TAGS: &[&str] = &["ACOUSTIC_ID", "ACOUSTIC ID", "ACOUSTID_ID", "ACOUSTID ID"];
+
+/// MusicBrainz artist ID tag keys.
+const MB_ARTIST_ID_TAGS: &[&str] = &["MUSICBRAINZ ARTIST ID", "MUSICBRAINZ_ARTISTID", "MUSICBRAINZARTISTID"];
+
+/// MusicBrainz track / recording ID tag keys.
+const MB_TRACK_ID_TAGS: &[&str] = &[
+    "MUSICBRAINZ RELEASE TRACK ID",
+    "MUSICBRAINZ_TRACKID",
+    "MUSICBRAINZTRACKID",
+    "MUSICBRAINZ_RELEASETRACKID",
+    // lofty maps Vorbis MUSICBRAINZ_TRACKID (Picard's recording ID) here
+    "MUSICBRAINZRECORDINGID",
+];
+
+/// MusicBrainz release (album) ID tag keys.
+const MB_ALBUM_ID_TAGS: &[&str] = &["MUSICBRAINZ ALBUM ID", "MUSICBRAINZ_ALBUMID", "MUSICBRAINZALBUMID", "MUSICBRAINZRELEASEID"];
+
+/// Original release date tag keys (lofty files ORIGINALDATE, ORIGINALYEAR
+/// and TDOR under `ORIGINALRELEASEDATE`). DATE needs no entry: lofty reads it
+/// as the year when YEAR is missing.
+const ORIGINAL_DATE_TAGS: &[&str] = &["ORIGINALRELEASEDATE", "ORIGINALDATE", "ORIGINALYEAR"];
+
 /// Get the value of the first matching tag key (case-insensitive).
 fn get_tag(tags: &HashMap<String, String>, keys: &[&str]) -> Option<String> {
     for k in keys {
@@ -287,16 +558,8 @@ fn collect_tags(tagged_file: &lofty::file::TaggedFile) -> HashMap<String, String
 
         // All custom / raw items
         for item in tag.items() {
-            let key = match item.key() {
-                lofty::tag::ItemKey::Unknown(s) => s.to_uppercase(),
-                other => {
-                    let mut k = format!("{:?}", other);
-                    k.make_ascii_uppercase();
-                    k
-                }
-            };
             if let lofty::tag::ItemValue::Text(val) = item.value() {
-                map.entry(key).or_insert_with(|| val.clone());
+                map.entry(item_key_name(item.key())).or_insert_with(|| val.clone());
             }
         }
     }
@@ -304,2047 +567,7180 @@ fn collect_tags(tagged_file: &lofty::file::TaggedFile) -> HashMap<String, String
     map
 }
 
+/// The key `collect_tags` files a raw item under: the uppercased name for
+/// keys lofty doesn't know, lofty's own key name (e.g. `TRACKARTIST`) otherwise.
+fn item_key_name(key: &lofty::tag::ItemKey) -> String {
+    match key {
+        lofty::tag::ItemKey::Unknown(s) => s.to_uppercase(),
+        other => {
+            let mut k = format!("{:?}", other);
+            k.make_ascii_uppercase();
+            k
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
-// Scan a single file
+// Throttled IO (--io-limit / --max-open-files)
 // ---------------------------------------------------------------------------
 
-fn scan_file(path: &Path) -> Result<(FileIssue, Vec<String>), String> {
-    let meta = fs::metadata(path).map_err(|e| e.to_string())?;
-    let file_size = meta.len();
-
-    let parse_opts = ParseOptions::new().read_properties(false);
-    let tagged_file = match Probe::open(path).map_err(|e| e.to_string())?.options(parse_opts).read() {
-        Ok(f) => f,
-        Err(e) => return Err(e.to_string()),
-    };
-
-    let has_art = tagged_file
-        .tags()
-        .iter()
-        .any(|t| t.pictures().iter().next().is_some());
-
-    let tags = collect_tags(&tagged_file);
+/// Shared by every rayon worker; set once in main when either limit is given.
+static IO_THROTTLE: OnceLock<IoThrottle> = OnceLock::new();
+
+/// Reads may run this far ahead of the rate before a worker sleeps, so small
+/// tag reads don't each pay a sleep.
+const IO_BURST: Duration = Duration::from_millis(250);
+
+struct IoThrottle {
+    /// Bytes per second (0 = unlimited)
+    rate: f64,
+    /// When the bytes handed out so far will have "drained" at `rate`
+    drained_at: Mutex<Instant>,
+    max_open: usize,
+    open: Mutex<usize>,
+    open_released: Condvar,
+}
 
-    // --- Critical ---
-    let missing_artist = !has_tag(&tags, &["ARTIST"]);
-    let missing_title = !has_tag(&tags, &["TITLE"]);
-    let missing_year = !has_tag(&tags, &["YEAR"]);
+impl IoThrottle {
+    fn new(mb_per_sec: f64, max_open: usize) -> Self {
+        Self {
+            rate: mb_per_sec * 1024.0 * 1024.0,
+            drained_at: Mutex::new(Instant::now()),
+            max_open,
+            open: Mutex::new(0),
+            open_released: Condvar::new(),
+        }
+    }
 
-    // --- MusicBrainz ---
-    let missing_mb_artist_id = !has_tag(
-        &tags,
-        &["MUSICBRAINZ ARTIST ID", "MUSICBRAINZ_ARTISTID", "MUSICBRAINZARTISTID"],
-    );
-    let missing_mb_track_id = !has_tag(
-        &tags,
-        &[
-            "MUSICBRAINZ RELEASE TRACK ID",
-            "MUSICBRAINZ_TRACKID",
-            "MUSICBRAINZTRACKID",
-            "MUSICBRAINZ_RELEASETRACKID",
-        ],
-    );
-    let missing_mb_album_id = !has_tag(
-        &tags,
-        &["MUSICBRAINZ ALBUM ID", "MUSICBRAINZ_ALBUMID", "MUSICBRAINZALBUMID", "MUSICBRAINZRELEASEID"],
-    );
+    /// Account for `bytes` just read, sleeping if the caller is ahead of the rate.
+    fn consume(&self, bytes: usize) {
+        if self.rate <= 0.0 || bytes == 0 {
+            return;
+        }
+        let wait = {
+            let mut drained_at = self.drained_at.lock().unwrap();
+            let now = Instant::now();
+            *drained_at = (*drained_at).max(now) + Duration::from_secs_f64(bytes as f64 / self.rate);
+            drained_at.saturating_duration_since(now + IO_BURST)
+        };
+        if !wait.is_zero() {
+            std::thread::sleep(wait);
+        }
+    }
 
-    // --- IDs ---
-    let missing_acoustic_id = !has_tag(&tags, &["ACOUSTIC_ID", "ACOUSTIC ID", "ACOUSTID_ID", "ACOUSTID ID"]);
-    let missing_songkong_id = !has_tag(&tags, &["SONGKONG_ID", "SONGKONGID"]);
-    let missing_bandcamp =
-        !has_tag(&tags, &["URL_BANDCAMP_ARTIST_SITE", "WWW BANDCAMP_ARTIST"]);
-    let missing_wikipedia_artist = !has_tag(&tags, &["WWW WIKIPEDIA_ARTIST"]);
+    /// Block until fewer than `max_open` files are open; the slot is freed on drop.
+    fn open_file(&self) -> OpenFilePermit<'_> {
+        if self.max_open > 0 {
+            let mut open = self.open.lock().unwrap();
+            while *open >= self.max_open {
+                open = self.open_released.wait(open).unwrap();
+            }
+            *open += 1;
+        }
+        OpenFilePermit(self)
+    }
+}
 
-    // --- Discogs ---
-    let missing_discogs_artist =
-        !has_tag(&tags, &["URL_DISCOGS_ARTIST_SITE", "WWW DISCOGS_ARTIST"]);
-    let missing_discogs_release =
-        !has_tag(&tags, &["URL_DISCOGS_RELEASE_SITE", "WWW DISCOGS_RELEASE"]);
+struct OpenFilePermit<'a>(&'a IoThrottle);
 
-    // --- Other ---
-    let missing_genre = !has_tag(&tags, &["GENRE"]);
-    let missing_bpm = !has_tag(&tags, &["BPM"]);
-    let missing_mood = !has_tag_prefix(&tags, "MOOD_");
-    let missing_album_art = !has_art;
+impl Drop for OpenFilePermit<'_> {
+    fn drop(&mut self) {
+        if self.0.max_open > 0 {
+            *self.0.open.lock().unwrap() -= 1;
+            self.0.open_released.notify_one();
+        }
+    }
+}
 
-    // --- Inconsistency: blank fields ---
-    let blank_artist =
-        tag_key_exists(&tags, &["ARTIST"]) && !has_tag(&tags, &["ARTIST"]);
-    let blank_title =
-        tag_key_exists(&tags, &["TITLE"]) && !has_tag(&tags, &["TITLE"]);
-    let blank_year =
-        tag_key_exists(&tags, &["YEAR"]) && !has_tag(&tags, &["YEAR"]);
-    let blank_genre =
-        tag_key_exists(&tags, &["GENRE"]) && !has_tag(&tags, &["GENRE"]);
+/// File reader that charges every read to `IO_THROTTLE`.
+struct ThrottledFile(fs::File);
 
-    // --- Inconsistency: invalid year ---
-    let year_value = get_tag(&tags, &["YEAR"]);
-    let invalid_year = year_value.as_ref().and_then(|y| {
-        let trimmed = y.trim();
-        match trimmed.parse::<i32>() {
-            Ok(n) if n <= 0 || n >= 2030 => Some(trimmed.to_string()),
-            Err(_) => Some(trimmed.to_string()),
-            _ => None,
+impl Read for ThrottledFile {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.0.read(buf)?;
+        if let Some(throttle) = IO_THROTTLE.get() {
+            throttle.consume(n);
         }
-    });
+        Ok(n)
+    }
+}
 
-    let tag_keys: Vec<String> = tags.keys().cloned().collect();
-    Ok((FileIssue {
-        path: path.to_path_buf(),
-        file_size,
-        missing_artist,
-        missing_title,
-        missing_year,
-        missing_mb_artist_id,
-        missing_mb_track_id,
-        missing_mb_album_id,
-        missing_acoustic_id,
-        missing_songkong_id,
-        missing_bandcamp,
-        missing_discogs_artist,
-        missing_discogs_release,
-        missing_wikipedia_artist,
-        missing_genre,
-        missing_bpm,
-        missing_mood,
-        missing_album_art,
-        invalid_year,
-        blank_artist,
-        blank_title,
-        blank_year,
-        blank_genre,
-    }, tag_keys))
+impl Seek for ThrottledFile {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.0.seek(pos)
+    }
 }
 
+/// `Probe::open(path)...read()`, but through the IO throttle when one is set.
+fn read_tagged_file(path: &Path, parse_opts: ParseOptions) -> Result<lofty::file::TaggedFile, String> {
+    let _permit = IO_THROTTLE.get().map(|t| t.open_file());
+    let file = fs::File::open(path).map_err(|e| e.to_string())?;
+    let mut probe = Probe::new(std::io::BufReader::new(ThrottledFile(file)));
+    if let Some(file_type) = FileType::from_path(path) {
+        probe = probe.set_file_type(file_type);
+    }
+    probe.options(parse_opts).read().map_err(|e| e.to_string())
+}
 
 // ---------------------------------------------------------------------------
-// Path formatting helpers
+// Style lints (--style / --style-fix)
 // ---------------------------------------------------------------------------
 
-/// Extract the first folder after the scan root (e.g., "Radiohead" from "/mnt/c/__DMP/Radiohead/...")
-fn get_artist_folder(path: &Path, scan_root: &str) -> String {
-    let path_str = path.to_string_lossy();
-    let relative = path_str
-        .strip_prefix(scan_root)
-        .unwrap_or(&path_str)
-        .trim_start_matches('/');
+/// Lints `scan_file` runs on artist/title; set once in main from `--style`.
+static STYLE_LINTS: OnceLock<StyleLints> = OnceLock::new();
 
-    relative
-        .split('/')
-        .next()
-        .unwrap_or("")
-        .to_string()
-}
+/// Values with fewer cased letters than this are never flagged as ALL CAPS /
+/// all lowercase, so acronyms like "ABBA" or "MGMT" pass.
+const CASE_MIN_LETTERS: usize = 5;
 
-/// Get the path relative to the scan root (e.g., "Radiohead/OK Computer/01 Airbag.flac")
-fn relative_path(path: &Path, scan_root: &str) -> String {
-    let path_str = path.to_string_lossy();
-    path_str
-        .strip_prefix(scan_root)
-        .unwrap_or(&path_str)
-        .trim_start_matches('/')
-        .to_string()
-}
+/// Title markers that put a guest artist in the title instead of the artist
+/// field. Searched in the ASCII-lowercased title.
+const FEAT_MARKERS: &[&str] = &[
+    "(feat. ", "[feat. ", "(ft. ", "[ft. ", "(featuring ", "[featuring ",
+    " feat. ", " ft. ", " featuring ",
+];
 
-// ---------------------------------------------------------------------------
-// Human-readable file size
-// ---------------------------------------------------------------------------
+const EXPLICIT_MARKERS: &[&str] = &["[explicit]", "(explicit)"];
 
-fn human_size(bytes: u64) -> String {
-    const KB: u64 = 1024;
-    const MB: u64 = 1024 * KB;
-    const GB: u64 = 1024 * MB;
-    const TB: u64 = 1024 * GB;
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct StyleLints {
+    case: bool,
+    explicit: bool,
+    spaces: bool,
+    feat: bool,
+}
 
-    if bytes >= TB {
-        format!("{:.2} TB", bytes as f64 / TB as f64)
-    } else if bytes >= GB {
-        format!("{:.2} GB", bytes as f64 / GB as f64)
-    } else if bytes >= MB {
-        format!("{:.2} MB", bytes as f64 / MB as f64)
-    } else if bytes >= KB {
-        format!("{:.2} KB", bytes as f64 / KB as f64)
-    } else {
-        format!("{} B", bytes)
+impl StyleLints {
+    const NAMES: [&'static str; 4] = ["case", "explicit", "spaces", "feat"];
+
+    /// Parse `--style`: comma-separated lint names, or `all`.
+    fn parse(list: &str) -> Result<Self, String> {
+        let mut lints = StyleLints::default();
+        for name in list.split(',').map(|n| n.trim().to_lowercase()).filter(|n| !n.is_empty()) {
+            match name.as_str() {
+                "all" => lints = StyleLints { case: true, explicit: true, spaces: true, feat: true },
+                "case" => lints.case = true,
+                "explicit" => lints.explicit = true,
+                "spaces" => lints.spaces = true,
+                "feat" => lints.feat = true,
+                _ => return Err(format!("unknown style lint '{}' (expected {} or all)", name, Self::NAMES.join(", "))),
+            }
+        }
+        Ok(lints)
     }
-}
 
-// ---------------------------------------------------------------------------
-// Report: shared CSS
-// ---------------------------------------------------------------------------
-
-const CSS: &str = r#":root {
-    --bg: #0f1117;
-    --surface: #1a1d27;
-    --surface2: #242836;
-    --border: #2e3348;
-    --text: #e2e4ed;
-    --text-dim: #8b8fa3;
-    --accent: #6c7ee1;
-    --accent-dim: #4a5699;
-    --red: #e5534b;
-    --green: #57ab5a;
-    --orange: #daaa3f;
-    --blue: #539bf5;
-}
-* { margin: 0; padding: 0; box-sizing: border-box; }
-body {
-    background: var(--bg);
-    color: var(--text);
-    font-family: 'Inter', -apple-system, BlinkMacSystemFont, 'Segoe UI', system-ui, sans-serif;
-    font-size: 14px;
-    line-height: 1.5;
-    padding: 24px;
-}
-.container { max-width: 100%; margin: 0 auto; }
-h1 {
-    font-size: 24px;
-    font-weight: 700;
-    margin-bottom: 8px;
-    color: var(--text);
-}
-.subtitle {
-    color: var(--text-dim);
-    margin-bottom: 12px;
-    font-size: 14px;
-    display: flex;
-    justify-content: space-between;
-    align-items: center;
-}
-.subtitle .meta {
-    color: var(--text-dim);
-    font-size: 13px;
-}
-
-/* Navigation */
-.nav-bar {
-    display: flex;
-    border-bottom: 2px solid var(--border);
-    margin-bottom: 24px;
-    gap: 0;
-}
-.nav-tab {
-    padding: 10px 20px;
-    color: var(--text-dim);
-    font-size: 13px;
-    font-weight: 600;
-    text-decoration: none;
-    border-bottom: 3px solid transparent;
-    margin-bottom: -2px;
-    transition: all 0.15s;
-}
-.nav-tab:hover { color: var(--text); }
-.nav-tab.active {
-    color: var(--accent);
-    border-bottom-color: var(--accent);
-}
-.nav-tab .badge {
-    background: var(--surface2);
-    color: var(--text-dim);
-    padding: 1px 7px;
-    border-radius: 10px;
-    font-size: 11px;
-    margin-left: 6px;
-}
-.nav-tab.active .badge {
-    background: var(--accent-dim);
-    color: #fff;
-}
-
-/* Stats cards */
-.stats-container {
-    display: flex;
-    justify-content: space-between;
-    gap: 24px;
-    margin-bottom: 24px;
-}
-.stats-group { display: flex; gap: 12px; flex-wrap: wrap; }
-.stat-card {
-    background: var(--surface);
-    border: 1px solid var(--border);
-    border-radius: 8px;
-    padding: 16px;
-    min-width: 140px;
-}
-.stat-card .label {
-    color: var(--text-dim);
-    font-size: 12px;
-    text-transform: uppercase;
-    letter-spacing: 0.5px;
-}
-.stat-card .value { font-size: 22px; font-weight: 700; margin-top: 4px; }
-.stat-card .value.ok { color: var(--green); }
-.stat-card .value.fail { color: var(--red); }
-.stat-card .value.warn { color: var(--orange); }
-.stat-card .value.info { color: var(--blue); }
-
-/* Tables */
-.search-box { display: flex; justify-content: flex-end; margin-bottom: 12px; }
-.search-box input {
-    background: var(--surface);
-    border: 1px solid var(--border);
-    border-radius: 6px;
-    color: var(--text);
-    padding: 6px 12px;
-    font-size: 13px;
-    width: 260px;
-    outline: none;
-}
-.search-box input:focus { border-color: var(--accent); }
-.table-wrap {
-    overflow-x: auto;
-    border: 1px solid var(--border);
-    border-radius: 8px;
-}
-table { width: 100%; border-collapse: collapse; font-size: 13px; }
-th {
-    background: var(--surface);
-    color: var(--text-dim);
-    font-weight: 600;
-    text-transform: uppercase;
-    font-size: 11px;
-    letter-spacing: 0.5px;
-    padding: 10px 12px;
-    text-align: left;
-    position: sticky;
-    top: 0;
-    border-bottom: 1px solid var(--border);
-    white-space: nowrap;
-    cursor: pointer;
-}
-th:hover { color: var(--text); }
-td {
-    padding: 8px 12px;
-    border-bottom: 1px solid var(--border);
-    white-space: nowrap;
-}
-td:first-child {
-    max-width: 600px;
-    overflow: hidden;
-    text-overflow: ellipsis;
-}
-td:not(:first-child) {
-    text-align: center;
-    min-width: 90px;
-}
-th:not(:first-child) {
-    text-align: center;
-}
-tr:hover td { background: var(--surface); }
-
-/* Icons */
-.miss { color: var(--red); font-weight: 700; font-size: 15px; }
-.warn { color: var(--orange); font-weight: 700; font-size: 15px; }
-.unknown { color: var(--orange); font-weight: 700; font-size: 15px; }
-.ok { color: var(--green); font-size: 15px; }
-.empty-state {
-    text-align: center;
-    padding: 48px;
-    color: var(--text-dim);
-    font-size: 15px;
-}
-
-/* Category breakdown on index */
-.breakdown { margin-top: 24px; }
-.breakdown h2 {
-    font-size: 16px;
-    font-weight: 600;
-    margin-bottom: 12px;
-    color: var(--text);
-}
-.breakdown td { padding: 8px 16px; }
-.breakdown a { color: var(--accent); text-decoration: none; }
-.breakdown a:hover { text-decoration: underline; }
-
-/* Subtab bar (data pages) */
-.subtab-bar {
-    display: flex;
-    gap: 8px;
-    margin-bottom: 16px;
-    flex-wrap: wrap;
-}
-.subtab {
-    background: none;
-    border: 1px solid var(--border);
-    border-radius: 6px;
-    color: var(--text-dim);
-    padding: 6px 14px;
-    font-size: 13px;
-    font-weight: 600;
-    cursor: pointer;
-    display: inline-flex;
-    align-items: center;
-    gap: 6px;
-    font-family: inherit;
-}
-.subtab:hover { color: var(--text); border-color: var(--accent-dim); }
-.subtab.active { background: var(--accent-dim); border-color: var(--accent); color: #fff; }
-.subtab-count {
-    background: rgba(0,0,0,0.25);
-    border-radius: 10px;
-    padding: 1px 6px;
-    font-size: 11px;
-}
-.panel.hidden { display: none; }
-.artist-list { display: flex; flex-direction: column; gap: 6px; }
-.artist-group {
-    border: 1px solid var(--border);
-    border-radius: 8px;
-    overflow: hidden;
-}
-.artist-header {
-    display: flex;
-    align-items: center;
-    gap: 10px;
-    padding: 10px 14px;
-    background: var(--surface);
-    cursor: pointer;
-    user-select: none;
-}
-.artist-header:hover { background: var(--surface2); }
-.artist-name { font-weight: 600; color: var(--text); flex: 1; font-size: 13px; }
-.file-count { color: var(--text-dim); font-size: 12px; }
-.arrow { color: var(--text-dim); font-size: 11px; display: inline-block; transition: transform 0.15s; }
-.artist-group.collapsed .arrow { transform: rotate(-90deg); }
-.file-list { list-style: none; border-top: 1px solid var(--border); }
-.artist-group.collapsed .file-list { display: none; }
-.file-item {
-    padding: 6px 14px 6px 36px;
-    border-bottom: 1px solid var(--border);
-    font-size: 12px;
-    color: var(--text-dim);
-    white-space: nowrap;
-    overflow: hidden;
-    text-overflow: ellipsis;
-}
-.file-item:last-child { border-bottom: none; }
-.file-item:hover { background: var(--surface); color: var(--text); }
-.annot { color: var(--orange); font-size: 11px; margin-left: 8px; }
-.empty-panel { text-align: center; padding: 48px; color: var(--text-dim); font-size: 15px; }
-
-/* Pagination */
-.pagination { display: flex; justify-content: center; align-items: center; gap: 4px; margin: 16px 0; }
-.pagination a, .pagination span { display: inline-flex; align-items: center; justify-content: center; min-width: 32px; height: 32px; padding: 4px 8px; border-radius: 6px; text-decoration: none; font-size: 13px; font-weight: 600; color: var(--text-dim); border: 1px solid var(--border); }
-.pagination a:hover { background: var(--surface2); color: var(--text); border-color: var(--accent-dim); }
-.pagination .active { background: var(--accent-dim); border-color: var(--accent); color: #fff; }
-.pagination .disabled { opacity: 0.3; pointer-events: none; border-color: transparent; }
-
-/* Autofix: matched file styling */
-.file-item { position: relative; }
-.file-item.matched { text-decoration: line-through; opacity: 0.5; }
-.file-item.matched:hover { opacity: 0.8; }
-.match-check { color: var(--green); font-size: 13px; margin-left: 8px; }
-.match-delta { color: var(--green); font-size: 10px; margin-left: 4px; }
-.match-popover { display:none; position:fixed; z-index:1000; background:var(--surface2); border:1px solid var(--border); border-radius:8px; padding:12px 16px; font-size:12px; line-height:1.6; max-width:500px; white-space:normal; box-shadow:0 4px 12px rgba(0,0,0,0.4); pointer-events:none; }
-.pop-title { font-weight:600; color:var(--text); margin-bottom:6px; }
-.pop-old { text-decoration:line-through; color:var(--red); }
-.pop-new { color:var(--green); }
-.pop-arrow { color:var(--text-dim); margin:0 6px; }"#;
-
-// ---------------------------------------------------------------------------
-// Report: shared JS
-// ---------------------------------------------------------------------------
-
-const JS: &str = r#"/* autofix: popover show/hide */
-function showMatchInfo(el) { var p=el.parentElement.querySelector('.match-popover'); if(!p) return; var r=el.getBoundingClientRect(); p.style.left=r.left+'px'; p.style.top=(r.bottom+6)+'px'; p.style.display='block'; }
-function hideMatchInfo(el) { var p=el.parentElement.querySelector('.match-popover'); if(p) p.style.display='none'; }
-/* issues.html: flat table search */
-function filterTable(input) {
-    var filter = input.value.toLowerCase();
-    var rows = document.querySelectorAll('table tbody tr');
-    for (var i = 0; i < rows.length; i++) {
-        var row = rows[i];
-        if (row.querySelector('.empty-state')) continue;
-        row.style.display = row.textContent.toLowerCase().indexOf(filter) !== -1 ? '' : 'none';
-    }
-}
-/* data pages: subtab switching */
-function switchSubtab(btn) {
-    var tabs = btn.parentNode.querySelectorAll('.subtab');
-    for (var i = 0; i < tabs.length; i++) tabs[i].classList.remove('active');
-    btn.classList.add('active');
-    var panels = document.querySelectorAll('.panel');
-    var target = btn.dataset.panel;
-    for (var i = 0; i < panels.length; i++) {
-        panels[i].classList.toggle('hidden', panels[i].id !== target);
-    }
-}
-/* data pages: collapse/expand artist group */
-function toggleArtist(header) {
-    header.parentNode.classList.toggle('collapsed');
-}
-/* data pages: filter within active panel */
-function filterGroups(input) {
-    var filter = input.value.toLowerCase().trim();
-    var panel = document.querySelector('.panel:not(.hidden)');
-    if (!panel) return;
-    var groups = panel.querySelectorAll('.artist-group');
-    for (var i = 0; i < groups.length; i++) {
-        var group = groups[i];
-        var nameEl = group.querySelector('.artist-name');
-        var artistMatch = filter === '' || (nameEl && nameEl.textContent.toLowerCase().indexOf(filter) !== -1);
-        var items = group.querySelectorAll('.file-item');
-        var visible = 0;
-        for (var j = 0; j < items.length; j++) {
-            var show = filter === '' || artistMatch || items[j].textContent.toLowerCase().indexOf(filter) !== -1;
-            items[j].style.display = show ? '' : 'none';
-            if (show) visible++;
-        }
-        group.style.display = (filter === '' || visible > 0) ? '' : 'none';
-        if (filter !== '' && visible > 0) group.classList.remove('collapsed');
-    }
-}
-/* issues.html: sortable columns */
-document.addEventListener('DOMContentLoaded', function() {
-    var headers = document.querySelectorAll('th[data-sort]');
-    for (var h = 0; h < headers.length; h++) {
-        (function(th) {
-            th.addEventListener('click', function() {
-                var table = th.closest('table');
-                var tbody = table.querySelector('tbody');
-                var rows = Array.prototype.slice.call(tbody.querySelectorAll('tr'));
-                var idx = parseInt(th.dataset.sort);
-                var asc = th.dataset.dir !== 'asc';
-                th.dataset.dir = asc ? 'asc' : 'desc';
-                var allTh = th.closest('thead').querySelectorAll('th');
-                for (var i = 0; i < allTh.length; i++) {
-                    if (allTh[i] !== th) delete allTh[i].dataset.dir;
-                }
-                rows.sort(function(a, b) {
-                    var av = (a.cells[idx] && a.cells[idx].textContent.trim()) || '';
-                    var bv = (b.cells[idx] && b.cells[idx].textContent.trim()) || '';
-                    return asc ? av.localeCompare(bv) : bv.localeCompare(av);
-                });
-                for (var i = 0; i < rows.length; i++) tbody.appendChild(rows[i]);
-            });
-        })(headers[h]);
+    fn is_empty(&self) -> bool {
+        *self == StyleLints::default()
     }
-});
-"#;
 
-// ---------------------------------------------------------------------------
-// Report: artist-grouped data helpers
-// ---------------------------------------------------------------------------
+    fn names(&self) -> Vec<&'static str> {
+        let enabled = [self.case, self.explicit, self.spaces, self.feat];
+        Self::NAMES.iter().zip(enabled).filter(|(_, on)| *on).map(|(n, _)| *n).collect()
+    }
+}
 
-/// BTreeMap<artist_folder -> Vec<(relative_path, optional_annotation, fix_status)>>
-type ArtistGroups = BTreeMap<String, Vec<(String, Option<String>, FileFixStatus)>>;
+/// Style findings for one file; see the `style_*` fields of `FileIssue`.
+#[derive(Debug, Default)]
+struct StyleCheck {
+    case: Option<String>,
+    explicit: bool,
+    spaces: Option<String>,
+    feat: Option<String>,
+}
 
-const ARTISTS_PER_PAGE: usize = 20;
+/// Entirely upper- or lowercase, ignoring short values (see `CASE_MIN_LETTERS`).
+fn has_bad_case(value: &str) -> bool {
+    let cased: Vec<char> = value.chars().filter(|c| c.is_uppercase() || c.is_lowercase()).collect();
+    cased.len() >= CASE_MIN_LETTERS
+        && (cased.iter().all(|c| c.is_uppercase()) || cased.iter().all(|c| c.is_lowercase()))
+}
 
-/// Build an artist-grouped list of files that satisfy `predicate`.
-/// Files within each group are sorted by relative path.
-/// When `diffs` and `field_name` are provided, each entry gets a fix_status based on whether
-/// the autofix diffs contain a FieldMatch matching `field_name` for that file.
-fn build_groups(
-    issues: &[FileIssue],
-    scan_root: &str,
-    predicate: impl Fn(&FileIssue) -> bool,
-    annotate: impl Fn(&FileIssue) -> Option<String>,
-    diffs: Option<&MatchDiffs>,
-    skipped_files: Option<&SkippedFiles>,
-    field_name: Option<&str>,
-) -> ArtistGroups {
-    let mut groups: ArtistGroups = BTreeMap::new();
-    for issue in issues {
-        if !predicate(issue) { continue; }
-        let artist = get_artist_folder(&issue.path, scan_root);
-        let rel    = relative_path(&issue.path, scan_root);
-        let ann    = annotate(issue);
-        let fix_status = if diffs.is_none() && skipped_files.is_none() {
-            FileFixStatus::NoAutofix
-        } else if let Some(sf) = skipped_files {
-            if let Some(reason) = sf.get(&issue.path) {
-                FileFixStatus::Skipped(reason.clone())
-            } else if let (Some(d), Some(fname)) = (diffs, field_name) {
-                if d.get(&issue.path).map_or(false, |fixes| fixes.iter().any(|fix| fix.field == fname)) {
-                    FileFixStatus::Matched
-                } else {
-                    FileFixStatus::NoAutofix
-                }
-            } else {
-                FileFixStatus::NoAutofix
-            }
-        } else if let (Some(d), Some(fname)) = (diffs, field_name) {
-            if d.get(&issue.path).map_or(false, |fixes| fixes.iter().any(|fix| fix.field == fname)) {
-                FileFixStatus::Matched
-            } else {
-                FileFixStatus::NoAutofix
-            }
-        } else {
-            FileFixStatus::NoAutofix
-        };
-        groups.entry(artist).or_default().push((rel, ann, fix_status));
-    }
-    for files in groups.values_mut() {
-        files.sort_by(|a, b| a.0.cmp(&b.0));
-    }
-    groups
+/// Runs of whitespace, or whitespace at either end.
+fn has_extra_spaces(value: &str) -> bool {
+    collapse_spaces(value) != value
 }
 
-fn group_total(groups: &ArtistGroups) -> usize {
-    groups.values().map(|v| v.len()).sum()
+fn collapse_spaces(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
 }
 
-fn group_matched_count(groups: &ArtistGroups) -> usize {
-    groups.values().flat_map(|v| v.iter())
-        .filter(|(_, _, fs)| matches!(fs, FileFixStatus::Matched))
-        .count()
+/// The title without "[Explicit]" / "(Explicit)", if it had one.
+fn strip_explicit(title: &str) -> Option<String> {
+    let mut stripped = title.to_string();
+    for marker in EXPLICIT_MARKERS {
+        while let Some(at) = stripped.to_ascii_lowercase().find(marker) {
+            stripped.replace_range(at..at + marker.len(), " ");
+        }
+    }
+    (stripped != title).then(|| collapse_spaces(&stripped))
 }
 
-/// Collect the sorted union of artist names across multiple ArtistGroups.
-fn collect_all_artists(groups_list: &[&ArtistGroups]) -> Vec<String> {
-    let mut set = BTreeSet::new();
-    for groups in groups_list {
-        for key in groups.keys() {
-            set.insert(key.clone());
+/// Split "Song (feat. Guest)" into ("Song", "Guest"). Bracketed markers keep
+/// whatever follows the closing bracket ("Song (feat. Guest) [Live]").
+fn split_featuring(title: &str) -> Option<(String, String)> {
+    let lower = title.to_ascii_lowercase();
+    let (at, marker) = FEAT_MARKERS.iter()
+        .filter_map(|m| lower.find(m).map(|at| (at, *m)))
+        .min_by_key(|(at, _)| *at)?;
+    let before = title[..at].trim_end();
+    let rest = &title[at + marker.len()..];
+
+    let (guest, after) = match marker.chars().next() {
+        Some(open @ ('(' | '[')) => {
+            let close = if open == '(' { ')' } else { ']' };
+            match rest.find(close) {
+                Some(end) => (&rest[..end], &rest[end + 1..]),
+                None => (rest, ""),
+            }
         }
+        _ => (rest, ""),
+    };
+    let guest = guest.trim();
+    if guest.is_empty() || before.is_empty() {
+        return None;
     }
-    set.into_iter().collect()
+    Some((collapse_spaces(&format!("{} {}", before, after)), guest.to_string()))
 }
 
-/// Filter an ArtistGroups to only include artists in the given set.
-fn filter_groups(groups: &ArtistGroups, artists: &HashSet<&str>) -> ArtistGroups {
-    groups.iter()
-        .filter(|(artist, _)| artists.contains(artist.as_str()))
-        .map(|(artist, files)| (artist.clone(), files.clone()))
-        .collect()
+/// Capitalise the first letter of every word and lowercase the rest.
+fn title_case(value: &str) -> String {
+    value.split(' ')
+        .map(|word| {
+            let mut seen_letter = false;
+            word.chars()
+                .flat_map(|c| {
+                    let upper = c.is_alphabetic() && !seen_letter;
+                    seen_letter |= c.is_alphabetic();
+                    if upper { c.to_uppercase().collect::<Vec<_>>() } else { c.to_lowercase().collect() }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
 }
 
-/// Write pagination controls (prev/next + page numbers).
-fn write_pagination<W: Write>(
-    f: &mut W,
-    base_name: &str,
-    current_page: usize,
-    total_pages: usize,
-) -> std::io::Result<()> {
-    if total_pages <= 1 {
-        return Ok(());
-    }
-    write!(f, "<div class=\"pagination\">\n")?;
-    if current_page > 1 {
-        write!(f, "<a href=\"{}_{}.html\">&lsaquo;</a>\n", base_name, current_page - 1)?;
-    } else {
-        write!(f, "<span class=\"disabled\">&lsaquo;</span>\n")?;
+fn check_style(artist: Option<&str>, title: Option<&str>, lints: &StyleLints) -> StyleCheck {
+    let fields = [("Artist", artist), ("Title", title)];
+    let matching = |pred: fn(&str) -> bool, show_value: bool| -> Option<String> {
+        let hits: Vec<&str> = fields.iter()
+            .filter_map(|(label, value)| value.filter(|v| pred(v)).map(|v| if show_value { v } else { *label }))
+            .collect();
+        (!hits.is_empty()).then(|| hits.join(" / "))
+    };
+    StyleCheck {
+        case: if lints.case { matching(has_bad_case, true) } else { None },
+        explicit: lints.explicit && title.is_some_and(|t| strip_explicit(t).is_some()),
+        spaces: if lints.spaces { matching(has_extra_spaces, false) } else { None },
+        feat: if lints.feat { title.and_then(split_featuring).map(|(_, guest)| guest) } else { None },
     }
-    for p in 1..=total_pages {
-        if p == current_page {
-            write!(f, "<span class=\"active\">{}</span>\n", p)?;
-        } else {
-            write!(f, "<a href=\"{}_{}.html\">{}</a>\n", base_name, p, p)?;
+}
+
+/// Apply the enabled style fixes to artist/title, in the order spaces,
+/// explicit, case, feat. Returns the new values and one change per fix.
+fn normalize_style(artist: &str, title: &str, lints: &StyleLints) -> (String, String, Vec<FieldMatch>) {
+    let mut fixes: Vec<FieldMatch> = Vec::new();
+    let (mut artist, mut title) = (artist.to_string(), title.to_string());
+    let mut apply = |field: &'static str, value: &mut String, fix: &dyn Fn(&str) -> String| {
+        let new = fix(value);
+        if new != *value {
+            fixes.push(FieldMatch {
+                field,
+                old_display: std::mem::replace(value, new.clone()),
+                new_value: new,
+                category: "style",
+            });
         }
+    };
+
+    if lints.spaces {
+        apply("Spacing", &mut artist, &collapse_spaces);
+        apply("Spacing", &mut title, &collapse_spaces);
     }
-    if current_page < total_pages {
-        write!(f, "<a href=\"{}_{}.html\">&rsaquo;</a>\n", base_name, current_page + 1)?;
-    } else {
-        write!(f, "<span class=\"disabled\">&rsaquo;</span>\n")?;
+    if lints.explicit {
+        apply("Explicit", &mut title, &|t| strip_explicit(t).unwrap_or_else(|| t.to_string()));
     }
-    write!(f, "</div>\n")?;
-    Ok(())
+    if lints.case {
+        let fix_case = |v: &str| if has_bad_case(v) { title_case(v) } else { v.to_string() };
+        apply("Casing", &mut artist, &fix_case);
+        apply("Casing", &mut title, &fix_case);
+    }
+    if lints.feat && !artist.is_empty() {
+        if let Some((song, guest)) = split_featuring(&title) {
+            if !artist.to_lowercase().contains(&guest.to_lowercase()) {
+                apply("Featuring", &mut artist, &|a| format!("{} feat. {}", a, guest));
+            }
+            apply("Featuring", &mut title, &|_| song.clone());
+        }
+    }
+    (artist, title, fixes)
 }
 
-/// Write the subtab bar. `tabs` = &[(panel_id, label, count, fixed_count), …].
-/// The first tab is active by default.
-fn write_subtab_bar<W: Write>(
-    f: &mut W,
-    tabs: &[(&str, &str, usize, usize)],
-) -> std::io::Result<()> {
-    write!(f, "<div class=\"subtab-bar\">\n")?;
-    for (i, &(id, label, count, matched)) in tabs.iter().enumerate() {
-        let active = if i == 0 { " active" } else { "" };
-        let delta = if matched > 0 {
-            format!("<span class=\"match-delta\"> (-{})</span>", matched)
-        } else {
-            String::new()
-        };
-        write!(
-            f,
-            "<button class=\"subtab{}\" onclick=\"switchSubtab(this)\" data-panel=\"panel-{}\">{}<span class=\"subtab-count\">{}{}</span></button>\n",
-            active, id, encode_text(label), count, delta
-        )?;
+/// Write artist/title into every tag of the file that already holds them.
+fn write_artist_title(path: &Path, artist: Option<&str>, title: Option<&str>) -> Result<(), String> {
+    let mut tagged = lofty::read_from_path(path).map_err(|e| e.to_string())?;
+    let tag_types: Vec<TagType> = tagged.tags().iter().map(|t| t.tag_type()).collect();
+    for tag_type in tag_types {
+        let Some(tag) = tagged.tag_mut(tag_type) else { continue };
+        if let Some(artist) = artist.filter(|_| tag.artist().is_some()) {
+            tag.set_artist(artist.to_string());
+        }
+        if let Some(title) = title.filter(|_| tag.title().is_some()) {
+            tag.set_title(title.to_string());
+        }
+        tag.save_to_path(path, WriteOptions::default()).map_err(|e| e.to_string())?;
     }
-    write!(f, "</div>\n")?;
     Ok(())
 }
 
-/// Write a single collapsible-artist-grouped panel.
-/// `active` controls whether the panel is visible on load.
-/// When `diffs`, `category`, and `scan_root` are provided, matched files get strikethrough styling
-/// and a popover showing field-level changes.
-fn write_field_panel<W: Write>(
-    f: &mut W,
-    panel_id: &str,
-    groups: &ArtistGroups,
-    active: bool,
-    category: &str,
-    diffs: Option<&MatchDiffs>,
-    scan_root: &str,
-) -> std::io::Result<()> {
-    let hidden = if active { "" } else { " hidden" };
-    write!(f, "<div class=\"panel{}\" id=\"panel-{}\">\n", hidden, panel_id)?;
-    if groups.is_empty() {
-        write!(f, "<div class=\"empty-panel\">No issues found</div>\n")?;
+/// `--style-fix`: normalize the artist/title of every file with a style issue.
+/// Returns the changes per file (nothing is written or returned when `dry`).
+fn fix_style(issues: &[FileIssue], scan_root: &str, lints: &StyleLints, dry: bool) -> MatchDiffs {
+    let mut diffs: MatchDiffs = HashMap::new();
+    let mut failed = 0u32;
+    let flagged: Vec<&FileIssue> = issues.iter().filter(|i| i.has_style()).collect();
+    if dry {
+        println!("[DRY RUN] Would normalize tags of {} file(s):", flagged.len());
     } else {
-        write!(f, "<div class=\"artist-list\">\n")?;
-        for (artist, files) in groups {
-            write!(
-                f,
-                "<div class=\"artist-group\">\n\
-                 <div class=\"artist-header\" onclick=\"toggleArtist(this)\">\
-                 <span class=\"arrow\">&#9660;</span>\
-                 <span class=\"artist-name\">{}</span>\
-                 <span class=\"file-count\">{} file{}</span>\
-                 </div>\n\
-                 <ul class=\"file-list\">\n",
-                encode_text(artist),
-                files.len(),
-                if files.len() == 1 { "" } else { "s" }
-            )?;
-            for (path, ann, fix_status) in files {
-                let ann_html = ann.as_ref()
-                    .map(|a| format!(" <span class=\"annot\">{}</span>", encode_text(a)))
-                    .unwrap_or_default();
-
-                match fix_status {
-                    FileFixStatus::Matched => {
-                        // Strikethrough + dim + green check + popover with field diffs
-                        let full_path = PathBuf::from(scan_root).join(path);
-                        let popover_html = if let Some(d) = diffs {
-                            if let Some(fixes) = d.get(&full_path) {
-                                let cat_fixes: Vec<&FieldMatch> = fixes.iter()
-                                    .filter(|fix| fix.category == category)
-                                    .collect();
-                                if !cat_fixes.is_empty() {
-                                    let mut pop = String::from("<div class=\"match-popover\"><div class=\"pop-title\">Matched by beets:</div>");
-                                    for fix in &cat_fixes {
-                                        pop.push_str(&format!(
-                                            "<div><span class=\"pop-old\">{}: {}</span><span class=\"pop-arrow\">&rarr;</span><span class=\"pop-new\">{}</span></div>",
-                                            encode_text(fix.field),
-                                            encode_text(&fix.old_display),
-                                            encode_text(&fix.new_value),
-                                        ));
-                                    }
-                                    pop.push_str("</div>");
-                                    pop
-                                } else {
-                                    String::new()
-                                }
-                            } else {
-                                String::new()
-                            }
-                        } else {
-                            String::new()
-                        };
-                        write!(
-                            f,
-                            "<li class=\"file-item matched\">{}{}<span class=\"match-check\" onmouseenter=\"showMatchInfo(this)\" onmouseleave=\"hideMatchInfo(this)\">&#10003;</span>{}</li>\n",
-                            encode_text(path), ann_html, popover_html
-                        )?;
-                    }
-                    FileFixStatus::Skipped(_) => {
-                        write!(f, "<li class=\"file-item\">{}{}</li>\n", encode_text(path), ann_html)?;
-                    }
-                    FileFixStatus::NoAutofix => {
-                        write!(f, "<li class=\"file-item\">{}{}</li>\n", encode_text(path), ann_html)?;
-                    }
-                }
-            }
-            write!(f, "</ul>\n</div>\n")?;
-        }
-        write!(f, "</div>\n")?;
+        println!("[Style] Normalizing tags of {} file(s)...", flagged.len());
     }
-    write!(f, "</div>\n")?;
-    Ok(())
-}
-
-// ---------------------------------------------------------------------------
-// Report: navigation bar
-// ---------------------------------------------------------------------------
-
-fn write_nav<W: Write>(
-    f: &mut W,
-    active: &str,
-    counts: &NavCounts,
-    pages: &PageFlags,
-    from_index: bool,
-) -> std::io::Result<()> {
-    // (id, label, filename, count, fixed_count, show)
-    let entries: Vec<(&str, &str, &str, Option<usize>, usize, bool)> = vec![
-        ("overview", "Overview", "index.html", None, 0, true),
-        ("issues", "Issues", "issues.html", Some(counts.issues), 0, true),
-        ("critical", "Critical", "critical_1.html", Some(counts.critical), counts.critical_matched, pages.critical),
-        ("mb", "MusicBrainz", "mb_1.html", Some(counts.mb), counts.mb_matched, pages.mb),
-        ("discogs", "Discogs", "discogs_1.html", Some(counts.discogs), counts.discogs_matched, pages.discogs),
-        ("ids", "IDs", "ids_1.html", Some(counts.ids), counts.ids_matched, pages.ids),
-        ("other", "Other", "other_1.html", Some(counts.other), counts.other_matched, pages.other),
-    ];
 
-    write!(f, "<nav class=\"nav-bar\">\n")?;
-    for (id, label, filename, count, matched, show) in &entries {
-        if !show { continue; }
-        let href = if *filename == "index.html" {
-            if from_index { "index.html".to_string() } else { "../index.html".to_string() }
-        } else if from_index {
-            format!("pages/{}", filename)
-        } else {
-            filename.to_string()
-        };
-        let active_class = if *id == active { " active" } else { "" };
-        let badge = match count {
-            Some(n) => {
-                let delta = if *matched > 0 {
-                    format!("<span class=\"match-delta\"> (-{})</span>", matched)
-                } else {
-                    String::new()
-                };
-                format!("<span class=\"badge\">{}{}</span>", n, delta)
+    for issue in flagged {
+        let tags = match read_tagged_file(&issue.path, ParseOptions::new().read_properties(false)) {
+            Ok(tagged) => collect_tags(&tagged),
+            Err(e) => {
+                eprintln!("  FAILED to read {}: {}", issue.path.display(), e);
+                failed += 1;
+                continue;
             }
-            None => String::new(),
         };
-        write!(f, "<a href=\"{}\" class=\"nav-tab{}\">{}{}</a>\n", href, active_class, label, badge)?;
+        let old_artist = get_tag(&tags, &["ARTIST"]).unwrap_or_default();
+        let old_title = get_tag(&tags, &["TITLE"]).unwrap_or_default();
+        let (artist, title, fixes) = normalize_style(&old_artist, &old_title, lints);
+        if fixes.is_empty() { continue; }
+
+        let rel = relative_path(&issue.path, scan_root);
+        for fix in &fixes {
+            println!("  {}: {} '{}' -> '{}'", rel, fix.field, fix.old_display, fix.new_value);
+        }
+        if dry { continue; }
+
+        let changed = |old: &str, new: String| (old != new).then_some(new);
+        match write_artist_title(
+            &issue.path,
+            changed(&old_artist, artist).as_deref(),
+            changed(&old_title, title).as_deref(),
+        ) {
+            Ok(()) => { diffs.insert(issue.path.clone(), fixes); }
+            Err(e) => {
+                eprintln!("  FAILED to write {}: {}", issue.path.display(), e);
+                failed += 1;
+            }
+        }
     }
-    write!(f, "</nav>\n")?;
-    Ok(())
+
+    if !dry {
+        println!("  Normalized: {} files | Failed: {}", diffs.len(), failed);
+    }
+    diffs
 }
 
 // ---------------------------------------------------------------------------
-// Report: page shell (start / end)
+// Tag containers (--migrate-tags)
 // ---------------------------------------------------------------------------
 
-fn write_page_start<W: Write>(
-    f: &mut W,
-    title: &str,
-    from_index: bool,
-) -> std::io::Result<()> {
-    let css_path = if from_index { "css/styles.css" } else { "../css/styles.css" };
-    write!(f, "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n\
-        <meta charset=\"UTF-8\">\n\
-        <meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">\n\
-        <title>{} &mdash; Audio Metadata Analysis</title>\n\
-        <link rel=\"stylesheet\" href=\"{}\">\n\
-        </head>\n<body>\n<div class=\"container\">\n\
-        <h1>Audio Metadata Analysis</h1>\n",
-        encode_text(title), css_path
-    )?;
-    Ok(())
+/// The container players expect for a format. lofty's primary tag, except for
+/// WAV: Explorer, DAWs and hardware players only read the RIFF INFO chunk.
+fn canonical_tag_type(file_type: FileType) -> TagType {
+    match file_type {
+        FileType::Wav => TagType::RiffInfo,
+        other => other.primary_tag_type(),
+    }
 }
 
-fn write_page_end<W: Write>(f: &mut W, from_index: bool) -> std::io::Result<()> {
-    let js_path = if from_index { "js/report.js" } else { "../js/report.js" };
-    write!(f, "<script src=\"{}\"></script>\n</div>\n</body>\n</html>\n", js_path)?;
-    Ok(())
+fn container_name(tag_type: TagType) -> &'static str {
+    match tag_type {
+        TagType::Ape => "APEv2",
+        TagType::Id3v1 => "ID3v1",
+        TagType::Id3v2 => "ID3v2",
+        TagType::Mp4Ilst => "MP4 ilst",
+        TagType::VorbisComments => "Vorbis comments",
+        TagType::RiffInfo => "RIFF INFO",
+        TagType::AiffText => "AIFF text",
+        _ => "unknown tag",
+    }
 }
 
-// ---------------------------------------------------------------------------
-// Report: index.html
-// ---------------------------------------------------------------------------
-
-fn write_index(
-    report_dir: &Path,
-    scan_root: &str,
-    total_files: u64,
-    total_size: u64,
-    error_count: u64,
-    file_type_counts: &HashMap<String, u64>,
-    elapsed: std::time::Duration,
-    issues_len: usize,
-    counts: &NavCounts,
-    pages: &PageFlags,
-) -> std::io::Result<()> {
-    let path = report_dir.join("index.html");
-    let mut f = BufWriter::new(fs::File::create(&path)?);
-
-    write_page_start(&mut f, "Overview", true)?;
-
-    // Subtitle
-    write!(f, "<p class=\"subtitle\">\
-        <span>Scanned <code>{}</code></span>\
-        <span class=\"meta\">{} &middot; {:.2}s</span>\
-        </p>\n",
-        encode_text(scan_root),
-        human_size(total_size),
-        elapsed.as_secs_f64(),
-    )?;
-
-    write_nav(&mut f, "overview", counts, pages, true)?;
+fn format_name(file_type: FileType) -> &'static str {
+    match file_type {
+        FileType::Flac => "FLAC",
+        FileType::Mpeg => "MP3",
+        FileType::Aac => "AAC",
+        FileType::Mp4 => "MP4",
+        FileType::Opus => "Opus",
+        FileType::Vorbis => "Ogg Vorbis",
+        FileType::Wav => "WAV",
+        _ => "file",
+    }
+}
 
-    // Stats cards
-    let readable = total_files.saturating_sub(error_count);
-    let ok_count = readable.saturating_sub(issues_len as u64);
+/// Items (and pictures) of a non-canonical `tag` that the canonical container
+/// could hold but doesn't. ID3v1 is never counted: it's a capped legacy copy.
+fn stranded_items<'a>(tag: &'a lofty::tag::Tag, main: Option<&lofty::tag::Tag>, canonical: TagType) -> (Vec<&'a lofty::tag::TagItem>, bool) {
+    if tag.tag_type() == canonical || tag.tag_type() == TagType::Id3v1 {
+        return (Vec::new(), false);
+    }
+    let items = tag.items()
+        .filter(|item| match item.value() {
+            lofty::tag::ItemValue::Text(v) | lofty::tag::ItemValue::Locator(v) => !v.trim().is_empty(),
+            _ => false,
+        })
+        .filter(|item| item.key().map_key(canonical, false).is_some())
+        .filter(|item| main.is_none_or(|m| m.get(item.key()).is_none()))
+        .collect();
+    let pictures = canonical != TagType::RiffInfo
+        && !tag.pictures().is_empty()
+        && main.is_none_or(|m| m.pictures().is_empty());
+    (items, pictures)
+}
 
-    write!(f, "<div class=\"stats-container\">\n<div class=\"stats-group\">\n")?;
+/// Containers holding values only they have, e.g. "APEv2 in MP3". Many
+/// players read just the canonical container and would show those as missing.
+fn unusual_containers(tagged_file: &lofty::file::TaggedFile) -> Option<String> {
+    let file_type = tagged_file.file_type();
+    let canonical = canonical_tag_type(file_type);
+    let main = tagged_file.tag(canonical);
+    let names: Vec<String> = tagged_file.tags().iter()
+        .filter(|tag| {
+            let (items, pictures) = stranded_items(tag, main, canonical);
+            !items.is_empty() || pictures
+        })
+        .map(|tag| format!("{} in {}", container_name(tag.tag_type()), format_name(file_type)))
+        .collect();
+    (!names.is_empty()).then(|| names.join(", "))
+}
 
-    // File type stats
-    let mut sorted_types: Vec<_> = file_type_counts.iter().collect();
-    sorted_types.sort_by(|a, b| b.1.cmp(a.1));
-    for (ext, count) in &sorted_types {
-        write!(f, "<div class=\"stat-card\"><div class=\"label\">{}</div><div class=\"value info\">{}</div></div>\n",
-            encode_text(ext), count)?;
+/// Copy the stranded values of every unusual container into the canonical
+/// one (created if missing). Returns how many items and pictures were copied.
+/// The unusual containers are left in place.
+fn migrate_file_tags(path: &Path) -> Result<usize, String> {
+    let mut tagged = lofty::read_from_path(path).map_err(|e| e.to_string())?;
+    let canonical = canonical_tag_type(tagged.file_type());
+    let mut copy: Vec<lofty::tag::TagItem> = Vec::new();
+    let mut pictures: Vec<lofty::picture::Picture> = Vec::new();
+    for tag in tagged.tags() {
+        let (items, stranded_pictures) = stranded_items(tag, tagged.tag(canonical), canonical);
+        for item in items {
+            // The first container wins when several hold the same key
+            if !copy.iter().any(|c| c.key() == item.key()) {
+                copy.push(item.clone());
+            }
+        }
+        if stranded_pictures && pictures.is_empty() {
+            pictures = tag.pictures().to_vec();
+        }
+    }
+    if copy.is_empty() && pictures.is_empty() {
+        return Ok(0);
     }
 
-    write!(f, "</div>\n<div class=\"stats-group\">\n")?;
-    write!(f, "<div class=\"stat-card\"><div class=\"label\">Files OK</div><div class=\"value ok\">{}</div></div>\n", ok_count)?;
-    write!(f, "<div class=\"stat-card\"><div class=\"label\">Files with Issues</div><div class=\"value fail\">{}</div></div>\n", issues_len)?;
-    write!(f, "<div class=\"stat-card\"><div class=\"label\">Unreadable Files</div><div class=\"value warn\">{}</div></div>\n", error_count)?;
-    write!(f, "</div>\n</div>\n")?;
+    if tagged.tag(canonical).is_none() {
+        tagged.insert_tag(lofty::tag::Tag::new(canonical));
+    }
+    let main = tagged.tag_mut(canonical).ok_or("no canonical tag")?;
+    let mut copied = 0;
+    for item in copy {
+        if main.insert(item) { copied += 1; }
+    }
+    for picture in pictures {
+        main.push_picture(picture);
+        copied += 1;
+    }
+    main.save_to_path(path, WriteOptions::default()).map_err(|e| e.to_string())?;
+    Ok(copied)
+}
 
-    // Category breakdown
-    write!(f, "<div class=\"breakdown\">\n<h2>Breakdown by Category</h2>\n\
-        <div class=\"table-wrap\"><table>\n\
-        <thead><tr><th>Category</th><th>Issues</th><th></th></tr></thead>\n<tbody>\n")?;
-
-    let breakdown: &[(&str, &str, usize, bool)] = &[
-        ("Issues", "pages/issues.html", counts.issues, true),
-        ("Critical", "pages/critical_1.html", counts.critical, pages.critical),
-        ("MusicBrainz", "pages/mb_1.html", counts.mb, pages.mb),
-        ("Discogs", "pages/discogs_1.html", counts.discogs, pages.discogs),
-        ("IDs", "pages/ids_1.html", counts.ids, pages.ids),
-        ("Other", "pages/other_1.html", counts.other, pages.other),
-    ];
-    for &(label, href, count, show) in breakdown {
-        if !show { continue; }
-        write!(f, "<tr><td>{}</td><td>{}</td><td><a href=\"{}\">View &rarr;</a></td></tr>\n",
-            label, count, href)?;
+/// `--migrate-tags`: copy values out of unusual containers for every flagged
+/// file. Returns the changes per file for the report's resolved markers.
+fn migrate_tags(issues: &[FileIssue], scan_root: &str) -> MatchDiffs {
+    let mut diffs: MatchDiffs = HashMap::new();
+    let mut failed = 0u32;
+    let flagged: Vec<&FileIssue> = issues.iter().filter(|i| i.unusual_tags.is_some()).collect();
+    println!("[Tags] Migrating tags of {} file(s) to the canonical container...", flagged.len());
+
+    for issue in flagged {
+        let rel = relative_path(&issue.path, scan_root);
+        match migrate_file_tags(&issue.path) {
+            Ok(copied) => {
+                let canonical = FileType::from_path(&issue.path)
+                    .map_or("canonical tag", |t| container_name(canonical_tag_type(t)));
+                println!("  {}: copied {} value(s) into {}", rel, copied, canonical);
+                diffs.insert(issue.path.clone(), vec![FieldMatch {
+                    field: "Tag Container",
+                    old_display: issue.unusual_tags.clone().unwrap_or_default(),
+                    new_value: canonical.to_string(),
+                    category: "other",
+                }]);
+            }
+            Err(e) => {
+                eprintln!("  FAILED to migrate {}: {}", issue.path.display(), e);
+                failed += 1;
+            }
+        }
     }
 
-    write!(f, "</tbody>\n</table></div>\n</div>\n")?;
-    write_page_end(&mut f, true)?;
-    Ok(())
+    println!("  Migrated: {} files | Failed: {}", diffs.len(), failed);
+    diffs
 }
 
 // ---------------------------------------------------------------------------
-// Report: issues.html
+// Blank tags (--strip-blank-tags)
 // ---------------------------------------------------------------------------
 
-fn write_issues_page(
-    report_dir: &Path,
-    scan_root: &str,
-    all_paths: &[PathBuf],
-    parent_audio_count: &HashMap<PathBuf, usize>,
-    unreadable: &[(PathBuf, String)],
-    counts: &NavCounts,
-    pages: &PageFlags,
-) -> std::io::Result<()> {
-    let path = report_dir.join("pages/issues.html");
-    let mut f = BufWriter::new(fs::File::create(&path)?);
+/// Fields checked for blank values, as (rules field, label, default keys).
+const BLANK_FIELDS: &[(&str, &str, &[&str])] = &[
+    ("artist", "Artist", &["ARTIST"]),
+    ("title", "Title", &["TITLE"]),
+    ("year", "Year", &["YEAR"]),
+    ("genre", "Genre", &["GENRE"]),
+];
+
+impl FileIssue {
+    /// Rules fields (see `BLANK_FIELDS`) of the file that hold a blank value.
+    fn blank_fields(&self) -> Vec<&'static str> {
+        [
+            ("artist", self.blank_artist),
+            ("title", self.blank_title),
+            ("year", self.blank_year),
+            ("genre", self.blank_genre),
+        ]
+        .into_iter()
+        .filter_map(|(field, blank)| blank.then_some(field))
+        .collect()
+    }
 
-    write_page_start(&mut f, "Issues", false)?;
-    write_nav(&mut f, "issues", counts, pages, false)?;
+    /// A blank field is also missing; once its items are gone it is only that.
+    fn clear_blank(&mut self, field: &str) {
+        match field {
+            "artist" => self.blank_artist = false,
+            "title" => self.blank_title = false,
+            "year" => self.blank_year = false,
+            "genre" => self.blank_genre = false,
+            _ => {}
+        }
+    }
+}
 
-    write!(f, "<div class=\"search-box\"><input type=\"text\" placeholder=\"Filter files\u{2026}\" oninput=\"filterTable(this)\"></div>\n")?;
-    write!(f, "<div class=\"table-wrap\"><table>\n\
-        <thead><tr><th data-sort=\"0\">Path</th><th data-sort=\"1\">Problem</th></tr></thead>\n<tbody>\n")?;
+/// Whether `item` is a whitespace-only value of one of `keys`, looked up the
+/// way `collect_tags` does: lofty's accessor name (ARTIST, TITLE, YEAR,
+/// GENRE) for the standard items, the raw key name for everything else.
+fn is_blank_item(item: &lofty::tag::TagItem, keys: &[String]) -> bool {
+    use lofty::tag::ItemKey;
+    let lofty::tag::ItemValue::Text(value) = item.value() else { return false };
+    if !value.trim().is_empty() {
+        return false;
+    }
+    let accessor = match item.key() {
+        ItemKey::TrackArtist => Some("ARTIST"),
+        ItemKey::TrackTitle => Some("TITLE"),
+        ItemKey::Year => Some("YEAR"),
+        ItemKey::Genre => Some("GENRE"),
+        _ => None,
+    };
+    let name = item_key_name(item.key());
+    keys.iter().any(|k| *k == name || accessor == Some(k.as_str()))
+}
 
-    // Lone files (only one audio file in parent directory)
-    let mut lone_files: Vec<&PathBuf> = all_paths.iter()
-        .filter(|p| {
-            p.parent()
-                .and_then(|par| parent_audio_count.get(par))
-                .copied()
-                .unwrap_or(0) == 1
-        })
+/// Remove the blank items of `fields` from every tag container of the file.
+/// Returns how many items were (or, when `dry`, would be) removed.
+fn strip_blank_file_tags(path: &Path, fields: &[&str], dry: bool) -> Result<usize, String> {
+    let rules = rules();
+    let keys: Vec<String> = BLANK_FIELDS.iter()
+        .filter(|(field, _, _)| fields.contains(field))
+        .flat_map(|(field, _, defaults)| rules.keys(field, defaults))
+        .map(|k| k.to_uppercase())
         .collect();
-    lone_files.sort();
 
-    for p in &lone_files {
-        let rel = relative_path(p, scan_root);
-        write!(f, "<tr><td title=\"{}\">{}</td><td>Only one file</td></tr>\n",
-            encode_text(&p.to_string_lossy()), encode_text(&rel))?;
+    let mut tagged = lofty::read_from_path(path).map_err(|e| e.to_string())?;
+    let tag_types: Vec<TagType> = tagged.tags().iter().map(|t| t.tag_type()).collect();
+    let mut removed = 0;
+    for tag_type in tag_types {
+        let Some(tag) = tagged.tag_mut(tag_type) else { continue };
+        let before = tag.len();
+        tag.retain(|item| !is_blank_item(item, &keys));
+        let stripped = before - tag.len();
+        if stripped == 0 { continue; }
+        removed += stripped;
+        if !dry {
+            tag.save_to_path(path, WriteOptions::default()).map_err(|e| e.to_string())?;
+        }
     }
+    Ok(removed)
+}
 
-    // Unreadable files
-    let mut sorted_unreadable: Vec<&(PathBuf, String)> = unreadable.iter().collect();
-    sorted_unreadable.sort_by(|a, b| a.0.cmp(&b.0));
-
-    for (p, err) in &sorted_unreadable {
-        let rel = relative_path(p, scan_root);
-        write!(f, "<tr><td title=\"{}\">{}</td><td>{}</td></tr>\n",
-            encode_text(&p.to_string_lossy()),
-            encode_text(&rel),
-            encode_text(err))?;
+/// `--strip-blank-tags`: remove blank artist / title / year / genre items
+/// from every file flagged with one. The stripped fields stay listed as
+/// missing, so nothing is marked as resolved. Nothing is written or updated
+/// when `dry`.
+fn strip_blank_tags(issues: &mut [FileIssue], scan_root: &str, dry: bool) {
+    let mut stripped = 0u32;
+    let mut failed = 0u32;
+    let flagged: Vec<&mut FileIssue> = issues.iter_mut().filter(|i| !i.blank_fields().is_empty()).collect();
+    if dry {
+        println!("[DRY RUN] Would remove blank tags from {} file(s):", flagged.len());
+    } else {
+        println!("[Blank] Removing blank tags from {} file(s)...", flagged.len());
     }
 
-    if lone_files.is_empty() && sorted_unreadable.is_empty() {
-        write!(f, "<tr><td colspan=\"2\" class=\"empty-state\">No issues found</td></tr>\n")?;
+    for issue in flagged {
+        let fields = issue.blank_fields();
+        let labels: Vec<&str> = BLANK_FIELDS.iter()
+            .filter(|(field, _, _)| fields.contains(field))
+            .map(|(_, label, _)| *label)
+            .collect();
+        let rel = relative_path(&issue.path, scan_root);
+        match strip_blank_file_tags(&issue.path, &fields, dry) {
+            Ok(0) => {}
+            Ok(removed) => {
+                println!("  {}: {} ({} item(s))", rel, labels.join(", "), removed);
+                if dry { continue; }
+                for field in fields {
+                    issue.clear_blank(field);
+                }
+                stripped += 1;
+            }
+            Err(e) => {
+                eprintln!("  FAILED to strip {}: {}", issue.path.display(), e);
+                failed += 1;
+            }
+        }
     }
 
-    write!(f, "</tbody>\n</table></div>\n")?;
-    write_page_end(&mut f, false)?;
-    Ok(())
+    if !dry {
+        println!("  Stripped: {} files | Failed: {}", stripped, failed);
+    }
 }
 
 // ---------------------------------------------------------------------------
-// Report: critical.html
+// Trivial fixes (--fix-trivial)
 // ---------------------------------------------------------------------------
 
-fn write_critical_page(
-    report_dir: &Path,
-    scan_root: &str,
-    issues: &[FileIssue],
-    counts: &NavCounts,
-    pages: &PageFlags,
-    diffs: Option<&MatchDiffs>,
-    skipped_files: Option<&SkippedFiles>,
-) -> std::io::Result<()> {
-    // Build per-field groups
-    let artist_groups = build_groups(
-        issues, scan_root,
-        |i| i.missing_artist || i.blank_artist,
-        |i| if i.blank_artist { Some("(blank)".into()) } else { None },
-        diffs, skipped_files, Some("Artist"),
-    );
-    let title_groups = build_groups(
-        issues, scan_root,
-        |i| i.missing_title || i.blank_title,
-        |i| if i.blank_title { Some("(blank)".into()) } else { None },
-        diffs, skipped_files, Some("Title"),
-    );
-    let year_groups = build_groups(
-        issues, scan_root,
-        |i| i.missing_year || i.blank_year || i.invalid_year.is_some(),
-        |i| {
-            if i.blank_year { Some("(blank)".into()) }
-            else if let Some(v) = &i.invalid_year { Some(format!("({})", v)) }
-            else { None }
-        },
-        diffs, skipped_files, Some("Year"),
-    );
-
-    let all_artists = collect_all_artists(&[&artist_groups, &title_groups, &year_groups]);
-    let total_pages = ((all_artists.len() + ARTISTS_PER_PAGE - 1) / ARTISTS_PER_PAGE).max(1);
+/// One change `--fix-trivial` makes to a tag item, with the item's key as
+/// the format spells it (e.g. `YEAR`, `TPE1`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TrivialFix {
+    key: String,
+    old: String,
+    new: String,
+}
 
-    for page_num in 1..=total_pages {
-        let start = (page_num - 1) * ARTISTS_PER_PAGE;
-        let end = (start + ARTISTS_PER_PAGE).min(all_artists.len());
-        let page_artists: HashSet<&str> = if start < all_artists.len() {
-            all_artists[start..end].iter().map(|s| s.as_str()).collect()
+impl TrivialFix {
+    fn describe(&self) -> String {
+        if self.new.is_empty() {
+            format!("{} (blank) removed", self.key)
         } else {
-            HashSet::new()
-        };
-
-        let pg_artist = filter_groups(&artist_groups, &page_artists);
-        let pg_title  = filter_groups(&title_groups, &page_artists);
-        let pg_year   = filter_groups(&year_groups, &page_artists);
-
-        let path = report_dir.join(format!("pages/critical_{}.html", page_num));
-        let mut f = BufWriter::new(fs::File::create(&path)?);
-
-        write_page_start(&mut f, "Critical", false)?;
-        write_nav(&mut f, "critical", counts, pages, false)?;
+            format!("{} '{}' -> '{}'", self.key, self.old, self.new)
+        }
+    }
+}
 
-        let tabs: &[(&str, &str, usize, usize)] = &[
-            ("artist", "Artist",  group_total(&pg_artist), group_matched_count(&pg_artist)),
-            ("title",  "Title",   group_total(&pg_title),  group_matched_count(&pg_title)),
-            ("year",   "Year",    group_total(&pg_year),   group_matched_count(&pg_year)),
-        ];
+fn is_plain_year(value: &str) -> bool {
+    value.len() == 4 && value.chars().all(|c| c.is_ascii_digit())
+}
 
-        write!(f, "<div class=\"search-box\"><input type=\"text\" placeholder=\"Filter files\u{2026}\" oninput=\"filterGroups(this)\"></div>\n")?;
-        write_pagination(&mut f, "critical", page_num, total_pages)?;
-        write_subtab_bar(&mut f, tabs)?;
-        write_field_panel(&mut f, "artist", &pg_artist, true,  "critical", diffs, scan_root)?;
-        write_field_panel(&mut f, "title",  &pg_title,  false, "critical", diffs, scan_root)?;
-        write_field_panel(&mut f, "year",   &pg_year,   false, "critical", diffs, scan_root)?;
-        write_pagination(&mut f, "critical", page_num, total_pages)?;
+/// The year an original release date ("1997-03-04", "1997") starts with,
+/// when the rules accept it.
+fn original_year(tags: &HashMap<String, String>, rules: &Rules) -> Option<i32> {
+    let date = get_tag(tags, ORIGINAL_DATE_TAGS)?;
+    let year = date.trim().get(..4).filter(|y| is_plain_year(y))?.parse().ok()?;
+    rules.year_is_valid(year).then_some(year)
+}
 
-        write_page_end(&mut f, false)?;
+/// The year inside an invalid YEAR value, when it holds exactly one:
+/// "2019-05-03", "03/05/2019" and "(2019)" give "2019". A plain year, two
+/// years ("1997/2007") or no four-digit year ("'97") give `None`.
+fn trivial_year(value: &str) -> Option<String> {
+    let value = value.trim();
+    if is_plain_year(value) {
+        return None;
     }
-    Ok(())
+    let runs: Vec<&str> = value.split(|c: char| !c.is_ascii_digit()).filter(|r| !r.is_empty()).collect();
+    let mut years = runs.iter().filter(|r| r.len() == 4);
+    let year = years.next()?;
+    let only_one = years.next().is_none() && runs.iter().all(|r| r.len() == 4 || r.len() <= 2);
+    let valid = year.parse().is_ok_and(|n| rules().year_is_valid(n));
+    (only_one && valid).then(|| year.to_string())
 }
 
-// ---------------------------------------------------------------------------
-// Report: mb.html
-// ---------------------------------------------------------------------------
+/// Apply the trivial fixes to every tag container of the file. Returns the
+/// changes made (or, when `dry`, that would be made).
+fn fix_trivial_file_tags(path: &Path, dry: bool, normalize: bool) -> Result<Vec<TrivialFix>, String> {
+    use lofty::tag::{ItemKey, ItemValue};
+    // The Vorbis vendor string reads as an (often empty) encoder item and is rewritten on every save
+    let is_blank = |item: &lofty::tag::TagItem| *item.key() != ItemKey::EncoderSoftware
+        && matches!(item.value(), ItemValue::Text(v) if v.trim().is_empty());
+
+    let mut tagged = lofty::read_from_path(path).map_err(|e| e.to_string())?;
+    let tag_types: Vec<TagType> = tagged.tags().iter().map(|t| t.tag_type()).collect();
+    let mut fixes = Vec::new();
+    for tag_type in tag_types {
+        let Some(tag) = tagged.tag_mut(tag_type) else { continue };
+        let key_name = |key: &ItemKey| key.map_key(tag_type, true).map_or_else(|| item_key_name(key), String::from);
+        let mut changed = Vec::new();
+
+        // Blank items: players show them as empty fields
+        for item in tag.items().filter(|i| is_blank(i)) {
+            changed.push(TrivialFix { key: key_name(item.key()), old: String::new(), new: String::new() });
+        }
+        tag.retain(|item| !is_blank(item));
 
-fn write_mb_page(
-    report_dir: &Path,
-    scan_root: &str,
-    issues: &[FileIssue],
-    counts: &NavCounts,
-    pages: &PageFlags,
-    diffs: Option<&MatchDiffs>,
-    skipped_files: Option<&SkippedFiles>,
-) -> std::io::Result<()> {
-    let artist_groups = build_groups(issues, scan_root, |i| i.missing_mb_artist_id, |_| None, diffs, skipped_files, Some("MB Artist ID"));
-    let track_groups  = build_groups(issues, scan_root, |i| i.missing_mb_track_id,  |_| None, diffs, skipped_files, Some("MB Track ID"));
-    let album_groups  = build_groups(issues, scan_root, |i| i.missing_mb_album_id,  |_| None, diffs, skipped_files, Some("MB Album ID"));
-
-    let all_artists = collect_all_artists(&[&artist_groups, &track_groups, &album_groups]);
-    let total_pages = ((all_artists.len() + ARTISTS_PER_PAGE - 1) / ARTISTS_PER_PAGE).max(1);
-
-    for page_num in 1..=total_pages {
-        let start = (page_num - 1) * ARTISTS_PER_PAGE;
-        let end = (start + ARTISTS_PER_PAGE).min(all_artists.len());
-        let page_artists: HashSet<&str> = if start < all_artists.len() {
-            all_artists[start..end].iter().map(|s| s.as_str()).collect()
-        } else {
-            HashSet::new()
-        };
+        // A date in YEAR: keep the year
+        if let Some(old) = tag.get_string(&ItemKey::Year).map(String::from) {
+            if let Some(year) = trivial_year(&old) {
+                tag.insert_text(ItemKey::Year, year.clone());
+                changed.push(TrivialFix { key: key_name(&ItemKey::Year), old, new: year });
+            }
+        }
 
-        let pg_artist = filter_groups(&artist_groups, &page_artists);
-        let pg_track  = filter_groups(&track_groups, &page_artists);
-        let pg_album  = filter_groups(&album_groups, &page_artists);
+        // YEAR and DATE where the format has both (Vorbis comments): fill the missing one
+        if ItemKey::Year.map_key(tag_type, false).is_some() && ItemKey::RecordingDate.map_key(tag_type, false).is_some() {
+            let year = tag.get_string(&ItemKey::Year).map(|v| v.trim().to_string());
+            let date = tag.get_string(&ItemKey::RecordingDate).map(|v| v.trim().to_string());
+            let fill = match (year, date) {
+                (None, Some(date)) => date.get(..4).filter(|y| is_plain_year(y)).map(|y| (ItemKey::Year, y.to_string())),
+                (Some(year), None) if is_plain_year(&year) => Some((ItemKey::RecordingDate, year)),
+                _ => None,
+            };
+            if let Some((key, value)) = fill {
+                changed.push(TrivialFix { key: key_name(&key), old: "Missing".into(), new: value.clone() });
+                tag.insert_text(key, value);
+            }
+        }
 
-        let path = report_dir.join(format!("pages/mb_{}.html", page_num));
-        let mut f = BufWriter::new(fs::File::create(&path)?);
+        // --normalize, neither YEAR nor DATE: take the year of the original release date
+        if normalize && tag.get_string(&ItemKey::Year).is_none() && tag.get_string(&ItemKey::RecordingDate).is_none() {
+            let original = tag.get_string(&ItemKey::OriginalReleaseDate).map(|v| v.trim().to_string());
+            let year = original.as_deref()
+                .and_then(|date| date.get(..4))
+                .filter(|y| is_plain_year(y) && y.parse().is_ok_and(|n| rules().year_is_valid(n)));
+            if let Some(year) = year.map(String::from) {
+                // ID3v2 has no YEAR of its own: lofty keeps the year in TDRC
+                let key = if ItemKey::Year.map_key(tag_type, false).is_some() { ItemKey::Year } else { ItemKey::RecordingDate };
+                changed.push(TrivialFix { key: key_name(&key), old: "Missing".into(), new: year.clone() });
+                tag.insert_text(key, year);
+            }
+        }
 
-        write_page_start(&mut f, "MusicBrainz", false)?;
-        write_nav(&mut f, "mb", counts, pages, false)?;
+        if changed.is_empty() { continue; }
+        if !dry {
+            tag.save_to_path(path, WriteOptions::default()).map_err(|e| e.to_string())?;
+        }
+        fixes.extend(changed);
+    }
+    Ok(fixes)
+}
 
-        let tabs: &[(&str, &str, usize, usize)] = &[
-            ("mb-artist", "MB Artist", group_total(&pg_artist), group_matched_count(&pg_artist)),
-            ("mb-track",  "MB Track",  group_total(&pg_track),  group_matched_count(&pg_track)),
-            ("mb-album",  "MB Album",  group_total(&pg_album),  group_matched_count(&pg_album)),
-        ];
+/// `--fix-trivial`: repair blank items and YEAR / DATE values in every
+/// scanned file. All of them are read again, not only the flagged ones: a
+/// YEAR of "2019-05-03" reads as 2019 and a DATE alone passes the scan.
+/// Flagged files drop their blank flags (the field stays missing, as with
+/// `--strip-blank-tags`), and fixed invalid years are returned as resolved
+/// changes. With `normalize`, a year missing next to an original release
+/// date is written too. Nothing is written or updated when `dry`.
+fn fix_trivial(paths: &[PathBuf], issues: &mut [FileIssue], scan_root: &str, dry: bool, normalize: bool) -> MatchDiffs {
+    if dry {
+        println!("[DRY RUN] Looking for trivial fixes in {} file(s):", paths.len());
+    } else {
+        println!("[Trivial] Fixing blank items and YEAR / DATE values in {} file(s)...", paths.len());
+    }
+    let mut results: Vec<(&PathBuf, Result<Vec<TrivialFix>, String>)> = paths.par_iter()
+        .map(|path| (path, fix_trivial_file_tags(path, dry, normalize)))
+        .filter(|(_, result)| result.as_ref().map_or(true, |fixes| !fixes.is_empty()))
+        .collect();
+    results.sort_by(|a, b| a.0.cmp(b.0));
 
-        write!(f, "<div class=\"search-box\"><input type=\"text\" placeholder=\"Filter files\u{2026}\" oninput=\"filterGroups(this)\"></div>\n")?;
-        write_pagination(&mut f, "mb", page_num, total_pages)?;
-        write_subtab_bar(&mut f, tabs)?;
-        write_field_panel(&mut f, "mb-artist", &pg_artist, true,  "mb", diffs, scan_root)?;
-        write_field_panel(&mut f, "mb-track",  &pg_track,  false, "mb", diffs, scan_root)?;
-        write_field_panel(&mut f, "mb-album",  &pg_album,  false, "mb", diffs, scan_root)?;
-        write_pagination(&mut f, "mb", page_num, total_pages)?;
+    let mut fixed: HashMap<&Path, Vec<TrivialFix>> = HashMap::new();
+    let mut failed = 0u32;
+    for (path, result) in results {
+        match result {
+            Ok(fixes) => {
+                let changes: Vec<String> = fixes.iter().map(TrivialFix::describe).collect();
+                println!("  {}: {}", relative_path(path, scan_root), changes.join(", "));
+                fixed.insert(path, fixes);
+            }
+            Err(e) => {
+                eprintln!("  FAILED to fix {}: {}", path.display(), e);
+                failed += 1;
+            }
+        }
+    }
 
-        write_page_end(&mut f, false)?;
+    let mut diffs: MatchDiffs = HashMap::new();
+    if dry {
+        println!("  Would fix: {} files | Unreadable: {}", fixed.len(), failed);
+        return diffs;
     }
-    Ok(())
+    for issue in issues.iter_mut() {
+        let Some(fixes) = fixed.get(issue.path.as_path()) else { continue };
+        if fixes.iter().any(|f| f.new.is_empty()) {
+            for field in issue.blank_fields() {
+                issue.clear_blank(field);
+            }
+        }
+        let year = fixes.iter().find(|f| !f.new.is_empty() && f.old != "Missing");
+        if let (Some(old), Some(year)) = (&issue.invalid_year, year) {
+            diffs.insert(issue.path.clone(), vec![FieldMatch {
+                field: "Year",
+                old_display: format!("({})", old),
+                new_value: year.new.clone(),
+                category: "critical",
+            }]);
+        }
+    }
+    println!("  Fixed: {} files | Failed: {}", fixed.len(), failed);
+    diffs
 }
 
 // ---------------------------------------------------------------------------
-// Report: discogs.html
+// Tag value consistency
 // ---------------------------------------------------------------------------
 
-fn write_discogs_page(
-    report_dir: &Path,
-    scan_root: &str,
-    issues: &[FileIssue],
-    counts: &NavCounts,
-    pages: &PageFlags,
-    diffs: Option<&MatchDiffs>,
-    skipped_files: Option<&SkippedFiles>,
-) -> std::io::Result<()> {
-    let artist_groups  = build_groups(issues, scan_root, |i| i.missing_discogs_artist,  |_| None, diffs, skipped_files, Some("Discogs Artist"));
-    let release_groups = build_groups(issues, scan_root, |i| i.missing_discogs_release, |_| None, diffs, skipped_files, Some("Discogs Release"));
-
-    let all_artists = collect_all_artists(&[&artist_groups, &release_groups]);
-    let total_pages = ((all_artists.len() + ARTISTS_PER_PAGE - 1) / ARTISTS_PER_PAGE).max(1);
-
-    for page_num in 1..=total_pages {
-        let start = (page_num - 1) * ARTISTS_PER_PAGE;
-        let end = (start + ARTISTS_PER_PAGE).min(all_artists.len());
-        let page_artists: HashSet<&str> = if start < all_artists.len() {
-            all_artists[start..end].iter().map(|s| s.as_str()).collect()
-        } else {
-            HashSet::new()
-        };
+/// Tags whose spellings are compared across the library, as (label, keys).
+const CONSISTENCY_FIELDS: &[(&str, &[&str])] = &[
+    ("Genre", &["GENRE"]),
+    ("Artist", &["ARTIST"]),
+    ("Album Artist", &["ALBUMARTIST"]),
+];
+
+/// A file's values for `CONSISTENCY_FIELDS`, as (label, value).
+type TagValues = Vec<(&'static str, String)>;
+
+/// How many files carry each exact (label, value).
+type ValueCounts = HashMap<(&'static str, String), u64>;
+
+/// Spellings of one field that only differ by case, diacritics, spacing or
+/// punctuation ("Hip-Hop", "Hip Hop", "hip hop"), most used first.
+#[derive(Debug)]
+struct Variants {
+    field: &'static str,
+    values: Vec<(String, u64)>,
+}
 
-        let pg_artist  = filter_groups(&artist_groups, &page_artists);
-        let pg_release = filter_groups(&release_groups, &page_artists);
+impl Variants {
+    fn files(&self) -> u64 {
+        self.values.iter().map(|(_, n)| n).sum()
+    }
+}
 
-        let path = report_dir.join(format!("pages/discogs_{}.html", page_num));
-        let mut f = BufWriter::new(fs::File::create(&path)?);
+fn tag_values(tags: &HashMap<String, String>) -> TagValues {
+    CONSISTENCY_FIELDS.iter()
+        .filter_map(|(label, keys)| get_tag(tags, keys).map(|v| (*label, v)))
+        .collect()
+}
 
-        write_page_start(&mut f, "Discogs", false)?;
-        write_nav(&mut f, "discogs", counts, pages, false)?;
+/// Comparison key: lowercase letters and digits only, accents stripped.
+fn consistency_key(value: &str) -> String {
+    value.nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .filter(|c| c.is_alphanumeric())
+        .collect()
+}
 
-        let tabs: &[(&str, &str, usize, usize)] = &[
-            ("dg-artist",  "Discogs Artist",  group_total(&pg_artist),  group_matched_count(&pg_artist)),
-            ("dg-release", "Discogs Release", group_total(&pg_release), group_matched_count(&pg_release)),
-        ];
+/// Every field value with more than one spelling, biggest groups first.
+fn find_variants(counts: &ValueCounts) -> Vec<Variants> {
+    let mut groups: BTreeMap<(&'static str, String), Vec<(String, u64)>> = BTreeMap::new();
+    for ((field, value), n) in counts {
+        let key = consistency_key(value);
+        if key.is_empty() { continue; }
+        groups.entry((*field, key)).or_default().push((value.clone(), *n));
+    }
 
-        write!(f, "<div class=\"search-box\"><input type=\"text\" placeholder=\"Filter files\u{2026}\" oninput=\"filterGroups(this)\"></div>\n")?;
-        write_pagination(&mut f, "discogs", page_num, total_pages)?;
-        write_subtab_bar(&mut f, tabs)?;
-        write_field_panel(&mut f, "dg-artist",  &pg_artist,  true,  "discogs", diffs, scan_root)?;
-        write_field_panel(&mut f, "dg-release", &pg_release, false, "discogs", diffs, scan_root)?;
-        write_pagination(&mut f, "discogs", page_num, total_pages)?;
+    let mut variants: Vec<Variants> = groups.into_iter()
+        .filter(|(_, values)| values.len() > 1)
+        .map(|((field, _), mut values)| {
+            values.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            Variants { field, values }
+        })
+        .collect();
+    variants.sort_by_key(|v| std::cmp::Reverse(v.files()));
+    variants
+}
 
-        write_page_end(&mut f, false)?;
-    }
-    Ok(())
+/// Quoted value with padding and repeated whitespace shown as "␣", which
+/// HTML would otherwise collapse.
+fn visible_spaces(value: &str) -> String {
+    let chars: Vec<char> = value.chars().collect();
+    let shown: String = chars.iter().enumerate()
+        .map(|(i, &c)| {
+            let inner = i > 0 && i + 1 < chars.len()
+                && !chars[i - 1].is_whitespace() && !chars[i + 1].is_whitespace();
+            if c.is_whitespace() && !(c == ' ' && inner) { '␣' } else { c }
+        })
+        .collect();
+    format!("\"{}\"", shown)
 }
 
 // ---------------------------------------------------------------------------
-// Report: ids.html
+// Issue rules (--rules)
 // ---------------------------------------------------------------------------
 
-fn write_ids_page(
-    report_dir: &Path,
-    scan_root: &str,
-    issues: &[FileIssue],
-    counts: &NavCounts,
-    pages: &PageFlags,
-    diffs: Option<&MatchDiffs>,
-    skipped_files: Option<&SkippedFiles>,
-) -> std::io::Result<()> {
-    let acoustic_groups  = build_groups(issues, scan_root, |i| i.missing_acoustic_id,       |_| None, diffs, skipped_files, Some("Acoustic ID"));
-    let songkong_groups  = build_groups(issues, scan_root, |i| i.missing_songkong_id,        |_| None, diffs, skipped_files, Some("SongKong ID"));
-    let bandcamp_groups  = build_groups(issues, scan_root, |i| i.missing_bandcamp,           |_| None, diffs, skipped_files, Some("Bandcamp"));
-    let wiki_groups      = build_groups(issues, scan_root, |i| i.missing_wikipedia_artist,   |_| None, diffs, skipped_files, Some("Wikipedia Artist"));
-
-    let all_artists = collect_all_artists(&[&acoustic_groups, &songkong_groups, &bandcamp_groups, &wiki_groups]);
-    let total_pages = ((all_artists.len() + ARTISTS_PER_PAGE - 1) / ARTISTS_PER_PAGE).max(1);
-
-    for page_num in 1..=total_pages {
-        let start = (page_num - 1) * ARTISTS_PER_PAGE;
-        let end = (start + ARTISTS_PER_PAGE).min(all_artists.len());
-        let page_artists: HashSet<&str> = if start < all_artists.len() {
-            all_artists[start..end].iter().map(|s| s.as_str()).collect()
-        } else {
-            HashSet::new()
-        };
+/// Rules of the run, set once from `--rules` before the scan.
+static RULES: OnceLock<Rules> = OnceLock::new();
 
-        let pg_acoustic = filter_groups(&acoustic_groups, &page_artists);
-        let pg_songkong = filter_groups(&songkong_groups, &page_artists);
-        let pg_bandcamp = filter_groups(&bandcamp_groups, &page_artists);
-        let pg_wiki     = filter_groups(&wiki_groups, &page_artists);
+/// Built-in rules: every field required under its default keys.
+static DEFAULT_RULES: Rules = Rules {
+    year: YearRange { min: 1, max: 2029 },
+    fields: BTreeMap::new(),
+    categories: Vec::new(),
+};
 
-        let path = report_dir.join(format!("pages/ids_{}.html", page_num));
-        let mut f = BufWriter::new(fs::File::create(&path)?);
+fn rules() -> &'static Rules {
+    RULES.get().unwrap_or(&DEFAULT_RULES)
+}
 
-        write_page_start(&mut f, "IDs", false)?;
-        write_nav(&mut f, "ids", counts, pages, false)?;
+/// Built-in fields a rules file can configure, by panel id. Album art has no
+/// tag keys; it can only be made optional.
+const RULE_FIELDS: &[&str] = &[
+    "artist", "title", "year",
+    "mb-artist", "mb-track", "mb-album",
+    "acoustic", "songkong", "bandcamp", "wikipedia",
+    "dg-artist", "dg-release",
+    "genre", "bpm", "mood", "album-art",
+];
+
+/// Page ids a custom category can't take.
+const RESERVED_PAGES: &[&str] = &[
+    "index", "issues", "consistency", "mirror", "archives", "fingerprints",
+];
+
+/// A `--rules` TOML file: tag keys and requirement per built-in field, the
+/// valid year range, and extra categories with their own report pages.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Rules {
+    #[serde(default)]
+    year: YearRange,
+    /// Built-in field (see `RULE_FIELDS`) -> overrides
+    #[serde(default)]
+    fields: BTreeMap<String, FieldRule>,
+    #[serde(default, rename = "category")]
+    categories: Vec<CustomCategory>,
+}
 
-        let tabs: &[(&str, &str, usize, usize)] = &[
-            ("acoustic",  "Acoustic ID", group_total(&pg_acoustic), group_matched_count(&pg_acoustic)),
-            ("songkong",  "SongKong",    group_total(&pg_songkong), group_matched_count(&pg_songkong)),
-            ("bandcamp",  "Bandcamp",    group_total(&pg_bandcamp), group_matched_count(&pg_bandcamp)),
-            ("wikipedia", "Wikipedia",   group_total(&pg_wiki),     group_matched_count(&pg_wiki)),
-        ];
+/// Years outside `min..=max` are flagged as invalid.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct YearRange {
+    #[serde(default = "YearRange::default_min")]
+    min: i32,
+    #[serde(default = "YearRange::default_max")]
+    max: i32,
+}
 
-        write!(f, "<div class=\"search-box\"><input type=\"text\" placeholder=\"Filter files\u{2026}\" oninput=\"filterGroups(this)\"></div>\n")?;
-        write_pagination(&mut f, "ids", page_num, total_pages)?;
-        write_subtab_bar(&mut f, tabs)?;
-        write_field_panel(&mut f, "acoustic",  &pg_acoustic, true,  "ids", diffs, scan_root)?;
-        write_field_panel(&mut f, "songkong",  &pg_songkong, false, "ids", diffs, scan_root)?;
-        write_field_panel(&mut f, "bandcamp",  &pg_bandcamp, false, "ids", diffs, scan_root)?;
-        write_field_panel(&mut f, "wikipedia", &pg_wiki,     false, "ids", diffs, scan_root)?;
-        write_pagination(&mut f, "ids", page_num, total_pages)?;
+impl YearRange {
+    fn default_min() -> i32 { 1 }
+    fn default_max() -> i32 { 2029 }
+}
 
-        write_page_end(&mut f, false)?;
+impl Default for YearRange {
+    fn default() -> Self {
+        YearRange { min: Self::default_min(), max: Self::default_max() }
     }
-    Ok(())
 }
 
-// ---------------------------------------------------------------------------
-// Report: other.html
-// ---------------------------------------------------------------------------
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FieldRule {
+    /// Replace the field's tag keys
+    keys: Option<Vec<String>>,
+    /// `false` stops flagging the field missing or blank
+    #[serde(default = "FieldRule::default_required")]
+    required: bool,
+}
 
-fn write_other_page(
-    report_dir: &Path,
-    scan_root: &str,
-    issues: &[FileIssue],
-    counts: &NavCounts,
-    pages: &PageFlags,
-    diffs: Option<&MatchDiffs>,
-    skipped_files: Option<&SkippedFiles>,
-) -> std::io::Result<()> {
-    let genre_groups = build_groups(
-        issues, scan_root,
-        |i| i.missing_genre || i.blank_genre,
-        |i| if i.blank_genre { Some("(blank)".into()) } else { None },
-        diffs, skipped_files, Some("Genre"),
-    );
-    let bpm_groups   = build_groups(issues, scan_root, |i| i.missing_bpm,       |_| None, diffs, skipped_files, Some("BPM"));
-    let mood_groups  = build_groups(issues, scan_root, |i| i.missing_mood,       |_| None, diffs, skipped_files, Some("Mood"));
-    let art_groups   = build_groups(issues, scan_root, |i| i.missing_album_art,  |_| None, diffs, skipped_files, Some("Album Art"));
-
-    let all_artists = collect_all_artists(&[&genre_groups, &bpm_groups, &mood_groups, &art_groups]);
-    let total_pages = ((all_artists.len() + ARTISTS_PER_PAGE - 1) / ARTISTS_PER_PAGE).max(1);
-
-    for page_num in 1..=total_pages {
-        let start = (page_num - 1) * ARTISTS_PER_PAGE;
-        let end = (start + ARTISTS_PER_PAGE).min(all_artists.len());
-        let page_artists: HashSet<&str> = if start < all_artists.len() {
-            all_artists[start..end].iter().map(|s| s.as_str()).collect()
-        } else {
-            HashSet::new()
-        };
+impl FieldRule {
+    fn default_required() -> bool { true }
+}
 
-        let pg_genre = filter_groups(&genre_groups, &page_artists);
-        let pg_bpm   = filter_groups(&bpm_groups, &page_artists);
-        let pg_mood  = filter_groups(&mood_groups, &page_artists);
-        let pg_art   = filter_groups(&art_groups, &page_artists);
+/// A category of required tags from the rules file, e.g. ReplayGain or ISRC.
+/// It gets its own paginated page, one panel per field.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CustomCategory {
+    id: String,
+    title: String,
+    fields: Vec<CustomField>,
+}
 
-        let path = report_dir.join(format!("pages/other_{}.html", page_num));
-        let mut f = BufWriter::new(fs::File::create(&path)?);
-
-        write_page_start(&mut f, "Other", false)?;
-        write_nav(&mut f, "other", counts, pages, false)?;
-
-        let tabs: &[(&str, &str, usize, usize)] = &[
-            ("genre",     "Genre",     group_total(&pg_genre), group_matched_count(&pg_genre)),
-            ("bpm",       "BPM",       group_total(&pg_bpm),   group_matched_count(&pg_bpm)),
-            ("mood",      "Mood",      group_total(&pg_mood),  group_matched_count(&pg_mood)),
-            ("album-art", "Album Art", group_total(&pg_art),   group_matched_count(&pg_art)),
-        ];
-
-        write!(f, "<div class=\"search-box\"><input type=\"text\" placeholder=\"Filter files\u{2026}\" oninput=\"filterGroups(this)\"></div>\n")?;
-        write_pagination(&mut f, "other", page_num, total_pages)?;
-        write_subtab_bar(&mut f, tabs)?;
-        write_field_panel(&mut f, "genre",     &pg_genre, true,  "other", diffs, scan_root)?;
-        write_field_panel(&mut f, "bpm",       &pg_bpm,   false, "other", diffs, scan_root)?;
-        write_field_panel(&mut f, "mood",      &pg_mood,  false, "other", diffs, scan_root)?;
-        write_field_panel(&mut f, "album-art", &pg_art,   false, "other", diffs, scan_root)?;
-        write_pagination(&mut f, "other", page_num, total_pages)?;
-
-        write_page_end(&mut f, false)?;
+impl CustomCategory {
+    fn has_issue(&self, issue: &FileIssue) -> bool {
+        self.fields.iter().any(|f| issue.custom_missing.contains(&f.id))
     }
-    Ok(())
 }
 
-// ---------------------------------------------------------------------------
-// Report: orchestrator
-// ---------------------------------------------------------------------------
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CustomField {
+    label: String,
+    keys: Vec<String>,
+    /// `<category id>-<slug of label>`; set by `Rules::parse`
+    #[serde(skip)]
+    id: String,
+}
 
-fn generate_report(
-    issues: &[FileIssue],
-    all_paths: &[PathBuf],
-    parent_audio_count: &HashMap<PathBuf, usize>,
-    unreadable: &[(PathBuf, String)],
-    scan_root: &str,
-    total_files: u64,
-    total_size: u64,
-    error_count: u64,
-    file_type_counts: &HashMap<String, u64>,
-    elapsed: std::time::Duration,
-    report_dir: &Path,
-    pages: &PageFlags,
-    diffs: Option<&MatchDiffs>,
-    skipped_files: Option<&SkippedFiles>,
-) -> std::io::Result<()> {
-    // Create directory structure
-    fs::create_dir_all(report_dir.join("css"))?;
-    fs::create_dir_all(report_dir.join("js"))?;
-    fs::create_dir_all(report_dir.join("pages"))?;
+/// "Track Gain" -> "track-gain".
+fn slug(s: &str) -> String {
+    s.to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
 
-    // Compute lone files count for nav badge
-    let lone_count = all_paths.iter()
-        .filter(|p| {
-            p.parent()
-                .and_then(|par| parent_audio_count.get(par))
-                .copied()
-                .unwrap_or(0) == 1
-        })
-        .count();
-
-    // Compute matched counts per category from autofix diffs
-    let (critical_matched, mb_matched, discogs_matched, ids_matched, other_matched) = if let Some(d) = diffs {
-        let mut cf = HashSet::new();
-        let mut mf = HashSet::new();
-        let mut df = HashSet::new();
-        let mut idf = HashSet::new();
-        let mut of = HashSet::new();
-        for (path, fixes) in d {
-            for fix in fixes {
-                match fix.category {
-                    "critical" => { cf.insert(path); }
-                    "mb"       => { mf.insert(path); }
-                    "discogs"  => { df.insert(path); }
-                    "ids"      => { idf.insert(path); }
-                    "other"    => { of.insert(path); }
-                    _ => {}
+impl Rules {
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut rules: Rules = toml::from_str(text).map_err(|e| e.to_string())?;
+
+        if rules.year.min > rules.year.max {
+            return Err(format!("year: min {} is after max {}", rules.year.min, rules.year.max));
+        }
+        for (name, rule) in &rules.fields {
+            if !RULE_FIELDS.contains(&name.as_str()) {
+                return Err(format!("fields.{}: unknown field (one of {})", name, RULE_FIELDS.join(", ")));
+            }
+            match rule.keys {
+                Some(_) if name == "album-art" => return Err("fields.album-art: has no tag keys, only `required`".into()),
+                Some(ref keys) if keys.iter().all(|k| k.trim().is_empty()) => {
+                    return Err(format!("fields.{}: keys is empty", name));
                 }
+                _ => {}
             }
         }
-        (cf.len(), mf.len(), df.len(), idf.len(), of.len())
-    } else {
-        (0, 0, 0, 0, 0)
-    };
-
-    let counts = NavCounts {
-        issues: lone_count + unreadable.len(),
-        critical: issues.iter().filter(|i| i.has_critical()).count(),
-        mb: issues.iter().filter(|i| i.has_mb()).count(),
-        discogs: issues.iter().filter(|i| i.has_discogs()).count(),
-        ids: issues.iter().filter(|i| i.has_ids()).count(),
-        other: issues.iter().filter(|i| i.has_other()).count(),
-        critical_matched,
-        mb_matched,
-        discogs_matched,
-        ids_matched,
-        other_matched,
-    };
 
-    // Write shared assets
-    fs::write(report_dir.join("css/styles.css"), CSS)?;
-    fs::write(report_dir.join("js/report.js"), JS)?;
+        let builtin_pages: Vec<&str> = CATEGORY_PAGES.iter().map(|c| c.id).collect();
+        let mut page_ids = HashSet::new();
+        let mut field_ids = HashSet::new();
+        for category in &mut rules.categories {
+            if category.id.is_empty() || slug(&category.id) != category.id {
+                return Err(format!("category \"{}\": id must be lowercase letters, digits and dashes", category.id));
+            }
+            if builtin_pages.contains(&category.id.as_str()) || RESERVED_PAGES.contains(&category.id.as_str()) {
+                return Err(format!("category \"{}\": id is taken by a built-in page", category.id));
+            }
+            if !page_ids.insert(category.id.clone()) {
+                return Err(format!("category \"{}\": defined twice", category.id));
+            }
+            if category.fields.is_empty() {
+                return Err(format!("category \"{}\": has no fields", category.id));
+            }
+            for field in &mut category.fields {
+                if field.keys.iter().all(|k| k.trim().is_empty()) {
+                    return Err(format!("category \"{}\": field \"{}\" has no keys", category.id, field.label));
+                }
+                field.id = format!("{}-{}", category.id, slug(&field.label));
+                if !field_ids.insert(field.id.clone()) {
+                    return Err(format!("category \"{}\": field \"{}\" defined twice", category.id, field.label));
+                }
+            }
+        }
+        Ok(rules)
+    }
 
-    // Write index (always)
-    write_index(
-        report_dir, scan_root, total_files, total_size, error_count,
-        file_type_counts, elapsed, issues.len(), &counts, pages,
-    )?;
+    fn load(path: &str) -> Result<Self, String> {
+        Rules::parse(&fs::read_to_string(path).map_err(|e| e.to_string())?)
+    }
 
-    // Write selected pages
-    // Issues page is always generated (lone files + unreadable files are always relevant)
-    write_issues_page(report_dir, scan_root, all_paths, parent_audio_count, unreadable, &counts, pages)?;
-    if pages.critical {
-        write_critical_page(report_dir, scan_root, issues, &counts, pages, diffs, skipped_files)?;
+    fn required(&self, field: &str) -> bool {
+        self.fields.get(field).is_none_or(|rule| rule.required)
     }
-    if pages.mb {
-        write_mb_page(report_dir, scan_root, issues, &counts, pages, diffs, skipped_files)?;
+
+    /// Tag keys of a built-in field: the rules file's, or `defaults`.
+    fn keys<'a>(&'a self, field: &str, defaults: &[&'a str]) -> Vec<&'a str> {
+        match self.fields.get(field).and_then(|rule| rule.keys.as_ref()) {
+            Some(keys) => keys.iter().map(String::as_str).collect(),
+            None => defaults.to_vec(),
+        }
     }
-    if pages.discogs {
-        write_discogs_page(report_dir, scan_root, issues, &counts, pages, diffs, skipped_files)?;
+
+    /// Label of a custom field, by id.
+    fn custom_label(&self, id: &str) -> Option<&str> {
+        self.categories.iter().flat_map(|c| &c.fields).find(|f| f.id == id).map(|f| f.label.as_str())
     }
-    if pages.ids {
-        write_ids_page(report_dir, scan_root, issues, &counts, pages, diffs, skipped_files)?;
+
+    fn year_is_valid(&self, year: i32) -> bool {
+        (self.year.min..=self.year.max).contains(&year)
     }
-    if pages.other {
-        write_other_page(report_dir, scan_root, issues, &counts, pages, diffs, skipped_files)?;
+
+    /// Ids of the custom fields missing from `tags`. A key from the rules file
+    /// also matches without its `_` / space separators, the way lofty names
+    /// the keys it maps (`REPLAYGAIN_TRACK_GAIN` -> `REPLAYGAINTRACKGAIN`).
+    fn missing_custom(&self, tags: &HashMap<String, String>) -> Vec<String> {
+        self.categories.iter()
+            .flat_map(|c| &c.fields)
+            .filter(|field| {
+                let keys: Vec<String> = field.keys.iter()
+                    .flat_map(|k| [k.clone(), k.replace(['_', ' '], "")])
+                    .collect();
+                let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+                !has_tag(tags, &keys)
+            })
+            .map(|field| field.id.clone())
+            .collect()
     }
 
-    Ok(())
+    /// Canonical text of the rules, for `rules_signature`.
+    fn signature_lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("year:{}-{}", self.year.min, self.year.max)];
+        for (name, rule) in &self.fields {
+            let keys = rule.keys.as_ref().map_or("default".to_string(), |k| k.join("|"));
+            lines.push(format!("field.{}:{}:{}", name, if rule.required { "required" } else { "optional" }, keys));
+        }
+        for category in &self.categories {
+            let fields: Vec<String> = category.fields.iter().map(|f| format!("{}={}", f.id, f.keys.join("|"))).collect();
+            lines.push(format!("{}:{}", category.id, fields.join(",")));
+        }
+        lines
+    }
 }
 
 // ---------------------------------------------------------------------------
-// Autofix: beets integration
+// Scan a single file
 // ---------------------------------------------------------------------------
 
-/// Print detailed installation instructions for beets and its required plugins.
-fn print_beet_install_instructions() {
-    eprintln!();
-    eprintln!("ERROR: beet (beets) is not installed or not found in PATH.");
-    eprintln!();
-    eprintln!("Install beets and required dependencies:");
-    eprintln!();
-    eprintln!("  # Core");
-    eprintln!("  pip install beets");
-    eprintln!();
-    eprintln!("  # Required plugins");
-    eprintln!("  pip install pyacoustid              # AcoustID fingerprinting (chroma plugin)");
-    eprintln!("  pip install python-discogs-client    # Discogs metadata");
-    eprintln!();
-    eprintln!("  # Chromaprint fingerprinter (system package)");
-    eprintln!("  sudo apt install libchromaprint-tools   # provides fpcalc");
-    eprintln!();
-    eprintln!("  # Recommended plugins");
-    eprintln!("  pip install beets-bandcamp           # Bandcamp metadata");
-    eprintln!("  pip install pylast                   # Last.fm genre tagging");
-    eprintln!();
-    eprintln!("Configure beets (~/.config/beets/config.yaml):");
-    eprintln!();
-    eprintln!("  plugins:");
-    eprintln!("    - chroma");
-    eprintln!("    - discogs");
-    eprintln!("    - fetchart");
-    eprintln!("    - embedart");
-    eprintln!("    - lastgenre");
-    eprintln!("    - bandcamp");
-    eprintln!();
-    eprintln!("  import:");
-    eprintln!("    write: yes");
-    eprintln!("    move: no");
-    eprintln!("    copy: no");
-    eprintln!("    quiet: yes");
-    eprintln!();
-    eprintln!("  acoustid:");
-    eprintln!("    apikey: YOUR_KEY   # Get from https://acoustid.org/api-key");
-    eprintln!();
-    eprintln!("  chroma:");
-    eprintln!("    auto: yes");
-    eprintln!();
-    eprintln!("  discogs:");
-    eprintln!("    user_token: YOUR_TOKEN   # Get from https://www.discogs.com/settings/developers");
+fn scan_file(path: &Path) -> Result<(FileIssue, TagValues), String> {
+    let meta = fs::metadata(path).map_err(|e| e.to_string())?;
+    let file_size = meta.len();
+
+    let quality = QUALITY_CHECKS.load(Ordering::Relaxed);
+    let parse_opts = ParseOptions::new().read_properties(quality);
+    let tagged_file = read_tagged_file(path, parse_opts)?;
+    let (mut issue, values) = check_tags(path, file_size, &tagged_file, rules());
+    if quality {
+        issue.audio = Some(AudioInfo::of(&tagged_file));
+    }
+    Ok((issue, values))
 }
 
-/// Check beets availability and required plugins. Returns Ok(version_string) or exits.
-fn check_beets_setup() {
-    // 1. Check beet binary
-    let beet_output = match std::process::Command::new("beet")
-        .arg("version")
-        .output()
-    {
-        Ok(o) if o.status.success() => o,
-        _ => {
-            print_beet_install_instructions();
-            std::process::exit(1);
-        }
+/// Every check on the tags of one file; `path` is only recorded.
+fn check_tags(
+    path: &Path,
+    file_size: u64,
+    tagged_file: &lofty::file::TaggedFile,
+    rules: &Rules,
+) -> (FileIssue, TagValues) {
+    let has_art = tagged_file
+        .tags()
+        .iter()
+        .any(|t| t.pictures().iter().next().is_some());
+
+    let tags = collect_tags(tagged_file);
+    let unusual_tags = unusual_containers(tagged_file);
+
+    // A field is missing when none of its keys has a value, unless the rules made it optional
+    let missing = |field: &str, defaults: &[&str]| {
+        rules.required(field) && !has_tag(&tags, &rules.keys(field, defaults))
+    };
+    let blank = |field: &str, defaults: &[&str]| {
+        let keys = rules.keys(field, defaults);
+        rules.required(field) && tag_key_exists(&tags, &keys) && !has_tag(&tags, &keys)
     };
 
-    let version_str = String::from_utf8_lossy(&beet_output.stdout);
-    for line in version_str.lines() {
-        println!("  {}", line.trim());
-    }
+    // --- Critical ---
+    let missing_artist = missing("artist", &["ARTIST"]);
+    let missing_title = missing("title", &["TITLE"]);
+    // A file with only an original release date still has a year
+    let missing_year = missing("year", &["YEAR"]) && original_year(&tags, rules).is_none();
 
-    // 2. Check fpcalc (chromaprint fingerprinter)
-    if std::process::Command::new("fpcalc")
-        .arg("-version")
-        .output()
-        .is_err()
-    {
-        eprintln!();
-        eprintln!("ERROR: fpcalc not found. Required by the chroma plugin for AcoustID fingerprinting.");
-        eprintln!();
-        eprintln!("  Install: sudo apt install libchromaprint-tools");
-        std::process::exit(1);
-    }
+    // --- MusicBrainz ---
+    let missing_mb_artist_id = missing("mb-artist", MB_ARTIST_ID_TAGS);
+    let missing_mb_track_id = missing("mb-track", MB_TRACK_ID_TAGS);
+    let missing_mb_album_id = missing("mb-album", MB_ALBUM_ID_TAGS);
 
-    // 3. Check required plugins from version output
-    let required = &["chroma", "discogs"];
-    let recommended = &["bandcamp", "fetchart", "embedart", "lastgenre"];
+    // --- IDs ---
+    let missing_acoustic_id = missing("acoustic", ACOUSTID_TAGS);
+    let missing_songkong_id = missing("songkong", &["SONGKONG_ID", "SONGKONGID"]);
+    let missing_bandcamp = missing("bandcamp", &["URL_BANDCAMP_ARTIST_SITE", "WWW BANDCAMP_ARTIST"]);
+    let missing_wikipedia_artist = missing("wikipedia", &["WWW WIKIPEDIA_ARTIST"]);
 
-    let mut missing_required: Vec<&str> = Vec::new();
-    let mut missing_recommended: Vec<&str> = Vec::new();
+    // --- Discogs ---
+    let missing_discogs_artist = missing("dg-artist", &["URL_DISCOGS_ARTIST_SITE", "WWW DISCOGS_ARTIST"]);
+    let missing_discogs_release = missing("dg-release", &["URL_DISCOGS_RELEASE_SITE", "WWW DISCOGS_RELEASE"]);
 
-    for &plugin in required {
-        if !version_str.contains(plugin) {
-            missing_required.push(plugin);
-        }
-    }
-    for &plugin in recommended {
-        if !version_str.contains(plugin) {
-            missing_recommended.push(plugin);
+    // --- Other ---
+    let missing_genre = missing("genre", &["GENRE"]);
+    // ID3v2 TBPM comes through lofty as IntegerBpm
+    let missing_bpm = missing("bpm", &["BPM", "INTEGERBPM"]);
+    // Any MOOD_* key, unless the rules name the keys
+    let missing_mood = if rules.keys("mood", &[]).is_empty() {
+        rules.required("mood") && !has_tag_prefix(&tags, "MOOD_")
+    } else {
+        missing("mood", &[])
+    };
+    let missing_album_art = rules.required("album-art") && !has_art;
+
+    // --- Inconsistency: blank fields ---
+    let blank_artist = blank("artist", &["ARTIST"]);
+    let blank_title = blank("title", &["TITLE"]);
+    let blank_year = blank("year", &["YEAR"]);
+    let blank_genre = blank("genre", &["GENRE"]);
+
+    // --- Inconsistency: invalid year ---
+    let year_value = get_tag(&tags, &rules.keys("year", &["YEAR"]));
+    let invalid_year = year_value.as_ref().and_then(|y| {
+        let trimmed = y.trim();
+        match trimmed.parse::<i32>() {
+            Ok(n) if rules.year_is_valid(n) => None,
+            _ => Some(trimmed.to_string()),
         }
+    });
+
+    // --- Custom categories (--rules) ---
+    let custom_missing = rules.missing_custom(&tags);
+
+    // --- Style (--style) ---
+    let lints = STYLE_LINTS.get().copied().unwrap_or_default();
+    let artist_value = get_tag(&tags, &["ARTIST"]);
+    let title_value = get_tag(&tags, &["TITLE"]);
+    let style = check_style(artist_value.as_deref(), title_value.as_deref(), &lints);
+
+    let values = tag_values(&tags);
+    (FileIssue {
+        path: path.to_path_buf(),
+        file_size,
+        missing_artist,
+        missing_title,
+        missing_year,
+        missing_mb_artist_id,
+        missing_mb_track_id,
+        missing_mb_album_id,
+        missing_acoustic_id,
+        missing_songkong_id,
+        missing_bandcamp,
+        missing_discogs_artist,
+        missing_discogs_release,
+        missing_wikipedia_artist,
+        missing_genre,
+        missing_bpm,
+        missing_mood,
+        missing_album_art,
+        unusual_tags,
+        invalid_year,
+        blank_artist,
+        blank_title,
+        blank_year,
+        blank_genre,
+        style_case: style.case,
+        style_explicit: style.explicit,
+        style_spaces: style.spaces,
+        style_feat: style.feat,
+        custom_missing,
+        audio: None,
+        encoder: encoder_tags(&tags),
+    }, values)
+}
+
+// ---------------------------------------------------------------------------
+// Scan cache (--cache)
+// ---------------------------------------------------------------------------
+
+/// Bump when `check_tags` changes what it records, so older caches are
+/// dropped instead of replaying stale results.
+const SCAN_CACHE_VERSION: u32 = 4;
+
+/// Size and modification time of a file on disk; a cached result is reused
+/// only while both are unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct FileStamp {
+    size: u64,
+    /// (seconds, nanoseconds) since the Unix epoch
+    mtime: (u64, u32),
+}
+
+impl FileStamp {
+    fn of(path: &Path) -> Option<Self> {
+        let meta = fs::metadata(path).ok()?;
+        let mtime = meta.modified().ok()?.duration_since(std::time::UNIX_EPOCH).ok()?;
+        Some(FileStamp { size: meta.len(), mtime: (mtime.as_secs(), mtime.subsec_nanos()) })
     }
+}
 
-    if !missing_required.is_empty() {
-        eprintln!();
-        eprintln!("ERROR: Missing required beets plugins: {}", missing_required.join(", "));
-        eprintln!();
-        for &p in &missing_required {
-            match p {
-                "chroma" => eprintln!("  pip install pyacoustid              # chroma plugin (AcoustID)"),
-                "discogs" => eprintln!("  pip install python-discogs-client    # discogs plugin"),
-                _ => eprintln!("  # Enable '{}' in your beets config plugins list", p),
-            }
-        }
-        eprintln!();
-        eprintln!("Then add them to plugins: in ~/.config/beets/config.yaml");
-        std::process::exit(1);
+/// One readable file's scan result. Unreadable files aren't cached.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedScan {
+    stamp: FileStamp,
+    issue: FileIssue,
+    /// `TagValues`, with the field label owned
+    values: Vec<(String, String)>,
+}
+
+impl CachedScan {
+    fn new(stamp: FileStamp, issue: FileIssue, values: &TagValues) -> Self {
+        let values = values.iter().map(|(label, value)| (label.to_string(), value.clone())).collect();
+        CachedScan { stamp, issue, values }
     }
 
-    if !missing_recommended.is_empty() {
-        println!("  Note: recommended plugins not loaded: {}", missing_recommended.join(", "));
+    /// The stored values with their `CONSISTENCY_FIELDS` labels back.
+    fn tag_values(&self) -> TagValues {
+        self.values.iter()
+            .filter_map(|(label, value)| {
+                CONSISTENCY_FIELDS.iter()
+                    .find(|(l, _)| l == label)
+                    .map(|(l, _)| (*l, value.clone()))
+            })
+            .collect()
     }
 }
 
-/// Run the autofix phase: invoke beet import on each directory containing files with issues.
-/// Returns a map of directory → skip reason for directories beets skipped (real run only).
-/// For dry runs the returned map is always empty.
-fn run_autofix(
-    issues: &[FileIssue],
-    scan_root: &str,
-    parent_audio_count: &HashMap<PathBuf, usize>,
-    dry: bool,
-) -> HashMap<PathBuf, String> {
-    let label = if dry { "Autofix DRY RUN" } else { "Autofix" };
+#[derive(Debug, Serialize, Deserialize)]
+struct ScanCacheFile {
+    version: u32,
+    /// `rules_signature` of the run that wrote it (`--style`, `--rules`)
+    rules: String,
+    files: Vec<CachedScan>,
+}
 
-    println!("\n[{}] Checking beets installation...", label);
-    check_beets_setup();
+/// Scan results of earlier runs, keyed by path as scanned.
+#[derive(Debug, Default)]
+struct ScanCache {
+    files: HashMap<PathBuf, CachedScan>,
+}
 
-    // Group issue files by parent directory
-    let mut dirs_to_fix: BTreeMap<PathBuf, usize> = BTreeMap::new();
-    for issue in issues {
-        if let Some(parent) = issue.path.parent() {
-            *dirs_to_fix.entry(parent.to_path_buf()).or_insert(0) += 1;
+impl ScanCache {
+    /// The cache in `path`: empty when the file doesn't exist yet, and
+    /// discarded when it was written by another version or with other rules
+    /// (`rules_signature`).
+    fn load(path: &Path, rules: &str) -> Result<Self, String> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.to_string()),
+        };
+        let cache: ScanCacheFile = serde_json::from_str(&text).map_err(|e| e.to_string())?;
+        if cache.version != SCAN_CACHE_VERSION || cache.rules != rules {
+            return Ok(Self::default());
         }
+        Ok(ScanCache {
+            files: cache.files.into_iter().map(|c| (c.issue.path.clone(), c)).collect(),
+        })
     }
 
-    let total_dirs = dirs_to_fix.len();
-    if total_dirs == 0 {
-        println!("\n[{}] No directories to process.", label);
-        return HashMap::new();
+    /// The cached result for `path` if the file hasn't changed since.
+    fn lookup(&self, path: &Path, stamp: FileStamp) -> Option<(FileIssue, TagValues)> {
+        let cached = self.files.get(path).filter(|c| c.stamp == stamp)?;
+        Some((cached.issue.clone(), cached.tag_values()))
     }
 
-    println!("\n[{}] Processing {} director{} ({} files with issues)...\n",
-        label,
-        total_dirs,
-        if total_dirs == 1 { "y" } else { "ies" },
-        issues.len(),
-    );
+    /// Merge this run's results and write the cache to `path` (through a temp
+    /// file, so an interrupted write leaves the previous cache intact).
+    /// `pruned` drops the old entries under it that this run didn't scan:
+    /// the scan root after a complete walk, when they were deleted or moved.
+    fn save(
+        mut self,
+        path: &Path,
+        rules: &str,
+        scanned: Vec<CachedScan>,
+        pruned: Option<&Path>,
+    ) -> std::io::Result<usize> {
+        if let Some(root) = pruned {
+            self.files.retain(|p, _| !p.starts_with(root));
+        }
+        for entry in scanned {
+            self.files.insert(entry.issue.path.clone(), entry);
+        }
+        let mut files: Vec<CachedScan> = self.files.into_values().collect();
+        files.sort_by(|a, b| a.issue.path.cmp(&b.issue.path));
+        let count = files.len();
+        let cache = ScanCacheFile {
+            version: SCAN_CACHE_VERSION,
+            rules: rules.to_string(),
+            files,
+        };
 
-    // Use a temporary library to avoid polluting user's main beet DB
-    let tmp_lib = format!("/tmp/analysis_autofix_{}.db", std::process::id());
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_string(&cache)?)?;
+        fs::rename(&tmp, path)?;
+        Ok(count)
+    }
+}
 
-    let mut processed = 0u32;
-    let mut skipped = 0u32;
-    let mut failed = 0u32;
+// ---------------------------------------------------------------------------
+// Path formatting helpers
+// ---------------------------------------------------------------------------
+
+/// Group name for files sitting directly in the scan root, outside any
+/// artist folder. Not a valid folder name, so it can't clash with a real one.
+const ROOT_GROUP: &str = "(scan root)";
+
+/// Extract the first folder after the scan root (e.g., "Radiohead" from "/mnt/c/__DMP/Radiohead/...")
+fn get_artist_folder(path: &Path, scan_root: &str) -> String {
+    let path_str = path.to_string_lossy();
+    let relative = path_str
+        .strip_prefix(scan_root)
+        .unwrap_or(&path_str)
+        .trim_start_matches('/');
+
+    match relative.split_once('/') {
+        Some((folder, _)) => folder.to_string(),
+        None => ROOT_GROUP.to_string(),
+    }
+}
+
+/// Folder holding the file, relative to the scan root (e.g., "Radiohead/OK Computer")
+fn get_album_folder(path: &Path, scan_root: &str) -> String {
+    match relative_path(path, scan_root).rsplit_once('/') {
+        Some((folder, _)) => folder.to_string(),
+        None => ROOT_GROUP.to_string(),
+    }
+}
+
+fn is_root_file(path: &Path, scan_root: &str) -> bool {
+    !relative_path(path, scan_root).contains('/')
+}
+
+/// Where a root file probably belongs, from its tags: `Artist/Album/`, or
+/// `Artist/` without an album. Album artist wins over track artist.
+fn suggested_folder(path: &Path) -> Option<String> {
+    let tags = read_tags(path)?;
+    let clean = |s: String| -> String {
+        s.trim()
+            .chars()
+            .map(|c| if matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') { '_' } else { c })
+            .collect()
+    };
+    let artist = clean(get_tag(&tags, &["ALBUMARTIST"]).or_else(|| get_tag(&tags, &["ARTIST"]))?);
+    match get_tag(&tags, &["ALBUMTITLE", "ALBUM"]) {
+        Some(album) => Some(format!("{}/{}/", artist, clean(album))),
+        None => Some(format!("{}/", artist)),
+    }
+}
+
+fn is_audio_file(path: &Path) -> bool {
+    const EXTENSIONS: [&str; 7] = ["mp3", "m4a", "opus", "aac", "ogg", "flac", "wav"];
+    path.extension()
+        .map(|ext| EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Get the path relative to the scan root (e.g., "Radiohead/OK Computer/01 Airbag.flac")
+fn relative_path(path: &Path, scan_root: &str) -> String {
+    let path_str = path.to_string_lossy();
+    path_str
+        .strip_prefix(scan_root)
+        .unwrap_or(&path_str)
+        .trim_start_matches('/')
+        .to_string()
+}
+
+// ---------------------------------------------------------------------------
+// Links (symlink and hardlink farms)
+// ---------------------------------------------------------------------------
+
+/// A path to a file the walk had already found, left out of the scan.
+#[derive(Debug)]
+struct LinkDuplicate {
+    path: PathBuf,
+    /// The scanned path of the same file
+    original: PathBuf,
+    kind: &'static str,
+}
+
+/// (device, inode) of the file a path resolves to; `None` where the platform
+/// has no inodes, so nothing is de-duplicated there.
+#[cfg(unix)]
+fn file_id(meta: &fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_meta: &fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// "symlink" when both paths resolve to the same real path, else "hardlink".
+fn link_kind(path: &Path, original: &Path) -> &'static str {
+    match (fs::canonicalize(path), fs::canonicalize(original)) {
+        (Ok(a), Ok(b)) if a == b => "symlink",
+        _ => "hardlink",
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Lone files
+// ---------------------------------------------------------------------------
+
+/// A lone file at least this long is taken for a whole album ripped as one track.
+const UNSPLIT_ALBUM: Duration = Duration::from_secs(30 * 60);
+
+/// The only audio file in its folder (root files excluded), with its length
+/// when the audio properties could be read.
+#[derive(Debug, Clone)]
+struct LoneFile {
+    path: PathBuf,
+    duration: Option<Duration>,
+}
+
+impl LoneFile {
+    /// Problem column on issues.html.
+    fn note(&self) -> String {
+        match self.duration {
+            Some(d) if d >= UNSPLIT_ALBUM => format!("Only one file: likely un-split album ({})", format_length(d)),
+            Some(d) => format!("Only one file: stray single ({})", format_length(d)),
+            None => "Only one file".to_string(),
+        }
+    }
+}
+
+/// "48:22", or "1:02:05" from an hour up.
+fn format_length(d: Duration) -> String {
+    let secs = d.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+/// Lone files sorted by path. Only these get their audio properties read;
+/// the metadata scan skips them.
+fn find_lone_files(
+    paths: &[PathBuf],
+    parent_audio_count: &HashMap<PathBuf, usize>,
+    scan_root: &str,
+) -> Vec<LoneFile> {
+    let mut lone: Vec<&PathBuf> = paths.iter()
+        .filter(|p| !is_root_file(p, scan_root))
+        .filter(|p| {
+            p.parent()
+                .and_then(|par| parent_audio_count.get(par))
+                .copied()
+                .unwrap_or(0) == 1
+        })
+        .filter(|p| !is_cue_rip(p))
+        .collect();
+    lone.sort();
+    lone.par_iter()
+        .map(|p| {
+            let duration = read_tagged_file(p, ParseOptions::new().read_tags(false))
+                .ok()
+                .map(|f| f.properties().duration())
+                .filter(|d| !d.is_zero());
+            LoneFile { path: (*p).clone(), duration }
+        })
+        .collect()
+}
+
+/// A FLAC or APE file with a .cue sheet beside it: a single-file album rip,
+/// which the indexer splits into its cue tracks rather than one lone file.
+fn is_cue_rip(path: &Path) -> bool {
+    let rip_format = path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("flac") || ext.eq_ignore_ascii_case("ape"));
+    rip_format && path.parent()
+        .and_then(|dir| fs::read_dir(dir).ok())
+        .is_some_and(|mut entries| entries.any(|e| {
+            e.is_ok_and(|e| e.path().extension().is_some_and(|ext| ext.eq_ignore_ascii_case("cue")))
+        }))
+}
+
+// ---------------------------------------------------------------------------
+// Explicit file list (--paths-from)
+// ---------------------------------------------------------------------------
+
+/// The files of a `--paths-from` list to scan, sorted and without repeats,
+/// and the entries left out, with why.
+#[derive(Debug, Default)]
+struct PathList {
+    paths: Vec<PathBuf>,
+    skipped: Vec<(String, &'static str)>,
+}
+
+/// Parse a `--paths-from` list: JSON (`{"handled": [...]}` from the report, or
+/// a plain array of paths) or text with one path per line, where blank lines
+/// and `#` comments are ignored. Relative paths are taken from the scan root.
+fn read_path_list(text: &str, scan_root: &str, filter: &FolderFilter) -> Result<PathList, String> {
+    let entries: Vec<String> = if text.trim_start().starts_with(['{', '[']) {
+        let json: serde_json::Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+        json.get("handled")
+            .unwrap_or(&json)
+            .as_array()
+            .ok_or_else(|| "expected a JSON array of paths or a \"handled\" array".to_string())?
+            .iter()
+            .filter_map(|v| v.as_str())
+            .map(str::to_string)
+            .collect()
+    } else {
+        text.lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(str::to_string)
+            .collect()
+    };
+
+    let root = Path::new(scan_root);
+    let mut list = PathList::default();
+    for entry in entries {
+        let path = if Path::new(&entry).is_absolute() { PathBuf::from(&entry) } else { root.join(&entry) };
+        let reason = if !path.starts_with(root) || path.components().any(|c| c == std::path::Component::ParentDir) {
+            Some("outside the scan root")
+        } else if !is_audio_file(&path) {
+            Some("not an audio file")
+        } else if !path.is_file() {
+            Some("not found")
+        } else if !filter.matches(&get_artist_folder(&path, scan_root)) {
+            Some("outside --only / --from / --to")
+        } else if excluded().is_some_and(|x| x.matches(&path, scan_root)) {
+            Some("excluded")
+        } else {
+            None
+        };
+        match reason {
+            Some(reason) => list.skipped.push((entry, reason)),
+            None => list.paths.push(path),
+        }
+    }
+    list.paths.sort();
+    list.paths.dedup();
+    Ok(list)
+}
+
+/// Audio files per folder on disk, for the folders of a `--paths-from` list,
+/// so a listed file isn't taken for a lone file because its neighbours
+/// weren't listed.
+fn count_folder_audio(paths: &[PathBuf]) -> HashMap<PathBuf, usize> {
+    let folders: BTreeSet<&Path> = paths.iter().filter_map(|p| p.parent()).collect();
+    folders
+        .into_iter()
+        .map(|folder| {
+            let count = fs::read_dir(folder)
+                .map(|entries| {
+                    entries
+                        .filter_map(|e| e.ok())
+                        .filter(|e| e.path().is_file() && is_audio_file(&e.path()))
+                        .count()
+                })
+                .unwrap_or(0);
+            (folder.to_path_buf(), count)
+        })
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Human-readable file size
+// ---------------------------------------------------------------------------
+
+/// `--page-size` unless given: big enough for 20 typical artists, small
+/// enough for a browser to open a page at once.
+const DEFAULT_PAGE_SIZE: &str = "2MB";
+
+/// `--page-size`: bytes, or a number with a KB / MB / GB suffix (1024-based,
+/// like [`human_size`]), e.g. `2MB`, `1.5 MB`, `500k`.
+fn parse_page_size(value: &str) -> Result<usize, String> {
+    let value = value.trim();
+    let split = value.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.parse().map_err(|_| format!("'{}' is not a size like 2MB or 500KB", value))?;
+    let multiplier = match unit.trim().to_ascii_lowercase().trim_end_matches('b') {
+        "" => 1.0,
+        "k" => 1024.0,
+        "m" => 1024.0 * 1024.0,
+        "g" => 1024.0 * 1024.0 * 1024.0,
+        _ => return Err(format!("unknown unit in '{}' (use KB, MB or GB)", value)),
+    };
+    Ok((number * multiplier) as usize)
+}
+
+fn human_size(bytes: u64) -> String {
+    const KB: u64 = 1024;
+    const MB: u64 = 1024 * KB;
+    const GB: u64 = 1024 * MB;
+    const TB: u64 = 1024 * GB;
+
+    if bytes >= TB {
+        format!("{:.2} TB", bytes as f64 / TB as f64)
+    } else if bytes >= GB {
+        format!("{:.2} GB", bytes as f64 / GB as f64)
+    } else if bytes >= MB {
+        format!("{:.2} MB", bytes as f64 / MB as f64)
+    } else if bytes >= KB {
+        format!("{:.2} KB", bytes as f64 / KB as f64)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Report: category pages
+// ---------------------------------------------------------------------------
+
+/// `<h1>` of every report page.
+const REPORT_TITLE: &str = "Audio Metadata Analysis";
+
+/// Limits of the paginated pages; set once in main from `--page-size` / `--page-files`.
+static PAGE_BUDGET: OnceLock<PageBudget> = OnceLock::new();
+
+/// `--single-file`; set once in main.
+static SINGLE_FILE: AtomicBool = AtomicBool::new(false);
+
+/// The `--single-file` bundle of a report: `analysis_<timestamp>.html` next to its folder.
+fn single_file_path(report_dir: &Path) -> PathBuf {
+    report_dir.with_extension("html")
+}
+
+/// A sub-tab of a category page: the files for which `predicate` holds,
+/// grouped by artist folder. `field` is the autofix field name it covers
+/// (see `FieldMatch::field`).
+struct FieldPanel {
+    id: &'static str,
+    label: &'static str,
+    field: &'static str,
+    predicate: fn(&FileIssue) -> bool,
+    annotate: fn(&FileIssue) -> Option<String>,
+}
+
+/// A paginated category page (`pages/{id}_N.html`); `id` is also the nav tab
+/// and the `FieldMatch::category` of its fixes.
+struct CategoryPage {
+    id: &'static str,
+    title: &'static str,
+    has_issue: fn(&FileIssue) -> bool,
+    panels: &'static [FieldPanel],
+    /// Health penalty of a file with an issue in this category (see `file_penalty`)
+    weight: u32,
+}
+
+fn no_annotation(_: &FileIssue) -> Option<String> {
+    None
+}
+
+fn blank_annotation(blank: bool) -> Option<String> {
+    if blank { Some("(blank)".into()) } else { None }
+}
+
+const CATEGORY_PAGES: &[CategoryPage] = &[
+    CategoryPage {
+        id: "critical",
+        title: "Critical",
+        has_issue: FileIssue::has_critical,
+        weight: 10,
+        panels: &[
+            FieldPanel { id: "artist", label: "Artist", field: "Artist",
+                predicate: |i| i.missing_artist || i.blank_artist,
+                annotate: |i| blank_annotation(i.blank_artist) },
+            FieldPanel { id: "title", label: "Title", field: "Title",
+                predicate: |i| i.missing_title || i.blank_title,
+                annotate: |i| blank_annotation(i.blank_title) },
+            FieldPanel { id: "year", label: "Year", field: "Year",
+                predicate: |i| i.missing_year || i.blank_year || i.invalid_year.is_some(),
+                annotate: |i| match &i.invalid_year {
+                    _ if i.blank_year => Some("(blank)".into()),
+                    Some(v) => Some(format!("({})", v)),
+                    None => None,
+                } },
+        ],
+    },
+    CategoryPage {
+        id: "mb",
+        title: "MusicBrainz",
+        has_issue: FileIssue::has_mb,
+        weight: 4,
+        panels: &[
+            FieldPanel { id: "mb-artist", label: "MB Artist", field: "MB Artist ID", predicate: |i| i.missing_mb_artist_id, annotate: no_annotation },
+            FieldPanel { id: "mb-track",  label: "MB Track",  field: "MB Track ID",  predicate: |i| i.missing_mb_track_id,  annotate: no_annotation },
+            FieldPanel { id: "mb-album",  label: "MB Album",  field: "MB Album ID",  predicate: |i| i.missing_mb_album_id,  annotate: no_annotation },
+        ],
+    },
+    CategoryPage {
+        id: "discogs",
+        title: "Discogs",
+        has_issue: FileIssue::has_discogs,
+        weight: 1,
+        panels: &[
+            FieldPanel { id: "dg-artist",  label: "Discogs Artist",  field: "Discogs Artist",  predicate: |i| i.missing_discogs_artist,  annotate: no_annotation },
+            FieldPanel { id: "dg-release", label: "Discogs Release", field: "Discogs Release", predicate: |i| i.missing_discogs_release, annotate: no_annotation },
+        ],
+    },
+    CategoryPage {
+        id: "ids",
+        title: "IDs",
+        has_issue: FileIssue::has_ids,
+        weight: 1,
+        panels: &[
+            FieldPanel { id: "acoustic",  label: "Acoustic ID", field: "Acoustic ID",      predicate: |i| i.missing_acoustic_id,      annotate: no_annotation },
+            FieldPanel { id: "songkong",  label: "SongKong",    field: "SongKong ID",      predicate: |i| i.missing_songkong_id,      annotate: no_annotation },
+            FieldPanel { id: "bandcamp",  label: "Bandcamp",    field: "Bandcamp",         predicate: |i| i.missing_bandcamp,         annotate: no_annotation },
+            FieldPanel { id: "wikipedia", label: "Wikipedia",   field: "Wikipedia Artist", predicate: |i| i.missing_wikipedia_artist, annotate: no_annotation },
+        ],
+    },
+    CategoryPage {
+        id: "other",
+        title: "Other",
+        has_issue: FileIssue::has_other,
+        weight: 2,
+        panels: &[
+            FieldPanel { id: "genre", label: "Genre", field: "Genre",
+                predicate: |i| i.missing_genre || i.blank_genre,
+                annotate: |i| blank_annotation(i.blank_genre) },
+            FieldPanel { id: "bpm",       label: "BPM",       field: "BPM",       predicate: |i| i.missing_bpm,       annotate: no_annotation },
+            FieldPanel { id: "mood",      label: "Mood",      field: "Mood",      predicate: |i| i.missing_mood,      annotate: no_annotation },
+            FieldPanel { id: "album-art", label: "Album Art", field: "Album Art", predicate: |i| i.missing_album_art, annotate: no_annotation },
+            FieldPanel { id: "containers", label: "Tag Container", field: "Tag Container",
+                predicate: |i| i.unusual_tags.is_some(),
+                annotate: |i| i.unusual_tags.as_ref().map(|v| format!("({})", v)) },
+        ],
+    },
+    CategoryPage {
+        id: "style",
+        title: "Style",
+        has_issue: FileIssue::has_style,
+        weight: 1,
+        panels: &[
+            FieldPanel { id: "case", label: "Casing", field: "Casing",
+                predicate: |i| i.style_case.is_some(),
+                annotate: |i| i.style_case.as_ref().map(|v| format!("({})", v)) },
+            FieldPanel { id: "explicit", label: "Explicit", field: "Explicit", predicate: |i| i.style_explicit, annotate: no_annotation },
+            FieldPanel { id: "spaces", label: "Spacing", field: "Spacing",
+                predicate: |i| i.style_spaces.is_some(),
+                annotate: |i| i.style_spaces.as_ref().map(|v| format!("({})", v)) },
+            FieldPanel { id: "featuring", label: "Featuring", field: "Featuring",
+                predicate: |i| i.style_feat.is_some(),
+                annotate: |i| i.style_feat.as_ref().map(|v| format!("(feat. {})", v)) },
+        ],
+    },
+];
+
+impl PageFlags {
+    fn shows(&self, category: &str) -> bool {
+        match category {
+            "critical" => self.critical,
+            "mb" => self.mb,
+            "discogs" => self.discogs,
+            "ids" => self.ids,
+            "other" => self.other,
+            "style" => self.style,
+            _ => false,
+        }
+    }
+}
+
+/// Artist-grouped report entries for one panel. A file counts as resolved
+/// when autofix wrote the panel's field and beets didn't skip it; its
+/// popover lists every fix autofix made in the same category.
+fn build_groups(
+    issues: &[FileIssue],
+    scan_root: &str,
+    category: &str,
+    panel: &FieldPanel,
+    diffs: Option<&MatchDiffs>,
+    skipped_files: Option<&SkippedFiles>,
+) -> report::Groups {
+    let mut groups = report::Groups::new();
+    for issue in issues {
+        if !(panel.predicate)(issue) { continue; }
+        let skipped = skipped_files.is_some_and(|sf| sf.contains_key(&issue.path));
+        let fixes = diffs
+            .and_then(|d| d.get(&issue.path))
+            .filter(|fixes| !skipped && fixes.iter().any(|fix| fix.field == panel.field));
+        let mut entry = report::Entry::new(relative_path(&issue.path, scan_root))
+            .annotated((panel.annotate)(issue));
+        if let Some(fixes) = fixes {
+            entry = entry.resolved(fixes.iter()
+                .filter(|fix| fix.category == category)
+                .map(|fix| report::Change {
+                    field: fix.field.to_string(),
+                    old: fix.old_display.clone(),
+                    new: fix.new_value.clone(),
+                })
+                .collect());
+        }
+        groups.entry(get_artist_folder(&issue.path, scan_root)).or_default().push(entry);
+    }
+    for entries in groups.values_mut() {
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+    }
+    groups
+}
+
+fn write_category_pages(
+    report: &Report,
+    scan_root: &str,
+    issues: &[FileIssue],
+    category: &CategoryPage,
+    diffs: Option<&MatchDiffs>,
+    skipped_files: Option<&SkippedFiles>,
+) -> std::io::Result<()> {
+    let panels: Vec<report::Panel> = category.panels.iter()
+        .map(|p| report::Panel::new(p.id, p.label, build_groups(issues, scan_root, category.id, p, diffs, skipped_files)))
+        .collect();
+    report.write_grouped_pages(category.id, category.title, &panels)?;
+    Ok(())
+}
+
+/// Pages of a `--rules` category: one panel per field, nothing resolved
+/// (autofix doesn't know these fields).
+fn write_custom_category_pages(
+    report: &Report,
+    scan_root: &str,
+    issues: &[FileIssue],
+    category: &CustomCategory,
+) -> std::io::Result<()> {
+    let panels: Vec<report::Panel> = category.fields.iter()
+        .map(|field| {
+            let mut groups = report::Groups::new();
+            for issue in issues.iter().filter(|i| i.custom_missing.contains(&field.id)) {
+                groups.entry(get_artist_folder(&issue.path, scan_root)).or_default()
+                    .push(report::Entry::new(relative_path(&issue.path, scan_root)));
+            }
+            for entries in groups.values_mut() {
+                entries.sort_by(|a, b| a.path.cmp(&b.path));
+            }
+            report::Panel::new(field.id.as_str(), field.label.as_str(), groups)
+        })
+        .collect();
+    report.write_grouped_pages(&category.id, &category.title, &panels)?;
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Report: albums.html
+// ---------------------------------------------------------------------------
+
+/// Every file with an issue, grouped by the folder it sits in (the release
+/// folder, re-tagged as a whole), annotated with its issues. Each group's
+/// header counts the files per issue in category page order, e.g.
+/// "Year: 3, MB Album: 12". A file counts as resolved when autofix or
+/// `--style-fix` changed it and beets didn't skip it.
+fn build_album_panel(
+    issues: &[FileIssue],
+    scan_root: &str,
+    diffs: Option<&MatchDiffs>,
+    skipped_files: Option<&SkippedFiles>,
+) -> report::Panel {
+    let mut groups = report::Groups::new();
+    let mut counts: HashMap<String, Vec<(&'static str, usize)>> = HashMap::new();
+    for issue in issues {
+        let labels = issue_labels(issue);
+        if labels.is_empty() { continue; }
+        let album = get_album_folder(&issue.path, scan_root);
+        let album_counts = counts.entry(album.clone()).or_default();
+        for label in &labels {
+            match album_counts.iter_mut().find(|(l, _)| l == label) {
+                Some((_, n)) => *n += 1,
+                None => album_counts.push((label, 1)),
+            }
+        }
+
+        let skipped = skipped_files.is_some_and(|sf| sf.contains_key(&issue.path));
+        let mut entry = report::Entry::new(relative_path(&issue.path, scan_root))
+            .annotated(Some(format!("({})", labels.join(", "))));
+        if let Some(fixes) = diffs.and_then(|d| d.get(&issue.path)).filter(|_| !skipped) {
+            entry = entry.resolved(fixes.iter()
+                .map(|fix| report::Change {
+                    field: fix.field.to_string(),
+                    old: fix.old_display.clone(),
+                    new: fix.new_value.clone(),
+                })
+                .collect());
+        }
+        groups.entry(album).or_default().push(entry);
+    }
+    for entries in groups.values_mut() {
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+    }
+
+    // Labels were collected in file order; show them in page order
+    let order: Vec<&str> = CATEGORY_PAGES.iter().flat_map(|c| c.panels).map(|p| p.label).collect();
+    let summaries = counts.into_iter()
+        .map(|(album, mut album_counts)| {
+            album_counts.sort_by_key(|(label, _)| order.iter().position(|l| l == label).unwrap_or(order.len()));
+            let summary: Vec<String> = album_counts.iter().map(|(label, n)| format!("{}: {}", label, n)).collect();
+            (album, summary.join(", "))
+        })
+        .collect();
+    report::Panel::new("albums", "Albums", groups).with_summaries(summaries)
+}
+
+/// Informational sub-tab next to the albums: every file of the albums in
+/// `albums` with its encoder tags, and which rip logs sit in the folder, to
+/// tell careless rips (no log, mixed encoders) worth re-ripping from albums
+/// that only need their tags fixed.
+fn build_rip_panel(rips: &RipReport, albums: &BTreeSet<&str>, scan_root: &str) -> report::Panel {
+    let mut groups = report::Groups::new();
+    let mut summaries = BTreeMap::new();
+    for album in albums {
+        let Some(files) = rips.albums.get(*album) else { continue };
+        let logs = files.first()
+            .and_then(|(path, _)| path.parent())
+            .map(rip_logs)
+            .unwrap_or_default();
+        let encoders: Vec<Option<&str>> = files.iter().map(|(_, e)| e.as_deref()).collect();
+        summaries.insert(album.to_string(), rip_summary(&logs, &encoders));
+
+        let mut entries: Vec<report::Entry> = files.iter()
+            .map(|(path, encoder)| {
+                report::Entry::new(relative_path(path, scan_root))
+                    .annotated(Some(format!("({})", encoder.as_deref().unwrap_or("no encoder tag"))))
+            })
+            .collect();
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        groups.insert(album.to_string(), entries);
+    }
+    report::Panel::new("rip", "Rip software", groups).with_summaries(summaries)
+}
+
+fn write_album_pages(report: &Report, panels: &[report::Panel]) -> std::io::Result<()> {
+    report.write_grouped_pages("albums", "Albums", panels)?;
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Report: index.html
+// ---------------------------------------------------------------------------
+
+fn write_index(
+    report: &Report,
+    data: &ReportData,
+    health: Health,
+    counts: &[(&'static str, &'static str, u64)],
+) -> std::io::Result<()> {
+    let ReportData {
+        scan_root, total_files, total_size, error_count, file_type_counts, elapsed, provenance, protected, trend, ..
+    } = *data;
+    let issues_len = data.issues.len();
+    let subtitle = format!(
+        "<span>Scanned <code>{}</code></span><span class=\"meta\">{} &middot; {:.2}s</span>",
+        html_text(scan_root),
+        human_size(total_size),
+        elapsed.as_secs_f64(),
+    );
+    let mut page = report.index_page("Overview", Some(&subtitle))?;
+
+    // Stats cards
+    let readable = total_files.saturating_sub(error_count);
+    let ok_count = readable.saturating_sub(issues_len as u64);
+
+    let mut sorted_types: Vec<_> = file_type_counts.iter().collect();
+    sorted_types.sort_by(|a, b| b.1.cmp(a.1));
+    let type_cards = sorted_types.iter()
+        .map(|(ext, count)| StatCard::new(ext.as_str(), count, "info"))
+        .collect();
+    let file_cards = vec![
+        StatCard::new("Files OK", ok_count, "ok"),
+        StatCard::new("Files with Issues", issues_len, "fail"),
+        StatCard::new("Unreadable Files", error_count, "warn"),
+        StatCard::new("Health Score", format!("{:.1} ({})", health.score(), health.grade()), health.class()),
+    ];
+    page.write_stats(&[type_cards, file_cards])?;
+
+    report.write_breakdown(&mut page, "Breakdown by Category", "Issues")?;
+    if trend.len() > 1 {
+        write_trends(&mut page, trend, counts)?;
+    }
+    if !protected.is_empty() {
+        let counts: Vec<String> = protected.iter().map(|(_, n)| n.to_string()).collect();
+        let rows: Vec<Vec<&str>> = protected.iter().zip(&counts)
+            .map(|((pattern, _), n)| vec![pattern.as_str(), n.as_str()])
+            .collect();
+        page.write_table("Protected folders (never moved by quarantine or autofix)", "mirror",
+            &["Pattern (--protect)", "Files with issues"], &rows)?;
+    }
+    page.write_details("Provenance", &provenance.rows())?;
+    page.finish()
+}
+
+// ---------------------------------------------------------------------------
+// Report: issues.html
+// ---------------------------------------------------------------------------
+
+fn write_issues_page(
+    report: &Report,
+    scan_root: &str,
+    all_paths: &[PathBuf],
+    lone_files: &[LoneFile],
+    unreadable: &[(PathBuf, String)],
+    link_dupes: &[LinkDuplicate],
+) -> std::io::Result<()> {
+    let mut page = report.page("issues.html", "Issues", "issues", None)?;
+    page.write_search_box(SearchTarget::Table)?;
+
+    // Files directly in the scan root, with a suggested home
+    let mut root_files: Vec<&PathBuf> = all_paths.iter()
+        .filter(|p| is_root_file(p, scan_root))
+        .collect();
+    root_files.sort();
+    let root_rows: Vec<(String, String)> = root_files.iter()
+        .map(|p| (relative_path(p, scan_root), suggested_folder(p).unwrap_or_else(|| "—".to_string())))
+        .collect();
+    let root_rows: Vec<Vec<&str>> = root_rows.iter()
+        .map(|(file, folder)| vec![file.as_str(), folder.as_str()])
+        .collect();
+    if !root_rows.is_empty() {
+        page.write_table("Unsorted root files", "mirror", &["File", "Suggested folder"], &root_rows)?;
+    }
+
+    // Unreadable files
+    let mut sorted_unreadable: Vec<&(PathBuf, String)> = unreadable.iter().collect();
+    sorted_unreadable.sort_by(|a, b| a.0.cmp(&b.0));
+
+    // Lone files (only one audio file in parent directory) first; root files are listed above
+    let rows: Vec<FileRow> = lone_files.iter()
+        .map(|lone| (&lone.path, lone.note()))
+        .chain(sorted_unreadable.iter().map(|(p, err)| (p, err.clone())))
+        .map(|(p, problem)| FileRow {
+            path: relative_path(p, scan_root),
+            title: p.to_string_lossy().into_owned(),
+            problem,
+        })
+        .collect();
+    page.write_file_table(&rows)?;
+
+    // Second paths to files already scanned (symlink / hardlink farms)
+    let link_rows: Vec<[String; 3]> = link_dupes.iter()
+        .map(|d| [relative_path(&d.path, scan_root), relative_path(&d.original, scan_root), d.kind.to_string()])
+        .collect();
+    let link_rows: Vec<Vec<&str>> = link_rows.iter().map(|r| r.iter().map(String::as_str).collect()).collect();
+    if !link_rows.is_empty() {
+        page.write_table("Linked duplicates (scanned once)", "mirror", &["Path", "Same file as", "Link"], &link_rows)?;
+    }
+    page.finish()
+}
+
+// ---------------------------------------------------------------------------
+// Report: consistency.html
+// ---------------------------------------------------------------------------
+
+fn write_consistency_page(report: &Report, variants: &[Variants]) -> std::io::Result<()> {
+    let subtitle = "<span>Values that only differ by case, accents, spacing or punctuation</span>";
+    let mut page = report.page("consistency.html", "Consistency", "consistency", Some(subtitle))?;
+
+    let mut cards = Vec::new();
+    for (label, _) in CONSISTENCY_FIELDS {
+        let count = variants.iter().filter(|v| v.field == *label).count();
+        cards.push(StatCard::new(*label, count, if count == 0 { "ok" } else { "warn" }));
+    }
+    page.write_stats(&[cards])?;
+    page.write_search_box(SearchTarget::Table)?;
+
+    for (label, _) in CONSISTENCY_FIELDS {
+        let rows: Vec<[String; 3]> = variants.iter()
+            .filter(|v| v.field == *label)
+            .map(|v| {
+                let (top, others) = v.values.split_first().expect("at least two spellings");
+                let others: Vec<String> = others.iter()
+                    .map(|(value, n)| format!("{} ({})", visible_spaces(value), n))
+                    .collect();
+                [format!("{} ({})", visible_spaces(&top.0), top.1), others.join(", "), v.files().to_string()]
+            })
+            .collect();
+        let rows: Vec<Vec<&str>> = rows.iter().map(|r| r.iter().map(String::as_str).collect()).collect();
+        page.write_table(label, "mirror", &["Most used", "Other spellings", "Files"], &rows)?;
+    }
+
+    page.finish()
+}
+
+// ---------------------------------------------------------------------------
+// Report: health.html
+// ---------------------------------------------------------------------------
+
+fn write_health_page(report: &Report, health: Health, artists: &[ArtistHealth]) -> std::io::Result<()> {
+    let subtitle = format!(
+        "<span>Artists by severity-weighted health, worst first. A file costs the weights of its categories ({}), at most {}; unreadable files {}</span>",
+        CATEGORY_PAGES.iter().map(|c| format!("{} {}", c.title, c.weight)).collect::<Vec<_>>().join(", "),
+        MAX_FILE_PENALTY,
+        MAX_FILE_PENALTY,
+    );
+    let mut page = report.page("health.html", "Health", "health", Some(&subtitle))?;
+
+    let graded = |grade: &str| artists.iter().filter(|a| a.health.grade() == grade).count();
+    page.write_stats(&[vec![
+        StatCard::new("Library", format!("{:.1} ({})", health.score(), health.grade()), health.class()),
+        StatCard::new("Artists with Issues", artists.len(), "info"),
+        StatCard::new("Graded D or F", graded("D") + graded("F"), "fail"),
+        StatCard::new("Graded C", graded("C"), "warn"),
+    ]])?;
+    page.write_search_box(SearchTarget::Table)?;
+
+    let rows: Vec<[String; 8]> = artists.iter().enumerate()
+        .map(|(i, a)| [
+            (i + 1).to_string(),
+            a.name.clone(),
+            format!("{:.1}", a.health.score()),
+            a.health.grade().to_string(),
+            a.health.files.to_string(),
+            a.issues.to_string(),
+            a.critical.to_string(),
+            a.unreadable.to_string(),
+        ])
+        .collect();
+    let rows: Vec<Vec<&str>> = rows.iter().map(|r| r.iter().map(String::as_str).collect()).collect();
+    page.write_table("Leaderboard", "mirror",
+        &["#", "Artist", "Score", "Grade", "Files", "With issues", "Critical", "Unreadable"], &rows)?;
+    page.finish()
+}
+
+// ---------------------------------------------------------------------------
+// Report: mirror.html
+// ---------------------------------------------------------------------------
+
+fn write_mirror_page(
+    report: &Report,
+    scan_root: &str,
+    mirror: &MirrorReport,
+) -> std::io::Result<()> {
+    let subtitle = format!(
+        "<span>Master <code>{}</code> &harr; mirror <code>{}</code></span>",
+        html_text(scan_root), html_text(&mirror.mirror_root)
+    );
+    let mut page = report.page("mirror.html", "Mirror", "mirror", Some(&subtitle))?;
+
+    page.write_stats(&[vec![
+        StatCard::new("In Both", mirror.common, "ok"),
+        StatCard::new("Tags Differ", mirror.diverged.len(), "warn"),
+        StatCard::new("Master Only", mirror.master_only.len(), "fail"),
+        StatCard::new("Mirror Only", mirror.mirror_only.len(), "fail"),
+        StatCard::new("Moved", mirror.moved.len(), "info"),
+    ]])?;
+
+    page.write_search_box(SearchTarget::Table)?;
+
+    let master_only: Vec<Vec<&str>> = mirror.master_only.iter().map(|p| vec![p.as_str()]).collect();
+    page.write_table("Missing from mirror", "mirror", &["Path"], &master_only)?;
+
+    let mirror_only: Vec<Vec<&str>> = mirror.mirror_only.iter().map(|p| vec![p.as_str()]).collect();
+    page.write_table("Only in mirror", "mirror", &["Path"], &mirror_only)?;
+
+    let moved: Vec<Vec<&str>> = mirror.moved.iter()
+        .map(|(master, copy)| vec![master.as_str(), copy.as_str()])
+        .collect();
+    page.write_table("Moved or renamed (same tags)", "mirror", &["Master path", "Mirror path"], &moved)?;
+
+    let mut diverged: Vec<Vec<&str>> = Vec::new();
+    for (rel, fields) in &mirror.diverged {
+        for (field, master, copy) in fields {
+            diverged.push(vec![rel.as_str(), field, master.as_str(), copy.as_str()]);
+        }
+    }
+    page.write_table("Tag differences", "mirror", &["Path", "Field", "Master", "Mirror"], &diverged)?;
+
+    page.finish()
+}
+
+// ---------------------------------------------------------------------------
+// Report: archives.html
+// ---------------------------------------------------------------------------
+
+fn write_archives_page(report: &Report, archives: &ArchiveReport) -> std::io::Result<()> {
+    let subtitle = format!("<span>Archives in <code>{}</code></span>", html_text(&archives.dir));
+    let mut page = report.page("archives.html", "Archives", "archives", Some(&subtitle))?;
+
+    let audio_files: usize = archives.archives.iter().map(|a| a.audio_files).sum();
+    page.write_stats(&[vec![
+        StatCard::new("Archives", archives.archives.len(), "info"),
+        StatCard::new("Audio Files", audio_files, "info"),
+        StatCard::new("Clean", archives.archives.len() - archives.flagged_count(), "ok"),
+        StatCard::new("With Issues", archives.flagged_count(), "fail"),
+    ]])?;
+    page.write_search_box(SearchTarget::Table)?;
+
+    let rows: Vec<[String; 4]> = archives.archives.iter()
+        .map(|a| {
+            let summary = if a.is_clean() { "—".to_string() } else { a.summary() };
+            [a.name.clone(), a.audio_files.to_string(), a.issues.len().to_string(), summary]
+        })
+        .collect();
+    let rows: Vec<Vec<&str>> = rows.iter().map(|r| r.iter().map(String::as_str).collect()).collect();
+    page.write_table("Archives", "mirror", &["Archive", "Audio files", "Files with issues", "Issues"], &rows)?;
+
+    let mut files: Vec<[String; 3]> = Vec::new();
+    for archive in &archives.archives {
+        for issue in &archive.issues {
+            let entry = relative_path(&issue.path, &format!("{}/{}", archives.dir, archive.name));
+            files.push([archive.name.clone(), entry, issue_labels(issue).join(", ")]);
+        }
+        for (entry, error) in &archive.unreadable {
+            files.push([archive.name.clone(), entry.clone(), format!("Unreadable: {}", error)]);
+        }
+    }
+    let files: Vec<Vec<&str>> = files.iter().map(|r| r.iter().map(String::as_str).collect()).collect();
+    page.write_table("Files", "mirror", &["Archive", "File", "Issues"], &files)?;
+
+    page.finish()
+}
+
+// ---------------------------------------------------------------------------
+// Report: fingerprints.html
+// ---------------------------------------------------------------------------
+
+fn write_fingerprints_page(
+    report: &Report,
+    scan_root: &str,
+    fingerprints: &FingerprintReport,
+) -> std::io::Result<()> {
+    let subtitle = format!(
+        "<span>Files whose AcoustID fingerprints match at {:.2} or more, whatever their tags and names</span>",
+        fingerprints.threshold
+    );
+    let mut page = report.page("fingerprints.html", "Fingerprint Dupes", "fingerprints", Some(&subtitle))?;
+
+    page.write_stats(&[vec![
+        StatCard::new("Fingerprinted", fingerprints.fingerprinted, "ok"),
+        StatCard::new("No Fingerprint", fingerprints.missing, "info"),
+        StatCard::new("Clusters", fingerprints.clusters.len(), "warn"),
+        StatCard::new("Extra Copies", fingerprints.extra_copies(), "fail"),
+    ]])?;
+    page.write_search_box(SearchTarget::Table)?;
+
+    let mut rows: Vec<[String; 8]> = Vec::new();
+    for (n, cluster) in fingerprints.clusters.iter().enumerate() {
+        for (i, file) in cluster.iter().enumerate() {
+            let [format, bitrate, length, size] = file.copy.cells();
+            rows.push([
+                (n + 1).to_string(),
+                relative_path(&file.copy.path, scan_root),
+                format,
+                bitrate,
+                length,
+                size,
+                format!("{:.1}%", file.similarity * 100.0),
+                if i == 0 { "Keep".to_string() } else { String::new() },
+            ]);
+        }
+    }
+    let rows: Vec<Vec<&str>> = rows.iter().map(|r| r.iter().map(String::as_str).collect()).collect();
+    page.write_table(
+        "Acoustic duplicates",
+        "mirror",
+        &["Cluster", "Path", "Format", "Bitrate", "Length", "Size", "Match", "Best copy"],
+        &rows,
+    )?;
+
+    page.finish()
+}
+
+// ---------------------------------------------------------------------------
+// Report: duplicates.html
+// ---------------------------------------------------------------------------
+
+fn write_duplicates_page(
+    report: &Report,
+    scan_root: &str,
+    duplicates: &DuplicateReport,
+) -> std::io::Result<()> {
+    let subtitle = format!(
+        "<span>Files that are the same track by {} (case, accents and punctuation ignored)</span>",
+        duplicates.mode.describe()
+    );
+    let mut page = report.page("duplicates.html", "Duplicates", "duplicates", Some(&subtitle))?;
+
+    let mut stats = vec![StatCard::new("Artist + Title", duplicates.keyed, "ok")];
+    match duplicates.mode {
+        DupeMode::Tags => {}
+        DupeMode::AcoustId => stats.push(StatCard::new("No AcoustID", duplicates.unmatched, "info")),
+        DupeMode::Audio => stats.push(StatCard::new("Not Hashed", duplicates.unmatched, "info")),
+    }
+    stats.push(StatCard::new("Groups", duplicates.groups.len(), "warn"));
+    stats.push(StatCard::new("Extra Copies", duplicates.extra_copies(), "fail"));
+    page.write_stats(&[stats])?;
+    page.write_search_box(SearchTarget::Table)?;
+
+    let mut rows: Vec<[String; 8]> = Vec::new();
+    for (n, group) in duplicates.groups.iter().enumerate() {
+        for (i, track) in group.iter().enumerate() {
+            let [format, bitrate, length, size] = track.copy.cells();
+            rows.push([
+                (n + 1).to_string(),
+                relative_path(&track.copy.path, scan_root),
+                format!("{} – {}", track.artist, track.title),
+                format,
+                bitrate,
+                length,
+                size,
+                if i == 0 { "Keep".to_string() } else { String::new() },
+            ]);
+        }
+    }
+    let rows: Vec<Vec<&str>> = rows.iter().map(|r| r.iter().map(String::as_str).collect()).collect();
+    page.write_table(
+        "Duplicate tracks",
+        "mirror",
+        &["Group", "Path", "Artist – Title", "Format", "Bitrate", "Length", "Size", "Best copy"],
+        &rows,
+    )?;
+
+    page.finish()
+}
+
+// ---------------------------------------------------------------------------
+// Report: quality.html
+// ---------------------------------------------------------------------------
+
+fn write_quality_page(
+    report: &Report,
+    scan_root: &str,
+    quality: &QualityReport,
+) -> std::io::Result<()> {
+    let subtitle = format!(
+        "<span>MP3s under {} kbps, sample rates that differ within a folder, tracks under {}</span>",
+        QUALITY_MIN_MP3_KBPS,
+        format_length(QUALITY_MIN_LENGTH)
+    );
+    let mut page = report.page("quality.html", "Quality", "quality", Some(&subtitle))?;
+
+    page.write_stats(&[vec![
+        StatCard::new("Checked", quality.checked, "ok"),
+        StatCard::new("Low Bitrate", quality.count(QualityCheck::LowBitrate), "warn"),
+        StatCard::new("Sample Rate", quality.count(QualityCheck::SampleRate), "warn"),
+        StatCard::new("Short", quality.count(QualityCheck::Short), "fail"),
+    ]])?;
+    page.write_search_box(SearchTarget::Table)?;
+
+    let rows: Vec<[String; 6]> = quality.flags.iter().map(|flag| [
+        flag.check.label().to_string(),
+        relative_path(&flag.path, scan_root),
+        flag.audio.bitrate.map_or("—".to_string(), |kbps| format!("{} kbps", kbps)),
+        flag.audio.sample_rate.map_or("—".to_string(), format_sample_rate),
+        format_length(flag.audio.length),
+        flag.note.clone(),
+    ]).collect();
+    let rows: Vec<Vec<&str>> = rows.iter().map(|r| r.iter().map(String::as_str).collect()).collect();
+    page.write_table(
+        "Audio quality",
+        "mirror",
+        &["Check", "Path", "Bitrate", "Sample rate", "Length", "Note"],
+        &rows,
+    )?;
+
+    page.finish()
+}
+
+// ---------------------------------------------------------------------------
+// Report: orchestrator
+// ---------------------------------------------------------------------------
+
+/// What a report is written from: the scan's results and the optional
+/// stages' reports (`None` when the stage didn't run).
+#[derive(Clone, Copy)]
+struct ReportData<'a> {
+    issues: &'a [FileIssue],
+    all_paths: &'a [PathBuf],
+    lone_files: &'a [LoneFile],
+    unreadable: &'a [(PathBuf, String)],
+    link_dupes: &'a [LinkDuplicate],
+    scan_root: &'a str,
+    total_files: u64,
+    total_size: u64,
+    error_count: u64,
+    file_type_counts: &'a HashMap<String, u64>,
+    elapsed: Duration,
+    /// Fixes made by autofix or --style-fix
+    diffs: Option<&'a MatchDiffs>,
+    /// Why autofix left files alone
+    skipped_files: Option<&'a SkippedFiles>,
+    mirror: Option<&'a MirrorReport>,
+    archives: Option<&'a ArchiveReport>,
+    fingerprints: Option<&'a FingerprintReport>,
+    duplicates: Option<&'a DuplicateReport>,
+    quality: Option<&'a QualityReport>,
+    rips: &'a RipReport,
+    variants: &'a [Variants],
+    provenance: &'a Provenance,
+    /// Issues per protected folder
+    protected: &'a [(String, usize)],
+    /// Earlier runs for the overview's trend charts
+    trend: &'a [HistoryEntry],
+}
+
+fn generate_report(data: &ReportData, report_dir: &Path, pages: &PageFlags) -> std::io::Result<()> {
+    let ReportData {
+        issues, all_paths, lone_files, unreadable, link_dupes, scan_root, total_files, error_count,
+        diffs, skipped_files, mirror, archives, fingerprints, duplicates, quality, rips, variants, provenance, ..
+    } = *data;
+
+    // Compute lone and root files count for nav badge
+    let root_count = all_paths.iter().filter(|p| is_root_file(p, scan_root)).count();
+    let lone_count = lone_files.len();
+
+    // Nav: overview, issues, the selected categories (with files fixed by autofix), mirror
+    let mut nav = vec![
+        NavItem::overview(),
+        NavItem::page("issues", "Issues", "issues.html", root_count + lone_count + unreadable.len() + link_dupes.len()),
+    ];
+    let health = Health::of(issues, total_files, error_count);
+    let artists = artist_health(issues, all_paths, unreadable, scan_root);
+    nav.push(NavItem::page("health", "Health", "health.html", artists.len()));
+    let albums = pages.albums.then(|| build_album_panel(issues, scan_root, diffs, skipped_files));
+    if let Some(panel) = &albums {
+        nav.push(NavItem::page("albums", "Albums", "albums_1.html", panel.groups.len()));
+    }
+    for category in CATEGORY_PAGES {
+        if !pages.shows(category.id) { continue; }
+        let count = issues.iter().filter(|i| (category.has_issue)(i)).count();
+        let matched = diffs.map_or(0, |d| {
+            d.values().filter(|fixes| fixes.iter().any(|fix| fix.category == category.id)).count()
+        });
+        nav.push(NavItem::page(category.id, category.title, format!("{}_1.html", category.id), count)
+            .with_resolved(matched));
+    }
+    if pages.custom {
+        for category in &rules().categories {
+            let count = issues.iter().filter(|i| category.has_issue(i)).count();
+            nav.push(NavItem::page(category.id.as_str(), category.title.as_str(), format!("{}_1.html", category.id), count));
+        }
+    }
+    if pages.consistency {
+        nav.push(NavItem::page("consistency", "Consistency", "consistency.html", variants.len()));
+    }
+    if let Some(m) = mirror {
+        nav.push(NavItem::page("mirror", "Mirror", "mirror.html", m.difference_count()));
+    }
+    if let Some(a) = archives {
+        nav.push(NavItem::page("archives", "Archives", "archives.html", a.flagged_count()));
+    }
+    if let Some(f) = fingerprints {
+        nav.push(NavItem::page("fingerprints", "Fingerprint Dupes", "fingerprints.html", f.extra_copies()));
+    }
+    if let Some(d) = duplicates {
+        nav.push(NavItem::page("duplicates", "Duplicates", "duplicates.html", d.extra_copies()));
+    }
+    if let Some(q) = quality {
+        nav.push(NavItem::page("quality", "Quality", "quality.html", q.flagged_files()));
+    }
+
+    // Skip reasons only exist after --autofix; otherwise the fixes came from --style-fix
+    let resolved_title = if skipped_files.is_some() { "Matched by beets:" } else { "Normalized:" };
+    let report = Report::create(report_dir, REPORT_TITLE, nav)?
+        .with_resolved_title(resolved_title)
+        .with_page_budget(PAGE_BUDGET.get().copied().unwrap_or_default());
+
+    // Write index (always)
+    let counts = run_counts(issues, total_files, error_count, STYLE_LINTS.get().is_some());
+    write_index(&report, data, health, &counts)?;
+    write_summary(report_dir, scan_root, provenance, &counts, health)?;
+    write_results(report_dir, &ScanResults::collect(issues, all_paths, unreadable, scan_root, &provenance.generated, &counts))?;
+
+    // Write selected pages
+    // Issues page is always generated (root, lone and unreadable files are always relevant)
+    write_issues_page(&report, scan_root, all_paths, lone_files, unreadable, link_dupes)?;
+    write_health_page(&report, health, &artists)?;
+    if let Some(panel) = albums {
+        let listed: BTreeSet<&str> = panel.groups.keys().map(String::as_str).collect();
+        let rip_panel = build_rip_panel(rips, &listed, scan_root);
+        write_album_pages(&report, &[panel, rip_panel])?;
+    }
+    for category in CATEGORY_PAGES {
+        if pages.shows(category.id) {
+            write_category_pages(&report, scan_root, issues, category, diffs, skipped_files)?;
+        }
+    }
+    if pages.custom {
+        for category in &rules().categories {
+            write_custom_category_pages(&report, scan_root, issues, category)?;
+        }
+    }
+    if pages.consistency {
+        write_consistency_page(&report, variants)?;
+    }
+    if let Some(m) = mirror {
+        write_mirror_page(&report, scan_root, m)?;
+    }
+    if let Some(a) = archives {
+        write_archives_page(&report, a)?;
+    }
+    if let Some(f) = fingerprints {
+        write_fingerprints_page(&report, scan_root, f)?;
+    }
+    if let Some(d) = duplicates {
+        write_duplicates_page(&report, scan_root, d)?;
+    }
+    if let Some(q) = quality {
+        write_quality_page(&report, scan_root, q)?;
+    }
+
+    if SINGLE_FILE.load(Ordering::Relaxed) {
+        report.write_single_file(&single_file_path(report_dir))?;
+    }
+
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Autofix: beets integration
+// ---------------------------------------------------------------------------
+
+/// Print detailed installation instructions for beets and its required plugins.
+fn print_beet_install_instructions() {
+    eprintln!();
+    eprintln!("ERROR: beet (beets) is not installed or not found in PATH.");
+    eprintln!();
+    eprintln!("Install beets and required dependencies:");
+    eprintln!();
+    eprintln!("  # Core");
+    eprintln!("  pip install beets");
+    eprintln!();
+    eprintln!("  # Required plugins");
+    eprintln!("  pip install pyacoustid              # AcoustID fingerprinting (chroma plugin)");
+    eprintln!("  pip install python-discogs-client    # Discogs metadata");
+    eprintln!();
+    eprintln!("  # Chromaprint fingerprinter (system package)");
+    eprintln!("  sudo apt install libchromaprint-tools   # provides fpcalc");
+    eprintln!();
+    eprintln!("  # Recommended plugins");
+    eprintln!("  pip install beets-bandcamp           # Bandcamp metadata");
+    eprintln!("  pip install pylast                   # Last.fm genre tagging");
+    eprintln!();
+    eprintln!("Configure beets (~/.config/beets/config.yaml):");
+    eprintln!();
+    eprintln!("  plugins:");
+    eprintln!("    - chroma");
+    eprintln!("    - discogs");
+    eprintln!("    - fetchart");
+    eprintln!("    - embedart");
+    eprintln!("    - lastgenre");
+    eprintln!("    - bandcamp");
+    eprintln!();
+    eprintln!("  import:");
+    eprintln!("    write: yes");
+    eprintln!("    move: no");
+    eprintln!("    copy: no");
+    eprintln!("    quiet: yes");
+    eprintln!();
+    eprintln!("  acoustid:");
+    eprintln!("    apikey: YOUR_KEY   # Get from https://acoustid.org/api-key");
+    eprintln!();
+    eprintln!("  chroma:");
+    eprintln!("    auto: yes");
+    eprintln!();
+    eprintln!("  discogs:");
+    eprintln!("    user_token: YOUR_TOKEN   # Get from https://www.discogs.com/settings/developers");
+}
+
+/// Check beets availability and required plugins. Returns Ok(version_string) or exits.
+fn check_beets_setup() {
+    // 1. Check beet binary
+    let beet_output = match std::process::Command::new("beet")
+        .arg("version")
+        .output()
+    {
+        Ok(o) if o.status.success() => o,
+        _ => {
+            print_beet_install_instructions();
+            std::process::exit(1);
+        }
+    };
+
+    let version_str = String::from_utf8_lossy(&beet_output.stdout);
+    for line in version_str.lines() {
+        println!("  {}", line.trim());
+    }
+
+    // 2. Check fpcalc (chromaprint fingerprinter)
+    if std::process::Command::new("fpcalc")
+        .arg("-version")
+        .output()
+        .is_err()
+    {
+        eprintln!();
+        eprintln!("ERROR: fpcalc not found. Required by the chroma plugin for AcoustID fingerprinting.");
+        eprintln!();
+        eprintln!("  Install: sudo apt install libchromaprint-tools");
+        std::process::exit(1);
+    }
+
+    // 3. Check required plugins from version output
+    let required = &["chroma", "discogs"];
+    let recommended = &["bandcamp", "fetchart", "embedart", "lastgenre"];
+
+    let mut missing_required: Vec<&str> = Vec::new();
+    let mut missing_recommended: Vec<&str> = Vec::new();
+
+    for &plugin in required {
+        if !version_str.contains(plugin) {
+            missing_required.push(plugin);
+        }
+    }
+    for &plugin in recommended {
+        if !version_str.contains(plugin) {
+            missing_recommended.push(plugin);
+        }
+    }
+
+    if !missing_required.is_empty() {
+        eprintln!();
+        eprintln!("ERROR: Missing required beets plugins: {}", missing_required.join(", "));
+        eprintln!();
+        for &p in &missing_required {
+            match p {
+                "chroma" => eprintln!("  pip install pyacoustid              # chroma plugin (AcoustID)"),
+                "discogs" => eprintln!("  pip install python-discogs-client    # discogs plugin"),
+                _ => eprintln!("  # Enable '{}' in your beets config plugins list", p),
+            }
+        }
+        eprintln!();
+        eprintln!("Then add them to plugins: in ~/.config/beets/config.yaml");
+        std::process::exit(1);
+    }
+
+    if !missing_recommended.is_empty() {
+        println!("  Note: recommended plugins not loaded: {}", missing_recommended.join(", "));
+    }
+}
+
+/// What one tagger run (`beet import` or a custom command) made of a directory.
+enum TaggerOutcome {
+    /// Tagged (or would be, on a dry run): the output lines to show on a dry run
+    Tagged(Vec<String>),
+    /// No confident match, with the reason from the tagger's output
+    Skipped(String),
+    /// The tagger exited with an error: its first output line
+    Error(String),
+    /// The tagger could not be started
+    Failed(String),
+}
+
+/// Runs `beet import` on one directory against the library `tmp_lib`.
+fn beet_import(dir: &Path, tmp_lib: &str, singleton: bool, dry: bool) -> TaggerOutcome {
+    let mut cmd = std::process::Command::new("beet");
+    cmd.arg("-l").arg(tmp_lib)
+        .arg("import")
+        .arg("-C")    // don't copy/move files
+        .arg("-q");   // quiet mode (no prompts, skip uncertain matches)
+
+    if dry {
+        cmd.arg("--pretend"); // dry run: show what would be tagged
+    } else {
+        cmd.arg("-w");        // write tags to files
+    }
+
+    if singleton {
+        cmd.arg("-s"); // singleton mode for lone files
+    }
+
+    cmd.arg(dir.as_os_str());
+    cmd.stdout(std::process::Stdio::piped());
+    cmd.stderr(std::process::Stdio::piped());
+
+    let output = match cmd.output() {
+        Ok(output) => output,
+        Err(e) => return TaggerOutcome::Failed(e.to_string()),
+    };
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let combined = format!("{}{}", stdout, stderr);
+    if !output.status.success() {
+        let first_line = combined
+            .lines()
+            .find(|l| !l.trim().is_empty())
+            .unwrap_or("unknown error");
+        return TaggerOutcome::Error(first_line.trim().to_string());
+    }
+    if combined.to_lowercase().contains("skipping") || combined.to_lowercase().contains("no good match") {
+        // Extract a meaningful reason from beets output, or use a default
+        let reason = combined.lines()
+            .find(|l| {
+                let lower = l.to_lowercase();
+                lower.contains("skipping") || lower.contains("no good match")
+                    || lower.contains("no candidate") || lower.contains("no match")
+            })
+            .map(|l| l.trim().to_string())
+            .unwrap_or_else(|| "No confident match from beets".to_string());
+        return TaggerOutcome::Skipped(reason);
+    }
+    TaggerOutcome::Tagged(stdout.lines().map(str::trim).filter(|l| !l.is_empty()).map(String::from).collect())
+}
+
+/// The tagger `--autofix` runs on each directory.
+enum Tagger {
+    /// `beet import`, with its plugin checks and a temporary library per job
+    Beets,
+    /// `--autofix-cmd` / `--autofix-config`
+    Command(TaggerCommand),
+    /// `--autofix-native`
+    Native(NativeTagger),
+}
+
+impl Tagger {
+    fn name(&self) -> &str {
+        match self {
+            Tagger::Beets => "beets",
+            Tagger::Command(command) => &command.name,
+            Tagger::Native(_) => "MusicBrainz",
+        }
+    }
+}
+
+/// An `--autofix-config` TOML file. Commands are split like a shell would
+/// (quotes group words), and `{dir}` / `{files}` are filled in per directory.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TaggerConfig {
+    /// Shown in the output; defaults to the command's program name
+    name: Option<String>,
+    command: Option<String>,
+    /// Run by --autofix-dry instead of `command`; without it, the dry run
+    /// only prints the commands
+    dry_command: Option<String>,
+    /// Output lines containing one of these (case insensitive) mark the
+    /// directory as skipped, with the line as the reason
+    #[serde(default)]
+    skip_patterns: Vec<String>,
+    /// When set, output must contain one of these to count as tagged;
+    /// anything else is skipped
+    #[serde(default)]
+    match_patterns: Vec<String>,
+}
+
+/// A custom tagger, ready to run: see [`TaggerConfig`].
+#[derive(Debug)]
+struct TaggerCommand {
+    name: String,
+    command: Vec<String>,
+    dry_command: Option<Vec<String>>,
+    skip_patterns: Vec<String>,
+    match_patterns: Vec<String>,
+}
+
+/// Placeholders a tagger command can use.
+const TAGGER_PLACEHOLDERS: &[&str] = &["{dir}", "{files}"];
+
+impl TaggerCommand {
+    /// The custom tagger from `--autofix-config` and `--autofix-cmd` (which
+    /// replaces the file's `command`), or `None` for beets.
+    fn from_args(cmd: Option<&str>, config: Option<&str>) -> Result<Option<Self>, String> {
+        let mut config = match config {
+            Some(path) => {
+                let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+                toml::from_str::<TaggerConfig>(&text).map_err(|e| format!("{}: {}", path, e))?
+            }
+            None if cmd.is_none() => return Ok(None),
+            None => TaggerConfig::default(),
+        };
+        if let Some(cmd) = cmd {
+            config.command = Some(cmd.to_string());
+        }
+        Self::parse(config).map(Some)
+    }
+
+    fn parse(config: TaggerConfig) -> Result<Self, String> {
+        let split = |field: &str, text: &str| -> Result<Vec<String>, String> {
+            let words = shlex::split(text).ok_or_else(|| format!("{}: unbalanced quotes", field))?;
+            if words.is_empty() {
+                return Err(format!("{}: is empty", field));
+            }
+            if !words.iter().any(|w| TAGGER_PLACEHOLDERS.iter().any(|p| w.contains(p))) {
+                return Err(format!("{}: needs {} or {} to know where to tag", field, TAGGER_PLACEHOLDERS[0], TAGGER_PLACEHOLDERS[1]));
+            }
+            Ok(words)
+        };
+        let command = split("command", config.command.as_deref().ok_or("command: missing")?)?;
+        let dry_command = config.dry_command.as_deref().map(|c| split("dry_command", c)).transpose()?;
+        let name = config.name.unwrap_or_else(|| {
+            Path::new(&command[0]).file_name().map_or(command[0].clone(), |n| n.to_string_lossy().into_owned())
+        });
+        let patterns = |list: Vec<String>| list.into_iter()
+            .map(|p| p.trim().to_lowercase())
+            .filter(|p| !p.is_empty())
+            .collect();
+        Ok(TaggerCommand {
+            name,
+            command,
+            dry_command,
+            skip_patterns: patterns(config.skip_patterns),
+            match_patterns: patterns(config.match_patterns),
+        })
+    }
+
+    /// `template` for one directory: a `{files}` word becomes one argument per
+    /// file, `{dir}` is replaced wherever it appears.
+    fn expand(template: &[String], dir: &Path, files: &[&Path]) -> Vec<String> {
+        let dir = dir.to_string_lossy();
+        let mut args = Vec::new();
+        for word in template {
+            if word == "{files}" {
+                args.extend(files.iter().map(|f| f.to_string_lossy().into_owned()));
+            } else {
+                args.push(word.replace("{dir}", &dir));
+            }
+        }
+        args
+    }
+
+    /// Runs the tagger on one directory and reads its exit status and output
+    /// against the skip / match patterns.
+    fn run(&self, dir: &Path, files: &[&Path], dry: bool) -> TaggerOutcome {
+        let template = match (dry, &self.dry_command) {
+            (false, _) => &self.command,
+            (true, Some(dry_command)) => dry_command,
+            (true, None) => {
+                let args = Self::expand(&self.command, dir, files);
+                let line = shlex::try_join(args.iter().map(String::as_str)).unwrap_or_else(|_| args.join(" "));
+                return TaggerOutcome::Tagged(vec![format!("would run: {}", line)]);
+            }
+        };
+        let args = Self::expand(template, dir, files);
+        let output = match std::process::Command::new(&args[0]).args(&args[1..]).output() {
+            Ok(output) => output,
+            Err(e) => return TaggerOutcome::Failed(e.to_string()),
+        };
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let combined = format!("{}{}", stdout, String::from_utf8_lossy(&output.stderr));
+        if !output.status.success() {
+            let first_line = combined.lines().find(|l| !l.trim().is_empty()).unwrap_or("unknown error");
+            return TaggerOutcome::Error(first_line.trim().to_string());
+        }
+        let has = |line: &str, patterns: &[String]| {
+            let lower = line.to_lowercase();
+            patterns.iter().any(|p| lower.contains(p.as_str()))
+        };
+        if let Some(line) = combined.lines().find(|l| has(l, &self.skip_patterns)) {
+            return TaggerOutcome::Skipped(line.trim().to_string());
+        }
+        if !self.match_patterns.is_empty() && !combined.lines().any(|l| has(l, &self.match_patterns)) {
+            return TaggerOutcome::Skipped(format!("No confident match from {}", self.name));
+        }
+        TaggerOutcome::Tagged(stdout.lines().map(str::trim).filter(|l| !l.is_empty()).map(String::from).collect())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Autofix: native MusicBrainz lookups (--autofix-native)
+// ---------------------------------------------------------------------------
+
+/// Release search hits below this score are never used.
+const NATIVE_MIN_SCORE: u32 = 90;
+
+#[derive(Debug, Deserialize)]
+struct MbReleaseSearch {
+    #[serde(default)]
+    releases: Vec<MbReleaseHit>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MbReleaseHit {
+    id: String,
+    score: Option<u32>,
+    #[serde(rename = "track-count", default)]
+    track_count: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct MbRelease {
+    id: String,
+    title: String,
+    date: Option<String>,
+    #[serde(rename = "artist-credit", default)]
+    artist_credit: Vec<MbCredit>,
+    #[serde(default)]
+    media: Vec<MbMedium>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MbMedium {
+    #[serde(default)]
+    position: u32,
+    #[serde(default)]
+    tracks: Vec<MbTrack>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MbTrack {
+    position: u32,
+    title: String,
+    recording: MbRecordingRef,
+    #[serde(rename = "artist-credit", default)]
+    artist_credit: Vec<MbCredit>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MbRecordingRef {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MbRecordingSearch {
+    #[serde(default)]
+    recordings: Vec<MbRecordingHit>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MbRecordingHit {
+    id: String,
+    title: String,
+    score: Option<u32>,
+    #[serde(rename = "artist-credit", default)]
+    artist_credit: Vec<MbCredit>,
+    #[serde(rename = "first-release-date")]
+    first_release_date: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MbCredit {
+    name: String,
+    #[serde(default)]
+    joinphrase: String,
+    artist: MbArtistRef,
+}
+
+#[derive(Debug, Deserialize)]
+struct MbArtistRef {
+    id: String,
+}
+
+/// The credited name as MusicBrainz prints it, e.g. "Simon & Garfunkel".
+fn credit_name(credits: &[MbCredit]) -> String {
+    credits.iter().map(|c| format!("{}{}", c.name, c.joinphrase)).collect()
+}
+
+/// The year of an MB date (`1997`, `1997-05`, `1997-05-21`).
+fn mb_year(date: Option<&str>) -> Option<String> {
+    date.and_then(|d| d.get(..4)).filter(|y| y.chars().all(|c| c.is_ascii_digit())).map(String::from)
+}
+
+/// One audio file of a directory `--autofix-native` looks up.
+#[derive(Debug)]
+struct LocalTrack {
+    path: PathBuf,
+    tags: HashMap<String, String>,
+    disc: u32,
+    track: Option<u32>,
+}
+
+impl LocalTrack {
+    fn new(path: PathBuf, tags: HashMap<String, String>) -> Self {
+        let number = |keys: &[&str]| -> Option<u32> {
+            get_tag(&tags, keys)?.split('/').next()?.trim().parse().ok()
+        };
+        let disc = number(&["DISCNUMBER"]).unwrap_or(1);
+        // Untagged files are usually still named "01 Airbag.flac"
+        let track = number(&["TRACKNUMBER"]).or_else(|| {
+            let stem = path.file_stem()?.to_string_lossy();
+            let digits: String = stem.chars().take_while(char::is_ascii_digit).collect();
+            digits.parse().ok().filter(|n| *n > 0)
+        });
+        LocalTrack { path, tags, disc, track }
+    }
+}
+
+/// What MusicBrainz says one file is.
+#[derive(Debug)]
+struct NativeMatch {
+    artist: String,
+    artist_id: Option<String>,
+    title: String,
+    recording_id: String,
+    /// `None` for a lone track looked up as a recording
+    release_id: Option<String>,
+    year: Option<String>,
+}
+
+/// The tags `--autofix-native` writes to a file: only the ones it lacks.
+fn native_writes(local: &LocalTrack, found: &NativeMatch) -> Vec<(&'static str, lofty::tag::ItemKey, String)> {
+    use lofty::tag::ItemKey;
+    let fields: [(&'static str, ItemKey, &[&str], Option<&String>); 6] = [
+        ("artist", ItemKey::TrackArtist, &["ARTIST"], Some(&found.artist)),
+        ("title", ItemKey::TrackTitle, &["TITLE"], Some(&found.title)),
+        ("year", ItemKey::Year, &["YEAR"], found.year.as_ref()),
+        ("MB artist ID", ItemKey::MusicBrainzArtistId, MB_ARTIST_ID_TAGS, found.artist_id.as_ref()),
+        ("MB track ID", ItemKey::MusicBrainzRecordingId, MB_TRACK_ID_TAGS, Some(&found.recording_id)),
+        ("MB album ID", ItemKey::MusicBrainzReleaseId, MB_ALBUM_ID_TAGS, found.release_id.as_ref()),
+    ];
+    fields.into_iter()
+        .filter(|(_, _, keys, _)| get_tag(&local.tags, keys).is_none())
+        .filter_map(|(label, key, _, value)| {
+            let value = value?.trim();
+            (!value.is_empty()).then(|| (label, key, value.to_string()))
+        })
+        .collect()
+}
+
+/// The search hit to use for a directory of `track_count` files: the best
+/// scoring one with that many tracks.
+fn pick_release(hits: &MbReleaseSearch, track_count: usize) -> Option<&str> {
+    hits.releases.iter()
+        .find(|r| r.score.unwrap_or(0) >= NATIVE_MIN_SCORE && r.track_count == track_count)
+        .map(|r| r.id.as_str())
+}
+
+/// The release track each local file is: by disc and track number when the
+/// file has one, else by title. `None` for files that match nothing.
+fn match_release_tracks(release: &MbRelease, tracks: &[LocalTrack]) -> Vec<Option<NativeMatch>> {
+    let single_medium = release.media.len() == 1;
+    let all: Vec<(&MbMedium, &MbTrack)> = release.media.iter()
+        .flat_map(|m| m.tracks.iter().map(move |t| (m, t)))
+        .collect();
+    tracks.iter().map(|local| {
+        let by_number = local.track.and_then(|n| all.iter().find(|(m, t)| {
+            t.position == n && (single_medium || m.position == local.disc)
+        }));
+        let by_title = || get_tag(&local.tags, &["TITLE"]).and_then(|title| {
+            let key = consistency_key(&title);
+            all.iter().find(|(_, t)| !key.is_empty() && consistency_key(&t.title) == key)
+        });
+        let (_, track) = by_number.or_else(by_title)?;
+        let credits = if track.artist_credit.is_empty() { &release.artist_credit } else { &track.artist_credit };
+        Some(NativeMatch {
+            artist: credit_name(credits),
+            artist_id: credits.first().map(|c| c.artist.id.clone()),
+            title: track.title.clone(),
+            recording_id: track.recording.id.clone(),
+            release_id: Some(release.id.clone()),
+            year: mb_year(release.date.as_deref()),
+        })
+    }).collect()
+}
+
+/// The most frequent non-empty value of `keys` across `tracks`.
+fn most_common_tag(tracks: &[LocalTrack], keys: &[&str]) -> Option<String> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for track in tracks {
+        if let Some(value) = get_tag(&track.tags, keys) {
+            *counts.entry(value.trim().to_string()).or_default() += 1;
+        }
+    }
+    counts.into_iter().max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0))).map(|(value, _)| value)
+}
+
+/// Writes `writes` into the file's primary tag, creating it if the file has none.
+fn write_native_tags(path: &Path, writes: &[(&'static str, lofty::tag::ItemKey, String)]) -> Result<(), String> {
+    let mut tagged = lofty::read_from_path(path).map_err(|e| e.to_string())?;
+    let tag_type = tagged.primary_tag_type();
+    if tagged.tag(tag_type).is_none() {
+        tagged.insert_tag(lofty::tag::Tag::new(tag_type));
+    }
+    let Some(tag) = tagged.tag_mut(tag_type) else { return Err(format!("no {:?} tag", tag_type)) };
+    for (label, key, value) in writes {
+        if !tag.insert_text(key.clone(), value.clone()) {
+            return Err(format!("{:?} can't hold {}", tag_type, label));
+        }
+    }
+    tag.save_to_path(path, WriteOptions::default()).map_err(|e| e.to_string())
+}
+
+/// `--autofix-native`: looks each directory up on MusicBrainz itself and
+/// writes the IDs, year, artist and title its files lack with lofty, so
+/// autofix works without Python or beets. Requests share sync's client and
+/// rate limit (one a second on musicbrainz.org).
+struct NativeTagger {
+    runtime: tokio::runtime::Runtime,
+    client: mb::Client,
+    limiter: Mutex<mb::RateLimiter>,
+}
+
+impl NativeTagger {
+    fn new(ca_bundle: Option<&s3::CaBundle>) -> Result<Self, String> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(NativeTagger {
+            runtime,
+            client: mb::http_client(ca_bundle),
+            limiter: Mutex::new(mb::RateLimiter::new()),
+        })
+    }
+
+    fn get<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T, String> {
+        let mut limiter = self.limiter.lock().map_err(|_| "rate limiter poisoned".to_string())?;
+        let body = self.runtime.block_on(mb::get(&self.client, url, &mut limiter))?;
+        serde_json::from_str(&body).map_err(|e| format!("Parse error: {}", e))
+    }
+
+    /// Looks up one directory (a lone track as a recording, anything else as
+    /// a release with the same number of tracks) and tags what matched.
+    fn run(&self, dir: &Path, scan_root: &str, singleton: bool, dry: bool) -> TaggerOutcome {
+        let mut paths: Vec<PathBuf> = match fs::read_dir(dir) {
+            Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).filter(|p| p.is_file() && is_audio_file(p)).collect(),
+            Err(e) => return TaggerOutcome::Failed(e.to_string()),
+        };
+        paths.sort();
+        let tracks: Vec<LocalTrack> = paths.into_iter()
+            .map(|path| {
+                let tags = read_tags(&path).unwrap_or_default();
+                LocalTrack::new(path, tags)
+            })
+            .collect();
+        if tracks.is_empty() {
+            return TaggerOutcome::Skipped("No audio files left in the directory".to_string());
+        }
+
+        // Tags first, then the folder names (Artist/Album/...)
+        let rel = relative_path(dir, scan_root);
+        let mut folders = rel.split('/').filter(|c| !c.is_empty());
+        let artist = most_common_tag(&tracks, &["ALBUMARTIST"])
+            .or_else(|| most_common_tag(&tracks, &["ARTIST"]))
+            .or_else(|| folders.next().map(String::from));
+
+        let (heading, matches) = if singleton {
+            let local = &tracks[0];
+            let title = get_tag(&local.tags, &["TITLE"])
+                .or_else(|| local.path.file_stem().map(|s| s.to_string_lossy().into_owned()));
+            let (Some(artist), Some(title)) = (artist, title) else {
+                return TaggerOutcome::Skipped("No artist or title to search MusicBrainz for".to_string());
+            };
+            let query = format!("recording:{} AND artist:{}", mb::quote_query(&title), mb::quote_query(&artist));
+            let hits: MbRecordingSearch = match self.get(&mb::search_url("recording", &query, 5)) {
+                Ok(hits) => hits,
+                Err(e) => return TaggerOutcome::Error(e),
+            };
+            let Some(hit) = hits.recordings.into_iter().find(|r| r.score == Some(100)) else {
+                return TaggerOutcome::Skipped(format!("No MusicBrainz recording matches {} - {}", artist, title));
+            };
+            let heading = format!("MusicBrainz: {} - {} (recording {})", credit_name(&hit.artist_credit), hit.title, hit.id);
+            let found = NativeMatch {
+                artist: credit_name(&hit.artist_credit),
+                artist_id: hit.artist_credit.first().map(|c| c.artist.id.clone()),
+                year: mb_year(hit.first_release_date.as_deref()),
+                title: hit.title,
+                recording_id: hit.id,
+                release_id: None,
+            };
+            (heading, vec![Some(found)])
+        } else {
+            let album = most_common_tag(&tracks, &["ALBUMTITLE", "ALBUM"])
+                .or_else(|| dir.file_name().map(|n| n.to_string_lossy().into_owned()));
+            let (Some(artist), Some(album)) = (artist, album) else {
+                return TaggerOutcome::Skipped("No artist or album to search MusicBrainz for".to_string());
+            };
+            let query = format!("release:{} AND artist:{}", mb::quote_query(&album), mb::quote_query(&artist));
+            let hits: MbReleaseSearch = match self.get(&mb::search_url("release", &query, 10)) {
+                Ok(hits) => hits,
+                Err(e) => return TaggerOutcome::Error(e),
+            };
+            let Some(id) = pick_release(&hits, tracks.len()) else {
+                return TaggerOutcome::Skipped(format!(
+                    "No MusicBrainz release of {} - {} with {} tracks", artist, album, tracks.len()));
+            };
+            let url = format!("{}/release/{}?inc=recordings+artist-credits&fmt=json", mb::base(), id);
+            let release: MbRelease = match self.get(&url) {
+                Ok(release) => release,
+                Err(e) => return TaggerOutcome::Error(e),
+            };
+            let heading = format!("MusicBrainz: {} - {} (release {})", credit_name(&release.artist_credit), release.title, release.id);
+            (heading, match_release_tracks(&release, &tracks))
+        };
+
+        if matches.iter().all(Option::is_none) {
+            return TaggerOutcome::Skipped(format!("No file matched a track of the release ({})", heading));
+        }
+        let mut lines = vec![heading];
+        for (local, found) in tracks.iter().zip(&matches) {
+            let name = local.path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+            let Some(found) = found else {
+                lines.push(format!("{}: no matching track", name));
+                continue;
+            };
+            let writes = native_writes(local, found);
+            if writes.is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = writes.iter().map(|(label, _, _)| *label).collect();
+            lines.push(format!("{}: {}", name, fields.join(", ")));
+            if !dry {
+                if let Err(e) = write_native_tags(&local.path, &writes) {
+                    return TaggerOutcome::Error(format!("{}: {}", name, e));
+                }
+            }
+        }
+        if lines.len() == 1 {
+            return TaggerOutcome::Skipped("Nothing missing that MusicBrainz has".to_string());
+        }
+        TaggerOutcome::Tagged(lines)
+    }
+}
+
+/// Counters shared by the autofix workers; also serializes their output.
+#[derive(Default)]
+struct AutofixTally {
+    done: usize,
+    processed: u32,
+    skipped: u32,
+    failed: u32,
     // dir → skip reason (only for non-dry runs, only for skipped dirs)
-    let mut skipped_dirs: HashMap<PathBuf, String> = HashMap::new();
+    skipped_dirs: HashMap<PathBuf, String>,
+}
+
+/// Run the autofix phase: invoke the tagger (beet import by default) on each directory
+/// containing files with issues. With `jobs` > 1, that many run at once (beets against
+/// a temporary library each), and each directory's line is printed when it finishes.
+/// Returns a map of directory → skip reason for directories beets skipped (real run only).
+/// For dry runs the returned map is always empty.
+fn run_autofix(
+    issues: &[FileIssue],
+    scan_root: &str,
+    parent_audio_count: &HashMap<PathBuf, usize>,
+    dry: bool,
+    jobs: usize,
+    tagger: &Tagger,
+) -> HashMap<PathBuf, String> {
+    let label = if dry { "Autofix DRY RUN" } else { "Autofix" };
+
+    if let Tagger::Beets = tagger {
+        println!("\n[{}] Checking beets installation...", label);
+        check_beets_setup();
+    }
+
+    // Group issue files by parent directory
+    let mut dirs_to_fix: BTreeMap<PathBuf, Vec<&Path>> = BTreeMap::new();
+    for issue in issues {
+        if let Some(parent) = issue.path.parent() {
+            dirs_to_fix.entry(parent.to_path_buf()).or_default().push(&issue.path);
+        }
+    }
+
+    let total_dirs = dirs_to_fix.len();
+    if total_dirs == 0 {
+        println!("\n[{}] No directories to process.", label);
+        return HashMap::new();
+    }
+    let jobs = jobs.clamp(1, total_dirs);
+
+    println!("\n[{}] Processing {} director{} ({} files with issues){}...\n",
+        label,
+        total_dirs,
+        if total_dirs == 1 { "y" } else { "ies" },
+        issues.len(),
+        if jobs > 1 { format!(", {} at a time", jobs) } else { String::new() },
+    );
+
+    let dirs: Vec<(&PathBuf, &Vec<&Path>)> = dirs_to_fix.iter().collect();
+    let next = std::sync::atomic::AtomicUsize::new(0);
+    let tally = Mutex::new(AutofixTally::default());
+    let started = Instant::now();
+
+    // One worker per job, each with its own temporary library: the libraries
+    // keep the user's main beet DB clean, and separate ones keep concurrent
+    // imports from locking each other out of the same SQLite file
+    let worker = |job: usize| {
+        let tmp_lib = if jobs == 1 {
+            format!("/tmp/analysis_autofix_{}.db", std::process::id())
+        } else {
+            format!("/tmp/analysis_autofix_{}_{}.db", std::process::id(), job)
+        };
+        loop {
+            let idx = next.fetch_add(1, Ordering::Relaxed);
+            let Some((dir, dir_files)) = dirs.get(idx) else { break };
+            let rel = relative_path(dir, scan_root);
+            let is_singleton = parent_audio_count.get(*dir).copied().unwrap_or(0) == 1;
+            let files = format!("{} file{}", dir_files.len(), if dir_files.len() == 1 { "" } else { "s" });
+
+            // A single job shows the directory while beets works on it
+            if jobs == 1 {
+                print!("  [{}/{}] {} ({}) ... ", idx + 1, total_dirs, rel, files);
+                std::io::stdout().flush().ok();
+            }
+            let outcome = match tagger {
+                Tagger::Beets => beet_import(dir, &tmp_lib, is_singleton, dry),
+                Tagger::Command(command) => command.run(dir, dir_files, dry),
+                Tagger::Native(native) => native.run(dir, scan_root, is_singleton, dry),
+            };
+
+            let Ok(mut tally) = tally.lock() else { break };
+            tally.done += 1;
+            if jobs > 1 {
+                print!("  [{}/{}] {} ({}) ... ", tally.done, total_dirs, rel, files);
+            }
+            match outcome {
+                TaggerOutcome::Tagged(pretend) => {
+                    if dry {
+                        println!("would tag");
+                        // Print pretend output indented
+                        for line in pretend {
+                            println!("    {}", line);
+                        }
+                    } else {
+                        println!("done");
+                    }
+                    tally.processed += 1;
+                }
+                TaggerOutcome::Skipped(reason) => {
+                    println!("skipped (no confident match)");
+                    tally.skipped += 1;
+                    if !dry {
+                        tally.skipped_dirs.insert((*dir).clone(), reason);
+                    }
+                }
+                TaggerOutcome::Error(line) => {
+                    println!("error: {}", line);
+                    tally.failed += 1;
+                }
+                TaggerOutcome::Failed(e) => {
+                    println!("failed: {}", e);
+                    tally.failed += 1;
+                }
+            }
+            if jobs > 1 && tally.done.is_multiple_of(25) && tally.done < total_dirs {
+                let elapsed = started.elapsed();
+                let eta = elapsed.mul_f64((total_dirs - tally.done) as f64 / tally.done as f64);
+                println!("  -- {}/{} directories, {} elapsed, ETA {}",
+                    tally.done, total_dirs,
+                    dmp_common::progress::format_duration(elapsed),
+                    dmp_common::progress::format_duration(eta));
+            }
+        }
+
+        // Clean up temporary beet library
+        if let Tagger::Beets = tagger {
+            let _ = fs::remove_file(&tmp_lib);
+        }
+    };
+    std::thread::scope(|s| {
+        for job in 1..jobs {
+            let worker = &worker;
+            s.spawn(move || worker(job));
+        }
+        worker(0);
+    });
+
+    let tally = tally.into_inner().unwrap_or_default();
+    println!();
+    println!("[{}] Complete.", label);
+    if dry {
+        println!("  Would tag: {}", tally.processed);
+    } else {
+        println!("  Tagged:    {}", tally.processed);
+    }
+    println!("  Skipped:   {} (no confident match)", tally.skipped);
+    println!("  Failed:    {}", tally.failed);
+    if jobs > 1 {
+        println!("  Time:      {} ({} jobs)", dmp_common::progress::format_duration(started.elapsed()), jobs);
+    }
+
+    tally.skipped_dirs
+}
+
+// ---------------------------------------------------------------------------
+// Autofix: re-scan after beets
+// ---------------------------------------------------------------------------
+
+/// Re-scan files that originally had issues, classify fix status, and build per-field diffs.
+/// Returns (fixed_paths, still_broken, newly_unreadable, autofix_diffs).
+/// Re-scan files that originally had issues, classify fix status, and build per-field diffs.
+/// `skip_dirs` maps directories that beets skipped → the skip reason.
+/// Returns (fixed_paths, still_broken, newly_unreadable, autofix_diffs, skipped_files).
+fn compute_autofix_diffs(
+    original_issues: &[FileIssue],
+    skip_dirs: &HashMap<PathBuf, String>,
+) -> (Vec<PathBuf>, Vec<FileIssue>, Vec<(PathBuf, String)>, MatchDiffs, SkippedFiles) {
+    let mut matched: Vec<PathBuf> = Vec::new();
+    let mut still_broken: Vec<FileIssue> = Vec::new();
+    let mut unreadable: Vec<(PathBuf, String)> = Vec::new();
+    let mut diffs: MatchDiffs = HashMap::new();
+    // Expand dir-level skip info to individual file paths
+    let mut skipped_files: SkippedFiles = HashMap::new();
+    for orig in original_issues {
+        if let Some(parent) = orig.path.parent() {
+            if let Some(reason) = skip_dirs.get(parent) {
+                skipped_files.insert(orig.path.clone(), reason.clone());
+            }
+        }
+    }
+
+    for orig in original_issues {
+        let (new_issue, _new_tags) = match scan_file(&orig.path) {
+            Ok(result) => result,
+            Err(err) => {
+                unreadable.push((orig.path.clone(), err));
+                continue;
+            }
+        };
+
+        // Re-open file to read new tag values for diffs
+        let parse_opts = ParseOptions::new().read_properties(false);
+        let tag_map = match read_tagged_file(&orig.path, parse_opts) {
+            Ok(tagged) => collect_tags(&tagged),
+            Err(_) => HashMap::new(),
+        };
+
+        // Build field-level diffs: for each field that was bad and is now good
+        let mut field_matches: Vec<FieldMatch> = Vec::new();
+
+        // --- Critical fields ---
+        if orig.missing_artist && !new_issue.missing_artist {
+            field_matches.push(FieldMatch {
+                field: "Artist",
+                old_display: "Missing".into(),
+                new_value: get_tag(&tag_map, &["ARTIST"]).unwrap_or_default(),
+                category: "critical",
+            });
+        }
+        if orig.blank_artist && !new_issue.blank_artist {
+            field_matches.push(FieldMatch {
+                field: "Artist",
+                old_display: "(blank)".into(),
+                new_value: get_tag(&tag_map, &["ARTIST"]).unwrap_or_default(),
+                category: "critical",
+            });
+        }
+        if orig.missing_title && !new_issue.missing_title {
+            field_matches.push(FieldMatch {
+                field: "Title",
+                old_display: "Missing".into(),
+                new_value: get_tag(&tag_map, &["TITLE"]).unwrap_or_default(),
+                category: "critical",
+            });
+        }
+        if orig.blank_title && !new_issue.blank_title {
+            field_matches.push(FieldMatch {
+                field: "Title",
+                old_display: "(blank)".into(),
+                new_value: get_tag(&tag_map, &["TITLE"]).unwrap_or_default(),
+                category: "critical",
+            });
+        }
+        if orig.missing_year && !new_issue.missing_year {
+            field_matches.push(FieldMatch {
+                field: "Year",
+                old_display: "Missing".into(),
+                new_value: get_tag(&tag_map, &["YEAR"]).unwrap_or_default(),
+                category: "critical",
+            });
+        }
+        if orig.blank_year && !new_issue.blank_year {
+            field_matches.push(FieldMatch {
+                field: "Year",
+                old_display: "(blank)".into(),
+                new_value: get_tag(&tag_map, &["YEAR"]).unwrap_or_default(),
+                category: "critical",
+            });
+        }
+        if orig.invalid_year.is_some() && new_issue.invalid_year.is_none() {
+            field_matches.push(FieldMatch {
+                field: "Year",
+                old_display: format!("({})", orig.invalid_year.as_ref().unwrap()),
+                new_value: get_tag(&tag_map, &["YEAR"]).unwrap_or_default(),
+                category: "critical",
+            });
+        }
+
+        // --- MusicBrainz fields ---
+        if orig.missing_mb_artist_id && !new_issue.missing_mb_artist_id {
+            field_matches.push(FieldMatch {
+                field: "MB Artist ID",
+                old_display: "Missing".into(),
+                new_value: get_tag(&tag_map, MB_ARTIST_ID_TAGS).unwrap_or_default(),
+                category: "mb",
+            });
+        }
+        if orig.missing_mb_track_id && !new_issue.missing_mb_track_id {
+            field_matches.push(FieldMatch {
+                field: "MB Track ID",
+                old_display: "Missing".into(),
+                new_value: get_tag(&tag_map, MB_TRACK_ID_TAGS).unwrap_or_default(),
+                category: "mb",
+            });
+        }
+        if orig.missing_mb_album_id && !new_issue.missing_mb_album_id {
+            field_matches.push(FieldMatch {
+                field: "MB Album ID",
+                old_display: "Missing".into(),
+                new_value: get_tag(&tag_map, MB_ALBUM_ID_TAGS).unwrap_or_default(),
+                category: "mb",
+            });
+        }
+
+        // --- Discogs fields ---
+        if orig.missing_discogs_artist && !new_issue.missing_discogs_artist {
+            field_matches.push(FieldMatch {
+                field: "Discogs Artist",
+                old_display: "Missing".into(),
+                new_value: get_tag(&tag_map, &["URL_DISCOGS_ARTIST_SITE", "WWW DISCOGS_ARTIST"]).unwrap_or_default(),
+                category: "discogs",
+            });
+        }
+        if orig.missing_discogs_release && !new_issue.missing_discogs_release {
+            field_matches.push(FieldMatch {
+                field: "Discogs Release",
+                old_display: "Missing".into(),
+                new_value: get_tag(&tag_map, &["URL_DISCOGS_RELEASE_SITE", "WWW DISCOGS_RELEASE"]).unwrap_or_default(),
+                category: "discogs",
+            });
+        }
+
+        // --- ID fields ---
+        if orig.missing_acoustic_id && !new_issue.missing_acoustic_id {
+            field_matches.push(FieldMatch {
+                field: "Acoustic ID",
+                old_display: "Missing".into(),
+                new_value: get_tag(&tag_map, ACOUSTID_TAGS).unwrap_or_default(),
+                category: "ids",
+            });
+        }
+        if orig.missing_songkong_id && !new_issue.missing_songkong_id {
+            field_matches.push(FieldMatch {
+                field: "SongKong ID",
+                old_display: "Missing".into(),
+                new_value: get_tag(&tag_map, &["SONGKONG_ID", "SONGKONGID"]).unwrap_or_default(),
+                category: "ids",
+            });
+        }
+        if orig.missing_bandcamp && !new_issue.missing_bandcamp {
+            field_matches.push(FieldMatch {
+                field: "Bandcamp",
+                old_display: "Missing".into(),
+                new_value: get_tag(&tag_map, &["URL_BANDCAMP_ARTIST_SITE", "WWW BANDCAMP_ARTIST"]).unwrap_or_default(),
+                category: "ids",
+            });
+        }
+        if orig.missing_wikipedia_artist && !new_issue.missing_wikipedia_artist {
+            field_matches.push(FieldMatch {
+                field: "Wikipedia Artist",
+                old_display: "Missing".into(),
+                new_value: get_tag(&tag_map, &["WWW WIKIPEDIA_ARTIST"]).unwrap_or_default(),
+                category: "ids",
+            });
+        }
+
+        // --- Other fields ---
+        if orig.missing_genre && !new_issue.missing_genre {
+            field_matches.push(FieldMatch {
+                field: "Genre",
+                old_display: "Missing".into(),
+                new_value: get_tag(&tag_map, &["GENRE"]).unwrap_or_default(),
+                category: "other",
+            });
+        }
+        if orig.blank_genre && !new_issue.blank_genre {
+            field_matches.push(FieldMatch {
+                field: "Genre",
+                old_display: "(blank)".into(),
+                new_value: get_tag(&tag_map, &["GENRE"]).unwrap_or_default(),
+                category: "other",
+            });
+        }
+        if orig.missing_bpm && !new_issue.missing_bpm {
+            field_matches.push(FieldMatch {
+                field: "BPM",
+                old_display: "Missing".into(),
+                new_value: get_tag(&tag_map, &["BPM", "INTEGERBPM"]).unwrap_or_default(),
+                category: "other",
+            });
+        }
+        if orig.missing_mood && !new_issue.missing_mood {
+            field_matches.push(FieldMatch {
+                field: "Mood",
+                old_display: "Missing".into(),
+                new_value: "Present".into(),
+                category: "other",
+            });
+        }
+        if orig.missing_album_art && !new_issue.missing_album_art {
+            field_matches.push(FieldMatch {
+                field: "Album Art",
+                old_display: "Missing".into(),
+                new_value: "Embedded".into(),
+                category: "other",
+            });
+        }
+
+        if !field_matches.is_empty() {
+            diffs.insert(orig.path.clone(), field_matches);
+        }
+
+        if new_issue.has_any_issue() {
+            still_broken.push(new_issue);
+        } else {
+            matched.push(orig.path.clone());
+        }
+    }
+
+    (matched, still_broken, unreadable, diffs, skipped_files)
+}
+
+// ---------------------------------------------------------------------------
+// Beets export (--beets-export)
+// ---------------------------------------------------------------------------
+
+/// The files of one report category, for running beets by hand.
+struct BeetsExport {
+    category: &'static str,
+    files: Vec<PathBuf>,
+    /// Parent folders of `files`, what `beet import` takes
+    dirs: Vec<PathBuf>,
+}
+
+/// One export per category with at least one affected file, in report order
+/// (`--rules` categories last).
+fn beets_exports(issues: &[FileIssue]) -> Vec<BeetsExport> {
+    CATEGORY_PAGES.iter()
+        .map(|category| category.id)
+        .chain(rules().categories.iter().map(|category| category.id.as_str()))
+        .filter_map(|id| {
+            let mut files: Vec<PathBuf> = issues
+                .iter()
+                .filter(|i| i.in_category(id))
+                .map(|i| i.path.clone())
+                .collect();
+            if files.is_empty() {
+                return None;
+            }
+            files.sort();
+            let dirs: BTreeSet<PathBuf> = files.iter().filter_map(|f| f.parent()).map(Path::to_path_buf).collect();
+            Some(BeetsExport { category: id, files, dirs: dirs.into_iter().collect() })
+        })
+        .collect()
+}
+
+/// Write `<category>.txt` (one file per line), `<category>.dirs.txt` (one folder
+/// per line) and `<category>.query` (one beets `path:` query per folder) into `dir`.
+fn write_beets_exports(dir: &Path, exports: &[BeetsExport]) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let lines = |paths: &[PathBuf], prefix: &str| {
+        paths.iter().map(|p| format!("{}{}\n", prefix, p.display())).collect::<String>()
+    };
+    for export in exports {
+        fs::write(dir.join(format!("{}.txt", export.category)), lines(&export.files, ""))?;
+        fs::write(dir.join(format!("{}.dirs.txt", export.category)), lines(&export.dirs, ""))?;
+        fs::write(dir.join(format!("{}.query", export.category)), lines(&export.dirs, "path:"))?;
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Quarantine helpers
+// ---------------------------------------------------------------------------
+
+/// Moves the files in `staging_dir` back under `scan_root`. `select` gets
+/// each file's staged and original path and says whether to restore it.
+fn restore_dir(
+    staging_dir: &Path,
+    scan_root: &str,
+    select: &dyn Fn(&Path, &Path) -> bool,
+    moved: &mut u32,
+    failed: &mut u32,
+) {
+    if !staging_dir.exists() {
+        return;
+    }
+
+    println!("Moving files from {} back to original locations...", staging_dir.display());
+
+    let manifest_path = QuarantineManifest::path(scan_root);
+    for entry in WalkDir::new(staging_dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && e.path() != manifest_path)
+    {
+        let src = entry.path();
+        let rel = match src.strip_prefix(staging_dir) {
+            Ok(r) => r,
+            Err(_) => continue,
+        };
+        let dst = PathBuf::from(scan_root).join(rel);
+        if !select(src, &dst) {
+            continue;
+        }
+
+        if let Some(dst_parent) = dst.parent() {
+            if let Err(e) = fs::create_dir_all(dst_parent) {
+                eprintln!("  FAILED to create {}: {}", dst_parent.display(), e);
+                *failed += 1;
+                continue;
+            }
+        }
+
+        match fs::rename(src, &dst) {
+            Ok(_) => {
+                println!("  Restored: {} -> {}", src.display(), dst.display());
+                *moved += 1;
+            }
+            Err(e) => {
+                eprintln!("  FAILED to move {}: {}", src.display(), e);
+                *failed += 1;
+            }
+        }
+    }
+
+    remove_empty_dirs(staging_dir);
+    let _ = fs::remove_dir(staging_dir);
+}
+
+/// Restores staged files: all of them, or with `--only` / `--from` / `--to`
+/// and `--category` the ones from matching artist folders moved for that
+/// category (per the manifest).
+fn end_quarantine(scan_root: &str, filter: &FolderFilter, category: Option<&str>) {
+    let quarantine_dir    = PathBuf::from(scan_root).join("__QUARANTINE");
+    let needs_review_dir  = PathBuf::from(scan_root).join("__NEEDS_REVIEW");
+    let unreadable_dir    = PathBuf::from(scan_root).join("__UNREADABLE");
+    let autofixed_dir     = PathBuf::from(scan_root).join("__AUTOFIXED");
+    let duplicates_dir    = PathBuf::from(scan_root).join("__DUPLICATES");
+
+    if !quarantine_dir.exists() && !needs_review_dir.exists()
+        && !unreadable_dir.exists() && !autofixed_dir.exists()
+        && !duplicates_dir.exists()
+    {
+        println!("Nothing to do: no staging folders found.");
+        return;
+    }
+
+    let selective = !filter.is_empty() || category.is_some();
+    let manifest_path = QuarantineManifest::path(scan_root);
+    let mut manifest = match QuarantineManifest::load(&manifest_path) {
+        Ok(manifest) => manifest,
+        // Rewriting it after a partial restore would lose the other entries
+        Err(e) if selective => {
+            eprintln!("Failed to read {}: {}", manifest_path.display(), e);
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Ignoring unreadable {}: {}", manifest_path.display(), e);
+            QuarantineManifest::default()
+        }
+    };
+    if let Some(category) = category {
+        println!("Restoring files moved for: {}", category);
+    }
+
+    // Files staged before manifests were written can't be matched by category
+    let unlisted = std::cell::Cell::new(0u32);
+    let select = |src: &Path, dst: &Path| {
+        filter.matches(&get_artist_folder(dst, scan_root))
+            && category.is_none_or(|category| match manifest.entry(src) {
+                Some(entry) => entry.categories.iter().any(|c| c == category),
+                None => {
+                    unlisted.set(unlisted.get() + 1);
+                    false
+                }
+            })
+    };
+
+    let mut moved = 0u32;
+    let mut failed = 0u32;
+
+    restore_dir(&quarantine_dir,   scan_root, &select, &mut moved, &mut failed);
+    restore_dir(&needs_review_dir, scan_root, &select, &mut moved, &mut failed);
+    restore_dir(&unreadable_dir,   scan_root, &select, &mut moved, &mut failed);
+    restore_dir(&autofixed_dir,    scan_root, &select, &mut moved, &mut failed);
+    restore_dir(&duplicates_dir,   scan_root, &select, &mut moved, &mut failed);
+
+    // Restored files leave the manifest; without any left it is removed
+    manifest.entries.retain(|e| e.destination.exists());
+    if let Err(e) = manifest.save(&manifest_path) {
+        eprintln!("Failed to update {}: {}", manifest_path.display(), e);
+    }
+    remove_empty_dirs(&quarantine_dir);
+    let _ = fs::remove_dir(&quarantine_dir);
+
+    println!("Done. Restored: {}, Failed: {}", moved, failed);
+    if unlisted.get() > 0 {
+        println!("Skipped {} file(s) without a manifest entry (staged before manifests were written)", unlisted.get());
+    }
+    if selective {
+        println!("Still staged: {} file(s) in the manifest", manifest.entries.len());
+    }
+}
+
+/// Recursively remove empty directories (deepest first).
+fn remove_empty_dirs(dir: &Path) {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if path.is_dir() {
+                remove_empty_dirs(&path);
+                let _ = fs::remove_dir(&path); // silently fails if not empty
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Quarantine manifest (__QUARANTINE/manifest.json)
+// ---------------------------------------------------------------------------
+
+/// One move into a staging folder.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct QuarantineEntry {
+    original: PathBuf,
+    destination: PathBuf,
+    /// Staging folder, e.g. `__NEEDS_REVIEW`
+    staging: String,
+    /// What `--end-quarantine --category` matches: category page ids
+    /// (`critical`, `mb`, ..., rules file categories), `unreadable`,
+    /// `duplicate` or `autofixed`
+    categories: Vec<String>,
+    /// The issue fields, the read error, or the copy that was kept
+    reason: String,
+    /// RFC 3339, local time
+    moved_at: String,
+}
+
+/// Every file currently in a staging folder that a `--quarantine` or
+/// `--quarantine-duplicates` run moved there, across runs. It lives in
+/// `__QUARANTINE` whatever the staging folder.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct QuarantineManifest {
+    entries: Vec<QuarantineEntry>,
+}
+
+impl QuarantineManifest {
+    fn path(scan_root: &str) -> PathBuf {
+        PathBuf::from(scan_root).join("__QUARANTINE").join("manifest.json")
+    }
+
+    /// The manifest in `path`, empty when there is none yet.
+    fn load(path: &Path) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text).map_err(|e| e.to_string()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Writes the manifest, or removes it once it has no entries left.
+    fn save(&self, path: &Path) -> std::io::Result<()> {
+        if self.entries.is_empty() {
+            return match fs::remove_file(path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            };
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&tmp, path)
+    }
+
+    fn entry(&self, destination: &Path) -> Option<&QuarantineEntry> {
+        self.entries.iter().find(|e| e.destination == destination)
+    }
+
+    /// Adds a move, replacing an older entry for the same staged path.
+    fn record(&mut self, entry: QuarantineEntry) {
+        self.entries.retain(|e| e.destination != entry.destination);
+        self.entries.push(entry);
+    }
+}
+
+/// Manifest categories and reason of a file moved for its tag issues.
+fn issue_reason(issue: &FileIssue) -> (Vec<String>, String) {
+    let categories = CATEGORY_PAGES.iter()
+        .filter(|c| (c.has_issue)(issue))
+        .map(|c| c.id.to_string())
+        .chain(rules().categories.iter().filter(|c| c.has_issue(issue)).map(|c| c.id.clone()))
+        .collect();
+    (categories, issue_labels(issue).join(", "))
+}
+
+// ---------------------------------------------------------------------------
+// Protected folders (--protect)
+// ---------------------------------------------------------------------------
+
+/// Folders that quarantine and autofix leave alone even when their files
+/// have issues (e.g. rare rips waiting to be tagged by hand).
+struct Protected {
+    /// (pattern as given, lowercased pattern)
+    patterns: Vec<(String, String)>,
+}
+
+impl Protected {
+    fn new(patterns: &[String]) -> Self {
+        let patterns = patterns.iter()
+            .map(|p| p.trim().trim_matches('/'))
+            .filter(|p| !p.is_empty())
+            .map(|p| (p.to_string(), p.to_lowercase()))
+            .collect();
+        Protected { patterns }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// The first pattern matching a folder that contains `path`.
+    fn matching(&self, path: &Path, scan_root: &str) -> Option<&str> {
+        let rel = path.strip_prefix(scan_root).unwrap_or(path).parent()?;
+        let rel = rel.to_string_lossy().to_lowercase();
+        let folders: Vec<&str> = rel.match_indices('/').map(|(i, _)| &rel[..i])
+            .chain(std::iter::once(rel.as_str()))
+            .filter(|f| !f.is_empty())
+            .collect();
+        self.patterns.iter()
+            .find(|(_, pattern)| folders.iter().any(|f| glob_match(pattern, f)))
+            .map(|(original, _)| original.as_str())
+    }
+
+    fn covers(&self, path: &Path, scan_root: &str) -> bool {
+        !self.is_empty() && self.matching(path, scan_root).is_some()
+    }
+
+    /// Files with issues held back by each pattern, in the order given.
+    fn counts(&self, issues: &[FileIssue], scan_root: &str) -> Vec<(String, usize)> {
+        let mut counts: Vec<(String, usize)> = self.patterns.iter().map(|(p, _)| (p.clone(), 0)).collect();
+        for issue in issues {
+            if let Some(pattern) = self.matching(&issue.path, scan_root) {
+                if let Some(entry) = counts.iter_mut().find(|(p, _)| p == pattern) {
+                    entry.1 += 1;
+                }
+            }
+        }
+        counts
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Excluded paths (--exclude, .analysisignore)
+// ---------------------------------------------------------------------------
+
+/// File in the scan root listing globs the scan skips, one per line.
+const IGNORE_FILE: &str = ".analysisignore";
+
+/// Excluded paths of the run, set once before the scan.
+static EXCLUDED: OnceLock<Excluded> = OnceLock::new();
+
+fn excluded() -> Option<&'static Excluded> {
+    EXCLUDED.get().filter(|e| !e.is_empty())
+}
+
+/// Folders and files the walk leaves out (e.g. `__QUARANTINE`, `_incoming`),
+/// so staging folders aren't scanned again on the next run.
+struct Excluded {
+    /// (pattern as given, lowercased pattern)
+    patterns: Vec<(String, String)>,
+}
+
+impl Excluded {
+    fn new(patterns: &[String]) -> Self {
+        let patterns = patterns.iter()
+            .map(|p| p.trim().trim_matches('/'))
+            .filter(|p| !p.is_empty() && !p.starts_with('#'))
+            .map(|p| (p.to_string(), p.to_lowercase()))
+            .collect();
+        Excluded { patterns }
+    }
+
+    /// The `--exclude` patterns, then those of the scan root's `.analysisignore`
+    /// (blank lines and `#` comments skipped), if there is one.
+    fn load(patterns: &[String], scan_root: &str) -> Result<Self, String> {
+        let mut patterns = patterns.to_vec();
+        let file = Path::new(scan_root).join(IGNORE_FILE);
+        match fs::read_to_string(&file) {
+            Ok(text) => patterns.extend(text.lines().map(String::from)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("{}: {}", file.display(), e)),
+        }
+        Ok(Excluded::new(&patterns))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Whether `path` (a folder or file below the scan root) or a folder it is
+    /// in matches a pattern.
+    fn matches(&self, path: &Path, scan_root: &str) -> bool {
+        let rel = relative_path(path, scan_root).to_lowercase();
+        if rel.is_empty() {
+            return false;
+        }
+        let mut prefixes = rel.match_indices('/').map(|(i, _)| &rel[..i])
+            .chain(std::iter::once(rel.as_str()));
+        prefixes.any(|p| self.patterns.iter().any(|(_, pattern)| glob_match(pattern, p)))
+    }
+}
+
+/// Shell-style glob over a relative folder path: `*` and `?` stay within one
+/// path segment, `**` spans any number of segments.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    fn go(p: &[char], t: &[char]) -> bool {
+        match p {
+            [] => t.is_empty(),
+            ['*', '*', rest @ ..] => {
+                // "**/" also matches no folder at all
+                let after_slash = rest.strip_prefix(&['/']).unwrap_or(rest);
+                go(after_slash, t) || (0..=t.len()).any(|i| go(rest, &t[i..]))
+            }
+            ['*', rest @ ..] => (0..=t.len())
+                .take_while(|&i| i == 0 || t[i - 1] != '/')
+                .any(|i| go(rest, &t[i..])),
+            ['?', rest @ ..] => matches!(t.first(), Some(&c) if c != '/') && go(rest, &t[1..]),
+            [c, rest @ ..] => t.first() == Some(c) && go(rest, &t[1..]),
+        }
+    }
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    go(&p, &t)
+}
+
+// ---------------------------------------------------------------------------
+// Mirror comparison (--mirror)
+// ---------------------------------------------------------------------------
+
+/// Tags compared between a master file and its mirror copy, as (label, keys).
+const MIRROR_FIELDS: &[(&str, &[&str])] = &[
+    ("Artist", &["ARTIST"]),
+    ("Album Artist", &["ALBUMARTIST"]),
+    ("Album", &["ALBUMTITLE", "ALBUM"]),
+    ("Title", &["TITLE"]),
+    ("Year", &["YEAR"]),
+    ("Track", &["TRACKNUMBER"]),
+    ("Disc", &["DISCNUMBER"]),
+    ("Genre", &["GENRE"]),
+];
+
+/// (field, master value, mirror value) for each tag that differs.
+type FieldDiffs = Vec<(&'static str, String, String)>;
+
+/// Result of comparing the scan root against a mirror root. Paths are
+/// relative to their own root.
+#[derive(Debug, Default)]
+struct MirrorReport {
+    mirror_root: String,
+    /// Files found under the same relative path (extension ignored)
+    common: usize,
+    master_only: Vec<String>,
+    mirror_only: Vec<String>,
+    /// (master path, mirror path): same content hash, different location
+    moved: Vec<(String, String)>,
+    /// (relative path, differing tags)
+    diverged: Vec<(String, FieldDiffs)>,
+}
+
+impl MirrorReport {
+    fn difference_count(&self) -> usize {
+        self.master_only.len() + self.mirror_only.len() + self.moved.len() + self.diverged.len()
+    }
+}
+
+/// Relative path without the extension, so `01 Airbag.flac` in the master
+/// pairs with `01 Airbag.mp3` in a lossy mirror.
+fn mirror_key(rel: &str) -> String {
+    match Path::new(rel).extension() {
+        Some(ext) => rel[..rel.len() - ext.len() - 1].to_string(),
+        None => rel.to_string(),
+    }
+}
+
+fn read_tags(path: &Path) -> Option<HashMap<String, String>> {
+    let parse_opts = ParseOptions::new().read_properties(false);
+    let tagged_file = read_tagged_file(path, parse_opts).ok()?;
+    Some(collect_tags(&tagged_file))
+}
+
+/// Normalised value of a compared field: trimmed, and for track/disc numbers
+/// just the number (`"01"`, `"1/12"` and `"1"` are the same track).
+fn mirror_field(tags: &HashMap<String, String>, label: &str, keys: &[&str]) -> String {
+    let value = get_tag(tags, keys).unwrap_or_default();
+    let value = value.trim();
+    if label == "Track" || label == "Disc" {
+        let number = value.split('/').next().unwrap_or("").trim().trim_start_matches('0');
+        return number.to_string();
+    }
+    value.to_string()
+}
+
+/// Codec-independent content hash: the identifying tags (artist, album,
+/// title, track, disc), lowercased. A transcode keeps its tags, so this
+/// matches where a byte hash wouldn't. `None` for untagged files.
+fn mirror_content_hash(tags: &HashMap<String, String>) -> Option<u64> {
+    use std::hash::{Hash, Hasher};
+    let fields: Vec<String> = MIRROR_FIELDS.iter()
+        .filter(|(label, _)| matches!(*label, "Artist" | "Album" | "Title" | "Track" | "Disc"))
+        .map(|(label, keys)| mirror_field(tags, label, keys).to_lowercase())
+        .collect();
+    if fields.iter().all(|f| f.is_empty()) {
+        return None;
+    }
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    fields.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+/// Compare two sets of audio files. Files pair up by relative path (minus
+/// extension); unpaired files on both sides are then matched by content hash
+/// to tell moved/renamed files apart from genuinely missing ones.
+fn compare_mirror(
+    master_root: &str,
+    master_paths: &[PathBuf],
+    mirror_root: &str,
+    mirror_paths: &[PathBuf],
+) -> MirrorReport {
+    // Several files can share a key (`x.flac` and `x.mp3` side by side)
+    let index = |root: &str, paths: &[PathBuf]| -> BTreeMap<String, Vec<(String, PathBuf)>> {
+        let mut index: BTreeMap<String, Vec<(String, PathBuf)>> = BTreeMap::new();
+        for p in paths {
+            let rel = relative_path(p, root);
+            index.entry(mirror_key(&rel)).or_default().push((rel, p.clone()));
+        }
+        for files in index.values_mut() {
+            files.sort();
+        }
+        index
+    };
+    let master = index(master_root, master_paths);
+    let mirror = index(mirror_root, mirror_paths);
+
+    // Per key: the same relative path pairs first, then a lone file on each
+    // side pairs across extensions; the rest is left for the content hash
+    let mut pairs: Vec<(String, PathBuf, PathBuf)> = Vec::new();
+    let mut master_unpaired: Vec<(String, PathBuf)> = Vec::new();
+    let mut mirror_unpaired: Vec<(String, PathBuf)> = Vec::new();
+    let keys: BTreeSet<&String> = master.keys().chain(mirror.keys()).collect();
+    for key in keys {
+        let mut master_files = master.get(key).cloned().unwrap_or_default();
+        let mut mirror_files = mirror.get(key).cloned().unwrap_or_default();
+        master_files.retain(|(rel, p)| match mirror_files.iter().position(|(m, _)| m == rel) {
+            Some(i) => {
+                pairs.push((rel.clone(), p.clone(), mirror_files.remove(i).1));
+                false
+            }
+            None => true,
+        });
+        if let ([(rel, p)], [(_, m)]) = (master_files.as_slice(), mirror_files.as_slice()) {
+            pairs.push((rel.clone(), p.clone(), m.clone()));
+            continue;
+        }
+        master_unpaired.extend(master_files);
+        mirror_unpaired.extend(mirror_files);
+    }
+
+    let mut report = MirrorReport { mirror_root: mirror_root.to_string(), ..Default::default() };
+
+    // Pairs: compare tags in parallel
+    report.common = pairs.len();
+    let mut diverged: Vec<(String, FieldDiffs)> = pairs
+        .par_iter()
+        .filter_map(|(rel, master_path, mirror_path)| {
+            let a = read_tags(master_path)?;
+            let b = read_tags(mirror_path)?;
+            let fields: FieldDiffs = MIRROR_FIELDS.iter()
+                .filter_map(|(label, keys)| {
+                    let va = mirror_field(&a, label, keys);
+                    let vb = mirror_field(&b, label, keys);
+                    (va != vb).then_some((*label, va, vb))
+                })
+                .collect();
+            (!fields.is_empty()).then(|| (rel.clone(), fields))
+        })
+        .collect();
+    diverged.sort_by(|a, b| a.0.cmp(&b.0));
+    report.diverged = diverged;
+
+    // Unpaired files: match the leftovers by content hash
+    let hashed = |side: Vec<(String, PathBuf)>| {
+        side.into_par_iter()
+            .map(|(rel, p)| {
+                let hash = read_tags(&p).and_then(|t| mirror_content_hash(&t));
+                (rel, hash)
+            })
+            .collect::<Vec<_>>()
+    };
+    let master_left = hashed(master_unpaired);
+    let mirror_left = hashed(mirror_unpaired);
+
+    let mut by_hash: HashMap<u64, Vec<&str>> = HashMap::new();
+    for (rel, hash) in mirror_left.iter().rev() {
+        if let Some(h) = hash {
+            by_hash.entry(*h).or_default().push(rel);
+        }
+    }
+    let mut claimed: HashSet<&str> = HashSet::new();
+    for (rel, hash) in &master_left {
+        match hash.and_then(|h| by_hash.get_mut(&h)).and_then(|v| v.pop()) {
+            Some(copy) => {
+                claimed.insert(copy);
+                report.moved.push((rel.clone(), copy.to_string()));
+            }
+            None => report.master_only.push(rel.clone()),
+        }
+    }
+    report.mirror_only = mirror_left.iter()
+        .filter(|(rel, _)| !claimed.contains(rel.as_str()))
+        .map(|(rel, _)| rel.clone())
+        .collect();
+
+    report
+}
+
+// ---------------------------------------------------------------------------
+// Archives (--archives)
+// ---------------------------------------------------------------------------
+
+/// Audio entries bigger than this (uncompressed) are reported instead of
+/// being read into memory.
+const MAX_ARCHIVE_ENTRY: u64 = 256 * 1024 * 1024;
+
+/// Archives read at once: each holds up to `MAX_ARCHIVE_ENTRY` in memory.
+const ARCHIVE_CONCURRENCY: usize = 4;
+
+/// Tag check of one `.zip` (e.g. a Bandcamp purchase) without extracting it.
+/// Issue paths are `<archive path>/<entry name>`.
+#[derive(Debug, Default)]
+struct ArchiveScan {
+    /// Path relative to the `--archives` folder
+    name: String,
+    audio_files: usize,
+    /// Entries with at least one issue
+    issues: Vec<FileIssue>,
+    /// (entry name, error)
+    unreadable: Vec<(String, String)>,
+    /// Why the archive itself couldn't be read
+    error: Option<String>,
+}
+
+impl ArchiveScan {
+    fn is_clean(&self) -> bool {
+        self.error.is_none() && self.issues.is_empty() && self.unreadable.is_empty()
+    }
+
+    /// "Year (12), MB Album (12)": entries per report panel, in report order.
+    fn summary(&self) -> String {
+        let mut parts: Vec<String> = CATEGORY_PAGES.iter()
+            .flat_map(|c| c.panels)
+            .filter_map(|panel| {
+                let n = self.issues.iter().filter(|i| (panel.predicate)(i)).count();
+                (n > 0).then(|| format!("{} ({})", panel.label, n))
+            })
+            .collect();
+        for field in rules().categories.iter().flat_map(|c| &c.fields) {
+            let n = self.issues.iter().filter(|i| i.custom_missing.contains(&field.id)).count();
+            if n > 0 {
+                parts.push(format!("{} ({})", field.label, n));
+            }
+        }
+        if !self.unreadable.is_empty() {
+            parts.push(format!("Unreadable ({})", self.unreadable.len()));
+        }
+        if let Some(ref error) = self.error {
+            parts.push(format!("Not a readable zip: {}", error));
+        }
+        parts.join(", ")
+    }
+}
+
+/// The archives found in an `--archives` folder.
+#[derive(Debug, Default)]
+struct ArchiveReport {
+    dir: String,
+    archives: Vec<ArchiveScan>,
+}
+
+impl ArchiveReport {
+    fn flagged_count(&self) -> usize {
+        self.archives.iter().filter(|a| !a.is_clean()).count()
+    }
+}
+
+/// Labels of the report panels (Year, MB Album, ...) a file shows up in.
+fn issue_labels(issue: &FileIssue) -> Vec<&'static str> {
+    CATEGORY_PAGES.iter()
+        .flat_map(|c| c.panels)
+        .filter(|panel| (panel.predicate)(issue))
+        .map(|panel| panel.label)
+        .chain(issue.custom_missing.iter().filter_map(|id| rules().custom_label(id)))
+        .collect()
+}
+
+fn find_archives(dir: &str) -> Vec<PathBuf> {
+    let mut archives: Vec<PathBuf> = WalkDir::new(dir)
+        .follow_links(true)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && e.path().extension().is_some_and(|ext| ext.eq_ignore_ascii_case("zip")))
+        .map(|e| e.into_path())
+        .collect();
+    archives.sort();
+    archives
+}
+
+/// Read the tags of every audio entry of `path` in memory and run the same
+/// checks as for files on disk.
+fn scan_archive(path: &Path, dir: &str) -> ArchiveScan {
+    let mut scan = ArchiveScan { name: relative_path(path, dir), ..Default::default() };
+
+    let _permit = IO_THROTTLE.get().map(|t| t.open_file());
+    let archive = fs::File::open(path)
+        .map_err(|e| e.to_string())
+        .and_then(|file| {
+            zip::ZipArchive::new(std::io::BufReader::new(ThrottledFile(file))).map_err(|e| e.to_string())
+        });
+    let mut archive = match archive {
+        Ok(archive) => archive,
+        Err(e) => {
+            scan.error = Some(e);
+            return scan;
+        }
+    };
+
+    let parse_opts = ParseOptions::new().read_properties(false);
+    for index in 0..archive.len() {
+        let mut entry = match archive.by_index(index) {
+            Ok(entry) => entry,
+            Err(e) => {
+                scan.unreadable.push((format!("entry #{}", index + 1), e.to_string()));
+                continue;
+            }
+        };
+        let name = entry.name().to_string();
+        if entry.is_dir() || !is_audio_file(Path::new(&name)) {
+            continue;
+        }
+        scan.audio_files += 1;
+
+        let size = entry.size();
+        if size > MAX_ARCHIVE_ENTRY {
+            scan.unreadable.push((name, format!("{} uncompressed, not read", human_size(size))));
+            continue;
+        }
+        // The header's size isn't trusted: grow as read, and stop past the cap
+        let mut data = Vec::new();
+        if let Err(e) = (&mut entry).take(MAX_ARCHIVE_ENTRY + 1).read_to_end(&mut data) {
+            scan.unreadable.push((name, e.to_string()));
+            continue;
+        }
+        if data.len() as u64 > MAX_ARCHIVE_ENTRY {
+            scan.unreadable.push((name, format!("over {} uncompressed, not read", human_size(MAX_ARCHIVE_ENTRY))));
+            continue;
+        }
+
+        let mut probe = Probe::new(std::io::Cursor::new(data));
+        if let Some(file_type) = FileType::from_path(&name) {
+            probe = probe.set_file_type(file_type);
+        }
+        match probe.options(parse_opts).read() {
+            Ok(tagged_file) => {
+                let (issue, _) = check_tags(&path.join(name.trim_start_matches('/')), size, &tagged_file, rules());
+                if issue.has_any_issue() {
+                    scan.issues.push(issue);
+                }
+            }
+            Err(e) => scan.unreadable.push((name, e.to_string())),
+        }
+    }
+    scan
+}
+
+// ---------------------------------------------------------------------------
+// Acoustic duplicates (--dupe-by-fingerprint)
+// ---------------------------------------------------------------------------
+
+/// Tags holding a Chromaprint fingerprint (beets `chroma`, Picard "Generate
+/// AcoustID Fingerprints"): Vorbis/APE, ID3v2 TXXX, MP4 freeform.
+const FINGERPRINT_TAGS: &[&str] = &[
+    "ACOUSTID_FINGERPRINT",
+    "ACOUSTID FINGERPRINT",
+    "----:COM.APPLE.ITUNES:ACOUSTID FINGERPRINT",
+];
+
+/// Items (~8 per second of audio) per fingerprint that go into the candidate index.
+const FINGERPRINT_INDEX_ITEMS: usize = 120;
+/// Index entries shared by more files than this (silence, noise) are ignored.
+const FINGERPRINT_BUCKET_LIMIT: usize = 64;
+/// Shared index entries a pair needs before its fingerprints are compared.
+const FINGERPRINT_MIN_SHARED: usize = 3;
+/// Alignment search when comparing two fingerprints, in items either way
+/// (covers a second or two of extra leading silence).
+const FINGERPRINT_MAX_SHIFT: usize = 16;
+/// Overlap under which an alignment is not scored.
+const FINGERPRINT_MIN_OVERLAP: usize = 40;
+/// Copies whose lengths differ by more than this are different edits.
+const DUPE_LENGTH_TOLERANCE: Duration = Duration::from_secs(5);
+
+/// One copy of a recording, and what it takes to pick the copy to keep.
+#[derive(Debug, Clone)]
+struct AudioCopy {
+    path: PathBuf,
+    duration: Option<Duration>,
+    /// kbps
+    bitrate: Option<u32>,
+    file_size: u64,
+    /// Lossless formats report a bit depth (FLAC, WAV, AIFF, APE, WavPack, ALAC)
+    lossless: bool,
+}
+
+impl AudioCopy {
+    fn new(path: &Path, tagged_file: &lofty::file::TaggedFile) -> Self {
+        let properties = tagged_file.properties();
+        AudioCopy {
+            path: path.to_path_buf(),
+            duration: Some(properties.duration()).filter(|d| !d.is_zero()),
+            bitrate: properties.audio_bitrate(),
+            file_size: fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+            lossless: properties.bit_depth().is_some(),
+        }
+    }
+
+    /// Copy to keep first: lossless, then highest bitrate, then largest.
+    fn best_first(a: &AudioCopy, b: &AudioCopy) -> std::cmp::Ordering {
+        b.lossless.cmp(&a.lossless)
+            .then(b.bitrate.cmp(&a.bitrate))
+            .then(b.file_size.cmp(&a.file_size))
+            .then(a.path.cmp(&b.path))
+    }
+
+    fn lengths_agree(&self, other: &AudioCopy) -> bool {
+        match (self.duration, other.duration) {
+            (Some(x), Some(y)) => x.abs_diff(y) <= DUPE_LENGTH_TOLERANCE,
+            _ => true,
+        }
+    }
+
+    /// Format, Bitrate, Length and Size columns of the duplicate tables.
+    fn cells(&self) -> [String; 4] {
+        [
+            self.path.extension().map(|e| e.to_string_lossy().to_uppercase()).unwrap_or_default(),
+            self.bitrate.map_or("—".to_string(), |kbps| format!("{} kbps", kbps)),
+            self.duration.map_or("—".to_string(), format_length),
+            human_size(self.file_size),
+        ]
+    }
+}
+
+/// A file with a readable fingerprint tag.
+#[derive(Debug, Clone)]
+struct FingerprintedFile {
+    copy: AudioCopy,
+    fingerprint: Vec<u32>,
+    /// Best match with the cluster's kept copy (1.0 for the copy itself);
+    /// set by `cluster_fingerprints`
+    similarity: f64,
+}
+
+/// Clusters of acoustically identical files, the copy to keep first.
+#[derive(Debug, Default)]
+struct FingerprintReport {
+    threshold: f64,
+    clusters: Vec<Vec<FingerprintedFile>>,
+    fingerprinted: usize,
+    /// Files without a fingerprint tag, or with one that doesn't decode
+    missing: usize,
+}
+
+impl FingerprintReport {
+    /// Copies beyond the kept one in every cluster.
+    fn extra_copies(&self) -> usize {
+        self.clusters.iter().map(|c| c.len() - 1).sum()
+    }
+}
+
+/// Base64url (Chromaprint's alphabet, no padding; standard `+/` and padding
+/// are accepted too).
+fn decode_base64url(s: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(s.len() * 3 / 4);
+    let (mut acc, mut bits) = (0u32, 0u32);
+    for c in s.trim().trim_end_matches('=').bytes() {
+        let v = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'-' | b'+' => 62,
+            b'_' | b'/' => 63,
+            _ => return None,
+        };
+        acc = (acc << 6) | v as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+/// Unpack `count` values of `width` bits, least significant bit first.
+fn unpack_bits(data: &[u8], width: usize, count: usize) -> Option<Vec<u8>> {
+    if data.len() * 8 < width * count {
+        return None;
+    }
+    Some((0..count).map(|i| {
+        (0..width).fold(0u8, |value, b| {
+            let bit = i * width + b;
+            value | ((data[bit / 8] >> (bit % 8)) & 1) << b
+        })
+    }).collect())
+}
+
+/// The raw 32-bit items of a compressed Chromaprint fingerprint (as `fpcalc`
+/// prints it): a 4-byte header (algorithm, item count), then per item the gaps
+/// between set bits of its XOR with the previous item as 3-bit values ending
+/// in 0, then 5-bit overflows for the gaps of 7 or more.
+fn decode_fingerprint(encoded: &str) -> Option<Vec<u32>> {
+    let data = decode_base64url(encoded)?;
+    if data.len() < 4 {
+        return None;
+    }
+    let count = u32::from_be_bytes([0, data[1], data[2], data[3]]) as usize;
+    if count == 0 {
+        return None;
+    }
+    let body = &data[4..];
+
+    // 3-bit values until `count` items have ended
+    let mut normal = Vec::new();
+    let mut ended = 0;
+    let mut bit = 0;
+    while ended < count {
+        if (bit + 3) > body.len() * 8 {
+            return None;
+        }
+        let value = (0..3).fold(0u8, |v, b| v | ((body[(bit + b) / 8] >> ((bit + b) % 8)) & 1) << b);
+        if value == 0 {
+            ended += 1;
+        }
+        normal.push(value);
+        bit += 3;
+    }
+    let exceptions = normal.iter().filter(|&&v| v == 7).count();
+    let overflow = unpack_bits(&body[bit.div_ceil(8)..], 5, exceptions)?;
+    let mut overflow = overflow.into_iter();
+
+    let mut items = Vec::with_capacity(count);
+    let (mut value, mut last_bit, mut previous) = (0u32, 0u32, 0u32);
+    for gap in normal {
+        if gap == 0 {
+            previous ^= value;
+            items.push(previous);
+            value = 0;
+            last_bit = 0;
+            continue;
+        }
+        let gap = if gap == 7 { 7 + overflow.next()? as u32 } else { gap as u32 };
+        last_bit += gap;
+        if last_bit > 32 {
+            return None;
+        }
+        value |= 1 << (last_bit - 1);
+    }
+    Some(items)
+}
+
+/// Share of matching bits (0-1) between two fingerprints at their best
+/// alignment within `FINGERPRINT_MAX_SHIFT` items. Unrelated audio scores
+/// around 0.5; re-encodes of one recording above 0.9.
+fn fingerprint_similarity(a: &[u32], b: &[u32]) -> f64 {
+    let mut best = 0.0f64;
+    for shift in 0..=FINGERPRINT_MAX_SHIFT {
+        for (x, y) in [(a.get(shift..), Some(b)), (Some(a), b.get(shift..))] {
+            let (Some(x), Some(y)) = (x, y) else { continue };
+            let overlap = x.len().min(y.len());
+            if overlap < FINGERPRINT_MIN_OVERLAP {
+                continue;
+            }
+            let differing: u32 = x.iter().zip(y).map(|(p, q)| (p ^ q).count_ones()).sum();
+            best = best.max(1.0 - differing as f64 / (32 * overlap) as f64);
+        }
+    }
+    best
+}
+
+/// Group files whose fingerprints match at `threshold` or more (and whose
+/// lengths agree). Candidates come from an index of each fingerprint's opening
+/// items, top 24 bits, so only likely pairs are compared in full. Each cluster
+/// lists the copy to keep first: lossless, then highest bitrate, then largest.
+fn cluster_fingerprints(mut files: Vec<FingerprintedFile>, threshold: f64) -> Vec<Vec<FingerprintedFile>> {
+    files.sort_by(|a, b| a.copy.path.cmp(&b.copy.path));
+
+    let mut index: HashMap<u32, Vec<usize>> = HashMap::new();
+    for (i, file) in files.iter().enumerate() {
+        let keys: HashSet<u32> = file.fingerprint.iter().take(FINGERPRINT_INDEX_ITEMS).map(|item| item >> 8).collect();
+        for key in keys {
+            index.entry(key).or_default().push(i);
+        }
+    }
+    index.retain(|_, bucket| bucket.len() > 1 && bucket.len() <= FINGERPRINT_BUCKET_LIMIT);
+    let mut buckets_of: Vec<Vec<&[usize]>> = vec![Vec::new(); files.len()];
+    for bucket in index.values() {
+        for &i in bucket {
+            buckets_of[i].push(bucket);
+        }
+    }
+
+    let pairs: Vec<(usize, usize)> = (0..files.len()).into_par_iter()
+        .flat_map_iter(|i| {
+            let mut shared: HashMap<usize, usize> = HashMap::new();
+            for bucket in &buckets_of[i] {
+                for &j in bucket.iter().filter(|&&j| j > i) {
+                    *shared.entry(j).or_default() += 1;
+                }
+            }
+            let files = &files;
+            shared.into_iter()
+                .filter(|&(_, n)| n >= FINGERPRINT_MIN_SHARED)
+                .filter(move |&(j, _)| {
+                    files[i].copy.lengths_agree(&files[j].copy)
+                        && fingerprint_similarity(&files[i].fingerprint, &files[j].fingerprint) >= threshold
+                })
+                .map(move |(j, _)| (i, j))
+        })
+        .collect();
+
+    // Union-find over the matching pairs
+    let mut parent: Vec<usize> = (0..files.len()).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    for (i, j) in pairs {
+        let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+        if a != b {
+            parent[a.max(b)] = a.min(b);
+        }
+    }
+    let mut groups: BTreeMap<usize, Vec<FingerprintedFile>> = BTreeMap::new();
+    for (i, file) in files.into_iter().enumerate() {
+        groups.entry(root(&mut parent, i)).or_default().push(file);
+    }
+
+    groups.into_values()
+        .filter(|group| group.len() > 1)
+        .map(|mut group| {
+            group.sort_by(|a, b| AudioCopy::best_first(&a.copy, &b.copy));
+            let kept = group[0].fingerprint.clone();
+            for file in &mut group {
+                file.similarity = fingerprint_similarity(&kept, &file.fingerprint);
+            }
+            group
+        })
+        .collect()
+}
+
+/// The fingerprint and audio properties of one file; `None` when it has no
+/// fingerprint tag or the tag doesn't decode.
+fn read_fingerprint(path: &Path) -> Option<FingerprintedFile> {
+    let tagged_file = read_tagged_file(path, ParseOptions::new()).ok()?;
+    let fingerprint = decode_fingerprint(&get_tag(&collect_tags(&tagged_file), FINGERPRINT_TAGS)?)?;
+    Some(FingerprintedFile { copy: AudioCopy::new(path, &tagged_file), fingerprint, similarity: 1.0 })
+}
+
+fn find_fingerprint_dupes(paths: &[PathBuf], threshold: f64) -> FingerprintReport {
+    let files: Vec<FingerprintedFile> = paths.par_iter().filter_map(|p| read_fingerprint(p)).collect();
+    let fingerprinted = files.len();
+    FingerprintReport {
+        threshold,
+        clusters: cluster_fingerprints(files, threshold),
+        fingerprinted,
+        missing: paths.len() - fingerprinted,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Track duplicates (--detect-duplicates)
+// ---------------------------------------------------------------------------
+
+/// What two files sharing a normalized artist + title must also share to
+/// count as copies of the same track.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DupeMode {
+    /// Nothing more, as long as their lengths agree
+    Tags,
+    /// The AcoustID tag
+    AcoustId,
+    /// The audio data itself (see `audio_hash`)
+    Audio,
+}
+
+impl DupeMode {
+    fn parse(value: &str) -> Self {
+        match value {
+            "acoustid" => DupeMode::AcoustId,
+            "audio" => DupeMode::Audio,
+            _ => DupeMode::Tags,
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            DupeMode::Tags => "artist + title",
+            DupeMode::AcoustId => "artist + title + AcoustID",
+            DupeMode::Audio => "artist + title + audio hash",
+        }
+    }
+}
+
+/// A file with both an artist and a title tag.
+#[derive(Debug, Clone)]
+struct TrackCopy {
+    copy: AudioCopy,
+    artist: String,
+    title: String,
+    /// Normalized artist + title (see `track_key`)
+    key: String,
+    /// AcoustID or audio hash, depending on the mode; `None` in tags mode or
+    /// when the file has none
+    matched_on: Option<String>,
+}
+
+struct DuplicateReport {
+    mode: DupeMode,
+    /// Best copy first in each group
+    groups: Vec<Vec<TrackCopy>>,
+    /// Files with both an artist and a title tag
+    keyed: usize,
+    /// Files sharing an artist + title left out because they have no AcoustID
+    /// tag / their audio couldn't be hashed
+    unmatched: usize,
+}
+
+impl DuplicateReport {
+    fn extra_copies(&self) -> usize {
+        self.groups.iter().map(|g| g.len() - 1).sum()
+    }
+
+    /// Every copy but the best of each group: what --quarantine-duplicates moves.
+    fn extra_paths(&self) -> Vec<PathBuf> {
+        self.groups.iter().flat_map(|g| g.iter().skip(1)).map(|c| c.copy.path.clone()).collect()
+    }
+}
+
+/// "Radiohead" + "Airbag" and "RADIOHEAD" + "Airbag!" are the same track.
+fn track_key(artist: &str, title: &str) -> Option<String> {
+    let (artist, title) = (consistency_key(artist), consistency_key(title));
+    (!artist.is_empty() && !title.is_empty()).then(|| format!("{}\u{1f}{}", artist, title))
+}
+
+fn read_at<R: Read + Seek>(reader: &mut R, pos: u64, buf: &mut [u8]) -> Option<()> {
+    reader.seek(SeekFrom::Start(pos)).ok()?;
+    reader.read_exact(buf).ok()
+}
+
+/// MD5 of a file's audio, so copies that differ only in their tags match.
+/// FLAC records the MD5 of its decoded audio in STREAMINFO; otherwise the
+/// bytes between a leading ID3v2 tag and trailing APEv2 / ID3v1 tags are
+/// hashed (formats with tags inside the stream, like M4A or Ogg, only match
+/// byte-identical files).
+fn audio_hash(path: &Path) -> Option<String> {
+    let _permit = IO_THROTTLE.get().map(|t| t.open_file());
+    let mut file = std::io::BufReader::new(ThrottledFile(fs::File::open(path).ok()?));
+    let len = file.seek(SeekFrom::End(0)).ok()?;
+
+    let mut start = 0;
+    let mut id3 = [0u8; 10];
+    if read_at(&mut file, 0, &mut id3).is_some() && id3.starts_with(b"ID3") {
+        let size = id3[6..10].iter().fold(0u64, |n, &b| (n << 7) | u64::from(b & 0x7F));
+        start = 10 + size + if id3[5] & 0x10 != 0 { 10 } else { 0 };
+    }
+
+    let mut magic = [0u8; 4];
+    if read_at(&mut file, start, &mut magic).is_some() && &magic == b"fLaC" {
+        // STREAMINFO is always the first block and ends with the MD5
+        let mut streaminfo = [0u8; 38];
+        read_at(&mut file, start + 4, &mut streaminfo)?;
+        let md5 = &streaminfo[22..38];
+        if md5.iter().any(|&b| b != 0) {
+            return Some(format!("pcm:{}", md5.iter().map(|b| format!("{:02x}", b)).collect::<String>()));
+        }
+        // No MD5 recorded by the encoder: hash the frames after the metadata blocks
+        start += 4;
+        loop {
+            let mut block = [0u8; 4];
+            read_at(&mut file, start, &mut block)?;
+            start += 4 + u64::from(u32::from_be_bytes([0, block[1], block[2], block[3]]));
+            if block[0] & 0x80 != 0 { break; }
+        }
+    }
+
+    let mut end = len;
+    let mut id3v1 = [0u8; 3];
+    if end >= start + 128 && read_at(&mut file, end - 128, &mut id3v1).is_some() && &id3v1 == b"TAG" {
+        end -= 128;
+    }
+    let mut ape = [0u8; 32];
+    if end >= start + 32 && read_at(&mut file, end - 32, &mut ape).is_some() && ape.starts_with(b"APETAGEX") {
+        // Size covers the items and the footer; a header flag adds 32 bytes more
+        let size = u64::from(u32::from_le_bytes([ape[12], ape[13], ape[14], ape[15]]));
+        let has_header = ape[23] & 0x80 != 0;
+        end = end.saturating_sub(size + if has_header { 32 } else { 0 }).max(start);
+    }
+    if start >= end {
+        return None;
+    }
+
+    file.seek(SeekFrom::Start(start)).ok()?;
+    let mut hasher = Md5::new();
+    std::io::copy(&mut file.take(end - start), &mut hasher).ok()?;
+    Some(format!("raw:{:x}", hasher.finalize()))
+}
+
+fn read_track_copy(path: &Path, mode: DupeMode, rules: &Rules) -> Option<TrackCopy> {
+    let tagged_file = read_tagged_file(path, ParseOptions::new()).ok()?;
+    let tags = collect_tags(&tagged_file);
+    let artist = get_tag(&tags, &rules.keys("artist", &["ARTIST"]))?;
+    let title = get_tag(&tags, &rules.keys("title", &["TITLE"]))?;
+    let key = track_key(&artist, &title)?;
+    let matched_on = match mode {
+        DupeMode::AcoustId => get_tag(&tags, &rules.keys("acoustic", ACOUSTID_TAGS)),
+        DupeMode::Tags | DupeMode::Audio => None,
+    };
+    Some(TrackCopy { copy: AudioCopy::new(path, &tagged_file), artist, title, key, matched_on })
+}
+
+/// Tags mode: copies of one title whose lengths are more than
+/// `DUPE_LENGTH_TOLERANCE` apart are different edits (radio edit, live take).
+fn split_by_length(mut copies: Vec<TrackCopy>) -> Vec<Vec<TrackCopy>> {
+    copies.sort_by_key(|c| c.copy.duration);
+    let mut groups: Vec<Vec<TrackCopy>> = Vec::new();
+    for copy in copies {
+        match groups.last_mut() {
+            Some(group) if group.last().is_some_and(|prev| prev.copy.lengths_agree(&copy.copy)) => group.push(copy),
+            _ => groups.push(vec![copy]),
+        }
+    }
+    groups
+}
+
+/// Group files by normalized artist + title, then by whatever else `mode`
+/// asks them to share. Audio is only hashed for files whose artist + title
+/// already match another's.
+fn find_duplicates(paths: &[PathBuf], mode: DupeMode) -> DuplicateReport {
+    let rules = rules();
+    let copies: Vec<TrackCopy> = paths.par_iter().filter_map(|p| read_track_copy(p, mode, rules)).collect();
+    let keyed = copies.len();
+
+    let mut by_key: BTreeMap<String, Vec<TrackCopy>> = BTreeMap::new();
+    for copy in copies {
+        by_key.entry(copy.key.clone()).or_default().push(copy);
+    }
+    let mut candidates: Vec<Vec<TrackCopy>> = by_key.into_values().filter(|g| g.len() > 1).collect();
+    if mode == DupeMode::Audio {
+        candidates.par_iter_mut().flat_map_iter(|g| g.iter_mut()).for_each(|c| {
+            c.matched_on = audio_hash(&c.copy.path);
+        });
+    }
+
+    let mut unmatched = 0;
+    let mut groups: Vec<Vec<TrackCopy>> = Vec::new();
+    for candidate in candidates {
+        let split = match mode {
+            DupeMode::Tags => split_by_length(candidate),
+            DupeMode::AcoustId | DupeMode::Audio => {
+                let mut by_value: BTreeMap<String, Vec<TrackCopy>> = BTreeMap::new();
+                for copy in candidate {
+                    match copy.matched_on.clone() {
+                        Some(value) => by_value.entry(value).or_default().push(copy),
+                        None => unmatched += 1,
+                    }
+                }
+                by_value.into_values().collect()
+            }
+        };
+        groups.extend(split.into_iter().filter(|g| g.len() > 1));
+    }
+    for group in &mut groups {
+        group.sort_by(|a, b| AudioCopy::best_first(&a.copy, &b.copy));
+    }
+    groups.sort_by(|a, b| a[0].copy.path.cmp(&b[0].copy.path));
+
+    DuplicateReport { mode, groups, keyed, unmatched }
+}
+
+// ---------------------------------------------------------------------------
+// Audio quality (--quality)
+// ---------------------------------------------------------------------------
+
+/// Set once in main from `--quality`: `scan_file` then reads audio properties too.
+static QUALITY_CHECKS: AtomicBool = AtomicBool::new(false);
+
+/// MP3s below this average bitrate are flagged (transcodes, old rips).
+const QUALITY_MIN_MP3_KBPS: u32 = 192;
+/// Tracks shorter than this are flagged (truncated downloads, hidden-track stubs).
+const QUALITY_MIN_LENGTH: Duration = Duration::from_secs(30);
+
+/// Audio properties of one file, read only with `--quality`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct AudioInfo {
+    /// kbps
+    bitrate: Option<u32>,
+    /// Hz
+    sample_rate: Option<u32>,
+    length: Duration,
+}
+
+impl AudioInfo {
+    fn of(tagged_file: &lofty::file::TaggedFile) -> Self {
+        let properties = tagged_file.properties();
+        AudioInfo {
+            bitrate: properties.audio_bitrate(),
+            sample_rate: properties.sample_rate(),
+            length: properties.duration(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum QualityCheck {
+    LowBitrate,
+    SampleRate,
+    Short,
+}
+
+impl QualityCheck {
+    fn label(self) -> &'static str {
+        match self {
+            QualityCheck::LowBitrate => "Low bitrate",
+            QualityCheck::SampleRate => "Sample rate",
+            QualityCheck::Short => "Short",
+        }
+    }
+}
+
+/// One file failing one check.
+#[derive(Debug, Clone)]
+struct QualityFlag {
+    check: QualityCheck,
+    path: PathBuf,
+    audio: AudioInfo,
+    /// What the file was measured against, e.g. "under 192 kbps"
+    note: String,
+}
+
+struct QualityReport {
+    /// Files whose audio properties were read
+    checked: usize,
+    /// By check, then path
+    flags: Vec<QualityFlag>,
+}
+
+impl QualityReport {
+    fn count(&self, check: QualityCheck) -> usize {
+        self.flags.iter().filter(|f| f.check == check).count()
+    }
+
+    fn flagged_files(&self) -> usize {
+        self.flags.iter().map(|f| &f.path).collect::<HashSet<_>>().len()
+    }
+}
+
+fn format_sample_rate(hz: u32) -> String {
+    if hz.is_multiple_of(1000) {
+        format!("{} kHz", hz / 1000)
+    } else {
+        format!("{:.1} kHz", hz as f64 / 1000.0)
+    }
+}
+
+/// Run the quality checks over every scanned file. Sample rates are compared
+/// per folder: files that differ from the folder's most common rate are
+/// flagged (a 48 kHz bonus track in a 44.1 kHz album).
+fn find_quality_issues<'a>(files: impl IntoIterator<Item = &'a FileIssue>) -> QualityReport {
+    let mut checked = 0;
+    let mut flags = Vec::new();
+    let mut rates_by_folder: BTreeMap<&Path, Vec<(&Path, AudioInfo, u32)>> = BTreeMap::new();
+
+    for issue in files {
+        let Some(audio) = issue.audio else { continue };
+        checked += 1;
+        let is_mp3 = issue.path.extension().is_some_and(|e| e.eq_ignore_ascii_case("mp3"));
+        if let Some(kbps) = audio.bitrate.filter(|&kbps| is_mp3 && kbps < QUALITY_MIN_MP3_KBPS) {
+            flags.push(QualityFlag {
+                check: QualityCheck::LowBitrate,
+                path: issue.path.clone(),
+                audio,
+                note: format!("{} kbps MP3, under {} kbps", kbps, QUALITY_MIN_MP3_KBPS),
+            });
+        }
+        if audio.length < QUALITY_MIN_LENGTH {
+            flags.push(QualityFlag {
+                check: QualityCheck::Short,
+                path: issue.path.clone(),
+                audio,
+                note: format!("under {}", format_length(QUALITY_MIN_LENGTH)),
+            });
+        }
+        if let (Some(folder), Some(rate)) = (issue.path.parent(), audio.sample_rate) {
+            rates_by_folder.entry(folder).or_default().push((&issue.path, audio, rate));
+        }
+    }
+
+    for files in rates_by_folder.values() {
+        let mut counts: BTreeMap<u32, usize> = BTreeMap::new();
+        for (_, _, rate) in files {
+            *counts.entry(*rate).or_insert(0) += 1;
+        }
+        if counts.len() < 2 {
+            continue;
+        }
+        // Most common rate wins; a tie goes to the higher rate
+        let (&usual, &usual_count) = counts.iter().max_by_key(|(rate, n)| (**n, **rate)).expect("two rates");
+        for (path, audio, rate) in files.iter().filter(|(_, _, rate)| *rate != usual) {
+            flags.push(QualityFlag {
+                check: QualityCheck::SampleRate,
+                path: path.to_path_buf(),
+                audio: *audio,
+                note: format!("{} in a folder of {} × {}", format_sample_rate(*rate), usual_count, format_sample_rate(usual)),
+            });
+        }
+    }
+
+    flags.sort_by(|a, b| a.check.cmp(&b.check).then_with(|| a.path.cmp(&b.path)));
+    QualityReport { checked, flags }
+}
+
+// ---------------------------------------------------------------------------
+// Rip software (albums.html)
+// ---------------------------------------------------------------------------
+
+/// Tags naming the ripper or encoder, in display order: `TENC` / `ENCODEDBY`
+/// (often the ripper), `TSSE` / `ENCODER`, and the encoder settings.
+const ENCODER_TAGS: &[&str] = &["ENCODEDBY", "ENCODERSOFTWARE", "ENCODERSETTINGS"];
+
+/// Rip logs recognized by a line near their top: (text, ripper).
+const RIP_LOG_SIGNATURES: &[(&str, &str)] = &[
+    ("Exact Audio Copy", "EAC"),
+    ("EAC extraction logfile", "EAC"),
+    ("X Lossless Decoder", "XLD"),
+    ("CUERipper", "CUERipper"),
+    ("whipper", "whipper"),
+    ("morituri", "morituri"),
+    ("dBpoweramp", "dBpoweramp"),
+];
+
+/// Bytes read from the top of each `.log` file.
+const RIP_LOG_HEAD: u64 = 4096;
+
+/// The distinct values of a file's encoder tags, e.g.
+/// "Exact Audio Copy · LAME 3.100".
+fn encoder_tags(tags: &HashMap<String, String>) -> Option<String> {
+    let mut parts: Vec<&str> = Vec::new();
+    for key in ENCODER_TAGS {
+        if let Some(value) = tags.get(*key).map(|v| v.trim()).filter(|v| !v.is_empty()) {
+            if !parts.contains(&value) {
+                parts.push(value);
+            }
+        }
+    }
+    (!parts.is_empty()).then(|| parts.join(" · "))
+}
 
-    for (idx, (dir, file_count)) in dirs_to_fix.iter().enumerate() {
-        let rel = dir
-            .to_string_lossy()
-            .strip_prefix(scan_root)
-            .unwrap_or(&dir.to_string_lossy())
-            .trim_start_matches('/')
-            .to_string();
+/// Log text as written by Windows rippers (UTF-16 LE with a BOM, as EAC
+/// does) or anything else (read as lossy UTF-8).
+fn decode_log(bytes: &[u8]) -> String {
+    match bytes.strip_prefix(&[0xFF, 0xFE]) {
+        Some(utf16) => {
+            let units: Vec<u16> = utf16.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+            String::from_utf16_lossy(&units)
+        }
+        None => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+/// The rippers whose logs are in `folder`, sorted ("EAC", "XLD", ...);
+/// "unknown" for a `.log` none of the signatures match.
+fn rip_logs(folder: &Path) -> Vec<&'static str> {
+    let mut rippers: Vec<&'static str> = Vec::new();
+    let Ok(entries) = fs::read_dir(folder) else { return rippers };
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        let is_log = path.extension().is_some_and(|e| e.eq_ignore_ascii_case("log"));
+        if !is_log || !path.is_file() {
+            continue;
+        }
+        let mut head = Vec::new();
+        if fs::File::open(&path).and_then(|f| f.take(RIP_LOG_HEAD).read_to_end(&mut head)).is_err() {
+            continue;
+        }
+        let text = decode_log(&head);
+        let ripper = RIP_LOG_SIGNATURES.iter()
+            .find(|(signature, _)| text.contains(signature))
+            .map_or("unknown", |(_, ripper)| *ripper);
+        if !rippers.contains(&ripper) {
+            rippers.push(ripper);
+        }
+    }
+    rippers.sort_unstable();
+    rippers
+}
 
-        let is_singleton = parent_audio_count.get(dir).copied().unwrap_or(0) == 1;
+/// Every scanned file's encoder tags, by album folder (see
+/// `get_album_folder`). Only the albums listed on albums.html get their
+/// logs read.
+#[derive(Debug, Default)]
+struct RipReport {
+    albums: HashMap<String, Vec<(PathBuf, Option<String>)>>,
+}
 
-        print!("  [{}/{}] {} ({} file{}) ... ",
-            idx + 1, total_dirs, rel, file_count,
-            if *file_count == 1 { "" } else { "s" },
-        );
-        std::io::stdout().flush().ok();
+impl RipReport {
+    fn collect<'a>(files: impl IntoIterator<Item = &'a FileIssue>, scan_root: &str) -> Self {
+        let mut albums: HashMap<String, Vec<(PathBuf, Option<String>)>> = HashMap::new();
+        for file in files {
+            albums.entry(get_album_folder(&file.path, scan_root))
+                .or_default()
+                .push((file.path.clone(), file.encoder.clone()));
+        }
+        RipReport { albums }
+    }
+}
+
+/// Group header of an album on the Rip software tab, e.g.
+/// "EAC log, LAME 3.100" or "no rip log, 2 encoders, 3 files untagged".
+fn rip_summary(logs: &[&str], encoders: &[Option<&str>]) -> String {
+    let mut parts = vec![match logs {
+        [] => "no rip log".to_string(),
+        [ripper] => format!("{} log", ripper),
+        _ => format!("{} logs", logs.join(" + ")),
+    }];
+    let mut distinct: Vec<&str> = encoders.iter().flatten().copied().collect();
+    distinct.sort_unstable();
+    distinct.dedup();
+    match distinct.as_slice() {
+        [] => {}
+        [encoder] => parts.push(encoder.to_string()),
+        _ => parts.push(format!("{} encoders", distinct.len())),
+    }
+    let untagged = encoders.iter().filter(|e| e.is_none()).count();
+    if untagged == encoders.len() {
+        parts.push("no encoder tags".to_string());
+    } else if untagged > 0 {
+        parts.push(format!("{} file{} untagged", untagged, if untagged == 1 { "" } else { "s" }));
+    }
+    parts.join(", ")
+}
+
+// ---------------------------------------------------------------------------
+// Provenance (index.html + summary.json)
+// ---------------------------------------------------------------------------
+
+/// How a report was produced, so two reports weeks apart can be compared.
+struct Provenance {
+    version: &'static str,
+    host: String,
+    /// The command line as typed (program name + arguments)
+    command: String,
+    /// MD5 of every setting that decides what counts as an issue (see `rules_signature`)
+    rules_hash: String,
+    generated: String,
+}
+
+impl Provenance {
+    fn collect(style: &StyleLints) -> Self {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        Provenance {
+            version: env!("CARGO_PKG_VERSION"),
+            host: host_name(),
+            command: command_line("analysis", &args),
+            rules_hash: format!("{:x}", Md5::digest(rules_signature(style).as_bytes())),
+            generated: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        }
+    }
+
+    fn rows(&self) -> Vec<(&str, &str)> {
+        vec![
+            ("Command", self.command.as_str()),
+            ("Rules hash", self.rules_hash.as_str()),
+            ("Version", self.version),
+            ("Host", self.host.as_str()),
+            ("Generated", self.generated.as_str()),
+        ]
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "command": self.command,
+            "rulesHash": self.rules_hash,
+            "version": self.version,
+            "host": self.host,
+            "generated": self.generated,
+        })
+    }
+}
 
-        let mut cmd = std::process::Command::new("beet");
-        cmd.arg("-l").arg(&tmp_lib)
-            .arg("import")
-            .arg("-C")    // don't copy/move files
-            .arg("-q");   // quiet mode (no prompts, skip uncertain matches)
+/// Arguments joined for display; the ones with spaces or quotes are single-quoted.
+fn command_line(program: &str, args: &[String]) -> String {
+    std::iter::once(program.to_string())
+        .chain(args.iter().map(|a| {
+            if a.is_empty() || a.contains([' ', '\'', '"', '\\']) {
+                format!("'{}'", a.replace('\'', "'\\''"))
+            } else {
+                a.clone()
+            }
+        }))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Canonical text of the issue rules: the fields of every category, the
+/// `--rules` file and the style lint settings. Its hash changes whenever a
+/// rule does.
+fn rules_signature(style: &StyleLints) -> String {
+    let mut lines: Vec<String> = CATEGORY_PAGES.iter()
+        .map(|c| format!("{}:{}", c.id, c.panels.iter().map(|p| p.field).collect::<Vec<_>>().join(",")))
+        .collect();
+    if let Some(rules) = RULES.get() {
+        lines.extend(rules.signature_lines());
+    }
+    lines.push(format!("style:{}", style.names().join(",")));
+    if !style.is_empty() {
+        lines.push(format!("style.case_min_letters:{}", CASE_MIN_LETTERS));
+        lines.push(format!("style.feat:{}", FEAT_MARKERS.join("|")));
+        lines.push(format!("style.explicit:{}", EXPLICIT_MARKERS.join("|")));
+    }
+    // Cached results without audio properties can't serve a --quality run
+    if QUALITY_CHECKS.load(Ordering::Relaxed) {
+        lines.push(format!("quality:mp3<{}kbps,length<{}s", QUALITY_MIN_MP3_KBPS, QUALITY_MIN_LENGTH.as_secs()));
+    }
+    lines.join("\n")
+}
+
+/// Machine name from $HOSTNAME / %COMPUTERNAME% or /etc/hostname.
+fn host_name() -> String {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .map(|h| h.trim().to_string())
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// `summary.json` next to `index.html`: the provenance, the run's counts and
+/// its health score.
+fn write_summary(
+    report_dir: &Path,
+    scan_root: &str,
+    provenance: &Provenance,
+    counts: &[(&'static str, &'static str, u64)],
+    health: Health,
+) -> std::io::Result<()> {
+    let counts: serde_json::Map<String, serde_json::Value> = counts.iter()
+        .map(|(key, _, n)| (key.to_string(), serde_json::Value::from(*n)))
+        .collect();
+    let summary = serde_json::json!({
+        "scanRoot": scan_root,
+        "provenance": provenance.to_json(),
+        "counts": counts,
+        "health": health.to_json(),
+    });
+    let json = serde_json::to_string_pretty(&summary).map_err(std::io::Error::other)?;
+    fs::write(report_dir.join("summary.json"), json + "\n")
+}
+
+// ---------------------------------------------------------------------------
+// Scan results (results.json) and `analysis diff`
+// ---------------------------------------------------------------------------
+
+/// `results.json` next to `summary.json`: the counts, every scanned file with
+/// the report panels it shows up in, and the unreadable files, so
+/// `analysis diff` can compare two runs file by file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ScanResults {
+    scan_root: String,
+    generated: String,
+    #[serde(default)]
+    counts: BTreeMap<String, u64>,
+    /// Relative path -> issue labels (empty for a file without issues).
+    /// `None` when read from a `summary.json`, which only has the counts.
+    #[serde(default)]
+    files: Option<BTreeMap<String, Vec<String>>>,
+    /// Relative path -> read error
+    #[serde(default)]
+    unreadable: BTreeMap<String, String>,
+}
+
+impl ScanResults {
+    fn collect(
+        issues: &[FileIssue],
+        all_paths: &[PathBuf],
+        unreadable: &[(PathBuf, String)],
+        scan_root: &str,
+        generated: &str,
+        counts: &[(&'static str, &'static str, u64)],
+    ) -> Self {
+        let unreadable: BTreeMap<String, String> = unreadable.iter()
+            .map(|(path, err)| (relative_path(path, scan_root), err.clone()))
+            .collect();
+        let mut files: BTreeMap<String, Vec<String>> = all_paths.iter()
+            .map(|path| relative_path(path, scan_root))
+            .filter(|rel| !unreadable.contains_key(rel))
+            .map(|rel| (rel, Vec::new()))
+            .collect();
+        for issue in issues {
+            let labels = issue_labels(issue).into_iter().map(String::from).collect();
+            files.insert(relative_path(&issue.path, scan_root), labels);
+        }
+        ScanResults {
+            scan_root: scan_root.to_string(),
+            generated: generated.to_string(),
+            counts: counts.iter().map(|(key, _, n)| (key.to_string(), *n)).collect(),
+            files: Some(files),
+            unreadable,
+        }
+    }
 
-        if dry {
-            cmd.arg("--pretend"); // dry run: show what would be tagged
+    /// Reads a report folder's `results.json` (or, for reports written before
+    /// it existed, its `summary.json`), or either file given directly.
+    fn load(path: &Path) -> Result<Self, String> {
+        let file = if path.is_dir() {
+            ["results.json", "summary.json"].iter()
+                .map(|name| path.join(name))
+                .find(|p| p.is_file())
+                .ok_or_else(|| format!("no results.json or summary.json in {}", path.display()))?
         } else {
-            cmd.arg("-w");        // write tags to files
-        }
-
-        if is_singleton {
-            cmd.arg("-s"); // singleton mode for lone files
-        }
-
-        cmd.arg(dir.as_os_str());
-        cmd.stdout(std::process::Stdio::piped());
-        cmd.stderr(std::process::Stdio::piped());
-
-        match cmd.output() {
-            Ok(output) => {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                let combined = format!("{}{}", stdout, stderr);
-                if output.status.success() {
-                    if combined.to_lowercase().contains("skipping") || combined.to_lowercase().contains("no good match") {
-                        // Extract a meaningful reason from beets output, or use a default
-                        let reason = combined.lines()
-                            .find(|l| {
-                                let lower = l.to_lowercase();
-                                lower.contains("skipping") || lower.contains("no good match")
-                                    || lower.contains("no candidate") || lower.contains("no match")
-                            })
-                            .map(|l| l.trim().to_string())
-                            .unwrap_or_else(|| "No confident match from beets".to_string());
-                        println!("skipped (no confident match)");
-                        skipped += 1;
-                        if !dry {
-                            skipped_dirs.insert(dir.clone(), reason);
-                        }
-                    } else {
-                        if dry {
-                            println!("would tag");
-                            // Print pretend output indented
-                            for line in stdout.lines().filter(|l| !l.trim().is_empty()) {
-                                println!("    {}", line.trim());
-                            }
-                        } else {
-                            println!("done");
-                        }
-                        processed += 1;
-                    }
-                } else {
-                    let first_line = combined
-                        .lines()
-                        .find(|l| !l.trim().is_empty())
-                        .unwrap_or("unknown error");
-                    println!("error: {}", first_line.trim());
-                    failed += 1;
+            path.to_path_buf()
+        };
+        let text = fs::read_to_string(&file).map_err(|e| format!("{}: {}", file.display(), e))?;
+        let json: serde_json::Value = serde_json::from_str(&text).map_err(|e| format!("{}: {}", file.display(), e))?;
+        if json.get("files").is_some() {
+            return serde_json::from_value(json).map_err(|e| format!("{}: {}", file.display(), e));
+        }
+        // summary.json: { scanRoot, provenance: { generated, .. }, counts }
+        let counts = json.get("counts").and_then(|c| c.as_object())
+            .ok_or_else(|| format!("{}: not a results.json or summary.json", file.display()))?;
+        Ok(ScanResults {
+            scan_root: json["scanRoot"].as_str().unwrap_or_default().to_string(),
+            generated: json["provenance"]["generated"].as_str().unwrap_or_default().to_string(),
+            counts: counts.iter().filter_map(|(key, n)| Some((key.clone(), n.as_u64()?))).collect(),
+            files: None,
+            unreadable: BTreeMap::new(),
+        })
+    }
+}
+
+fn write_results(report_dir: &Path, results: &ScanResults) -> std::io::Result<()> {
+    let json = serde_json::to_string_pretty(results).map_err(std::io::Error::other)?;
+    fs::write(report_dir.join("results.json"), json + "\n")
+}
+
+/// What changed between two scans, file by file. Files only one scan covers
+/// (added, deleted, or outside its `--only` / `--from` / `--to`) are counted
+/// but their issues are neither fixed nor introduced, except that the issues
+/// of a new file count as introduced.
+#[derive(Debug, Default)]
+struct ScanDiff {
+    /// File -> labels it no longer has
+    fixed: Vec<(String, Vec<String>)>,
+    /// File -> labels it didn't have (all of them for a new file)
+    introduced: Vec<(String, Vec<String>)>,
+    /// File -> read error, for files that were readable or not there before
+    new_unreadable: Vec<(String, String)>,
+    readable_again: Vec<String>,
+    added: usize,
+    removed: usize,
+}
+
+impl ScanDiff {
+    /// `None` when either side only has counts.
+    fn compare(old: &ScanResults, new: &ScanResults) -> Option<Self> {
+        let (old_files, new_files) = (old.files.as_ref()?, new.files.as_ref()?);
+        let mut diff = ScanDiff::default();
+        for (path, labels) in new_files {
+            let before = match old_files.get(path) {
+                Some(before) => before.as_slice(),
+                None if old.unreadable.contains_key(path) => {
+                    diff.readable_again.push(path.clone());
+                    &[]
+                }
+                None => {
+                    diff.added += 1;
+                    &[]
+                }
+            };
+            let gained: Vec<String> = labels.iter().filter(|l| !before.contains(l)).cloned().collect();
+            if !gained.is_empty() {
+                diff.introduced.push((path.clone(), gained));
+            }
+            let lost: Vec<String> = before.iter().filter(|l| !labels.contains(l)).cloned().collect();
+            if !lost.is_empty() {
+                diff.fixed.push((path.clone(), lost));
+            }
+        }
+        for (path, error) in &new.unreadable {
+            if !old.unreadable.contains_key(path) {
+                diff.new_unreadable.push((path.clone(), error.clone()));
+            }
+        }
+        diff.removed = old_files.keys().chain(old.unreadable.keys())
+            .filter(|path| !new_files.contains_key(*path) && !new.unreadable.contains_key(*path))
+            .count();
+        Some(diff)
+    }
+
+    fn fixed_count(&self) -> usize {
+        self.fixed.iter().map(|(_, labels)| labels.len()).sum()
+    }
+
+    fn introduced_count(&self) -> usize {
+        self.introduced.iter().map(|(_, labels)| labels.len()).sum()
+    }
+}
+
+/// Counts of both scans in report order (totals, then categories), with
+/// their labels.
+fn diff_counts(old: &ScanResults, new: &ScanResults) -> Vec<(String, Option<u64>, Option<u64>)> {
+    let mut keys: Vec<&str> = ["files", "ok", "issues", "unreadable"].into_iter()
+        .chain(CATEGORY_PAGES.iter().map(|c| c.id))
+        .chain(rules().categories.iter().map(|c| c.id.as_str()))
+        .filter(|key| old.counts.contains_key(*key) || new.counts.contains_key(*key))
+        .collect();
+    for key in old.counts.keys().chain(new.counts.keys()) {
+        if !keys.contains(&key.as_str()) {
+            keys.push(key);
+        }
+    }
+    keys.into_iter()
+        .map(|key| (count_label(key), old.counts.get(key).copied(), new.counts.get(key).copied()))
+        .collect()
+}
+
+fn count_label(key: &str) -> String {
+    let fixed = match key {
+        "files" => Some("Files"),
+        "ok" => Some("Files OK"),
+        "issues" => Some("Files with Issues"),
+        "unreadable" => Some("Unreadable"),
+        _ => CATEGORY_PAGES.iter().find(|c| c.id == key).map(|c| c.title),
+    };
+    fixed.map(String::from)
+        .or_else(|| rules().categories.iter().find(|c| c.id == key).map(|c| c.title.clone()))
+        .unwrap_or_else(|| key.to_string())
+}
+
+fn count_change(old: Option<u64>, new: Option<u64>) -> String {
+    match (old, new) {
+        (Some(old), Some(new)) if new > old => format!("+{}", new - old),
+        (Some(old), Some(new)) if new < old => format!("-{}", old - new),
+        (Some(_), Some(_)) => "=".to_string(),
+        _ => "-".to_string(),
+    }
+}
+
+/// The delta as one HTML document with inline styles, like the digest.
+fn render_diff(old: &ScanResults, new: &ScanResults, diff: Option<&ScanDiff>) -> String {
+    const CELL: &str = "padding:4px 10px;border-bottom:1px solid #e5e5e5;";
+    const NUM: &str = "padding:4px 10px;border-bottom:1px solid #e5e5e5;text-align:right;";
+
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\">");
+    html.push_str(&format!("<title>{} diff</title></head>\n", REPORT_TITLE));
+    html.push_str("<body style=\"font-family:Arial,Helvetica,sans-serif;font-size:14px;color:#222;margin:16px;\">\n");
+    html.push_str(&format!("<h2 style=\"margin:0 0 4px;\">{} diff</h2>\n", REPORT_TITLE));
+    html.push_str(&format!(
+        "<p style=\"margin:0 0 16px;color:#666;\">{} &rarr; {} &middot; <code>{}</code></p>\n",
+        html_text(&old.generated), html_text(&new.generated), html_text(&new.scan_root)
+    ));
+
+    html.push_str("<table style=\"border-collapse:collapse;margin-bottom:20px;\">\n");
+    html.push_str(&format!(
+        "<tr><th style=\"{c}text-align:left;\">Count</th><th style=\"{n}\">Before</th><th style=\"{n}\">After</th><th style=\"{n}\">Change</th></tr>\n",
+        c = CELL, n = NUM
+    ));
+    let show = |n: Option<u64>| n.map_or("&ndash;".to_string(), |n| n.to_string());
+    for (label, before, after) in diff_counts(old, new) {
+        html.push_str(&format!(
+            "<tr><td style=\"{c}\">{}</td><td style=\"{n}\">{}</td><td style=\"{n}\">{}</td><td style=\"{n}color:#666;\">{}</td></tr>\n",
+            html_text(&label), show(before), show(after), html_text(&count_change(before, after)),
+            c = CELL, n = NUM
+        ));
+    }
+    html.push_str("</table>\n");
+
+    let Some(diff) = diff else {
+        html.push_str("<p>One of the scans only has counts (a report from before <code>results.json</code>), so there are no per-file changes.</p>\n");
+        html.push_str("</body></html>\n");
+        return html;
+    };
+    let mut section = |title: String, color: &str, rows: Vec<(&str, String)>| {
+        html.push_str(&format!("<h3 style=\"margin:16px 0 8px;color:{};\">{}</h3>\n", color, html_text(&title)));
+        if rows.is_empty() {
+            html.push_str("<p style=\"color:#666;\">None.</p>\n");
+            return;
+        }
+        html.push_str("<table style=\"border-collapse:collapse;\">\n");
+        for (path, detail) in rows {
+            html.push_str(&format!(
+                "<tr><td style=\"{c}\"><code>{}</code></td><td style=\"{c}\">{}</td></tr>\n",
+                html_text(path), html_text(&detail), c = CELL
+            ));
+        }
+        html.push_str("</table>\n");
+    };
+    section(
+        format!("Fixed: {} issue(s) in {} file(s)", diff.fixed_count(), diff.fixed.len()), "#2e7d32",
+        diff.fixed.iter().map(|(path, labels)| (path.as_str(), labels.join(", "))).collect(),
+    );
+    section(
+        format!("Introduced: {} issue(s) in {} file(s)", diff.introduced_count(), diff.introduced.len()), "#c62828",
+        diff.introduced.iter().map(|(path, labels)| (path.as_str(), labels.join(", "))).collect(),
+    );
+    section(
+        format!("New unreadable: {} file(s)", diff.new_unreadable.len()), "#c62828",
+        diff.new_unreadable.iter().map(|(path, error)| (path.as_str(), error.clone())).collect(),
+    );
+    section(
+        format!("Readable again: {} file(s)", diff.readable_again.len()), "#2e7d32",
+        diff.readable_again.iter().map(|path| (path.as_str(), String::new())).collect(),
+    );
+    html.push_str(&format!(
+        "<p style=\"color:#666;\">Files added: {} &middot; removed or out of scope: {}</p>\n",
+        diff.added, diff.removed
+    ));
+    html.push_str("</body></html>\n");
+    html
+}
+
+/// `analysis diff OLD NEW`: prints the changelog between two scans and
+/// optionally writes it as HTML.
+fn run_diff(args: DiffArgs) {
+    let load = |path: &str| ScanResults::load(Path::new(path)).unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    });
+    let (old, new) = (load(&args.old), load(&args.new));
+    let diff = ScanDiff::compare(&old, &new);
+
+    println!("Scan Diff");
+    println!("=========");
+    println!("Old       : {} ({})", old.generated, args.old);
+    println!("New       : {} ({})", new.generated, args.new);
+    if old.scan_root != new.scan_root {
+        println!("Scan root : {} -> {} (compared by path relative to each root)", old.scan_root, new.scan_root);
+    } else {
+        println!("Scan root : {}", new.scan_root);
+    }
+
+    println!();
+    println!("  {:<24} {:>8} {:>8} {:>8}", "Count", "Before", "After", "Change");
+    for (label, before, after) in diff_counts(&old, &new) {
+        let show = |n: Option<u64>| n.map_or("-".to_string(), |n| n.to_string());
+        println!("  {:<24} {:>8} {:>8} {:>8}", label, show(before), show(after), count_change(before, after));
+    }
+
+    match &diff {
+        None => println!("\n  No per-file changes: one of the scans only has a summary.json (report from before results.json)"),
+        Some(diff) => {
+            println!("\n[Fixed] {} issue(s) in {} file(s)", diff.fixed_count(), diff.fixed.len());
+            for (path, labels) in &diff.fixed {
+                println!("  {}: {}", path, labels.join(", "));
+            }
+            println!("\n[Introduced] {} issue(s) in {} file(s)", diff.introduced_count(), diff.introduced.len());
+            for (path, labels) in &diff.introduced {
+                println!("  {}: {}", path, labels.join(", "));
+            }
+            println!("\n[New unreadable] {} file(s)", diff.new_unreadable.len());
+            for (path, error) in &diff.new_unreadable {
+                println!("  {}: {}", path, error);
+            }
+            if !diff.readable_again.is_empty() {
+                println!("\n[Readable again] {} file(s)", diff.readable_again.len());
+                for path in &diff.readable_again {
+                    println!("  {}", path);
                 }
             }
+            println!("\nFiles added: {} | Removed or out of scope: {}", diff.added, diff.removed);
+        }
+    }
+
+    if let Some(ref html) = args.html {
+        let path = Path::new(html);
+        let written = path.parent().filter(|p| !p.as_os_str().is_empty())
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(path, render_diff(&old, &new, diff.as_ref())));
+        match written {
+            Ok(_) => println!("Diff written to: {}", path.display()),
             Err(e) => {
-                println!("failed: {}", e);
-                failed += 1;
+                eprintln!("Failed to write diff {}: {}", path.display(), e);
+                std::process::exit(1);
             }
         }
     }
+}
+
+// ---------------------------------------------------------------------------
+// Metrics (metrics.csv / metrics.json, --metrics-csv)
+// ---------------------------------------------------------------------------
+
+/// Columns of metrics.csv and of the --metrics-csv history.
+const METRICS_HEADER: &str = "timestamp,category,count,matched_count";
+
+/// One headline count of the run, flat for Grafana and the like.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct MetricRow {
+    category: &'static str,
+    count: u64,
+    /// Files of the category fixed this run (autofix, --style-fix, --migrate-tags);
+    /// for "issues" every fixed file, 0 for the other totals
+    matched: u64,
+}
+
+/// The `run_counts` with their matched counts.
+fn metric_rows(counts: &[(&'static str, &'static str, u64)], diffs: Option<&MatchDiffs>) -> Vec<MetricRow> {
+    counts.iter()
+        .map(|&(category, _, count)| {
+            let matched = diffs.map_or(0, |d| match category {
+                "issues" => d.len(),
+                _ => d.values().filter(|fixes| fixes.iter().any(|fix| fix.category == category)).count(),
+            });
+            MetricRow { category, count, matched: matched as u64 }
+        })
+        .collect()
+}
+
+/// CSV lines (no header) for `rows`, all stamped with `timestamp`.
+fn metrics_csv(timestamp: &str, rows: &[MetricRow]) -> String {
+    rows.iter()
+        .map(|r| format!("{},{},{},{}\n", timestamp, r.category, r.count, r.matched))
+        .collect()
+}
+
+/// `metrics.csv` and `metrics.json` (the same rows as an array of objects) in the report folder.
+fn write_metrics(report_dir: &Path, timestamp: &str, rows: &[MetricRow]) -> std::io::Result<()> {
+    fs::write(report_dir.join("metrics.csv"), format!("{}\n{}", METRICS_HEADER, metrics_csv(timestamp, rows)))?;
+    let json: Vec<serde_json::Value> = rows.iter()
+        .map(|r| serde_json::json!({
+            "timestamp": timestamp,
+            "category": r.category,
+            "count": r.count,
+            "matched_count": r.matched,
+        }))
+        .collect();
+    let json = serde_json::to_string_pretty(&json).map_err(std::io::Error::other)?;
+    fs::write(report_dir.join("metrics.json"), json + "\n")
+}
+
+/// Append `rows` to the CSV at `path`, writing the header when the file is new or empty.
+fn append_metrics(path: &Path, timestamp: &str, rows: &[MetricRow]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+    if file.metadata()?.len() == 0 {
+        writeln!(file, "{}", METRICS_HEADER)?;
+    }
+    file.write_all(metrics_csv(timestamp, rows).as_bytes())
+}
+
+// ---------------------------------------------------------------------------
+// Trend history (history.jsonl in the output directory, trends on index.html)
+// ---------------------------------------------------------------------------
+
+/// One line per full scan, next to the `analysis_*` report folders.
+const HISTORY_FILE: &str = "history.jsonl";
+
+/// Runs charted in the trends on index.html, the most recent ones.
+const TREND_RUNS: usize = 30;
+
+/// The headline numbers of one run: a line of `history.jsonl`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HistoryEntry {
+    generated: String,
+    scan_root: String,
+    rules_hash: String,
+    elapsed_seconds: f64,
+    /// Health score (0.0-100.0)
+    health: f64,
+    counts: BTreeMap<String, u64>,
+}
+
+impl HistoryEntry {
+    fn new(
+        generated: &str,
+        scan_root: &str,
+        rules_hash: &str,
+        elapsed: Duration,
+        health: Health,
+        counts: &[(&'static str, &'static str, u64)],
+    ) -> Self {
+        HistoryEntry {
+            generated: generated.to_string(),
+            scan_root: scan_root.to_string(),
+            rules_hash: rules_hash.to_string(),
+            elapsed_seconds: (elapsed.as_secs_f64() * 10.0).round() / 10.0,
+            health: health.score(),
+            counts: counts.iter().map(|&(key, _, n)| (key.to_string(), n)).collect(),
+        }
+    }
+}
+
+/// The last `TREND_RUNS` runs of `scan_root` in the history at `path`, oldest
+/// first. A missing file is an empty history; lines that don't parse (a run
+/// killed mid-write) are skipped.
+fn load_history(path: &Path, scan_root: &str) -> Vec<HistoryEntry> {
+    let Ok(text) = fs::read_to_string(path) else { return Vec::new() };
+    let runs: Vec<HistoryEntry> = text.lines()
+        .filter_map(|line| serde_json::from_str::<HistoryEntry>(line).ok())
+        .filter(|run| run.scan_root == scan_root)
+        .collect();
+    runs[runs.len().saturating_sub(TREND_RUNS)..].to_vec()
+}
+
+/// Append `entry` to the history at `path` as one JSON line.
+fn append_history(path: &Path, entry: &HistoryEntry) -> std::io::Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    let line = serde_json::to_string(entry).map_err(std::io::Error::other)?;
+    let mut file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", line)
+}
+
+/// Inline SVG polyline of `values`, scaled to their own range (flat when
+/// they're all the same).
+fn sparkline(values: &[f64]) -> String {
+    const WIDTH: f64 = 120.0;
+    const HEIGHT: f64 = 24.0;
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let step = if values.len() > 1 { WIDTH / (values.len() - 1) as f64 } else { 0.0 };
+    let points: Vec<String> = values.iter().enumerate()
+        .map(|(i, v)| {
+            let level = if max > min { (v - min) / (max - min) } else { 0.5 };
+            format!("{:.1},{:.1}", i as f64 * step, 2.0 + (1.0 - level) * (HEIGHT - 4.0))
+        })
+        .collect();
+    format!("<svg class=\"sparkline\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\"><polyline points=\"{2}\"/></svg>",
+        WIDTH, HEIGHT, points.join(" "))
+}
+
+/// Which way a trend should go for the library to be getting better.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Better {
+    Up,
+    Down,
+    Neither,
+}
+
+/// "Trends" on index.html: a sparkline per count of this run across the
+/// history (`runs`, this run last), with the first and latest values.
+fn write_trends(page: &mut report::Page, runs: &[HistoryEntry], counts: &[(&'static str, &'static str, u64)]) -> std::io::Result<()> {
+    let mut series: Vec<(&str, Better, Vec<f64>)> = vec![
+        ("Health Score", Better::Up, runs.iter().map(|r| r.health).collect()),
+    ];
+    for &(key, label, _) in counts {
+        let better = match key {
+            "files" => Better::Neither,
+            "ok" => Better::Up,
+            _ => Better::Down,
+        };
+        series.push((label, better, runs.iter().filter_map(|r| r.counts.get(key)).map(|&n| n as f64).collect()));
+    }
+    series.push(("Scan Time (s)", Better::Neither, runs.iter().map(|r| r.elapsed_seconds).collect()));
+
+    let since = runs[0].generated.get(..10).unwrap_or(&runs[0].generated);
+    write!(page, "<div class=\"breakdown\">\n<h2>Trends (last {} runs, since {})</h2>\n\
+        <div class=\"table-wrap\"><table>\n\
+        <thead><tr><th>Count</th><th>Trend</th><th>First</th><th>Latest</th><th>Change</th></tr></thead>\n<tbody>\n",
+        runs.len(), html_text(since))?;
+    for (label, better, values) in &series {
+        let (Some(first), Some(latest)) = (values.first(), values.last()) else { continue };
+        let change = latest - first;
+        let class = match better {
+            _ if change == 0.0 => "",
+            Better::Up if change > 0.0 => "trend-better",
+            Better::Down if change < 0.0 => "trend-better",
+            Better::Neither => "",
+            _ => "trend-worse",
+        };
+        writeln!(page, "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td class=\"{}\">{:+}</td></tr>",
+            html_text(label), sparkline(values), first, latest, class, (change * 10.0).round() / 10.0)?;
+    }
+    writeln!(page, "</tbody>\n</table></div>")?;
+    let latest = &runs[runs.len() - 1];
+    if runs.iter().any(|r| r.rules_hash != latest.rules_hash) {
+        writeln!(page, "<p class=\"trend-note\">The rules changed during these runs (see the rules hash), so some steps come from the rules rather than the library.</p>")?;
+    }
+    writeln!(page, "</div>")?;
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Library health score
+// ---------------------------------------------------------------------------
+
+/// Penalty at which a file counts as fully unhealthy: any critical issue, or
+/// an unreadable file.
+const MAX_FILE_PENALTY: u32 = 10;
+
+/// Weight of a `--rules` category, which has no severity of its own.
+const CUSTOM_CATEGORY_WEIGHT: u32 = 1;
+
+/// Sum of the weights of the categories `issue` has issues in, capped at
+/// `MAX_FILE_PENALTY`: a missing year costs 10, missing MB IDs 4, a missing
+/// genre 2, a missing Discogs link 1.
+fn file_penalty(issue: &FileIssue) -> u32 {
+    let built_in: u32 = CATEGORY_PAGES.iter()
+        .filter(|c| (c.has_issue)(issue))
+        .map(|c| c.weight)
+        .sum();
+    let custom = rules().categories.iter().filter(|c| c.has_issue(issue)).count() as u32 * CUSTOM_CATEGORY_WEIGHT;
+    (built_in + custom).min(MAX_FILE_PENALTY)
+}
+
+/// Severity-weighted health of a set of files: 100 when no file has an
+/// issue, 0 when every file has a critical one (or can't be read).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Health {
+    files: u64,
+    penalty: u64,
+}
+
+impl Health {
+    /// Health of the whole scan.
+    fn of(issues: &[FileIssue], total_files: u64, error_count: u64) -> Self {
+        let penalty = issues.iter().map(|i| file_penalty(i) as u64).sum::<u64>()
+            + error_count * MAX_FILE_PENALTY as u64;
+        Health { files: total_files, penalty }
+    }
+
+    /// 0.0-100.0, one decimal.
+    fn score(&self) -> f64 {
+        if self.files == 0 {
+            return 100.0;
+        }
+        let healthy = 1.0 - self.penalty as f64 / (self.files * MAX_FILE_PENALTY as u64) as f64;
+        (healthy * 1000.0).round() / 10.0
+    }
+
+    /// School grade of the score: A from 90, B from 80, C from 70, D from 60, F below.
+    fn grade(&self) -> &'static str {
+        match self.score() {
+            s if s >= 90.0 => "A",
+            s if s >= 80.0 => "B",
+            s if s >= 70.0 => "C",
+            s if s >= 60.0 => "D",
+            _ => "F",
+        }
+    }
+
+    /// Stat card class of the grade.
+    fn class(&self) -> &'static str {
+        match self.grade() {
+            "A" | "B" => "ok",
+            "C" | "D" => "warn",
+            _ => "fail",
+        }
+    }
+
+    fn to_json(self) -> serde_json::Value {
+        serde_json::json!({ "score": self.score(), "grade": self.grade() })
+    }
+}
+
+/// An artist folder on the health leaderboard.
+#[derive(Debug, Clone, PartialEq)]
+struct ArtistHealth {
+    name: String,
+    health: Health,
+    issues: usize,
+    critical: usize,
+    unreadable: usize,
+}
+
+/// Health of every artist folder with an issue or an unreadable file, worst
+/// first (then most files with issues, then by name).
+fn artist_health(
+    issues: &[FileIssue],
+    all_paths: &[PathBuf],
+    unreadable: &[(PathBuf, String)],
+    scan_root: &str,
+) -> Vec<ArtistHealth> {
+    fn artist<'a>(by_artist: &'a mut HashMap<String, ArtistHealth>, path: &Path, scan_root: &str) -> &'a mut ArtistHealth {
+        let name = get_artist_folder(path, scan_root);
+        by_artist.entry(name.clone())
+            .or_insert_with(|| ArtistHealth { name, health: Health::default(), issues: 0, critical: 0, unreadable: 0 })
+    }
+    let mut by_artist: HashMap<String, ArtistHealth> = HashMap::new();
+    for path in all_paths {
+        artist(&mut by_artist, path, scan_root).health.files += 1;
+    }
+    for issue in issues {
+        let entry = artist(&mut by_artist, &issue.path, scan_root);
+        entry.health.penalty += file_penalty(issue) as u64;
+        entry.issues += 1;
+        if issue.has_critical() { entry.critical += 1; }
+    }
+    for (path, _) in unreadable {
+        let entry = artist(&mut by_artist, path, scan_root);
+        entry.health.penalty += MAX_FILE_PENALTY as u64;
+        entry.unreadable += 1;
+    }
+    let mut artists: Vec<ArtistHealth> = by_artist.into_values()
+        .filter(|a| a.health.penalty > 0)
+        .collect();
+    artists.sort_by(|a, b| a.health.score().total_cmp(&b.health.score())
+        .then_with(|| b.issues.cmp(&a.issues))
+        .then_with(|| a.name.cmp(&b.name)));
+    artists
+}
+
+// ---------------------------------------------------------------------------
+// Digest (--digest)
+// ---------------------------------------------------------------------------
+
+/// How many artists the digest lists.
+const DIGEST_TOP_ARTISTS: usize = 20;
+
+/// Marker of the HTML comment holding the digest's counts; the next run reads
+/// it back from the previous digest to show deltas.
+const DIGEST_MARKER: &str = "dmp-digest";
+
+/// An artist folder in the digest's "worst artists" table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DigestArtist {
+    name: String,
+    files: usize,
+    issues: usize,
+    critical: usize,
+}
+
+/// Headline counts of a run, in display order: (key, label, count). Shared by
+/// the digest and `summary.json`.
+fn run_counts(
+    issues: &[FileIssue],
+    total_files: u64,
+    error_count: u64,
+    with_style: bool,
+) -> Vec<(&'static str, &'static str, u64)> {
+    let readable = total_files.saturating_sub(error_count);
+    let mut counts = vec![
+        ("files", "Files", total_files),
+        ("ok", "Files OK", readable.saturating_sub(issues.len() as u64)),
+        ("issues", "Files with Issues", issues.len() as u64),
+        ("unreadable", "Unreadable", error_count),
+    ];
+    for category in CATEGORY_PAGES {
+        if category.id == "style" && !with_style { continue; }
+        let count = issues.iter().filter(|i| (category.has_issue)(i)).count();
+        counts.push((category.id, category.title, count as u64));
+    }
+    for category in &rules().categories {
+        let count = issues.iter().filter(|i| category.has_issue(i)).count();
+        counts.push((category.id.as_str(), category.title.as_str(), count as u64));
+    }
+    counts
+}
+
+/// Artist folders with the most files with issues (ties by name), at most `limit`.
+fn worst_artists(issues: &[FileIssue], paths: &[PathBuf], scan_root: &str, limit: usize) -> Vec<DigestArtist> {
+    let mut by_artist: HashMap<String, DigestArtist> = HashMap::new();
+    for p in paths {
+        let name = get_artist_folder(p, scan_root);
+        by_artist.entry(name.clone())
+            .or_insert_with(|| DigestArtist { name, files: 0, issues: 0, critical: 0 })
+            .files += 1;
+    }
+    for issue in issues {
+        let name = get_artist_folder(&issue.path, scan_root);
+        let artist = by_artist.entry(name.clone())
+            .or_insert_with(|| DigestArtist { name, files: 0, issues: 0, critical: 0 });
+        artist.issues += 1;
+        if issue.has_critical() { artist.critical += 1; }
+    }
+    let mut artists: Vec<DigestArtist> = by_artist.into_values().filter(|a| a.issues > 0).collect();
+    artists.sort_by(|a, b| b.issues.cmp(&a.issues).then_with(|| a.name.cmp(&b.name)));
+    artists.truncate(limit);
+    artists
+}
+
+/// Counts stored in a digest written by a previous run (empty if there is none).
+fn read_previous_digest(html: &str) -> HashMap<String, u64> {
+    let start = format!("<!-- {} ", DIGEST_MARKER);
+    html.find(&start)
+        .and_then(|i| {
+            let rest = &html[i + start.len()..];
+            rest.find(" -->").map(|end| &rest[..end])
+        })
+        .and_then(|json| serde_json::from_str(json).ok())
+        .unwrap_or_default()
+}
+
+fn format_delta(current: u64, previous: Option<u64>) -> String {
+    match previous {
+        None => "&ndash;".to_string(),
+        Some(prev) if prev == current => "=".to_string(),
+        Some(prev) if current > prev => format!("+{}", current - prev),
+        Some(prev) => format!("&minus;{}", prev - current),
+    }
+}
+
+/// The digest: one HTML document with inline styles only, so mail clients
+/// render it as is.
+fn render_digest(
+    scan_root: &str,
+    generated: &str,
+    counts: &[(&'static str, &'static str, u64)],
+    previous: &HashMap<String, u64>,
+    artists: &[DigestArtist],
+) -> String {
+    const CELL: &str = "padding:4px 10px;border-bottom:1px solid #e5e5e5;";
+    const NUM: &str = "padding:4px 10px;border-bottom:1px solid #e5e5e5;text-align:right;";
+
+    let stored: BTreeMap<&str, u64> = counts.iter().map(|(key, _, n)| (*key, *n)).collect();
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\">");
+    html.push_str(&format!("<title>{} digest</title></head>\n", REPORT_TITLE));
+    html.push_str(&format!("<!-- {} {} -->\n", DIGEST_MARKER, serde_json::to_string(&stored).unwrap_or_default()));
+    html.push_str("<body style=\"font-family:Arial,Helvetica,sans-serif;font-size:14px;color:#222;margin:16px;\">\n");
+    html.push_str(&format!("<h2 style=\"margin:0 0 4px;\">{} digest</h2>\n", REPORT_TITLE));
+    html.push_str(&format!(
+        "<p style=\"margin:0 0 16px;color:#666;\">{} &middot; <code>{}</code></p>\n",
+        html_text(generated), html_text(scan_root)
+    ));
+
+    html.push_str("<table style=\"border-collapse:collapse;margin-bottom:20px;\">\n");
+    html.push_str(&format!(
+        "<tr><th style=\"{c}text-align:left;\">Count</th><th style=\"{n}\">Now</th><th style=\"{n}\">Since last run</th></tr>\n",
+        c = CELL, n = NUM
+    ));
+    for (key, label, count) in counts {
+        html.push_str(&format!(
+            "<tr><td style=\"{}\">{}</td><td style=\"{}\">{}</td><td style=\"{}color:#666;\">{}</td></tr>\n",
+            CELL, label, NUM, count, NUM, format_delta(*count, previous.get(*key).copied())
+        ));
+    }
+    html.push_str("</table>\n");
+
+    if artists.is_empty() {
+        html.push_str("<p>No artist has files with issues.</p>\n");
+    } else {
+        html.push_str(&format!("<h3 style=\"margin:0 0 8px;\">Top {} artists by files with issues</h3>\n", artists.len()));
+        html.push_str("<table style=\"border-collapse:collapse;\">\n");
+        html.push_str(&format!(
+            "<tr><th style=\"{c}text-align:left;\">Artist</th><th style=\"{n}\">Issues</th><th style=\"{n}\">Critical</th><th style=\"{n}\">Files</th><th style=\"{n}\">Share</th></tr>\n",
+            c = CELL, n = NUM
+        ));
+        for artist in artists {
+            let share = (artist.issues * 100).checked_div(artist.files).unwrap_or(100);
+            html.push_str(&format!(
+                "<tr><td style=\"{c}\">{}</td><td style=\"{n}\">{}</td><td style=\"{n}\">{}</td><td style=\"{n}\">{}</td><td style=\"{n}\">{}%</td></tr>\n",
+                html_text(&artist.name), artist.issues, artist.critical, artist.files, share,
+                c = CELL, n = NUM
+            ));
+        }
+        html.push_str("</table>\n");
+    }
+    html.push_str("</body></html>\n");
+    html
+}
 
-    // Clean up temporary beet library
-    let _ = fs::remove_file(&tmp_lib);
-
-    println!();
-    println!("[{}] Complete.", label);
-    if dry {
-        println!("  Would tag: {}", processed);
-    } else {
-        println!("  Tagged:    {}", processed);
+/// Writes the digest to `path`, comparing against the digest already there.
+fn write_digest(
+    path: &Path,
+    scan_root: &str,
+    issues: &[FileIssue],
+    paths: &[PathBuf],
+    total_files: u64,
+    error_count: u64,
+    with_style: bool,
+) -> std::io::Result<()> {
+    let previous = fs::read_to_string(path).map(|html| read_previous_digest(&html)).unwrap_or_default();
+    let counts = run_counts(issues, total_files, error_count, with_style);
+    let artists = worst_artists(issues, paths, scan_root, DIGEST_TOP_ARTISTS);
+    let generated = Local::now().format("%Y-%m-%d %H:%M").to_string();
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
     }
-    println!("  Skipped:   {} (no confident match)", skipped);
-    println!("  Failed:    {}", failed);
+    fs::write(path, render_digest(scan_root, &generated, &counts, &previous, &artists))
+}
 
-    skipped_dirs
+// ---------------------------------------------------------------------------
+// Handled files (--apply-handled)
+// ---------------------------------------------------------------------------
+
+/// Load the relative paths from a handled.json exported by the report:
+/// `{ "handled": ["Artist/Album/01 Track.flac", ...] }`
+fn load_handled(path: &str) -> Result<HashSet<String>, String> {
+    let data = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let json: serde_json::Value = serde_json::from_str(&data).map_err(|e| e.to_string())?;
+    let list = json
+        .get("handled")
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| "expected a \"handled\" array".to_string())?;
+    Ok(list
+        .iter()
+        .filter_map(|v| v.as_str())
+        .map(|s| s.to_string())
+        .collect())
 }
 
 // ---------------------------------------------------------------------------
-// Autofix: re-scan after beets
+// Publish to S3 (--publish-s3)
 // ---------------------------------------------------------------------------
 
-/// Re-scan files that originally had issues, classify fix status, and build per-field diffs.
-/// Returns (fixed_paths, still_broken, newly_unreadable, autofix_diffs).
-/// Re-scan files that originally had issues, classify fix status, and build per-field diffs.
-/// `skip_dirs` maps directories that beets skipped → the skip reason.
-/// Returns (fixed_paths, still_broken, newly_unreadable, autofix_diffs, skipped_files).
-fn compute_autofix_diffs(
-    original_issues: &[FileIssue],
-    skip_dirs: &HashMap<PathBuf, String>,
-) -> (Vec<PathBuf>, Vec<FileIssue>, Vec<(PathBuf, String)>, MatchDiffs, SkippedFiles) {
-    let mut matched: Vec<PathBuf> = Vec::new();
-    let mut still_broken: Vec<FileIssue> = Vec::new();
-    let mut unreadable: Vec<(PathBuf, String)> = Vec::new();
-    let mut diffs: MatchDiffs = HashMap::new();
-    // Expand dir-level skip info to individual file paths
-    let mut skipped_files: SkippedFiles = HashMap::new();
-    for orig in original_issues {
-        if let Some(parent) = orig.path.parent() {
-            if let Some(reason) = skip_dirs.get(parent) {
-                skipped_files.insert(orig.path.clone(), reason.clone());
-            }
+/// Upload every file under `report_dir` to `<prefix>/<report dir name>/...` in
+/// the image bucket. Returns the number of files uploaded and the public URL of
+/// index.html (`None` when `S3_PUBLIC_URL` is unset).
+fn publish_report(report_dir: &Path, prefix: &str) -> Result<(usize, Option<String>), String> {
+    let config = s3::S3Config::from_env()
+        .ok_or_else(|| "S3_IMAGE_BUCKET and AWS_REGION must be set (web/.env)".to_string())?;
+    let dir_name = report_dir
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let mut files: Vec<PathBuf> = WalkDir::new(report_dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.into_path())
+        .collect();
+    files.sort();
+
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+    runtime.block_on(async {
+        let client = config.client().await?;
+        for path in &files {
+            let rel = path.strip_prefix(report_dir).unwrap_or(path);
+            let rel = rel.to_string_lossy().replace('\\', "/");
+            let key = s3::object_key(&[prefix, &dir_name, &rel]);
+            s3::upload_file(&client, &config.bucket, &key, path)
+                .await
+                .map_err(|e| format!("{}: {}", key, s3::error_chain(e.as_ref())))?;
         }
-    }
+        Ok::<(), String>(())
+    })?;
 
-    for orig in original_issues {
-        let (new_issue, _new_tags) = match scan_file(&orig.path) {
-            Ok(result) => result,
-            Err(err) => {
-                unreadable.push((orig.path.clone(), err));
-                continue;
-            }
-        };
+    let index_key = s3::object_key(&[prefix, &dir_name, "index.html"]);
+    Ok((files.len(), config.public_url_for(&index_key)))
+}
 
-        // Re-open file to read new tag values for diffs
-        let parse_opts = ParseOptions::new().read_properties(false);
-        let tag_map = if let Ok(probe) = Probe::open(&orig.path) {
-            if let Ok(tagged) = probe.options(parse_opts).read() {
-                collect_tags(&tagged)
-            } else {
-                HashMap::new()
-            }
-        } else {
-            HashMap::new()
-        };
+// ---------------------------------------------------------------------------
+// Serve the report (--serve)
+// ---------------------------------------------------------------------------
 
-        // Build field-level diffs: for each field that was bad and is now good
-        let mut field_matches: Vec<FieldMatch> = Vec::new();
+/// A static file server for one report folder: GET / HEAD only, one thread
+/// per connection, no keep-alive. Enough for a browser on another machine.
+struct ReportServer {
+    listener: std::net::TcpListener,
+}
 
-        // --- Critical fields ---
-        if orig.missing_artist && !new_issue.missing_artist {
-            field_matches.push(FieldMatch {
-                field: "Artist",
-                old_display: "Missing".into(),
-                new_value: get_tag(&tag_map, &["ARTIST"]).unwrap_or_default(),
-                category: "critical",
-            });
-        }
-        if orig.blank_artist && !new_issue.blank_artist {
-            field_matches.push(FieldMatch {
-                field: "Artist",
-                old_display: "(blank)".into(),
-                new_value: get_tag(&tag_map, &["ARTIST"]).unwrap_or_default(),
-                category: "critical",
-            });
-        }
-        if orig.missing_title && !new_issue.missing_title {
-            field_matches.push(FieldMatch {
-                field: "Title",
-                old_display: "Missing".into(),
-                new_value: get_tag(&tag_map, &["TITLE"]).unwrap_or_default(),
-                category: "critical",
-            });
-        }
-        if orig.blank_title && !new_issue.blank_title {
-            field_matches.push(FieldMatch {
-                field: "Title",
-                old_display: "(blank)".into(),
-                new_value: get_tag(&tag_map, &["TITLE"]).unwrap_or_default(),
-                category: "critical",
-            });
-        }
-        if orig.missing_year && !new_issue.missing_year {
-            field_matches.push(FieldMatch {
-                field: "Year",
-                old_display: "Missing".into(),
-                new_value: get_tag(&tag_map, &["YEAR"]).unwrap_or_default(),
-                category: "critical",
-            });
-        }
-        if orig.blank_year && !new_issue.blank_year {
-            field_matches.push(FieldMatch {
-                field: "Year",
-                old_display: "(blank)".into(),
-                new_value: get_tag(&tag_map, &["YEAR"]).unwrap_or_default(),
-                category: "critical",
-            });
-        }
-        if orig.invalid_year.is_some() && new_issue.invalid_year.is_none() {
-            field_matches.push(FieldMatch {
-                field: "Year",
-                old_display: format!("({})", orig.invalid_year.as_ref().unwrap()),
-                new_value: get_tag(&tag_map, &["YEAR"]).unwrap_or_default(),
-                category: "critical",
-            });
-        }
+impl ReportServer {
+    /// Binds before the scan, so a port in use fails the run up front.
+    fn bind(port: u16) -> std::io::Result<Self> {
+        Ok(Self { listener: std::net::TcpListener::bind(("0.0.0.0", port))? })
+    }
 
-        // --- MusicBrainz fields ---
-        if orig.missing_mb_artist_id && !new_issue.missing_mb_artist_id {
-            field_matches.push(FieldMatch {
-                field: "MB Artist ID",
-                old_display: "Missing".into(),
-                new_value: get_tag(&tag_map, &["MUSICBRAINZ ARTIST ID", "MUSICBRAINZ_ARTISTID", "MUSICBRAINZARTISTID"]).unwrap_or_default(),
-                category: "mb",
-            });
-        }
-        if orig.missing_mb_track_id && !new_issue.missing_mb_track_id {
-            field_matches.push(FieldMatch {
-                field: "MB Track ID",
-                old_display: "Missing".into(),
-                new_value: get_tag(&tag_map, &["MUSICBRAINZ RELEASE TRACK ID", "MUSICBRAINZ_TRACKID", "MUSICBRAINZTRACKID", "MUSICBRAINZ_RELEASETRACKID"]).unwrap_or_default(),
-                category: "mb",
-            });
-        }
-        if orig.missing_mb_album_id && !new_issue.missing_mb_album_id {
-            field_matches.push(FieldMatch {
-                field: "MB Album ID",
-                old_display: "Missing".into(),
-                new_value: get_tag(&tag_map, &["MUSICBRAINZ ALBUM ID", "MUSICBRAINZ_ALBUMID", "MUSICBRAINZALBUMID", "MUSICBRAINZRELEASEID"]).unwrap_or_default(),
-                category: "mb",
-            });
-        }
+    fn port(&self) -> u16 {
+        self.listener.local_addr().map(|a| a.port()).unwrap_or(0)
+    }
 
-        // --- Discogs fields ---
-        if orig.missing_discogs_artist && !new_issue.missing_discogs_artist {
-            field_matches.push(FieldMatch {
-                field: "Discogs Artist",
-                old_display: "Missing".into(),
-                new_value: get_tag(&tag_map, &["URL_DISCOGS_ARTIST_SITE", "WWW DISCOGS_ARTIST"]).unwrap_or_default(),
-                category: "discogs",
-            });
-        }
-        if orig.missing_discogs_release && !new_issue.missing_discogs_release {
-            field_matches.push(FieldMatch {
-                field: "Discogs Release",
-                old_display: "Missing".into(),
-                new_value: get_tag(&tag_map, &["URL_DISCOGS_RELEASE_SITE", "WWW DISCOGS_RELEASE"]).unwrap_or_default(),
-                category: "discogs",
+    /// Answers requests for files under `root` until the process ends.
+    fn serve(self, root: PathBuf) {
+        let root = std::sync::Arc::new(root);
+        for stream in self.listener.incoming().flatten() {
+            let root = root.clone();
+            std::thread::spawn(move || {
+                stream.set_read_timeout(Some(Duration::from_secs(10))).ok();
+                if let Err(e) = answer_request(stream, &root) {
+                    eprintln!("  Serve error: {}", e);
+                }
             });
         }
+    }
+}
 
-        // --- ID fields ---
-        if orig.missing_acoustic_id && !new_issue.missing_acoustic_id {
-            field_matches.push(FieldMatch {
-                field: "Acoustic ID",
-                old_display: "Missing".into(),
-                new_value: get_tag(&tag_map, &["ACOUSTIC_ID", "ACOUSTIC ID", "ACOUSTID_ID", "ACOUSTID ID"]).unwrap_or_default(),
-                category: "ids",
-            });
-        }
-        if orig.missing_songkong_id && !new_issue.missing_songkong_id {
-            field_matches.push(FieldMatch {
-                field: "SongKong ID",
-                old_display: "Missing".into(),
-                new_value: get_tag(&tag_map, &["SONGKONG_ID", "SONGKONGID"]).unwrap_or_default(),
-                category: "ids",
-            });
-        }
-        if orig.missing_bandcamp && !new_issue.missing_bandcamp {
-            field_matches.push(FieldMatch {
-                field: "Bandcamp",
-                old_display: "Missing".into(),
-                new_value: get_tag(&tag_map, &["URL_BANDCAMP_ARTIST_SITE", "WWW BANDCAMP_ARTIST"]).unwrap_or_default(),
-                category: "ids",
-            });
+/// The file a request target stands for: percent-decoded, query dropped,
+/// `index.html` for folders. `None` for anything that could leave `root`.
+fn served_file(root: &Path, target: &str) -> Option<PathBuf> {
+    let path = target.split(['?', '#']).next().unwrap_or("");
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = (bytes[i] == b'%').then(|| bytes.get(i + 1..i + 3)).flatten()
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match hex {
+            Some(b) => { decoded.push(b); i += 3; }
+            None => { decoded.push(bytes[i]); i += 1; }
         }
-        if orig.missing_wikipedia_artist && !new_issue.missing_wikipedia_artist {
-            field_matches.push(FieldMatch {
-                field: "Wikipedia Artist",
-                old_display: "Missing".into(),
-                new_value: get_tag(&tag_map, &["WWW WIKIPEDIA_ARTIST"]).unwrap_or_default(),
-                category: "ids",
-            });
+    }
+    let decoded = String::from_utf8(decoded).ok()?;
+    let mut file = root.to_path_buf();
+    for part in decoded.split('/').filter(|p| !p.is_empty() && *p != ".") {
+        if part == ".." || part.contains('\\') || part.contains(':') {
+            return None;
         }
+        file.push(part);
+    }
+    if file.is_dir() {
+        file.push("index.html");
+    }
+    Some(file)
+}
 
-        // --- Other fields ---
-        if orig.missing_genre && !new_issue.missing_genre {
-            field_matches.push(FieldMatch {
-                field: "Genre",
-                old_display: "Missing".into(),
-                new_value: get_tag(&tag_map, &["GENRE"]).unwrap_or_default(),
-                category: "other",
-            });
-        }
-        if orig.blank_genre && !new_issue.blank_genre {
-            field_matches.push(FieldMatch {
-                field: "Genre",
-                old_display: "(blank)".into(),
-                new_value: get_tag(&tag_map, &["GENRE"]).unwrap_or_default(),
-                category: "other",
-            });
+/// Reads one request from `stream` and answers it.
+fn answer_request(mut stream: std::net::TcpStream, root: &Path) -> std::io::Result<()> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") && head.len() < 16 * 1024 {
+        let n = stream.read(&mut buf)?;
+        if n == 0 { break; }
+        head.extend_from_slice(&buf[..n]);
+    }
+    let head = String::from_utf8_lossy(&head);
+    let mut request_line = head.lines().next().unwrap_or("").split_whitespace();
+    let (method, target) = (request_line.next().unwrap_or(""), request_line.next().unwrap_or("/"));
+
+    let (status, content_type, body) = if method != "GET" && method != "HEAD" {
+        ("405 Method Not Allowed", "text/plain; charset=utf-8", b"Method not allowed\n".to_vec())
+    } else {
+        match served_file(root, target).map(|file| fs::read(&file).map(|body| (file, body))) {
+            Some(Ok((file, body))) => ("200 OK", s3::content_type(&file), body),
+            _ => ("404 Not Found", "text/plain; charset=utf-8", b"Not found\n".to_vec()),
         }
-        if orig.missing_bpm && !new_issue.missing_bpm {
-            field_matches.push(FieldMatch {
-                field: "BPM",
-                old_display: "Missing".into(),
-                new_value: get_tag(&tag_map, &["BPM"]).unwrap_or_default(),
-                category: "other",
-            });
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+        status, content_type, body.len(),
+    )?;
+    if method != "HEAD" {
+        stream.write_all(&body)?;
+    }
+    stream.flush()
+}
+
+// ---------------------------------------------------------------------------
+// Notifications (--notify-webhook, --notify-email)
+// ---------------------------------------------------------------------------
+
+/// A finished run, as sent to the webhook and by email.
+struct RunNotice<'a> {
+    scan_root: &'a str,
+    elapsed: Duration,
+    counts: &'a [(&'static str, &'static str, u64)],
+    /// `None` with --no-report
+    report: Option<&'a Path>,
+    /// With --publish-s3 and S3_PUBLIC_URL
+    report_url: Option<&'a str>,
+}
+
+impl RunNotice<'_> {
+    fn count(&self, key: &str) -> u64 {
+        self.counts.iter().find(|(k, _, _)| *k == key).map_or(0, |(_, _, n)| *n)
+    }
+
+    /// One line for chat webhooks and the email subject.
+    fn headline(&self) -> String {
+        format!(
+            "{} files with issues ({} critical), {} unreadable of {} in {}",
+            self.count("issues"), self.count("critical"), self.count("unreadable"), self.count("files"), self.scan_root
+        )
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let counts: serde_json::Map<String, serde_json::Value> = self.counts.iter()
+            .map(|(key, _, n)| (key.to_string(), serde_json::Value::from(*n)))
+            .collect();
+        serde_json::json!({
+            "event": "analysis.finished",
+            "text": format!("{}: {}", REPORT_TITLE, self.headline()),
+            "scanRoot": self.scan_root,
+            "host": host_name(),
+            "finished": Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+            "elapsedSeconds": (self.elapsed.as_secs_f64() * 10.0).round() / 10.0,
+            "reportPath": self.report.map(|p| fs::canonicalize(p).unwrap_or_else(|_| p.to_path_buf()).display().to_string()),
+            "reportUrl": self.report_url,
+            "counts": counts,
+        })
+    }
+
+    fn to_text(&self) -> String {
+        let mut text = format!("{}\n\nScan root : {}\nHost      : {}\nElapsed   : {:.1}s\n",
+            self.headline(), self.scan_root, host_name(), self.elapsed.as_secs_f64());
+        match (self.report_url, self.report) {
+            (Some(url), _) => text.push_str(&format!("Report    : {}\n", url)),
+            (None, Some(dir)) => text.push_str(&format!("Report    : {}\n", dir.display())),
+            (None, None) => {}
         }
-        if orig.missing_mood && !new_issue.missing_mood {
-            field_matches.push(FieldMatch {
-                field: "Mood",
-                old_display: "Missing".into(),
-                new_value: "Present".into(),
-                category: "other",
-            });
+        text.push('\n');
+        for (_, label, n) in self.counts {
+            text.push_str(&format!("{:<20} {:>8}\n", label, n));
         }
-        if orig.missing_album_art && !new_issue.missing_album_art {
-            field_matches.push(FieldMatch {
-                field: "Album Art",
-                old_display: "Missing".into(),
-                new_value: "Embedded".into(),
-                category: "other",
-            });
+        text
+    }
+}
+
+/// POSTs the run summary as JSON; any status other than 2xx is an error.
+fn send_webhook(url: &str, notice: &RunNotice, ca_bundle: Option<&s3::CaBundle>) -> Result<(), String> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| e.to_string())?;
+    let client = mb::http_client(ca_bundle);
+    runtime.block_on(async {
+        let resp = client.post(url).json(&notice.to_json()).send().await
+            .map_err(|e| s3::error_chain(&e))?;
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("HTTP {}", resp.status().as_u16()))
         }
+    })
+}
 
-        if !field_matches.is_empty() {
-            diffs.insert(orig.path.clone(), field_matches);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SmtpSecurity {
+    /// TLS from the first byte (port 465)
+    Tls,
+    /// Plain connection upgraded with STARTTLS (port 587)
+    StartTls,
+    /// No encryption (a relay on the LAN, port 25)
+    None,
+}
+
+/// The SMTP server of --notify-email, from web/.env.
+#[derive(Debug)]
+struct SmtpSettings {
+    host: String,
+    port: u16,
+    security: SmtpSecurity,
+    credentials: Option<(String, String)>,
+    from: String,
+}
+
+impl SmtpSettings {
+    /// SMTP_HOST (required), SMTP_PORT (587), SMTP_SECURITY (tls, starttls or
+    /// none; tls on port 465, else starttls), SMTP_USER / SMTP_PASSWORD and
+    /// SMTP_FROM (defaults to SMTP_USER).
+    fn from_env() -> Result<Self, String> {
+        let var = |name: &str| std::env::var(name).ok().map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        let host = var("SMTP_HOST").ok_or("SMTP_HOST is not set (web/.env)")?;
+        let port = match var("SMTP_PORT") {
+            Some(port) => port.parse().map_err(|_| format!("SMTP_PORT is not a port number: {}", port))?,
+            None => 587,
+        };
+        let security = match var("SMTP_SECURITY").map(|v| v.to_lowercase()).as_deref() {
+            None if port == 465 => SmtpSecurity::Tls,
+            None => SmtpSecurity::StartTls,
+            Some("tls") => SmtpSecurity::Tls,
+            Some("starttls") => SmtpSecurity::StartTls,
+            Some("none") => SmtpSecurity::None,
+            Some(other) => return Err(format!("SMTP_SECURITY must be tls, starttls or none (got {})", other)),
+        };
+        let user = var("SMTP_USER");
+        let credentials = user.clone().map(|user| (user, var("SMTP_PASSWORD").unwrap_or_default()));
+        let from = var("SMTP_FROM").or(user).ok_or("SMTP_FROM (or SMTP_USER) is not set (web/.env)")?;
+        from.parse::<lettre::message::Mailbox>().map_err(|e| format!("SMTP_FROM {}: {}", from, e))?;
+        Ok(SmtpSettings { host, port, security, credentials, from })
+    }
+
+    fn describe(&self) -> String {
+        let security = match self.security {
+            SmtpSecurity::Tls => "TLS",
+            SmtpSecurity::StartTls => "STARTTLS",
+            SmtpSecurity::None => "unencrypted",
+        };
+        format!("{}:{} ({}), from {}", self.host, self.port, security, self.from)
+    }
+}
+
+/// Mails the run summary as plain text to every address in `to`.
+fn send_email(settings: &SmtpSettings, to: &[String], notice: &RunNotice) -> Result<(), String> {
+    use lettre::message::{header::ContentType, Mailbox};
+    use lettre::transport::smtp::authentication::Credentials;
+    use lettre::{Message, SmtpTransport, Transport};
+
+    let mut message = Message::builder()
+        .from(settings.from.parse::<Mailbox>().map_err(|e| e.to_string())?)
+        .subject(format!("[{}] {}", REPORT_TITLE, notice.headline()))
+        .header(ContentType::TEXT_PLAIN);
+    for address in to {
+        message = message.to(address.parse::<Mailbox>().map_err(|e| format!("{}: {}", address, e))?);
+    }
+    let email = message.body(notice.to_text()).map_err(|e| e.to_string())?;
+
+    let mut transport = match settings.security {
+        SmtpSecurity::Tls => SmtpTransport::relay(&settings.host).map_err(|e| e.to_string())?,
+        SmtpSecurity::StartTls => SmtpTransport::starttls_relay(&settings.host).map_err(|e| e.to_string())?,
+        SmtpSecurity::None => SmtpTransport::builder_dangerous(&settings.host),
+    }
+    .port(settings.port)
+    .timeout(Some(Duration::from_secs(30)));
+    if let Some((user, password)) = &settings.credentials {
+        transport = transport.credentials(Credentials::new(user.clone(), password.clone()));
+    }
+    transport.build().send(&email).map(|_| ()).map_err(|e| e.to_string())
+}
+
+// ---------------------------------------------------------------------------
+// Hook commands (--on-complete, --on-issue-threshold)
+// ---------------------------------------------------------------------------
+
+/// `--on-complete` / the command of `--on-issue-threshold`, split like a
+/// shell would (quotes, but no globs or pipes; wrap those in `sh -c`).
+#[derive(Debug, Clone, PartialEq)]
+struct HookCommand(Vec<String>);
+
+impl HookCommand {
+    fn parse(value: &str) -> Result<Self, String> {
+        match shlex::split(value) {
+            Some(words) if !words.is_empty() => Ok(Self(words)),
+            Some(_) => Err("empty command".to_string()),
+            None => Err(format!("unbalanced quotes in '{}'", value)),
         }
+    }
 
-        if new_issue.has_any_issue() {
-            still_broken.push(new_issue);
+    /// The command line as printed in the header and in errors.
+    fn line(&self) -> String {
+        shlex::try_join(self.0.iter().map(String::as_str)).unwrap_or_else(|_| self.0.join(" "))
+    }
+
+    /// Runs the command with `summary` appended, its output going to the
+    /// terminal, and waits for it. A non-zero exit is an error.
+    fn run(&self, summary: &Path) -> Result<(), String> {
+        let status = std::process::Command::new(&self.0[0])
+            .args(&self.0[1..])
+            .arg(summary)
+            .status()
+            .map_err(|e| e.to_string())?;
+        if status.success() {
+            Ok(())
         } else {
-            matched.push(orig.path.clone());
+            Err(format!("exited with {}", status))
         }
     }
+}
 
-    (matched, still_broken, unreadable, diffs, skipped_files)
+/// `--on-issue-threshold N:CMD`: run CMD once at least `threshold` files have issues.
+#[derive(Debug, Clone, PartialEq)]
+struct IssueHook {
+    threshold: u64,
+    command: HookCommand,
+}
+
+impl IssueHook {
+    fn parse(value: &str) -> Result<Self, String> {
+        let (threshold, command) = value.split_once(':')
+            .ok_or_else(|| format!("'{}' is not N:CMD", value))?;
+        let threshold = threshold.trim().parse()
+            .map_err(|_| format!("'{}' is not a file count", threshold.trim()))?;
+        Ok(Self { threshold, command: HookCommand::parse(command)? })
+    }
 }
 
 // ---------------------------------------------------------------------------
-// Quarantine helpers
+// Watch mode (--watch)
 // ---------------------------------------------------------------------------
 
-fn restore_dir(staging_dir: &Path, scan_root: &str, moved: &mut u32, failed: &mut u32) {
-    if !staging_dir.exists() {
-        return;
+/// The library as last scanned while watching: readable files as cache
+/// entries (stamp, issue, tag values), unreadable ones with their error.
+#[derive(Debug, Default)]
+struct WatchState {
+    files: HashMap<PathBuf, CachedScan>,
+    unreadable: HashMap<PathBuf, String>,
+}
+
+/// What one batch of file events changed.
+#[derive(Debug, Default)]
+struct WatchChanges {
+    /// New or modified files parsed again
+    scanned: usize,
+    /// Files gone from disk (deleted, or moved out of the scan root)
+    removed: usize,
+    /// This batch's results, merged into `--cache`
+    entries: Vec<CachedScan>,
+}
+
+impl WatchChanges {
+    fn is_empty(&self) -> bool {
+        self.scanned == 0 && self.removed == 0
     }
+}
 
-    println!("Moving files from {} back to original locations...", staging_dir.display());
+/// Everything needed to rewrite the report the way the initial run wrote it.
+struct WatchOptions<'a> {
+    scan_root: &'a str,
+    report_dir: &'a Path,
+    pages: &'a PageFlags,
+    /// Quiet time after the last event before a batch is processed
+    debounce: Duration,
+    filter: &'a FolderFilter,
+    follow_links: bool,
+    handled: &'a HashSet<String>,
+    protected: &'a Protected,
+    /// From the initial walk; not tracked while watching
+    link_dupes: &'a [LinkDuplicate],
+    style_lints: &'a StyleLints,
+    quality: bool,
+    lone_files: bool,
+    beets_export: bool,
+    cache: Option<&'a str>,
+    digest: Option<&'a str>,
+    /// Runs up to the initial scan; the latest one is replaced by the live state
+    trend: &'a [HistoryEntry],
+}
 
-    for entry in WalkDir::new(staging_dir)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.file_type().is_file())
-    {
-        let src = entry.path();
-        let rel = match src.strip_prefix(staging_dir) {
-            Ok(r) => r,
-            Err(_) => continue,
-        };
-        let dst = PathBuf::from(scan_root).join(rel);
+impl WatchState {
+    fn new(scanned: &[CachedScan], unreadable: &[(PathBuf, String)]) -> Self {
+        WatchState {
+            files: scanned.iter().map(|c| (c.issue.path.clone(), c.clone())).collect(),
+            unreadable: unreadable.iter().cloned().collect(),
+        }
+    }
 
-        if let Some(dst_parent) = dst.parent() {
-            if let Err(e) = fs::create_dir_all(dst_parent) {
-                eprintln!("  FAILED to create {}: {}", dst_parent.display(), e);
-                *failed += 1;
-                continue;
+    fn total_files(&self) -> usize {
+        self.files.len() + self.unreadable.len()
+    }
+
+    /// Brings the state up to date with the paths events were reported for:
+    /// audio files, or whole folders (a rip dropped in, a folder renamed).
+    /// Paths no longer on disk drop every file at or under them. Files whose
+    /// size and mtime haven't changed are skipped, so the scanner's own reads
+    /// (and `touch`) don't cause a re-scan.
+    fn apply(
+        &mut self,
+        paths: &BTreeSet<PathBuf>,
+        scan_root: &str,
+        filter: &FolderFilter,
+        follow_links: bool,
+    ) -> WatchChanges {
+        let mut changes = WatchChanges::default();
+        let mut candidates: BTreeSet<PathBuf> = BTreeSet::new();
+        for path in paths {
+            if !path.exists() {
+                let before = self.total_files();
+                self.files.retain(|p, _| !p.starts_with(path));
+                self.unreadable.retain(|p, _| !p.starts_with(path));
+                changes.removed += before - self.total_files();
+            } else if path.is_dir() {
+                candidates.extend(
+                    WalkDir::new(path)
+                        .follow_links(follow_links)
+                        .into_iter()
+                        .filter_map(|e| e.ok())
+                        .filter(|e| !e.file_type().is_dir() && (follow_links || !e.path_is_symlink()))
+                        .map(|e| e.into_path()),
+                );
+            } else if follow_links || !path.is_symlink() {
+                candidates.insert(path.clone());
             }
         }
 
-        match fs::rename(src, &dst) {
-            Ok(_) => {
-                println!("  Restored: {} -> {}", src.display(), dst.display());
-                *moved += 1;
+        let changed: Vec<(PathBuf, FileStamp)> = candidates
+            .into_iter()
+            .filter(|p| is_audio_file(p) && filter.matches(&get_artist_folder(p, scan_root)))
+            .filter(|p| !excluded().is_some_and(|x| x.matches(p, scan_root)))
+            .filter_map(|p| {
+                let stamp = FileStamp::of(&p)?;
+                let unchanged = self.files.get(&p).is_some_and(|c| c.stamp == stamp);
+                (!unchanged).then_some((p, stamp))
+            })
+            .collect();
+        let scans: Vec<_> = changed
+            .into_par_iter()
+            .map(|(p, stamp)| {
+                let scan = scan_file(&p);
+                (p, stamp, scan)
+            })
+            .collect();
+
+        for (path, stamp, scan) in scans {
+            changes.scanned += 1;
+            match scan {
+                Ok((issue, values)) => {
+                    let entry = CachedScan::new(stamp, issue, &values);
+                    self.unreadable.remove(&path);
+                    self.files.insert(path, entry.clone());
+                    changes.entries.push(entry);
+                }
+                Err(err) => {
+                    eprintln!("  UNREADABLE: {} — {}", path.display(), err);
+                    self.files.remove(&path);
+                    self.unreadable.insert(path, err);
+                }
+            }
+        }
+        changes
+    }
+}
+
+/// Rewrites the report (and metrics, beets exports, digest) in place from
+/// the current state, like phases 3 and 5 of a normal run. Returns the
+/// number of files with issues.
+fn write_watch_report(state: &WatchState, options: &WatchOptions, elapsed: Duration) -> std::io::Result<usize> {
+    let scan_root = options.scan_root;
+    let mut paths: Vec<PathBuf> = state.files.keys().chain(state.unreadable.keys()).cloned().collect();
+    paths.sort();
+
+    let mut file_type_counts: HashMap<String, u64> = HashMap::new();
+    let mut parent_audio_count: HashMap<PathBuf, usize> = HashMap::new();
+    for p in &paths {
+        if let Some(ext) = p.extension() {
+            *file_type_counts.entry(ext.to_string_lossy().to_ascii_uppercase()).or_insert(0) += 1;
+        }
+        if let Some(parent) = p.parent() {
+            *parent_audio_count.entry(parent.to_path_buf()).or_insert(0) += 1;
+        }
+    }
+    let mut value_counts = ValueCounts::new();
+    for cached in state.files.values() {
+        for value in cached.tag_values() {
+            *value_counts.entry(value).or_insert(0) += 1;
+        }
+    }
+    let total_files = paths.len() as u64;
+    let total_size = state.files.values().map(|c| c.issue.file_size).sum();
+    let error_count = state.unreadable.len() as u64;
+
+    let is_handled = |p: &Path| !options.handled.is_empty() && options.handled.contains(&relative_path(p, scan_root));
+    let results = state.files.values().map(|c| &c.issue).filter(|i| !is_handled(&i.path));
+    let quality_report = options.quality.then(|| find_quality_issues(results.clone()));
+    let rip_report = RipReport::collect(results.clone(), scan_root);
+    let mut issues: Vec<FileIssue> = results.filter(|i| i.has_any_issue()).cloned().collect();
+    issues.sort_by(|a, b| a.path.cmp(&b.path));
+    let mut unreadable_paths: Vec<(PathBuf, String)> = state.unreadable.iter()
+        .filter(|(p, _)| !is_handled(p))
+        .map(|(p, e)| (p.clone(), e.clone()))
+        .collect();
+    unreadable_paths.sort();
+    let report_paths: Vec<PathBuf> = paths.iter().filter(|p| !is_handled(p)).cloned().collect();
+    let variants = find_variants(&value_counts);
+    let lone_files = if options.lone_files {
+        find_lone_files(&report_paths, &parent_audio_count, scan_root)
+    } else {
+        Vec::new()
+    };
+    let with_style = !options.style_lints.is_empty();
+    let metrics_time = Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false);
+    let counts = run_counts(&issues, total_files, error_count, with_style);
+    let provenance = Provenance::collect(options.style_lints);
+    let mut trend = options.trend.to_vec();
+    if let Some(latest) = trend.last_mut() {
+        *latest = HistoryEntry::new(&metrics_time, &latest.scan_root, &provenance.rules_hash, elapsed,
+            Health::of(&issues, total_files, error_count), &counts);
+    }
+
+    let data = ReportData {
+        issues: &issues,
+        all_paths: &report_paths,
+        lone_files: &lone_files,
+        unreadable: &unreadable_paths,
+        link_dupes: options.link_dupes,
+        scan_root,
+        total_files,
+        total_size,
+        error_count,
+        file_type_counts: &file_type_counts,
+        elapsed,
+        diffs: None,
+        skipped_files: None,
+        mirror: None,
+        archives: None,
+        fingerprints: None,
+        duplicates: None,
+        quality: quality_report.as_ref(),
+        rips: &rip_report,
+        variants: &variants,
+        provenance: &provenance,
+        protected: &options.protected.counts(&issues, scan_root),
+        trend: &trend,
+    };
+    generate_report(&data, options.report_dir, options.pages)?;
+
+    let metrics = metric_rows(&counts, None);
+    write_metrics(options.report_dir, &metrics_time, &metrics)?;
+    if options.beets_export {
+        write_beets_exports(&options.report_dir.join("beets"), &beets_exports(&issues))?;
+    }
+    if let Some(digest) = options.digest {
+        write_digest(Path::new(digest), scan_root, &issues, &report_paths, total_files, error_count, with_style)?;
+    }
+    Ok(issues.len())
+}
+
+/// Watches the scan root until interrupted. Events are collected until none
+/// arrived for `debounce`, then the batch is re-scanned and the report
+/// rewritten, with a one-line summary per batch (`issues`: the count the
+/// initial run reported, for the first change).
+fn watch_library(mut state: WatchState, issues: usize, options: &WatchOptions) -> Result<(), String> {
+    use notify::{EventKind, RecursiveMode, Watcher};
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).map_err(|e| e.to_string())?;
+    watcher.watch(Path::new(options.scan_root), RecursiveMode::Recursive).map_err(|e| e.to_string())?;
+    println!();
+    println!("[Watch] Watching {} (Ctrl-C to stop)", options.scan_root);
+
+    let rules = rules_signature(options.style_lints);
+    let mut issue_count = issues;
+    loop {
+        let mut pending: BTreeSet<PathBuf> = BTreeSet::new();
+        let mut next = rx.recv().map_err(|e| e.to_string())?;
+        loop {
+            match next {
+                Ok(event) => {
+                    // Reads (ours included) change nothing
+                    if !matches!(event.kind, EventKind::Access(_)) {
+                        pending.extend(event.paths.into_iter().filter(|p| !p.starts_with(options.report_dir)));
+                    }
+                }
+                Err(e) => eprintln!("  Watch error: {}", e),
             }
+            next = match rx.recv_timeout(options.debounce) {
+                Ok(next) => next,
+                Err(std::sync::mpsc::RecvTimeoutError::Timeout) => break,
+                Err(e) => return Err(e.to_string()),
+            };
+        }
+        if pending.is_empty() {
+            continue;
+        }
+
+        let start = Instant::now();
+        let changes = state.apply(&pending, options.scan_root, options.filter, options.follow_links);
+        if changes.is_empty() {
+            continue;
+        }
+        let count = match write_watch_report(&state, options, start.elapsed()) {
+            Ok(count) => count,
             Err(e) => {
-                eprintln!("  FAILED to move {}: {}", src.display(), e);
-                *failed += 1;
+                eprintln!("  Failed to write report: {}", e);
+                continue;
+            }
+        };
+        let delta = if count == issue_count {
+            String::new()
+        } else {
+            format!(" ({:+})", count as i64 - issue_count as i64)
+        };
+        issue_count = count;
+        println!("[Watch] {}  {} scanned, {} removed | Files: {} | Issues: {}{} | Unreadable: {}",
+            Local::now().format("%H:%M:%S"), changes.scanned, changes.removed,
+            state.total_files(), count, delta, state.unreadable.len());
+
+        if let Some(file) = options.cache {
+            let path = Path::new(file);
+            let cache = ScanCache::load(path, &rules).unwrap_or_default();
+            if let Err(e) = cache.save(path, &rules, changes.entries, None) {
+                eprintln!("  Failed to write cache {}: {}", file, e);
             }
         }
     }
-
-    remove_empty_dirs(staging_dir);
-    let _ = fs::remove_dir(staging_dir);
 }
 
-fn end_quarantine(scan_root: &str) {
-    let quarantine_dir    = PathBuf::from(scan_root).join("__QUARANTINE");
-    let needs_review_dir  = PathBuf::from(scan_root).join("__NEEDS_REVIEW");
-    let unreadable_dir    = PathBuf::from(scan_root).join("__UNREADABLE");
-    let autofixed_dir     = PathBuf::from(scan_root).join("__AUTOFIXED");
+// ---------------------------------------------------------------------------
+// Main
+// ---------------------------------------------------------------------------
 
-    if !quarantine_dir.exists() && !needs_review_dir.exists()
-        && !unreadable_dir.exists() && !autofixed_dir.exists()
-    {
-        println!("Nothing to do: no staging folders found.");
+fn main() {
+    if std::env::args().nth(1).as_deref() == Some("diff") {
+        run_diff(DiffArgs::parse_from(std::env::args().skip(1)));
         return;
     }
+    let mut args = Args::parse();
+    let scan_root = args.scan_path.trim_end_matches('/').to_string();
 
-    let mut moved = 0u32;
-    let mut failed = 0u32;
-
-    restore_dir(&quarantine_dir,   scan_root, &mut moved, &mut failed);
-    restore_dir(&needs_review_dir, scan_root, &mut moved, &mut failed);
-    restore_dir(&unreadable_dir,   scan_root, &mut moved, &mut failed);
-    restore_dir(&autofixed_dir,    scan_root, &mut moved, &mut failed);
+    if args.end_quarantine {
+        end_quarantine(&scan_root, &FolderFilter::new(&args.only, &args.from, &args.to), args.category.as_deref());
+        return;
+    }
 
-    println!("Done. Restored: {}, Failed: {}", moved, failed);
-}
+    println!("Audio Metadata Scanner");
+    println!("======================");
+    println!("Scan root : {}", scan_root);
+    if !args.unc_prefix.is_empty() {
+        println!("UNC prefix: {}", args.unc_prefix);
+    }
+    if args.limit > 0 {
+        println!("Limit     : {} files", args.limit);
+    }
+    // Handle --autofix / --autofix-dry + --only-* interaction
+    let do_autofix = args.autofix || args.autofix_dry;
+    {
+        let any_only = args.only_critical || args.only_mb || args.only_discogs
+            || args.only_issues || args.only_ids || args.only_other || args.only_style
+            || args.only_consistency || args.only_albums;
 
-/// Recursively remove empty directories (deepest first).
-fn remove_empty_dirs(dir: &Path) {
-    if let Ok(entries) = fs::read_dir(dir) {
-        for entry in entries.filter_map(|e| e.ok()) {
-            let path = entry.path();
-            if path.is_dir() {
-                remove_empty_dirs(&path);
-                let _ = fs::remove_dir(&path); // silently fails if not empty
+        if do_autofix && any_only {
+            println!("Autofix enabled, skipping --only-* commands");
+            args.only_critical = false;
+            args.only_mb       = false;
+            args.only_discogs  = false;
+            args.only_issues   = false;
+            args.only_ids      = false;
+            args.only_other    = false;
+            args.only_style    = false;
+            args.only_consistency = false;
+            args.only_albums   = false;
+        } else if any_only {
+            let mut modes = Vec::new();
+            if args.only_critical { modes.push("critical"); }
+            if args.only_mb       { modes.push("mb"); }
+            if args.only_discogs  { modes.push("discogs"); }
+            if args.only_issues   { modes.push("issues"); }
+            if args.only_ids      { modes.push("ids"); }
+            if args.only_other    { modes.push("other"); }
+            if args.only_style    { modes.push("style"); }
+            if args.only_consistency { modes.push("consistency"); }
+            if args.only_albums   { modes.push("albums"); }
+            println!("Pages     : {}", modes.join(", "));
+        }
+    }
+    let protected = Protected::new(&args.protect);
+    if !protected.is_empty() {
+        let patterns: Vec<&str> = protected.patterns.iter().map(|(p, _)| p.as_str()).collect();
+        println!("Protect   : {}", patterns.join(", "));
+    }
+    match Excluded::load(&args.exclude, &scan_root) {
+        Ok(excluded) => {
+            if !excluded.is_empty() {
+                let patterns: Vec<&str> = excluded.patterns.iter().map(|(p, _)| p.as_str()).collect();
+                println!("Exclude   : {}", patterns.join(", "));
+            }
+            EXCLUDED.set(excluded).ok();
+        }
+        Err(e) => {
+            eprintln!("Cannot read {}", e);
+            std::process::exit(1);
+        }
+    }
+    let tagger = if args.autofix_native {
+        // MB_URL and DMP_CA_BUNDLE come from web/.env, like for sync
+        s3::load_web_env();
+        let mb_url = args.mb_url.clone()
+            .or_else(|| std::env::var("MB_URL").ok().filter(|s| !s.trim().is_empty()));
+        if let Some(url) = mb_url {
+            mb::set_base(mb::service_url(&url));
+        }
+        let ca_bundle = match s3::ca_bundle_path().as_deref().map(s3::CaBundle::read).transpose() {
+            Ok(bundle) => bundle,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        };
+        match NativeTagger::new(ca_bundle.as_ref()) {
+            Ok(native) => Tagger::Native(native),
+            Err(e) => {
+                eprintln!("Invalid autofix tagger: {}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        match TaggerCommand::from_args(args.autofix_cmd.as_deref(), args.autofix_config.as_deref()) {
+            Ok(Some(command)) => Tagger::Command(command),
+            Ok(None) => Tagger::Beets,
+            Err(e) => {
+                eprintln!("Invalid autofix tagger: {}", e);
+                std::process::exit(1);
+            }
+        }
+    };
+    if args.autofix {
+        println!("Autofix   : enabled ({})", tagger.name());
+    } else if args.autofix_dry {
+        match &tagger {
+            Tagger::Beets => println!("Autofix   : dry run (beets --pretend)"),
+            Tagger::Command(c) if c.dry_command.is_some() => println!("Autofix   : dry run ({} dry_command)", c.name),
+            Tagger::Command(c) => println!("Autofix   : dry run (printing {} commands)", c.name),
+            Tagger::Native(_) => println!("Autofix   : dry run (MusicBrainz lookups, nothing written)"),
+        }
+    }
+    if do_autofix {
+        if let Tagger::Native(_) = tagger {
+            println!("MB server : {}", mb::base());
+            // One shared rate limit: more jobs would only queue behind it
+            if args.autofix_jobs > 1 {
+                println!("Jobs      : 1 (--autofix-jobs ignored, one MusicBrainz request a second)");
+                args.autofix_jobs = 1;
+            }
+        }
+    }
+    if args.no_report {
+        println!("Report    : disabled");
+    } else if args.single_file {
+        println!("Report    : + single file (analysis_<timestamp>.html next to the report folder)");
+        SINGLE_FILE.store(true, Ordering::Relaxed);
+    }
+    let page_budget = PageBudget { bytes: args.page_size, files: args.page_files, ..PageBudget::default() };
+    if !args.no_report && (parse_page_size(DEFAULT_PAGE_SIZE) != Ok(args.page_size) || args.page_files > 0) {
+        let mut limits = Vec::new();
+        if page_budget.bytes > 0 {
+            limits.push(human_size(page_budget.bytes as u64));
+        }
+        if page_budget.files > 0 {
+            limits.push(format!("{} files", page_budget.files));
+        }
+        limits.push(format!("{} artists", page_budget.groups));
+        println!("Page size : at most {} per page", limits.join(", "));
+    }
+    PAGE_BUDGET.set(page_budget).ok();
+    if let Some(ref digest) = args.digest {
+        println!("Digest    : {}", digest);
+    }
+    if let Some(ref history) = args.metrics_csv {
+        println!("Metrics   : appending to {}", history);
+    }
+    let partial_scan = args.limit > 0 || args.paths_from.is_some()
+        || !args.only.is_empty() || !args.from.is_empty() || !args.to.is_empty();
+    if args.no_history {
+        println!("History   : off (--no-history)");
+    } else if partial_scan {
+        println!("History   : not recorded (partial scan)");
+    }
+    if args.beets_export {
+        println!("Beets     : {}", if args.no_report { "export skipped (--no-report)" } else { "export file lists + queries" });
+    }
+    if let Some(ref prefix) = args.publish_s3 {
+        if args.no_report {
+            println!("Publish   : skipped (--no-report)");
+        } else {
+            s3::load_web_env();
+            match s3::S3Config::from_env() {
+                Some(config) => {
+                    println!("Publish   : s3://{}/{}", config.bucket, prefix.trim_matches('/'));
+                    if let Some((var, url)) = s3::proxy_from_env() {
+                        println!("Proxy     : {} ({})", url, var);
+                    }
+                    if let Some(ref path) = config.ca_bundle {
+                        match s3::CaBundle::read(path) {
+                            Ok(bundle) => println!("CA bundle : {} ({} certificate(s))", path.display(), bundle.certificates),
+                            Err(e) => {
+                                eprintln!("{}", e);
+                                std::process::exit(1);
+                            }
+                        }
+                    }
+                }
+                None => {
+                    eprintln!("--publish-s3 needs S3_IMAGE_BUCKET and AWS_REGION (web/.env)");
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+    let notify_smtp = if args.notify_email.is_empty() {
+        None
+    } else {
+        s3::load_web_env();
+        let settings = SmtpSettings::from_env().and_then(|settings| {
+            match args.notify_email.iter().find(|a| a.parse::<lettre::message::Mailbox>().is_err()) {
+                Some(address) => Err(format!("--notify-email: not an email address: {}", address)),
+                None => Ok(settings),
+            }
+        });
+        match settings {
+            Ok(settings) => {
+                println!("Notify    : email {} via {}", args.notify_email.join(", "), settings.describe());
+                Some(settings)
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    };
+    let notify_ca = if let Some(ref url) = args.notify_webhook {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            eprintln!("--notify-webhook needs an http:// or https:// URL (got {})", url);
+            std::process::exit(1);
+        }
+        println!("Notify    : POST {}", url);
+        // DMP_CA_BUNDLE comes from web/.env, like for --publish-s3
+        s3::load_web_env();
+        match s3::ca_bundle_path().as_deref().map(s3::CaBundle::read).transpose() {
+            Ok(bundle) => bundle,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+    if let Some(ref command) = args.on_complete {
+        println!("Hook      : {} (on complete)", command.line());
+    }
+    for hook in &args.on_issue_threshold {
+        println!("Hook      : {} (at {}+ files with issues)", hook.command.line(), hook.threshold);
+    }
+    if let Some(ref file) = args.rules {
+        match Rules::load(file) {
+            Ok(rules) => {
+                let categories: Vec<&str> = rules.categories.iter().map(|c| c.title.as_str()).collect();
+                if categories.is_empty() {
+                    println!("Rules     : {}", file);
+                } else {
+                    println!("Rules     : {} (+ {})", file, categories.join(", "));
+                }
+                RULES.set(rules).ok();
+            }
+            Err(e) => {
+                eprintln!("Invalid --rules {}: {}", file, e);
+                std::process::exit(1);
             }
         }
     }
-}
-
-// ---------------------------------------------------------------------------
-// Main
-// ---------------------------------------------------------------------------
-
-fn main() {
-    let mut args = Args::parse();
-    let scan_root = args.scan_path.trim_end_matches('/').to_string();
-
-    if args.end_quarantine {
-        end_quarantine(&scan_root);
-        return;
+    let style_lints = match StyleLints::parse(&args.style) {
+        Ok(lints) => lints,
+        Err(e) => {
+            eprintln!("Invalid --style: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if !style_lints.is_empty() {
+        let action = if args.style_fix {
+            " (normalize)"
+        } else if args.style_fix_dry {
+            " (normalize, dry run)"
+        } else {
+            ""
+        };
+        println!("Style     : {}{}", style_lints.names().join(", "), action);
+        STYLE_LINTS.set(style_lints).ok();
+    } else if args.style_fix || args.style_fix_dry {
+        eprintln!("--style-fix needs --style to select the lints to fix");
+        std::process::exit(1);
     }
-
-    println!("Audio Metadata Scanner");
-    println!("======================");
-    println!("Scan root : {}", scan_root);
-    if !args.unc_prefix.is_empty() {
-        println!("UNC prefix: {}", args.unc_prefix);
+    if args.strip_blank_tags || args.strip_blank_tags_dry {
+        println!("Blanks    : remove blank artist / title / year / genre items{}",
+            if args.strip_blank_tags { "" } else { " (dry run)" });
     }
-    if args.limit > 0 {
-        println!("Limit     : {} files", args.limit);
+    if args.fix_trivial || args.fix_trivial_dry {
+        println!("Trivial   : remove blank items, cut dates in YEAR to the year, fill YEAR / DATE{}{}",
+            if args.normalize { " (and from the original date)" } else { "" },
+            if args.fix_trivial { "" } else { " (dry run)" });
+    } else if args.normalize {
+        eprintln!("--normalize needs --fix-trivial or --fix-trivial-dry");
+        std::process::exit(1);
     }
-    // Handle --autofix / --autofix-dry + --only-* interaction
-    let do_autofix = args.autofix || args.autofix_dry;
-    {
-        let any_only = args.only_critical || args.only_mb || args.only_discogs
-            || args.only_issues || args.only_ids || args.only_other;
-
-        if do_autofix && any_only {
-            println!("Autofix enabled, skipping --only-* commands");
-            args.only_critical = false;
-            args.only_mb       = false;
-            args.only_discogs  = false;
-            args.only_issues   = false;
-            args.only_ids      = false;
-            args.only_other    = false;
-        } else if any_only {
-            let mut modes = Vec::new();
-            if args.only_critical { modes.push("critical"); }
-            if args.only_mb       { modes.push("mb"); }
-            if args.only_discogs  { modes.push("discogs"); }
-            if args.only_issues   { modes.push("issues"); }
-            if args.only_ids      { modes.push("ids"); }
-            if args.only_other    { modes.push("other"); }
-            println!("Pages     : {}", modes.join(", "));
+    if args.migrate_tags {
+        println!("Tags      : copy unusual containers into the canonical one");
+    }
+    if args.quality {
+        println!("Quality   : MP3s under {} kbps, mixed sample rates per folder, tracks under {}",
+            QUALITY_MIN_MP3_KBPS, format_length(QUALITY_MIN_LENGTH));
+        QUALITY_CHECKS.store(true, Ordering::Relaxed);
+    }
+    let scan_cache = args.cache.as_ref().map(|file| {
+        let cache = ScanCache::load(Path::new(file), &rules_signature(&style_lints)).unwrap_or_else(|e| {
+            eprintln!("Ignoring unreadable cache {}: {}", file, e);
+            ScanCache::default()
+        });
+        println!("Cache     : {} ({} files)", file, cache.files.len());
+        cache
+    });
+    let handled: HashSet<String> = match args.apply_handled {
+        Some(ref file) => match load_handled(file) {
+            Ok(h) => {
+                println!("Handled   : {} files from {}", h.len(), file);
+                h
+            }
+            Err(e) => {
+                eprintln!("Failed to read handled file {}: {}", file, e);
+                std::process::exit(1);
+            }
+        },
+        None => HashSet::new(),
+    };
+    let mirror_root = args.mirror.as_ref().map(|m| m.trim_end_matches('/').to_string());
+    if let Some(ref m) = mirror_root {
+        println!("Mirror    : {}", m);
+    }
+    let archives_dir = args.archives.as_ref().map(|a| a.trim_end_matches('/').to_string());
+    if let Some(ref a) = archives_dir {
+        println!("Archives  : {}", a);
+    }
+    if let Some(score) = args.dupe_by_fingerprint {
+        if !(score > 0.0 && score <= 1.0) {
+            eprintln!("--dupe-by-fingerprint needs a score above 0 and at most 1 (got {})", score);
+            std::process::exit(1);
         }
+        println!("Dupes     : acoustic, fingerprints matching at {:.2} or more", score);
     }
-    if args.autofix {
-        println!("Autofix   : enabled (beets)");
-    } else if args.autofix_dry {
-        println!("Autofix   : dry run (beets --pretend)");
+    let dupe_mode = args.detect_duplicates.as_deref().map(DupeMode::parse);
+    if let Some(mode) = dupe_mode {
+        let action = if args.quarantine_duplicates { ", extra copies moved to __DUPLICATES" } else { "" };
+        println!("Dupes     : same track by {}{}", mode.describe(), action);
     }
-    if args.no_report {
-        println!("Report    : disabled");
+    if let Some(ref list) = args.paths_from {
+        println!("Paths     : files listed in {} (no walk)", if list == "-" { "stdin" } else { list.as_str() });
     }
     if !args.only.is_empty() {
         println!("Filter    : only folders matching '{}'", args.only);
@@ -2353,91 +7749,145 @@ fn main() {
         let to_str = if args.to.is_empty() { "Z".to_string() } else { args.to.to_uppercase() };
         println!("Filter    : {} to {}", from_str, to_str);
     }
+    if args.io_limit > 0.0 || args.max_open_files > 0 {
+        let rate = if args.io_limit > 0.0 { format!("{} MB/s", args.io_limit) } else { "unlimited".to_string() };
+        let open = if args.max_open_files > 0 { args.max_open_files.to_string() } else { "unlimited".to_string() };
+        println!("IO limit  : {} | max open files: {}", rate, open);
+        IO_THROTTLE.set(IoThrottle::new(args.io_limit, args.max_open_files)).ok();
+    }
+    let report_server = args.serve.map(|port| match ReportServer::bind(port) {
+        Ok(server) => {
+            println!("Serve     : http://{}:{}/ once the report is written", host_name(), server.port());
+            server
+        }
+        Err(e) => {
+            eprintln!("--serve: can't listen on port {}: {}", port, e);
+            std::process::exit(1);
+        }
+    });
+    if args.watch {
+        println!("Watch     : re-scan changed files, rewrite the report {}s after the last change", args.watch_debounce);
+    }
     println!("CPU cores : {}", num_cpus::get());
     println!();
 
     let start = Instant::now();
 
     // --- Phase 1: Collect file paths ---
-    println!("[1/4] Walking directory tree...");
-    let extensions = ["mp3", "m4a", "opus", "aac", "ogg", "flac"];
     let total_dirs = AtomicU64::new(0);
 
     let limit = args.limit;
-    let from_filter = args.from.to_lowercase();
-    let to_filter = args.to.to_lowercase();
-    let only_filter = args.only.to_lowercase();
+    let follow_links = !args.no_follow_links;
+    let folder_filter = FolderFilter::new(&args.only, &args.from, &args.to);
     let scan_root_clone = scan_root.clone();
 
-    let paths: Vec<PathBuf> = WalkDir::new(&scan_root)
-        .follow_links(true)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| {
-            if e.file_type().is_dir() {
-                total_dirs.fetch_add(1, Ordering::Relaxed);
-                return false;
+    // One path per physical file: (dev, inode) -> index of the kept path
+    let mut seen_files: HashMap<(u64, u64), usize> = HashMap::new();
+    let mut link_hits: Vec<(PathBuf, usize)> = Vec::new();
+    let mut kept = 0usize;
+    let path_list = args.paths_from.as_ref().map(|source| {
+        println!("[1/4] Reading file list...");
+        let text = if source == "-" {
+            let mut text = String::new();
+            std::io::Read::read_to_string(&mut std::io::stdin(), &mut text).map(|_| text)
+        } else {
+            fs::read_to_string(source)
+        };
+        match text.map_err(|e| e.to_string()).and_then(|t| read_path_list(&t, &scan_root, &folder_filter)) {
+            Ok(list) => list,
+            Err(e) => {
+                eprintln!("Failed to read file list {}: {}", source, e);
+                std::process::exit(1);
             }
-
-            // Apply filters based on artist folder
-            let folder = get_artist_folder(e.path(), &scan_root_clone);
-            let folder_lower = folder.to_lowercase();
-
-            // --only filter: starts with match (takes precedence)
-            if !only_filter.is_empty() {
-                if !folder_lower.starts_with(&only_filter) {
+        }
+    });
+    let paths: Vec<PathBuf> = if let Some(ref list) = path_list {
+        for (entry, reason) in &list.skipped {
+            println!("  Skipped {} ({})", entry, reason);
+        }
+        let folders: HashSet<&Path> = list.paths.iter().filter_map(|p| p.parent()).collect();
+        total_dirs.store(folders.len() as u64, Ordering::Relaxed);
+        list.paths.iter().take(if limit > 0 { limit } else { usize::MAX }).cloned().collect()
+    } else {
+        println!("[1/4] Walking directory tree...");
+        WalkDir::new(&scan_root)
+            .follow_links(follow_links)
+            .into_iter()
+            .filter_entry(|e| !excluded().is_some_and(|x| x.matches(e.path(), &scan_root_clone)))
+            .filter_map(|e| e.ok())
+            .filter(|e| {
+                if e.file_type().is_dir() {
+                    total_dirs.fetch_add(1, Ordering::Relaxed);
                     return false;
                 }
-            }
-            // --from/--to filter: string range (lexicographic comparison)
-            else if !from_filter.is_empty() || !to_filter.is_empty() {
-                if !from_filter.is_empty() && folder_lower < from_filter {
+                if e.path_is_symlink() && !follow_links {
+                    return false;
+                }
+
+                // Apply filters based on artist folder
+                let folder = get_artist_folder(e.path(), &scan_root_clone);
+                if !folder_filter.matches(&folder) || !is_audio_file(e.path()) {
                     return false;
                 }
-                if !to_filter.is_empty() {
-                    let to_upper = format!("{}\u{10FFFF}", to_filter);
-                    if folder_lower > to_upper {
+
+                if let Some(id) = e.metadata().ok().and_then(|m| file_id(&m)) {
+                    if let Some(&original) = seen_files.get(&id) {
+                        link_hits.push((e.path().to_path_buf(), original));
                         return false;
                     }
+                    seen_files.insert(id, kept);
                 }
-            }
-
-            if let Some(ext) = e.path().extension() {
-                let ext_lower = ext.to_string_lossy().to_lowercase();
-                extensions.contains(&ext_lower.as_str())
-            } else {
-                false
-            }
+                kept += 1;
+                true
+            })
+            .map(|e| e.into_path())
+            .take(if limit > 0 { limit } else { usize::MAX })
+            .collect()
+    };
+    drop(seen_files);
+    let link_dupes: Vec<LinkDuplicate> = link_hits.into_iter()
+        .map(|(path, original)| {
+            let original = paths[original].clone();
+            let kind = link_kind(&path, &original);
+            LinkDuplicate { path, original, kind }
         })
-        .map(|e| e.into_path())
-        .take(if limit > 0 { limit } else { usize::MAX })
         .collect();
 
     let total_files = paths.len() as u64;
     let total_dirs = total_dirs.load(Ordering::Relaxed);
     println!("  Found {} audio files in {} folders", total_files, total_dirs);
+    if !link_dupes.is_empty() {
+        println!("  Skipped {} more paths to the same files (symlinks / hardlinks)", link_dupes.len());
+    }
 
     // --- Always build parent_audio_count (needed for issues.html and quarantine) ---
-    let mut parent_audio_count: HashMap<PathBuf, usize> = HashMap::new();
-    for p in &paths {
-        if let Some(parent) = p.parent() {
-            *parent_audio_count.entry(parent.to_path_buf()).or_insert(0) += 1;
+    // A file list only names some of a folder's files, so count them on disk
+    let parent_audio_count: HashMap<PathBuf, usize> = if path_list.is_some() {
+        count_folder_audio(&paths)
+    } else {
+        let mut counts = HashMap::new();
+        for p in &paths {
+            if let Some(parent) = p.parent() {
+                *counts.entry(parent.to_path_buf()).or_insert(0) += 1;
+            }
         }
-    }
+        counts
+    };
 
     // --- Phase 2: Parallel scan ---
     println!("[2/4] Scanning metadata ({} threads)...", rayon::current_num_threads());
     let scanned = AtomicU64::new(0);
+    let from_cache = AtomicU64::new(0);
 
     // Lock-free accumulation via rayon fold/reduce.
-    // Each thread builds its own local (issues, tag_keys, file_type_counts, total_size, error_count, unreadable_paths)
-    // and they are merged at the end — no Mutex contention in the hot path.
-    type ScanAcc = (Vec<FileIssue>, HashSet<String>, HashMap<String, u64>, u64, u64, Vec<(PathBuf, String)>);
+    // Each thread builds its own local (issues, value_counts, file_type_counts, total_size, error_count, unreadable_paths,
+    // results to cache) and they are merged at the end — no Mutex contention in the hot path.
+    type ScanAcc = (Vec<FileIssue>, ValueCounts, HashMap<String, u64>, u64, u64, Vec<(PathBuf, String)>, Vec<CachedScan>);
 
-    let (results, _all_tag_keys, file_type_counts, total_size, error_count, unreadable_paths): ScanAcc = paths
+    let (results, value_counts, file_type_counts, total_size, error_count, unreadable_paths, cache_entries): ScanAcc = paths
         .par_iter()
         .fold(
-            || (Vec::<FileIssue>::new(), HashSet::<String>::new(), HashMap::<String, u64>::new(), 0u64, 0u64, Vec::<(PathBuf, String)>::new()),
+            || (Vec::<FileIssue>::new(), ValueCounts::new(), HashMap::<String, u64>::new(), 0u64, 0u64, Vec::<(PathBuf, String)>::new(), Vec::<CachedScan>::new()),
             |mut acc, p| {
                 let n = scanned.fetch_add(1, Ordering::Relaxed) + 1;
 
@@ -2453,10 +7903,21 @@ fn main() {
                     *acc.2.entry(ext_str).or_insert(0) += 1;
                 }
 
-                match scan_file(p) {
-                    Ok((issue, tag_keys)) => {
+                // Stamped before reading, so a file changed mid-read is re-parsed next run
+                let stamp = if scan_cache.is_some() || args.watch { FileStamp::of(p) } else { None };
+                let cached = scan_cache.as_ref().zip(stamp).and_then(|(cache, stamp)| cache.lookup(p, stamp));
+                if cached.is_some() {
+                    from_cache.fetch_add(1, Ordering::Relaxed);
+                }
+                match cached.map(Ok).unwrap_or_else(|| scan_file(p)) {
+                    Ok((issue, values)) => {
                         acc.3 += issue.file_size;
-                        acc.1.extend(tag_keys);
+                        if let Some(stamp) = stamp {
+                            acc.6.push(CachedScan::new(stamp, issue.clone(), &values));
+                        }
+                        for value in values {
+                            *acc.1.entry(value).or_insert(0) += 1;
+                        }
                         acc.0.push(issue);
                     }
                     Err(err) => {
@@ -2469,53 +7930,245 @@ fn main() {
             },
         )
         .reduce(
-            || (Vec::new(), HashSet::new(), HashMap::new(), 0, 0, Vec::new()),
+            || (Vec::new(), HashMap::new(), HashMap::new(), 0, 0, Vec::new(), Vec::new()),
             |mut a, b| {
                 a.0.extend(b.0);
-                a.1.extend(b.1);
+                for (k, v) in b.1 {
+                    *a.1.entry(k).or_insert(0) += v;
+                }
                 for (k, v) in b.2 {
                     *a.2.entry(k).or_insert(0) += v;
                 }
                 a.3 += b.3;
                 a.4 += b.4;
                 a.5.extend(b.5);
+                a.6.extend(b.6);
                 a
             },
         );
 
-    println!("  Scanned {} files ({} errors)", results.len(), error_count);
+    let from_cache = from_cache.load(Ordering::Relaxed);
+    if from_cache > 0 {
+        println!("  Scanned {} files ({} errors, {} unchanged since the cached run)", results.len(), error_count, from_cache);
+    } else {
+        println!("  Scanned {} files ({} errors)", results.len(), error_count);
+    }
+    // --watch starts from this scan and keeps it current
+    let watch_state = args.watch.then(|| WatchState::new(&cache_entries, &unreadable_paths));
+    if let (Some(cache), Some(file)) = (scan_cache, args.cache.as_ref()) {
+        // After a complete walk, files the cache still lists under the root are gone
+        let complete = path_list.is_none() && folder_filter.is_empty() && limit == 0;
+        let root = Path::new(&scan_root);
+        match cache.save(Path::new(file), &rules_signature(&style_lints), cache_entries, complete.then_some(root)) {
+            Ok(n) => println!("  Cache: {} files in {}", n, file),
+            Err(e) => eprintln!("  Failed to write cache {}: {}", file, e),
+        }
+    }
 
     // --- Phase 3: Filter to only files with issues ---
     println!("[3/4] Filtering results...");
-    let issues: Vec<FileIssue> = results
+    let is_handled = |p: &Path| !handled.is_empty() && handled.contains(&relative_path(p, &scan_root));
+    let mut handled_skipped = 0usize;
+    // Every scanned file, not only those with tag issues
+    let quality_report = args.quality.then(|| find_quality_issues(results.iter().filter(|i| !is_handled(&i.path))));
+    let rip_report = RipReport::collect(results.iter().filter(|i| !is_handled(&i.path)), &scan_root);
+    let mut issues: Vec<FileIssue> = results
         .into_iter()
         .filter(|i| i.has_any_issue())
+        .filter(|i| {
+            let keep = !is_handled(&i.path);
+            if !keep { handled_skipped += 1; }
+            keep
+        })
+        .collect();
+    let unreadable_paths: Vec<(PathBuf, String)> = unreadable_paths
+        .into_iter()
+        .filter(|(p, _)| !is_handled(p))
         .collect();
+    // Only used for the lone-file list on issues.html and --dupe-by-fingerprint
+    let report_paths: Cow<[PathBuf]> = if handled.is_empty() {
+        Cow::Borrowed(&paths)
+    } else {
+        Cow::Owned(paths.iter().filter(|p| !is_handled(p)).cloned().collect())
+    };
 
     println!("  {} files with at least one issue", issues.len());
+    let variants = find_variants(&value_counts);
+    if !variants.is_empty() {
+        println!("  {} tag values spelled more than one way", variants.len());
+    }
+    if let Some(ref quality) = quality_report {
+        println!("  Quality: {} low bitrate | {} sample rate | {} short",
+            quality.count(QualityCheck::LowBitrate), quality.count(QualityCheck::SampleRate), quality.count(QualityCheck::Short));
+    }
+    if handled_skipped > 0 {
+        println!("  {} files excluded as handled", handled_skipped);
+    }
+
+    // --- Blank tags: drop empty frames so players stop showing empty fields ---
+    if args.strip_blank_tags || args.strip_blank_tags_dry {
+        println!();
+        strip_blank_tags(&mut issues, &scan_root, !args.strip_blank_tags);
+    }
+
+    // --- Trivial fixes: what needs no lookup, before beets sees the files ---
+    let trivial_diffs = if args.fix_trivial || args.fix_trivial_dry {
+        println!();
+        fix_trivial(&report_paths, &mut issues, &scan_root, !args.fix_trivial, args.normalize)
+    } else {
+        MatchDiffs::new()
+    };
+
+    // --- Style: normalize artist/title before beets sees the files ---
+    let style_diffs = if args.style_fix || args.style_fix_dry {
+        println!();
+        fix_style(&issues, &scan_root, &style_lints, !args.style_fix)
+    } else {
+        MatchDiffs::new()
+    };
+
+    // --- Tag containers: copy stranded values where players will find them ---
+    let migrate_diffs = if args.migrate_tags {
+        println!();
+        migrate_tags(&issues, &scan_root)
+    } else {
+        MatchDiffs::new()
+    };
 
     // --- Autofix: use beets to tag files with issues, then re-scan for diffs ---
+    // Beets never sees protected folders
+    let autofix_issues: Vec<FileIssue> = if do_autofix {
+        issues.iter().filter(|issue| !protected.covers(&issue.path, &scan_root)).cloned().collect()
+    } else {
+        Vec::new()
+    };
+    if do_autofix && autofix_issues.len() < issues.len() {
+        println!("\n[Autofix] Leaving {} file(s) in protected folders alone", issues.len() - autofix_issues.len());
+    }
     let autofix_data = if args.autofix {
-        let skip_dirs = run_autofix(&issues, &scan_root, &parent_audio_count, false);
+        let skip_dirs = run_autofix(&autofix_issues, &scan_root, &parent_audio_count, false, args.autofix_jobs as usize, &tagger);
         println!("\n[4/5] Re-scanning files after autofix...");
-        let result = compute_autofix_diffs(&issues, &skip_dirs);
+        let result = compute_autofix_diffs(&autofix_issues, &skip_dirs);
         println!("  Matched: {} | Still broken: {} | Newly unreadable: {} | Diffs: {} files | Skipped: {} files",
             result.0.len(), result.1.len(), result.2.len(), result.3.len(), result.4.len());
         Some(result)
     } else {
         if args.autofix_dry {
-            run_autofix(&issues, &scan_root, &parent_audio_count, true);
+            run_autofix(&autofix_issues, &scan_root, &parent_audio_count, true, args.autofix_jobs as usize, &tagger);
         }
         None
     };
 
+    // --- Mirror: compare against a second library root ---
+    let mirror_report = mirror_root.as_ref().map(|mirror_root| {
+        println!("[Mirror] Comparing with {}...", mirror_root);
+        if limit > 0 {
+            println!("  --limit only applies to the scan root; mirror-only counts will be inflated");
+        }
+        let mirror_root_clone = mirror_root.clone();
+        let mirror_paths: Vec<PathBuf> = WalkDir::new(mirror_root)
+            .follow_links(follow_links)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| {
+                !e.file_type().is_dir()
+                    && (follow_links || !e.path_is_symlink())
+                    && folder_filter.matches(&get_artist_folder(e.path(), &mirror_root_clone))
+                    && is_audio_file(e.path())
+            })
+            .map(|e| e.into_path())
+            .collect();
+        let report = compare_mirror(&scan_root, &paths, mirror_root, &mirror_paths);
+        println!("  In both: {} | Tags differ: {} | Master only: {} | Mirror only: {} | Moved: {}",
+            report.common, report.diverged.len(), report.master_only.len(), report.mirror_only.len(), report.moved.len());
+        report
+    });
+
+    // --- Archives: vet purchase downloads before they are extracted into the library ---
+    let archive_report = archives_dir.as_ref().map(|dir| {
+        let paths = find_archives(dir);
+        println!("[Archives] Scanning {} archive(s) in {}...", paths.len(), dir);
+        let archives: Vec<ArchiveScan> = paths.chunks(ARCHIVE_CONCURRENCY)
+            .flat_map(|chunk| chunk.par_iter().map(|p| scan_archive(p, dir)).collect::<Vec<_>>())
+            .collect();
+        for archive in &archives {
+            if archive.is_clean() {
+                println!("  OK     {} ({} files)", archive.name, archive.audio_files);
+            } else {
+                println!("  ISSUES {} ({} files): {}", archive.name, archive.audio_files, archive.summary());
+            }
+        }
+        ArchiveReport { dir: dir.clone(), archives }
+    });
+
+    // --- Fingerprints: acoustically identical files, whatever their tags say ---
+    let fingerprint_report = args.dupe_by_fingerprint.map(|threshold| {
+        println!("[Fingerprints] Comparing AcoustID fingerprints of {} files...", report_paths.len());
+        let report = find_fingerprint_dupes(&report_paths, threshold);
+        println!("  Fingerprinted: {} | No fingerprint: {} | Clusters: {} | Extra copies: {}",
+            report.fingerprinted, report.missing, report.clusters.len(), report.extra_copies());
+        report
+    });
+
+    // --- Duplicates: same track by artist + title (+ AcoustID / audio hash) ---
+    let duplicate_report = dupe_mode.map(|mode| {
+        println!("[Duplicates] Grouping {} files by {}...", report_paths.len(), mode.describe());
+        let report = find_duplicates(&report_paths, mode);
+        println!("  Artist + title: {} | Groups: {} | Extra copies: {}",
+            report.keyed, report.groups.len(), report.extra_copies());
+        report
+    });
+
     // --- Phase 4: Move files to staging folders (if requested) ---
-    if args.quarantine || args.quarantine_dry {
+    let move_duplicates = args.quarantine_duplicates || args.quarantine_duplicates_dry;
+    if args.quarantine || args.quarantine_dry || move_duplicates {
         let scan_root_path = PathBuf::from(&scan_root);
         let dry = args.quarantine_dry;
 
+        // Why each file would move, for __QUARANTINE/manifest.json
+        let mut reasons: HashMap<&Path, (Vec<String>, String)> = HashMap::new();
+        let broken = match autofix_data {
+            Some((_, ref still_broken, _, _, _)) => still_broken,
+            None => &issues,
+        };
+        for issue in broken {
+            let (categories, mut reason) = issue_reason(issue);
+            if issue.path.parent().and_then(|p| parent_audio_count.get(p)) == Some(&1) {
+                reason.push_str(" (only audio file in its folder)");
+            }
+            reasons.insert(&issue.path, (categories, reason));
+        }
+        let new_unreadable = autofix_data.iter().flat_map(|(_, _, unreadable, _, _)| unreadable);
+        for (path, err) in unreadable_paths.iter().chain(new_unreadable) {
+            reasons.insert(path, (vec!["unreadable".to_string()], err.clone()));
+        }
+        for path in autofix_data.iter().flat_map(|(matched, _, _, _, _)| matched) {
+            reasons.insert(path, (vec!["autofixed".to_string()], "fixed by beets".to_string()));
+        }
+        for group in duplicate_report.iter().flat_map(|r| &r.groups) {
+            for copy in group.iter().skip(1) {
+                let reason = format!("duplicate of {}", relative_path(&group[0].copy.path, &scan_root));
+                reasons.insert(&copy.copy.path, (vec!["duplicate".to_string()], reason));
+            }
+        }
+        let manifest_path = QuarantineManifest::path(&scan_root);
+        let mut manifest = match QuarantineManifest::load(&manifest_path) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                eprintln!("Failed to read {}: {} (fix or remove it, nothing was moved)", manifest_path.display(), e);
+                std::process::exit(1);
+            }
+        };
+
         // Helper closure: move (or dry-run) a batch of files to a staging directory.
-        let move_batch = |batch: &[PathBuf], staging_dir: &Path, label: &str, dry: bool| {
+        // Files under --protect folders are held back from every batch.
+        let mut move_batch = |batch: &[PathBuf], staging_dir: &Path, label: &str, dry: bool| {
+            let (held, batch): (Vec<&PathBuf>, Vec<&PathBuf>) = batch.iter()
+                .partition(|src| protected.covers(src, &scan_root));
+            if !held.is_empty() {
+                println!();
+                println!("[Protect] Not moving {} file(s) in protected folders to {}", held.len(), label);
+            }
             if batch.is_empty() { return; }
             println!();
             if dry {
@@ -2537,136 +8190,218 @@ fn main() {
                         }
                     }
                     match fs::rename(src, &dst) {
-                        Ok(_) => println!("  Moved: {} -> {}", src.display(), dst.display()),
+                        Ok(_) => {
+                            println!("  Moved: {} -> {}", src.display(), dst.display());
+                            let (categories, reason) = reasons.get(src.as_path()).cloned().unwrap_or_default();
+                            manifest.record(QuarantineEntry {
+                                original: src.to_path_buf(),
+                                destination: dst,
+                                staging: label.to_string(),
+                                categories,
+                                reason,
+                                moved_at: Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+                            });
+                        }
                         Err(e) => eprintln!("  FAILED to move {}: {}", src.display(), e),
                     }
                 }
             }
         };
 
-        if let Some(ref data) = autofix_data {
-            // --- Autofix + quarantine: use pre-computed diffs ---
-            let (ref matched_paths, ref still_broken, ref new_unreadable, _, _) = *data;
-
-            let autofixed_dir    = scan_root_path.join("__AUTOFIXED");
-            let quarantine_dir   = scan_root_path.join("__QUARANTINE");
-            let needs_review_dir = scan_root_path.join("__NEEDS_REVIEW");
-            let unreadable_dir   = scan_root_path.join("__UNREADABLE");
-
-            // Matched files → __AUTOFIXED
-            let mut sorted_matched = matched_paths.clone();
-            sorted_matched.sort();
-            move_batch(&sorted_matched, &autofixed_dir, "__AUTOFIXED", dry);
-
-            // Still-broken files → __QUARANTINE or __NEEDS_REVIEW
-            let mut to_quarantine:   Vec<PathBuf> = Vec::new();
-            let mut to_needs_review: Vec<PathBuf> = Vec::new();
-            for issue in still_broken {
-                let count = issue.path.parent()
-                    .and_then(|p| parent_audio_count.get(p))
-                    .copied()
-                    .unwrap_or(1);
-                if count == 1 {
-                    to_needs_review.push(issue.path.clone());
-                } else {
-                    to_quarantine.push(issue.path.clone());
+        // Lower-quality copies → __DUPLICATES; issue batches below leave them out
+        let duplicates: HashSet<PathBuf> = match duplicate_report {
+            Some(ref report) if move_duplicates => report.extra_paths().into_iter().collect(),
+            _ => HashSet::new(),
+        };
+        if !duplicates.is_empty() {
+            let mut sorted_duplicates: Vec<PathBuf> = duplicates.iter().cloned().collect();
+            sorted_duplicates.sort();
+            move_batch(&sorted_duplicates, &scan_root_path.join("__DUPLICATES"), "__DUPLICATES",
+                args.quarantine_duplicates_dry);
+        }
+
+        if args.quarantine || args.quarantine_dry {
+            if let Some(ref data) = autofix_data {
+                // --- Autofix + quarantine: use pre-computed diffs ---
+                let (ref matched_paths, ref still_broken, ref new_unreadable, _, _) = *data;
+
+                let autofixed_dir    = scan_root_path.join("__AUTOFIXED");
+                let quarantine_dir   = scan_root_path.join("__QUARANTINE");
+                let needs_review_dir = scan_root_path.join("__NEEDS_REVIEW");
+                let unreadable_dir   = scan_root_path.join("__UNREADABLE");
+
+                // Matched files → __AUTOFIXED
+                let mut sorted_matched: Vec<PathBuf> = matched_paths.iter()
+                    .filter(|p| !duplicates.contains(*p))
+                    .cloned()
+                    .collect();
+                sorted_matched.sort();
+                move_batch(&sorted_matched, &autofixed_dir, "__AUTOFIXED", dry);
+
+                // Still-broken files → __QUARANTINE or __NEEDS_REVIEW
+                let mut to_quarantine:   Vec<PathBuf> = Vec::new();
+                let mut to_needs_review: Vec<PathBuf> = Vec::new();
+                for issue in still_broken.iter().filter(|i| !duplicates.contains(&i.path)) {
+                    let count = issue.path.parent()
+                        .and_then(|p| parent_audio_count.get(p))
+                        .copied()
+                        .unwrap_or(1);
+                    if count == 1 {
+                        to_needs_review.push(issue.path.clone());
+                    } else {
+                        to_quarantine.push(issue.path.clone());
+                    }
                 }
-            }
-            to_quarantine.sort();
-            to_needs_review.sort();
-            move_batch(&to_quarantine,   &quarantine_dir,   "__QUARANTINE",   dry);
-            move_batch(&to_needs_review, &needs_review_dir, "__NEEDS_REVIEW", dry);
-
-            // Unreadable files (original + newly unreadable after autofix) → __UNREADABLE
-            let mut all_unreadable: Vec<PathBuf> = unreadable_paths.iter().map(|(p, _)| p.clone()).collect();
-            all_unreadable.extend(new_unreadable.iter().map(|(p, _)| p.clone()));
-            all_unreadable.sort();
-            all_unreadable.dedup();
-            move_batch(&all_unreadable, &unreadable_dir, "__UNREADABLE", dry);
-        } else {
-            // --- Standard quarantine (no autofix) ---
-            let quarantine_dir   = scan_root_path.join("__QUARANTINE");
-            let needs_review_dir = scan_root_path.join("__NEEDS_REVIEW");
-            let unreadable_dir   = scan_root_path.join("__UNREADABLE");
-
-            // Split issue files: lone files → __NEEDS_REVIEW, rest → __QUARANTINE
-            let mut to_quarantine:   Vec<PathBuf> = Vec::new();
-            let mut to_needs_review: Vec<PathBuf> = Vec::new();
-            for issue in &issues {
-                let count = issue.path.parent()
-                    .and_then(|p| parent_audio_count.get(p))
-                    .copied()
-                    .unwrap_or(1);
-                if count == 1 {
-                    to_needs_review.push(issue.path.clone());
-                } else {
-                    to_quarantine.push(issue.path.clone());
+                to_quarantine.sort();
+                to_needs_review.sort();
+                move_batch(&to_quarantine,   &quarantine_dir,   "__QUARANTINE",   dry);
+                move_batch(&to_needs_review, &needs_review_dir, "__NEEDS_REVIEW", dry);
+
+                // Unreadable files (original + newly unreadable after autofix) → __UNREADABLE
+                let mut all_unreadable: Vec<PathBuf> = unreadable_paths.iter().map(|(p, _)| p.clone()).collect();
+                all_unreadable.extend(new_unreadable.iter().map(|(p, _)| p.clone()));
+                all_unreadable.sort();
+                all_unreadable.dedup();
+                move_batch(&all_unreadable, &unreadable_dir, "__UNREADABLE", dry);
+            } else {
+                // --- Standard quarantine (no autofix) ---
+                let quarantine_dir   = scan_root_path.join("__QUARANTINE");
+                let needs_review_dir = scan_root_path.join("__NEEDS_REVIEW");
+                let unreadable_dir   = scan_root_path.join("__UNREADABLE");
+
+                // Split issue files: lone files → __NEEDS_REVIEW, rest → __QUARANTINE
+                let mut to_quarantine:   Vec<PathBuf> = Vec::new();
+                let mut to_needs_review: Vec<PathBuf> = Vec::new();
+                for issue in issues.iter().filter(|i| !duplicates.contains(&i.path)) {
+                    let count = issue.path.parent()
+                        .and_then(|p| parent_audio_count.get(p))
+                        .copied()
+                        .unwrap_or(1);
+                    if count == 1 {
+                        to_needs_review.push(issue.path.clone());
+                    } else {
+                        to_quarantine.push(issue.path.clone());
+                    }
                 }
+                to_quarantine.sort();
+                to_needs_review.sort();
+                move_batch(&to_quarantine,   &quarantine_dir,   "__QUARANTINE",   dry);
+                move_batch(&to_needs_review, &needs_review_dir, "__NEEDS_REVIEW", dry);
+
+                // Unreadable files → __UNREADABLE
+                let mut unreadable: Vec<PathBuf> = unreadable_paths.iter().map(|(p, _)| p.clone()).collect();
+                unreadable.sort();
+                move_batch(&unreadable, &unreadable_dir, "__UNREADABLE", dry);
             }
-            to_quarantine.sort();
-            to_needs_review.sort();
-            move_batch(&to_quarantine,   &quarantine_dir,   "__QUARANTINE",   dry);
-            move_batch(&to_needs_review, &needs_review_dir, "__NEEDS_REVIEW", dry);
+        }
 
-            // Unreadable files → __UNREADABLE
-            let mut unreadable: Vec<PathBuf> = unreadable_paths.iter().map(|(p, _)| p.clone()).collect();
-            unreadable.sort();
-            move_batch(&unreadable, &unreadable_dir, "__UNREADABLE", dry);
+        if let Err(e) = manifest.save(&manifest_path) {
+            eprintln!("Failed to write {}: {}", manifest_path.display(), e);
         }
     }
 
+    // Autofix, trivial and style fixes and tag migration share the report's "resolved" markers
+    let mut diffs = autofix_data.as_ref().map(|(_, _, _, d, _)| d.clone()).unwrap_or_default();
+    for (path, fixes) in trivial_diffs.into_iter().chain(style_diffs).chain(migrate_diffs) {
+        diffs.entry(path).or_default().extend(fixes);
+    }
+    let diffs_ref = (autofix_data.is_some() || !diffs.is_empty()).then_some(&diffs);
+    let metrics_time = Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false);
+    let counts = run_counts(&issues, total_files, error_count, !style_lints.is_empty());
+    let metrics = metric_rows(&counts, diffs_ref);
+
     // --- Phase 5: Generate report ---
+    let timestamp = Local::now().format("%Y%m%d_%H%M%S").to_string();
+    let output_dir = if args.output_dir.starts_with('/') {
+        PathBuf::from(&args.output_dir)
+    } else {
+        std::env::current_dir()
+            .unwrap_or_default()
+            .join(&args.output_dir)
+    };
+    let report_dir = output_dir.join(format!("analysis_{}", timestamp));
+
+    // Trend history: full scans only, a partial one would look like a cleanup
+    let elapsed = start.elapsed();
+    let provenance = Provenance::collect(&style_lints);
+    let history_path = (!args.no_history && !partial_scan).then(|| output_dir.join(HISTORY_FILE));
+    // Absolute, so runs started from another directory still line up
+    let history_root = fs::canonicalize(&scan_root)
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| scan_root.clone());
+    let run = HistoryEntry::new(&metrics_time, &history_root, &provenance.rules_hash, elapsed,
+        Health::of(&issues, total_files, error_count), &counts);
+    let mut trend = history_path.as_deref().map(|p| load_history(p, &history_root)).unwrap_or_default();
+    if history_path.is_some() {
+        trend.push(run.clone());
+        trend.drain(..trend.len().saturating_sub(TREND_RUNS));
+    }
+
+    // Determine which pages to generate
+    let any_only_flag = args.only_critical || args.only_mb || args.only_discogs
+        || args.only_issues || args.only_ids || args.only_other || args.only_style
+        || args.only_consistency || args.only_albums;
+
+    let pages = PageFlags {
+        critical: !any_only_flag || args.only_critical,
+        mb:       !any_only_flag || args.only_mb,
+        discogs:  !any_only_flag || args.only_discogs,
+        ids:      !any_only_flag || args.only_ids,
+        other:    !any_only_flag || args.only_other,
+        custom:   !any_only_flag,
+        style:    !style_lints.is_empty() && (!any_only_flag || args.only_style),
+        consistency: !any_only_flag || args.only_consistency,
+        albums:   !any_only_flag || args.only_albums,
+    };
+
+    let mut report_url: Option<String> = None;
     if args.no_report {
         println!("\n[5/5] Report generation skipped (--no-report)");
     } else {
         println!("[5/5] Generating HTML report...");
 
-        let timestamp = Local::now().format("%Y%m%d_%H%M%S").to_string();
-        let output_dir = if args.output_dir.starts_with('/') {
-            PathBuf::from(&args.output_dir)
+        let skipped_ref = autofix_data.as_ref().map(|(_, _, _, _, s)| s);
+
+        // Lone files get their length read here, after the scan (--no-lone-files skips the list)
+        let lone_files = if args.no_lone_files {
+            Vec::new()
         } else {
-            std::env::current_dir()
-                .unwrap_or_default()
-                .join(&args.output_dir)
-        };
-        let report_dir = output_dir.join(format!("analysis_{}", timestamp));
-
-        // Determine which pages to generate
-        let any_only_flag = args.only_critical || args.only_mb || args.only_discogs
-            || args.only_issues || args.only_ids || args.only_other;
-
-        let pages = PageFlags {
-            critical: !any_only_flag || args.only_critical,
-            mb:       !any_only_flag || args.only_mb,
-            discogs:  !any_only_flag || args.only_discogs,
-            ids:      !any_only_flag || args.only_ids,
-            other:    !any_only_flag || args.only_other,
+            find_lone_files(&report_paths, &parent_audio_count, &scan_root)
         };
 
-        let elapsed = start.elapsed();
-
-        let diffs_ref = autofix_data.as_ref().map(|(_, _, _, d, _)| d);
-        let skipped_ref = autofix_data.as_ref().map(|(_, _, _, _, s)| s);
-
-        match generate_report(
-            &issues,
-            &paths,
-            &parent_audio_count,
-            &unreadable_paths,
-            &scan_root,
+        let protected_counts = protected.counts(&issues, &scan_root);
+        let data = ReportData {
+            issues: &issues,
+            all_paths: &report_paths,
+            lone_files: &lone_files,
+            unreadable: &unreadable_paths,
+            link_dupes: &link_dupes,
+            scan_root: &scan_root,
             total_files,
             total_size,
             error_count,
-            &file_type_counts,
+            file_type_counts: &file_type_counts,
             elapsed,
-            &report_dir,
-            &pages,
-            diffs_ref,
-            skipped_ref,
-        ) {
+            diffs: diffs_ref,
+            skipped_files: skipped_ref,
+            mirror: mirror_report.as_ref(),
+            archives: archive_report.as_ref(),
+            fingerprints: fingerprint_report.as_ref(),
+            duplicates: duplicate_report.as_ref(),
+            quality: quality_report.as_ref(),
+            rips: &rip_report,
+            variants: &variants,
+            provenance: &provenance,
+            protected: &protected_counts,
+            trend: &trend,
+        };
+        match generate_report(&data, &report_dir, &pages) {
             Ok(_) => {
                 println!();
                 println!("Report written to: {}", report_dir.display());
+                if args.single_file {
+                    println!("Single file written to: {}", single_file_path(&report_dir).display());
+                }
                 println!("Total time: {:.2}s", elapsed.as_secs_f64());
                 let readable = total_files.saturating_sub(error_count);
                 let ok = readable.saturating_sub(issues.len() as u64);
@@ -2677,6 +8412,182 @@ fn main() {
                 std::process::exit(1);
             }
         }
+
+        if let Err(e) = write_metrics(&report_dir, &metrics_time, &metrics) {
+            eprintln!("Failed to write metrics: {}", e);
+        }
+
+        if args.beets_export {
+            let beets_dir = report_dir.join("beets");
+            let exports = beets_exports(&issues);
+            match write_beets_exports(&beets_dir, &exports) {
+                Ok(_) => {
+                    println!("Beets exports written to: {}", beets_dir.display());
+                    for export in &exports {
+                        println!("  {:<10} {} files in {} folders", export.category, export.files.len(), export.dirs.len());
+                    }
+                }
+                Err(e) => eprintln!("Failed to write beets exports: {}", e),
+            }
+        }
+
+        if let Some(ref prefix) = args.publish_s3 {
+            println!("Publishing report to S3...");
+            match publish_report(&report_dir, prefix) {
+                Ok((n, Some(url))) => {
+                    println!("Published {} files: {}", n, url);
+                    report_url = Some(url);
+                }
+                Ok((n, None)) => println!("Published {} files (set S3_PUBLIC_URL to get a link)", n),
+                Err(e) => {
+                    eprintln!("Failed to publish report: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+
+    // --- Trend history: one JSON line per full scan (also with --no-report) ---
+    if let Some(ref history_path) = history_path {
+        match append_history(history_path, &run) {
+            Ok(_) => println!("History appended to: {}", history_path.display()),
+            Err(e) => eprintln!("Failed to append history to {}: {}", history_path.display(), e),
+        }
+    }
+
+    // --- Metrics history: one CSV across runs (also appended with --no-report) ---
+    if let Some(ref history) = args.metrics_csv {
+        let history_path = PathBuf::from(history);
+        match append_metrics(&history_path, &metrics_time, &metrics) {
+            Ok(_) => println!("Metrics appended to: {}", history_path.display()),
+            Err(e) => {
+                eprintln!("Failed to append metrics to {}: {}", history_path.display(), e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // --- Digest: independent of the full report (also written with --no-report) ---
+    if let Some(ref digest) = args.digest {
+        let digest_path = PathBuf::from(digest);
+        match write_digest(&digest_path, &scan_root, &issues, &report_paths, total_files, error_count, !style_lints.is_empty()) {
+            Ok(_) => println!("Digest written to: {}", digest_path.display()),
+            Err(e) => {
+                eprintln!("Failed to write digest {}: {}", digest_path.display(), e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // --- Notifications: tell cron's owner the run is done ---
+    if args.notify_webhook.is_some() || notify_smtp.is_some() {
+        let counts = run_counts(&issues, total_files, error_count, !style_lints.is_empty());
+        let notice = RunNotice {
+            scan_root: &scan_root,
+            elapsed: start.elapsed(),
+            counts: &counts,
+            report: (!args.no_report).then_some(report_dir.as_path()),
+            report_url: report_url.as_deref(),
+        };
+        let mut failed = false;
+        if let Some(ref url) = args.notify_webhook {
+            match send_webhook(url, &notice, notify_ca.as_ref()) {
+                Ok(_) => println!("Notified: {}", url),
+                Err(e) => {
+                    eprintln!("Failed to notify {}: {}", url, e);
+                    failed = true;
+                }
+            }
+        }
+        if let Some(ref settings) = notify_smtp {
+            match send_email(settings, &args.notify_email, &notice) {
+                Ok(_) => println!("Emailed: {}", args.notify_email.join(", ")),
+                Err(e) => {
+                    eprintln!("Failed to email {}: {}", args.notify_email.join(", "), e);
+                    failed = true;
+                }
+            }
+        }
+        if failed {
+            std::process::exit(1);
+        }
+    }
+
+    // --- Hooks: hand the summary to the user's own automation ---
+    let hooks: Vec<&HookCommand> = args.on_complete.iter()
+        .chain(args.on_issue_threshold.iter()
+            .filter(|hook| issues.len() as u64 >= hook.threshold)
+            .map(|hook| &hook.command))
+        .collect();
+    if !hooks.is_empty() {
+        // With --no-report there is no report folder to hold summary.json
+        let summary_dir = if args.no_report {
+            std::env::temp_dir().join(format!("dmp-analysis_{}", timestamp))
+        } else {
+            report_dir.clone()
+        };
+        let summary = summary_dir.join("summary.json");
+        if args.no_report {
+            let counts = run_counts(&issues, total_files, error_count, !style_lints.is_empty());
+            if let Err(e) = fs::create_dir_all(&summary_dir)
+                .and_then(|_| write_summary(&summary_dir, &scan_root, &Provenance::collect(&style_lints), &counts,
+                    Health::of(&issues, total_files, error_count)))
+            {
+                eprintln!("Failed to write {}: {}", summary.display(), e);
+                std::process::exit(1);
+            }
+        }
+        let mut failed = false;
+        for command in hooks {
+            println!("Running hook: {}", command.line());
+            if let Err(e) = command.run(&summary) {
+                eprintln!("Hook failed ({}): {}", command.line(), e);
+                failed = true;
+            }
+        }
+        if failed {
+            std::process::exit(1);
+        }
+    }
+
+    // --- Serve: in the background while watching, otherwise until Ctrl-C ---
+    if let Some(server) = report_server {
+        let url = format!("http://{}:{}/", host_name(), server.port());
+        if watch_state.is_some() {
+            let root = report_dir.clone();
+            std::thread::spawn(move || server.serve(root));
+            println!("Serving report at {}", url);
+        } else {
+            println!();
+            println!("Serving report at {} (Ctrl-C to stop)", url);
+            server.serve(report_dir.clone());
+        }
+    }
+
+    // --- Watch: keep the report current as files change ---
+    if let Some(state) = watch_state {
+        let options = WatchOptions {
+            scan_root: &scan_root,
+            report_dir: &report_dir,
+            pages: &pages,
+            debounce: Duration::from_secs(args.watch_debounce),
+            filter: &folder_filter,
+            follow_links,
+            handled: &handled,
+            protected: &protected,
+            link_dupes: &link_dupes,
+            style_lints: &style_lints,
+            quality: args.quality,
+            lone_files: !args.no_lone_files,
+            beets_export: args.beets_export,
+            cache: args.cache.as_deref(),
+            digest: args.digest.as_deref(),
+            trend: &trend,
+        };
+        if let Err(e) = watch_library(state, issues.len(), &options) {
+            eprintln!("Watch stopped: {}", e);
+            std::process::exit(1);
+        }
     }
 
     if args.autofix_dry {
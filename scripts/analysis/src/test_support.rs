@@ -0,0 +1,225 @@
+// ---------------------------------------------------------------------------
+// Test support: synthetic audio fixtures
+// ---------------------------------------------------------------------------
+//
+// Builds tiny FLAC / MP3 files with exactly the tags a test asks for, so the
+// issue rules in `scan_file` can be exercised without real audio on disk.
+// The files contain a valid stream header (and a few silent MPEG frames) but
+// no decodable audio — enough for lofty to read and write tags.
+
+use lofty::ape::{ApeItem, ApeTag};
+use lofty::config::WriteOptions;
+use lofty::id3::v2::{Frame, FrameId, Id3v2Tag, TextInformationFrame};
+use lofty::ogg::{OggPictureStorage, VorbisComments};
+use lofty::picture::{MimeType, Picture, PictureType};
+use lofty::prelude::*;
+use lofty::tag::ItemValue;
+use lofty::TextEncoding;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+use crate::{scan_file, FileIssue};
+
+#[derive(Debug, Clone, Copy)]
+pub enum Format {
+    Flac,
+    Mp3,
+}
+
+impl Format {
+    fn ext(self) -> &'static str {
+        match self {
+            Format::Flac => "flac",
+            Format::Mp3 => "mp3",
+        }
+    }
+
+    /// Smallest byte stream lofty accepts as a file of this format.
+    fn skeleton(self) -> Vec<u8> {
+        match self {
+            Format::Flac => {
+                let mut bytes = b"fLaC".to_vec();
+                // STREAMINFO (type 0), 34 bytes long
+                bytes.extend_from_slice(&[0x00, 0x00, 0x00, 0x22]);
+                bytes.extend_from_slice(&4096u16.to_be_bytes()); // min block size
+                bytes.extend_from_slice(&4096u16.to_be_bytes()); // max block size
+                bytes.extend_from_slice(&[0; 6]); // min / max frame size (unknown)
+                // 44.1 kHz, 2 channels, 16 bits per sample, 0 total samples
+                let packed: u64 = (44_100u64 << 44) | (1 << 41) | (15 << 36);
+                bytes.extend_from_slice(&packed.to_be_bytes());
+                bytes.extend_from_slice(&[0; 16]); // MD5 of audio data
+                // Last-metadata-block flag + PADDING (type 1), 64 bytes long
+                bytes.extend_from_slice(&[0x81, 0x00, 0x00, 0x40]);
+                bytes.extend_from_slice(&[0; 64]);
+                bytes
+            }
+            Format::Mp3 => {
+                // MPEG-1 Layer III, 128 kbps, 44.1 kHz, no padding: 417-byte frames
+                let mut frame = vec![0u8; 417];
+                frame[..4].copy_from_slice(&[0xFF, 0xFB, 0x90, 0x00]);
+                frame.repeat(4)
+            }
+        }
+    }
+}
+
+/// A synthetic audio file description. Build with [`Fixture::flac`] or
+/// [`Fixture::mp3`], add tags, then [`Fixture::scan`] it.
+#[derive(Debug, Clone)]
+pub struct Fixture {
+    format: Format,
+    tags: Vec<(String, String)>,
+    picture: bool,
+    /// Items of a second, APEv2 tag (MP3 only)
+    ape: Vec<(String, String)>,
+}
+
+impl Fixture {
+    pub fn flac() -> Self {
+        Self { format: Format::Flac, tags: Vec::new(), picture: false, ape: Vec::new() }
+    }
+
+    pub fn mp3() -> Self {
+        Self { format: Format::Mp3, tags: Vec::new(), picture: false, ape: Vec::new() }
+    }
+
+    /// A file with every tag the rules look for, i.e. no issues at all.
+    pub fn complete(format: Format) -> Self {
+        let base = match format {
+            Format::Flac => Self::flac(),
+            Format::Mp3 => Self::mp3(),
+        };
+        base.tag("ARTIST", "Radiohead")
+            .tag("TITLE", "Airbag")
+            .tag("YEAR", "1997")
+            .tag("GENRE", "Alternative Rock")
+            .tag("MUSICBRAINZ_ARTISTID", "a74b1b7f-71a5-4011-9441-d0b5e4122711")
+            .tag("MUSICBRAINZ_TRACKID", "6a6f2ec5-7f4b-4c0c-8a1f-0a6d9b3f0a11")
+            .tag("MUSICBRAINZ_ALBUMID", "b1392450-e666-3926-a536-22c65f834433")
+            .tag("ACOUSTID_ID", "0f5a7c4e-1d44-4a1a-9d4b-8b7e3e1f2a33")
+            .tag("SONGKONG_ID", "12345")
+            .tag("URL_BANDCAMP_ARTIST_SITE", "https://radiohead.bandcamp.com")
+            .tag("WWW WIKIPEDIA_ARTIST", "https://en.wikipedia.org/wiki/Radiohead")
+            .tag("URL_DISCOGS_ARTIST_SITE", "https://www.discogs.com/artist/3840")
+            .tag("URL_DISCOGS_RELEASE_SITE", "https://www.discogs.com/release/83182")
+            .tag("BPM", "84")
+            .tag("MOOD_HAPPY", "not happy")
+            .with_picture()
+    }
+
+    /// Set a tag, replacing any previous value for the same key.
+    /// An empty value writes the key with a blank value.
+    pub fn tag(mut self, key: &str, value: &str) -> Self {
+        self.tags.retain(|(k, _)| !k.eq_ignore_ascii_case(key));
+        self.tags.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Drop a tag entirely (key not present in the file).
+    pub fn without(mut self, key: &str) -> Self {
+        self.tags.retain(|(k, _)| !k.eq_ignore_ascii_case(key));
+        self
+    }
+
+    /// Embed a front cover picture.
+    pub fn with_picture(mut self) -> Self {
+        self.picture = true;
+        self
+    }
+
+    pub fn without_picture(mut self) -> Self {
+        self.picture = false;
+        self
+    }
+
+    /// Also write `key` into an APEv2 tag next to the ID3v2 one (MP3 only).
+    pub fn ape(mut self, key: &str, value: &str) -> Self {
+        self.ape.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Write the fixture into `dir` and return its path.
+    pub fn write(&self, dir: &Path, stem: &str) -> PathBuf {
+        let path = dir.join(format!("{}.{}", stem, self.format.ext()));
+        fs::write(&path, self.format.skeleton()).expect("write skeleton");
+
+        match self.format {
+            Format::Flac => {
+                let mut tag = VorbisComments::default();
+                for (key, value) in &self.tags {
+                    tag.insert(key.clone(), value.clone());
+                }
+                if self.picture {
+                    tag.insert_picture(cover_picture(), None).expect("insert picture");
+                }
+                tag.save_to_path(&path, WriteOptions::default()).expect("save FLAC tags");
+            }
+            Format::Mp3 => {
+                let mut tag = Id3v2Tag::new();
+                for (key, value) in &self.tags {
+                    match id3v2_frame_id(key) {
+                        Some(id) => {
+                            let id = FrameId::new(id).expect("valid frame id");
+                            tag.insert(Frame::Text(TextInformationFrame::new(
+                                id,
+                                TextEncoding::UTF8,
+                                value.clone(),
+                            )));
+                        }
+                        // Everything non-standard lives in TXXX, like taggers write it
+                        None => {
+                            tag.insert_user_text(key.clone(), value.clone());
+                        }
+                    }
+                }
+                if self.picture {
+                    tag.insert_picture(cover_picture());
+                }
+                tag.save_to_path(&path, WriteOptions::default()).expect("save MP3 tags");
+
+                if !self.ape.is_empty() {
+                    let mut ape = ApeTag::new();
+                    for (key, value) in &self.ape {
+                        ape.insert(ApeItem::new(key.clone(), ItemValue::Text(value.clone())).expect("valid APE key"));
+                    }
+                    ape.save_to_path(&path, WriteOptions::default()).expect("save APE tags");
+                }
+            }
+        }
+        path
+    }
+
+    /// Write the fixture to a temp dir and run it through `scan_file`.
+    pub fn scan(&self) -> FileIssue {
+        let dir = TempDir::new().expect("temp dir");
+        let path = self.write(dir.path(), "fixture");
+        scan_file(&path).expect("scan fixture").0
+    }
+}
+
+/// ID3v2 text frame for the Vorbis-style keys the rules read directly.
+fn id3v2_frame_id(key: &str) -> Option<&'static str> {
+    match key.to_uppercase().as_str() {
+        "ARTIST" => Some("TPE1"),
+        "TITLE" => Some("TIT2"),
+        "YEAR" => Some("TDRC"),
+        "GENRE" => Some("TCON"),
+        "BPM" => Some("TBPM"),
+        "ORIGINALDATE" => Some("TDOR"),
+        _ => None,
+    }
+}
+
+fn cover_picture() -> Picture {
+    Picture::new_unchecked(PictureType::CoverFront, Some(MimeType::Png), None, PNG_1X1.to_vec())
+}
+
+/// 1x1 transparent PNG, used as embedded album art.
+const PNG_1X1: &[u8] = &[
+    0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00, 0x00, 0x0D, 0x49, 0x48, 0x44,
+    0x52, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x06, 0x00, 0x00, 0x00, 0x1F,
+    0x15, 0xC4, 0x89, 0x00, 0x00, 0x00, 0x0D, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9C, 0x63, 0x00,
+    0x01, 0x00, 0x00, 0x05, 0x00, 0x01, 0x0D, 0x0A, 0x2D, 0xB4, 0x00, 0x00, 0x00, 0x00, 0x49,
+    0x45, 0x4E, 0x44, 0xAE, 0x42, 0x60, 0x82,
+];
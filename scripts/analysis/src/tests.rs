@@ -0,0 +1,1821 @@
+// ---------------------------------------------------------------------------
+// Issue rule tests
+// ---------------------------------------------------------------------------
+//
+// Each test starts from a complete fixture (no issues) and changes a single
+// tag, then asserts the exact set of flags raised — so a rule change can't
+// silently affect its neighbours.
+
+use crate::test_support::{Fixture, Format};
+use crate::FileIssue;
+
+fn flac() -> Fixture {
+    Fixture::complete(Format::Flac)
+}
+
+fn mp3() -> Fixture {
+    Fixture::complete(Format::Mp3)
+}
+
+/// Names of every flag raised on a scanned file, in struct order.
+fn flagged(issue: &FileIssue) -> Vec<&'static str> {
+    let flags = [
+        ("missing_artist", issue.missing_artist),
+        ("missing_title", issue.missing_title),
+        ("missing_year", issue.missing_year),
+        ("missing_mb_artist_id", issue.missing_mb_artist_id),
+        ("missing_mb_track_id", issue.missing_mb_track_id),
+        ("missing_mb_album_id", issue.missing_mb_album_id),
+        ("missing_acoustic_id", issue.missing_acoustic_id),
+        ("missing_songkong_id", issue.missing_songkong_id),
+        ("missing_bandcamp", issue.missing_bandcamp),
+        ("missing_wikipedia_artist", issue.missing_wikipedia_artist),
+        ("missing_discogs_artist", issue.missing_discogs_artist),
+        ("missing_discogs_release", issue.missing_discogs_release),
+        ("missing_genre", issue.missing_genre),
+        ("missing_bpm", issue.missing_bpm),
+        ("missing_mood", issue.missing_mood),
+        ("missing_album_art", issue.missing_album_art),
+        ("unusual_tags", issue.unusual_tags.is_some()),
+        ("invalid_year", issue.invalid_year.is_some()),
+        ("blank_artist", issue.blank_artist),
+        ("blank_title", issue.blank_title),
+        ("blank_year", issue.blank_year),
+        ("blank_genre", issue.blank_genre),
+        ("style_case", issue.style_case.is_some()),
+        ("style_explicit", issue.style_explicit),
+        ("style_spaces", issue.style_spaces.is_some()),
+        ("style_feat", issue.style_feat.is_some()),
+    ];
+    flags.iter().filter(|(_, set)| *set).map(|(name, _)| *name).collect()
+}
+
+// --- Baseline ---
+
+#[test]
+fn complete_flac_has_no_issues() {
+    let issue = flac().scan();
+    assert_eq!(flagged(&issue), Vec::<&str>::new());
+    assert!(!issue.has_any_issue());
+}
+
+#[test]
+fn complete_mp3_has_no_issues() {
+    let issue = mp3().scan();
+    assert_eq!(flagged(&issue), Vec::<&str>::new());
+    assert!(!issue.has_any_issue());
+}
+
+#[test]
+fn untagged_file_is_missing_everything() {
+    let issue = Fixture::flac().scan();
+    let flags = flagged(&issue);
+    assert_eq!(flags.len(), 16);
+    assert!(flags.iter().all(|f| f.starts_with("missing_")));
+}
+
+#[test]
+fn unreadable_file_is_an_error() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("broken.flac");
+    std::fs::write(&path, b"not a flac file").unwrap();
+    assert!(crate::scan_file(&path).is_err());
+}
+
+// --- Critical: missing vs blank ---
+//
+// lofty drops items with an empty value when reading, so a "blank" tag in
+// practice is one holding only whitespace.
+
+#[test]
+fn missing_artist() {
+    let issue = flac().without("ARTIST").scan();
+    assert_eq!(flagged(&issue), ["missing_artist"]);
+    assert!(issue.has_critical());
+}
+
+#[test]
+fn blank_artist() {
+    let issue = flac().tag("ARTIST", "   ").scan();
+    assert_eq!(flagged(&issue), ["missing_artist", "blank_artist"]);
+}
+
+#[test]
+fn empty_artist_reads_as_missing() {
+    let issue = flac().tag("ARTIST", "").scan();
+    assert_eq!(flagged(&issue), ["missing_artist"]);
+}
+
+#[test]
+fn missing_title() {
+    let issue = flac().without("TITLE").scan();
+    assert_eq!(flagged(&issue), ["missing_title"]);
+    assert!(issue.has_critical());
+}
+
+#[test]
+fn blank_title() {
+    let issue = flac().tag("TITLE", " ").scan();
+    assert_eq!(flagged(&issue), ["missing_title", "blank_title"]);
+}
+
+#[test]
+fn missing_year() {
+    let issue = flac().without("YEAR").scan();
+    assert_eq!(flagged(&issue), ["missing_year"]);
+    assert!(issue.has_critical());
+}
+
+#[test]
+fn date_or_original_date_stands_in_for_year() {
+    assert!(flagged(&flac().without("YEAR").tag("DATE", "1997-03-04").scan()).is_empty());
+    assert!(flagged(&flac().without("YEAR").tag("ORIGINALDATE", "1997-03-04").scan()).is_empty());
+    assert!(flagged(&flac().without("YEAR").tag("ORIGINALYEAR", "1997").scan()).is_empty());
+    assert!(flagged(&mp3().without("YEAR").tag("ORIGINALDATE", "1997").scan()).is_empty());
+    // Only a year the rules accept
+    assert_eq!(flagged(&flac().without("YEAR").tag("ORIGINALDATE", "9999-01-01").scan()), ["missing_year"]);
+    assert_eq!(flagged(&flac().without("YEAR").tag("ORIGINALDATE", "unknown").scan()), ["missing_year"]);
+}
+
+#[test]
+fn blank_year() {
+    let issue = flac().tag("YEAR", " ").scan();
+    assert_eq!(flagged(&issue), ["missing_year", "blank_year"]);
+}
+
+#[test]
+fn mp3_missing_artist() {
+    let issue = mp3().without("ARTIST").scan();
+    assert_eq!(flagged(&issue), ["missing_artist"]);
+}
+
+#[test]
+fn mp3_missing_title_and_year() {
+    let issue = mp3().without("TITLE").without("YEAR").scan();
+    assert_eq!(flagged(&issue), ["missing_title", "missing_year"]);
+}
+
+// --- Critical: invalid year ---
+
+#[test]
+fn non_numeric_year_is_invalid() {
+    let issue = flac().tag("YEAR", "unknown").scan();
+    assert_eq!(flagged(&issue), ["invalid_year"]);
+    assert_eq!(issue.invalid_year.as_deref(), Some("unknown"));
+    assert!(issue.has_critical());
+}
+
+#[test]
+fn zero_year_is_invalid() {
+    let issue = flac().tag("YEAR", "0000").scan();
+    assert_eq!(flagged(&issue), ["invalid_year"]);
+}
+
+#[test]
+fn future_year_is_invalid() {
+    let issue = flac().tag("YEAR", "2030").scan();
+    assert_eq!(flagged(&issue), ["invalid_year"]);
+    assert_eq!(issue.invalid_year.as_deref(), Some("2030"));
+}
+
+#[test]
+fn last_valid_year_is_accepted() {
+    let issue = flac().tag("YEAR", "2029").scan();
+    assert_eq!(flagged(&issue), Vec::<&str>::new());
+}
+
+#[test]
+fn padded_year_is_trimmed() {
+    let issue = flac().tag("YEAR", " 1997 ").scan();
+    assert_eq!(flagged(&issue), Vec::<&str>::new());
+}
+
+// --- MusicBrainz ---
+
+#[test]
+fn missing_mb_artist_id() {
+    let issue = flac().without("MUSICBRAINZ_ARTISTID").scan();
+    assert_eq!(flagged(&issue), ["missing_mb_artist_id"]);
+    assert!(issue.has_mb());
+    assert!(!issue.has_critical());
+}
+
+#[test]
+fn missing_mb_track_id() {
+    let issue = flac().without("MUSICBRAINZ_TRACKID").scan();
+    assert_eq!(flagged(&issue), ["missing_mb_track_id"]);
+}
+
+#[test]
+fn mb_release_track_id_satisfies_track_id() {
+    let issue = flac()
+        .without("MUSICBRAINZ_TRACKID")
+        .tag("MUSICBRAINZ_RELEASETRACKID", "c1f5d2a4-3b9e-4f1e-a3a2-5d6c7e8f9a00")
+        .scan();
+    assert_eq!(flagged(&issue), Vec::<&str>::new());
+}
+
+#[test]
+fn missing_mb_album_id() {
+    let issue = flac().without("MUSICBRAINZ_ALBUMID").scan();
+    assert_eq!(flagged(&issue), ["missing_mb_album_id"]);
+}
+
+#[test]
+fn blank_mb_id_counts_as_missing() {
+    let issue = flac().tag("MUSICBRAINZ_ALBUMID", " ").scan();
+    assert_eq!(flagged(&issue), ["missing_mb_album_id"]);
+}
+
+// --- IDs ---
+
+#[test]
+fn missing_acoustic_id() {
+    let issue = flac().without("ACOUSTID_ID").scan();
+    assert_eq!(flagged(&issue), ["missing_acoustic_id"]);
+    assert!(issue.has_ids());
+}
+
+#[test]
+fn acoustic_id_alias_is_accepted() {
+    let issue = flac()
+        .without("ACOUSTID_ID")
+        .tag("ACOUSTIC_ID", "0f5a7c4e-1d44-4a1a-9d4b-8b7e3e1f2a33")
+        .scan();
+    assert_eq!(flagged(&issue), Vec::<&str>::new());
+}
+
+#[test]
+fn missing_songkong_id() {
+    let issue = flac().without("SONGKONG_ID").scan();
+    assert_eq!(flagged(&issue), ["missing_songkong_id"]);
+}
+
+#[test]
+fn missing_bandcamp() {
+    let issue = flac().without("URL_BANDCAMP_ARTIST_SITE").scan();
+    assert_eq!(flagged(&issue), ["missing_bandcamp"]);
+}
+
+#[test]
+fn bandcamp_www_alias_is_accepted() {
+    let issue = flac()
+        .without("URL_BANDCAMP_ARTIST_SITE")
+        .tag("WWW BANDCAMP_ARTIST", "https://radiohead.bandcamp.com")
+        .scan();
+    assert_eq!(flagged(&issue), Vec::<&str>::new());
+}
+
+#[test]
+fn missing_wikipedia_artist() {
+    let issue = flac().without("WWW WIKIPEDIA_ARTIST").scan();
+    assert_eq!(flagged(&issue), ["missing_wikipedia_artist"]);
+}
+
+// --- Discogs ---
+
+#[test]
+fn missing_discogs_artist() {
+    let issue = flac().without("URL_DISCOGS_ARTIST_SITE").scan();
+    assert_eq!(flagged(&issue), ["missing_discogs_artist"]);
+    assert!(issue.has_discogs());
+}
+
+#[test]
+fn missing_discogs_release() {
+    let issue = flac().without("URL_DISCOGS_RELEASE_SITE").scan();
+    assert_eq!(flagged(&issue), ["missing_discogs_release"]);
+}
+
+#[test]
+fn discogs_www_aliases_are_accepted() {
+    let issue = flac()
+        .without("URL_DISCOGS_ARTIST_SITE")
+        .without("URL_DISCOGS_RELEASE_SITE")
+        .tag("WWW DISCOGS_ARTIST", "https://www.discogs.com/artist/3840")
+        .tag("WWW DISCOGS_RELEASE", "https://www.discogs.com/release/83182")
+        .scan();
+    assert_eq!(flagged(&issue), Vec::<&str>::new());
+}
+
+// --- Other ---
+
+#[test]
+fn missing_genre() {
+    let issue = flac().without("GENRE").scan();
+    assert_eq!(flagged(&issue), ["missing_genre"]);
+    assert!(issue.has_other());
+}
+
+#[test]
+fn blank_genre() {
+    let issue = flac().tag("GENRE", " ").scan();
+    assert_eq!(flagged(&issue), ["missing_genre", "blank_genre"]);
+    assert!(!issue.has_critical());
+}
+
+#[test]
+fn missing_bpm() {
+    let issue = flac().without("BPM").scan();
+    assert_eq!(flagged(&issue), ["missing_bpm"]);
+}
+
+#[test]
+fn mp3_missing_bpm() {
+    let issue = mp3().without("BPM").scan();
+    assert_eq!(flagged(&issue), ["missing_bpm"]);
+}
+
+#[test]
+fn missing_mood() {
+    let issue = flac().without("MOOD_HAPPY").scan();
+    assert_eq!(flagged(&issue), ["missing_mood"]);
+}
+
+#[test]
+fn any_mood_prefix_satisfies_mood() {
+    let issue = flac().without("MOOD_HAPPY").tag("MOOD_RELAXED", "relaxed").scan();
+    assert_eq!(flagged(&issue), Vec::<&str>::new());
+}
+
+#[test]
+fn missing_album_art() {
+    let issue = flac().without_picture().scan();
+    assert_eq!(flagged(&issue), ["missing_album_art"]);
+}
+
+#[test]
+fn mp3_missing_album_art() {
+    let issue = mp3().without_picture().scan();
+    assert_eq!(flagged(&issue), ["missing_album_art"]);
+}
+
+// --- Style lints ---
+//
+// `scan_file` only runs the lints selected with --style (a process-wide
+// setting), so these call the lint and fix functions directly.
+
+use crate::{check_style, normalize_style, StyleLints};
+
+fn all_lints() -> StyleLints {
+    StyleLints::parse("all").unwrap()
+}
+
+#[test]
+fn style_lints_parse_names() {
+    let lints = StyleLints::parse("case, Feat").unwrap();
+    assert_eq!(lints.names(), ["case", "feat"]);
+    assert!(StyleLints::parse("").unwrap().is_empty());
+    assert_eq!(all_lints().names().len(), 4);
+    assert!(StyleLints::parse("case,shouting").is_err());
+}
+
+#[test]
+fn style_clean_tags_pass() {
+    let check = check_style(Some("Radiohead"), Some("Airbag"), &all_lints());
+    assert!(check.case.is_none() && !check.explicit && check.spaces.is_none() && check.feat.is_none());
+}
+
+#[test]
+fn style_all_caps_and_lowercase() {
+    let check = check_style(Some("RADIOHEAD"), Some("paranoid android"), &all_lints());
+    assert_eq!(check.case.as_deref(), Some("RADIOHEAD / paranoid android"));
+}
+
+#[test]
+fn style_short_acronyms_pass_case_lint() {
+    let check = check_style(Some("ABBA"), Some("SOS"), &all_lints());
+    assert!(check.case.is_none());
+}
+
+#[test]
+fn style_explicit_marker() {
+    assert!(check_style(None, Some("Airbag [Explicit]"), &all_lints()).explicit);
+    assert!(check_style(None, Some("Airbag (explicit)"), &all_lints()).explicit);
+    assert!(!check_style(None, Some("Explicit Content"), &all_lints()).explicit);
+}
+
+#[test]
+fn style_double_and_padding_spaces() {
+    let check = check_style(Some("Radiohead "), Some("Paranoid  Android"), &all_lints());
+    assert_eq!(check.spaces.as_deref(), Some("Artist / Title"));
+}
+
+#[test]
+fn style_featuring_in_title() {
+    let check = check_style(Some("Radiohead"), Some("Airbag (feat. Thom Yorke) [Live]"), &all_lints());
+    assert_eq!(check.feat.as_deref(), Some("Thom Yorke"));
+    assert!(check_style(None, Some("Featuring Time"), &all_lints()).feat.is_none());
+}
+
+#[test]
+fn style_disabled_lints_are_skipped() {
+    let lints = StyleLints::parse("explicit").unwrap();
+    let check = check_style(Some("RADIOHEAD"), Some("Airbag  (feat. Thom Yorke)"), &lints);
+    assert!(check.case.is_none() && check.spaces.is_none() && check.feat.is_none());
+}
+
+#[test]
+fn style_normalize_applies_every_fix() {
+    let (artist, title, fixes) =
+        normalize_style("RADIOHEAD", "AIRBAG  (FEAT. THOM YORKE) [Explicit]", &all_lints());
+    assert_eq!(artist, "Radiohead feat. Thom Yorke");
+    assert_eq!(title, "Airbag");
+    let fields: Vec<&str> = fixes.iter().map(|f| f.field).collect();
+    assert_eq!(fields, ["Spacing", "Explicit", "Casing", "Casing", "Featuring", "Featuring"]);
+    assert!(fixes.iter().all(|f| f.category == "style"));
+}
+
+#[test]
+fn style_normalize_keeps_guest_already_in_artist() {
+    let (artist, title, _) =
+        normalize_style("Radiohead feat. Thom Yorke", "Airbag (ft. Thom Yorke)", &all_lints());
+    assert_eq!(artist, "Radiohead feat. Thom Yorke");
+    assert_eq!(title, "Airbag");
+}
+
+#[test]
+fn style_fix_rewrites_tags() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let root = dir.path().to_str().unwrap();
+    for fixture in [flac(), mp3()] {
+        let path = fixture.tag("ARTIST", "RADIOHEAD").tag("TITLE", "Airbag [Explicit]").write(dir.path(), "fixture");
+        let mut issue = crate::scan_file(&path).unwrap().0;
+        issue.style_case = Some("RADIOHEAD".into());
+
+        let diffs = crate::fix_style(&[issue], root, &all_lints(), false);
+        assert_eq!(diffs[&path].len(), 2);
+
+        let tags = crate::read_tags(&path).unwrap();
+        assert_eq!(tags["ARTIST"], "Radiohead");
+        assert_eq!(tags["TITLE"], "Airbag");
+        assert_eq!(flagged(&crate::scan_file(&path).unwrap().0), Vec::<&str>::new());
+    }
+}
+
+#[test]
+fn style_fix_dry_run_writes_nothing() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = flac().tag("ARTIST", "RADIOHEAD").write(dir.path(), "fixture");
+    let mut issue = crate::scan_file(&path).unwrap().0;
+    issue.style_case = Some("RADIOHEAD".into());
+
+    let diffs = crate::fix_style(&[issue], dir.path().to_str().unwrap(), &all_lints(), true);
+    assert!(diffs.is_empty());
+    assert_eq!(crate::read_tags(&path).unwrap()["ARTIST"], "RADIOHEAD");
+}
+
+// --- Blank tags ---
+
+#[test]
+fn strip_blank_tags_removes_empty_items_and_leaves_them_missing() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let root = dir.path().to_str().unwrap();
+    let path = flac().tag("ARTIST", " ").tag("GENRE", " ").write(dir.path(), "fixture");
+    let mut issues = vec![crate::scan_file(&path).unwrap().0];
+    assert_eq!(issues[0].blank_fields(), vec!["artist", "genre"]);
+
+    crate::strip_blank_tags(&mut issues, root, true);
+    assert!(crate::read_tags(&path).unwrap().contains_key("ARTIST"));
+    assert_eq!(flagged(&issues[0]), vec!["missing_artist", "missing_genre", "blank_artist", "blank_genre"]);
+
+    crate::strip_blank_tags(&mut issues, root, false);
+    let tags = crate::read_tags(&path).unwrap();
+    assert!(!tags.contains_key("ARTIST") && !tags.contains_key("GENRE"));
+    assert_eq!(tags["TITLE"], "Airbag");
+    // The in-memory issue matches a fresh scan of the stripped file
+    assert_eq!(flagged(&issues[0]), vec!["missing_artist", "missing_genre"]);
+    assert_eq!(flagged(&crate::scan_file(&path).unwrap().0), flagged(&issues[0]));
+}
+
+// --- Trivial fixes ---
+
+#[test]
+fn trivial_year_keeps_a_lone_year_from_a_date() {
+    assert_eq!(crate::trivial_year("2019-05-03").as_deref(), Some("2019"));
+    assert_eq!(crate::trivial_year("03/05/2019").as_deref(), Some("2019"));
+    assert_eq!(crate::trivial_year("(1997)").as_deref(), Some("1997"));
+    assert_eq!(crate::trivial_year("1997"), None);
+    assert_eq!(crate::trivial_year("1997/2007"), None);
+    assert_eq!(crate::trivial_year("'97"), None);
+    assert_eq!(crate::trivial_year("2019-123"), None);
+    assert_eq!(crate::trivial_year("0000-01-01"), None);
+}
+
+#[test]
+fn fix_trivial_strips_blanks_cuts_dates_and_fills_year_from_date() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let root = dir.path().to_str().unwrap();
+    let dated = flac().tag("YEAR", "unknown 2019-05-03").tag("GENRE", " ").write(dir.path(), "dated");
+    let date_only = flac().without("YEAR").tag("DATE", "1997-05-21").write(dir.path(), "date_only");
+    let paths = vec![dated.clone(), date_only.clone()];
+    let mut issues = vec![crate::scan_file(&dated).unwrap().0];
+    assert_eq!(flagged(&issues[0]), vec!["missing_genre", "invalid_year", "blank_genre"]);
+
+    let diffs = crate::fix_trivial(&paths, &mut issues, root, true, false);
+    assert!(diffs.is_empty());
+    assert_eq!(crate::read_tags(&dated).unwrap()["GENRE"], " ");
+
+    let diffs = crate::fix_trivial(&paths, &mut issues, root, false, false);
+    assert_eq!(diffs[&dated][0].new_value, "2019");
+    assert_eq!(flagged(&issues[0]), vec!["missing_genre", "invalid_year"]);
+    assert_eq!(flagged(&crate::scan_file(&dated).unwrap().0), vec!["missing_genre"]);
+    // The YEAR accessor falls back to DATE, so check the items themselves
+    use lofty::file::TaggedFileExt;
+    let tagged = lofty::read_from_path(&date_only).unwrap();
+    let tag = tagged.primary_tag().unwrap();
+    assert_eq!(tag.get_string(&lofty::tag::ItemKey::Year), Some("1997"));
+    assert_eq!(tag.get_string(&lofty::tag::ItemKey::RecordingDate), Some("1997-05-21"));
+
+    // A second run has nothing left to do
+    for path in &paths {
+        assert_eq!(crate::fix_trivial_file_tags(path, true, true).unwrap(), vec![]);
+    }
+}
+
+#[test]
+fn fix_trivial_normalize_fills_year_from_original_date() {
+    use lofty::file::TaggedFileExt;
+    use lofty::tag::ItemKey;
+    let dir = tempfile::TempDir::new().unwrap();
+    let flac_path = flac().without("YEAR").tag("ORIGINALDATE", "1971-11-08").write(dir.path(), "flac");
+    let mp3_path = mp3().without("YEAR").tag("ORIGINALDATE", "1971-11-08").write(dir.path(), "mp3");
+    let far_path = flac().without("YEAR").tag("ORIGINALDATE", "9999").write(dir.path(), "far");
+
+    // Only under --normalize
+    assert_eq!(crate::fix_trivial_file_tags(&flac_path, false, false).unwrap(), vec![]);
+    for path in [&flac_path, &mp3_path] {
+        let fixes = crate::fix_trivial_file_tags(path, false, true).unwrap();
+        assert_eq!(fixes.len(), 1);
+        assert_eq!((fixes[0].old.as_str(), fixes[0].new.as_str()), ("Missing", "1971"));
+    }
+    let tagged = lofty::read_from_path(&flac_path).unwrap();
+    assert_eq!(tagged.primary_tag().unwrap().get_string(&ItemKey::Year), Some("1971"));
+    assert_eq!(tagged.primary_tag().unwrap().get_string(&ItemKey::RecordingDate), None);
+    // ID3v2 keeps the year in TDRC
+    let tagged = lofty::read_from_path(&mp3_path).unwrap();
+    assert_eq!(tagged.primary_tag().unwrap().get_string(&ItemKey::RecordingDate), Some("1971"));
+    // A year outside the rules is left for the report
+    assert_eq!(crate::fix_trivial_file_tags(&far_path, false, true).unwrap(), vec![]);
+}
+
+// --- Rules file ---
+
+/// Scan a fixture under `rules` instead of the built-in ones.
+fn scan_with(fixture: Fixture, rules: &crate::Rules) -> FileIssue {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = fixture.write(dir.path(), "fixture");
+    let tagged = crate::read_tagged_file(&path, lofty::config::ParseOptions::new()).unwrap();
+    crate::check_tags(&path, 0, &tagged, rules).0
+}
+
+#[test]
+fn rules_file_replaces_keys_requirements_and_year_range() {
+    let rules = crate::Rules::parse(r#"
+        [year]
+        min = 1950
+        max = 1990
+
+        [fields.artist]
+        keys = ["ARTIST", "PERFORMER"]
+
+        [fields.bpm]
+        required = false
+
+        [fields.album-art]
+        required = false
+
+        [[category]]
+        id = "mastering"
+        title = "Mastering"
+        fields = [
+            { label = "Track Gain", keys = ["REPLAYGAIN_TRACK_GAIN"] },
+            { label = "ISRC", keys = ["ISRC"] },
+        ]
+    "#).unwrap();
+
+    let issue = scan_with(flac().without("ARTIST").tag("PERFORMER", "Radiohead").without("BPM").without_picture(), &rules);
+    assert_eq!(flagged(&issue), ["invalid_year"]);
+    assert_eq!(issue.custom_missing, ["mastering-track-gain", "mastering-isrc"]);
+    assert!(rules.categories[0].has_issue(&issue));
+
+    // Lofty reports REPLAYGAIN_TRACK_GAIN as REPLAYGAINTRACKGAIN
+    let issue = scan_with(flac().tag("YEAR", "1985").tag("REPLAYGAIN_TRACK_GAIN", "-6.2 dB").tag("ISRC", "GBAYE9700001"), &rules);
+    assert_eq!(flagged(&issue), Vec::<&str>::new());
+    assert!(issue.custom_missing.is_empty());
+
+    // Built-in rules are unchanged without a file
+    let issue = flac().without("BPM").scan();
+    assert_eq!(flagged(&issue), ["missing_bpm"]);
+    assert!(!issue.has_custom());
+}
+
+#[test]
+fn rules_file_errors_name_the_entry() {
+    let error = |text: &str| crate::Rules::parse(text).unwrap_err();
+    assert!(error("[fields.composer]\nrequired = false").contains("fields.composer"));
+    assert!(error("[fields.album-art]\nkeys = [\"COVER\"]").contains("album-art"));
+    assert!(error("[fields.genre]\nkeys = []").contains("fields.genre"));
+    assert!(error("[year]\nmin = 2000\nmax = 1990").contains("year"));
+    assert!(error("[[category]]\nid = \"mb\"\ntitle = \"Mine\"\nfields = [{ label = \"X\", keys = [\"X\"] }]").contains("built-in"));
+    assert!(error("[[category]]\nid = \"Loud Ness\"\ntitle = \"L\"\nfields = [{ label = \"X\", keys = [\"X\"] }]").contains("lowercase"));
+    assert!(error("[[category]]\nid = \"x\"\ntitle = \"X\"\nfields = []").contains("no fields"));
+    assert!(error("[strict]\non = true").contains("strict"));
+}
+
+// --- Mirror comparison ---
+
+/// Write `fixture` at `rel` (no extension) under `root`, creating folders.
+fn write_at(fixture: &Fixture, root: &std::path::Path, rel: &str) -> std::path::PathBuf {
+    let path = root.join(rel);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fixture.write(path.parent().unwrap(), path.file_name().unwrap().to_str().unwrap())
+}
+
+fn compare(
+    master: &[std::path::PathBuf],
+    mirror: &[std::path::PathBuf],
+    roots: (&std::path::Path, &std::path::Path),
+) -> crate::MirrorReport {
+    crate::compare_mirror(
+        roots.0.to_str().unwrap(),
+        master,
+        roots.1.to_str().unwrap(),
+        mirror,
+    )
+}
+
+#[test]
+fn mirror_transcode_pairs_by_path_without_extension() {
+    let (a, b) = (tempfile::TempDir::new().unwrap(), tempfile::TempDir::new().unwrap());
+    let master = write_at(&flac(), a.path(), "Radiohead/OK Computer/01 Airbag");
+    let copy = write_at(&mp3(), b.path(), "Radiohead/OK Computer/01 Airbag");
+    let report = compare(&[master], &[copy], (a.path(), b.path()));
+    assert_eq!(report.common, 1);
+    assert_eq!(report.difference_count(), 0);
+}
+
+#[test]
+fn mirror_reports_tag_divergence() {
+    let (a, b) = (tempfile::TempDir::new().unwrap(), tempfile::TempDir::new().unwrap());
+    let master = write_at(&flac(), a.path(), "Radiohead/OK Computer/01 Airbag");
+    let copy = write_at(&mp3().tag("TITLE", "Airbag (Remaster)"), b.path(), "Radiohead/OK Computer/01 Airbag");
+    let report = compare(&[master], &[copy], (a.path(), b.path()));
+    assert_eq!(report.diverged.len(), 1);
+    let (rel, fields) = &report.diverged[0];
+    assert_eq!(rel, "Radiohead/OK Computer/01 Airbag.flac");
+    assert_eq!(fields, &[("Title", "Airbag".to_string(), "Airbag (Remaster)".to_string())]);
+}
+
+#[test]
+fn mirror_track_numbers_compare_by_value() {
+    let (a, b) = (tempfile::TempDir::new().unwrap(), tempfile::TempDir::new().unwrap());
+    let master = write_at(&flac().tag("TRACKNUMBER", "01"), a.path(), "Radiohead/OK Computer/01 Airbag");
+    let copy = write_at(&flac().tag("TRACKNUMBER", "1"), b.path(), "Radiohead/OK Computer/01 Airbag");
+    let report = compare(&[master], &[copy], (a.path(), b.path()));
+    assert_eq!(report.difference_count(), 0);
+}
+
+#[test]
+fn mirror_keeps_files_differing_only_in_extension() {
+    let (a, b) = (tempfile::TempDir::new().unwrap(), tempfile::TempDir::new().unwrap());
+    let master = vec![
+        write_at(&flac(), a.path(), "Radiohead/OK Computer/01 Airbag"),
+        write_at(&mp3(), a.path(), "Radiohead/OK Computer/01 Airbag"),
+    ];
+    let mirror = vec![write_at(&mp3().tag("TITLE", "Airbag (Remaster)"), b.path(), "Radiohead/OK Computer/01 Airbag")];
+    let report = compare(&master, &mirror, (a.path(), b.path()));
+    // The .mp3 pairs with its namesake, the .flac isn't lost behind it
+    assert_eq!(report.common, 1);
+    assert_eq!(report.diverged[0].0, "Radiohead/OK Computer/01 Airbag.mp3");
+    assert_eq!(report.master_only, ["Radiohead/OK Computer/01 Airbag.flac"]);
+    assert!(report.mirror_only.is_empty());
+}
+
+#[test]
+fn mirror_separates_moved_from_missing() {
+    let (a, b) = (tempfile::TempDir::new().unwrap(), tempfile::TempDir::new().unwrap());
+    let master = vec![
+        write_at(&flac(), a.path(), "Radiohead/OK Computer/01 Airbag"),
+        write_at(&flac().tag("TITLE", "Paranoid Android"), a.path(), "Radiohead/OK Computer/02 Paranoid Android"),
+    ];
+    let mirror = vec![
+        write_at(&mp3(), b.path(), "Radiohead/OK Computer (1997)/01 Airbag"),
+        write_at(&mp3().tag("TITLE", "Creep"), b.path(), "Radiohead/Pablo Honey/02 Creep"),
+    ];
+    let report = compare(&master, &mirror, (a.path(), b.path()));
+    assert_eq!(report.common, 0);
+    assert_eq!(
+        report.moved,
+        [("Radiohead/OK Computer/01 Airbag.flac".to_string(), "Radiohead/OK Computer (1997)/01 Airbag.mp3".to_string())]
+    );
+    assert_eq!(report.master_only, ["Radiohead/OK Computer/02 Paranoid Android.flac"]);
+    assert_eq!(report.mirror_only, ["Radiohead/Pablo Honey/02 Creep.mp3"]);
+}
+
+// --- IO throttle ---
+
+#[test]
+fn io_throttle_sleeps_once_past_the_burst() {
+    let throttle = crate::IoThrottle::new(1.0, 0);
+    let start = std::time::Instant::now();
+    throttle.consume(128 * 1024); // 125 ms of budget: within the burst
+    assert!(start.elapsed() < std::time::Duration::from_millis(100));
+    throttle.consume(512 * 1024); // now 625 ms ahead: sleeps ~375 ms
+    assert!(start.elapsed() >= std::time::Duration::from_millis(300));
+}
+
+#[test]
+fn io_throttle_caps_open_files() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    let throttle = crate::IoThrottle::new(0.0, 2);
+    let open = AtomicUsize::new(0);
+    let peak = AtomicUsize::new(0);
+    std::thread::scope(|s| {
+        for _ in 0..8 {
+            s.spawn(|| {
+                let _permit = throttle.open_file();
+                let now = open.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                std::thread::sleep(std::time::Duration::from_millis(10));
+                open.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+    });
+    assert_eq!(peak.load(Ordering::SeqCst), 2);
+}
+
+// --- Digest ---
+
+#[test]
+fn digest_ranks_artists_by_files_with_issues() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let root = dir.path();
+    let paths = vec![
+        write_at(&flac().without("ARTIST"), root, "Alpha/Album/01"),
+        write_at(&flac().without("GENRE"), root, "Alpha/Album/02"),
+        write_at(&flac(), root, "Alpha/Album/03"),
+        write_at(&flac().without("GENRE"), root, "Beta/Album/01"),
+        write_at(&flac(), root, "Clean/Album/01"),
+    ];
+    let issues: Vec<FileIssue> = paths.iter()
+        .map(|p| crate::scan_file(p).unwrap().0)
+        .filter(|i| i.has_any_issue())
+        .collect();
+
+    let artists = crate::worst_artists(&issues, &paths, root.to_str().unwrap(), 20);
+    let summary: Vec<(&str, usize, usize, usize)> = artists.iter()
+        .map(|a| (a.name.as_str(), a.issues, a.critical, a.files))
+        .collect();
+    assert_eq!(summary, vec![("Alpha", 2, 1, 3), ("Beta", 1, 0, 1)]);
+    assert_eq!(crate::worst_artists(&issues, &paths, root.to_str().unwrap(), 1).len(), 1);
+}
+
+// --- Health score ---
+
+#[test]
+fn health_weights_critical_issues_above_cosmetic_ones() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let root = dir.path();
+    let mut paths = vec![
+        write_at(&flac().without("ARTIST"), root, "Alpha/Album/01"),
+        write_at(&flac().without("GENRE"), root, "Alpha/Album/02"),
+        write_at(&flac(), root, "Alpha/Album/03"),
+        write_at(&flac().without("GENRE"), root, "Beta/Album/01"),
+        write_at(&flac(), root, "Clean/Album/01"),
+    ];
+    let issues: Vec<FileIssue> = paths.iter()
+        .map(|p| crate::scan_file(p).unwrap().0)
+        .filter(|i| i.has_any_issue())
+        .collect();
+    let broken = root.join("Gamma/Album/01.flac");
+    paths.push(broken.clone());
+    let unreadable = vec![(broken, "not a FLAC file".to_string())];
+
+    // Critical 10 + genre 2 + genre 2 + unreadable 10 of 6 files x 10
+    let health = crate::Health::of(&issues, 6, 1);
+    assert_eq!((health.score(), health.grade()), (60.0, "D"));
+    assert_eq!(crate::Health::default().score(), 100.0);
+
+    let artists = crate::artist_health(&issues, &paths, &unreadable, root.to_str().unwrap());
+    let board: Vec<(&str, f64, &str, usize, usize)> = artists.iter()
+        .map(|a| (a.name.as_str(), a.health.score(), a.health.grade(), a.issues, a.unreadable))
+        .collect();
+    assert_eq!(board, vec![("Gamma", 0.0, "F", 0, 1), ("Alpha", 60.0, "D", 2, 0), ("Beta", 80.0, "B", 1, 0)]);
+}
+
+// --- Albums ---
+
+#[test]
+fn album_view_groups_files_by_release_folder_with_issue_counts() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let root = dir.path();
+    let paths = [
+        write_at(&flac().without("YEAR").without("MUSICBRAINZ_ALBUMID"), root, "Radiohead/OK Computer/01"),
+        write_at(&flac().without("MUSICBRAINZ_ALBUMID"), root, "Radiohead/OK Computer/02"),
+        write_at(&flac(), root, "Radiohead/OK Computer/03"),
+        write_at(&flac().without("GENRE"), root, "Radiohead/Kid A/01"),
+        write_at(&flac().without("YEAR"), root, "Loose"),
+    ];
+    let issues: Vec<FileIssue> = paths.iter()
+        .map(|p| crate::scan_file(p).unwrap().0)
+        .filter(|i| i.has_any_issue())
+        .collect();
+
+    let panel = crate::build_album_panel(&issues, root.to_str().unwrap(), None, None);
+    let groups: Vec<(&str, usize)> = panel.groups.iter().map(|(k, v)| (k.as_str(), v.len())).collect();
+    assert_eq!(groups, vec![("(scan root)", 1), ("Radiohead/Kid A", 1), ("Radiohead/OK Computer", 2)]);
+    assert_eq!(panel.summaries["Radiohead/OK Computer"], "Year: 1, MB Album: 2");
+    assert_eq!(panel.summaries["Radiohead/Kid A"], "Genre: 1");
+    let first = &panel.groups["Radiohead/OK Computer"][0];
+    assert_eq!(first.path, "Radiohead/OK Computer/01.flac");
+    assert_eq!(first.annotation.as_deref(), Some("(Year, MB Album)"));
+}
+
+#[test]
+fn digest_reads_back_its_counts_for_deltas() {
+    let counts = vec![("files", "Files", 10), ("issues", "Files with Issues", 4)];
+    let html = crate::render_digest("/music", "2026-10-18 03:00", &counts, &Default::default(), &[]);
+    let previous = crate::read_previous_digest(&html);
+    assert_eq!(previous.get("files"), Some(&10));
+    assert_eq!(previous.get("issues"), Some(&4));
+    assert!(crate::read_previous_digest("<html>not a digest</html>").is_empty());
+
+    let next = vec![("files", "Files", 12), ("issues", "Files with Issues", 1)];
+    let html = crate::render_digest("/music", "2026-10-19 03:00", &next, &previous, &[]);
+    assert!(html.contains(">+2<"));
+    assert!(html.contains(">&minus;3<"));
+}
+
+#[test]
+fn digest_delta_formatting() {
+    assert_eq!(crate::format_delta(5, None), "&ndash;");
+    assert_eq!(crate::format_delta(5, Some(5)), "=");
+    assert_eq!(crate::format_delta(7, Some(5)), "+2");
+    assert_eq!(crate::format_delta(3, Some(5)), "&minus;2");
+}
+
+// --- Provenance ---
+
+#[test]
+fn provenance_command_line_quotes_arguments_with_spaces() {
+    let args: Vec<String> = ["/mnt/c/__DMP", "--only=pink floyd", "--style", "all", "it's"]
+        .iter().map(|a| a.to_string()).collect();
+    assert_eq!(
+        crate::command_line("analysis", &args),
+        "analysis /mnt/c/__DMP '--only=pink floyd' --style all 'it'\\''s'"
+    );
+}
+
+#[test]
+fn provenance_rules_signature_follows_the_style_lints() {
+    let none = crate::rules_signature(&StyleLints::default());
+    let all = crate::rules_signature(&all_lints());
+    assert_ne!(none, all);
+    assert_eq!(none, crate::rules_signature(&StyleLints::parse("").unwrap()));
+    assert!(none.contains("critical:Artist,Title,Year"));
+    assert!(all.contains("style:case,explicit,spaces,feat"));
+}
+
+// --- Protected folders ---
+
+#[test]
+fn protect_glob_keeps_single_stars_within_a_folder() {
+    assert!(crate::glob_match("vinyl rips", "vinyl rips"));
+    assert!(crate::glob_match("*/vinyl*", "pink floyd/vinyl 1973"));
+    assert!(!crate::glob_match("*/vinyl*", "pink floyd/live/vinyl 1973"));
+    assert!(crate::glob_match("**/vinyl*", "pink floyd/live/vinyl 1973"));
+    assert!(crate::glob_match("**/vinyl*", "vinyl 1973"));
+    assert!(crate::glob_match("pink floyd/19??*", "pink floyd/1973 - dsotm"));
+    assert!(!crate::glob_match("pink floyd/19??*", "pink floyd/2011 - remaster"));
+}
+
+#[test]
+fn protect_covers_files_below_a_matching_folder() {
+    let protected = crate::Protected::new(&["Pink Floyd/Vinyl*".to_string(), "**/_hold".to_string()]);
+    let root = "/music";
+    let covered = |rel: &str| protected.matching(std::path::Path::new(&format!("{}/{}", root, rel)), root);
+    assert_eq!(covered("Pink Floyd/Vinyl 1973/CD1/01.flac"), Some("Pink Floyd/Vinyl*"));
+    assert_eq!(covered("pink floyd/vinyl 1973/01.flac"), Some("Pink Floyd/Vinyl*"));
+    assert_eq!(covered("Beta/_hold/01.flac"), Some("**/_hold"));
+    assert_eq!(covered("Pink Floyd/Animals/01.flac"), None);
+    assert_eq!(covered("Pink Floyd.flac"), None);
+    assert!(!crate::Protected::new(&[]).covers(std::path::Path::new("/music/Pink Floyd/Vinyl/01.flac"), root));
+}
+
+// --- Excluded paths ---
+
+#[test]
+fn exclude_reads_the_ignore_file_and_skips_matching_folders_and_files() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let root = dir.path().to_str().unwrap();
+    std::fs::write(dir.path().join(".analysisignore"), "# staging\n__QUARANTINE\n\n*/live bootlegs/*\n").unwrap();
+    let excluded = crate::Excluded::load(&["_incoming/".to_string()], root).unwrap();
+    let patterns: Vec<&str> = excluded.patterns.iter().map(|(p, _)| p.as_str()).collect();
+    assert_eq!(patterns, ["_incoming", "__QUARANTINE", "*/live bootlegs/*"]);
+
+    let skipped = |rel: &str| excluded.matches(&dir.path().join(rel), root);
+    assert!(skipped("__quarantine"));
+    assert!(skipped("__QUARANTINE/Radiohead/OK Computer/01 Airbag.flac"));
+    assert!(skipped("_incoming/new rip/01.flac"));
+    assert!(skipped("Radiohead/Live Bootlegs/1997-05-21 Paris.flac"));
+    assert!(skipped("Radiohead/live bootlegs/Paris/01.flac"));
+    assert!(!skipped("Radiohead/live bootlegs"));
+    assert!(!skipped("Radiohead/_incoming/01.flac"));
+    assert!(!skipped("Radiohead/OK Computer/01 Airbag.flac"));
+    assert!(!excluded.matches(dir.path(), root));
+
+    // No ignore file: only the flags
+    let bare = tempfile::TempDir::new().unwrap();
+    assert!(crate::Excluded::load(&[], bare.path().to_str().unwrap()).unwrap().is_empty());
+}
+
+// --- Quarantine manifest ---
+
+#[test]
+fn end_quarantine_restores_by_artist_and_manifest_category() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let root = dir.path().to_str().unwrap();
+    let mut manifest = crate::QuarantineManifest::default();
+    let mut stage = |staging: &str, rel: &str, category: &str| {
+        let destination = dir.path().join(staging).join(rel);
+        std::fs::create_dir_all(destination.parent().unwrap()).unwrap();
+        std::fs::write(&destination, "audio").unwrap();
+        manifest.record(crate::QuarantineEntry {
+            original: dir.path().join(rel),
+            destination,
+            staging: staging.to_string(),
+            categories: vec![category.to_string()],
+            reason: String::new(),
+            moved_at: String::new(),
+        });
+    };
+    stage("__QUARANTINE", "Beck/Odelay/01.flac", "critical");
+    stage("__QUARANTINE", "Air/Moon Safari/01.flac", "critical");
+    stage("__UNREADABLE", "Beck/Odelay/02.mp3", "unreadable");
+    let manifest_path = crate::QuarantineManifest::path(root);
+    manifest.save(&manifest_path).unwrap();
+
+    let beck = dmp_common::FolderFilter::new("beck", "", "");
+    crate::end_quarantine(root, &beck, Some("critical"));
+    assert!(dir.path().join("Beck/Odelay/01.flac").exists());
+    assert!(!dir.path().join("Air/Moon Safari/01.flac").exists());
+    assert!(!dir.path().join("Beck/Odelay/02.mp3").exists());
+    let left = crate::QuarantineManifest::load(&manifest_path).unwrap();
+    assert_eq!(left.entries.len(), 2);
+
+    // Everything else; the manifest and staging folders go with it
+    crate::end_quarantine(root, &dmp_common::FolderFilter::new("", "", ""), None);
+    assert!(dir.path().join("Air/Moon Safari/01.flac").exists());
+    assert!(dir.path().join("Beck/Odelay/02.mp3").exists());
+    assert!(!dir.path().join("__QUARANTINE").exists() && !dir.path().join("__UNREADABLE").exists());
+}
+
+// --- Custom autofix tagger ---
+
+#[test]
+fn tagger_command_fills_placeholders_and_reads_skip_and_match_patterns() {
+    use crate::{TaggerCommand, TaggerConfig, TaggerOutcome};
+    use std::path::Path;
+
+    let config = |command: &str, skip: &[&str], matched: &[&str]| TaggerConfig {
+        command: Some(command.to_string()),
+        skip_patterns: skip.iter().map(|p| p.to_string()).collect(),
+        match_patterns: matched.iter().map(|p| p.to_string()).collect(),
+        ..TaggerConfig::default()
+    };
+    assert!(TaggerCommand::parse(config("tagger --write", &[], &[])).unwrap_err().contains("{dir}"));
+    assert!(TaggerCommand::parse(config("tagger '{dir}", &[], &[])).is_err());
+    assert!(TaggerCommand::from_args(None, None).unwrap().is_none());
+
+    let tagger = TaggerCommand::parse(config("/opt/bin/tag-it --root={dir} \"{files}\"", &[], &[])).unwrap();
+    assert_eq!(tagger.name, "tag-it");
+    let files = [Path::new("/lib/A B/01.flac"), Path::new("/lib/A B/02.flac")];
+    assert_eq!(
+        TaggerCommand::expand(&tagger.command, Path::new("/lib/A B"), &files),
+        vec!["/opt/bin/tag-it", "--root=/lib/A B", "/lib/A B/01.flac", "/lib/A B/02.flac"],
+    );
+    // No dry_command: a dry run prints the command without running it
+    match tagger.run(Path::new("/lib/A B"), &files[..1], true) {
+        TaggerOutcome::Tagged(lines) => assert_eq!(lines, vec!["would run: /opt/bin/tag-it '--root=/lib/A B' '/lib/A B/01.flac'"]),
+        _ => panic!("expected a printed command"),
+    }
+
+    let run = |command: &str, skip: &[&str], matched: &[&str]| {
+        TaggerCommand::parse(config(command, skip, matched)).unwrap().run(Path::new("/lib"), &[], false)
+    };
+    assert!(matches!(run("sh -c 'echo Saved {dir}'", &["no match"], &["saved"]), TaggerOutcome::Tagged(_)));
+    match run("sh -c 'echo looking up {dir}; echo NO MATCH found'", &["no match"], &[]) {
+        TaggerOutcome::Skipped(reason) => assert_eq!(reason, "NO MATCH found"),
+        _ => panic!("expected a skip"),
+    }
+    match run("sh -c 'echo looked up {dir}'", &[], &["saved"]) {
+        TaggerOutcome::Skipped(reason) => assert_eq!(reason, "No confident match from sh"),
+        _ => panic!("expected a skip"),
+    }
+    match run("sh -c 'echo {dir} is read-only >&2; exit 2'", &[], &[]) {
+        TaggerOutcome::Error(line) => assert_eq!(line, "/lib is read-only"),
+        _ => panic!("expected an error"),
+    }
+    assert!(matches!(run("/nonexistent/tagger {dir}", &[], &[]), TaggerOutcome::Failed(_)));
+}
+
+#[test]
+fn native_autofix_picks_a_release_matches_tracks_and_writes_only_missing_tags() {
+    use crate::{match_release_tracks, native_writes, pick_release, read_tags, write_native_tags, LocalTrack, MbRelease, MbReleaseSearch};
+
+    let hits: MbReleaseSearch = serde_json::from_str(r#"{"releases": [
+        {"id": "low", "score": 80, "track-count": 2},
+        {"id": "deluxe", "score": 100, "track-count": 24},
+        {"id": "ok", "score": 95, "track-count": 2}
+    ]}"#).unwrap();
+    assert_eq!(pick_release(&hits, 2), Some("ok"));
+    assert_eq!(pick_release(&hits, 3), None);
+
+    let release: MbRelease = serde_json::from_str(r#"{
+        "id": "rel-1", "title": "OK Computer", "date": "1997-05-21",
+        "artist-credit": [{"name": "Radiohead", "joinphrase": "", "artist": {"id": "art-1"}}],
+        "media": [{"position": 1, "tracks": [
+            {"position": 1, "title": "Airbag", "recording": {"id": "rec-1"}},
+            {"position": 2, "title": "Paranoid Android", "recording": {"id": "rec-2"}}
+        ]}]
+    }"#).unwrap();
+
+    // One file found by its track number, one by its title, one by neither
+    let dir = tempfile::TempDir::new().unwrap();
+    let by_number = write_at(&flac().without("YEAR").without("MUSICBRAINZ_TRACKID").without("MUSICBRAINZ_ALBUMID").tag("TRACKNUMBER", "1"), dir.path(), "01");
+    let by_title = write_at(&mp3().tag("TITLE", "paranoid android").without("MUSICBRAINZ_ARTISTID").without("MUSICBRAINZ_ALBUMID"), dir.path(), "02");
+    let stray = write_at(&flac().tag("TITLE", "Lucky"), dir.path(), "03");
+    let tracks: Vec<LocalTrack> = [&by_number, &by_title, &stray].iter()
+        .map(|p| LocalTrack::new(p.to_path_buf(), read_tags(p).unwrap()))
+        .collect();
+    let matches = match_release_tracks(&release, &tracks);
+    assert_eq!(matches[0].as_ref().unwrap().recording_id, "rec-1");
+    assert_eq!(matches[1].as_ref().unwrap().recording_id, "rec-2");
+    assert!(matches[2].is_none());
+
+    let labels = |i: usize| -> Vec<&str> {
+        native_writes(&tracks[i], matches[i].as_ref().unwrap()).iter().map(|(label, _, _)| *label).collect()
+    };
+    assert_eq!(labels(0), ["year", "MB track ID", "MB album ID"]);
+    assert_eq!(labels(1), ["MB artist ID", "MB album ID"]);
+
+    for i in 0..2 {
+        write_native_tags(&tracks[i].path, &native_writes(&tracks[i], matches[i].as_ref().unwrap())).unwrap();
+        let issue = crate::scan_file(&tracks[i].path).unwrap().0;
+        assert!(!issue.missing_year && !issue.missing_mb_artist_id && !issue.missing_mb_track_id && !issue.missing_mb_album_id);
+    }
+    // The title the file already had is kept
+    assert_eq!(read_tags(&by_title).unwrap().get("TITLE").map(String::as_str), Some("paranoid android"));
+}
+
+// --- Beets export ---
+
+#[test]
+fn beets_export_groups_files_by_category_and_folder() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let root = dir.path();
+    let paths = [
+        write_at(&flac().without("ARTIST"), root, "Alpha/Album/01"),
+        write_at(&flac().without("ARTIST").without("GENRE"), root, "Alpha/Album/02"),
+        write_at(&flac().without("GENRE"), root, "Beta/Album/01"),
+        write_at(&flac(), root, "Clean/Album/01"),
+    ];
+    let issues: Vec<FileIssue> = paths.iter()
+        .map(|p| crate::scan_file(p).unwrap().0)
+        .filter(|i| i.has_any_issue())
+        .collect();
+
+    let exports = crate::beets_exports(&issues);
+    let categories: Vec<&str> = exports.iter().map(|e| e.category).collect();
+    assert_eq!(categories, vec!["critical", "other"]);
+    assert_eq!(exports[0].files, vec![paths[0].clone(), paths[1].clone()]);
+    assert_eq!(exports[0].dirs, vec![root.join("Alpha/Album")]);
+    assert_eq!(exports[1].dirs, vec![root.join("Alpha/Album"), root.join("Beta/Album")]);
+
+    let out = root.join("beets");
+    crate::write_beets_exports(&out, &exports).unwrap();
+    let query = std::fs::read_to_string(out.join("other.query")).unwrap();
+    assert_eq!(query, format!("path:{}\npath:{}\n", root.join("Alpha/Album").display(), root.join("Beta/Album").display()));
+    let files = std::fs::read_to_string(out.join("critical.txt")).unwrap();
+    assert_eq!(files.lines().count(), 2);
+    assert!(!out.join("mb.txt").exists());
+}
+
+// --- Root files ---
+
+#[test]
+fn root_files_get_their_own_group_and_a_suggested_folder() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let root = dir.path().to_str().unwrap();
+    let loose = write_at(&flac().tag("ALBUM", "OK Computer"), dir.path(), "01 Airbag");
+    let sorted = write_at(&flac(), dir.path(), "Radiohead/OK Computer/01 Airbag");
+    let untagged = write_at(&flac().without("ARTIST"), dir.path(), "02 Unknown");
+
+    assert!(crate::is_root_file(&loose, root));
+    assert!(!crate::is_root_file(&sorted, root));
+    assert_eq!(crate::get_artist_folder(&loose, root), crate::ROOT_GROUP);
+    assert_eq!(crate::get_artist_folder(&sorted, root), "Radiohead");
+    assert_eq!(crate::suggested_folder(&loose).as_deref(), Some("Radiohead/OK Computer/"));
+    assert_eq!(crate::suggested_folder(&untagged), None);
+}
+
+// --- Tag containers ---
+
+#[test]
+fn ape_values_missing_from_id3v2_are_flagged() {
+    let issue = mp3().without("GENRE").ape("Genre", "Alternative Rock").scan();
+    assert_eq!(flagged(&issue), ["unusual_tags"]);
+    assert_eq!(issue.unusual_tags.as_deref(), Some("APEv2 in MP3"));
+}
+
+#[test]
+fn ape_duplicating_id3v2_is_not_flagged() {
+    let issue = mp3().ape("Genre", "Alternative Rock").scan();
+    assert_eq!(flagged(&issue), Vec::<&str>::new());
+}
+
+#[test]
+fn migrate_tags_copies_into_id3v2() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = mp3().without("GENRE").ape("Genre", "Alternative Rock").write(dir.path(), "fixture");
+    let issue = crate::scan_file(&path).unwrap().0;
+
+    let diffs = crate::migrate_tags(&[issue], dir.path().to_str().unwrap());
+    assert_eq!(diffs[&path][0].new_value, "ID3v2");
+    assert_eq!(flagged(&crate::scan_file(&path).unwrap().0), Vec::<&str>::new());
+}
+
+// --- Tag value consistency ---
+
+#[test]
+fn spellings_differing_by_case_accents_and_spacing_are_grouped() {
+    let mut counts = crate::ValueCounts::new();
+    for (field, value, n) in [
+        ("Genre", "Hip-Hop", 12), ("Genre", "Hip Hop", 3), ("Genre", "hip hop", 1),
+        ("Genre", "Rock", 40), ("Artist", "Björk", 9), ("Artist", "Bjork ", 2),
+        ("Artist", "!!!", 4), ("Artist", "???", 1),
+    ] {
+        counts.insert((field, value.to_string()), n);
+    }
+
+    let variants = crate::find_variants(&counts);
+    assert_eq!(variants.len(), 2);
+    assert_eq!(variants[0].field, "Genre");
+    assert_eq!(variants[0].files(), 16);
+    assert_eq!(variants[0].values[0], ("Hip-Hop".to_string(), 12));
+    assert_eq!(variants[1].values, [("Björk".to_string(), 9), ("Bjork ".to_string(), 2)]);
+    assert_eq!(crate::visible_spaces("Bjork "), "\"Bjork␣\"");
+    assert_eq!(crate::visible_spaces("Hip  Hop"), "\"Hip␣␣Hop\"");
+}
+
+#[test]
+fn scan_returns_the_compared_tag_values() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = flac().tag("GENRE", "Trip-Hop").write(dir.path(), "fixture");
+    let (_, values) = crate::scan_file(&path).unwrap();
+    assert!(values.contains(&("Genre", "Trip-Hop".to_string())));
+    assert!(values.iter().any(|(field, _)| *field == "Artist"));
+}
+
+// --- Archives ---
+
+#[test]
+fn archive_entries_are_checked_in_memory() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let good = flac().write(dir.path(), "01 Airbag");
+    let bad = mp3().without("YEAR").write(dir.path(), "02 Paranoid Android");
+
+    let zip_path = dir.path().join("incoming").join("Radiohead - OK Computer.zip");
+    std::fs::create_dir_all(zip_path.parent().unwrap()).unwrap();
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(&zip_path).unwrap());
+    let options = zip::write::SimpleFileOptions::default();
+    for (name, path) in [("OK Computer/01 Airbag.flac", &good), ("OK Computer/02 Paranoid Android.mp3", &bad)] {
+        zip.start_file(name, options).unwrap();
+        std::io::Write::write_all(&mut zip, &std::fs::read(path).unwrap()).unwrap();
+    }
+    zip.start_file("OK Computer/cover.jpg", options).unwrap();
+    zip.start_file("OK Computer/03 Subterranean.flac", options).unwrap();
+    std::io::Write::write_all(&mut zip, b"not audio").unwrap();
+    zip.finish().unwrap();
+
+    let incoming = dir.path().join("incoming");
+    let incoming = incoming.to_str().unwrap();
+    assert_eq!(crate::find_archives(incoming), vec![zip_path.clone()]);
+    let scan = crate::scan_archive(&zip_path, incoming);
+    assert_eq!(scan.name, "Radiohead - OK Computer.zip");
+    assert_eq!(scan.audio_files, 3);
+    assert_eq!(scan.issues.len(), 1);
+    assert_eq!(flagged(&scan.issues[0]), ["missing_year"]);
+    assert!(scan.issues[0].path.ends_with("OK Computer/02 Paranoid Android.mp3"));
+    assert_eq!(scan.unreadable.len(), 1);
+    assert_eq!(scan.unreadable[0].0, "OK Computer/03 Subterranean.flac");
+    assert_eq!(scan.summary(), "Year (1), Unreadable (1)");
+}
+
+#[test]
+fn corrupt_archive_is_reported() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("broken.zip");
+    std::fs::write(&path, b"PK not really").unwrap();
+    let scan = crate::scan_archive(&path, dir.path().to_str().unwrap());
+    assert!(!scan.is_clean());
+    assert!(scan.error.is_some());
+}
+
+// --- Links ---
+
+#[cfg(unix)]
+#[test]
+fn symlinks_and_hardlinks_resolve_to_the_same_file() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let original = flac().write(dir.path(), "01 Airbag");
+    let hard = dir.path().join("hard.flac");
+    let soft = dir.path().join("soft.flac");
+    std::fs::hard_link(&original, &hard).unwrap();
+    std::os::unix::fs::symlink(&original, &soft).unwrap();
+    let other = flac().write(dir.path(), "02 Paranoid Android");
+
+    let id = |p: &std::path::Path| crate::file_id(&std::fs::metadata(p).unwrap());
+    assert_eq!(id(&hard), id(&original));
+    assert_eq!(id(&soft), id(&original));
+    assert_ne!(id(&other), id(&original));
+    assert_eq!(crate::link_kind(&soft, &original), "symlink");
+    assert_eq!(crate::link_kind(&hard, &original), "hardlink");
+}
+
+// --- Lone files ---
+
+#[test]
+fn lone_file_note_tells_unsplit_albums_from_singles() {
+    let lone = |secs: Option<u64>| crate::LoneFile {
+        path: "Artist/Album/track.flac".into(),
+        duration: secs.map(std::time::Duration::from_secs),
+    };
+    assert_eq!(lone(Some(48 * 60 + 22)).note(), "Only one file: likely un-split album (48:22)");
+    assert_eq!(lone(Some(3662)).note(), "Only one file: likely un-split album (1:01:02)");
+    assert_eq!(lone(Some(225)).note(), "Only one file: stray single (3:45)");
+    assert_eq!(lone(None).note(), "Only one file");
+}
+
+#[test]
+fn lone_files_with_a_cue_sheet_are_album_rips() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let (rip_dir, single_dir) = (dir.path().join("Artist/Live"), dir.path().join("Artist/Single"));
+    std::fs::create_dir_all(&rip_dir).unwrap();
+    std::fs::create_dir_all(&single_dir).unwrap();
+    let rip = flac().write(&rip_dir, "Live");
+    std::fs::write(rip_dir.join("Live.cue"), "FILE \"Live.flac\" WAVE\n").unwrap();
+    let single = flac().write(&single_dir, "01 Single");
+
+    let paths = vec![rip.clone(), single.clone()];
+    let counts = crate::count_folder_audio(&paths);
+    assert!(crate::is_cue_rip(&rip));
+    assert!(!crate::is_cue_rip(&single));
+    let lone = crate::find_lone_files(&paths, &counts, dir.path().to_str().unwrap());
+    assert_eq!(lone.iter().map(|l| &l.path).collect::<Vec<_>>(), vec![&single]);
+}
+
+// --- Fingerprint dupes ---
+
+/// Chromaprint's compression, the inverse of `decode_fingerprint`.
+fn compress_fingerprint(items: &[u32]) -> String {
+    let (mut normal, mut overflow) = (Vec::new(), Vec::new());
+    let mut previous = 0;
+    for &item in items {
+        let (mut x, mut last_bit) = (item ^ previous, 0);
+        for bit in 1..=32 {
+            if x & 1 == 1 {
+                let gap = bit - last_bit;
+                normal.push(gap.min(7) as u8);
+                if gap >= 7 {
+                    overflow.push((gap - 7) as u8);
+                }
+                last_bit = bit;
+            }
+            x >>= 1;
+        }
+        normal.push(0);
+        previous = item;
+    }
+    let pack = |values: &[u8], width: usize| {
+        let mut bytes = vec![0u8; (values.len() * width).div_ceil(8)];
+        for (i, v) in values.iter().enumerate() {
+            for b in 0..width {
+                let bit = i * width + b;
+                bytes[bit / 8] |= ((v >> b) & 1) << (bit % 8);
+            }
+        }
+        bytes
+    };
+    let count = items.len() as u32;
+    let mut data = vec![1, (count >> 16) as u8, (count >> 8) as u8, count as u8];
+    data.extend(pack(&normal, 3));
+    data.extend(pack(&overflow, 5));
+
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+    let mut out = String::new();
+    for chunk in data.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+        }
+    }
+    out
+}
+
+/// Deterministic pseudo-random items (xorshift).
+fn noise(seed: u32, len: usize) -> Vec<u32> {
+    let mut x = seed;
+    (0..len).map(|_| {
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        x
+    }).collect()
+}
+
+#[test]
+fn chromaprint_fingerprints_decode() {
+    // Chromaprint's own compressor test vectors
+    assert_eq!(crate::decode_fingerprint("AAAAAQE"), Some(vec![1]));
+    assert_eq!(crate::decode_fingerprint("AAAAAUkA"), Some(vec![7]));
+    assert_eq!(crate::decode_fingerprint("AAAAAQcC"), Some(vec![1 << 8]));
+    assert_eq!(crate::decode_fingerprint("AAAAAkEA"), Some(vec![1, 0]));
+    assert_eq!(crate::decode_fingerprint("AAAAAQ"), None);
+    assert_eq!(crate::decode_fingerprint("not base64!"), None);
+
+    let items = noise(7, 300);
+    assert_eq!(crate::decode_fingerprint(&compress_fingerprint(&items)), Some(items));
+}
+
+#[test]
+fn acoustic_copies_cluster_whatever_their_tags() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let song = noise(1, 400);
+    // Re-encode: a bit flipped in every third item, and 3 items of extra lead-in
+    let mut reencode: Vec<u32> = noise(2, 3);
+    reencode.extend(song.iter().enumerate().map(|(i, x)| if i % 3 == 0 { x ^ 1 << (i % 32) } else { *x }));
+
+    let original = flac().tag("ACOUSTID_FINGERPRINT", &compress_fingerprint(&song)).write(dir.path(), "01 Song");
+    let copy = mp3()
+        .tag("ARTIST", "Someone Else")
+        .tag("TITLE", "Track 7")
+        .tag("ACOUSTID_FINGERPRINT", &compress_fingerprint(&reencode))
+        .write(dir.path(), "copy");
+    let other = flac().tag("ACOUSTID_FINGERPRINT", &compress_fingerprint(&noise(3, 400))).write(dir.path(), "02 Other");
+    let untagged = flac().write(dir.path(), "03 Untagged");
+
+    assert!(crate::fingerprint_similarity(&song, &reencode) > 0.98);
+    assert!(crate::fingerprint_similarity(&song, &noise(3, 400)) < 0.6);
+
+    let report = crate::find_fingerprint_dupes(&[original.clone(), copy.clone(), other, untagged], 0.9);
+    assert_eq!((report.fingerprinted, report.missing), (3, 1));
+    assert_eq!(report.clusters.len(), 1);
+    // The lossless copy is the one to keep
+    let cluster: Vec<&std::path::PathBuf> = report.clusters[0].iter().map(|f| &f.copy.path).collect();
+    assert_eq!(cluster, vec![&original, &copy]);
+    assert_eq!(report.extra_copies(), 1);
+}
+
+// --- Track duplicates ---
+
+#[test]
+fn same_track_groups_by_normalized_artist_and_title() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let original = flac().write(dir.path(), "01 Airbag");
+    let shouted = mp3().tag("ARTIST", "RADIOHEAD").tag("TITLE", "Airbag!").write(dir.path(), "airbag");
+    let other = flac().tag("TITLE", "Let Down").write(dir.path(), "02 Let Down");
+    let untitled = flac().without("TITLE").write(dir.path(), "03");
+    let paths = [original.clone(), shouted.clone(), other, untitled];
+
+    let report = crate::find_duplicates(&paths, crate::DupeMode::Tags);
+    assert_eq!(report.keyed, 3);
+    assert_eq!(report.groups.len(), 1);
+    // The lossless copy is the one to keep
+    let group: Vec<&std::path::PathBuf> = report.groups[0].iter().map(|t| &t.copy.path).collect();
+    assert_eq!(group, vec![&original, &shouted]);
+    assert_eq!(report.extra_paths(), vec![shouted.clone()]);
+
+    // AcoustID mode: a different recording ID splits the pair, a missing one drops the file
+    let relinked = mp3().tag("ACOUSTID_ID", "9a1c6c9e-0000-4000-8000-000000000000").write(dir.path(), "relinked");
+    let unlinked = mp3().without("ACOUSTID_ID").write(dir.path(), "unlinked");
+    let report = crate::find_duplicates(&[original.clone(), relinked, unlinked], crate::DupeMode::AcoustId);
+    assert!(report.groups.is_empty());
+    assert_eq!(report.unmatched, 1);
+
+    let report = crate::find_duplicates(&[original, shouted], crate::DupeMode::AcoustId);
+    assert_eq!(report.extra_copies(), 1);
+}
+
+#[test]
+fn audio_hash_ignores_tags() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let plain = crate::test_support::Fixture::mp3().write(dir.path(), "plain");
+    let tagged = mp3().ape("REPLAYGAIN_TRACK_GAIN", "-6.2 dB").write(dir.path(), "tagged");
+    let hash = crate::audio_hash(&plain).expect("MP3 frames hash");
+    assert!(hash.starts_with("raw:"));
+    assert_eq!(crate::audio_hash(&tagged), Some(hash.clone()));
+
+    // Same tags, one byte of audio changed
+    let edited = crate::test_support::Fixture::mp3().write(dir.path(), "edited");
+    let mut bytes = std::fs::read(&edited).unwrap();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0xFF;
+    std::fs::write(&edited, bytes).unwrap();
+    assert_ne!(crate::audio_hash(&edited), Some(hash));
+
+    // FLAC: the STREAMINFO MD5 when the encoder recorded one, whatever the tags
+    let empty = flac().write(dir.path(), "empty");
+    assert_eq!(crate::audio_hash(&empty), None);
+    let mut bytes = std::fs::read(&empty).unwrap();
+    bytes[26..42].copy_from_slice(&[0xAB; 16]);
+    std::fs::write(&empty, bytes).unwrap();
+    assert_eq!(crate::audio_hash(&empty), Some(format!("pcm:{}", "ab".repeat(16))));
+}
+
+// --- Audio quality ---
+
+#[test]
+fn audio_info_comes_from_the_stream_properties() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = mp3().write(dir.path(), "track");
+    let tagged = crate::read_tagged_file(&path, lofty::config::ParseOptions::new()).unwrap();
+    let audio = crate::AudioInfo::of(&tagged);
+    assert_eq!((audio.bitrate, audio.sample_rate), (Some(128), Some(44_100)));
+}
+
+#[test]
+fn quality_flags_low_bitrate_mp3s_short_tracks_and_odd_sample_rates() {
+    use crate::{AudioInfo, QualityCheck};
+    use std::time::Duration;
+
+    let file = |path: &str, bitrate: u32, sample_rate: u32, secs: u64| {
+        let mut issue = flac().scan();
+        issue.path = path.into();
+        issue.audio = Some(AudioInfo { bitrate: Some(bitrate), sample_rate: Some(sample_rate), length: Duration::from_secs(secs) });
+        issue
+    };
+    let mut unread = flac().scan();
+    unread.path = "/lib/C/01.flac".into();
+    let files = vec![
+        file("/lib/A/01.flac", 1411, 44_100, 240),
+        file("/lib/A/02.flac", 1411, 44_100, 250),
+        file("/lib/A/03 Bonus.flac", 1536, 48_000, 260),
+        file("/lib/B/01.mp3", 128, 44_100, 200),
+        file("/lib/B/02.mp3", 320, 44_100, 12),
+        // Lossless files are never "low bitrate", whatever they report
+        file("/lib/D/01.flac", 96, 44_100, 180),
+        unread,
+    ];
+
+    let report = crate::find_quality_issues(&files);
+    assert_eq!(report.checked, 6);
+    let flagged: Vec<(QualityCheck, &str)> = report.flags.iter()
+        .map(|f| (f.check, f.path.to_str().unwrap()))
+        .collect();
+    assert_eq!(flagged, vec![
+        (QualityCheck::LowBitrate, "/lib/B/01.mp3"),
+        (QualityCheck::SampleRate, "/lib/A/03 Bonus.flac"),
+        (QualityCheck::Short, "/lib/B/02.mp3"),
+    ]);
+    assert_eq!(report.flags[1].note, "48 kHz in a folder of 2 × 44.1 kHz");
+    assert_eq!(report.flagged_files(), 3);
+}
+
+// --- Rip software ---
+
+#[test]
+fn encoder_tags_and_rip_logs_are_summarized_per_album() {
+    let issue = flac().tag("ENCODEDBY", "Exact Audio Copy").tag("ENCODER", " dBpoweramp ").scan();
+    assert_eq!(issue.encoder.as_deref(), Some("Exact Audio Copy · dBpoweramp"));
+    assert_eq!(flac().scan().encoder, None);
+
+    let dir = tempfile::TempDir::new().unwrap();
+    assert!(crate::rip_logs(dir.path()).is_empty());
+    // EAC writes UTF-16 LE with a BOM
+    let mut eac = vec![0xFF, 0xFE];
+    eac.extend("Exact Audio Copy V1.6 from 23. October 2020\r\n".encode_utf16().flat_map(u16::to_le_bytes));
+    std::fs::write(dir.path().join("rip.log"), eac).unwrap();
+    std::fs::write(dir.path().join("Album.LOG"), "X Lossless Decoder version 20230916\n").unwrap();
+    std::fs::write(dir.path().join("notes.txt"), "Exact Audio Copy").unwrap();
+    assert_eq!(crate::rip_logs(dir.path()), vec!["EAC", "XLD"]);
+    std::fs::write(dir.path().join("beets.log"), "import started").unwrap();
+    assert_eq!(crate::rip_logs(dir.path()), vec!["EAC", "XLD", "unknown"]);
+
+    assert_eq!(crate::rip_summary(&["EAC"], &[Some("LAME 3.100"), Some("LAME 3.100")]), "EAC log, LAME 3.100");
+    assert_eq!(
+        crate::rip_summary(&[], &[Some("LAME 3.99"), Some("LAME 3.100"), None, None]),
+        "no rip log, 2 encoders, 2 files untagged",
+    );
+    assert_eq!(crate::rip_summary(&["EAC", "XLD"], &[None]), "EAC + XLD logs, no encoder tags");
+}
+
+// --- Scan cache ---
+
+#[test]
+fn scan_cache_replays_unchanged_files_only() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let cache_file = dir.path().join("cache/scan.json");
+    let kept = flac().write(dir.path(), "kept");
+    let edited = flac().write(dir.path(), "edited");
+
+    let entries: Vec<crate::CachedScan> = [&kept, &edited].iter().map(|p| {
+        let (issue, values) = crate::scan_file(p).unwrap();
+        let values = values.iter().map(|(l, v)| (l.to_string(), v.clone())).collect();
+        crate::CachedScan { stamp: crate::FileStamp::of(p).unwrap(), issue, values }
+    }).collect();
+    let saved = crate::ScanCache::default().save(&cache_file, "rules v1", entries, None).unwrap();
+    assert_eq!(saved, 2);
+
+    // Rewritten with another size: parsed again
+    flac().without("ARTIST").write(dir.path(), "edited");
+    let cache = crate::ScanCache::load(&cache_file, "rules v1").unwrap();
+    let (issue, values) = cache.lookup(&kept, crate::FileStamp::of(&kept).unwrap()).unwrap();
+    assert_eq!(issue.path, kept);
+    assert_eq!(values, crate::scan_file(&kept).unwrap().1);
+    assert!(cache.lookup(&edited, crate::FileStamp::of(&edited).unwrap()).is_none());
+
+    // Checked under other rules (e.g. another --style): start over
+    assert!(crate::ScanCache::load(&cache_file, "rules v2").unwrap().files.is_empty());
+    assert!(crate::ScanCache::load(&dir.path().join("none.json"), "rules v1").unwrap().files.is_empty());
+}
+
+// --- Watch mode ---
+
+#[test]
+fn watch_rescans_changed_files_and_drops_removed_folders() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let root = dir.path().to_str().unwrap();
+    let filter = dmp_common::FolderFilter::new("", "", "");
+    std::fs::create_dir_all(dir.path().join("Alpha")).unwrap();
+    let kept = flac().write(&dir.path().join("Alpha"), "kept");
+    let mut state = crate::WatchState::default();
+    let all = |paths: &[&std::path::Path]| paths.iter().map(|p| p.to_path_buf()).collect();
+
+    let changes = state.apply(&all(&[dir.path()]), root, &filter, true);
+    assert_eq!((changes.scanned, changes.removed), (1, 0));
+    // Unchanged since: an event for it (e.g. the scanner reading it) changes nothing
+    assert!(state.apply(&all(&[&kept]), root, &filter, true).is_empty());
+
+    // A rip dropped in as a folder
+    let rip = dir.path().join("Beta/Album");
+    std::fs::create_dir_all(&rip).unwrap();
+    flac().without("ARTIST").write(&rip, "01");
+    flac().write(&rip, "02");
+    std::fs::write(rip.join("cover.txt"), "not audio").unwrap();
+    let changes = state.apply(&all(&[&rip]), root, &filter, true);
+    assert_eq!((changes.scanned, changes.removed, changes.entries.len()), (2, 0, 2));
+    assert_eq!(state.files.values().filter(|c| c.issue.has_any_issue()).count(), 1);
+
+    // Fixed in place, then the whole folder removed
+    flac().write(&rip, "01");
+    assert_eq!(state.apply(&all(&[&rip.join("01.flac")]), root, &filter, true).scanned, 1);
+    assert!(state.files.values().all(|c| !c.issue.has_any_issue()));
+    std::fs::remove_dir_all(dir.path().join("Beta")).unwrap();
+    let changes = state.apply(&all(&[&dir.path().join("Beta")]), root, &filter, true);
+    assert_eq!((changes.scanned, changes.removed), (0, 2));
+    assert_eq!(state.files.keys().collect::<Vec<_>>(), vec![&kept]);
+}
+
+// --- Metrics ---
+
+#[test]
+fn metrics_history_gets_one_header_and_a_row_per_count() {
+    let counts = vec![("files", "Files", 10), ("issues", "Files with Issues", 3), ("critical", "Critical", 2)];
+    let mut diffs = crate::MatchDiffs::new();
+    diffs.insert("a.flac".into(), vec![crate::FieldMatch {
+        field: "Artist", old_display: "Missing".into(), new_value: "Radiohead".into(), category: "critical",
+    }]);
+    let rows = crate::metric_rows(&counts, Some(&diffs));
+    assert_eq!(rows.iter().map(|r| (r.category, r.count, r.matched)).collect::<Vec<_>>(),
+        vec![("files", 10, 0), ("issues", 3, 1), ("critical", 2, 1)]);
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("history/metrics.csv");
+    crate::append_metrics(&path, "2026-10-18T10:00:00+00:00", &rows).unwrap();
+    crate::append_metrics(&path, "2026-10-19T10:00:00+00:00", &rows).unwrap();
+    let csv = std::fs::read_to_string(&path).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 7);
+    assert_eq!(lines[0], "timestamp,category,count,matched_count");
+    assert_eq!(lines[2], "2026-10-18T10:00:00+00:00,issues,3,1");
+    assert_eq!(lines[6], "2026-10-19T10:00:00+00:00,critical,2,1");
+}
+
+#[test]
+fn history_keeps_the_last_runs_of_the_scan_root_and_charts_them() {
+    use std::io::Write;
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("reports/history.jsonl");
+    assert!(crate::load_history(&path, "/music").is_empty());
+
+    let run = |day: u32, root: &str, issues: u64| {
+        let counts = vec![("files", "Files", 100), ("issues", "Files with Issues", issues)];
+        let health = crate::Health { files: 100, penalty: issues * 10 };
+        crate::HistoryEntry::new(&format!("2026-10-{:02}T10:00:00+00:00", day), root, "c35c", std::time::Duration::from_millis(1234), health, &counts)
+    };
+    for day in 1..=crate::TREND_RUNS as u32 + 2 {
+        crate::append_history(&path, &run(day, "/music", 40 - day as u64)).unwrap();
+    }
+    crate::append_history(&path, &run(20, "/other", 1)).unwrap();
+    // A run killed mid-write leaves half a line
+    std::fs::OpenOptions::new().append(true).open(&path).unwrap()
+        .write_all(b"{\"generated\":\"2026-").unwrap();
+
+    let runs = crate::load_history(&path, "/music");
+    assert_eq!(runs.len(), crate::TREND_RUNS);
+    assert_eq!(runs[0].generated, "2026-10-03T10:00:00+00:00");
+    assert_eq!(runs[0].counts["issues"], 37);
+    assert_eq!(runs[0].health, 63.0);
+    assert_eq!(runs[0].elapsed_seconds, 1.2);
+    assert!(runs.iter().all(|r| r.scan_root == "/music"));
+    assert_eq!(crate::load_history(&path, "/other").len(), 1);
+
+    // Scaled to its own range, flat when nothing moved
+    assert!(crate::sparkline(&[3.0, 1.0, 2.0]).contains("points=\"0.0,2.0 60.0,22.0 120.0,12.0\""));
+    assert!(crate::sparkline(&[5.0, 5.0]).contains("points=\"0.0,12.0 120.0,12.0\""));
+}
+
+// --- Scan diff ---
+
+#[test]
+fn scan_diff_lists_fixed_and_introduced_issues_and_new_unreadable_files() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let root = dir.path().to_str().unwrap();
+    let fixed = flac().tag("YEAR", "unknown").write(dir.path(), "fixed");
+    let broken = flac().write(dir.path(), "broken");
+    let issue = crate::scan_file(&fixed).unwrap().0;
+    let old = crate::ScanResults::collect(&[issue], &[fixed.clone(), broken.clone()], &[], root, "2026-10-11", &[]);
+    crate::write_results(dir.path(), &old).unwrap();
+
+    let added = flac().without("GENRE").write(dir.path(), "added");
+    let issue = crate::scan_file(&added).unwrap().0;
+    let unreadable = vec![(broken.clone(), "end of file".to_string())];
+    let new = crate::ScanResults::collect(&[issue], &[fixed, broken, added], &unreadable, root, "2026-10-18", &[]);
+
+    // A report folder loads its results.json back
+    let old = crate::ScanResults::load(dir.path()).unwrap();
+    let diff = crate::ScanDiff::compare(&old, &new).unwrap();
+    assert_eq!(diff.fixed, vec![("fixed.flac".to_string(), vec!["Year".to_string()])]);
+    assert_eq!(diff.introduced, vec![("added.flac".to_string(), vec!["Genre".to_string()])]);
+    assert_eq!(diff.new_unreadable, vec![("broken.flac".to_string(), "end of file".to_string())]);
+    assert_eq!((diff.added, diff.removed), (1, 0));
+
+    // A summary.json only has the counts
+    let summary = dir.path().join("summary.json");
+    std::fs::write(&summary, r#"{"scanRoot": "/music", "provenance": {"generated": "2026-10-04"}, "counts": {"files": 2}}"#).unwrap();
+    let summary = crate::ScanResults::load(&summary).unwrap();
+    assert_eq!(summary.counts["files"], 2);
+    assert!(crate::ScanDiff::compare(&summary, &new).is_none());
+}
+
+// --- Notifications ---
+
+#[test]
+fn run_notice_summarises_counts_for_webhooks_and_email() {
+    let counts = vec![("files", "Files", 120), ("issues", "Files with Issues", 14), ("unreadable", "Unreadable", 2), ("critical", "Critical", 3)];
+    let notice = crate::RunNotice {
+        scan_root: "/music",
+        elapsed: std::time::Duration::from_millis(61_240),
+        counts: &counts,
+        report: None,
+        report_url: Some("https://cdn.example.com/reports/analysis_20261018_030000/index.html"),
+    };
+    assert_eq!(notice.headline(), "14 files with issues (3 critical), 2 unreadable of 120 in /music");
+
+    let json = notice.to_json();
+    assert_eq!(json["event"], "analysis.finished");
+    assert_eq!(json["counts"]["critical"], 3);
+    assert_eq!(json["elapsedSeconds"], 61.2);
+    assert!(json["reportPath"].is_null());
+    assert!(notice.to_text().contains("Report    : https://cdn.example.com/"));
+}
+
+// --- Hook commands ---
+
+#[test]
+fn hooks_parse_thresholds_and_get_the_summary_path() {
+    let hook = crate::IssueHook::parse("50: notify-send 'Library needs work'").unwrap();
+    assert_eq!(hook.threshold, 50);
+    assert_eq!(hook.command.0, vec!["notify-send", "Library needs work"]);
+    assert!(crate::IssueHook::parse("notify-send").is_err());
+    assert!(crate::IssueHook::parse("lots:notify-send").is_err());
+    assert!(crate::IssueHook::parse("5:").is_err());
+    assert!(crate::HookCommand::parse("echo 'unbalanced").is_err());
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let summary = dir.path().join("summary.json");
+    std::fs::write(&summary, "{}\n").unwrap();
+    let copy = dir.path().join("copy.json");
+    let command = crate::HookCommand::parse(&format!("sh -c 'cp \"$1\" {}' sh", copy.display())).unwrap();
+    command.run(&summary).unwrap();
+    assert_eq!(std::fs::read_to_string(&copy).unwrap(), "{}\n");
+    assert!(crate::HookCommand::parse("false").unwrap().run(&summary).is_err());
+}
+
+// --- Serve ---
+
+#[test]
+fn report_server_serves_files_under_the_report_folder_only() {
+    use std::io::{Read, Write};
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let root = dir.path().join("analysis_20261018_030000");
+    std::fs::create_dir_all(root.join("pages")).unwrap();
+    std::fs::write(root.join("index.html"), "<h1>Report</h1>").unwrap();
+    std::fs::write(root.join("pages/critical 2.html"), "<p>critical</p>").unwrap();
+
+    assert_eq!(crate::served_file(&root, "/"), Some(root.join("index.html")));
+    assert_eq!(crate::served_file(&root, "/pages/critical%202.html?page=2"), Some(root.join("pages/critical 2.html")));
+    assert_eq!(crate::served_file(&root, "/pages/../../secret.txt"), None);
+    assert_eq!(crate::served_file(&root, "/%2e%2e/secret.txt"), None);
+
+    let server = crate::ReportServer::bind(0).unwrap();
+    let port = server.port();
+    std::thread::spawn(move || server.serve(root));
+    let get = |target: &str| {
+        let mut stream = std::net::TcpStream::connect(("127.0.0.1", port)).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", target).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    };
+    let index = get("/");
+    assert!(index.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(index.contains("Content-Type: text/html; charset=utf-8\r\n"));
+    assert!(index.ends_with("\r\n\r\n<h1>Report</h1>"));
+    assert!(get("/pages/missing.html").starts_with("HTTP/1.1 404"));
+    assert!(get("/../secret.txt").starts_with("HTTP/1.1 404"));
+}
+
+// --- Explicit file list ---
+
+#[test]
+fn path_list_keeps_listed_audio_files_under_the_root() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let album = dir.path().join("Radiohead/OK Computer");
+    std::fs::create_dir_all(&album).unwrap();
+    let airbag = flac().write(&album, "01 Airbag");
+    let android = mp3().write(&album, "02 Paranoid Android");
+    let root = dir.path().to_str().unwrap();
+    let all = dmp_common::FolderFilter::new("", "", "");
+
+    let text = format!(
+        "# problem set\n{}\n\nRadiohead/OK Computer/02 Paranoid Android.mp3\n{}\nRadiohead/OK Computer/cover.jpg\nRadiohead/Kid A/01 Everything.flac\n../elsewhere.flac\n",
+        android.display(),
+        airbag.display(),
+    );
+    let list = crate::read_path_list(&text, root, &all).unwrap();
+    assert_eq!(list.paths, vec![airbag.clone(), android.clone()]);
+    assert_eq!(list.skipped.iter().map(|(_, reason)| *reason).collect::<Vec<_>>(),
+        vec!["not an audio file", "not found", "outside the scan root"]);
+
+    let only = dmp_common::FolderFilter::new("beck", "", "");
+    assert_eq!(crate::read_path_list(&text, root, &only).unwrap().paths, Vec::<std::path::PathBuf>::new());
+
+    let handled = r#"{"handled": ["Radiohead/OK Computer/01 Airbag.flac"]}"#;
+    assert_eq!(crate::read_path_list(handled, root, &all).unwrap().paths, vec![airbag.clone()]);
+    assert_eq!(crate::count_folder_audio(&[airbag])[&album], 2);
+}
+
+// --- Hostile names ---
+
+#[test]
+fn hostile_folder_and_file_names_are_escaped_in_the_report() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let folder = dir.path().join("AC\"DC <live>\nbonus");
+    std::fs::create_dir_all(&folder).unwrap();
+    let path = flac().without("YEAR").write(&folder, "01 It's a \"Long\" <Way>");
+    let issue = crate::scan_file(&path).unwrap().0;
+    let lone = crate::LoneFile { path: path.clone(), duration: None };
+
+    let root = dir.path().to_str().unwrap();
+    let report_dir = dir.path().join("report");
+    let report = dmp_common::report::Report::create(&report_dir, "QC", vec![dmp_common::report::NavItem::overview()]).unwrap();
+    let critical = crate::CATEGORY_PAGES.iter().find(|c| c.id == "critical").unwrap();
+    crate::write_category_pages(&report, root, &[issue], critical, None, None).unwrap();
+    crate::write_issues_page(&report, root, &[path], &[lone], &[], &[]).unwrap();
+
+    for page in ["pages/critical_1.html", "pages/issues.html"] {
+        let html = std::fs::read_to_string(report_dir.join(page)).unwrap();
+        assert!(!html.contains("<live>") && !html.contains("<Way>"), "{}", page);
+        assert!(!html.contains("\nbonus"), "{}", page);
+        assert!(html.contains("data-path=\"AC&quot;DC &lt;live&gt;&#xA;bonus/01 It's a &quot;Long&quot; &lt;Way&gt;.flac\""), "{}", page);
+        assert!(html.contains("AC\"DC &lt;live&gt;\u{240A}bonus/01 It's a \"Long\" &lt;Way&gt;.flac"), "{}", page);
+    }
+}
+
+// --- Page size ---
+
+#[test]
+fn page_size_accepts_bytes_and_binary_units() {
+    use crate::parse_page_size;
+    assert_eq!(parse_page_size("2MB"), Ok(2 * 1024 * 1024));
+    assert_eq!(parse_page_size("1.5 mb"), Ok(1536 * 1024));
+    assert_eq!(parse_page_size("500k"), Ok(500 * 1024));
+    assert_eq!(parse_page_size("4096"), Ok(4096));
+    assert_eq!(parse_page_size("0"), Ok(0));
+    assert!(parse_page_size("2 pages").is_err());
+    assert!(parse_page_size("MB").is_err());
+}
@@ -3,6 +3,8 @@ use aws_sdk_s3::Client as S3Client;
 use chrono::Utc;
 use clap::Parser;
 use colored::*;
+use dmp_common::deletion_queue::object_key_in_use;
+use dmp_common::run_lock::{LockMode, RunLock};
 use dotenvy;
 use sqlx::postgres::PgPoolOptions;
 use sqlx::PgPool;
@@ -15,11 +17,23 @@ use std::path::PathBuf;
 // ---------------------------------------------------------------------------
 
 #[derive(Parser, Debug)]
-#[command(name = "dmp-clean", about = "Clean up orphaned images from S3 and local storage")]
+#[command(name = "dmp-clean", version = dmp_common::version!(), long_version = dmp_common::long_version!(), about = "Clean up orphaned images from S3 and local storage")]
 struct Args {
     /// Dry run - show what would be deleted without actually deleting
     #[arg(long)]
     dry_run: bool,
+
+    /// Check that each queued image still exists (HEAD on S3, stat locally) before deleting it: the summary tells deleted, already gone and blocked entries apart
+    #[arg(long)]
+    verify: bool,
+
+    /// Wait for another DMP script's run to finish instead of exiting
+    #[arg(long, conflicts_with = "steal_lock")]
+    wait: bool,
+
+    /// Take the run lock from another DMP script (ends its lock session; for hung runs)
+    #[arg(long)]
+    steal_lock: bool,
 }
 
 // ---------------------------------------------------------------------------
@@ -157,11 +171,15 @@ async fn delete_from_s3(
     Ok(())
 }
 
-fn delete_from_local(object_key: &str, config: &CleanConfig) -> Result<(), std::io::Error> {
-    // Convert S3 key to local path using project_root
-    let path = PathBuf::from(&config.project_root)
+/// Local path of an S3 key, under project_root.
+fn local_path(object_key: &str, config: &CleanConfig) -> PathBuf {
+    PathBuf::from(&config.project_root)
         .join("web/public/img")
-        .join(object_key);
+        .join(object_key)
+}
+
+fn delete_from_local(object_key: &str, config: &CleanConfig) -> Result<(), std::io::Error> {
+    let path = local_path(object_key, config);
 
     if path.exists() {
         fs::remove_file(&path)?;
@@ -174,6 +192,29 @@ fn delete_from_local(object_key: &str, config: &CleanConfig) -> Result<(), std::
     }
 }
 
+// ---------------------------------------------------------------------------
+// Existence checks (--verify)
+// ---------------------------------------------------------------------------
+
+/// HEAD the object: `Ok(false)` when S3 answers 404, `Err` when it can't tell
+/// (no access, network).
+async fn s3_object_exists(client: &S3Client, bucket: &str, object_key: &str) -> Result<bool, String> {
+    match client.head_object().bucket(bucket).key(object_key).send().await {
+        Ok(_) => Ok(true),
+        Err(e) if e.as_service_error().is_some_and(|e| e.is_not_found()) => Ok(false),
+        Err(e) => Err(aws_sdk_s3::error::DisplayErrorContext(&e).to_string()),
+    }
+}
+
+/// Stat the local copy: `Err` when it can't tell (permissions, I/O).
+fn local_file_exists(object_key: &str, config: &CleanConfig) -> Result<bool, std::io::Error> {
+    match fs::symlink_metadata(local_path(object_key, config)) {
+        Ok(_) => Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
 async fn remove_from_queue(pool: &PgPool, id: &str) -> Result<(), sqlx::Error> {
     sqlx::query(r#"DELETE FROM "S3DeletionQueue" WHERE id = $1"#)
         .bind(id)
@@ -195,6 +236,9 @@ async fn main() {
     if args.dry_run {
         println!("Mode: {} (no changes will be made)", "DRY RUN".yellow().bold());
     }
+    if args.verify {
+        println!("Verify: checking each image exists before deleting it");
+    }
     println!();
 
     // Initialize error log
@@ -218,6 +262,20 @@ async fn main() {
         .await
         .expect("Failed to connect to database. Is PostgreSQL running?");
 
+    // Only one DMP script (index, sync, clean, nuke) writes at a time; a dry run only reads
+    let _run_lock = if args.dry_run {
+        None
+    } else {
+        let mode = LockMode::from_flags(args.wait, args.steal_lock);
+        match RunLock::acquire(&config.database_url, "clean", mode).await {
+            Ok(lock) => Some(lock),
+            Err(e) => {
+                eprintln!("{} {}", "✗".red(), e);
+                std::process::exit(1);
+            }
+        }
+    };
+
     // Initialize S3 client if needed
     let s3_client = if use_s3 {
         match create_s3_client(&config).await {
@@ -268,6 +326,11 @@ async fn main() {
     let mut s3_failed = 0;
     let mut local_failed = 0;
     let mut queue_removed = 0;
+    let mut in_use = 0;
+    // --verify outcomes, per queue entry
+    let mut verified_deleted = 0;
+    let mut already_gone = 0;
+    let mut blocked = 0;
 
     let total_items = queue_items.len();
 
@@ -281,6 +344,55 @@ async fn main() {
         );
         std::io::stdout().flush().ok();
 
+        // Index or sync may have stored a new image under the same key since it was queued
+        if object_key_in_use(&pool, object_key).await.unwrap_or(false) {
+            println!("{} still in use, kept", "○".yellow());
+            in_use += 1;
+            if !args.dry_run && remove_from_queue(&pool, id).await.is_ok() {
+                queue_removed += 1;
+            }
+            continue;
+        }
+
+        // Without --verify every configured store is tried; S3 deletes succeed even for missing keys
+        let mut s3_present = true;
+        let mut local_present = true;
+        if args.verify {
+            let mut unknown = Vec::new();
+            if use_s3 {
+                if let (Some(ref client), Some(ref bucket)) = (&s3_client, &config.s3_bucket) {
+                    match s3_object_exists(client, bucket, object_key).await {
+                        Ok(exists) => s3_present = exists,
+                        Err(e) => unknown.push(format!("S3: {}", e)),
+                    }
+                }
+            }
+            if use_local {
+                match local_file_exists(object_key, &config) {
+                    Ok(exists) => local_present = exists,
+                    Err(e) => unknown.push(format!("local: {}", e)),
+                }
+            }
+            if !unknown.is_empty() {
+                println!("{} blocked (can't check: {})", "✗".red(), unknown.join("; "));
+                blocked += 1;
+                if let Ok(mut f) = error_log.lock() {
+                    writeln!(f, "[CLEAN] Failed to check '{}': {}", object_key, unknown.join("; ")).ok();
+                }
+                continue;
+            }
+            let s3_found = use_s3 && s3_client.is_some() && s3_present;
+            let local_found = use_local && local_present;
+            if !s3_found && !local_found {
+                println!("{} already gone", "○".bright_black());
+                already_gone += 1;
+                if !args.dry_run && remove_from_queue(&pool, id).await.is_ok() {
+                    queue_removed += 1;
+                }
+                continue;
+            }
+        }
+
         if args.dry_run {
             println!("{} (dry run)", "○".cyan());
             continue;
@@ -289,9 +401,10 @@ async fn main() {
         let mut s3_success = false;
         let mut local_success = false;
         let mut any_success = false;
+        let mut any_failure = false;
 
         // Delete from S3
-        if use_s3 {
+        if use_s3 && s3_present {
             if let (Some(ref client), Some(ref bucket)) = (&s3_client, &config.s3_bucket) {
                 match delete_from_s3(client, bucket, &object_key).await {
                     Ok(_) => {
@@ -301,6 +414,7 @@ async fn main() {
                     }
                     Err(e) => {
                         s3_failed += 1;
+                        any_failure = true;
                         if let Ok(mut f) = error_log.lock() {
                             writeln!(f, "[CLEAN] Failed to delete S3 object '{}': {}", object_key, e).ok();
                         }
@@ -310,7 +424,7 @@ async fn main() {
         }
 
         // Delete from local storage
-        if use_local {
+        if use_local && local_present {
             match delete_from_local(&object_key, &config) {
                 Ok(_) => {
                     local_success = true;
@@ -321,6 +435,7 @@ async fn main() {
                     // Don't count as failure if file simply doesn't exist
                     if e.kind() != std::io::ErrorKind::NotFound {
                         local_failed += 1;
+                        any_failure = true;
                         if let Ok(mut f) = error_log.lock() {
                             writeln!(f, "[CLEAN] Failed to delete local file '{}': {}", object_key, e).ok();
                         }
@@ -329,8 +444,15 @@ async fn main() {
             }
         }
 
-        // Print result
-        if any_success {
+        // Print result. With --verify an entry only counts as deleted once every
+        // copy found is gone; the rest stay queued as blocked
+        if args.verify && any_failure {
+            println!("{} blocked (delete failed)", "✗".red());
+            blocked += 1;
+        } else if any_success {
+            if args.verify {
+                verified_deleted += 1;
+            }
             let mut parts = Vec::new();
             if s3_success {
                 parts.push("S3".to_string());
@@ -357,8 +479,11 @@ async fn main() {
     if args.dry_run {
         println!("{} {} image(s) would be deleted", 
             "Dry run:".cyan().bold(),
-            total_items
+            total_items - in_use - already_gone - blocked
         );
+        if args.verify {
+            println!("  Verified : {} already gone, {} blocked", already_gone, blocked);
+        }
     } else {
         println!("Summary:");
         if use_s3 {
@@ -373,9 +498,19 @@ async fn main() {
                 if local_failed > 0 { format!("{}", local_failed).red().to_string() } else { "0".to_string() }
             );
         }
+        if in_use > 0 {
+            println!("  In use   : {} kept (referenced again)", in_use);
+        }
+        if args.verify {
+            println!("  Verified : {} deleted, {} already gone, {} blocked",
+                format!("{}", verified_deleted).green(),
+                already_gone,
+                if blocked > 0 { format!("{}", blocked).red().to_string() } else { "0".to_string() }
+            );
+        }
         println!("  Queue    : {} removed", format!("{}", queue_removed).green());
         
-        if s3_failed > 0 || local_failed > 0 {
+        if s3_failed > 0 || local_failed > 0 || blocked > 0 {
             println!();
             println!("{}: Check errors.log for details", "Note".yellow());
         }
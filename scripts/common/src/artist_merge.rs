@@ -0,0 +1,185 @@
+// ---------------------------------------------------------------------------
+// Artist merge
+// ---------------------------------------------------------------------------
+
+//! Folding one `Artist` row into another: `sync merge` for tag variants
+//! ("The Beatles" / "Beatles, The") and the indexer's `--repair-unicode` for
+//! names that differ only in Unicode normalization.
+
+use crate::deletion_queue::{image_object_key, object_key_in_use, queue_s3_deletion};
+use sqlx::PgConnection;
+use std::collections::BTreeSet;
+
+/// What `merge_artist` moved, merged and queued.
+#[derive(Debug, Default)]
+pub struct MergeCounts {
+    pub releases_moved: u64,
+    pub releases_merged: u64,
+    pub mb_releases_moved: u64,
+    pub mb_releases_merged: u64,
+    pub credits_moved: u64,
+    pub urls_moved: u64,
+    pub genres_added: u64,
+    pub images_queued: u64,
+}
+
+/// Move everything of artist `dup` onto `keep`, delete `dup` and queue its
+/// images for `clean`. Rows `keep` already has (same release title, credit,
+/// URL, MB release) are merged or dropped. Run it in a transaction: it stops
+/// at the first error.
+pub async fn merge_artist(conn: &mut PgConnection, keep: &str, dup: &str) -> Result<MergeCounts, sqlx::Error> {
+    let mut counts = MergeCounts::default();
+
+    // Read before the row goes away: the delete trigger only knows the default keys
+    let (image, image_url, background, background_url, logo, logo_url) = sqlx::query_as::<
+        _,
+        (Option<String>, Option<String>, Option<String>, Option<String>, Option<String>, Option<String>),
+    >(
+        r#"SELECT image, "imageUrl", "backgroundImage", "backgroundImageUrl", "logoImage", "logoImageUrl"
+           FROM "Artist" WHERE id = $1"#,
+    )
+    .bind(dup)
+    .fetch_one(&mut *conn)
+    .await?;
+    let image_keys: BTreeSet<String> = [image, image_url, background, background_url, logo, logo_url]
+        .iter()
+        .flatten()
+        .filter_map(|r| image_object_key(r))
+        .collect();
+
+    // Releases both have: move the tracks over, then the rest of the releases
+    sqlx::query(
+        r#"UPDATE "LocalReleaseTrack" t SET "localReleaseId" = k.id
+           FROM "LocalRelease" d, "LocalRelease" k
+           WHERE t."localReleaseId" = d.id AND d."artistId" = $2
+             AND k."artistId" = $1 AND k.title = d.title"#,
+    )
+    .bind(keep)
+    .bind(dup)
+    .execute(&mut *conn)
+    .await?;
+    counts.releases_merged = sqlx::query(
+        r#"DELETE FROM "LocalRelease" d USING "LocalRelease" k
+           WHERE d."artistId" = $2 AND k."artistId" = $1 AND k.title = d.title"#,
+    )
+    .bind(keep)
+    .bind(dup)
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+    counts.releases_moved = sqlx::query(r#"UPDATE "LocalRelease" SET "artistId" = $1, "updatedAt" = NOW() WHERE "artistId" = $2"#)
+        .bind(keep)
+        .bind(dup)
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+
+    // MB releases both have: the next sync re-links the local releases
+    sqlx::query(
+        r#"UPDATE "LocalRelease" SET "releaseId" = NULL
+           WHERE "releaseId" IN (
+             SELECT d.id FROM "MusicBrainzRelease" d JOIN "MusicBrainzRelease" k
+               ON k."artistId" = $1 AND k.title = d.title
+             WHERE d."artistId" = $2)"#,
+    )
+    .bind(keep)
+    .bind(dup)
+    .execute(&mut *conn)
+    .await?;
+    counts.mb_releases_merged = sqlx::query(
+        r#"DELETE FROM "MusicBrainzRelease" d USING "MusicBrainzRelease" k
+           WHERE d."artistId" = $2 AND k."artistId" = $1 AND k.title = d.title"#,
+    )
+    .bind(keep)
+    .bind(dup)
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+    counts.mb_releases_moved = sqlx::query(r#"UPDATE "MusicBrainzRelease" SET "artistId" = $1 WHERE "artistId" = $2"#)
+        .bind(keep)
+        .bind(dup)
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+
+    // Credits and URLs: drop the ones `keep` already has, move the rest
+    sqlx::query(
+        r#"DELETE FROM "TrackArtist" d USING "TrackArtist" k
+           WHERE d."artistId" = $2 AND k."artistId" = $1
+             AND k."trackId" = d."trackId" AND k.role = d.role"#,
+    )
+    .bind(keep)
+    .bind(dup)
+    .execute(&mut *conn)
+    .await?;
+    counts.credits_moved = sqlx::query(r#"UPDATE "TrackArtist" SET "artistId" = $1 WHERE "artistId" = $2"#)
+        .bind(keep)
+        .bind(dup)
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+    sqlx::query(
+        r#"DELETE FROM "ArtistUrl" d USING "ArtistUrl" k
+           WHERE d."artistId" = $2 AND k."artistId" = $1
+             AND k.type = d.type AND k.url = d.url"#,
+    )
+    .bind(keep)
+    .bind(dup)
+    .execute(&mut *conn)
+    .await?;
+    counts.urls_moved = sqlx::query(r#"UPDATE "ArtistUrl" SET "artistId" = $1 WHERE "artistId" = $2"#)
+        .bind(keep)
+        .bind(dup)
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+    counts.genres_added = sqlx::query(
+        r#"INSERT INTO "_ArtistGenres" ("A", "B")
+           SELECT $1, "B" FROM "_ArtistGenres" WHERE "A" = $2
+           ON CONFLICT DO NOTHING"#,
+    )
+    .bind(keep)
+    .bind(dup)
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+
+    // Keep the MB ID and play count, and have the next sync revisit the artist
+    // so the merged releases get matched
+    sqlx::query(
+        r#"UPDATE "Artist" k SET
+             "musicbrainzId" = COALESCE(k."musicbrainzId", d."musicbrainzId"),
+             "totalPlayCount" = k."totalPlayCount" + d."totalPlayCount",
+             "archivedAt" = CASE WHEN d."archivedAt" IS NULL THEN NULL ELSE k."archivedAt" END,
+             "lastSyncedAt" = NULL,
+             "updatedAt" = NOW()
+           FROM "Artist" d
+           WHERE k.id = $1 AND d.id = $2"#,
+    )
+    .bind(keep)
+    .bind(dup)
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query(r#"DELETE FROM "Artist" WHERE id = $1"#)
+        .bind(dup)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query(
+        r#"UPDATE "Artist" a SET
+             "totalTracks" = (SELECT COUNT(t.id)::int FROM "LocalReleaseTrack" t
+                              JOIN "LocalRelease" lr ON t."localReleaseId" = lr.id WHERE lr."artistId" = a.id),
+             "totalFileSize" = (SELECT COALESCE(SUM(t."fileSize"), 0) FROM "LocalReleaseTrack" t
+                                JOIN "LocalRelease" lr ON t."localReleaseId" = lr.id WHERE lr."artistId" = a.id)
+           WHERE a.id = $1"#,
+    )
+    .bind(keep)
+    .execute(&mut *conn)
+    .await?;
+
+    for key in &image_keys {
+        if !object_key_in_use(&mut *conn, key).await? && queue_s3_deletion(&mut *conn, key).await? {
+            counts.images_queued += 1;
+        }
+    }
+    Ok(counts)
+}
@@ -0,0 +1,100 @@
+//! Version and build info for `--version`.
+//!
+//! A script's `build.rs` calls [`emit`], which records the git commit, the
+//! build date and the schema revision the build expects (the last commit that
+//! touched `web/prisma`) as compile-time variables. [`version!`](crate::version)
+//! and [`long_version!`](crate::long_version) read them back in the binary:
+//!
+//! ```text
+//! #[command(version = dmp_common::version!(), long_version = dmp_common::long_version!())]
+//! ```
+//!
+//! Comparing the schema line with `git log -1 -- web/prisma` on the deployed
+//! web app tells whether the tool and the database come from the same schema.
+
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Print the `cargo:` lines for `DMP_GIT_COMMIT`, `DMP_BUILD_DATE` and
+/// `DMP_SCHEMA_REVISION`. For build scripts only; outside a git checkout the
+/// commit and schema revision are "unknown".
+pub fn emit() {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| ".".to_string());
+    let repo = Path::new(&manifest_dir).join("../..");
+    let git = |args: &[&str]| -> Option<String> {
+        let output = Command::new("git").arg("-C").arg(&repo).args(args).output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let text = String::from_utf8(output.stdout).ok()?.trim().to_string();
+        (!text.is_empty()).then_some(text)
+    };
+
+    let commit = git(&["rev-parse", "--short", "HEAD"]).unwrap_or_else(|| "unknown".to_string());
+    let schema = git(&["log", "-1", "--format=%h (%cs)", "--", "web/prisma"]).unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=DMP_GIT_COMMIT={}", commit);
+    println!("cargo:rustc-env=DMP_BUILD_DATE={}", build_date());
+    println!("cargo:rustc-env=DMP_SCHEMA_REVISION={}", schema);
+
+    // Re-run when HEAD moves (checkout, new commit) or the schema changes
+    if let Some(git_dir) = git(&["rev-parse", "--absolute-git-dir"]) {
+        println!("cargo:rerun-if-changed={}/HEAD", git_dir);
+        if let Some(head_ref) = git(&["symbolic-ref", "-q", "HEAD"]) {
+            println!("cargo:rerun-if-changed={}/{}", git_dir, head_ref);
+        }
+    }
+    println!("cargo:rerun-if-changed={}", repo.join("web/prisma").display());
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+}
+
+/// UTC date of the build, or of `SOURCE_DATE_EPOCH` for reproducible builds.
+fn build_date() -> String {
+    let secs = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or_else(|| SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0));
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Days since 1970-01-01 as a (year, month, day) Gregorian date.
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153; // March-based month
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    (year_of_era + era * 400 + i64::from(month <= 2), month, day)
+}
+
+/// `-V`: "0.1.0 (8b88321, built 2026-10-18)".
+#[macro_export]
+macro_rules! version {
+    () => {
+        concat!(
+            env!("CARGO_PKG_VERSION"),
+            " (",
+            env!("DMP_GIT_COMMIT"),
+            ", built ",
+            env!("DMP_BUILD_DATE"),
+            ")"
+        )
+    };
+}
+
+/// `--version`: the short version plus the schema revision the build expects.
+#[macro_export]
+macro_rules! long_version {
+    () => {
+        concat!(
+            $crate::version!(),
+            "\nschema: ",
+            env!("DMP_SCHEMA_REVISION"),
+            ", the last web/prisma commit this build knows"
+        )
+    };
+}
@@ -0,0 +1,18 @@
+// ---------------------------------------------------------------------------
+// Checkpoints
+// ---------------------------------------------------------------------------
+
+//! Helpers for the `IndexCheckpoint` / `SyncCheckpoint` rows. index and sync
+//! each warn about the other's checkpoint, and word its age the same way.
+
+use chrono::{NaiveDateTime, Utc};
+
+/// "5 min ago" / "3 h ago" / "2 days ago" for a checkpoint's `updatedAt`.
+pub fn checkpoint_age(updated_at: NaiveDateTime) -> String {
+    let minutes = (Utc::now().naive_utc() - updated_at).num_minutes().max(0);
+    match minutes {
+        0..=59 => format!("{} min ago", minutes),
+        60..=2879 => format!("{} h ago", minutes / 60),
+        _ => format!("{} days ago", minutes / 1440),
+    }
+}
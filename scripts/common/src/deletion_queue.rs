@@ -0,0 +1,62 @@
+// ---------------------------------------------------------------------------
+// S3 deletion queue
+// ---------------------------------------------------------------------------
+
+//! `S3DeletionQueue`: images nothing references anymore, for `clean` to delete
+//! from the bucket and from `web/public/img`. Database triggers queue the
+//! images of deleted artists and releases; scripts call `queue_s3_deletion`
+//! when they replace an image under a new key. A key is the path below both
+//! roots, e.g. `releases/<id>.jpg` or `artists/backgrounds/<slug>.jpg`.
+
+use sqlx::PgExecutor;
+
+/// Queue `object_key` for deletion by `clean`. Returns false when the key was
+/// already queued.
+pub async fn queue_s3_deletion<'e>(executor: impl PgExecutor<'e>, object_key: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"INSERT INTO "S3DeletionQueue" (id, "objectKey", "createdAt")
+           SELECT $1, $2, NOW()
+           WHERE NOT EXISTS (SELECT 1 FROM "S3DeletionQueue" WHERE "objectKey" = $2)"#,
+    )
+    .bind(cuid2::create_id())
+    .bind(object_key)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Whether an artist or release still points at `object_key`, e.g. because a
+/// re-sync stored a new image under the same key after it was queued.
+pub async fn object_key_in_use<'e>(executor: impl PgExecutor<'e>, object_key: &str) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"SELECT EXISTS (
+             SELECT 1 FROM "Artist"
+             WHERE 'artists/' || image = $1 OR 'artists/' || "backgroundImage" = $1 OR 'artists/' || "logoImage" = $1
+                OR "imageUrl" LIKE '%/' || $1 OR "backgroundImageUrl" LIKE '%/' || $1 OR "logoImageUrl" LIKE '%/' || $1
+           ) OR EXISTS (
+             SELECT 1 FROM "LocalRelease" WHERE image = '/img/' || $1 OR "imageUrl" LIKE '%/' || $1
+           )"#,
+    )
+    .bind(object_key)
+    .fetch_one(executor)
+    .await
+}
+
+/// Object key of a stored image reference: an S3 URL or `/img/...` path
+/// (`LocalRelease.image`), or an `Artist` image column, which holds the path
+/// below `img/artists/` (`<slug>.jpg`, `logos/<slug>.png`).
+pub fn image_object_key(image_ref: &str) -> Option<String> {
+    let image_ref = image_ref.trim();
+    if image_ref.is_empty() {
+        return None;
+    }
+    for dir in ["/artists/", "/releases/"] {
+        if let Some(i) = image_ref.find(dir) {
+            return Some(image_ref[i + 1..].to_string());
+        }
+    }
+    if image_ref.contains("://") || image_ref.starts_with('/') {
+        return None;
+    }
+    Some(format!("artists/{}", image_ref))
+}
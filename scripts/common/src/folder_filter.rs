@@ -0,0 +1,100 @@
+// ---------------------------------------------------------------------------
+// Artist folder filter (--only / --from / --to)
+// ---------------------------------------------------------------------------
+
+/// The `--only` / `--from` / `--to` artist selection shared by every script.
+///
+/// Names are compared lowercased (full Unicode lowercasing), by code point:
+///
+/// - `--only PREFIX` keeps names starting with the prefix and overrides the range
+/// - `--from PREFIX` keeps names sorting at or after the prefix
+/// - `--to PREFIX` keeps names sorting at or before the prefix, plus every name
+///   starting with it, so `--to m` includes "Muse" and `--to radio` includes "Radiohead"
+///
+/// Filters apply to the artist folder name on disk and to `Artist.name` in the
+/// database, never to the slug ("AC/DC" is `ac-dc` as a slug but "ac/dc" here).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FolderFilter {
+    only: String,
+    from: String,
+    to: String,
+}
+
+impl FolderFilter {
+    /// Builds a filter from the raw CLI values; empty strings mean "not set".
+    pub fn new(only: &str, from: &str, to: &str) -> Self {
+        Self {
+            only: only.trim().to_lowercase(),
+            from: from.trim().to_lowercase(),
+            to: to.trim().to_lowercase(),
+        }
+    }
+
+    /// Same as [`FolderFilter::new`] for scripts whose flags are `Option<String>`.
+    pub fn from_options(only: Option<&str>, from: Option<&str>, to: Option<&str>) -> Self {
+        Self::new(only.unwrap_or(""), from.unwrap_or(""), to.unwrap_or(""))
+    }
+
+    /// Lowercased `--only` value ("" when unset).
+    pub fn only(&self) -> &str {
+        &self.only
+    }
+
+    /// Lowercased `--from` value ("" when unset).
+    pub fn from(&self) -> &str {
+        &self.from
+    }
+
+    /// Lowercased `--to` value ("" when unset).
+    pub fn to(&self) -> &str {
+        &self.to
+    }
+
+    /// True when no filter is set and every name matches.
+    pub fn is_empty(&self) -> bool {
+        self.only.is_empty() && self.from.is_empty() && self.to.is_empty()
+    }
+
+    /// Whether an artist folder (or artist name) is selected.
+    pub fn matches(&self, name: &str) -> bool {
+        let name = name.trim().to_lowercase();
+
+        if !self.only.is_empty() {
+            return name.starts_with(&self.only);
+        }
+        if !self.from.is_empty() && name < self.from {
+            return false;
+        }
+        if !self.to.is_empty() && name > self.to && !name.starts_with(&self.to) {
+            return false;
+        }
+        true
+    }
+
+    /// SQL conditions equivalent to [`FolderFilter::matches`] on `column`, with
+    /// placeholders numbered from `first_param`. Returns the conditions (to be
+    /// AND-ed) and the values to bind, in order. Comparisons use the "C"
+    /// collation so the database sorts by code point like `matches` does.
+    pub fn sql_conditions(&self, column: &str, first_param: usize) -> (Vec<String>, Vec<String>) {
+        let lowered = format!("LOWER({}) COLLATE \"C\"", column);
+        let mut conditions = Vec::new();
+        let mut binds = Vec::new();
+
+        if !self.only.is_empty() {
+            binds.push(self.only.clone());
+            conditions.push(format!("starts_with(LOWER({}), ${})", column, first_param));
+            return (conditions, binds);
+        }
+        if !self.from.is_empty() {
+            binds.push(self.from.clone());
+            let p = first_param + binds.len() - 1;
+            conditions.push(format!("{} >= ${}", lowered, p));
+        }
+        if !self.to.is_empty() {
+            binds.push(self.to.clone());
+            let p = first_param + binds.len() - 1;
+            conditions.push(format!("({} <= ${p} OR starts_with(LOWER({}), ${p}))", lowered, column, p = p));
+        }
+        (conditions, binds)
+    }
+}
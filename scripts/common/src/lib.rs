@@ -0,0 +1,27 @@
+//! Code shared by the DMP scripts (index, sync, analysis, clean, nuke).
+
+#[cfg(feature = "artist-merge")]
+pub mod artist_merge;
+pub mod build_info;
+#[cfg(feature = "checkpoint")]
+pub mod checkpoint;
+#[cfg(feature = "deletion-queue")]
+pub mod deletion_queue;
+mod folder_filter;
+#[cfg(feature = "musicbrainz")]
+pub mod musicbrainz;
+pub mod progress;
+pub mod report;
+#[cfg(feature = "run-lock")]
+pub mod run_lock;
+#[cfg(feature = "s3")]
+pub mod s3;
+#[cfg(feature = "statistics-history")]
+pub mod statistics_history;
+#[cfg(feature = "unicode")]
+pub mod unicode;
+
+#[cfg(test)]
+mod tests;
+
+pub use folder_filter::FolderFilter;
@@ -0,0 +1,215 @@
+// ---------------------------------------------------------------------------
+// MusicBrainz API client
+// ---------------------------------------------------------------------------
+
+//! The MusicBrainz web service client shared by sync (artist and release
+//! lookups) and analysis (`--autofix-native`): an adaptive rate limiter, a
+//! GET that retries while the server or a mirror's proxy is busy, and the
+//! mirror root set from `--mb-url` / `MB_URL`.
+
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use tokio::time::sleep;
+
+pub use reqwest::Client;
+
+use crate::s3::{self, CaBundle};
+
+pub const DEFAULT_URL: &str = "https://musicbrainz.org/ws/2";
+pub const USER_AGENT: &str = "DMPv6/0.1.0 ( https://github.com/dmp )";
+
+/// Web service root of every MB request, set once in main from `--mb-url` / `MB_URL`.
+static BASE: OnceLock<String> = OnceLock::new();
+
+/// The web service root: the mirror given to [`set_base`], else musicbrainz.org.
+pub fn base() -> &'static str {
+    BASE.get().map_or(DEFAULT_URL, String::as_str)
+}
+
+/// Points every later request at a mirror (see [`service_url`]). Only the
+/// first call counts.
+pub fn set_base(url: String) {
+    BASE.set(url).ok();
+}
+
+/// A mirror's root as given (`http://mb.lan:5000`, with or without a trailing
+/// slash or `/ws/2`) -> its `/ws/2` web service root.
+pub fn service_url(url: &str) -> String {
+    let url = url.trim().trim_end_matches('/');
+    if url.ends_with("/ws/2") {
+        url.to_string()
+    } else {
+        format!("{}/ws/2", url)
+    }
+}
+
+/// The HTTP client for MB and the other metadata sites, trusting `ca_bundle`
+/// on top of the built-in roots.
+pub fn http_client(ca_bundle: Option<&CaBundle>) -> Client {
+    // Mirrors often sit behind a proxy that redirects (http -> https, /ws/2 -> /ws/2/)
+    let mut builder = Client::builder()
+        .timeout(Duration::from_secs(30))
+        .redirect(reqwest::redirect::Policy::limited(10));
+    if let Some(bundle) = ca_bundle {
+        // Added to the built-in roots, so sites the proxy doesn't inspect still verify
+        for cert in reqwest::Certificate::from_pem_bundle(&bundle.pem).expect("checked by CaBundle::read") {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+    builder.build().expect("Failed to create HTTP client")
+}
+
+// ---------------------------------------------------------------------------
+// Adaptive rate limiter
+// ---------------------------------------------------------------------------
+
+pub struct RateLimiter {
+    delay_ms: u64,
+    min_delay: u64,
+    max_delay: u64,
+    last_request: Instant,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self {
+            delay_ms: 1000,      // Start at 1 second (conservative)
+            min_delay: 1000,     // Never go below 1 second
+            max_delay: 10000,    // Max 10 seconds between requests
+            last_request: Instant::now(),
+        }
+    }
+
+    async fn wait(&mut self) {
+        let elapsed = self.last_request.elapsed().as_millis() as u64;
+        if elapsed < self.delay_ms {
+            sleep(Duration::from_millis(self.delay_ms - elapsed)).await;
+        }
+        self.last_request = Instant::now();
+    }
+
+    fn on_success(&mut self) {
+        // Reduce delay by 15% on success (recovery from 10s→1s in ~14 requests vs ~46)
+        if self.delay_ms > self.min_delay {
+            self.delay_ms = (self.delay_ms * 85 / 100).max(self.min_delay);
+        }
+    }
+
+    fn on_rate_limit(&mut self) {
+        // Double the delay on rate limit
+        self.delay_ms = (self.delay_ms * 2).min(self.max_delay);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Requests
+// ---------------------------------------------------------------------------
+
+/// GETs `url` as JSON text, waiting out the rate limit and retrying with
+/// backoff while MB (or a mirror's proxy) answers 503/429/502/504.
+pub async fn get(
+    client: &Client,
+    url: &str,
+    limiter: &mut RateLimiter,
+) -> Result<String, String> {
+    let max_attempts = 10;
+    let mut wait_time = limiter.delay_ms; // Start with current rate limit delay
+
+    for attempt in 0..max_attempts {
+        limiter.wait().await;
+
+        let resp = client
+            .get(url)
+            .header("User-Agent", USER_AGENT)
+            .header("Accept", "application/json")
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", s3::error_chain(&e)))?;
+
+        let status = resp.status().as_u16();
+
+        if status == 200 {
+            limiter.on_success();
+            let body = resp.text().await.map_err(|e| format!("Read body failed: {}", e))?;
+            // Some mirrors (and proxies in front of them) prepend a UTF-8 BOM, which serde_json rejects
+            return Ok(match body.strip_prefix('\u{feff}') {
+                Some(stripped) => stripped.to_string(),
+                None => body,
+            });
+        }
+
+        // 502/504: a proxy in front of a self-hosted mirror while it restarts or reindexes
+        if status == 503 || status == 429 || status == 502 || status == 504 {
+            limiter.on_rate_limit();
+
+            if attempt < max_attempts - 1 {
+                // Exponential backoff: double the wait time each retry
+                wait_time = (wait_time * 2).min(60000); // Cap at 60 seconds per retry
+
+                let reason = match status {
+                    503 => "MB server busy",
+                    429 => "Rate limited",
+                    _ => "MB gateway error",
+                };
+
+                eprint!(
+                    "\r  {} - waiting {:.1}s before retry {}/{}...          ",
+                    reason, wait_time as f64 / 1000.0, attempt + 1, max_attempts - 1
+                );
+
+                sleep(Duration::from_millis(wait_time)).await;
+                continue;
+            } else {
+                eprintln!();
+                return Err(format!(
+                    "MusicBrainz API still unavailable after {} retries (waited up to {}s). Will retry this release next time.",
+                    max_attempts,
+                    wait_time / 1000
+                ));
+            }
+        }
+
+        // Other errors (404, 400, etc) fail immediately
+        return Err(format!("HTTP {} for {}", status, url));
+    }
+
+    Err("Max retries exceeded".to_string())
+}
+
+/// A [`get`] error for an entity MusicBrainz no longer has (deleted, or merged
+/// away between the release group list and the editions call).
+pub fn is_gone(error: &str) -> bool {
+    error.starts_with("HTTP 404 ") || error.starts_with("HTTP 410 ")
+}
+
+/// `<base>/<entity>/?query=...&limit=...&fmt=json`, with the query encoded.
+pub fn search_url(entity: &str, query: &str, limit: u32) -> String {
+    let limit = limit.to_string();
+    let url = format!("{}/{}/", base(), entity);
+    match reqwest::Url::parse_with_params(&url, [("query", query), ("limit", limit.as_str()), ("fmt", "json")]) {
+        Ok(url) => url.to_string(),
+        Err(_) => url,
+    }
+}
+
+/// Quotes `value` as a Lucene phrase for a `/ws/2/<entity>?query=` search,
+/// e.g. `release:` + `quote_query("Live \"Unplugged\"")`.
+pub fn quote_query(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        if c == '"' || c == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,314 @@
+// ---------------------------------------------------------------------------
+// Terminal progress bars
+// ---------------------------------------------------------------------------
+
+//! Per-phase progress bars on stderr: position, files/s, ETA and an error
+//! counter, redrawn in place as one block.
+//!
+//! Bars are only drawn when stderr is a terminal. Piped into a log (cron,
+//! `tee`), each bar prints its final line once when it finishes instead of a
+//! stream of carriage returns; `--quiet` hides them entirely.
+
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Minimum time between two redraws; updates in between only bump counters.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+/// Width of the `[####----]` part.
+const BAR_WIDTH: usize = 24;
+/// Longest message (usually the current artist folder) shown after a bar.
+const MESSAGE_WIDTH: usize = 40;
+
+/// How bars are shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressMode {
+    /// Redrawn in place (stderr is a terminal)
+    Live,
+    /// Final line only, once per bar (stderr is a file or pipe)
+    Summary,
+    /// Nothing (`--quiet`)
+    Hidden,
+}
+
+impl ProgressMode {
+    /// `Live` on a terminal, `Summary` otherwise, `Hidden` when `quiet`.
+    pub fn detect(quiet: bool) -> Self {
+        if quiet {
+            ProgressMode::Hidden
+        } else if std::io::stderr().is_terminal() {
+            ProgressMode::Live
+        } else {
+            ProgressMode::Summary
+        }
+    }
+}
+
+/// The set of bars drawn together. Clone it freely; clones share the block.
+#[derive(Clone)]
+pub struct Progress {
+    mode: ProgressMode,
+    color: bool,
+    block: Arc<Mutex<Block>>,
+}
+
+struct Block {
+    bars: Vec<Arc<BarState>>,
+    /// Lines of the block currently on screen (to move the cursor back up)
+    drawn: usize,
+    last_draw: Option<Instant>,
+}
+
+impl Progress {
+    pub fn new(mode: ProgressMode, color: bool) -> Self {
+        Self {
+            mode,
+            color,
+            block: Arc::new(Mutex::new(Block { bars: Vec::new(), drawn: 0, last_draw: None })),
+        }
+    }
+
+    /// Adds a bar below the active ones. `total` is `None` while unknown
+    /// (e.g. walking the tree), which shows a count and rate but no ETA.
+    pub fn add(&self, label: &str, total: Option<u64>, unit: &str) -> Bar {
+        let bar = Bar {
+            progress: self.clone(),
+            state: Arc::new(BarState {
+                label: label.to_string(),
+                unit: unit.to_string(),
+                total: AtomicU64::new(total.unwrap_or(u64::MAX)),
+                position: AtomicU64::new(0),
+                errors: AtomicU64::new(0),
+                message: Mutex::new(String::new()),
+                started: Instant::now(),
+                finished: Mutex::new(None),
+            }),
+        };
+        if let Ok(mut block) = self.block.lock() {
+            block.bars.push(Arc::clone(&bar.state));
+        }
+        bar
+    }
+
+    /// Redraws the block, at most every [`REDRAW_INTERVAL`] unless `force`.
+    /// Skips the redraw when another thread is already drawing.
+    fn draw(&self, force: bool) {
+        if self.mode != ProgressMode::Live {
+            return;
+        }
+        let mut block = if force {
+            match self.block.lock() {
+                Ok(block) => block,
+                Err(_) => return,
+            }
+        } else {
+            match self.block.try_lock() {
+                Ok(block) => block,
+                Err(_) => return,
+            }
+        };
+        if !force && block.last_draw.is_some_and(|t| t.elapsed() < REDRAW_INTERVAL) {
+            return;
+        }
+
+        let mut out = String::new();
+        if block.drawn > 0 {
+            out.push_str(&format!("\x1b[{}A", block.drawn));
+        }
+        for bar in &block.bars {
+            out.push_str("\r\x1b[2K");
+            out.push_str(&bar.line(self.color));
+            out.push('\n');
+        }
+        let mut stderr = std::io::stderr().lock();
+        stderr.write_all(out.as_bytes()).ok();
+        stderr.flush().ok();
+
+        // Finished bars stay on screen as their final line; the next bar
+        // starts a new block below them
+        block.drawn = block.bars.len();
+        if block.bars.iter().all(|b| b.is_finished()) {
+            block.bars.clear();
+            block.drawn = 0;
+        }
+        block.last_draw = Some(Instant::now());
+    }
+}
+
+/// One phase's bar. Cheap to clone and safe to update from worker threads.
+#[derive(Clone)]
+pub struct Bar {
+    progress: Progress,
+    state: Arc<BarState>,
+}
+
+struct BarState {
+    label: String,
+    unit: String,
+    /// `u64::MAX` while unknown
+    total: AtomicU64,
+    position: AtomicU64,
+    errors: AtomicU64,
+    message: Mutex<String>,
+    started: Instant,
+    /// Elapsed time, frozen at `finish`
+    finished: Mutex<Option<Duration>>,
+}
+
+impl Bar {
+    /// Advances the bar by `n` and returns the new position.
+    pub fn inc(&self, n: u64) -> u64 {
+        let position = self.state.position.fetch_add(n, Ordering::Relaxed) + n;
+        self.progress.draw(false);
+        position
+    }
+
+    pub fn set_position(&self, position: u64) {
+        self.state.position.store(position, Ordering::Relaxed);
+        self.progress.draw(false);
+    }
+
+    pub fn set_total(&self, total: u64) {
+        self.state.total.store(total, Ordering::Relaxed);
+    }
+
+    /// Counts `n` failed items (shown in red after the ETA).
+    pub fn add_errors(&self, n: u64) {
+        self.state.errors.fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// Text shown after the counters, e.g. the artist folder being worked on.
+    pub fn set_message(&self, message: &str) {
+        if let Ok(mut m) = self.state.message.lock() {
+            if *m != message {
+                m.clear();
+                m.push_str(message);
+            }
+        }
+    }
+
+    pub fn position(&self) -> u64 {
+        self.state.position.load(Ordering::Relaxed)
+    }
+
+    /// Freezes the bar (rate and elapsed time) and draws its final line.
+    pub fn finish(&self) {
+        if let Ok(mut finished) = self.state.finished.lock() {
+            if finished.is_some() {
+                return;
+            }
+            *finished = Some(self.state.started.elapsed());
+        }
+        if let Ok(mut m) = self.state.message.lock() {
+            m.clear();
+        }
+        match self.progress.mode {
+            ProgressMode::Live => self.progress.draw(true),
+            ProgressMode::Summary => eprintln!("{}", self.state.line(false)),
+            ProgressMode::Hidden => {}
+        }
+    }
+}
+
+impl BarState {
+    fn is_finished(&self) -> bool {
+        self.finished.lock().map(|f| f.is_some()).unwrap_or(true)
+    }
+
+    fn line(&self, color: bool) -> String {
+        let total = self.total.load(Ordering::Relaxed);
+        let finished = self.finished.lock().ok().and_then(|f| *f);
+        let message = self.message.lock().map(|m| m.clone()).unwrap_or_default();
+        render_line(&LineParts {
+            label: &self.label,
+            unit: &self.unit,
+            position: self.position.load(Ordering::Relaxed),
+            total: (total != u64::MAX).then_some(total),
+            errors: self.errors.load(Ordering::Relaxed),
+            elapsed: finished.unwrap_or_else(|| self.started.elapsed()),
+            finished: finished.is_some(),
+            message: &message,
+            color,
+        })
+    }
+}
+
+/// Everything a bar line shows, as plain values (see [`render_line`]).
+pub struct LineParts<'a> {
+    pub label: &'a str,
+    pub unit: &'a str,
+    pub position: u64,
+    pub total: Option<u64>,
+    pub errors: u64,
+    pub elapsed: Duration,
+    pub finished: bool,
+    pub message: &'a str,
+    pub color: bool,
+}
+
+/// One bar line, e.g.
+/// `  ✓ Scanning [########################] 5210/5210 100%  812 files/s  0:06  2 errors`.
+/// While running, the ETA replaces the elapsed time and the message follows.
+pub fn render_line(p: &LineParts) -> String {
+    let paint = |code: &str, text: &str| {
+        if p.color {
+            format!("\x1b[{}m{}\x1b[0m", code, text)
+        } else {
+            text.to_string()
+        }
+    };
+
+    let secs = p.elapsed.as_secs_f64();
+    let rate = if secs > 0.0 { p.position as f64 / secs } else { 0.0 };
+    let icon = if p.finished { paint("32", "✓") } else { paint("90", "→") };
+    let mut line = format!("  {} {:<9}", icon, p.label);
+
+    match p.total {
+        Some(total) => {
+            let ratio = if total > 0 { (p.position as f64 / total as f64).min(1.0) } else { 1.0 };
+            let filled = (ratio * BAR_WIDTH as f64).round() as usize;
+            let bar = format!("{}{}", "#".repeat(filled), "-".repeat(BAR_WIDTH - filled));
+            line.push_str(&format!(
+                " [{}] {}/{} {:>3.0}%",
+                paint("36", &bar),
+                p.position,
+                total,
+                ratio * 100.0
+            ));
+        }
+        None => line.push_str(&format!(" {}", p.position)),
+    }
+
+    line.push_str(&format!("  {:.0} {}/s", rate, p.unit));
+
+    if p.finished {
+        line.push_str(&format!("  {}", format_duration(p.elapsed)));
+    } else if let Some(total) = p.total {
+        if rate > 0.0 && total >= p.position {
+            let eta = Duration::from_secs_f64((total - p.position) as f64 / rate);
+            line.push_str(&format!("  ETA {}", format_duration(eta)));
+        }
+    }
+
+    if p.errors > 0 {
+        let errors = format!("{} error{}", p.errors, if p.errors == 1 { "" } else { "s" });
+        line.push_str(&format!("  {}", paint("31", &errors)));
+    }
+
+    if !p.finished && !p.message.is_empty() {
+        let message: String = p.message.chars().take(MESSAGE_WIDTH).collect();
+        line.push_str(&format!("  {}", paint("96", &message)));
+    }
+    line
+}
+
+/// `m:ss`, or `h:mm:ss` from an hour up.
+pub fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
@@ -0,0 +1,776 @@
+//! Multi-page HTML reports in the style of the analysis report.
+//!
+//! A report is a directory with `index.html`, `pages/*.html` and shared
+//! `css/styles.css` / `js/report.js`. The writer knows nothing about audio
+//! files: callers describe their findings as [`Entry`]s grouped by a key
+//! (usually the artist folder) into [`Panel`]s, or as flat [`FileRow`]s, and
+//! pick which pages show up in the navigation bar with [`NavItem`]s.
+//!
+//! [`Report::write_single_file`] bundles the pages written so far into one
+//! standalone HTML file, for attaching to a mail or a ticket.
+//!
+//! ```no_run
+//! use dmp_common::report::{Entry, Groups, NavItem, Panel, Report};
+//! use std::path::Path;
+//!
+//! let mut groups = Groups::new();
+//! groups.entry("Radiohead".to_string()).or_default().push(Entry::new("Radiohead/OK Computer/01 Airbag.flac"));
+//!
+//! let nav = vec![NavItem::overview(), NavItem::page("missing", "Missing", "missing_1.html", 1)];
+//! let report = Report::create(Path::new("reports/qc"), "Index QC", nav)?;
+//!
+//! let mut index = report.index_page("Overview", None)?;
+//! report.write_breakdown(&mut index, "Breakdown by Category", "Issues")?;
+//! index.finish()?;
+//!
+//! report.write_grouped_pages("missing", "Missing", &[Panel::new("files", "Files", groups)])?;
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use html_escape::{encode_double_quoted_attribute, encode_text};
+use std::fmt::Write as _;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+mod assets;
+
+/// Group keys (artists) per page on paginated pages.
+pub const GROUPS_PER_PAGE: usize = 20;
+
+/// Markup of a group around its entries (header, list), counted towards
+/// [`PageBudget::bytes`] once per group and panel.
+const GROUP_MARKUP_BYTES: usize = 300;
+
+/// Checkbox rendered on every file row; state is kept client-side (see `toggleHandled` in JS).
+pub const HANDLED_BOX: &str = "<input type=\"checkbox\" class=\"handled-box\" title=\"Mark as handled\" onchange=\"toggleHandled(this)\">";
+
+// ---------------------------------------------------------------------------
+// Escaping
+// ---------------------------------------------------------------------------
+//
+// Paths and folder names come straight from the filesystem and can hold
+// quotes, angle brackets, newlines or other control characters. Everything a
+// caller passes in goes through one of these two.
+
+/// Escape `s` for an HTML text node. Control characters are shown as their
+/// Unicode control pictures ("␊" for a newline), so a hostile name stays on
+/// one visible line.
+pub fn html_text(s: &str) -> String {
+    let shown: String = s.chars().map(control_picture).collect();
+    encode_text(&shown).into_owned()
+}
+
+/// Escape `s` for a double-quoted attribute value. C0 controls and DEL become
+/// numeric references, which the parser reads back unchanged (a raw CR would
+/// be turned into a newline), so `dataset.path` is the exact path again.
+/// C1 controls stay raw: their references would be remapped to Windows-1252.
+pub fn html_attr(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in encode_double_quoted_attribute(s).chars() {
+        if c.is_ascii_control() {
+            write!(out, "&#x{:X};", c as u32).ok();
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn control_picture(c: char) -> char {
+    match c {
+        '\0'..='\x1f' => char::from_u32(0x2400 + c as u32).unwrap_or('\u{FFFD}'),
+        '\x7f' => '\u{2421}',
+        '\u{80}'..='\u{9f}' => '\u{FFFD}',
+        _ => c,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Issue model
+// ---------------------------------------------------------------------------
+
+/// One field change shown in a resolved entry's popover.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub field: String,
+    pub old: String,
+    pub new: String,
+}
+
+/// One file listed on a grouped page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// Path shown to the user; also the key used by "Mark as handled".
+    pub path: String,
+    /// Short note shown after the path, e.g. "(blank)".
+    pub annotation: Option<String>,
+    /// `Some` when the issue was fixed during the run: the entry is struck
+    /// through, counted in the "(-N)" deltas and, when there are changes,
+    /// gets a popover listing them.
+    pub resolved: Option<Vec<Change>>,
+}
+
+impl Entry {
+    pub fn new(path: impl Into<String>) -> Self {
+        Self { path: path.into(), annotation: None, resolved: None }
+    }
+
+    pub fn annotated(mut self, annotation: Option<String>) -> Self {
+        self.annotation = annotation;
+        self
+    }
+
+    pub fn resolved(mut self, changes: Vec<Change>) -> Self {
+        self.resolved = Some(changes);
+        self
+    }
+}
+
+/// Entries keyed by group (artist folder), both sorted.
+pub type Groups = BTreeMap<String, Vec<Entry>>;
+
+/// A sub-tab on a grouped page: one kind of issue.
+#[derive(Debug, Clone)]
+pub struct Panel {
+    /// HTML id suffix, unique within the page (`panel-{id}`).
+    pub id: String,
+    pub label: String,
+    pub groups: Groups,
+    /// Short text shown in a group's header next to its file count,
+    /// e.g. "Year: 3, MB Album: 12". Groups without one show the count only.
+    pub summaries: BTreeMap<String, String>,
+}
+
+impl Panel {
+    pub fn new(id: impl Into<String>, label: impl Into<String>, groups: Groups) -> Self {
+        Self { id: id.into(), label: label.into(), groups, summaries: BTreeMap::new() }
+    }
+
+    pub fn with_summaries(mut self, summaries: BTreeMap<String, String>) -> Self {
+        self.summaries = summaries;
+        self
+    }
+
+    /// Number of entries across all groups.
+    pub fn total(&self) -> usize {
+        self.groups.values().map(|v| v.len()).sum()
+    }
+
+    /// Number of resolved entries across all groups.
+    pub fn resolved(&self) -> usize {
+        self.groups.values().flatten().filter(|e| e.resolved.is_some()).count()
+    }
+
+    fn only_groups(&self, keys: &BTreeSet<&str>) -> Panel {
+        Panel {
+            id: self.id.clone(),
+            label: self.label.clone(),
+            groups: self.groups.iter()
+                .filter(|(key, _)| keys.contains(key.as_str()))
+                .map(|(key, entries)| (key.clone(), entries.clone()))
+                .collect(),
+            summaries: self.summaries.iter()
+                .filter(|(key, _)| keys.contains(key.as_str()))
+                .map(|(key, summary)| (key.clone(), summary.clone()))
+                .collect(),
+        }
+    }
+}
+
+/// A row of a flat file table (see [`Page::write_file_table`]).
+#[derive(Debug, Clone)]
+pub struct FileRow {
+    /// Path shown to the user; also the key used by "Mark as handled".
+    pub path: String,
+    /// Tooltip on the path cell, usually the absolute path.
+    pub title: String,
+    pub problem: String,
+}
+
+/// A card in a stats row (see [`Page::write_stats`]). `class` is one of
+/// `ok`, `warn`, `fail`, `info`.
+#[derive(Debug, Clone)]
+pub struct StatCard {
+    pub label: String,
+    pub value: String,
+    pub class: &'static str,
+}
+
+impl StatCard {
+    pub fn new(label: impl Into<String>, value: impl ToString, class: &'static str) -> Self {
+        Self { label: label.into(), value: value.to_string(), class }
+    }
+}
+
+/// How much a paginated page may hold (see [`Report::with_page_budget`]).
+/// A page ends at whichever limit is reached first; a group never spans two
+/// pages, so one group over budget gets a page of its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageBudget {
+    /// Group keys per page, at most.
+    pub groups: usize,
+    /// Approximate HTML size of the groups on a page, in bytes (0 = no limit).
+    pub bytes: usize,
+    /// Entries across all panels of a page (0 = no limit).
+    pub files: usize,
+}
+
+impl Default for PageBudget {
+    fn default() -> Self {
+        Self { groups: GROUPS_PER_PAGE, bytes: 0, files: 0 }
+    }
+}
+
+impl PageBudget {
+    /// Splits groups of the given (bytes, files) sizes into pages, as index
+    /// ranges. Always returns at least one (possibly empty) page.
+    pub fn paginate(&self, sizes: &[(usize, usize)]) -> Vec<std::ops::Range<usize>> {
+        let mut pages = Vec::new();
+        let (mut start, mut bytes, mut files) = (0, 0, 0);
+        for (i, &(group_bytes, group_files)) in sizes.iter().enumerate() {
+            let full = i > start
+                && (i - start >= self.groups.max(1)
+                    || (self.bytes > 0 && bytes + group_bytes > self.bytes)
+                    || (self.files > 0 && files + group_files > self.files));
+            if full {
+                pages.push(start..i);
+                start = i;
+                bytes = 0;
+                files = 0;
+            }
+            bytes += group_bytes;
+            files += group_files;
+        }
+        if start < sizes.len() || pages.is_empty() {
+            pages.push(start..sizes.len());
+        }
+        pages
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Navigation
+// ---------------------------------------------------------------------------
+
+/// An entry of the navigation bar (and of the index breakdown table).
+#[derive(Debug, Clone)]
+pub struct NavItem {
+    /// Matched against the `active` id of a page to highlight the tab.
+    pub id: String,
+    pub label: String,
+    /// `index.html`, or a file name under `pages/`.
+    pub file: String,
+    /// Badge count; `None` hides the badge and the breakdown row.
+    pub count: Option<usize>,
+    /// Resolved count, shown as "(-N)" next to the badge.
+    pub resolved: usize,
+}
+
+impl NavItem {
+    /// The "Overview" tab linking to `index.html`.
+    pub fn overview() -> Self {
+        Self { id: "overview".into(), label: "Overview".into(), file: "index.html".into(), count: None, resolved: 0 }
+    }
+
+    pub fn page(id: impl Into<String>, label: impl Into<String>, file: impl Into<String>, count: usize) -> Self {
+        Self { id: id.into(), label: label.into(), file: file.into(), count: Some(count), resolved: 0 }
+    }
+
+    pub fn with_resolved(mut self, resolved: usize) -> Self {
+        self.resolved = resolved;
+        self
+    }
+
+    fn href(&self, at_root: bool) -> String {
+        match (self.file == "index.html", at_root) {
+            (true, true) => "index.html".into(),
+            (true, false) => "../index.html".into(),
+            (false, true) => format!("pages/{}", self.file),
+            (false, false) => self.file.clone(),
+        }
+    }
+}
+
+/// "(-N)" delta shown next to counts when entries were resolved.
+fn delta_html(resolved: usize) -> String {
+    if resolved > 0 {
+        format!("<span class=\"match-delta\"> (-{})</span>", resolved)
+    } else {
+        String::new()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Report
+// ---------------------------------------------------------------------------
+
+/// A report directory being written.
+pub struct Report {
+    dir: PathBuf,
+    title: String,
+    nav: Vec<NavItem>,
+    resolved_title: String,
+    page_budget: PageBudget,
+    /// Pages opened so far, relative to `dir`, in order (for the single file).
+    written: Mutex<Vec<String>>,
+}
+
+impl Report {
+    /// Creates `dir` (with `css/`, `js/`, `pages/`) and writes the shared
+    /// assets. `title` is the `<h1>` of every page.
+    pub fn create(dir: &Path, title: &str, nav: Vec<NavItem>) -> std::io::Result<Self> {
+        fs::create_dir_all(dir.join("css"))?;
+        fs::create_dir_all(dir.join("js"))?;
+        fs::create_dir_all(dir.join("pages"))?;
+        fs::write(dir.join("css/styles.css"), assets::CSS)?;
+        fs::write(dir.join("js/report.js"), assets::JS)?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            title: title.to_string(),
+            nav,
+            resolved_title: "Resolved:".into(),
+            page_budget: PageBudget::default(),
+            written: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Limits of each page written by [`Report::write_grouped_pages`]
+    /// (default: [`GROUPS_PER_PAGE`] groups, no size or file limit).
+    pub fn with_page_budget(mut self, budget: PageBudget) -> Self {
+        self.page_budget = budget;
+        self
+    }
+
+    /// Heading of the popover on resolved entries (default "Resolved:").
+    pub fn with_resolved_title(mut self, title: &str) -> Self {
+        self.resolved_title = title.to_string();
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Starts `index.html`. `subtitle` is raw HTML placed above the nav bar.
+    pub fn index_page(&self, title: &str, subtitle: Option<&str>) -> std::io::Result<Page> {
+        self.open("index.html", title, "overview", subtitle)
+    }
+
+    /// Starts `pages/{file}` with the tab `active` highlighted. `subtitle` is
+    /// raw HTML placed above the nav bar.
+    pub fn page(&self, file: &str, title: &str, active: &str, subtitle: Option<&str>) -> std::io::Result<Page> {
+        self.open(&format!("pages/{}", file), title, active, subtitle)
+    }
+
+    fn open(&self, rel: &str, title: &str, active: &str, subtitle: Option<&str>) -> std::io::Result<Page> {
+        let at_root = !rel.starts_with("pages/");
+        let mut page = Page {
+            out: BufWriter::new(fs::File::create(self.dir.join(rel))?),
+            at_root,
+        };
+        let mut written = self.written.lock().unwrap_or_else(|e| e.into_inner());
+        if !written.iter().any(|w| w == rel) {
+            written.push(rel.to_string());
+        }
+        drop(written);
+        let css_path = if at_root { "css/styles.css" } else { "../css/styles.css" };
+        write!(page, "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n\
+            <meta charset=\"UTF-8\">\n\
+            <meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">\n\
+            <title>{} &mdash; {}</title>\n\
+            <link rel=\"stylesheet\" href=\"{}\">\n\
+            </head>\n<body>\n<div class=\"container\">\n\
+            <h1>{}</h1>\n",
+            html_text(title), html_text(&self.title), css_path, html_text(&self.title)
+        )?;
+        if let Some(subtitle) = subtitle {
+            writeln!(page, "<p class=\"subtitle\">{}</p>", subtitle)?;
+        }
+        self.write_nav(&mut page, active)?;
+        Ok(page)
+    }
+
+    fn write_nav(&self, page: &mut Page, active: &str) -> std::io::Result<()> {
+        writeln!(page, "<nav class=\"nav-bar\">")?;
+        for item in &self.nav {
+            let active_class = if item.id == active { " active" } else { "" };
+            let badge = item.count
+                .map(|n| format!("<span class=\"badge\">{}{}</span>", n, delta_html(item.resolved)))
+                .unwrap_or_default();
+            writeln!(page, "<a href=\"{}\" class=\"nav-tab{}\">{}{}</a>",
+                html_attr(&item.href(page.at_root)), active_class, html_text(&item.label), badge)?;
+        }
+        writeln!(page, "<button class=\"handled-export\" onclick=\"exportHandled()\" title=\"Download handled.json for --apply-handled\">Export handled (<span class=\"handled-count\">0</span>)</button>")?;
+        writeln!(page, "</nav>")?;
+        Ok(())
+    }
+
+    /// Table of every counted nav item with a link to its page, for `index.html`.
+    pub fn write_breakdown(&self, page: &mut Page, heading: &str, count_label: &str) -> std::io::Result<()> {
+        write!(page, "<div class=\"breakdown\">\n<h2>{}</h2>\n\
+            <div class=\"table-wrap\"><table>\n\
+            <thead><tr><th>Category</th><th>{}</th><th></th></tr></thead>\n<tbody>\n",
+            html_text(heading), html_text(count_label))?;
+        for item in &self.nav {
+            let Some(count) = item.count else { continue };
+            writeln!(page, "<tr><td>{}</td><td>{}</td><td><a href=\"{}\">View &rarr;</a></td></tr>",
+                html_text(&item.label), count, html_attr(&item.href(page.at_root)))?;
+        }
+        writeln!(page, "</tbody>\n</table></div>\n</div>")?;
+        Ok(())
+    }
+
+    /// Writes `pages/{id}_1.html`, `pages/{id}_2.html`, ... with the panels
+    /// as sub-tabs (the first one open), paginated by group key so that a
+    /// group always shows up on the same page in every panel. Pages end at
+    /// the [`PageBudget`]. `id` is also the nav tab highlighted. Returns the
+    /// number of pages.
+    pub fn write_grouped_pages(&self, id: &str, title: &str, panels: &[Panel]) -> std::io::Result<usize> {
+        let keys: Vec<&str> = panels.iter()
+            .flat_map(|p| p.groups.keys().map(String::as_str))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let sizes: Vec<(usize, usize)> = keys.iter()
+            .map(|key| {
+                panels.iter()
+                    .filter_map(|p| p.groups.get(*key))
+                    .map(|entries| {
+                        let bytes = entries.iter().map(|e| self.entry_html(e).len()).sum::<usize>();
+                        (GROUP_MARKUP_BYTES + key.len() + bytes, entries.len())
+                    })
+                    .fold((0, 0), |(b, f), (eb, ef)| (b + eb, f + ef))
+            })
+            .collect();
+        let ranges = self.page_budget.paginate(&sizes);
+        let total_pages = ranges.len();
+
+        for (i, range) in ranges.into_iter().enumerate() {
+            let page_num = i + 1;
+            let page_keys: BTreeSet<&str> = keys[range].iter().copied().collect();
+            let page_panels: Vec<Panel> = panels.iter().map(|p| p.only_groups(&page_keys)).collect();
+
+            let mut page = self.page(&format!("{}_{}.html", id, page_num), title, id, None)?;
+            page.write_search_box(SearchTarget::Groups)?;
+            write_pagination(&mut page, id, page_num, total_pages)?;
+            write_subtab_bar(&mut page, &page_panels)?;
+            for (i, panel) in page_panels.iter().enumerate() {
+                self.write_panel(&mut page, panel, i == 0)?;
+            }
+            write_pagination(&mut page, id, page_num, total_pages)?;
+            page.finish()?;
+        }
+        Ok(total_pages)
+    }
+
+    /// One collapsible-group panel; `active` controls whether it is visible on load.
+    fn write_panel(&self, page: &mut Page, panel: &Panel, active: bool) -> std::io::Result<()> {
+        let hidden = if active { "" } else { " hidden" };
+        writeln!(page, "<div class=\"panel{}\" id=\"panel-{}\">", hidden, html_attr(&panel.id))?;
+        if panel.groups.is_empty() {
+            writeln!(page, "<div class=\"empty-panel\">No issues found</div>")?;
+        } else {
+            writeln!(page, "<div class=\"artist-list\">")?;
+            for (key, entries) in &panel.groups {
+                let summary = panel.summaries.get(key)
+                    .map(|s| format!("<span class=\"group-summary\">{}</span>", html_text(s)))
+                    .unwrap_or_default();
+                write!(
+                    page,
+                    "<div class=\"artist-group\">\n\
+                     <div class=\"artist-header\" onclick=\"toggleArtist(this)\">\
+                     <span class=\"arrow\">&#9660;</span>\
+                     <span class=\"artist-name\">{}</span>{}\
+                     <span class=\"file-count\">{} file{}</span>\
+                     </div>\n\
+                     <ul class=\"file-list\">\n",
+                    html_text(key),
+                    summary,
+                    entries.len(),
+                    if entries.len() == 1 { "" } else { "s" }
+                )?;
+                for entry in entries {
+                    self.write_entry(page, entry)?;
+                }
+                writeln!(page, "</ul>\n</div>")?;
+            }
+            writeln!(page, "</div>")?;
+        }
+        writeln!(page, "</div>")?;
+        Ok(())
+    }
+
+    fn write_entry(&self, page: &mut Page, entry: &Entry) -> std::io::Result<()> {
+        writeln!(page, "{}", self.entry_html(entry))
+    }
+
+    /// One `<li>` of a group (see [`Entry`]).
+    fn entry_html(&self, entry: &Entry) -> String {
+        let path = &entry.path;
+        let ann_html = entry.annotation.as_ref()
+            .map(|a| format!(" <span class=\"annot\">{}</span>", html_text(a)))
+            .unwrap_or_default();
+
+        match &entry.resolved {
+            Some(changes) => {
+                // Strikethrough + dim + green check + popover with field changes
+                let mut popover_html = String::new();
+                if !changes.is_empty() {
+                    popover_html.push_str(&format!(
+                        "<div class=\"match-popover\"><div class=\"pop-title\">{}</div>",
+                        html_text(&self.resolved_title)
+                    ));
+                    for change in changes {
+                        popover_html.push_str(&format!(
+                            "<div><span class=\"pop-old\">{}: {}</span><span class=\"pop-arrow\">&rarr;</span><span class=\"pop-new\">{}</span></div>",
+                            html_text(&change.field),
+                            html_text(&change.old),
+                            html_text(&change.new),
+                        ));
+                    }
+                    popover_html.push_str("</div>");
+                }
+                format!(
+                    "<li class=\"file-item matched\" data-path=\"{}\">{}{}{}<span class=\"match-check\" onmouseenter=\"showMatchInfo(this)\" onmouseleave=\"hideMatchInfo(this)\">&#10003;</span>{}</li>",
+                    html_attr(path), HANDLED_BOX, html_text(path), ann_html, popover_html
+                )
+            }
+            None => format!("<li class=\"file-item\" data-path=\"{}\">{}{}{}</li>",
+                html_attr(path), HANDLED_BOX, html_text(path), ann_html),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Single file
+// ---------------------------------------------------------------------------
+
+impl Report {
+    /// Writes every page this report wrote into one standalone HTML file at
+    /// `out`: the CSS and JS inlined, each page a `<section>` shown when the
+    /// URL hash names it (`#page-index`, `#page-critical_1`, ...; the overview
+    /// otherwise), and links between pages turned into those anchors. Call it
+    /// once the last page is finished.
+    pub fn write_single_file(&self, out: &Path) -> std::io::Result<()> {
+        let pages = self.written.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        let mut html = String::new();
+        write!(html, "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n\
+            <meta charset=\"UTF-8\">\n\
+            <meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">\n\
+            <title>{}</title>\n<style>\n{}\n{}\n</style>\n</head>\n<body>\n",
+            html_text(&self.title), assets::CSS, assets::SINGLE_FILE_CSS).map_err(std::io::Error::other)?;
+        for rel in &pages {
+            let text = fs::read_to_string(self.dir.join(rel))?;
+            let body = text.split_once("<body>\n").map_or("", |(_, rest)| rest);
+            let body = body.rsplit_once("</body>").map_or(body, |(body, _)| body);
+            let body = body.replace(&format!("<script src=\"{}\"></script>\n", script_path(rel)), "");
+            writeln!(html, "<section class=\"report-page\" id=\"{}\">", page_anchor(rel)).map_err(std::io::Error::other)?;
+            html.push_str(&page_links_to_anchors(&body, rel, &pages));
+            html.push_str("</section>\n");
+        }
+        write!(html, "<script>\n{}\n{}\n</script>\n</body>\n</html>\n", assets::JS, assets::SINGLE_FILE_JS)
+            .map_err(std::io::Error::other)?;
+        fs::write(out, html)
+    }
+}
+
+/// `src` of the script tag [`Page::finish`] writes on the page at `rel`.
+fn script_path(rel: &str) -> &'static str {
+    if rel.starts_with("pages/") { "../js/report.js" } else { "js/report.js" }
+}
+
+/// Section id of a page in the single file: `page-` and its file stem.
+fn page_anchor(rel: &str) -> String {
+    let name = rel.rsplit('/').next().unwrap_or(rel);
+    format!("page-{}", name.strip_suffix(".html").unwrap_or(name))
+}
+
+/// Points every `href` of the page at `rel` that leads to one of `pages` at
+/// that page's section instead. Other links are left as they are.
+fn page_links_to_anchors(body: &str, rel: &str, pages: &[String]) -> String {
+    let in_pages = rel.starts_with("pages/");
+    let mut out = String::with_capacity(body.len());
+    let mut rest = body;
+    while let Some(start) = rest.find("href=\"") {
+        let (head, tail) = rest.split_at(start + "href=\"".len());
+        out.push_str(head);
+        let end = tail.find('"').unwrap_or(tail.len());
+        let href = &tail[..end];
+        let file = href.split('#').next().unwrap_or(href);
+        let target = match (in_pages, file.strip_prefix("../")) {
+            (true, Some(up)) => up.to_string(),
+            (true, None) => format!("pages/{}", file),
+            (false, _) => file.to_string(),
+        };
+        if !file.is_empty() && pages.contains(&target) {
+            out.push('#');
+            out.push_str(&page_anchor(&target));
+        } else {
+            out.push_str(href);
+        }
+        rest = &tail[end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Pagination controls (prev/next + page numbers); nothing for a single page.
+fn write_pagination(page: &mut Page, base_name: &str, current_page: usize, total_pages: usize) -> std::io::Result<()> {
+    if total_pages <= 1 {
+        return Ok(());
+    }
+    let base_name = html_attr(base_name);
+    writeln!(page, "<div class=\"pagination\">")?;
+    if current_page > 1 {
+        writeln!(page, "<a href=\"{}_{}.html\">&lsaquo;</a>", base_name, current_page - 1)?;
+    } else {
+        writeln!(page, "<span class=\"disabled\">&lsaquo;</span>")?;
+    }
+    for p in 1..=total_pages {
+        if p == current_page {
+            writeln!(page, "<span class=\"active\">{}</span>", p)?;
+        } else {
+            writeln!(page, "<a href=\"{}_{}.html\">{}</a>", base_name, p, p)?;
+        }
+    }
+    if current_page < total_pages {
+        writeln!(page, "<a href=\"{}_{}.html\">&rsaquo;</a>", base_name, current_page + 1)?;
+    } else {
+        writeln!(page, "<span class=\"disabled\">&rsaquo;</span>")?;
+    }
+    writeln!(page, "</div>")?;
+    Ok(())
+}
+
+/// Sub-tab bar, one tab per panel with its counts. The first tab is active.
+fn write_subtab_bar(page: &mut Page, panels: &[Panel]) -> std::io::Result<()> {
+    writeln!(page, "<div class=\"subtab-bar\">")?;
+    for (i, panel) in panels.iter().enumerate() {
+        let active = if i == 0 { " active" } else { "" };
+        writeln!(
+            page,
+            "<button class=\"subtab{}\" onclick=\"switchSubtab(this)\" data-panel=\"panel-{}\">{}<span class=\"subtab-count\">{}{}</span></button>",
+            active, html_attr(&panel.id), html_text(&panel.label), panel.total(), delta_html(panel.resolved())
+        )?;
+    }
+    writeln!(page, "</div>")?;
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Page
+// ---------------------------------------------------------------------------
+
+/// What a page's search box filters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchTarget {
+    /// Rows of the tables on the page.
+    Table,
+    /// Files of the collapsible groups on the page.
+    Groups,
+}
+
+/// An open report page. Write extra HTML through `std::io::Write` (escape
+/// it yourself) and close it with [`Page::finish`].
+pub struct Page {
+    out: BufWriter<fs::File>,
+    at_root: bool,
+}
+
+impl Page {
+    pub fn write_search_box(&mut self, target: SearchTarget) -> std::io::Result<()> {
+        let handler = match target {
+            SearchTarget::Table => "filterTable",
+            SearchTarget::Groups => "filterGroups",
+        };
+        writeln!(self, "<div class=\"search-box\"><input type=\"text\" placeholder=\"Filter files\u{2026}\" oninput=\"{}(this)\"></div>", handler)
+    }
+
+    /// Rows of stat cards.
+    pub fn write_stats(&mut self, rows: &[Vec<StatCard>]) -> std::io::Result<()> {
+        writeln!(self, "<div class=\"stats-container\">")?;
+        for row in rows {
+            writeln!(self, "<div class=\"stats-group\">")?;
+            for card in row {
+                writeln!(self, "<div class=\"stat-card\"><div class=\"label\">{}</div><div class=\"value {}\">{}</div></div>",
+                    html_text(&card.label), card.class, html_text(&card.value))?;
+            }
+            writeln!(self, "</div>")?;
+        }
+        writeln!(self, "</div>")?;
+        Ok(())
+    }
+
+    /// Sortable "Path / Problem" table with a "Mark as handled" box per row.
+    pub fn write_file_table(&mut self, rows: &[FileRow]) -> std::io::Result<()> {
+        write!(self, "<div class=\"table-wrap\"><table>\n\
+            <thead><tr><th data-sort=\"0\">Path</th><th data-sort=\"1\">Problem</th></tr></thead>\n<tbody>\n")?;
+        for row in rows {
+            writeln!(self, "<tr data-path=\"{}\"><td title=\"{}\">{}{}</td><td>{}</td></tr>",
+                html_attr(&row.path),
+                html_attr(&row.title),
+                HANDLED_BOX,
+                html_text(&row.path),
+                html_text(&row.problem))?;
+        }
+        if rows.is_empty() {
+            writeln!(self, "<tr><td colspan=\"2\" class=\"empty-state\">No issues found</td></tr>")?;
+        }
+        writeln!(self, "</tbody>\n</table></div>")?;
+        Ok(())
+    }
+
+    /// Titled, sortable table of plain-text cells. `class` is added to the
+    /// `<table>` (e.g. `mirror` for left-aligned cells).
+    pub fn write_table(&mut self, title: &str, class: &str, headers: &[&str], rows: &[Vec<&str>]) -> std::io::Result<()> {
+        write!(self, "<div class=\"breakdown\">\n<h2>{} ({})</h2>\n<div class=\"table-wrap\"><table class=\"{}\">\n<thead><tr>",
+            html_text(title), rows.len(), html_attr(class))?;
+        for (i, h) in headers.iter().enumerate() {
+            write!(self, "<th data-sort=\"{}\">{}</th>", i, html_text(h))?;
+        }
+        writeln!(self, "</tr></thead>\n<tbody>")?;
+        for row in rows {
+            write!(self, "<tr>")?;
+            for cell in row {
+                write!(self, "<td>{}</td>", html_text(cell))?;
+            }
+            writeln!(self, "</tr>")?;
+        }
+        if rows.is_empty() {
+            writeln!(self, "<tr><td colspan=\"{}\" class=\"empty-state\">Nothing here</td></tr>", headers.len())?;
+        }
+        writeln!(self, "</tbody>\n</table></div>\n</div>")?;
+        Ok(())
+    }
+
+    /// Titled two-column "field: value" table (e.g. how the report was produced).
+    pub fn write_details(&mut self, title: &str, rows: &[(&str, &str)]) -> std::io::Result<()> {
+        write!(self, "<div class=\"breakdown\">\n<h2>{}</h2>\n<div class=\"table-wrap\"><table class=\"details\">\n<tbody>\n",
+            html_text(title))?;
+        for (field, value) in rows {
+            writeln!(self, "<tr><th>{}</th><td>{}</td></tr>", html_text(field), html_text(value))?;
+        }
+        writeln!(self, "</tbody>\n</table></div>\n</div>")?;
+        Ok(())
+    }
+
+    /// Closes the page and flushes it to disk.
+    pub fn finish(mut self) -> std::io::Result<()> {
+        let js_path = if self.at_root { "js/report.js" } else { "../js/report.js" };
+        write!(self, "<script src=\"{}\"></script>\n</div>\n</body>\n</html>\n", js_path)?;
+        self.out.flush()
+    }
+}
+
+impl Write for Page {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.out.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.out.flush()
+    }
+}
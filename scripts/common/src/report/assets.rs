@@ -0,0 +1,438 @@
+// ---------------------------------------------------------------------------
+// Shared CSS
+// ---------------------------------------------------------------------------
+
+pub(crate) const CSS: &str = r#":root {
+    --bg: #0f1117;
+    --surface: #1a1d27;
+    --surface2: #242836;
+    --border: #2e3348;
+    --text: #e2e4ed;
+    --text-dim: #8b8fa3;
+    --accent: #6c7ee1;
+    --accent-dim: #4a5699;
+    --red: #e5534b;
+    --green: #57ab5a;
+    --orange: #daaa3f;
+    --blue: #539bf5;
+}
+* { margin: 0; padding: 0; box-sizing: border-box; }
+body {
+    background: var(--bg);
+    color: var(--text);
+    font-family: 'Inter', -apple-system, BlinkMacSystemFont, 'Segoe UI', system-ui, sans-serif;
+    font-size: 14px;
+    line-height: 1.5;
+    padding: 24px;
+}
+.container { max-width: 100%; margin: 0 auto; }
+h1 {
+    font-size: 24px;
+    font-weight: 700;
+    margin-bottom: 8px;
+    color: var(--text);
+}
+.subtitle {
+    color: var(--text-dim);
+    margin-bottom: 12px;
+    font-size: 14px;
+    display: flex;
+    justify-content: space-between;
+    align-items: center;
+}
+.subtitle .meta {
+    color: var(--text-dim);
+    font-size: 13px;
+}
+
+/* Navigation */
+.nav-bar {
+    display: flex;
+    border-bottom: 2px solid var(--border);
+    margin-bottom: 24px;
+    gap: 0;
+}
+.nav-tab {
+    padding: 10px 20px;
+    color: var(--text-dim);
+    font-size: 13px;
+    font-weight: 600;
+    text-decoration: none;
+    border-bottom: 3px solid transparent;
+    margin-bottom: -2px;
+    transition: all 0.15s;
+}
+.nav-tab:hover { color: var(--text); }
+.nav-tab.active {
+    color: var(--accent);
+    border-bottom-color: var(--accent);
+}
+.nav-tab .badge {
+    background: var(--surface2);
+    color: var(--text-dim);
+    padding: 1px 7px;
+    border-radius: 10px;
+    font-size: 11px;
+    margin-left: 6px;
+}
+.nav-tab.active .badge {
+    background: var(--accent-dim);
+    color: #fff;
+}
+
+/* Stats cards */
+.stats-container {
+    display: flex;
+    justify-content: space-between;
+    gap: 24px;
+    margin-bottom: 24px;
+}
+.stats-group { display: flex; gap: 12px; flex-wrap: wrap; }
+.stat-card {
+    background: var(--surface);
+    border: 1px solid var(--border);
+    border-radius: 8px;
+    padding: 16px;
+    min-width: 140px;
+}
+.stat-card .label {
+    color: var(--text-dim);
+    font-size: 12px;
+    text-transform: uppercase;
+    letter-spacing: 0.5px;
+}
+.stat-card .value { font-size: 22px; font-weight: 700; margin-top: 4px; }
+.stat-card .value.ok { color: var(--green); }
+.stat-card .value.fail { color: var(--red); }
+.stat-card .value.warn { color: var(--orange); }
+.stat-card .value.info { color: var(--blue); }
+
+/* Tables */
+.search-box { display: flex; justify-content: flex-end; margin-bottom: 12px; }
+.search-box input {
+    background: var(--surface);
+    border: 1px solid var(--border);
+    border-radius: 6px;
+    color: var(--text);
+    padding: 6px 12px;
+    font-size: 13px;
+    width: 260px;
+    outline: none;
+}
+.search-box input:focus { border-color: var(--accent); }
+.table-wrap {
+    overflow-x: auto;
+    border: 1px solid var(--border);
+    border-radius: 8px;
+}
+table { width: 100%; border-collapse: collapse; font-size: 13px; }
+th {
+    background: var(--surface);
+    color: var(--text-dim);
+    font-weight: 600;
+    text-transform: uppercase;
+    font-size: 11px;
+    letter-spacing: 0.5px;
+    padding: 10px 12px;
+    text-align: left;
+    position: sticky;
+    top: 0;
+    border-bottom: 1px solid var(--border);
+    white-space: nowrap;
+    cursor: pointer;
+}
+th:hover { color: var(--text); }
+td {
+    padding: 8px 12px;
+    border-bottom: 1px solid var(--border);
+    white-space: nowrap;
+}
+td:first-child {
+    max-width: 600px;
+    overflow: hidden;
+    text-overflow: ellipsis;
+}
+td:not(:first-child) {
+    text-align: center;
+    min-width: 90px;
+}
+th:not(:first-child) {
+    text-align: center;
+}
+tr:hover td { background: var(--surface); }
+
+/* Icons */
+.miss { color: var(--red); font-weight: 700; font-size: 15px; }
+.warn { color: var(--orange); font-weight: 700; font-size: 15px; }
+.unknown { color: var(--orange); font-weight: 700; font-size: 15px; }
+.ok { color: var(--green); font-size: 15px; }
+.empty-state {
+    text-align: center;
+    padding: 48px;
+    color: var(--text-dim);
+    font-size: 15px;
+}
+
+/* mirror.html: both path columns left-aligned */
+.mirror td, .mirror th { text-align: left; }
+
+/* Field / value tables (provenance on index) */
+.details th { text-align: left; white-space: nowrap; width: 1%; }
+.details td { text-align: left; font-family: monospace; word-break: break-all; }
+
+/* Category breakdown on index */
+.breakdown { margin-top: 24px; }
+.breakdown h2 {
+    font-size: 16px;
+    font-weight: 600;
+    margin-bottom: 12px;
+    color: var(--text);
+}
+.breakdown td { padding: 8px 16px; }
+.breakdown a { color: var(--accent); text-decoration: none; }
+.breakdown a:hover { text-decoration: underline; }
+
+/* Trends on index: sparkline per count across runs */
+.sparkline polyline { fill: none; stroke: var(--accent); stroke-width: 1.5; }
+.trend-better { color: var(--green); }
+.trend-worse { color: var(--red); }
+.trend-note { margin-top: 8px; font-size: 12px; color: var(--text-dim); }
+
+/* Subtab bar (data pages) */
+.subtab-bar {
+    display: flex;
+    gap: 8px;
+    margin-bottom: 16px;
+    flex-wrap: wrap;
+}
+.subtab {
+    background: none;
+    border: 1px solid var(--border);
+    border-radius: 6px;
+    color: var(--text-dim);
+    padding: 6px 14px;
+    font-size: 13px;
+    font-weight: 600;
+    cursor: pointer;
+    display: inline-flex;
+    align-items: center;
+    gap: 6px;
+    font-family: inherit;
+}
+.subtab:hover { color: var(--text); border-color: var(--accent-dim); }
+.subtab.active { background: var(--accent-dim); border-color: var(--accent); color: #fff; }
+.subtab-count {
+    background: rgba(0,0,0,0.25);
+    border-radius: 10px;
+    padding: 1px 6px;
+    font-size: 11px;
+}
+.panel.hidden { display: none; }
+.artist-list { display: flex; flex-direction: column; gap: 6px; }
+.artist-group {
+    border: 1px solid var(--border);
+    border-radius: 8px;
+    overflow: hidden;
+}
+.artist-header {
+    display: flex;
+    align-items: center;
+    gap: 10px;
+    padding: 10px 14px;
+    background: var(--surface);
+    cursor: pointer;
+    user-select: none;
+}
+.artist-header:hover { background: var(--surface2); }
+.artist-name { font-weight: 600; color: var(--text); flex: 1; font-size: 13px; }
+.group-summary { color: var(--orange); font-size: 12px; }
+.file-count { color: var(--text-dim); font-size: 12px; }
+.arrow { color: var(--text-dim); font-size: 11px; display: inline-block; transition: transform 0.15s; }
+.artist-group.collapsed .arrow { transform: rotate(-90deg); }
+.file-list { list-style: none; border-top: 1px solid var(--border); }
+.artist-group.collapsed .file-list { display: none; }
+.file-item {
+    padding: 6px 14px 6px 36px;
+    border-bottom: 1px solid var(--border);
+    font-size: 12px;
+    color: var(--text-dim);
+    white-space: nowrap;
+    overflow: hidden;
+    text-overflow: ellipsis;
+}
+.file-item:last-child { border-bottom: none; }
+.file-item:hover { background: var(--surface); color: var(--text); }
+.annot { color: var(--orange); font-size: 11px; margin-left: 8px; }
+.empty-panel { text-align: center; padding: 48px; color: var(--text-dim); font-size: 15px; }
+
+/* Pagination */
+.pagination { display: flex; justify-content: center; align-items: center; gap: 4px; margin: 16px 0; }
+.pagination a, .pagination span { display: inline-flex; align-items: center; justify-content: center; min-width: 32px; height: 32px; padding: 4px 8px; border-radius: 6px; text-decoration: none; font-size: 13px; font-weight: 600; color: var(--text-dim); border: 1px solid var(--border); }
+.pagination a:hover { background: var(--surface2); color: var(--text); border-color: var(--accent-dim); }
+.pagination .active { background: var(--accent-dim); border-color: var(--accent); color: #fff; }
+.pagination .disabled { opacity: 0.3; pointer-events: none; border-color: transparent; }
+
+/* Autofix: matched file styling */
+.file-item { position: relative; }
+.file-item.matched { text-decoration: line-through; opacity: 0.5; }
+.file-item.matched:hover { opacity: 0.8; }
+.match-check { color: var(--green); font-size: 13px; margin-left: 8px; }
+.match-delta { color: var(--green); font-size: 10px; margin-left: 4px; }
+.match-popover { display:none; position:fixed; z-index:1000; background:var(--surface2); border:1px solid var(--border); border-radius:8px; padding:12px 16px; font-size:12px; line-height:1.6; max-width:500px; white-space:normal; box-shadow:0 4px 12px rgba(0,0,0,0.4); pointer-events:none; }
+.pop-title { font-weight:600; color:var(--text); margin-bottom:6px; }
+.pop-old { text-decoration:line-through; color:var(--red); }
+.pop-new { color:var(--green); }
+
+/* Handled marking */
+.handled-box { position: absolute; left: 12px; top: 50%; transform: translateY(-50%); margin: 0; cursor: pointer; accent-color: var(--accent); }
+td .handled-box { position: static; transform: none; margin-right: 8px; vertical-align: middle; }
+.file-item.handled, tr.handled td { text-decoration: line-through; opacity: 0.4; }
+.handled-export { margin-left: auto; align-self: center; background: var(--surface2); color: var(--text-dim); border: 1px solid var(--border); border-radius: 6px; padding: 4px 12px; font-size: 12px; font-weight: 600; cursor: pointer; }
+.handled-export:hover { color: var(--text); border-color: var(--accent-dim); }
+.pop-arrow { color:var(--text-dim); margin:0 6px; }"#;
+
+// ---------------------------------------------------------------------------
+// Shared JS
+// ---------------------------------------------------------------------------
+
+pub(crate) const JS: &str = r#"/* autofix: popover show/hide */
+function showMatchInfo(el) { var p=el.parentElement.querySelector('.match-popover'); if(!p) return; var r=el.getBoundingClientRect(); p.style.left=r.left+'px'; p.style.top=(r.bottom+6)+'px'; p.style.display='block'; }
+function hideMatchInfo(el) { var p=el.parentElement.querySelector('.match-popover'); if(p) p.style.display='none'; }
+/* the page an element is on: its section in a single-file report, else the document */
+function pageOf(el) {
+    return el.closest('.report-page') || document;
+}
+/* issues.html: flat table search */
+function filterTable(input) {
+    var filter = input.value.toLowerCase();
+    var rows = pageOf(input).querySelectorAll('table tbody tr');
+    for (var i = 0; i < rows.length; i++) {
+        var row = rows[i];
+        if (row.querySelector('.empty-state')) continue;
+        row.style.display = row.textContent.toLowerCase().indexOf(filter) !== -1 ? '' : 'none';
+    }
+}
+/* data pages: subtab switching */
+function switchSubtab(btn) {
+    var tabs = btn.parentNode.querySelectorAll('.subtab');
+    for (var i = 0; i < tabs.length; i++) tabs[i].classList.remove('active');
+    btn.classList.add('active');
+    var panels = pageOf(btn).querySelectorAll('.panel');
+    var target = btn.dataset.panel;
+    for (var i = 0; i < panels.length; i++) {
+        panels[i].classList.toggle('hidden', panels[i].id !== target);
+    }
+}
+/* data pages: collapse/expand artist group */
+function toggleArtist(header) {
+    header.parentNode.classList.toggle('collapsed');
+}
+/* data pages: filter within active panel */
+function filterGroups(input) {
+    var filter = input.value.toLowerCase().trim();
+    var panel = pageOf(input).querySelector('.panel:not(.hidden)');
+    if (!panel) return;
+    var groups = panel.querySelectorAll('.artist-group');
+    for (var i = 0; i < groups.length; i++) {
+        var group = groups[i];
+        var nameEl = group.querySelector('.artist-name');
+        var artistMatch = filter === '' || (nameEl && nameEl.textContent.toLowerCase().indexOf(filter) !== -1);
+        var items = group.querySelectorAll('.file-item');
+        var visible = 0;
+        for (var j = 0; j < items.length; j++) {
+            var show = filter === '' || artistMatch || items[j].textContent.toLowerCase().indexOf(filter) !== -1;
+            items[j].style.display = show ? '' : 'none';
+            if (show) visible++;
+        }
+        group.style.display = (filter === '' || visible > 0) ? '' : 'none';
+        if (filter !== '' && visible > 0) group.classList.remove('collapsed');
+    }
+}
+/* handled marking: ticks persist in localStorage, export feeds --apply-handled */
+var HANDLED_KEY = 'analysis-handled';
+function loadHandled() {
+    try { return JSON.parse(localStorage.getItem(HANDLED_KEY)) || {}; } catch (e) { return {}; }
+}
+function updateHandledCount(handled) {
+    var n = Object.keys(handled).length;
+    var els = document.querySelectorAll('.handled-count');
+    for (var i = 0; i < els.length; i++) els[i].textContent = n;
+}
+function toggleHandled(box) {
+    var item = box.closest('[data-path]');
+    var handled = loadHandled();
+    if (box.checked) handled[item.dataset.path] = new Date().toISOString();
+    else delete handled[item.dataset.path];
+    item.classList.toggle('handled', box.checked);
+    localStorage.setItem(HANDLED_KEY, JSON.stringify(handled));
+    updateHandledCount(handled);
+}
+function exportHandled() {
+    var data = JSON.stringify({ handled: Object.keys(loadHandled()).sort() }, null, 2);
+    var a = document.createElement('a');
+    a.href = URL.createObjectURL(new Blob([data], { type: 'application/json' }));
+    a.download = 'handled.json';
+    document.body.appendChild(a);
+    a.click();
+    document.body.removeChild(a);
+    setTimeout(function() { URL.revokeObjectURL(a.href); }, 0);
+}
+document.addEventListener('DOMContentLoaded', function() {
+    var handled = loadHandled();
+    var items = document.querySelectorAll('[data-path]');
+    for (var i = 0; i < items.length; i++) {
+        if (!handled[items[i].dataset.path]) continue;
+        items[i].classList.add('handled');
+        var box = items[i].querySelector('.handled-box');
+        if (box) box.checked = true;
+    }
+    updateHandledCount(handled);
+});
+/* issues.html: sortable columns */
+document.addEventListener('DOMContentLoaded', function() {
+    var headers = document.querySelectorAll('th[data-sort]');
+    for (var h = 0; h < headers.length; h++) {
+        (function(th) {
+            th.addEventListener('click', function() {
+                var table = th.closest('table');
+                var tbody = table.querySelector('tbody');
+                var rows = Array.prototype.slice.call(tbody.querySelectorAll('tr'));
+                var idx = parseInt(th.dataset.sort);
+                var asc = th.dataset.dir !== 'asc';
+                th.dataset.dir = asc ? 'asc' : 'desc';
+                var allTh = th.closest('thead').querySelectorAll('th');
+                for (var i = 0; i < allTh.length; i++) {
+                    if (allTh[i] !== th) delete allTh[i].dataset.dir;
+                }
+                rows.sort(function(a, b) {
+                    var av = (a.cells[idx] && a.cells[idx].textContent.trim()) || '';
+                    var bv = (b.cells[idx] && b.cells[idx].textContent.trim()) || '';
+                    return asc ? av.localeCompare(bv) : bv.localeCompare(av);
+                });
+                for (var i = 0; i < rows.length; i++) tbody.appendChild(rows[i]);
+            });
+        })(headers[h]);
+    }
+});
+"#;
+
+// ---------------------------------------------------------------------------
+// Single file (Report::write_single_file)
+// ---------------------------------------------------------------------------
+
+pub(crate) const SINGLE_FILE_CSS: &str = r#"/* single file: one page section visible at a time */
+.report-page { display: none; }
+.report-page.active { display: block; }"#;
+
+pub(crate) const SINGLE_FILE_JS: &str = r#"/* single file: show the page section named by the URL hash (the first one otherwise) */
+function showReportPage() {
+    var pages = document.querySelectorAll('.report-page');
+    if (!pages.length) return;
+    var current = document.getElementById(location.hash.slice(1));
+    if (!current || !current.classList.contains('report-page')) current = pages[0];
+    for (var i = 0; i < pages.length; i++) pages[i].classList.toggle('active', pages[i] === current);
+    window.scrollTo(0, 0);
+}
+window.addEventListener('hashchange', showReportPage);
+document.addEventListener('DOMContentLoaded', showReportPage);
+"#;
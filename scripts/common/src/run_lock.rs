@@ -0,0 +1,211 @@
+// ---------------------------------------------------------------------------
+// Run lock (--wait / --steal-lock)
+// ---------------------------------------------------------------------------
+
+//! One Postgres advisory lock shared by index, sync, clean and nuke, so two
+//! overlapping runs (cron + manual, or index + nuke) never write at once.
+//!
+//! The lock is session-level and lives on a dedicated connection owned by
+//! [`RunLock`]: it is released when the value is dropped, when the process
+//! exits, or when the connection dies, so a crashed run never leaves it behind.
+//! A hung run still holds it; `--steal-lock` terminates that session.
+//!
+//! Sync workers (`sync --worker`) take it in shared mode instead: any number of
+//! them run side by side, and they still keep every exclusive run out.
+
+use sqlx::postgres::PgConnectOptions;
+use sqlx::{ConnectOptions, Connection, PgConnection, Row};
+use std::fmt;
+use std::str::FromStr;
+
+/// First half of the two-key advisory lock ("DMP" in ASCII).
+const LOCK_CLASS: i32 = 0x0044_4D50;
+/// Second half of the two-key advisory lock.
+const LOCK_ID: i32 = 6;
+
+/// What to do when another script already holds the lock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockMode {
+    /// Give up with [`LockError::Held`] (the default)
+    Fail,
+    /// Block until the other run finishes (`--wait`)
+    Wait,
+    /// Terminate the holder's lock session and take over (`--steal-lock`)
+    Steal,
+}
+
+impl LockMode {
+    /// Maps the `--wait` / `--steal-lock` flags; stealing wins when both are set.
+    pub fn from_flags(wait: bool, steal: bool) -> Self {
+        if steal {
+            LockMode::Steal
+        } else if wait {
+            LockMode::Wait
+        } else {
+            LockMode::Fail
+        }
+    }
+}
+
+/// The session currently holding the lock, as reported by `pg_stat_activity`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Holder {
+    /// Script name the holder connected with ("index", "sync", ...)
+    pub tool: String,
+    /// Postgres backend pid of the holder's lock connection
+    pub pid: i32,
+    /// When the holder's lock connection was opened (`YYYY-MM-DD HH:MM`, server time)
+    pub since: String,
+}
+
+impl fmt::Display for Holder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tool = if self.tool.is_empty() { "unknown script" } else { &self.tool };
+        write!(f, "{}", tool)?;
+        if self.pid != 0 {
+            write!(f, " (backend pid {}", self.pid)?;
+            if !self.since.is_empty() {
+                write!(f, ", since {}", self.since)?;
+            }
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum LockError {
+    /// Another run holds the lock and the mode was [`LockMode::Fail`]
+    Held(Holder),
+    Db(sqlx::Error),
+}
+
+impl fmt::Display for LockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LockError::Held(holder) => write!(
+                f,
+                "Another DMP script is running: {}. Use --wait to queue behind it or --steal-lock if it is hung.",
+                holder
+            ),
+            LockError::Db(e) => write!(f, "Could not take the run lock: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for LockError {}
+
+impl From<sqlx::Error> for LockError {
+    fn from(e: sqlx::Error) -> Self {
+        LockError::Db(e)
+    }
+}
+
+/// Holds the run lock until dropped.
+pub struct RunLock {
+    _conn: PgConnection,
+}
+
+impl RunLock {
+    /// Opens a dedicated connection (tagged with `tool` as its application name)
+    /// and takes the lock according to `mode`. Prints one line when it has to
+    /// wait or steal.
+    pub async fn acquire(database_url: &str, tool: &str, mode: LockMode) -> Result<RunLock, LockError> {
+        Self::take(database_url, tool, mode, false).await
+    }
+
+    /// Like [`RunLock::acquire`], but shares the lock with other shared holders;
+    /// only exclusive holders block it (and are what `--steal-lock` terminates).
+    pub async fn acquire_shared(database_url: &str, tool: &str, mode: LockMode) -> Result<RunLock, LockError> {
+        Self::take(database_url, tool, mode, true).await
+    }
+
+    async fn take(database_url: &str, tool: &str, mode: LockMode, shared: bool) -> Result<RunLock, LockError> {
+        let options = PgConnectOptions::from_str(database_url)?.application_name(tool);
+        let mut conn = options.connect().await?;
+
+        // The holders can finish between the failed try and the lookup, so no
+        // holders just means "try again".
+        let mut holders = Vec::new();
+        for _ in 0..3 {
+            if try_lock(&mut conn, shared).await? {
+                return Ok(RunLock { _conn: conn });
+            }
+            holders = find_holders(&mut conn, shared).await?;
+            if !holders.is_empty() {
+                break;
+            }
+        }
+        let holder = holders.first().cloned().unwrap_or(Holder {
+            tool: String::new(),
+            pid: 0,
+            since: String::new(),
+        });
+
+        match mode {
+            LockMode::Fail => {
+                conn.close().await.ok();
+                return Err(LockError::Held(holder));
+            }
+            LockMode::Wait => println!("Waiting for {} to finish...", holder),
+            LockMode::Steal => {
+                println!("Stealing the run lock from {}", holder);
+                // Shared holders (sync workers) all have to go
+                for pid in holders.iter().map(|h| h.pid).filter(|&pid| pid != 0) {
+                    sqlx::query("SELECT pg_terminate_backend($1)")
+                        .bind(pid)
+                        .execute(&mut conn)
+                        .await?;
+                }
+            }
+        }
+
+        // Blocks until the holders end (or their terminated sessions are gone)
+        let lock = if shared { "pg_advisory_lock_shared" } else { "pg_advisory_lock" };
+        sqlx::query(&format!("SELECT {}($1, $2)", lock))
+            .bind(LOCK_CLASS)
+            .bind(LOCK_ID)
+            .execute(&mut conn)
+            .await?;
+        Ok(RunLock { _conn: conn })
+    }
+}
+
+async fn try_lock(conn: &mut PgConnection, shared: bool) -> Result<bool, sqlx::Error> {
+    let lock = if shared { "pg_try_advisory_lock_shared" } else { "pg_try_advisory_lock" };
+    sqlx::query_scalar(&format!("SELECT {}($1, $2)", lock))
+        .bind(LOCK_CLASS)
+        .bind(LOCK_ID)
+        .fetch_one(conn)
+        .await
+}
+
+/// Sessions holding the lock, oldest first. A shared request is only blocked by
+/// an exclusive holder, so `exclusive_only` leaves the other workers out.
+async fn find_holders(conn: &mut PgConnection, exclusive_only: bool) -> Result<Vec<Holder>, sqlx::Error> {
+    // Two-key advisory locks show up with objsubid = 2
+    let rows = sqlx::query(
+        r#"SELECT a.pid, COALESCE(a.application_name, '') AS tool,
+                  COALESCE(to_char(a.backend_start, 'YYYY-MM-DD HH24:MI'), '') AS since
+           FROM pg_locks l
+           JOIN pg_stat_activity a ON a.pid = l.pid
+           WHERE l.locktype = 'advisory' AND l.granted
+             AND l.classid::bigint = $1 AND l.objid::bigint = $2 AND l.objsubid = 2
+             AND (NOT $3 OR l.mode = 'ExclusiveLock')
+           ORDER BY a.backend_start"#,
+    )
+    .bind(LOCK_CLASS as i64)
+    .bind(LOCK_ID as i64)
+    .bind(exclusive_only)
+    .fetch_all(conn)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| Holder {
+            tool: r.get("tool"),
+            pid: r.get("pid"),
+            since: r.get("since"),
+        })
+        .collect())
+}
@@ -0,0 +1,49 @@
+// ---------------------------------------------------------------------------
+// Statistics history
+// ---------------------------------------------------------------------------
+
+//! `StatisticsHistory`: one row per index or sync run, a snapshot of the
+//! `Statistics` row plus coverage percentages, for the dashboard's charts.
+//! Both scripts write it through here so the coverage formulas stay the same.
+
+use sqlx::PgExecutor;
+
+/// Append the current Statistics row, with coverage percentages, to
+/// StatisticsHistory. `source` is the script that ran ("index" or "sync");
+/// `artists_synced` is how many artists it synced (fully or partially, 0
+/// for index runs).
+pub async fn record_statistics_history<'e>(
+    executor: impl PgExecutor<'e>,
+    source: &str,
+    artists_synced: i32,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"INSERT INTO "StatisticsHistory" (
+             id, source, "artistsSynced",
+             artists, tracks, releases, genres, playtime,
+             "artistsSyncedWithMusicbrainz", "releasesSyncedWithMusicbrainz",
+             "artistsWithCoverArt", "releasesWithCoverArt",
+             "artistMusicbrainzCoverage", "releaseMusicbrainzCoverage",
+             "artistCoverArtCoverage", "releaseCoverArtCoverage"
+           )
+           SELECT $1, $2, $3,
+                  s.artists, s.tracks, s.releases, s.genres, s.playtime,
+                  s."artistsSyncedWithMusicbrainz", s."releasesSyncedWithMusicbrainz",
+                  s."artistsWithCoverArt", s."releasesWithCoverArt",
+                  a.mb_pct, r.mb_pct, a.art_pct, r.art_pct
+           FROM "Statistics" s,
+                (SELECT (100.0 * COUNT(*) FILTER (WHERE "musicbrainzId" IS NOT NULL) / NULLIF(COUNT(*), 0))::float8 AS mb_pct,
+                        (100.0 * COUNT(*) FILTER (WHERE image IS NOT NULL AND NOT "imagePlaceholder") / NULLIF(COUNT(*), 0))::float8 AS art_pct
+                 FROM "Artist") a,
+                (SELECT (100.0 * COUNT(*) FILTER (WHERE "releaseId" IS NOT NULL) / NULLIF(COUNT(*), 0))::float8 AS mb_pct,
+                        (100.0 * COUNT(*) FILTER (WHERE image IS NOT NULL) / NULLIF(COUNT(*), 0))::float8 AS art_pct
+                 FROM "LocalRelease") r
+           WHERE s.id = 'main'"#,
+    )
+    .bind(cuid2::create_id())
+    .bind(source)
+    .bind(artists_synced)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
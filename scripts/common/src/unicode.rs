@@ -0,0 +1,34 @@
+// ---------------------------------------------------------------------------
+// Unicode normalization
+// ---------------------------------------------------------------------------
+
+//! NFC and case folding for tag text, and `Artist.slug`, shared by the
+//! indexer and sync so an artist created by either gets the same row.
+
+use slug::slugify;
+use unicode_normalization::UnicodeNormalization;
+
+/// Compose a tag string to NFC. macOS (and some taggers) write "é" as "e" plus
+/// a combining accent (NFD); both forms must end up as the same text in the DB.
+pub fn nfc(s: &str) -> String {
+    s.nfc().collect()
+}
+
+/// NFC plus Unicode case folding, for comparing names. `to_lowercase` alone
+/// leaves "ß"/"SS" and final/medial sigma apart.
+pub fn fold_case(s: &str) -> String {
+    let mut folded = String::with_capacity(s.len());
+    for c in s.nfc().flat_map(char::to_lowercase) {
+        match c {
+            'ß' | 'ẞ' => folded.push_str("ss"),
+            'ς' => folded.push('σ'),
+            _ => folded.push(c),
+        }
+    }
+    folded
+}
+
+/// `Artist.slug` for a name. Every artist lookup goes through this.
+pub fn artist_slug(name: &str) -> String {
+    slugify(fold_case(name))
+}
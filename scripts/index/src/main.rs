@@ -4,6 +4,14 @@ use aws_sdk_s3::Client as S3Client;
 use chrono::{NaiveDateTime, Utc};
 use clap::Parser;
 use colored::*;
+use dmp_common::artist_merge::merge_artist;
+use dmp_common::checkpoint::checkpoint_age;
+use dmp_common::progress::{Progress, ProgressMode};
+use dmp_common::run_lock::{LockMode, RunLock};
+use dmp_common::statistics_history::record_statistics_history;
+use dmp_common::unicode::{artist_slug, fold_case, nfc};
+use dmp_common::FolderFilter;
+use futures_util::stream::{self, StreamExt};
 use lofty::config::ParseOptions;
 use lofty::prelude::*;
 use lofty::probe::Probe;
@@ -13,14 +21,16 @@ use regex::Regex;
 use serde_json::Value as JsonValue;
 use slug::slugify;
 use sqlx::postgres::PgPoolOptions;
-use sqlx::{PgPool, Row};
-use std::collections::HashMap;
+use sqlx::{PgConnection, PgPool, Row};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs;
 use std::io::Write as IoWrite;
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
+use unicode_normalization::UnicodeNormalization;
 use walkdir::WalkDir;
 
 // ---------------------------------------------------------------------------
@@ -28,7 +38,7 @@ use walkdir::WalkDir;
 // ---------------------------------------------------------------------------
 
 #[derive(Parser, Debug)]
-#[command(name = "dmp-index", about = "Index local audio files into the DMP database")]
+#[command(name = "dmp-index", version = dmp_common::version!(), long_version = dmp_common::long_version!(), about = "Index local audio files into the DMP database")]
 struct Args {
     /// Override MUSIC_DIR from .env
     #[arg()]
@@ -54,17 +64,125 @@ struct Args {
     #[arg(long)]
     resume: bool,
 
+    /// Index only the audio files listed in FILE, one path per line (absolute or relative to the music dir; "-" reads stdin), instead of walking the music dir
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["resume", "overwrite"])]
+    paths_from: Option<String>,
+
+    /// Index even if sync has a checkpoint (a sync is in progress or was interrupted)
+    #[arg(long)]
+    force: bool,
+
+    /// Wait for another DMP script's run to finish instead of exiting
+    #[arg(long, conflicts_with = "steal_lock")]
+    wait: bool,
+
+    /// Take the run lock from another DMP script (ends its lock session; for hung runs)
+    #[arg(long)]
+    steal_lock: bool,
+
     /// Skip cover art extraction
     #[arg(long)]
     skip_images: bool,
 
+    /// Release cover sources in order: embedded (picture tags), sidecar (cover.jpg / folder.jpg next to the tracks)
+    #[arg(long, value_delimiter = ',', default_value = "embedded,sidecar", value_parser = ["embedded", "sidecar"])]
+    art_priority: Vec<String>,
+
     /// Number of parallel workers (default: all cores)
     #[arg(long, default_value = "0")]
     threads: usize,
 
+    /// Artist folders written to the database at once (1 = one folder at a time)
+    #[arg(long, default_value = "4", value_parser = clap::value_parser!(u32).range(1..=8))]
+    write_tasks: u32,
+
     /// Limit to first N files (0 = no limit)
     #[arg(long, default_value = "0")]
     limit: usize,
+
+    /// Fill missing release years from the linked MusicBrainz release (marked as backfilled)
+    #[arg(long)]
+    backfill_years: bool,
+
+    /// Fill missing release years from the earliest file mtime (marked as inferred)
+    #[arg(long)]
+    infer_year_from_mtime: bool,
+
+    /// Treat paths differing only in case as distinct files when checking for collisions
+    #[arg(long)]
+    case_sensitive_paths: bool,
+
+    /// Extra tag keys to leave out of the metadata JSON, comma-separated (case insensitive)
+    #[arg(long, value_delimiter = ',')]
+    metadata_exclude: Vec<String>,
+
+    /// Truncate metadata JSON values longer than N bytes (0 = no limit)
+    #[arg(long, default_value = "4096")]
+    metadata_max_value: usize,
+
+    /// Don't store the metadata JSON at all
+    #[arg(long, visible_alias = "no-json")]
+    no_metadata_json: bool,
+
+    /// Don't compute content hashes (changed files are always re-written; a later run fills them in)
+    #[arg(long)]
+    no_hash: bool,
+
+    /// Don't read audio properties: duration, bitrate, sample rate (a later run fills them in)
+    #[arg(long)]
+    no_properties: bool,
+
+    /// First bulk import: shorthand for --no-hash --no-properties --no-json
+    #[arg(long)]
+    fast: bool,
+
+    /// Generate initials placeholders for artists that have no image
+    #[arg(long)]
+    placeholders: bool,
+
+    /// Characters GENRE values are split on (NUL always splits; "" keeps each value whole)
+    #[arg(long, value_name = "CHARS", default_value = ";|")]
+    genre_separators: String,
+
+    /// Store embedded lyrics (LYRICS / USLT) in TrackLyrics instead of the metadata JSON
+    #[arg(long)]
+    extract_lyrics: bool,
+
+    /// Find tracks stored in two codecs (e.g. FLAC + MP3) and list the copies to delete in codec-dupes.txt
+    #[arg(long)]
+    find_codec_dupes: bool,
+
+    /// With --find-codec-dupes: which copy to keep
+    #[arg(long, default_value = "lossless", value_parser = ["lossless", "lossy"], requires = "find_codec_dupes")]
+    prefer: String,
+
+    /// Merge artists and releases duplicated by differently normalized tags (NFC/NFD), then exit
+    #[arg(long)]
+    repair_unicode: bool,
+
+    /// After indexing, write an HTML QC report (per-artist counts, files without an artist tag, cover failures, errors) under DIR
+    #[arg(long, value_name = "DIR", num_args = 0..=1, default_missing_value = "reports")]
+    qc_report: Option<String>,
+
+    /// After post-processing, ANALYZE the tables index writes to and print their row counts before and after the run
+    #[arg(long)]
+    maintenance: bool,
+
+    /// Index even if the music dir has no artist folders (normally an unmounted drive)
+    #[arg(long)]
+    allow_empty_root: bool,
+
+    /// Index even if the music dir's folders don't match the library indexed last time, and remember the new one
+    #[arg(long)]
+    accept_new_root: bool,
+
+    /// Hide the progress bars (phase headers and summaries are still printed)
+    #[arg(long)]
+    quiet: bool,
+
+    /// Print without ANSI colors (also honours the NO_COLOR environment variable)
+    #[arg(long)]
+    no_color: bool,
 }
 
 // ---------------------------------------------------------------------------
@@ -81,16 +199,129 @@ struct TrackMeta {
     album_artist: Option<String>,
     album: Option<String>,
     year: Option<i32>,
+    /// Year of the original release (ORIGINALYEAR / ORIGINALDATE / TDOR), for
+    /// remasters and reissues whose YEAR is the edition's
+    original_year: Option<i32>,
+    /// The first of `genres`
     genre: Option<String>,
+    /// GENRE split on the separators, in tag order -> TrackGenre rows
+    genres: Vec<String>,
     track_number: Option<i32>,
     disc_number: Option<i32>,
     duration: Option<i32>,
     bitrate: Option<i32>,
     sample_rate: Option<i32>,
     position: Option<String>,
-    content_hash: String,
-    metadata_json: JsonValue,
+    /// None with --no-hash
+    content_hash: Option<String>,
+    metadata_json: Option<JsonValue>,
     has_picture: bool,
+    /// First embedded picture, when the PictureCache kept it
+    picture: Option<Arc<[u8]>>,
+    /// Credit tags (COMPOSER, REMIXER, CONDUCTOR) -> TrackArtist roles
+    credits: Vec<(&'static str, String)>,
+    /// (language, lyrics) per language, only with --extract-lyrics
+    lyrics: Vec<(String, String)>,
+    /// Offsets within the rip for cue tracks, whose file_path is `<rip>#NN`
+    cue: Option<CueSpan>,
+}
+
+impl TrackMeta {
+    /// The audio file relative to the music dir; cue tracks share their rip's.
+    fn source_path(&self) -> &str {
+        match self.cue {
+            Some(_) => self.file_path.rsplit_once('#').map_or(&self.file_path, |(rip, _)| rip),
+            None => &self.file_path,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Metadata JSON options
+// ---------------------------------------------------------------------------
+
+/// Tags that have their own columns and are never copied into the metadata JSON.
+const METADATA_COLUMN_KEYS: &[&str] = &[
+    "ARTIST", "TITLE", "ALBUM", "YEAR", "DATE", "GENRE",
+    "TRACKNUMBER", "TRACK", "DISCNUMBER", "DISC", "ALBUMARTIST",
+    "ALBUM_ARTIST", "ALBUM ARTIST",
+];
+
+/// Lyrics tags: Vorbis/APE LYRICS, ID3v2 USLT and MP4 ©lyr all read as "LYRICS";
+/// foobar2000 writes UNSYNCEDLYRICS.
+const LYRICS_KEYS: &[&str] = &["LYRICS", "UNSYNCEDLYRICS", "UNSYNCED LYRICS"];
+
+/// Original release date tags, by preference: lofty reads Vorbis ORIGINALDATE /
+/// ORIGINALYEAR and ID3v2 TDOR as "ORIGINALRELEASEDATE"; ID3v2 TXXX frames keep
+/// their own names.
+const ORIGINAL_YEAR_KEYS: &[&str] = &["ORIGINALYEAR", "ORIGINALRELEASEDATE", "ORIGINALDATE"];
+
+/// `TrackLyrics.language` when the tag has none (ISO 639-2 "undetermined").
+const LYRICS_NO_LANGUAGE: &str = "und";
+
+/// What goes into `LocalReleaseTrack.metadata`.
+struct MetadataOptions {
+    enabled: bool,
+    /// Lyrics go to `TrackLyrics` (--extract-lyrics) and are left out of the JSON
+    lyrics: bool,
+    /// Uppercased keys to skip, on top of `METADATA_COLUMN_KEYS`
+    excluded: Vec<String>,
+    /// Values longer than this many bytes are truncated (0 = no limit)
+    max_value_len: usize,
+    /// --genre-separators
+    genre_separators: Vec<char>,
+}
+
+impl MetadataOptions {
+    fn from_args(args: &Args) -> Self {
+        Self {
+            enabled: !args.no_metadata_json && !args.fast,
+            lyrics: args.extract_lyrics,
+            excluded: args.metadata_exclude.iter().map(|k| k.trim().to_uppercase()).collect(),
+            max_value_len: args.metadata_max_value,
+            genre_separators: args.genre_separators.chars().collect(),
+        }
+    }
+
+    fn is_excluded(&self, key_upper: &str) -> bool {
+        METADATA_COLUMN_KEYS.contains(&key_upper)
+            || (self.lyrics && LYRICS_KEYS.contains(&key_upper))
+            || self.excluded.iter().any(|k| k == key_upper)
+    }
+
+    /// Cut `value` to the size cap on a char boundary, noting how much was dropped.
+    fn cap(&self, value: &str) -> String {
+        if self.max_value_len == 0 || value.len() <= self.max_value_len {
+            return value.to_string();
+        }
+        let mut end = self.max_value_len;
+        while !value.is_char_boundary(end) {
+            end -= 1;
+        }
+        format!("{}… [truncated {} bytes]", &value[..end], value.len() - end)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Fast import options
+// ---------------------------------------------------------------------------
+
+/// Work left out of extraction to speed up a first bulk import. Skipped
+/// columns stay NULL, and the next run without the flag fills them in for
+/// files it would otherwise skip as unchanged.
+#[derive(Clone, Copy)]
+struct FastOptions {
+    hash: bool,
+    properties: bool,
+}
+
+impl FastOptions {
+    fn from_args(args: &Args) -> Self {
+        Self {
+            hash: !args.no_hash && !args.fast,
+            properties: !args.no_properties && !args.fast,
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -190,26 +421,52 @@ fn load_config(music_dir_override: &Option<String>) -> Config {
 
 /// Strip characters that PostgreSQL JSON rejects: null bytes and C0/C1 control characters.
 /// serde_json serialises \0 as \u0000 which Postgres refuses in jsonb columns.
+/// The result is NFC, like every other tag string.
 fn sanitize_tag(s: &str) -> String {
-    s.chars()
+    s.nfc()
         .filter(|&c| c != '\0' && !('\x01'..='\x1F').contains(&c) && !('\u{007F}'..='\u{009F}').contains(&c))
         .collect()
 }
 
-fn extract_metadata(path: &Path, music_dir: &str) -> Option<TrackMeta> {
+/// Split GENRE values ("Rock; Indie; Shoegaze") into genres, in tag order.
+/// NUL (ID3v2.4 multi-value frames) always splits; genres differing only in
+/// case are kept once.
+fn split_genres<'a>(values: impl IntoIterator<Item = &'a str>, separators: &[char]) -> Vec<String> {
+    let mut genres: Vec<String> = Vec::new();
+    let mut seen = HashSet::new();
+    for value in values {
+        for piece in value.split(|c: char| c == '\0' || separators.contains(&c)) {
+            let name = sanitize_tag(piece).trim().to_string();
+            if !name.is_empty() && seen.insert(fold_case(&name)) {
+                genres.push(name);
+            }
+        }
+    }
+    genres
+}
+
+/// ID3v2 USLT language code, lowercased; anything else (including lofty's
+/// "XXX" placeholder for tags without one) is `LYRICS_NO_LANGUAGE`.
+fn lyrics_language(lang: &[u8; 3]) -> String {
+    if lang.iter().all(|b| b.is_ascii_alphabetic()) && !lang.eq_ignore_ascii_case(b"xxx") {
+        String::from_utf8_lossy(lang).to_lowercase()
+    } else {
+        LYRICS_NO_LANGUAGE.to_string()
+    }
+}
+
+fn extract_metadata(
+    path: &Path,
+    music_dir: &str,
+    metadata_opts: &MetadataOptions,
+    fast: FastOptions,
+    pictures: Option<&PictureCache>,
+) -> Option<TrackMeta> {
     let meta = fs::metadata(path).ok()?;
     let file_size = meta.len() as i64;
-    let mtime = meta
-        .modified()
-        .ok()
-        .and_then(|t| {
-            let duration = t.duration_since(std::time::UNIX_EPOCH).ok()?;
-            chrono::DateTime::from_timestamp(duration.as_secs() as i64, 0)
-                .map(|dt| dt.naive_utc())
-        })
-        .unwrap_or_else(|| Utc::now().naive_utc());
+    let mtime = file_mtime(&meta);
 
-    let parse_opts = ParseOptions::new().read_properties(true);
+    let parse_opts = ParseOptions::new().read_properties(fast.properties);
     let tagged_file = Probe::open(path).ok()?.options(parse_opts).read().ok()?;
 
     // Collect standard tags
@@ -218,30 +475,38 @@ fn extract_metadata(path: &Path, music_dir: &str) -> Option<TrackMeta> {
     let mut album_artist: Option<String> = None;
     let mut album: Option<String> = None;
     let mut year: Option<i32> = None;
-    let mut genre: Option<String> = None;
+    // (index in ORIGINAL_YEAR_KEYS, year)
+    let mut original_year: Option<(usize, i32)> = None;
+    let mut genres: Vec<String> = Vec::new();
     let mut track_number: Option<i32> = None;
     let mut disc_number: Option<i32> = None;
     let mut position: Option<String> = None;
+    let mut credits: Vec<(&'static str, String)> = Vec::new();
+    let mut lyrics: Vec<(String, String)> = Vec::new();
     let mut all_tags: HashMap<String, String> = HashMap::new();
     let mut has_picture = false;
+    let mut picture: Option<Arc<[u8]>> = None;
 
     for tag in tagged_file.tags() {
         if title.is_none() {
-            title = tag.title().map(|s| s.to_string());
+            title = tag.title().map(|s| nfc(&s));
         }
         if artist.is_none() {
-            artist = tag.artist().map(|s| s.to_string());
+            artist = tag.artist().map(|s| nfc(&s));
         }
         if album.is_none() {
-            album = tag.album().map(|s| s.to_string());
+            album = tag.album().map(|s| nfc(&s));
         }
         if year.is_none() {
             year = tag.year().and_then(|y| i32::try_from(y).ok());
         }
-        if genre.is_none() {
-            genre = tag.genre().map(|s| s.to_string());
+        if genres.is_empty() {
+            genres = split_genres(tag.get_strings(&lofty::tag::ItemKey::Genre), &metadata_opts.genre_separators);
         }
-        if !tag.pictures().is_empty() {
+        if let Some(pic) = tag.pictures().first() {
+            if !has_picture {
+                picture = pictures.and_then(|cache| cache.keep(pic.data()));
+            }
             has_picture = true;
         }
 
@@ -277,48 +542,57 @@ fn extract_metadata(path: &Path, music_dir: &str) -> Option<TrackMeta> {
                 if position.is_none() && key_upper == "POSITION" {
                     position = Some(val.clone());
                 }
+                if let Some(rank) = ORIGINAL_YEAR_KEYS.iter().position(|k| *k == key_upper) {
+                    if original_year.is_none_or(|(best, _)| rank < best) {
+                        if let Some(y) = leading_year(&val) {
+                            original_year = Some((rank, y));
+                        }
+                    }
+                }
+                if let Some(role) = credit_role(&key_upper) {
+                    if !val.trim().is_empty() && !credits.iter().any(|(r, _)| *r == role) {
+                        credits.push((role, val.clone()));
+                    }
+                }
+                if metadata_opts.lyrics && LYRICS_KEYS.contains(&key_upper.as_str()) {
+                    // Keep line breaks, which sanitize_tag strips
+                    let text = nfc(raw_val.replace('\0', "").replace("\r\n", "\n").trim());
+                    let language = lyrics_language(item.lang());
+                    if !text.is_empty() && !lyrics.iter().any(|(l, _)| *l == language) {
+                        lyrics.push((language, text));
+                    }
+                }
 
                 all_tags.insert(key, val.clone());
             }
         }
     }
 
-    // Properties (duration, bitrate, sample rate)
-    let props = tagged_file.properties();
-    let duration = Some(props.duration().as_secs() as i32);
-    let bitrate = props.audio_bitrate().map(|b| b as i32);
-    let sample_rate = props.sample_rate().map(|s| s as i32);
+    // Properties (duration, bitrate, sample rate); all unset with --no-properties
+    let (duration, bitrate, sample_rate) = if fast.properties {
+        let props = tagged_file.properties();
+        (
+            Some(props.duration().as_secs() as i32),
+            props.audio_bitrate().map(|b| b as i32),
+            props.sample_rate().map(|s| s as i32),
+        )
+    } else {
+        (None, None, None)
+    };
 
-    // Compute content hash
-    let hash_input = format!(
-        "{}|{}|{}|{}|{}|{}|{}|{}",
-        artist.as_deref().unwrap_or("").to_lowercase(),
-        album_artist.as_deref().unwrap_or("").to_lowercase(),
-        album.as_deref().unwrap_or("").to_lowercase(),
-        title.as_deref().unwrap_or("").to_lowercase(),
-        year.unwrap_or(0),
-        track_number.unwrap_or(0),
-        disc_number.unwrap_or(0),
-        genre.as_deref().unwrap_or("").to_lowercase(),
-    );
-    let mut hasher = Md5::new();
-    hasher.update(hash_input.as_bytes());
-    let content_hash = format!("{:x}", hasher.finalize());
 
-    // Build metadata JSON (exclude fields that have their own columns)
-    let excluded_keys: Vec<&str> = vec![
-        "ARTIST", "TITLE", "ALBUM", "YEAR", "DATE", "GENRE",
-        "TRACKNUMBER", "TRACK", "DISCNUMBER", "DISC", "ALBUMARTIST",
-        "ALBUM_ARTIST", "ALBUM ARTIST",
-    ];
-    let mut meta_map = serde_json::Map::new();
-    for (k, v) in &all_tags {
-        let k_upper = k.to_uppercase();
-        if !excluded_keys.iter().any(|e| k_upper == *e) && !v.trim().is_empty() {
-            meta_map.insert(k.clone(), JsonValue::String(v.clone()));
+    // Build metadata JSON (exclude fields that have their own columns + --metadata-exclude)
+    let metadata_json = if metadata_opts.enabled {
+        let mut meta_map = serde_json::Map::new();
+        for (k, v) in &all_tags {
+            if !metadata_opts.is_excluded(&k.to_uppercase()) && !v.trim().is_empty() {
+                meta_map.insert(k.clone(), JsonValue::String(metadata_opts.cap(v)));
+            }
         }
-    }
-    let metadata_json = JsonValue::Object(meta_map);
+        Some(JsonValue::Object(meta_map))
+    } else {
+        None
+    };
 
     // Store relative path from music_dir
     let path_str = path.to_string_lossy();
@@ -328,7 +602,7 @@ fn extract_metadata(path: &Path, music_dir: &str) -> Option<TrackMeta> {
         .trim_start_matches('/')
         .to_string();
 
-    Some(TrackMeta {
+    let mut track = TrackMeta {
         file_path: relative_path,
         file_size,
         mtime,
@@ -337,17 +611,84 @@ fn extract_metadata(path: &Path, music_dir: &str) -> Option<TrackMeta> {
         album_artist,
         album,
         year,
-        genre,
+        original_year: original_year.map(|(_, y)| y),
+        genre: genres.first().cloned(),
+        genres,
         track_number,
         disc_number,
         duration,
         bitrate,
         sample_rate,
         position,
-        content_hash,
+        content_hash: None,
         metadata_json,
         has_picture,
-    })
+        picture,
+        credits,
+        lyrics,
+        cue: None,
+    };
+    // Skipped with --no-hash
+    if fast.hash {
+        track.content_hash = Some(content_hash(&track));
+    }
+    Some(track)
+}
+
+/// The year a date tag starts with: "1971-11-08" and "1971" give 1971.
+fn leading_year(value: &str) -> Option<i32> {
+    let year = value.trim().get(..4)?;
+    if !year.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    year.parse().ok().filter(|&y| y > 0)
+}
+
+fn file_mtime(meta: &fs::Metadata) -> NaiveDateTime {
+    meta.modified()
+        .ok()
+        .and_then(|t| {
+            let duration = t.duration_since(std::time::UNIX_EPOCH).ok()?;
+            chrono::DateTime::from_timestamp(duration.as_secs() as i64, 0)
+                .map(|dt| dt.naive_utc())
+        })
+        .unwrap_or_else(|| Utc::now().naive_utc())
+}
+
+/// MD5 of the tags that make up a track, used for change detection.
+fn content_hash(track: &TrackMeta) -> String {
+    let hash_input = format!(
+        "{}|{}|{}|{}|{}|{}|{}|{}",
+        track.artist.as_deref().unwrap_or("").to_lowercase(),
+        track.album_artist.as_deref().unwrap_or("").to_lowercase(),
+        track.album.as_deref().unwrap_or("").to_lowercase(),
+        track.title.as_deref().unwrap_or("").to_lowercase(),
+        track.year.unwrap_or(0),
+        track.track_number.unwrap_or(0),
+        track.disc_number.unwrap_or(0),
+        track.genre.as_deref().unwrap_or("").to_lowercase(),
+    );
+    // Credits only join the hash when present, so hashes of files without them don't change
+    let credits_input: String = track.credits.iter()
+        .map(|(role, name)| format!("|{}={}", role, name.to_lowercase()))
+        .collect();
+    // Same for lyrics (--extract-lyrics), so editing them re-imports the track
+    let lyrics_input: String = track.lyrics.iter()
+        .map(|(language, text)| format!("|lyrics:{}={}", language, text))
+        .collect();
+    // And for the original year, so files tagged with one are re-imported once
+    let original_input = track.original_year.map(|y| format!("|original={}", y)).unwrap_or_default();
+    // And for cue offsets, so moving an INDEX line re-imports the track
+    let cue_input = track.cue
+        .map(|span| format!("|cue={}-{}", span.start, span.end.unwrap_or(-1)))
+        .unwrap_or_default();
+    let mut hasher = Md5::new();
+    hasher.update(hash_input.as_bytes());
+    hasher.update(credits_input.as_bytes());
+    hasher.update(lyrics_input.as_bytes());
+    hasher.update(original_input.as_bytes());
+    hasher.update(cue_input.as_bytes());
+    format!("{:x}", hasher.finalize())
 }
 
 // ---------------------------------------------------------------------------
@@ -363,30 +704,337 @@ fn get_artist_folder(path: &Path, scan_root: &str) -> String {
     relative.split('/').next().unwrap_or("").to_string()
 }
 
-fn matches_filter(folder: &str, from: &str, to: &str, only: &str) -> bool {
-    let folder_lower = folder.to_lowercase();
+/// Key two files share when they would end up as the same track: path
+/// segments with trailing whitespace trimmed, lowercased unless
+/// `case_sensitive` (case-insensitive filesystems, SMB/NFS mounts).
+fn path_collision_key(file_path: &str, case_sensitive: bool) -> String {
+    let key = file_path
+        .split('/')
+        .map(|segment| segment.trim_end())
+        .collect::<Vec<_>>()
+        .join("/");
+    if case_sensitive { key } else { key.to_lowercase() }
+}
+
+/// Find tracks whose filePath collides with an earlier one. Returns
+/// (kept index, dropped index) pairs; the first file seen wins.
+fn find_path_collisions(tracks: &[TrackMeta], case_sensitive: bool) -> Vec<(usize, usize)> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut collisions = Vec::new();
+    for (i, track) in tracks.iter().enumerate() {
+        match seen.entry(path_collision_key(&track.file_path, case_sensitive)) {
+            Entry::Occupied(e) => collisions.push((*e.get(), i)),
+            Entry::Vacant(e) => {
+                e.insert(i);
+            }
+        }
+    }
+    collisions
+}
+
+// ---------------------------------------------------------------------------
+// Explicit file list (--paths-from)
+// ---------------------------------------------------------------------------
+
+/// Extensions the indexer picks up, lowercase.
+const AUDIO_EXTENSIONS: &[&str] = &["mp3", "m4a", "opus", "aac", "ogg", "flac"];
+
+fn is_audio_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| AUDIO_EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str()))
+}
+
+/// The files of a `--paths-from` list to index (sorted like the walk, no
+/// repeats) and the lines left out, with why.
+struct PathList {
+    paths: Vec<PathBuf>,
+    skipped: Vec<(String, &'static str)>,
+}
+
+/// Blank lines and `#` comments are ignored. Files directly in the music dir
+/// pass the folder filter, as they do in the walk. CUE sheets are kept, to be
+/// paired with their rips like in the walk.
+fn read_path_list(text: &str, music_dir: &str, filter: &FolderFilter) -> PathList {
+    let root = Path::new(music_dir);
+    let mut paths = Vec::new();
+    let mut skipped = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let path = if Path::new(line).is_absolute() { PathBuf::from(line) } else { root.join(line) };
+        let reason = if !path.starts_with(root) || path.components().any(|c| c == std::path::Component::ParentDir) {
+            Some("outside the music dir")
+        } else if !is_audio_file(&path) && !is_cue_sheet(&path) {
+            Some("not an audio file")
+        } else if !path.is_file() {
+            Some("not found")
+        } else if path.parent() != Some(root) && !filter.matches(&get_artist_folder(&path, music_dir)) {
+            Some("outside --only / --from / --to")
+        } else {
+            None
+        };
+        match reason {
+            Some(reason) => skipped.push((line.to_string(), reason)),
+            None => paths.push(path),
+        }
+    }
+    paths.sort();
+    paths.dedup();
+    PathList { paths, skipped }
+}
+
+/// Read the `--paths-from` file, or stdin for "-".
+fn read_path_list_source(source: &str) -> std::io::Result<String> {
+    if source == "-" {
+        let mut text = String::new();
+        std::io::Read::read_to_string(&mut std::io::stdin(), &mut text)?;
+        Ok(text)
+    } else {
+        fs::read_to_string(source)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// CUE sheets (single-file album rips)
+// ---------------------------------------------------------------------------
+
+/// Formats a CUE sheet can split, lowercase. APE files are only indexed
+/// through a sheet.
+const CUE_RIP_EXTENSIONS: &[&str] = &["flac", "ape"];
+
+fn is_cue_sheet(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("cue"))
+}
+
+/// Where a cue track sits in its rip, in milliseconds. The last track has no
+/// end and runs to the end of the file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct CueSpan {
+    start: i32,
+    end: Option<i32>,
+}
+
+#[derive(Debug, Default)]
+struct CueTrack {
+    number: i32,
+    title: Option<String>,
+    performer: Option<String>,
+    songwriter: Option<String>,
+    /// INDEX 01, in milliseconds
+    start: Option<i32>,
+}
+
+/// The parts of a CUE sheet the indexer uses.
+#[derive(Debug, Default)]
+struct CueSheet {
+    /// FILE names, as written
+    files: Vec<String>,
+    title: Option<String>,
+    performer: Option<String>,
+    date: Option<i32>,
+    genre: Option<String>,
+    tracks: Vec<CueTrack>,
+}
+
+/// A CUE sheet paired with the rip it splits.
+struct CueRip {
+    sheet: CueSheet,
+    /// Sheet mtime; cue tracks take the later of this and the rip's
+    mtime: NaiveDateTime,
+}
+
+/// The value of a CUE command: the quoted string, or the rest of the line.
+fn cue_value(rest: &str) -> String {
+    let rest = rest.trim();
+    let value = match rest.strip_prefix('"') {
+        Some(quoted) => quoted.split('"').next().unwrap_or(""),
+        None => rest,
+    };
+    sanitize_tag(value.trim())
+}
+
+/// "mm:ss:ff" (75 frames a second) in milliseconds.
+fn cue_time(value: &str) -> Option<i32> {
+    let parts: Vec<i32> = value.split(':').map(|p| p.trim().parse().ok()).collect::<Option<_>>()?;
+    match parts[..] {
+        [m, s, f] if (0..60).contains(&s) && (0..75).contains(&f) => Some((m * 60 + s) * 1000 + f * 1000 / 75),
+        _ => None,
+    }
+}
 
-    if !only.is_empty() {
-        return folder_lower.starts_with(only);
+/// Parse a CUE sheet. Commands before the first TRACK describe the album;
+/// anything the indexer doesn't use is ignored.
+fn parse_cue_sheet(text: &str) -> CueSheet {
+    let mut sheet = CueSheet::default();
+    for line in text.trim_start_matches('\u{feff}').lines() {
+        let line = line.trim();
+        let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let track = sheet.tracks.last_mut();
+        match (command.to_uppercase().as_str(), track) {
+            ("FILE", _) => {
+                // FILE "name.flac" WAVE; the type is dropped from unquoted names
+                let name = if rest.trim().starts_with('"') {
+                    cue_value(rest)
+                } else {
+                    cue_value(rest.trim().rsplit_once(char::is_whitespace).map_or(rest, |(n, _)| n))
+                };
+                sheet.files.push(name);
+            }
+            ("TRACK", _) => sheet.tracks.push(CueTrack {
+                number: rest.split_whitespace().next().and_then(|n| n.parse().ok()).unwrap_or(0),
+                ..CueTrack::default()
+            }),
+            ("TITLE", Some(t)) => t.title = Some(cue_value(rest)),
+            ("TITLE", None) => sheet.title = Some(cue_value(rest)),
+            ("PERFORMER", Some(t)) => t.performer = Some(cue_value(rest)),
+            ("PERFORMER", None) => sheet.performer = Some(cue_value(rest)),
+            ("SONGWRITER", Some(t)) => t.songwriter = Some(cue_value(rest)),
+            ("INDEX", Some(t)) => {
+                if let Some(("01", time)) = rest.trim().split_once(char::is_whitespace) {
+                    t.start = cue_time(time);
+                }
+            }
+            ("REM", None) => {
+                let (key, value) = rest.trim().split_once(char::is_whitespace).unwrap_or((rest, ""));
+                match key.to_uppercase().as_str() {
+                    "DATE" => sheet.date = cue_value(value).get(..4).and_then(|y| y.parse().ok()),
+                    "GENRE" => sheet.genre = Some(cue_value(value)),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
     }
+    sheet
+}
+
+/// CUE sheets aren't always UTF-8; older rippers wrote Latin-1.
+fn read_cue_text(path: &Path) -> std::io::Result<String> {
+    let bytes = fs::read(path)?;
+    Ok(String::from_utf8(bytes).unwrap_or_else(|e| e.into_bytes().into_iter().map(char::from).collect()))
+}
 
-    if !from.is_empty() && folder_lower < from.to_string() {
-        return false;
+/// The rip a sheet splits: its single FILE, next to the sheet. Rippers often
+/// name a WAV that was compressed afterwards, so the same stem as FLAC or APE
+/// counts too.
+fn cue_rip_path(cue_path: &Path, file: &str) -> Option<PathBuf> {
+    let relative = Path::new(file);
+    if !relative.components().all(|c| matches!(c, std::path::Component::Normal(_))) {
+        return None;
     }
-    if !to.is_empty() {
-        let to_upper = format!("{}\u{10FFFF}", to);
-        if folder_lower > to_upper {
-            return false;
+    let named = cue_path.parent()?.join(relative);
+    std::iter::once(named.clone())
+        .chain(CUE_RIP_EXTENSIONS.iter().map(|ext| named.with_extension(ext)))
+        .find(|p| {
+            p.extension().is_some_and(|ext| CUE_RIP_EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str()))
+                && p.is_file()
+        })
+}
+
+/// Pull the .cue files out of `paths` and pair each with the rip it splits.
+/// The rips stay in (or, for APE, join) the returned list and are split into
+/// cue tracks at extraction. Sheets for already split albums (one FILE per
+/// track) or a single track are dropped quietly; broken ones are reported.
+fn pair_cue_sheets(paths: Vec<PathBuf>) -> (PathList, HashMap<PathBuf, CueRip>) {
+    let (cues, mut audio): (Vec<PathBuf>, Vec<PathBuf>) = paths.into_iter().partition(|p| is_cue_sheet(p));
+    let mut rips: HashMap<PathBuf, CueRip> = HashMap::new();
+    let mut skipped = Vec::new();
+    for cue_path in cues {
+        let shown = cue_path.to_string_lossy().into_owned();
+        let (sheet, mtime) = match (read_cue_text(&cue_path), fs::metadata(&cue_path)) {
+            (Ok(text), Ok(meta)) => (parse_cue_sheet(&text), file_mtime(&meta)),
+            _ => {
+                skipped.push((shown, "cannot read CUE sheet"));
+                continue;
+            }
+        };
+        if sheet.files.len() != 1 || sheet.tracks.len() < 2 {
+            continue;
+        }
+        let reason = match cue_rip_path(&cue_path, &sheet.files[0]) {
+            None => Some("referenced FLAC/APE file not found"),
+            Some(_) if sheet.tracks.iter().any(|t| t.start.is_none()) => Some("track without INDEX 01"),
+            Some(_) if sheet.tracks.windows(2).any(|w| w[0].start >= w[1].start) => Some("INDEX 01 times out of order"),
+            Some(rip) if rips.contains_key(&rip) => Some("rip already has a CUE sheet"),
+            Some(rip) => {
+                if !audio.contains(&rip) {
+                    audio.push(rip.clone());
+                }
+                rips.insert(rip, CueRip { sheet, mtime });
+                None
+            }
+        };
+        if let Some(reason) = reason {
+            skipped.push((shown, reason));
         }
     }
+    audio.sort();
+    audio.dedup();
+    (PathList { paths: audio, skipped }, rips)
+}
 
-    true
+/// Split a rip's metadata into one TrackMeta per cue track. What the sheet
+/// leaves out comes from the rip's own tags; per-file extras (credits,
+/// lyrics) don't carry over.
+fn cue_tracks(rip: &CueRip, file: TrackMeta, fast: FastOptions, genre_separators: &[char]) -> Vec<TrackMeta> {
+    let sheet = &rip.sheet;
+    let genres = match &sheet.genre {
+        Some(genre) => split_genres([genre.as_str()], genre_separators),
+        None => file.genres.clone(),
+    };
+    let file_ms = file.duration.map(|d| d.saturating_mul(1000));
+    sheet.tracks.iter().enumerate().map(|(i, t)| {
+        let start = t.start.unwrap_or(0);
+        let end = sheet.tracks.get(i + 1).and_then(|next| next.start);
+        let mut track = TrackMeta {
+            file_path: format!("{}#{:02}", file.file_path, t.number),
+            file_size: file.file_size,
+            mtime: file.mtime.max(rip.mtime),
+            title: t.title.clone(),
+            artist: t.performer.clone().or_else(|| sheet.performer.clone()).or_else(|| file.artist.clone()),
+            album_artist: file.album_artist.clone().or_else(|| sheet.performer.clone()),
+            album: sheet.title.clone().or_else(|| file.album.clone()),
+            year: sheet.date.or(file.year),
+            original_year: file.original_year,
+            genre: genres.first().cloned(),
+            genres: genres.clone(),
+            track_number: Some(t.number),
+            disc_number: file.disc_number,
+            // Unset with --no-properties, like the rip's own
+            duration: file_ms.map(|total| (end.unwrap_or(total) - start).max(0) / 1000),
+            bitrate: file.bitrate,
+            sample_rate: file.sample_rate,
+            position: None,
+            content_hash: None,
+            metadata_json: file.metadata_json.clone(),
+            has_picture: file.has_picture,
+            picture: file.picture.clone(),
+            credits: t.songwriter.iter().filter(|s| !s.is_empty()).map(|s| ("COMPOSER", s.clone())).collect(),
+            lyrics: Vec::new(),
+            cue: Some(CueSpan { start, end }),
+        };
+        if fast.hash {
+            track.content_hash = Some(content_hash(&track));
+        }
+        track
+    }).collect()
 }
 
 // ---------------------------------------------------------------------------
 // Artist tag splitting
 // ---------------------------------------------------------------------------
 
+/// TrackArtist role for a credit tag key (uppercased, as lofty names it).
+fn credit_role(key_upper: &str) -> Option<&'static str> {
+    match key_upper {
+        "COMPOSER" => Some("COMPOSER"),
+        "REMIXER" | "MIXARTIST" => Some("REMIXER"),
+        "CONDUCTOR" => Some("CONDUCTOR"),
+        _ => None,
+    }
+}
+
 /// Check if a name is a "Various Artists" variant that should be skipped.
 fn is_various_artists(name: &str) -> bool {
     let lower = name.to_lowercase();
@@ -469,7 +1117,7 @@ fn split_artists(tag: &str) -> (Vec<String>, Vec<String>) {
     // Deduplicate while preserving order
     {
         let mut seen = std::collections::HashSet::new();
-        main_artists.retain(|a| seen.insert(a.to_lowercase()));
+        main_artists.retain(|a| seen.insert(fold_case(a)));
     }
 
     let mut featured_artists = match feat_part {
@@ -479,10 +1127,10 @@ fn split_artists(tag: &str) -> (Vec<String>, Vec<String>) {
     // Deduplicate featured, also excluding anyone already in main
     {
         let main_lower: std::collections::HashSet<String> =
-            main_artists.iter().map(|a| a.to_lowercase()).collect();
+            main_artists.iter().map(|a| fold_case(a)).collect();
         let mut seen = std::collections::HashSet::new();
         featured_artists.retain(|a| {
-            let lower = a.to_lowercase();
+            let lower = fold_case(a);
             !main_lower.contains(&lower) && seen.insert(lower)
         });
     }
@@ -494,58 +1142,414 @@ fn split_artists(tag: &str) -> (Vec<String>, Vec<String>) {
 // Cover art extraction
 // ---------------------------------------------------------------------------
 
-fn extract_cover_art(path: &Path, output_path: &Path) -> bool {
-    let parse_opts = ParseOptions::new().read_properties(false);
-    let tagged_file = match Probe::open(path).ok().and_then(|p| p.options(parse_opts).read().ok()) {
-        Some(f) => f,
-        None => return false,
-    };
+/// Where a release cover can come from (`--art-priority`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArtSource {
+    /// Picture tag in the audio file
+    Embedded,
+    /// Image file next to the tracks (cover.jpg, folder.jpg, ...)
+    Sidecar,
+}
 
-    for tag in tagged_file.tags() {
-        if let Some(pic) = tag.pictures().first() {
-            let data: &[u8] = pic.data();
-            // Load and resize to 200x200
-            match image::load_from_memory(data) {
-                Ok(img) => {
-                    let resized = img.resize_to_fill(
-                        200,
-                        200,
-                        image::imageops::FilterType::Lanczos3,
-                    );
-                    if let Some(parent) = output_path.parent() {
-                        fs::create_dir_all(parent).ok();
-                    }
-                    match resized.save(output_path) {
-                        Ok(_) => return true,
-                        Err(_) => return false,
-                    }
-                }
-                Err(_) => return false,
+impl ArtSource {
+    /// `--art-priority` values in order, without repeats.
+    fn parse_list(names: &[String]) -> Vec<ArtSource> {
+        let mut sources = Vec::new();
+        for name in names {
+            let source = match name.as_str() {
+                "sidecar" => ArtSource::Sidecar,
+                _ => ArtSource::Embedded,
+            };
+            if !sources.contains(&source) {
+                sources.push(source);
             }
         }
+        sources
     }
-    false
 }
 
-// ---------------------------------------------------------------------------
-// S3 Upload
-// ---------------------------------------------------------------------------
+/// A track a release cover can be taken from.
+#[derive(Debug, Clone)]
+struct ArtCandidate {
+    path: PathBuf,
+    has_picture: bool,
+    /// The picture kept by the metadata pass; None means read it from `path`
+    picture: Option<Arc<[u8]>>,
+}
 
-async fn create_s3_client(config: &Config) -> Option<S3Client> {
-    if config.s3_bucket.is_none() || config.s3_region.is_none() {
-        return None;
-    }
-    
-    let mut aws_config = aws_config::defaults(BehaviorVersion::latest());
-    
-    if let Some(ref region) = config.s3_region {
-        aws_config = aws_config.region(aws_sdk_s3::config::Region::new(region.clone()));
+/// Largest embedded picture the metadata pass keeps for the art phase.
+const INLINE_PICTURE_MAX: usize = 4 * 1024 * 1024;
+/// Picture bytes kept per run; past it the art phase opens the file again.
+const INLINE_PICTURE_BUDGET: usize = 512 * 1024 * 1024;
+
+/// Embedded pictures captured by `extract_metadata`, so the art phase doesn't
+/// re-open and re-parse the source file. The tracks of an album usually carry
+/// the same picture, which is stored once.
+#[derive(Default)]
+struct PictureCache {
+    /// MD5 of the picture -> its bytes
+    pictures: Mutex<HashMap<[u8; 16], Arc<[u8]>>>,
+    bytes: AtomicUsize,
+}
+
+impl PictureCache {
+    /// Shared copy of `data`, or None when it's over the size cap or the budget is spent.
+    fn keep(&self, data: &[u8]) -> Option<Arc<[u8]>> {
+        if data.len() > INLINE_PICTURE_MAX {
+            return None;
+        }
+        let key: [u8; 16] = Md5::digest(data).into();
+        let mut pictures = self.pictures.lock().ok()?;
+        if let Some(picture) = pictures.get(&key) {
+            return Some(picture.clone());
+        }
+        if self.bytes.load(Ordering::Relaxed) + data.len() > INLINE_PICTURE_BUDGET {
+            return None;
+        }
+        self.bytes.fetch_add(data.len(), Ordering::Relaxed);
+        let picture: Arc<[u8]> = Arc::from(data);
+        pictures.insert(key, picture.clone());
+        Some(picture)
     }
-    
-    if let (Some(ref key), Some(ref secret)) = (&config.s3_access_key, &config.s3_secret_key) {
-        aws_config = aws_config.credentials_provider(
-            aws_sdk_s3::config::Credentials::new(
-                key,
+}
+
+/// Sidecar file stems, best first. Matched case-insensitively.
+const SIDECAR_NAMES: &[&str] = &["cover", "folder", "front", "albumart", "album"];
+const SIDECAR_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp"];
+/// Subfolders some rippers keep the artwork in.
+const ARTWORK_DIRS: &[&str] = &["artwork", "covers", "scans"];
+
+/// "CD1", "CD 2", "Disc 1", "Disk 02": a per-disc folder inside an album folder.
+fn is_disc_folder(name: &str) -> bool {
+    let lower = name.to_lowercase();
+    ["cd", "disc", "disk"].iter().any(|prefix| {
+        lower
+            .strip_prefix(prefix)
+            .map(|rest| rest.trim_start_matches([' ', '_', '-']))
+            .is_some_and(|rest| !rest.is_empty() && rest.chars().all(|c| c.is_ascii_digit()))
+    })
+}
+
+/// Best sidecar image in `dir`: a known stem first ("cover" beats "folder"),
+/// then any image whose name starts with "cover" or "front" ("Cover (front).jpg").
+fn sidecar_in(dir: &Path) -> Option<PathBuf> {
+    let mut images: Vec<(String, PathBuf)> = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .filter(|p| {
+            p.extension()
+                .map(|e| SIDECAR_EXTENSIONS.contains(&e.to_string_lossy().to_lowercase().as_str()))
+                .unwrap_or(false)
+        })
+        .filter_map(|p| Some((p.file_stem()?.to_string_lossy().to_lowercase(), p)))
+        .collect();
+    images.sort();
+
+    SIDECAR_NAMES
+        .iter()
+        .find_map(|name| images.iter().find(|(stem, _)| stem == name))
+        .or_else(|| images.iter().find(|(stem, _)| stem.starts_with("cover") || stem.starts_with("front")))
+        .map(|(_, p)| p.clone())
+}
+
+/// Sidecar cover for a track: its own folder, then the album folder when the
+/// track sits in a disc folder, then an artwork subfolder of either.
+fn find_sidecar_art(track_path: &Path) -> Option<PathBuf> {
+    let dir = track_path.parent()?;
+    let mut dirs = vec![dir.to_path_buf()];
+    if let (Some(name), Some(parent)) = (dir.file_name(), dir.parent()) {
+        if is_disc_folder(&name.to_string_lossy()) {
+            dirs.push(parent.to_path_buf());
+        }
+    }
+    let artwork: Vec<PathBuf> = dirs
+        .iter()
+        .filter_map(|d| fs::read_dir(d).ok())
+        .flat_map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()))
+        .filter(|p| p.is_dir())
+        .filter(|p| {
+            p.file_name()
+                .map(|n| ARTWORK_DIRS.contains(&n.to_string_lossy().to_lowercase().as_str()))
+                .unwrap_or(false)
+        })
+        .collect();
+    dirs.extend(artwork);
+    dirs.iter().find_map(|d| sidecar_in(d))
+}
+
+/// Resize to the 200x200 release cover and save it as JPEG.
+fn save_cover(img: image::DynamicImage, output_path: &Path) -> bool {
+    let resized = img.resize_to_fill(200, 200, image::imageops::FilterType::Lanczos3);
+    if let Some(parent) = output_path.parent() {
+        fs::create_dir_all(parent).ok();
+    }
+    image::DynamicImage::ImageRgb8(resized.to_rgb8()).save(output_path).is_ok()
+}
+
+/// Decode an embedded picture and save it as the release cover.
+fn save_picture(data: &[u8], output_path: &Path) -> bool {
+    match image::load_from_memory(data) {
+        Ok(img) => save_cover(img, output_path),
+        Err(_) => false,
+    }
+}
+
+fn extract_cover_art(path: &Path, output_path: &Path) -> bool {
+    let parse_opts = ParseOptions::new().read_properties(false);
+    let tagged_file = match Probe::open(path).ok().and_then(|p| p.options(parse_opts).read().ok()) {
+        Some(f) => f,
+        None => return false,
+    };
+
+    for tag in tagged_file.tags() {
+        if let Some(pic) = tag.pictures().first() {
+            return save_picture(pic.data(), output_path);
+        }
+    }
+    false
+}
+
+/// Write a release's cover from the first source in `priority` that has one.
+/// `picture` is the track's embedded picture when the metadata pass kept it.
+/// Returns the source used.
+fn release_cover(
+    track_path: &Path,
+    picture: Option<&[u8]>,
+    output_path: &Path,
+    priority: &[ArtSource],
+) -> Option<ArtSource> {
+    priority.iter().copied().find(|source| match source {
+        ArtSource::Embedded => match picture {
+            Some(data) => save_picture(data, output_path),
+            None => extract_cover_art(track_path, output_path),
+        },
+        ArtSource::Sidecar => find_sidecar_art(track_path)
+            .and_then(|p| image::open(p).ok())
+            .is_some_and(|img| save_cover(img, output_path)),
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Artist placeholder images (--placeholders)
+// ---------------------------------------------------------------------------
+
+const PLACEHOLDER_SIZE: u32 = 200;
+/// Pixels per glyph dot: two 5x7 glyphs + a 1-dot gap are 110x70px.
+const PLACEHOLDER_SCALE: u32 = 10;
+
+/// 5x7 bitmap glyph, one row per byte, bit 4 = leftmost column.
+fn placeholder_glyph(c: char) -> [u8; 7] {
+    match c {
+        'A' => [0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001],
+        'B' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110],
+        'C' => [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110],
+        'D' => [0b11110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b11110],
+        'E' => [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111],
+        'F' => [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000],
+        'G' => [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111],
+        'H' => [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001],
+        'I' => [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+        'J' => [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100],
+        'K' => [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001],
+        'L' => [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111],
+        'M' => [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001],
+        'N' => [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001],
+        'O' => [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110],
+        'P' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000],
+        'Q' => [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101],
+        'R' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001],
+        'S' => [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110],
+        'T' => [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100],
+        'U' => [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110],
+        'V' => [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100],
+        'W' => [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010],
+        'X' => [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001],
+        'Y' => [0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100],
+        'Z' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111],
+        '0' => [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110],
+        '1' => [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+        '2' => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111],
+        '3' => [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110],
+        '4' => [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010],
+        '5' => [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110],
+        '6' => [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110],
+        '7' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000],
+        '8' => [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110],
+        '9' => [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100],
+        _ => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100], // '?'
+    }
+}
+
+/// Up to two initials (first two words), transliterated to ASCII the same
+/// way slugs are, so "Björk" gives "B" and "Sigur Rós" gives "SR".
+fn placeholder_initials(name: &str) -> String {
+    let initials: String = name
+        .split_whitespace()
+        .filter_map(|word| word.chars().find(|c| c.is_alphanumeric()))
+        .filter_map(|c| slugify(c.to_string()).chars().next())
+        .take(2)
+        .collect();
+    if initials.is_empty() { "?".to_string() } else { initials.to_uppercase() }
+}
+
+/// Background colour derived from the slug: hue from its MD5, fixed
+/// saturation/lightness so white initials stay readable.
+fn placeholder_color(slug: &str) -> image::Rgb<u8> {
+    let digest = Md5::digest(slug.as_bytes());
+    let hue = u16::from_be_bytes([digest[0], digest[1]]) as f32 / 65536.0 * 360.0;
+    let (s, l) = (0.45f32, 0.38f32);
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let x = c * (1.0 - ((hue / 60.0) % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as u32 / 60 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let m = l - c / 2.0;
+    let channel = |v: f32| ((v + m) * 255.0).round() as u8;
+    image::Rgb([channel(r), channel(g), channel(b)])
+}
+
+/// Deterministic 200x200 placeholder: initials centred on the slug colour.
+fn render_placeholder(name: &str, slug: &str) -> image::RgbImage {
+    let mut img = image::RgbImage::from_pixel(PLACEHOLDER_SIZE, PLACEHOLDER_SIZE, placeholder_color(slug));
+    let initials: Vec<char> = placeholder_initials(name).chars().collect();
+    let width = (initials.len() as u32 * 6 - 1) * PLACEHOLDER_SCALE;
+    let left = (PLACEHOLDER_SIZE - width) / 2;
+    let top = (PLACEHOLDER_SIZE - 7 * PLACEHOLDER_SCALE) / 2;
+    let ink = image::Rgb([245, 245, 245]);
+
+    for (i, c) in initials.iter().enumerate() {
+        let glyph_left = left + i as u32 * 6 * PLACEHOLDER_SCALE;
+        for (row, bits) in placeholder_glyph(*c).iter().enumerate() {
+            for col in 0..5u32 {
+                if bits & (0b10000 >> col) == 0 {
+                    continue;
+                }
+                let x0 = glyph_left + col * PLACEHOLDER_SCALE;
+                let y0 = top + row as u32 * PLACEHOLDER_SCALE;
+                for y in y0..y0 + PLACEHOLDER_SCALE {
+                    for x in x0..x0 + PLACEHOLDER_SCALE {
+                        img.put_pixel(x, y, ink);
+                    }
+                }
+            }
+        }
+    }
+    img
+}
+
+/// Give every artist without an image (matching the folder filter) a
+/// placeholder, stored exactly like a synced image (`img/artists/<slug>.jpg`
+/// and/or S3 `artists/<slug>.jpg`) with `imagePlaceholder` set. Sync
+/// overwrites the file and clears the flag once it finds a real image.
+/// Returns (generated, failed).
+async fn generate_artist_placeholders(
+    pool: &PgPool,
+    config: &Config,
+    filter: &FolderFilter,
+    error_log: &Mutex<fs::File>,
+) -> (u32, u32) {
+    let artists: Vec<(String, String, String)> = sqlx::query_as(
+        r#"SELECT id, name, slug FROM "Artist"
+           WHERE (image IS NULL OR image = '')
+             AND ("imageUrl" IS NULL OR "imageUrl" = '')
+           ORDER BY slug"#,
+    )
+    .fetch_all(pool)
+    .await
+    .unwrap_or_default();
+
+    let use_s3 = config.image_storage == "s3" || config.image_storage == "both";
+    let use_local = config.image_storage == "local" || config.image_storage == "both";
+    let s3_client = if use_s3 { create_s3_client(config).await } else { None };
+    let img_dir = PathBuf::from(&config.project_root).join("web/public/img/artists");
+    fs::create_dir_all(&img_dir).ok();
+
+    let (mut generated, mut failed) = (0u32, 0u32);
+    for (artist_id, name, slug) in artists.iter().filter(|(_, name, _)| filter.matches(name)) {
+        let filename = format!("{}.jpg", slug);
+        let out_path = img_dir.join(&filename);
+        if let Err(e) = render_placeholder(name, slug).save(&out_path) {
+            if let Ok(mut f) = error_log.lock() {
+                writeln!(f, "[INDEXER] Placeholder failed for artist '{}': {}", name, e).ok();
+            }
+            failed += 1;
+            continue;
+        }
+
+        let mut stored = false;
+        if use_s3 {
+            if let (Some(ref client), Some(ref bucket), Some(ref public_url)) =
+                (&s3_client, &config.s3_bucket, &config.s3_public_url)
+            {
+                let s3_key = format!("artists/{}", filename);
+                match upload_to_s3(client, bucket, &s3_key, &out_path).await {
+                    Ok(_) => {
+                        let image_url = format!("{}/{}", public_url.trim_end_matches('/'), s3_key);
+                        stored |= sqlx::query(
+                            r#"UPDATE "Artist" SET "imageUrl" = $1, "imagePlaceholder" = true, "updatedAt" = NOW() WHERE id = $2"#,
+                        )
+                        .bind(&image_url)
+                        .bind(artist_id)
+                        .execute(pool)
+                        .await
+                        .is_ok();
+                    }
+                    Err(e) => {
+                        if let Ok(mut f) = error_log.lock() {
+                            writeln!(f, "[INDEXER] S3 upload failed for placeholder of artist '{}': {:?}", name, e).ok();
+                        }
+                    }
+                }
+            }
+        }
+
+        if use_local {
+            stored |= sqlx::query(
+                r#"UPDATE "Artist" SET image = $1, "imagePlaceholder" = true, "updatedAt" = NOW() WHERE id = $2"#,
+            )
+            .bind(&filename)
+            .bind(artist_id)
+            .execute(pool)
+            .await
+            .is_ok();
+        }
+
+        // Delete local file if only using S3
+        if !use_local && use_s3 {
+            fs::remove_file(&out_path).ok();
+        }
+
+        if stored { generated += 1; } else { failed += 1; }
+    }
+    (generated, failed)
+}
+
+// ---------------------------------------------------------------------------
+// S3 Upload
+// ---------------------------------------------------------------------------
+
+async fn create_s3_client(config: &Config) -> Option<S3Client> {
+    if config.s3_bucket.is_none() || config.s3_region.is_none() {
+        return None;
+    }
+    
+    let mut aws_config = aws_config::defaults(BehaviorVersion::latest());
+    
+    if let Some(ref region) = config.s3_region {
+        aws_config = aws_config.region(aws_sdk_s3::config::Region::new(region.clone()));
+    }
+    
+    if let (Some(ref key), Some(ref secret)) = (&config.s3_access_key, &config.s3_secret_key) {
+        aws_config = aws_config.credentials_provider(
+            aws_sdk_s3::config::Credentials::new(
+                key,
                 secret,
                 None,
                 None,
@@ -588,8 +1592,8 @@ async fn upload_to_s3(
 // Database operations
 // ---------------------------------------------------------------------------
 
-async fn ensure_artist(pool: &PgPool, name: &str) -> Result<String, sqlx::Error> {
-    let artist_slug = slugify(name);
+async fn ensure_artist(conn: &mut PgConnection, name: &str) -> Result<String, sqlx::Error> {
+    let artist_slug = artist_slug(name);
     if artist_slug.is_empty() {
         return Ok(String::new());
     }
@@ -598,7 +1602,7 @@ async fn ensure_artist(pool: &PgPool, name: &str) -> Result<String, sqlx::Error>
         r#"SELECT id FROM "Artist" WHERE slug = $1"#,
     )
     .bind(&artist_slug)
-    .fetch_optional(pool)
+    .fetch_optional(&mut *conn)
     .await?;
 
     if let Some((id,)) = existing {
@@ -616,7 +1620,7 @@ async fn ensure_artist(pool: &PgPool, name: &str) -> Result<String, sqlx::Error>
     .bind(name)
     .bind(&artist_slug)
     .bind(now)
-    .execute(pool)
+    .execute(&mut *conn)
     .await?;
 
     // Return the actual ID (might be different if ON CONFLICT hit)
@@ -624,7 +1628,7 @@ async fn ensure_artist(pool: &PgPool, name: &str) -> Result<String, sqlx::Error>
         r#"SELECT id FROM "Artist" WHERE slug = $1"#,
     )
     .bind(&artist_slug)
-    .fetch_one(pool)
+    .fetch_one(&mut *conn)
     .await?;
 
     Ok(row.0)
@@ -632,11 +1636,11 @@ async fn ensure_artist(pool: &PgPool, name: &str) -> Result<String, sqlx::Error>
 
 /// Cached version of ensure_artist - checks HashMap before hitting DB
 async fn ensure_artist_cached(
-    pool: &PgPool,
+    conn: &mut PgConnection,
     name: &str,
     cache: &mut HashMap<String, String>,
 ) -> Result<String, sqlx::Error> {
-    let artist_slug = slugify(name);
+    let artist_slug = artist_slug(name);
     if artist_slug.is_empty() {
         return Ok(String::new());
     }
@@ -645,7 +1649,7 @@ async fn ensure_artist_cached(
         return Ok(id.clone());
     }
 
-    let id = ensure_artist(pool, name).await?;
+    let id = ensure_artist(conn, name).await?;
     if !id.is_empty() {
         cache.insert(artist_slug, id.clone());
     }
@@ -653,7 +1657,7 @@ async fn ensure_artist_cached(
 }
 
 async fn ensure_local_release(
-    pool: &PgPool,
+    conn: &mut PgConnection,
     artist_id: &str,
     title: &str,
     year: Option<i32>,
@@ -664,7 +1668,7 @@ async fn ensure_local_release(
     )
     .bind(artist_id)
     .bind(title)
-    .fetch_optional(pool)
+    .fetch_optional(&mut *conn)
     .await?;
 
     if let Some((id,)) = existing {
@@ -684,7 +1688,7 @@ async fn ensure_local_release(
     .bind(artist_id)
     .bind(now)
     .bind(folder_path)
-    .execute(pool)
+    .execute(&mut *conn)
     .await?;
 
     let row: (String,) = sqlx::query_as(
@@ -692,7 +1696,7 @@ async fn ensure_local_release(
     )
     .bind(artist_id)
     .bind(title)
-    .fetch_one(pool)
+    .fetch_one(&mut *conn)
     .await?;
 
     Ok(row.0)
@@ -700,7 +1704,7 @@ async fn ensure_local_release(
 
 /// Cached version of ensure_local_release - checks HashMap before hitting DB
 async fn ensure_local_release_cached(
-    pool: &PgPool,
+    conn: &mut PgConnection,
     artist_id: &str,
     title: &str,
     year: Option<i32>,
@@ -712,32 +1716,33 @@ async fn ensure_local_release_cached(
         return Ok(id.clone());
     }
 
-    let id = ensure_local_release(pool, artist_id, title, year, folder_path).await?;
+    let id = ensure_local_release(conn, artist_id, title, year, folder_path).await?;
     cache.insert(key, id.clone());
     Ok(id)
 }
 
 async fn upsert_track(
-    pool: &PgPool,
+    conn: &mut PgConnection,
     track: &TrackMeta,
     local_release_id: &str,
 ) -> Result<String, sqlx::Error> {
     let id = cuid2::create_id();
     let now = Utc::now().naive_utc();
-    let metadata_value = serde_json::to_value(&track.metadata_json).unwrap_or(JsonValue::Null);
+    let metadata_value = track.metadata_json.as_ref();
 
     sqlx::query(
         r#"INSERT INTO "LocalReleaseTrack"
            (id, title, artist, "albumArtist", album, year, genre,
             duration, bitrate, "sampleRate", "filePath", position, "trackNumber", "discNumber",
             "localReleaseId", "fileSize", mtime, "contentHash", metadata,
-            "playCount", "createdAt", "updatedAt")
-           VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, 0, $20, $20)
+            "playCount", "createdAt", "updatedAt", "cueStart", "cueEnd", "originalYear")
+           VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, 0, $20, $20, $21, $22, $23)
            ON CONFLICT ("filePath") DO UPDATE SET
              title = $2, artist = $3, "albumArtist" = $4, album = $5, year = $6,
              genre = $7, duration = $8, bitrate = $9, "sampleRate" = $10,
              position = $12, "trackNumber" = $13, "discNumber" = $14, "localReleaseId" = $15,
-             "fileSize" = $16, mtime = $17, "contentHash" = $18, metadata = $19, "updatedAt" = $20
+             "fileSize" = $16, mtime = $17, "contentHash" = $18, metadata = $19, "updatedAt" = $20,
+             "cueStart" = $21, "cueEnd" = $22, "originalYear" = $23
            RETURNING id"#,
     )
     .bind(&id)
@@ -758,15 +1763,27 @@ async fn upsert_track(
     .bind(track.file_size)
     .bind(track.mtime)
     .bind(&track.content_hash)
-    .bind(&metadata_value)
+    .bind(metadata_value)
     .bind(now)
-    .fetch_one(pool)
+    .bind(track.cue.map(|span| span.start))
+    .bind(track.cue.and_then(|span| span.end))
+    .bind(track.original_year)
+    .fetch_one(&mut *conn)
     .await
     .map(|row| row.get::<String, _>("id"))
 }
 
+/// Drop the whole-file tracks of rips that are now indexed per cue track.
+async fn remove_unsplit_rips(pool: &PgPool, rips: &[String]) -> Result<u64, sqlx::Error> {
+    sqlx::query(r#"DELETE FROM "LocalReleaseTrack" WHERE "filePath" = ANY($1) AND "cueStart" IS NULL"#)
+        .bind(rips)
+        .execute(pool)
+        .await
+        .map(|r| r.rows_affected())
+}
+
 async fn ensure_track_artist(
-    pool: &PgPool,
+    conn: &mut PgConnection,
     track_id: &str,
     artist_id: &str,
     role: &str,
@@ -783,49 +1800,429 @@ async fn ensure_track_artist(
     .bind(artist_id)
     .bind(role)
     .bind(now)
-    .execute(pool)
+    .execute(&mut *conn)
     .await?;
     Ok(())
 }
 
-// ---------------------------------------------------------------------------
-// Overwrite / nuke
-// ---------------------------------------------------------------------------
+/// Replace the track's `TrackLyrics` rows with what the file has now (none removes them).
+async fn replace_track_lyrics(
+    conn: &mut PgConnection,
+    track_id: &str,
+    lyrics: &[(String, String)],
+) -> Result<(), sqlx::Error> {
+    sqlx::query(r#"DELETE FROM "TrackLyrics" WHERE "trackId" = $1"#)
+        .bind(track_id)
+        .execute(&mut *conn)
+        .await?;
+    let now = Utc::now().naive_utc();
+    for (language, text) in lyrics {
+        sqlx::query(
+            r#"INSERT INTO "TrackLyrics" (id, "trackId", language, lyrics, "createdAt", "updatedAt")
+               VALUES ($1, $2, $3, $4, $5, $5)"#,
+        )
+        .bind(cuid2::create_id())
+        .bind(track_id)
+        .bind(language)
+        .bind(text)
+        .bind(now)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
 
-async fn nuke_artists(pool: &PgPool, from: &str, to: &str, only: &str) -> Result<u64, sqlx::Error> {
-    // Find matching artists
-    let artists: Vec<(String, String, Option<String>)> = sqlx::query_as(
-        r#"SELECT id, slug, image FROM "Artist""#,
+/// Replace the track's `TrackGenre` rows with `genres`, creating the genres
+/// that don't exist yet. An existing genre is reused whatever its case, so
+/// "Rock" from a tag links to sync's "rock".
+async fn replace_track_genres(
+    conn: &mut PgConnection,
+    track_id: &str,
+    genres: &[String],
+) -> Result<(), sqlx::Error> {
+    sqlx::query(r#"DELETE FROM "TrackGenre" WHERE "trackId" = $1"#)
+        .bind(track_id)
+        .execute(&mut *conn)
+        .await?;
+    if genres.is_empty() {
+        return Ok(());
+    }
+    let genre_ids: Vec<String> = genres.iter().map(|_| cuid2::create_id()).collect();
+    sqlx::query(
+        r#"INSERT INTO "Genre" (id, name)
+           SELECT n.id, n.name FROM UNNEST($1::text[], $2::text[]) AS n(id, name)
+           WHERE NOT EXISTS (SELECT 1 FROM "Genre" g WHERE lower(g.name) = lower(n.name))
+           ON CONFLICT (name) DO NOTHING"#,
     )
-    .fetch_all(pool)
+    .bind(&genre_ids)
+    .bind(genres)
+    .execute(&mut *conn)
     .await?;
 
-    let mut deleted = 0u64;
-    for (artist_id, slug, image) in &artists {
-        if !matches_filter(slug, from, to, only) {
+    let link_ids: Vec<String> = genres.iter().map(|_| cuid2::create_id()).collect();
+    sqlx::query(
+        r#"INSERT INTO "TrackGenre" (id, "trackId", "genreId", position, "createdAt")
+           SELECT DISTINCT ON (g.id) n.id, $2, g.id, (n.position - 1)::int, $4
+           FROM UNNEST($1::text[], $3::text[]) WITH ORDINALITY AS n(id, name, position)
+           JOIN "Genre" g ON lower(g.name) = lower(n.name)
+           ORDER BY g.id, n.position
+           ON CONFLICT ("trackId", "genreId") DO NOTHING"#,
+    )
+    .bind(&link_ids)
+    .bind(track_id)
+    .bind(genres)
+    .bind(Utc::now().naive_utc())
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Phase 3: per-artist-folder writes
+// ---------------------------------------------------------------------------
+
+/// A `LocalReleaseTrack` row as loaded for change detection.
+struct ExistingTrack {
+    size: i64,
+    mtime: NaiveDateTime,
+    /// None when written with --no-hash
+    hash: Option<String>,
+    /// Duration / metadata JSON were stored (not left out by --no-properties / --no-json)
+    has_properties: bool,
+    has_metadata: bool,
+    genre: Option<String>,
+    /// TrackGenre rows
+    genre_count: i64,
+}
+
+/// ("filePath", "fileSize", mtime, "contentHash", has duration, has metadata, genre, TrackGenre rows)
+type ExistingRow = (String, i64, Option<NaiveDateTime>, Option<String>, bool, bool, Option<String>, i64);
+
+/// Read-only state shared by the Phase 3 artist-folder tasks.
+struct WriteContext<'a> {
+    pool: &'a PgPool,
+    extracted: &'a [TrackMeta],
+    /// filePath -> the tracks already indexed
+    existing_tracks: &'a HashMap<String, ExistingTrack>,
+    error_log: &'a Mutex<fs::File>,
+    music_dir: &'a str,
+    release_img_dir: PathBuf,
+    extract_lyrics: bool,
+    skip_images: bool,
+    use_embedded: bool,
+    use_sidecar: bool,
+}
+
+/// What one artist folder's task did, merged into the run totals in folder order.
+#[derive(Default)]
+struct GroupOutcome {
+    new: u64,
+    updated: u64,
+    skipped: u64,
+    /// Unchanged tracks whose hash / properties / metadata JSON a --fast run left
+    /// empty, or whose genres were stored unsplit
+    filled: u64,
+    errors: u64,
+    lyrics: u64,
+    messages: Vec<String>,
+    /// Releases that need cover art: release id -> the track to take it from
+    art: HashMap<String, ArtCandidate>,
+    /// Rolled back after waiting on another folder's uncommitted rows; the
+    /// folder is written again once the others are
+    retry: bool,
+}
+
+/// How long a folder's transaction waits for a row another folder's
+/// transaction holds. Folders commit in walk order, so an earlier folder
+/// stuck behind a later one's uncommitted upsert (the same new artist or
+/// genre) would otherwise wait forever; it gives up and is written again
+/// after the others (see `GroupOutcome::retry`).
+const GROUP_LOCK_TIMEOUT: &str = "10s";
+
+/// Write all tracks of one artist folder in one transaction: either every
+/// track of the folder is written or, after a DB error, none is. The
+/// artist/release caches are local to the task, so concurrent folders never
+/// share in-memory state; the `ensure_*` upserts keep the rare cross-folder
+/// artist (a guest, a composer) consistent in the database. The folder's
+/// transaction is returned uncommitted so the caller can commit folders in
+/// walk order.
+async fn write_artist_group(
+    ctx: &WriteContext<'_>,
+    folder: &str,
+    indices: &[usize],
+) -> (GroupOutcome, Option<sqlx::Transaction<'static, sqlx::Postgres>>) {
+    let begun = async {
+        let mut tx = ctx.pool.begin().await?;
+        sqlx::query(&format!("SET LOCAL lock_timeout = '{}'", GROUP_LOCK_TIMEOUT))
+            .execute(&mut *tx)
+            .await?;
+        Ok::<_, sqlx::Error>(tx)
+    };
+    let mut tx = match begun.await {
+        Ok(tx) => tx,
+        Err(e) => {
+            if let Ok(mut f) = ctx.error_log.lock() {
+                writeln!(f, "[INDEXER] Failed to begin transaction for folder '{}': {}", folder, e).ok();
+            }
+            let out = GroupOutcome { errors: indices.len() as u64, ..Default::default() };
+            return (out, None);
+        }
+    };
+
+    let mut out = GroupOutcome::default();
+    match write_group_tracks(ctx, &mut tx, indices, &mut out).await {
+        Ok(()) => (out, Some(tx)),
+        Err((context, e)) => {
+            // Dropping the transaction rolls the whole folder back
+            let retry = matches!(&e, sqlx::Error::Database(db) if db.code().as_deref() == Some("55P03"));
+            let msg = format!("DB error ({}), folder '{}' rolled back: {}", context, folder, e);
+            if !retry {
+                if let Ok(mut f) = ctx.error_log.lock() { writeln!(f, "[INDEXER] {}", msg).ok(); }
+            }
+            let out = GroupOutcome {
+                errors: indices.len() as u64,
+                messages: vec![msg],
+                retry,
+                ..Default::default()
+            };
+            (out, None)
+        }
+    }
+}
+
+/// The writes of `write_artist_group`, all on the folder's transaction. The
+/// first DB error ends the folder, with what was being written.
+async fn write_group_tracks(
+    ctx: &WriteContext<'_>,
+    conn: &mut PgConnection,
+    indices: &[usize],
+    out: &mut GroupOutcome,
+) -> Result<(), (String, sqlx::Error)> {
+    let mut artist_cache: HashMap<String, String> = HashMap::new();
+    let mut release_cache: HashMap<(String, String), String> = HashMap::new();
+
+    for &idx in indices {
+        let track = &ctx.extracted[idx];
+        let context = |what: &str| {
+            let file = track.file_path.clone();
+            let what = what.to_string();
+            move |e: sqlx::Error| (format!("{} {}", what, file), e)
+        };
+
+        // Change detection using in-memory HashMap (replaces per-track DB query)
+        if let Some(existing) = ctx.existing_tracks.get(&track.file_path) {
+            // mtime + size match, or size/mtime changed but the content hash didn't
+            // (never provable when either side was indexed with --no-hash)
+            let same_file = existing.size == track.file_size
+                && (existing.mtime - track.mtime).num_seconds().abs() < 2;
+            let same_content = existing.hash.is_some() && existing.hash == track.content_hash;
+            if same_file || same_content {
+                // Fill in what a --fast run left out, if this run has it
+                // ...and split genres indexed before --genre-separators (or with other separators)
+                let regenre = existing.genre != track.genre || existing.genre_count != track.genres.len() as i64;
+                let fill = (existing.hash.is_none() && track.content_hash.is_some())
+                    || (!existing.has_properties && track.duration.is_some())
+                    || (!existing.has_metadata && track.metadata_json.is_some())
+                    || regenre;
+                if !same_file || fill {
+                    let now = Utc::now().naive_utc();
+                    let track_id: Option<String> = sqlx::query_scalar(
+                        r#"UPDATE "LocalReleaseTrack" SET mtime = $1,
+                             "contentHash" = COALESCE("contentHash", $2),
+                             duration = COALESCE(duration, $3),
+                             bitrate = COALESCE(bitrate, $4),
+                             "sampleRate" = COALESCE("sampleRate", $5),
+                             metadata = COALESCE(metadata, $6),
+                             genre = $7,
+                             "updatedAt" = $8
+                           WHERE "filePath" = $9
+                           RETURNING id"#,
+                    )
+                    .bind(track.mtime)
+                    .bind(&track.content_hash)
+                    .bind(track.duration)
+                    .bind(track.bitrate)
+                    .bind(track.sample_rate)
+                    .bind(track.metadata_json.as_ref())
+                    .bind(&track.genre)
+                    .bind(now)
+                    .bind(&track.file_path)
+                    .fetch_optional(&mut *conn)
+                    .await
+                    .map_err(context("track"))?;
+                    if let (true, Some(track_id)) = (regenre, track_id) {
+                        replace_track_genres(conn, &track_id, &track.genres).await.map_err(context("genres"))?;
+                    }
+                }
+                if fill {
+                    out.filled += 1;
+                } else {
+                    out.skipped += 1;
+                }
+                continue;
+            }
+            // Content changed -> full update
+            out.updated += 1;
+        } else {
+            // New file
+            out.new += 1;
+        }
+
+        // Split artist tags into individual artists
+        let album_artist_tag = track.album_artist.as_deref().unwrap_or("");
+        let track_artist_tag = track.artist.as_deref().unwrap_or("");
+
+        let (main_album_artists, feat_album_artists) = if !album_artist_tag.is_empty() && !is_various_artists(album_artist_tag) {
+            split_artists(album_artist_tag)
+        } else {
+            (Vec::new(), Vec::new())
+        };
+
+        let (main_track_artists, feat_track_artists) = if !track_artist_tag.is_empty() {
+            split_artists(track_artist_tag)
+        } else {
+            (Vec::new(), Vec::new())
+        };
+
+        // Canonical artist: first main album artist, or first main track artist
+        let canonical_name = main_album_artists.first()
+            .or(main_track_artists.first())
+            .map(|s| s.as_str())
+            .unwrap_or("Unknown Artist");
+        let album_name = track.album.as_deref().unwrap_or("Unknown Album");
+
+        // Ensure canonical artist exists (cached)
+        let artist_id = ensure_artist_cached(conn, canonical_name, &mut artist_cache).await
+            .map_err(context(&format!("artist '{}'", canonical_name)))?;
+        if artist_id.is_empty() {
+            out.errors += 1;
             continue;
         }
 
-        // Delete cover images for local releases
-        let release_images: Vec<(Option<String>,)> = sqlx::query_as(
-            r#"SELECT image FROM "LocalRelease" WHERE "artistId" = $1"#,
+        // Ensure local release exists (cached)
+        let folder_path = {
+            let parts: Vec<&str> = track.file_path.rsplitn(2, '/').collect();
+            if parts.len() > 1 { Some(parts[1].to_string()) } else { None }
+        };
+        let release_id = ensure_local_release_cached(
+            conn,
+            &artist_id,
+            album_name,
+            track.year,
+            folder_path.as_deref(),
+            &mut release_cache,
         )
-        .bind(artist_id)
-        .fetch_all(pool)
-        .await?;
+        .await
+        .map_err(context(&format!("release '{}'", album_name)))?;
+
+        let track_id = upsert_track(conn, track, &release_id).await.map_err(context("track"))?;
+
+        // Genres: mirror the file's current GENRE values
+        replace_track_genres(conn, &track_id, &track.genres).await.map_err(context("genres"))?;
 
-        for (img,) in &release_images {
-            if let Some(img_path) = img {
-                fs::remove_file(img_path).ok();
+        // Lyrics: mirror the file's current lyrics
+        if ctx.extract_lyrics {
+            replace_track_lyrics(conn, &track_id, &track.lyrics).await.map_err(context("lyrics"))?;
+            out.lyrics += track.lyrics.len() as u64;
+        }
+
+        // TrackArtist: ALBUM_ARTIST role for all main album artists
+        for aa_name in &main_album_artists {
+            let aa_id = ensure_artist_cached(conn, aa_name, &mut artist_cache).await
+                .map_err(context(&format!("artist '{}'", aa_name)))?;
+            if !aa_id.is_empty() {
+                ensure_track_artist(conn, &track_id, &aa_id, "ALBUM_ARTIST").await.map_err(context("credits"))?;
+            }
+        }
+        // If no album artists were split, at least tag the canonical artist
+        if main_album_artists.is_empty() {
+            ensure_track_artist(conn, &track_id, &artist_id, "ALBUM_ARTIST").await.map_err(context("credits"))?;
+        }
+
+        // TrackArtist: PRIMARY role for all main track artists
+        if main_track_artists.is_empty() {
+            // No track artist tag — use canonical artist as PRIMARY
+            ensure_track_artist(conn, &track_id, &artist_id, "PRIMARY").await.map_err(context("credits"))?;
+        } else {
+            for ta_name in &main_track_artists {
+                let ta_id = ensure_artist_cached(conn, ta_name, &mut artist_cache).await
+                    .map_err(context(&format!("artist '{}'", ta_name)))?;
+                if !ta_id.is_empty() {
+                    ensure_track_artist(conn, &track_id, &ta_id, "PRIMARY").await.map_err(context("credits"))?;
+                }
+            }
+        }
+
+        // TrackArtist: FEATURED role for all featured artists (from both tags)
+        let all_featured: Vec<String> = feat_album_artists.iter()
+            .chain(feat_track_artists.iter())
+            .cloned()
+            .collect::<std::collections::HashSet<String>>()
+            .into_iter()
+            .collect();
+        for feat_name in &all_featured {
+            let feat_id = ensure_artist_cached(conn, feat_name, &mut artist_cache).await
+                .map_err(context(&format!("artist '{}'", feat_name)))?;
+            if !feat_id.is_empty() {
+                ensure_track_artist(conn, &track_id, &feat_id, "FEATURED").await.map_err(context("credits"))?;
             }
         }
 
-        // Delete artist image
-        if let Some(img_path) = image {
-            fs::remove_file(img_path).ok();
+        // TrackArtist: COMPOSER / REMIXER / CONDUCTOR for every credited artist
+        for (role, credit_tag) in &track.credits {
+            if is_various_artists(credit_tag) { continue; }
+            let (main, feat) = split_artists(credit_tag);
+            for name in main.iter().chain(feat.iter()) {
+                let credit_id = ensure_artist_cached(conn, name, &mut artist_cache).await
+                    .map_err(context(&format!("artist '{}'", name)))?;
+                if !credit_id.is_empty() {
+                    ensure_track_artist(conn, &track_id, &credit_id, role).await.map_err(context("credits"))?;
+                }
+            }
+        }
+
+        // Track cover art candidates (any track can lead to a sidecar file)
+        let has_art = (track.has_picture && ctx.use_embedded) || ctx.use_sidecar;
+        if has_art && !ctx.skip_images {
+            let out_path = ctx.release_img_dir.join(format!("{}.jpg", release_id));
+
+            if !out_path.exists() {
+                let candidate = ArtCandidate {
+                    path: Path::new(ctx.music_dir).join(track.source_path()),
+                    has_picture: track.has_picture,
+                    picture: track.picture.clone(),
+                };
+                let entry = out.art.entry(release_id.clone()).or_insert_with(|| candidate.clone());
+                if ctx.use_embedded && !entry.has_picture && candidate.has_picture {
+                    *entry = candidate;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Overwrite / nuke
+// ---------------------------------------------------------------------------
+
+async fn nuke_artists(pool: &PgPool, filter: &FolderFilter) -> Result<u64, sqlx::Error> {
+    // Find matching artists
+    let artists: Vec<(String, String)> = sqlx::query_as(
+        r#"SELECT id, name FROM "Artist""#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut deleted = 0u64;
+    for (artist_id, name) in &artists {
+        if !filter.matches(name) {
+            continue;
         }
 
-        // Cascade delete the artist (will cascade to LocalRelease, LocalReleaseTrack, TrackArtist, ArtistUrl)
+        // Cascade delete the artist (will cascade to LocalRelease, LocalReleaseTrack, TrackArtist, ArtistUrl).
+        // The delete triggers queue the artist's images and release covers in S3DeletionQueue for `clean`
         sqlx::query(r#"DELETE FROM "Artist" WHERE id = $1"#)
             .bind(artist_id)
             .execute(pool)
@@ -837,6 +2234,350 @@ async fn nuke_artists(pool: &PgPool, from: &str, to: &str, only: &str) -> Result
     Ok(deleted)
 }
 
+// ---------------------------------------------------------------------------
+// Cross-codec duplicates (--find-codec-dupes)
+// ---------------------------------------------------------------------------
+
+/// Two copies of a track count as the same when their lengths are this close.
+const CODEC_DUPE_TOLERANCE_SECS: i32 = 2;
+
+/// M4A above this bitrate is ALAC, below it AAC.
+const ALAC_MIN_BITRATE: i32 = 500;
+
+/// Suggested deletions, one absolute path per line (overwritten each run).
+const CODEC_DUPES_FILE: &str = "codec-dupes.txt";
+
+/// Codec name and whether it is lossless, from the extension (and bitrate for M4A).
+fn track_codec(file_path: &str, bitrate: Option<i32>) -> (String, bool) {
+    let ext = Path::new(file_path)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "flac" | "wav" | "aiff" | "ape" | "wv" => (ext, true),
+        "m4a" if bitrate.unwrap_or(0) >= ALAC_MIN_BITRATE => ("alac".to_string(), true),
+        "m4a" => ("aac".to_string(), false),
+        _ => (ext, false),
+    }
+}
+
+struct CodecTrack {
+    id: String,
+    file_path: String,
+    duration: i32,
+    bitrate: i32,
+    codec: String,
+    lossless: bool,
+}
+
+/// (id, filePath, title, duration, bitrate, artistId) of a track with its release's artist.
+type CodecRow = (String, String, Option<String>, Option<i32>, Option<i32>, String);
+
+/// A copy to delete and the copy that stays.
+struct CodecDupe<'a> {
+    keep: &'a CodecTrack,
+    drop: &'a CodecTrack,
+}
+
+/// In each group of same-artist, same-title tracks, keep the best copy by
+/// `prefer_lossless` (then bitrate) and pair it with every copy in another
+/// codec whose length is within `CODEC_DUPE_TOLERANCE_SECS`.
+fn find_codec_dupes(groups: &[Vec<CodecTrack>], prefer_lossless: bool) -> Vec<CodecDupe<'_>> {
+    let mut dupes = Vec::new();
+    for tracks in groups {
+        let mut ranked: Vec<&CodecTrack> = tracks.iter().collect();
+        ranked.sort_by_key(|t| (t.lossless != prefer_lossless, std::cmp::Reverse(t.bitrate)));
+        let mut kept: Vec<&CodecTrack> = Vec::new();
+        for track in ranked {
+            let keep = kept.iter().find(|k| {
+                k.codec != track.codec && (k.duration - track.duration).abs() <= CODEC_DUPE_TOLERANCE_SECS
+            });
+            match keep {
+                Some(keep) => dupes.push(CodecDupe { keep, drop: track }),
+                None => kept.push(track),
+            }
+        }
+    }
+    dupes
+}
+
+/// Replace the `CodecDuplicate` table with the library's current cross-codec
+/// pairs and write the suggested deletions to `CODEC_DUPES_FILE`.
+/// Returns (pairs, files in the list).
+async fn detect_codec_dupes(
+    pool: &PgPool,
+    music_dir: &str,
+    prefer_lossless: bool,
+) -> Result<(u64, usize), sqlx::Error> {
+    let rows: Vec<CodecRow> = sqlx::query_as(
+        r#"SELECT t.id, t."filePath", t.title, t.duration, t.bitrate, r."artistId"
+           FROM "LocalReleaseTrack" t
+           JOIN "LocalRelease" r ON t."localReleaseId" = r.id
+           WHERE t.title IS NOT NULL AND t.duration IS NOT NULL
+             AND t."cueStart" IS NULL"#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut by_key: HashMap<(String, String), Vec<CodecTrack>> = HashMap::new();
+    for (id, file_path, title, duration, bitrate, artist_id) in rows {
+        let title = fold_case(title.as_deref().unwrap_or("").trim());
+        if title.is_empty() {
+            continue;
+        }
+        let (codec, lossless) = track_codec(&file_path, bitrate);
+        by_key.entry((artist_id, title)).or_default().push(CodecTrack {
+            id,
+            file_path,
+            duration: duration.unwrap_or(0),
+            bitrate: bitrate.unwrap_or(0),
+            codec,
+            lossless,
+        });
+    }
+    let groups: Vec<Vec<CodecTrack>> = by_key.into_values().filter(|g| g.len() > 1).collect();
+    let dupes = find_codec_dupes(&groups, prefer_lossless);
+
+    let mut tx = pool.begin().await?;
+    sqlx::query(r#"DELETE FROM "CodecDuplicate""#).execute(&mut *tx).await?;
+    for dupe in &dupes {
+        sqlx::query(
+            r#"INSERT INTO "CodecDuplicate" (id, "keepTrackId", "dropTrackId", "durationDelta", "createdAt")
+               VALUES ($1, $2, $3, $4, NOW())
+               ON CONFLICT ("keepTrackId", "dropTrackId") DO NOTHING"#,
+        )
+        .bind(cuid2::create_id())
+        .bind(&dupe.keep.id)
+        .bind(&dupe.drop.id)
+        .bind(dupe.drop.duration - dupe.keep.duration)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+
+    let mut to_delete: Vec<String> = dupes.iter()
+        .map(|d| format!("{}/{}", music_dir, d.drop.file_path))
+        .collect();
+    to_delete.sort();
+    to_delete.dedup();
+    let mut list = to_delete.join("\n");
+    if !list.is_empty() {
+        list.push('\n');
+    }
+    fs::write(CODEC_DUPES_FILE, list).ok();
+
+    Ok((dupes.len() as u64, to_delete.len()))
+}
+
+// ---------------------------------------------------------------------------
+// QC report (--qc-report)
+// ---------------------------------------------------------------------------
+
+/// One artist folder's row on the QC report.
+struct QcFolder {
+    folder: String,
+    new: u64,
+    updated: u64,
+    skipped: u64,
+    filled: u64,
+    errors: u64,
+}
+
+/// What the run did, collected as it goes for `--qc-report`. Paths are
+/// relative to the music dir.
+#[derive(Default)]
+struct QcReport {
+    /// In walk order
+    folders: Vec<QcFolder>,
+    /// Files dropped for having no artist tag
+    missing_artist: Vec<String>,
+    /// Releases without a cover after this run: release id -> the track it was taken from
+    cover_failures: HashMap<String, String>,
+    errors: Vec<String>,
+}
+
+/// Write `index_<timestamp>/` under `dir`: a report in the analysis style with
+/// the run's counts and one row per artist folder on the overview, and a page
+/// each for missing artist tags, cover failures and errors. `details` describes
+/// the run (music dir, filters, ...). Returns the report directory.
+fn write_qc_report(dir: &str, qc: &QcReport, details: &[(&str, String)]) -> std::io::Result<PathBuf> {
+    use dmp_common::report::{Entry, Groups, NavItem, Panel, Report, SearchTarget, StatCard};
+
+    let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+    let report_dir = PathBuf::from(dir).join(format!("index_{}", timestamp));
+
+    fn by_folder<'a>(paths: impl Iterator<Item = &'a String>) -> Groups {
+        let mut groups = Groups::new();
+        for path in paths {
+            let folder = path.split_once('/').map_or("(music dir)", |(folder, _)| folder);
+            groups.entry(folder.to_string()).or_default().push(Entry::new(path.clone()));
+        }
+        for entries in groups.values_mut() {
+            entries.sort_by(|a, b| a.path.cmp(&b.path));
+        }
+        groups
+    }
+    let missing_artist = by_folder(qc.missing_artist.iter());
+    let covers = by_folder(qc.cover_failures.values());
+
+    let sum = |f: fn(&QcFolder) -> u64| qc.folders.iter().map(f).sum::<u64>();
+    let (new, updated, skipped, filled) = (sum(|f| f.new), sum(|f| f.updated), sum(|f| f.skipped), sum(|f| f.filled));
+
+    let nav = vec![
+        NavItem::overview(),
+        NavItem::page("missing_artist", "Missing Artist", "missing_artist_1.html", qc.missing_artist.len()),
+        NavItem::page("covers", "Cover Failures", "covers_1.html", qc.cover_failures.len()),
+        NavItem::page("errors", "Errors", "errors.html", qc.errors.len()),
+    ];
+    let report = Report::create(&report_dir, "DMP Index QC", nav)?;
+
+    let mut index = report.index_page("Overview", None)?;
+    let warn_if = |n: usize| if n > 0 { "warn" } else { "ok" };
+    index.write_stats(&[
+        vec![
+            StatCard::new("New", new, "ok"),
+            StatCard::new("Updated", updated, "info"),
+            StatCard::new("Skipped", skipped, "info"),
+            StatCard::new("Filled In", filled, "info"),
+        ],
+        vec![
+            StatCard::new("Missing Artist", qc.missing_artist.len(), warn_if(qc.missing_artist.len())),
+            StatCard::new("Cover Failures", qc.cover_failures.len(), warn_if(qc.cover_failures.len())),
+            StatCard::new("Errors", qc.errors.len(), if qc.errors.is_empty() { "ok" } else { "fail" }),
+        ],
+    ])?;
+    let details: Vec<(&str, &str)> = details.iter().map(|(field, value)| (*field, value.as_str())).collect();
+    index.write_details("Run", &details)?;
+    report.write_breakdown(&mut index, "Breakdown", "Files")?;
+    index.write_search_box(SearchTarget::Table)?;
+    let counts: Vec<[String; 5]> = qc.folders.iter()
+        .map(|f| [f.new, f.updated, f.skipped, f.filled, f.errors].map(|n| n.to_string()))
+        .collect();
+    let rows: Vec<Vec<&str>> = qc.folders.iter().zip(&counts)
+        .map(|(f, c)| std::iter::once(f.folder.as_str()).chain(c.iter().map(String::as_str)).collect())
+        .collect();
+    index.write_table("Artist Folders", "mirror", &["Artist folder", "New", "Updated", "Skipped", "Filled in", "Errors"], &rows)?;
+    index.finish()?;
+
+    report.write_grouped_pages("missing_artist", "Missing Artist", &[Panel::new("files", "No artist tag", missing_artist)])?;
+    report.write_grouped_pages("covers", "Cover Failures", &[Panel::new("releases", "No cover found", covers)])?;
+
+    let mut errors = report.page("errors.html", "Errors", "errors", None)?;
+    errors.write_search_box(SearchTarget::Table)?;
+    let rows: Vec<Vec<&str>> = qc.errors.iter().map(|e| vec![e.as_str()]).collect();
+    errors.write_table("Errors", "mirror", &["Error"], &rows)?;
+    errors.finish()?;
+
+    Ok(report_dir)
+}
+
+// ---------------------------------------------------------------------------
+// Unicode repair (--repair-unicode)
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Default)]
+struct UnicodeRepair {
+    artists_merged: u64,
+    releases_merged: u64,
+    renamed: u64,
+}
+
+/// Merge artists whose names fold to the same slug and releases of one artist
+/// whose titles differ only in Unicode normalization (the indexer used to store
+/// tags as found, so NFD and NFC spellings became separate rows), then rewrite
+/// the remaining names and titles to NFC.
+async fn repair_unicode_duplicates(pool: &PgPool) -> Result<UnicodeRepair, sqlx::Error> {
+    let mut repair = UnicodeRepair::default();
+    let mut tx = pool.begin().await?;
+
+    // Artists: the one with an MB ID wins, then the one with the most tracks
+    let artists: Vec<(String, String, String)> = sqlx::query_as(
+        r#"SELECT id, name, slug FROM "Artist"
+           ORDER BY ("musicbrainzId" IS NULL), "totalTracks" DESC, "createdAt""#,
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    let mut keepers: HashMap<String, String> = HashMap::new();
+    let mut artist_renames: Vec<(String, String, String)> = Vec::new();
+    for (id, name, slug) in &artists {
+        let key = artist_slug(name);
+        if key.is_empty() {
+            continue;
+        }
+        match keepers.entry(key.clone()) {
+            Entry::Occupied(keep) => {
+                merge_artist(&mut tx, keep.get(), id).await?;
+                repair.artists_merged += 1;
+            }
+            Entry::Vacant(e) => {
+                e.insert(id.clone());
+                let normalized = nfc(name);
+                if normalized != *name || key != *slug {
+                    artist_renames.push((id.clone(), normalized, key));
+                }
+            }
+        }
+    }
+    // Renamed after the merges, when the slug is no longer taken by a duplicate
+    for (id, name, slug) in &artist_renames {
+        repair.renamed += sqlx::query(
+            r#"UPDATE "Artist" SET name = $1, slug = $2, "updatedAt" = NOW()
+               WHERE id = $3 AND NOT EXISTS (SELECT 1 FROM "Artist" WHERE slug = $2 AND id <> $3)"#,
+        )
+        .bind(name)
+        .bind(slug)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    }
+
+    // Releases of one artist: the one already linked to MB wins, then the oldest
+    let releases: Vec<(String, String, String)> = sqlx::query_as(
+        r#"SELECT id, "artistId", title FROM "LocalRelease"
+           ORDER BY ("releaseId" IS NULL), "createdAt""#,
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    let mut release_keepers: HashMap<(String, String), String> = HashMap::new();
+    let mut release_renames: Vec<(String, String)> = Vec::new();
+    for (id, artist_id, title) in &releases {
+        let normalized = nfc(title);
+        match release_keepers.entry((artist_id.clone(), normalized.clone())) {
+            Entry::Occupied(keep) => {
+                sqlx::query(r#"UPDATE "LocalReleaseTrack" SET "localReleaseId" = $1 WHERE "localReleaseId" = $2"#)
+                    .bind(keep.get())
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+                sqlx::query(r#"DELETE FROM "LocalRelease" WHERE id = $1"#)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+                repair.releases_merged += 1;
+            }
+            Entry::Vacant(e) => {
+                e.insert(id.clone());
+                if normalized != *title {
+                    release_renames.push((id.clone(), normalized));
+                }
+            }
+        }
+    }
+    // Renamed after the merges, when the NFC title is no longer taken
+    for (id, title) in &release_renames {
+        repair.renamed += sqlx::query(r#"UPDATE "LocalRelease" SET title = $1, "updatedAt" = NOW() WHERE id = $2"#)
+            .bind(title)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+    }
+
+    tx.commit().await?;
+    Ok(repair)
+}
+
 // ---------------------------------------------------------------------------
 // Checkpoint
 // ---------------------------------------------------------------------------
@@ -846,10 +2587,9 @@ async fn save_checkpoint(
     last_folder: &str,
     files_processed: i32,
     music_dir: &str,
-    from: &str,
-    to: &str,
-    only: &str,
+    filter: &FolderFilter,
 ) -> Result<(), sqlx::Error> {
+    let (from, to, only) = (filter.from(), filter.to(), filter.only());
     let now = Utc::now().naive_utc();
     sqlx::query(
         r#"INSERT INTO "IndexCheckpoint" (id, "lastFolder", "filesProcessed", "musicDir", "filterFrom", "filterTo", "filterOnly", "createdAt", "updatedAt")
@@ -887,6 +2627,18 @@ async fn clear_checkpoint(pool: &PgPool) -> Result<(), sqlx::Error> {
     Ok(())
 }
 
+/// Sync's checkpoint (last artist slug, artists processed, last update), which
+/// exists while a sync is in progress or after one was interrupted.
+async fn load_sync_checkpoint(pool: &PgPool) -> Result<Option<(String, i32, NaiveDateTime)>, sqlx::Error> {
+    let row: Option<(Option<String>, i32, NaiveDateTime)> = sqlx::query_as(
+        r#"SELECT "lastArtistSlug", "artistsProcessed", "updatedAt" FROM "SyncCheckpoint" WHERE id = 'main'"#,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|(slug, count, updated_at)| (slug.unwrap_or_default(), count, updated_at)))
+}
+
 // ---------------------------------------------------------------------------
 // Post-processing: update release and artist totals
 // ---------------------------------------------------------------------------
@@ -912,6 +2664,92 @@ async fn update_release_totals(pool: &PgPool) -> Result<u64, sqlx::Error> {
     Ok(result.rows_affected())
 }
 
+/// Replace inferred and backfilled years with a real tagged year once one
+/// shows up on any of the release's tracks (e.g. after the files were retagged).
+async fn promote_tagged_years(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        r#"UPDATE "LocalRelease" lr SET
+             year = sub.tagged_year,
+             "yearInferred" = false,
+             "yearBackfilled" = false,
+             "updatedAt" = NOW()
+           FROM (
+             SELECT "localReleaseId", MIN(year) as tagged_year
+             FROM "LocalReleaseTrack"
+             WHERE "localReleaseId" IS NOT NULL AND year > 0
+             GROUP BY "localReleaseId"
+           ) sub
+           WHERE lr.id = sub."localReleaseId"
+             AND (lr."yearInferred" = true OR lr."yearBackfilled" = true)"#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Set each release's original year to the earliest one tagged on its tracks,
+/// or clear it when none has one anymore. Unlike `year`, it is never inferred.
+async fn update_original_years(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        r#"UPDATE "LocalRelease" lr SET
+             "originalYear" = sub.original_year,
+             "updatedAt" = NOW()
+           FROM (
+             SELECT r.id, MIN(t."originalYear") as original_year
+             FROM "LocalRelease" r
+             LEFT JOIN "LocalReleaseTrack" t ON t."localReleaseId" = r.id
+             GROUP BY r.id
+           ) sub
+           WHERE lr.id = sub.id AND lr."originalYear" IS DISTINCT FROM sub.original_year"#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Copy the year of the linked MusicBrainz release onto releases without a
+/// tagged one, flagged with `yearBackfilled`. Mtime approximations are
+/// replaced too, and backfilled years follow later MusicBrainz corrections.
+async fn backfill_release_years(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        r#"UPDATE "LocalRelease" lr SET
+             year = mr.year,
+             "yearBackfilled" = true,
+             "yearInferred" = false,
+             "updatedAt" = NOW()
+           FROM "MusicBrainzRelease" mr
+           WHERE lr."releaseId" = mr.id
+             AND mr.year IS NOT NULL
+             AND (lr.year IS NULL
+                  OR lr."yearInferred" = true
+                  OR (lr."yearBackfilled" = true AND lr.year <> mr.year))"#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Approximate the year of releases without one from the earliest file mtime
+/// in the release. Flagged with `yearInferred` so the UI can tell them apart.
+async fn infer_release_years(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        r#"UPDATE "LocalRelease" lr SET
+             year = EXTRACT(YEAR FROM sub.earliest)::int,
+             "yearInferred" = true,
+             "updatedAt" = NOW()
+           FROM (
+             SELECT "localReleaseId", MIN(mtime) as earliest
+             FROM "LocalReleaseTrack"
+             WHERE "localReleaseId" IS NOT NULL AND mtime IS NOT NULL
+             GROUP BY "localReleaseId"
+           ) sub
+           WHERE lr.id = sub."localReleaseId" AND lr.year IS NULL"#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
 async fn update_artist_totals(pool: &PgPool) -> Result<u64, sqlx::Error> {
     let result = sqlx::query(
         r#"UPDATE "Artist" a SET
@@ -996,6 +2834,219 @@ async fn update_statistics(pool: &PgPool) -> Result<(), sqlx::Error> {
     Ok(())
 }
 
+// ---------------------------------------------------------------------------
+// Maintenance (--maintenance)
+// ---------------------------------------------------------------------------
+
+/// Tables index inserts into, updates or deletes from (besides its own
+/// checkpoint and the statistics rows).
+const MAINTENANCE_TABLES: &[&str] = &[
+    "Artist",
+    "_ArtistGenres",
+    "LocalRelease",
+    "LocalReleaseTrack",
+    "TrackArtist",
+    "TrackGenre",
+    "TrackLyrics",
+    "CodecDuplicate",
+];
+
+/// Exact row count of each maintenance table. Tables the database doesn't
+/// have (an older schema) are left out.
+async fn count_table_rows(pool: &PgPool) -> Vec<(&'static str, i64)> {
+    let mut counts = Vec::new();
+    for table in MAINTENANCE_TABLES {
+        let sql = format!(r#"SELECT COUNT(*)::bigint FROM "{}""#, table);
+        if let Ok((rows,)) = sqlx::query_as::<_, (i64,)>(&sql).fetch_one(pool).await {
+            counts.push((*table, rows));
+        }
+    }
+    counts
+}
+
+/// Refresh the planner statistics of `tables`. Autovacuum only gets to a
+/// table once enough of it changed, and until then a big import leaves the
+/// web app's and sync's joins planned for the old row counts.
+async fn analyze_tables(pool: &PgPool, tables: &[&str]) -> Result<(), sqlx::Error> {
+    for table in tables {
+        sqlx::query(&format!(r#"ANALYZE "{}""#, table)).execute(pool).await?;
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Library root fingerprint
+// ---------------------------------------------------------------------------
+
+/// Artist folder names kept in `LibraryRoot.sampleFolders`.
+const ROOT_SAMPLE: usize = 200;
+
+/// Share of the stored sample that must still be in the music dir; below it
+/// the dir is taken for another library (wrong drive, wrong mount point).
+const ROOT_MIN_OVERLAP: f64 = 0.5;
+
+/// Top-level folder names of the music dir, sorted.
+fn root_folders(music_dir: &str) -> Vec<String> {
+    let mut folders: Vec<String> = fs::read_dir(music_dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter(|e| e.path().is_dir())
+                .map(|e| e.file_name().to_string_lossy().into_owned())
+                .collect()
+        })
+        .unwrap_or_default();
+    folders.sort();
+    folders
+}
+
+/// Up to `ROOT_SAMPLE` names spread evenly over the sorted folders, so a
+/// library that grows at one end keeps most of its sample.
+fn sample_folders(folders: &[String]) -> Vec<String> {
+    let step = folders.len().div_ceil(ROOT_SAMPLE).max(1);
+    folders.iter().step_by(step).cloned().collect()
+}
+
+/// What the last run saw in the music dir: (music dir, folder count, sample).
+async fn load_library_root(pool: &PgPool) -> Result<Option<(String, i32, Vec<String>)>, sqlx::Error> {
+    sqlx::query_as(
+        r#"SELECT "musicDir", "folderCount", "sampleFolders" FROM "LibraryRoot" WHERE id = 'main'"#,
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+async fn save_library_root(pool: &PgPool, music_dir: &str, folders: &[String]) -> Result<(), sqlx::Error> {
+    let now = Utc::now().naive_utc();
+    sqlx::query(
+        r#"INSERT INTO "LibraryRoot" (id, "musicDir", "folderCount", "sampleFolders", "createdAt", "updatedAt")
+           VALUES ('main', $1, $2, $3, $4, $4)
+           ON CONFLICT (id) DO UPDATE SET
+             "musicDir" = $1, "folderCount" = $2, "sampleFolders" = $3, "updatedAt" = $4"#,
+    )
+    .bind(music_dir)
+    .bind(folders.len() as i32)
+    .bind(sample_folders(folders))
+    .bind(now)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Compare the music dir with the library indexed last time. Returns an error
+/// describing the mismatch when too few of the remembered folders are there;
+/// otherwise (or with `accept`) records the current folders for the next run.
+async fn check_library_root(pool: &PgPool, music_dir: &str, accept: bool) -> Result<(), String> {
+    let folders = root_folders(music_dir);
+    // An empty root got past the preflight with --allow-empty-root: nothing to compare, nothing worth remembering
+    if folders.is_empty() {
+        return Ok(());
+    }
+    let stored = load_library_root(pool).await.map_err(|e| format!("Can't read LibraryRoot ({}). Apply the add_library_root.sql migration", e))?;
+    if let Some((stored_dir, stored_count, sample)) = stored.filter(|_| !accept) {
+        let present: HashSet<&str> = folders.iter().map(String::as_str).collect();
+        let found = sample.iter().filter(|f| present.contains(f.as_str())).count();
+        if !sample.is_empty() && (found as f64) < sample.len() as f64 * ROOT_MIN_OVERLAP {
+            return Err(format!(
+                "{} doesn't look like the library indexed last time ({}, {} artist folders): only {} of {} remembered folders are here, next to {} folders. Is the right drive mounted? Pass --accept-new-root to index it anyway",
+                music_dir, stored_dir, stored_count, found, sample.len(), folders.len()
+            ));
+        }
+    }
+    save_library_root(pool, music_dir, &folders).await.map_err(|e| format!("Can't write LibraryRoot: {}", e))
+}
+
+// ---------------------------------------------------------------------------
+// Permission preflight
+// ---------------------------------------------------------------------------
+
+/// Artist folders opened by the preflight: enough to tell "nothing can be
+/// read" from "a few folders are locked" without walking the library.
+const PREFLIGHT_SAMPLE: usize = 50;
+
+/// Problems found before indexing starts. `fatal` ones would fail every file
+/// or image the same way, so the run stops before it touches the database.
+#[derive(Default)]
+struct Preflight {
+    fatal: Vec<String>,
+    warnings: Vec<String>,
+}
+
+/// Check the music dir can be listed (and a sample of artist folders read),
+/// and that every local folder the run writes to accepts files. `outputs` are
+/// (folder, what needs it); the working directory is always checked for
+/// `errors.log`.
+fn preflight(music_dir: &str, filter: &FolderFilter, outputs: &[(PathBuf, &str)], allow_empty_root: bool) -> Preflight {
+    let mut result = Preflight::default();
+
+    match fs::read_dir(music_dir) {
+        Ok(entries) => {
+            let all: Vec<PathBuf> = entries
+                .filter_map(|e| e.ok())
+                .filter(|e| e.path().is_dir())
+                .map(|e| e.path())
+                .collect();
+            let mut folders: Vec<PathBuf> = all.iter()
+                .filter(|p| p.file_name().is_some_and(|n| filter.matches(&n.to_string_lossy())))
+                .cloned()
+                .collect();
+            folders.sort();
+            folders.truncate(PREFLIGHT_SAMPLE);
+            let locked: Vec<&PathBuf> = folders.iter().filter(|f| fs::read_dir(f).is_err()).collect();
+            if all.is_empty() && !allow_empty_root {
+                // An unmounted drive leaves its empty mount point behind: indexing
+                // it would look like every file was deleted
+                result.fatal.push(format!(
+                    "{} has no artist folders. Is the drive mounted? Pass --allow-empty-root if the library really is empty",
+                    music_dir
+                ));
+            } else if folders.is_empty() {
+                result.warnings.push(format!(
+                    "{} has no artist folders{} (is the drive mounted?)",
+                    music_dir,
+                    if filter.is_empty() { "" } else { " matching the filter" }
+                ));
+            } else if locked.len() == folders.len() {
+                result.fatal.push(format!(
+                    "None of the {} artist folders checked in {} can be read. Give the user running the indexer read and execute permission on the library (e.g. chmod -R a+rX, or the mount's uid/gid options)",
+                    folders.len(), music_dir
+                ));
+            } else if !locked.is_empty() {
+                result.warnings.push(format!(
+                    "{} of {} artist folders checked can't be read (e.g. {}); their files will be skipped",
+                    locked.len(), folders.len(), locked[0].display()
+                ));
+            }
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => result.fatal.push(format!(
+            "Music dir {} doesn't exist. Pass the path as the first argument or set MUSIC_DIR in web/.env", music_dir
+        )),
+        Err(e) => result.fatal.push(format!(
+            "Can't list music dir {}: {}. The indexer only reads it, so read-only mounts are fine, but it needs read and execute permission", music_dir, e
+        )),
+    }
+
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let mut targets: Vec<(PathBuf, &str)> = outputs.to_vec();
+    targets.push((cwd, "errors.log"));
+    for (dir, purpose) in &targets {
+        let probe = dir.join(".dmp-preflight");
+        let written = fs::create_dir_all(dir).and_then(|_| fs::write(&probe, b"")).and_then(|_| fs::remove_file(&probe));
+        if let Err(e) = written {
+            result.fatal.push(format!(
+                "Can't write {} to {}: {}. Fix the folder's owner or permissions{}",
+                purpose, dir.display(), e,
+                match *purpose {
+                    "cover art" => ", set IMAGE_STORAGE=s3, or pass --skip-images",
+                    "artist placeholders" => ", set IMAGE_STORAGE=s3, or drop --placeholders",
+                    _ => ", or run the indexer from a writable directory",
+                }
+            ));
+        }
+    }
+    result
+}
+
 // ---------------------------------------------------------------------------
 // Main
 // ---------------------------------------------------------------------------
@@ -1003,6 +3054,14 @@ async fn update_statistics(pool: &PgPool) -> Result<(), sqlx::Error> {
 #[tokio::main]
 async fn main() {
     let args = Args::parse();
+    if args.no_color {
+        colored::control::set_override(false);
+    }
+    // Bars go to stderr: redrawn in place on a terminal, one final line per phase in a log
+    let progress = Progress::new(
+        ProgressMode::detect(args.quiet),
+        colored::control::SHOULD_COLORIZE.should_colorize(),
+    );
     let config = load_config(&args.music_dir);
     let music_dir = config.music_dir.trim_end_matches('/').to_string();
 
@@ -1020,8 +3079,9 @@ async fn main() {
     println!("{}", "===========".bright_black());
     println!("Music dir     : {}", music_dir.bright_white());
     println!("Image storage : {}", config.image_storage.bright_white());
-    if !args.only.is_empty() {
-        println!("Filter        : only '{}'", args.only.bright_white());
+    // Also shown on the QC report
+    let filter_desc = if !args.only.is_empty() {
+        Some(format!("only '{}'", args.only))
     } else if !args.from.is_empty() || !args.to.is_empty() {
         let from_str = if args.from.is_empty() {
             "A".to_string()
@@ -1033,7 +3093,12 @@ async fn main() {
         } else {
             args.to.to_uppercase()
         };
-        println!("Filter        : {} to {}", from_str.bright_white(), to_str.bright_white());
+        Some(format!("{} to {}", from_str, to_str))
+    } else {
+        None
+    };
+    if let Some(ref filter) = filter_desc {
+        println!("Filter        : {}", filter.bright_white());
     }
     if args.limit > 0 {
         println!("Limit         : {} files", args.limit.to_string().bright_white());
@@ -1041,15 +3106,82 @@ async fn main() {
     if args.resume {
         println!("Mode          : {}", "resume from checkpoint".yellow());
     }
+    if let Some(ref list) = args.paths_from {
+        let source = if list == "-" { "stdin" } else { list.as_str() };
+        println!("Mode          : files listed in {} (no directory walk)", source.bright_white());
+    }
+    if let Some(ref dir) = args.qc_report {
+        println!("QC report     : {}", dir.bright_white());
+    }
     if args.overwrite {
         println!("Mode          : {}", "overwrite (nuke + re-index)".red());
     }
+    if args.repair_unicode {
+        println!("Mode          : {}", "repair Unicode duplicates".yellow());
+    }
+    let art_priority = ArtSource::parse_list(&args.art_priority);
     if args.skip_images {
         println!("Images        : {}", "skipped".yellow());
+    } else if art_priority != [ArtSource::Embedded, ArtSource::Sidecar] {
+        println!("Cover art     : {}", args.art_priority.join(" > ").bright_white());
+    }
+    if args.fast || args.no_hash || args.no_properties {
+        let mut skipped = Vec::new();
+        if args.fast || args.no_hash { skipped.push("hashes"); }
+        if args.fast || args.no_properties { skipped.push("properties"); }
+        println!("Fast import   : skipping {}", skipped.join(", ").yellow());
+    }
+    if args.no_metadata_json || args.fast {
+        println!("Metadata JSON : {}", "disabled".yellow());
+    } else if !args.metadata_exclude.is_empty() {
+        println!("Metadata JSON : excluding {}", args.metadata_exclude.join(", ").bright_white());
+    }
+    if args.case_sensitive_paths {
+        println!("Paths         : {}", "case-sensitive".bright_white());
+    }
+    if args.placeholders {
+        println!("Placeholders  : {}", "artists without image".bright_white());
+    }
+    if args.extract_lyrics {
+        println!("Lyrics        : {}", "TrackLyrics".bright_white());
+    }
+    if args.find_codec_dupes {
+        println!("Codec dupes   : keep {}", args.prefer.bright_white());
+    }
+    if args.maintenance {
+        println!("Maintenance   : {}", "ANALYZE written tables".bright_white());
     }
     println!("Threads       : {}", thread_count.to_string().bright_white());
+    println!("Write tasks   : {}", args.write_tasks.to_string().bright_white());
     println!();
 
+    // --- Preflight: fail on permissions before thousands of identical per-file errors ---
+    if !args.repair_unicode {
+        let use_local = config.image_storage == "local" || config.image_storage == "both";
+        let img_root = PathBuf::from(&config.project_root).join("web/public/img");
+        let mut outputs = Vec::new();
+        if use_local && !args.skip_images {
+            outputs.push((img_root.join("releases"), "cover art"));
+        }
+        if use_local && args.placeholders {
+            outputs.push((img_root.join("artists"), "artist placeholders"));
+        }
+        let filter = FolderFilter::new(&args.only, &args.from, &args.to);
+        let check = preflight(&music_dir, &filter, &outputs, args.allow_empty_root);
+        for warning in &check.warnings {
+            println!("{} {}", "⚠".yellow(), warning);
+        }
+        for problem in &check.fatal {
+            println!("{} {}", "✗".red(), problem);
+        }
+        if !check.fatal.is_empty() {
+            std::process::exit(1);
+        }
+        if !check.warnings.is_empty() {
+            println!();
+        }
+    }
+
     // Connect to database
     let pool = PgPoolOptions::new()
         .max_connections(20)
@@ -1057,15 +3189,67 @@ async fn main() {
         .await
         .expect("Failed to connect to database. Is PostgreSQL running?");
 
+    // Only one DMP script (index, sync, clean, nuke) writes at a time
+    let mode = LockMode::from_flags(args.wait, args.steal_lock);
+    let _run_lock = match RunLock::acquire(&config.database_url, "index", mode).await {
+        Ok(lock) => lock,
+        Err(e) => {
+            println!("{} {}", "✗".red(), e);
+            std::process::exit(1);
+        }
+    };
+
+    // --- Safeguard: don't change releases under a running sync ---
+    if let Ok(Some((slug, artists, updated_at))) = load_sync_checkpoint(&pool).await {
+        println!("{} Sync has a checkpoint: artist '{}', {} artists, updated {}",
+            "⚠".yellow(), slug.bright_white(), artists, checkpoint_age(updated_at));
+        if !args.force {
+            println!("  Indexing now would change releases while sync is matching them.");
+            println!("  Let sync finish (or `sync --resume` an interrupted run), or pass --force.");
+            std::process::exit(1);
+        }
+        println!("  {} --force: indexing anyway", "→".bright_black());
+        println!();
+    }
+
+    // --- Repair: merge rows duplicated by NFC/NFD tags, then exit ---
+    if args.repair_unicode {
+        println!("{} Merging Unicode duplicates...", "[repair]".bright_blue().bold());
+        match repair_unicode_duplicates(&pool).await {
+            Ok(repair) => {
+                update_release_totals(&pool).await.ok();
+                update_artist_totals(&pool).await.ok();
+                update_statistics(&pool).await.ok();
+                println!("  {} Merged {} artists and {} releases, normalized {} names",
+                    "✓".green(),
+                    repair.artists_merged.to_string().bright_white(),
+                    repair.releases_merged.to_string().bright_white(),
+                    repair.renamed.to_string().bright_white()
+                );
+            }
+            Err(e) => {
+                eprintln!("  {} Repair failed (nothing changed): {}", "✗".red(), format!("{}", e).red());
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    // --- Safeguard: the music dir must be the library the database describes ---
+    if let Err(e) = check_library_root(&pool, &music_dir, args.accept_new_root).await {
+        println!("{} {}", "✗".red(), e);
+        std::process::exit(1);
+    }
+
     let start = Instant::now();
-    let from_filter = args.from.to_lowercase();
-    let to_filter = args.to.to_lowercase();
-    let only_filter = args.only.to_lowercase();
+    let folder_filter = FolderFilter::new(&args.only, &args.from, &args.to);
+    // Before --overwrite, so its deletions show in the deltas
+    let rows_before = if args.maintenance { Some(count_table_rows(&pool).await) } else { None };
 
     // --- Overwrite: nuke matching data first ---
     if args.overwrite {
         println!("{} Nuking matching data...", "[0]".red().bold());
-        match nuke_artists(&pool, &from_filter, &to_filter, &only_filter).await {
+        match nuke_artists(&pool, &folder_filter).await {
             Ok(count) => println!("  {} Deleted {} artists and all related data", "✓".green(), count.to_string().bright_white()),
             Err(e) => {
                 eprintln!("  {} Error during nuke: {}", "✗".red(), format!("{}", e).red());
@@ -1093,97 +3277,115 @@ async fn main() {
             }
         }
     } else {
-        clear_checkpoint(&pool).await.ok();
+        // A file list run leaves an interrupted full run's checkpoint alone
+        if args.paths_from.is_none() {
+            clear_checkpoint(&pool).await.ok();
+        }
         None
     };
 
-    // --- Phase 1: Walk directory tree ---
-    println!("{} Walking directory tree...", "[1/4]".bright_blue().bold());
-    let extensions = ["mp3", "m4a", "opus", "aac", "ogg", "flac"];
+    // --- Phase 1: Walk directory tree (or read --paths-from) ---
     let total_dirs = AtomicU64::new(0);
     let music_dir_clone = music_dir.clone();
-    let last_walk_folder: Mutex<String> = Mutex::new(String::new());
-
-    let from_filter_clone = from_filter.clone();
-    let to_filter_clone = to_filter.clone();
-    let only_filter_clone = only_filter.clone();
-    
-    let paths: Vec<PathBuf> = WalkDir::new(&music_dir)
-        .follow_links(true)
-        .sort_by_file_name()
-        .into_iter()
-        .filter_entry(|e| {
-            // For the root directory, always enter
-            if e.depth() == 0 {
-                return true;
+    let metadata_opts = MetadataOptions::from_args(&args);
+    let fast_opts = FastOptions::from_args(&args);
+    // Keep embedded pictures for the cover art phase, which would otherwise read every source file again
+    let picture_cache = (!args.skip_images && art_priority.contains(&ArtSource::Embedded))
+        .then(PictureCache::default);
+
+    let paths: Vec<PathBuf> = if let Some(ref source) = args.paths_from {
+        println!("{} Reading file list...", "[1/4]".bright_blue().bold());
+        let text = match read_path_list_source(source) {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("  {} Cannot read {}: {}", "✗".red(), source, e);
+                std::process::exit(1);
             }
-            
-            // For artist folders (depth 1), check if they match the filter
-            if e.depth() == 1 && e.file_type().is_dir() {
-                let folder = e.file_name().to_string_lossy().to_string();
-                let matches = matches_filter(&folder, &from_filter_clone, &to_filter_clone, &only_filter_clone);
-                
-                // Show progress for matching folders
-                if matches {
-                    let dir_count = total_dirs.fetch_add(1, Ordering::Relaxed) + 1;
-                    if dir_count % 10 == 0 || dir_count == 1 {
-                        let mut last = last_walk_folder.lock().unwrap();
-                        if *last != folder {
-                            eprint!(
-                                "\r  {} {} ({} folders)",
-                                "→".bright_black(),
-                                format!("Scanning: {:<40}", folder).bright_cyan(),
-                                dir_count
-                            );
-                            *last = folder.clone();
-                        }
+        };
+        let list = read_path_list(&text, &music_dir, &folder_filter);
+        for (line, reason) in &list.skipped {
+            println!("  {} {} ({})", "↷".yellow(), line, reason);
+        }
+        let folders: HashSet<String> = list.paths.iter().map(|p| get_artist_folder(p, &music_dir)).collect();
+        total_dirs.store(folders.len() as u64, Ordering::Relaxed);
+        list.paths.into_iter().take(if args.limit > 0 { args.limit } else { usize::MAX }).collect()
+    } else {
+        println!("{} Walking directory tree...", "[1/4]".bright_blue().bold());
+        let walk_bar = progress.add("Walking", None, "folders");
+        let walked: Vec<PathBuf> = WalkDir::new(&music_dir)
+            .follow_links(true)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|e| {
+                // For the root directory, always enter
+                if e.depth() == 0 {
+                    return true;
+                }
+
+                // For artist folders (depth 1), check if they match the filter
+                if e.depth() == 1 && e.file_type().is_dir() {
+                    let folder = e.file_name().to_string_lossy().to_string();
+                    let matches = folder_filter.matches(&folder);
+
+                    // Show progress for matching folders
+                    if matches {
+                        total_dirs.fetch_add(1, Ordering::Relaxed);
+                        walk_bar.set_message(&folder);
+                        walk_bar.inc(1);
                     }
+
+                    // Skip this entire directory tree if it doesn't match
+                    return matches;
                 }
-                
-                // Skip this entire directory tree if it doesn't match
-                return matches;
-            }
-            
-            // For deeper levels, always enter (we already filtered at artist level)
-            true
-        })
-        .filter_map(|e| e.ok())
-        .filter(|e| {
-            // Skip directories in the final collection
-            if e.file_type().is_dir() {
-                return false;
-            }
 
-            let folder = get_artist_folder(e.path(), &music_dir_clone);
-            
-            // Resume: skip folders already processed
-            if let Some(ref resume_f) = resume_folder {
-                if folder.to_lowercase() <= resume_f.to_lowercase() {
+                // For deeper levels, always enter (we already filtered at artist level)
+                true
+            })
+            .filter_map(|e| e.ok())
+            .filter(|e| {
+                // Skip directories in the final collection
+                if e.file_type().is_dir() {
                     return false;
                 }
-            }
 
-            // Check file extension
-            if let Some(ext) = e.path().extension() {
-                let ext_lower = ext.to_string_lossy().to_lowercase();
-                extensions.contains(&ext_lower.as_str())
-            } else {
-                false
-            }
-        })
-        .map(|e| e.into_path())
-        .take(if args.limit > 0 { args.limit } else { usize::MAX })
-        .collect();
+                let folder = get_artist_folder(e.path(), &music_dir_clone);
+
+                // Resume: skip folders already processed
+                if let Some(ref resume_f) = resume_folder {
+                    if folder.to_lowercase() <= resume_f.to_lowercase() {
+                        return false;
+                    }
+                }
 
+                is_audio_file(e.path()) || is_cue_sheet(e.path())
+            })
+            .map(|e| e.into_path())
+            .take(if args.limit > 0 { args.limit } else { usize::MAX })
+            .collect();
+        walk_bar.finish();
+        walked
+    };
+
+    let (cue_list, cue_rips) = pair_cue_sheets(paths);
+    let paths = cue_list.paths;
     let total_files = paths.len() as u64;
     let total_dirs = total_dirs.load(Ordering::Relaxed);
-    eprintln!(); // Clear progress line
     println!(
         "  {} Found {} audio files in {} folders",
         "✓".green(),
         total_files.to_string().bright_white(),
         total_dirs.to_string().bright_white()
     );
+    if !cue_rips.is_empty() {
+        println!(
+            "  {} {} of them are album rips split by a CUE sheet",
+            "✓".green(),
+            cue_rips.len().to_string().bright_white()
+        );
+    }
+    for (cue, reason) in &cue_list.skipped {
+        println!("  {} {} ({})", "↷".yellow(), cue, reason);
+    }
     println!();
 
     if total_files == 0 {
@@ -1193,10 +3395,10 @@ async fn main() {
 
     // --- Phase 2: Extract metadata in parallel ---
     println!("{} Scanning metadata...", "[2/4]".bright_blue().bold());
-    let scanned = AtomicU64::new(0);
+    let scan_bar = progress.add("Scanning", Some(total_files), "files");
     let errors = AtomicU64::new(0);
-    let last_folder: Mutex<String> = Mutex::new(String::new());
     let error_messages: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    let missing_artist: Mutex<Vec<String>> = Mutex::new(Vec::new());
     let error_log = Mutex::new(
         fs::OpenOptions::new()
             .create(true)
@@ -1205,52 +3407,47 @@ async fn main() {
             .expect("Cannot open errors.log"),
     );
 
-    let extracted: Vec<TrackMeta> = paths
+    let mut extracted: Vec<TrackMeta> = paths
         .par_iter()
-        .filter_map(|p| {
-            let n = scanned.fetch_add(1, Ordering::Relaxed) + 1;
-
+        .flat_map_iter(|p| {
             // Progress
+            let n = scan_bar.inc(1);
             if n % 100 == 0 || n == 1 {
-                let folder = get_artist_folder(p, &music_dir);
-                let mut last = last_folder.lock().unwrap();
-                if *last != folder || n % 500 == 0 {
-                    eprint!(
-                        "\r  {} {} {} / {}  ({:.1}%)",
-                        "→".bright_black(),
-                        format!("Scanning: {:<40}", folder).bright_cyan(),
-                        format!("{:>8}", n).white(),
-                        total_files,
-                        (n as f64 / total_files as f64) * 100.0
-                    );
-                    *last = folder;
-                }
+                scan_bar.set_message(&get_artist_folder(p, &music_dir));
             }
 
-            match extract_metadata(p, &music_dir_clone) {
-                Some(meta) => {
-                    // Skip if no artist (critical field)
-                    if meta.artist.is_none() || meta.artist.as_deref() == Some("") {
-                        errors.fetch_add(1, Ordering::Relaxed);
-                        let msg = format!("Missing artist tag: {}", p.display());
-                        if let Ok(mut f) = error_log.lock() { writeln!(f, "[INDEXER] {}", msg).ok(); }
-                        if let Ok(mut v) = error_messages.lock() { v.push(msg); }
-                        return None;
-                    }
-                    Some(meta)
-                }
+            let metas = match extract_metadata(p, &music_dir_clone, &metadata_opts, fast_opts, picture_cache.as_ref()) {
+                // A rip with a CUE sheet becomes one track per cue track
+                Some(meta) => match cue_rips.get(p) {
+                    Some(rip) => cue_tracks(rip, meta, fast_opts, &metadata_opts.genre_separators),
+                    None => vec![meta],
+                },
                 None => {
                     errors.fetch_add(1, Ordering::Relaxed);
+                    scan_bar.add_errors(1);
                     let msg = format!("Failed to read: {}", p.display());
                     if let Ok(mut f) = error_log.lock() { writeln!(f, "[INDEXER] {}", msg).ok(); }
                     if let Ok(mut v) = error_messages.lock() { v.push(msg); }
-                    None
+                    Vec::new()
                 }
-            }
+            };
+            metas.into_iter().filter(|meta| {
+                // Skip if no artist (critical field)
+                if meta.artist.is_none() || meta.artist.as_deref() == Some("") {
+                    errors.fetch_add(1, Ordering::Relaxed);
+                    scan_bar.add_errors(1);
+                    let msg = format!("Missing artist tag: {}", Path::new(&music_dir).join(&meta.file_path).display());
+                    if let Ok(mut f) = error_log.lock() { writeln!(f, "[INDEXER] {}", msg).ok(); }
+                    if let Ok(mut v) = error_messages.lock() { v.push(msg); }
+                    if let Ok(mut v) = missing_artist.lock() { v.push(meta.file_path.clone()); }
+                    return false;
+                }
+                true
+            }).collect::<Vec<_>>()
         })
         .collect();
 
-    eprintln!(); // Clear progress line
+    scan_bar.finish();
     let error_count = errors.load(Ordering::Relaxed);
     if error_count > 0 {
         println!(
@@ -1268,49 +3465,99 @@ async fn main() {
     }
     // Collect Phase 2 error messages for final report
     let mut all_errors: Vec<String> = error_messages.into_inner().unwrap_or_default();
+    let mut qc = QcReport {
+        missing_artist: missing_artist.into_inner().unwrap_or_default(),
+        ..QcReport::default()
+    };
+
+    // Detect files that would share a filePath (and overwrite each other via ON CONFLICT)
+    let collisions = find_path_collisions(&extracted, args.case_sensitive_paths);
+    if !collisions.is_empty() {
+        let mut dropped: HashSet<usize> = HashSet::new();
+        for &(kept, dup) in &collisions {
+            let msg = format!(
+                "filePath collision: '{}' conflicts with '{}' (skipped)",
+                extracted[dup].file_path, extracted[kept].file_path
+            );
+            if let Ok(mut f) = error_log.lock() { writeln!(f, "[INDEXER] {}", msg).ok(); }
+            all_errors.push(msg);
+            dropped.insert(dup);
+        }
+        errors.fetch_add(dropped.len() as u64, Ordering::Relaxed);
+        extracted = extracted
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| !dropped.contains(i))
+            .map(|(_, t)| t)
+            .collect();
+        println!(
+            "  {} Skipped {} files colliding with another filePath{}",
+            "✗".red(),
+            dropped.len().to_string().yellow(),
+            if args.case_sensitive_paths { "" } else { " (case-insensitive)" }
+        );
+    }
     println!();
 
+    // Rips split by a CUE sheet, whose whole-file track (from before the sheet) goes in Phase 4
+    let split_rips: Vec<String> = extracted.iter()
+        .filter(|t| t.cue.is_some())
+        .map(|t| t.source_path().to_string())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+
     // --- Phase 3: Write to database ---
     println!("{} Writing to database...", "[3/4]".bright_blue().bold());
     let mut new_total = 0u64;
     let mut updated_total = 0u64;
     let mut skipped_total = 0u64;
+    let mut filled_total = 0u64;
     let mut db_error_total = 0u64;
+    let mut lyrics_total = 0u64;
 
-    // Track releases that need cover art (first track per release)
-    let mut releases_needing_art: HashMap<String, PathBuf> = HashMap::new();
+    // Track releases that need cover art: first track per release, or the
+    // first one with a picture when embedded art is wanted
+    let mut releases_needing_art: HashMap<String, ArtCandidate> = HashMap::new();
+    let use_sidecar = art_priority.contains(&ArtSource::Sidecar);
+    let use_embedded = art_priority.contains(&ArtSource::Embedded);
 
     let total_extracted = extracted.len() as u64;
 
     // --- Bulk change detection: load ALL existing tracks into memory ---
     eprint!("  {} Loading existing tracks for change detection...", "→".bright_black());
-    let existing_rows: Vec<(String, i64, Option<NaiveDateTime>, Option<String>)> = sqlx::query_as(
-        r#"SELECT "filePath", "fileSize", mtime, "contentHash" FROM "LocalReleaseTrack""#,
+    let existing_rows: Vec<ExistingRow> = sqlx::query_as(
+        r#"SELECT t."filePath", t."fileSize", t.mtime, t."contentHash",
+                  t.duration IS NOT NULL, t.metadata IS NOT NULL,
+                  t.genre, COALESCE(g.n, 0)
+           FROM "LocalReleaseTrack" t
+           LEFT JOIN (SELECT "trackId", COUNT(*)::bigint AS n FROM "TrackGenre" GROUP BY "trackId") g
+             ON g."trackId" = t.id"#,
     )
     .fetch_all(&pool)
     .await
     .unwrap_or_default();
 
-    let existing_tracks: HashMap<String, (i64, NaiveDateTime, String)> = existing_rows
+    let existing_tracks: HashMap<String, ExistingTrack> = existing_rows
         .into_iter()
-        .map(|(path, size, mtime, hash)| {
+        .map(|(path, size, mtime, hash, has_properties, has_metadata, genre, genre_count)| {
             (
                 path,
-                (
+                ExistingTrack {
                     size,
-                    mtime.unwrap_or_else(|| Utc::now().naive_utc()),
-                    hash.unwrap_or_default(),
-                ),
+                    mtime: mtime.unwrap_or_else(|| Utc::now().naive_utc()),
+                    hash: hash.filter(|h| !h.is_empty()),
+                    has_properties,
+                    has_metadata,
+                    genre,
+                    genre_count,
+                },
             )
         })
         .collect();
     eprintln!(" {} ({} existing tracks loaded)", "✓".green(), existing_tracks.len());
 
-    // --- In-memory caches for artist/release lookups ---
-    let mut artist_cache: HashMap<String, String> = HashMap::new();
-    let mut release_cache: HashMap<(String, String), String> = HashMap::new();
-
-    // --- Group tracks by artist folder for transaction batching ---
+    // --- Group tracks by artist folder: one task per folder ---
     let mut groups: Vec<(String, Vec<usize>)> = Vec::new();
     {
         let mut group_map: HashMap<String, Vec<usize>> = HashMap::new();
@@ -1331,229 +3578,107 @@ async fn main() {
 
     let mut processed = 0u64;
     let total_groups = groups.len();
+    let ctx = WriteContext {
+        pool: &pool,
+        extracted: &extracted,
+        existing_tracks: &existing_tracks,
+        error_log: &error_log,
+        music_dir: &music_dir,
+        release_img_dir: PathBuf::from(&config.project_root).join("web/public/img/releases"),
+        extract_lyrics: args.extract_lyrics,
+        skip_images: args.skip_images,
+        use_embedded,
+        use_sidecar,
+    };
 
-    for (group_idx, (folder, indices)) in groups.iter().enumerate() {
-        // Progress
-        eprint!(
-            "\r  {} {} {} / {}  ({:.1}%) [{}/{}]",
-            "→".bright_black(),
-            format!("Writing: {:<40}", folder).bright_cyan(),
-            format!("{:>8}", processed).white(),
-            total_extracted,
-            (processed as f64 / total_extracted as f64) * 100.0,
-            group_idx + 1,
-            total_groups
-        );
-
-        // Begin transaction for this artist folder group
-        let mut tx = match pool.begin().await {
-            Ok(tx) => tx,
-            Err(e) => {
-                db_error_total += indices.len() as u64;
-                if let Ok(mut f) = error_log.lock() {
-                    writeln!(f, "[INDEXER] Failed to begin transaction for folder '{}': {}", folder, e).ok();
-                }
-                processed += indices.len() as u64;
-                continue;
-            }
+    // Up to --write-tasks folders run at once; `buffered` hands them back in
+    // walk order, so commits and checkpoints only ever move past folders
+    // whose predecessors are all written (resume semantics are unchanged).
+    // A folder that timed out on another folder's lock is written again, on
+    // its own, after the stream; no checkpoint is saved past it.
+    let mut results = stream::iter(groups.iter())
+        .map(|(folder, indices)| write_artist_group(&ctx, folder, indices))
+        .buffered(args.write_tasks as usize);
+    let mut next_idx = 0usize;
+    let mut deferred: VecDeque<usize> = VecDeque::new();
+    let mut any_deferred = false;
+    let write_bar = progress.add("Writing", Some(total_extracted), "tracks");
+    loop {
+        let (group_idx, retried, (mut outcome, tx)) = if let Some(result) = results.next().await {
+            next_idx += 1;
+            (next_idx - 1, false, result)
+        } else if let Some(group_idx) = deferred.pop_front() {
+            let (folder, indices) = &groups[group_idx];
+            (group_idx, true, write_artist_group(&ctx, folder, indices).await)
+        } else {
+            break;
         };
+        let (folder, indices) = &groups[group_idx];
 
-        let mut group_errors = 0u64;
-
-        for &idx in indices {
-            let track = &extracted[idx];
-            processed += 1;
-
-            // Change detection using in-memory HashMap (replaces per-track DB query)
-            if let Some((existing_size, existing_mtime, existing_hash)) = existing_tracks.get(&track.file_path) {
-                if *existing_size == track.file_size
-                    && (*existing_mtime - track.mtime).num_seconds().abs() < 2
-                {
-                    // mtime + size match -> skip
-                    skipped_total += 1;
-                    continue;
-                }
-                // Size or mtime changed -> check content hash
-                if *existing_hash == track.content_hash {
-                    // Content unchanged, just update mtime
-                    let now = Utc::now().naive_utc();
-                    sqlx::query(
-                        r#"UPDATE "LocalReleaseTrack" SET mtime = $1, "updatedAt" = $2 WHERE "filePath" = $3"#,
-                    )
-                    .bind(track.mtime)
-                    .bind(now)
-                    .bind(&track.file_path)
-                    .execute(&mut *tx)
-                    .await
-                    .ok();
-                    skipped_total += 1;
-                    continue;
-                }
-                // Content changed -> full update
-                updated_total += 1;
-            } else {
-                // New file
-                new_total += 1;
-            }
-
-            // Split artist tags into individual artists
-            let album_artist_tag = track.album_artist.as_deref().unwrap_or("");
-            let track_artist_tag = track.artist.as_deref().unwrap_or("");
-
-            let (main_album_artists, feat_album_artists) = if !album_artist_tag.is_empty() && !is_various_artists(album_artist_tag) {
-                split_artists(album_artist_tag)
-            } else {
-                (Vec::new(), Vec::new())
-            };
-
-            let (main_track_artists, feat_track_artists) = if !track_artist_tag.is_empty() {
-                split_artists(track_artist_tag)
-            } else {
-                (Vec::new(), Vec::new())
-            };
-
-            // Canonical artist: first main album artist, or first main track artist
-            let canonical_name = main_album_artists.first()
-                .or(main_track_artists.first())
-                .map(|s| s.as_str())
-                .unwrap_or("Unknown Artist");
-            let album_name = track.album.as_deref().unwrap_or("Unknown Album");
-
-            // Ensure canonical artist exists (cached)
-            let artist_id = match ensure_artist_cached(&pool, canonical_name, &mut artist_cache).await {
-                Ok(id) if !id.is_empty() => id,
-                Ok(_) => {
-                    group_errors += 1;
-                    continue;
-                }
-                Err(e) => {
-                    group_errors += 1;
-                    let msg = format!("DB error (artist '{}') {}: {}", canonical_name, track.file_path, e);
-                    if let Ok(mut f) = error_log.lock() { writeln!(f, "[INDEXER] {}", msg).ok(); }
-                    all_errors.push(msg);
-                    continue;
-                }
-            };
-
-            // Ensure local release exists (cached)
-            let folder_path = {
-                let parts: Vec<&str> = track.file_path.rsplitn(2, '/').collect();
-                if parts.len() > 1 { Some(parts[1].to_string()) } else { None }
-            };
-            let release_id = match ensure_local_release_cached(
-                &pool,
-                &artist_id,
-                album_name,
-                track.year,
-                folder_path.as_deref(),
-                &mut release_cache,
-            )
-            .await
-            {
-                Ok(id) => id,
-                Err(e) => {
-                    group_errors += 1;
-                    let msg = format!("DB error (release '{}') {}: {}", album_name, track.file_path, e);
-                    if let Ok(mut f) = error_log.lock() { writeln!(f, "[INDEXER] {}", msg).ok(); }
-                    all_errors.push(msg);
-                    continue;
-                }
-            };
-
-            // Upsert track (on transaction)
-            let track_id = match upsert_track(&pool, track, &release_id).await {
-                Ok(id) => id,
-                Err(e) => {
-                    group_errors += 1;
-                    let msg = format!("DB error (track) {}: {}", track.file_path, e);
-                    if let Ok(mut f) = error_log.lock() { writeln!(f, "[INDEXER] {}", msg).ok(); }
-                    all_errors.push(msg);
-                    continue;
-                }
-            };
-
-            // TrackArtist: ALBUM_ARTIST role for all main album artists
-            for aa_name in &main_album_artists {
-                if let Ok(aa_id) = ensure_artist_cached(&pool, aa_name, &mut artist_cache).await {
-                    if !aa_id.is_empty() {
-                        ensure_track_artist(&pool, &track_id, &aa_id, "ALBUM_ARTIST").await.ok();
-                    }
-                }
-            }
-            // If no album artists were split, at least tag the canonical artist
-            if main_album_artists.is_empty() {
-                ensure_track_artist(&pool, &track_id, &artist_id, "ALBUM_ARTIST").await.ok();
-            }
-
-            // TrackArtist: PRIMARY role for all main track artists
-            if main_track_artists.is_empty() {
-                // No track artist tag — use canonical artist as PRIMARY
-                ensure_track_artist(&pool, &track_id, &artist_id, "PRIMARY").await.ok();
-            } else {
-                for ta_name in &main_track_artists {
-                    if let Ok(ta_id) = ensure_artist_cached(&pool, ta_name, &mut artist_cache).await {
-                        if !ta_id.is_empty() {
-                            ensure_track_artist(&pool, &track_id, &ta_id, "PRIMARY").await.ok();
-                        }
-                    }
-                }
+        if outcome.retry {
+            if !retried {
+                deferred.push_back(group_idx);
+                any_deferred = true;
+                continue;
             }
-
-            // TrackArtist: FEATURED role for all featured artists (from both tags)
-            let all_featured: Vec<String> = feat_album_artists.iter()
-                .chain(feat_track_artists.iter())
-                .cloned()
-                .collect::<std::collections::HashSet<String>>()
-                .into_iter()
-                .collect();
-            for feat_name in &all_featured {
-                if let Ok(feat_id) = ensure_artist_cached(&pool, feat_name, &mut artist_cache).await {
-                    if !feat_id.is_empty() {
-                        ensure_track_artist(&pool, &track_id, &feat_id, "FEATURED").await.ok();
-                    }
-                }
+            // Timed out even when written alone
+            if let Ok(mut f) = error_log.lock() {
+                for msg in &outcome.messages { writeln!(f, "[INDEXER] {}", msg).ok(); }
             }
+        }
+        processed += indices.len() as u64;
 
-            // Track cover art candidates (first track per release with a picture)
-            if track.has_picture && !args.skip_images {
-                let img_dir = PathBuf::from(&config.project_root)
-                    .join("web/public/img/releases");
-                let out_path = img_dir.join(format!("{}.jpg", release_id));
-
-                if !out_path.exists() {
-                    releases_needing_art
-                        .entry(release_id.clone())
-                        .or_insert_with(|| PathBuf::from(&track.file_path));
-                }
+        // Commit transaction for this folder group
+        if let Some(tx) = tx {
+            if let Err(e) = tx.commit().await {
+                let msg = format!("Failed to commit transaction for folder '{}': {}", folder, e);
+                if let Ok(mut f) = error_log.lock() { writeln!(f, "[INDEXER] {}", msg).ok(); }
+                outcome = GroupOutcome { errors: indices.len() as u64, messages: vec![msg], ..Default::default() };
             }
         }
 
-        db_error_total += group_errors;
-
-        // Commit transaction for this folder group
-        if let Err(e) = tx.commit().await {
-            if let Ok(mut f) = error_log.lock() {
-                writeln!(f, "[INDEXER] Failed to commit transaction for folder '{}': {}", folder, e).ok();
+        qc.folders.push(QcFolder {
+            folder: folder.clone(),
+            new: outcome.new,
+            updated: outcome.updated,
+            skipped: outcome.skipped,
+            filled: outcome.filled,
+            errors: outcome.errors,
+        });
+        new_total += outcome.new;
+        updated_total += outcome.updated;
+        skipped_total += outcome.skipped;
+        filled_total += outcome.filled;
+        db_error_total += outcome.errors;
+        write_bar.add_errors(outcome.errors);
+        lyrics_total += outcome.lyrics;
+        all_errors.extend(outcome.messages);
+        for (release_id, candidate) in outcome.art {
+            let entry = releases_needing_art.entry(release_id).or_insert_with(|| candidate.clone());
+            if use_embedded && !entry.has_picture && candidate.has_picture {
+                *entry = candidate;
             }
         }
 
-        // Save checkpoint every 10 folders
-        if (group_idx + 1) % 10 == 0 {
+        // Progress
+        write_bar.set_message(&format!("[{}/{}] {}", group_idx + 1, total_groups, folder));
+        write_bar.set_position(processed);
+
+        // Save checkpoint every 10 folders (not for a --paths-from list, which has no walk to resume)
+        if (group_idx + 1).is_multiple_of(10) && args.paths_from.is_none() && !any_deferred {
             save_checkpoint(
                 &pool,
                 folder,
                 processed as i32,
                 &music_dir,
-                &from_filter,
-                &to_filter,
-                &only_filter,
+                &folder_filter,
             )
             .await
             .ok();
         }
     }
 
-    eprintln!(); // Clear progress line
+    write_bar.finish();
     println!(
         "  {} New: {} | Updated: {} | Skipped: {} | Errors: {}",
         "✓".green(),
@@ -1594,28 +3719,36 @@ async fn main() {
                 .join("web/public/img/releases");
 
             // Phase 1: Parallel CPU-bound extract+resize using rayon
-            let art_entries: Vec<(&String, &PathBuf)> = art_map.iter().collect();
-            let extracted_covers: Vec<(String, PathBuf, bool)> = art_entries
+            // (embedded pictures kept by the metadata pass are decoded without re-reading the file)
+            let art_entries: Vec<(&String, &ArtCandidate)> = art_map.iter().collect();
+            let extracted_covers: Vec<(String, PathBuf, Option<ArtSource>)> = art_entries
                 .par_iter()
-                .map(|(release_id, source_path)| {
+                .map(|(release_id, candidate)| {
                     let out_path = img_dir.join(format!("{}.jpg", release_id));
                     if out_path.exists() {
-                        return ((*release_id).clone(), out_path, false); // already exists
+                        return ((*release_id).clone(), out_path, None); // already exists
                     }
-                    let success = extract_cover_art(source_path, &out_path);
-                    ((*release_id).clone(), out_path, success)
+                    let source = release_cover(&candidate.path, candidate.picture.as_deref(), &out_path, &art_priority);
+                    ((*release_id).clone(), out_path, source)
                 })
                 .collect();
 
             // Phase 2: Sequential S3 uploads + DB updates
             let mut saved = 0u32;
             let mut existing = 0u32;
-            for (release_id, out_path, newly_extracted) in &extracted_covers {
-                if !newly_extracted {
+            let mut from_sidecar = 0u32;
+            for (release_id, out_path, source) in &extracted_covers {
+                let Some(source) = source else {
                     if out_path.exists() {
                         existing += 1;
+                    } else if let Some(candidate) = art_map.get(release_id) {
+                        let track = candidate.path.strip_prefix(&music_dir).unwrap_or(&candidate.path);
+                        qc.cover_failures.insert(release_id.clone(), track.to_string_lossy().into_owned());
                     }
                     continue;
+                };
+                if *source == ArtSource::Sidecar {
+                    from_sidecar += 1;
                 }
 
                 // S3 upload
@@ -1676,16 +3809,19 @@ async fn main() {
                 saved += 1;
             }
             println!(
-                "  {} Saved {} covers, {} already exist",
+                "  {} Saved {} covers ({} from sidecar files), {} already exist",
                 "✓".green(),
                 saved.to_string().bright_white(),
+                from_sidecar,
                 existing.to_string().bright_black()
             );
             println!();
         }
         
         let missing_releases: Vec<(String, String)> = sqlx::query_as(
-            r#"SELECT DISTINCT ON (lr.id) lr.id, lrt."filePath"
+            r#"SELECT DISTINCT ON (lr.id) lr.id,
+                 CASE WHEN lrt."cueStart" IS NULL THEN lrt."filePath"
+                      ELSE regexp_replace(lrt."filePath", '#[0-9]+$', '') END
                FROM "LocalRelease" lr
                JOIN "LocalReleaseTrack" lrt ON lrt."localReleaseId" = lr.id
                WHERE (lr.image IS NULL OR lr.image = '')
@@ -1717,7 +3853,7 @@ async fn main() {
                 let full_path = PathBuf::from(&music_dir).join(&file_path);
                 let out_path = img_dir.join(format!("{}.jpg", release_id));
                 
-                if extract_cover_art(&full_path, &out_path) {
+                if release_cover(&full_path, None, &out_path, &art_priority).is_some() {
                     // S3 upload
                     if use_s3 {
                         if let (Some(ref client), Some(ref bucket), Some(ref public_url)) = 
@@ -1763,6 +3899,7 @@ async fn main() {
                     extracted += 1;
                 } else {
                     failed += 1;
+                    qc.cover_failures.entry(release_id).or_insert(file_path);
                 }
             }
             
@@ -1779,8 +3916,53 @@ async fn main() {
         }
     }
 
+    // --- Artist placeholders ---
+    if args.placeholders {
+        println!("{} Generating artist placeholders...", "[3c]".bright_blue().bold());
+        let (generated, failed) = generate_artist_placeholders(&pool, &config, &folder_filter, &error_log).await;
+        println!(
+            "  {} Generated {} placeholders, {} failed",
+            "✓".green(),
+            generated.to_string().bright_white(),
+            if failed > 0 { failed.to_string().yellow() } else { failed.to_string().bright_black() }
+        );
+        println!();
+    }
+
+    // --- Cross-codec duplicates ---
+    let mut codec_dupes = 0u64;
+    if args.find_codec_dupes {
+        println!("{} Finding tracks stored in two codecs...", "[3d]".bright_blue().bold());
+        match detect_codec_dupes(&pool, &music_dir, args.prefer == "lossless").await {
+            Ok((pairs, files)) => {
+                codec_dupes = pairs;
+                println!(
+                    "  {} Found {} duplicate pairs; {} {} copies to delete listed in {}",
+                    "✓".green(),
+                    pairs.to_string().bright_white(),
+                    files.to_string().bright_white(),
+                    if args.prefer == "lossless" { "lossy" } else { "lossless" },
+                    CODEC_DUPES_FILE
+                );
+            }
+            Err(e) => eprintln!("  {} Failed to find codec duplicates: {}", "✗".red(), e),
+        }
+        println!();
+    }
+
     // --- Phase 4: Post-processing ---
     println!("{} Post-processing...", "[4/4]".bright_blue().bold());
+    if !split_rips.is_empty() {
+        match remove_unsplit_rips(&pool, &split_rips).await {
+            Ok(0) => {}
+            Ok(n) => println!(
+                "  {} Removed {} whole-file tracks now split by a CUE sheet",
+                "✓".green(),
+                n.to_string().bright_white()
+            ),
+            Err(e) => eprintln!("  {} Failed to remove whole-file tracks of CUE rips: {}", "✗".red(), e),
+        }
+    }
     let releases_updated = update_release_totals(&pool).await.unwrap_or(0);
     let artists_updated = update_artist_totals(&pool).await.unwrap_or(0);
     println!(
@@ -1790,15 +3972,85 @@ async fn main() {
         artists_updated.to_string().bright_white()
     );
 
+    let years_promoted = promote_tagged_years(&pool).await.unwrap_or(0);
+    if years_promoted > 0 {
+        println!(
+            "  {} Replaced {} inferred or backfilled years with tagged years",
+            "✓".green(),
+            years_promoted.to_string().bright_white()
+        );
+    }
+    match update_original_years(&pool).await {
+        Ok(0) => {}
+        Ok(n) => println!(
+            "  {} Updated the original year of {} releases",
+            "✓".green(),
+            n.to_string().bright_white()
+        ),
+        Err(e) => eprintln!("  {} Failed to update original years: {}", "✗".red(), e),
+    }
+    // Before mtime inference, so releases matched on MusicBrainz get the real year
+    if args.backfill_years {
+        match backfill_release_years(&pool).await {
+            Ok(n) => println!(
+                "  {} Backfilled {} release years from MusicBrainz",
+                "✓".green(),
+                n.to_string().bright_white()
+            ),
+            Err(e) => eprintln!("  {} Failed to backfill release years: {}", "✗".red(), e),
+        }
+    }
+    if args.infer_year_from_mtime {
+        match infer_release_years(&pool).await {
+            Ok(n) => println!(
+                "  {} Inferred {} release years from file mtime",
+                "✓".green(),
+                n.to_string().bright_white()
+            ),
+            Err(e) => eprintln!("  {} Failed to infer release years: {}", "✗".red(), e),
+        }
+    }
+
     // Update statistics
     match update_statistics(&pool).await {
         Ok(_) => println!("  {} Updated statistics", "✓".green()),
         Err(e) => eprintln!("  {} Failed to update statistics: {}", "✗".red(), e),
     }
+    if let Err(e) = record_statistics_history(&pool, "index", 0).await {
+        eprintln!("  {} Failed to record statistics history: {}", "✗".red(), e);
+    }
+
+    // --- Maintenance: fresh planner statistics for the web app and sync ---
+    if let Some(ref before) = rows_before {
+        let tables: Vec<&str> = before.iter().map(|(table, _)| *table).collect();
+        let started = Instant::now();
+        match analyze_tables(&pool, &tables).await {
+            Ok(()) => {
+                println!(
+                    "  {} Analyzed {} tables in {:.1}s",
+                    "✓".green(),
+                    tables.len().to_string().bright_white(),
+                    started.elapsed().as_secs_f64()
+                );
+                for (table, rows) in count_table_rows(&pool).await {
+                    let previous = before.iter().find(|(t, _)| *t == table).map(|(_, n)| *n).unwrap_or(0);
+                    let delta = match rows - previous {
+                        0 => "=".bright_black(),
+                        d if d > 0 => format!("+{}", d).green(),
+                        d => d.to_string().red(),
+                    };
+                    println!("      {:<18} {:>9} → {:>9}  {}", table, previous, rows, delta);
+                }
+            }
+            Err(e) => eprintln!("  {} Failed to analyze tables: {}", "✗".red(), e),
+        }
+    }
 
     // Clear checkpoint on success
-    clear_checkpoint(&pool).await.ok();
-    println!("  {} Checkpoint cleared", "✓".green());
+    if args.paths_from.is_none() {
+        clear_checkpoint(&pool).await.ok();
+        println!("  {} Checkpoint cleared", "✓".green());
+    }
 
     let elapsed = start.elapsed();
     println!();
@@ -1808,7 +4060,32 @@ async fn main() {
     println!("  {} {}", "New tracks:".green(), new_total);
     println!("  {} {}", "Updated:".yellow(), updated_total);
     println!("  {} {}", "Skipped:".bright_black(), skipped_total);
+    if filled_total > 0 {
+        println!("  {} {}", "Filled in:".white(), filled_total);
+    }
+    if args.extract_lyrics {
+        println!("  {} {}", "Lyrics:".white(), lyrics_total);
+    }
+    if args.find_codec_dupes {
+        println!("  {} {} (see {})", "Codec dupes:".white(), codec_dupes, CODEC_DUPES_FILE);
+    }
     if total_errors > 0 {
         println!("  {} {}", "Errors:".red(), total_errors);
     }
+    if let Some(ref dir) = args.qc_report {
+        qc.errors = all_errors;
+        let mut details = vec![("Music dir", music_dir.clone())];
+        if let Some(ref list) = args.paths_from {
+            details.push(("Files", format!("listed in {}", if list == "-" { "stdin" } else { list.as_str() })));
+        }
+        if let Some(filter) = filter_desc {
+            details.push(("Filter", filter));
+        }
+        details.push(("Cover art", if args.skip_images { "skipped".to_string() } else { args.art_priority.join(" > ") }));
+        details.push(("Duration", format!("{:.1}s", elapsed.as_secs_f64())));
+        match write_qc_report(dir, &qc, &details) {
+            Ok(report_dir) => println!("  {} {}", "QC report:".white(), report_dir.join("index.html").display()),
+            Err(e) => eprintln!("  {} Failed to write QC report: {}", "✗".red(), e),
+        }
+    }
 }
@@ -1,6 +1,7 @@
 use aws_config::BehaviorVersion;
 use aws_sdk_s3::Client as S3Client;
 use clap::Parser;
+use dmp_common::run_lock::{LockMode, RunLock};
 use dotenvy;
 use sqlx::postgres::PgPoolOptions;
 use std::fs;
@@ -8,11 +9,19 @@ use std::io::{self, Write};
 use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
-#[command(name = "dmp-nuke", about = "Delete all data from DMP database")]
+#[command(name = "dmp-nuke", version = dmp_common::version!(), long_version = dmp_common::long_version!(), about = "Delete all data from DMP database")]
 struct Args {
     /// Skip confirmation prompt
     #[arg(long)]
     y: bool,
+
+    /// Wait for another DMP script's run to finish instead of exiting
+    #[arg(long, conflicts_with = "steal_lock")]
+    wait: bool,
+
+    /// Take the run lock from another DMP script (ends its lock session; for hung runs)
+    #[arg(long)]
+    steal_lock: bool,
 }
 
 async fn create_s3_client() -> Option<S3Client> {
@@ -177,6 +186,16 @@ async fn main() {
         }
     };
 
+    // Only one DMP script (index, sync, clean, nuke) writes at a time
+    let mode = LockMode::from_flags(args.wait, args.steal_lock);
+    let _run_lock = match RunLock::acquire(&database_url, "nuke", mode).await {
+        Ok(lock) => lock,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
     println!("Nuking all tables...");
 
     // Truncate all tables in correct order (respecting foreign key constraints)
@@ -199,8 +218,12 @@ async fn main() {
         "SearchSource",
         "Settings",
         "Statistics",
+        "StatisticsHistory",
         "IndexCheckpoint",
+        "LibraryRoot",
         "SyncCheckpoint",
+        "SyncRetryQueue",
+        "SyncRun",
         "S3DeletionQueue",
     ];
 
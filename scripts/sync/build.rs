@@ -0,0 +1,4 @@
+// Git commit, build date and schema revision for --version
+fn main() {
+    dmp_common::build_info::emit();
+}
@@ -4,27 +4,37 @@ use aws_sdk_s3::Client as S3Client;
 use chrono::Utc;
 use clap::Parser;
 use colored::*;
+use dmp_common::artist_merge::{merge_artist, MergeCounts};
+use dmp_common::checkpoint::checkpoint_age;
+use dmp_common::deletion_queue::{image_object_key, queue_s3_deletion};
+use dmp_common::musicbrainz::{self as mb, RateLimiter, USER_AGENT};
+use dmp_common::run_lock::{LockMode, RunLock};
+use dmp_common::s3::{self, CaBundle};
+use dmp_common::statistics_history::record_statistics_history;
+use dmp_common::unicode::{artist_slug, nfc};
+use dmp_common::FolderFilter;
 use dotenvy;
 use reqwest::Client;
 use serde::Deserialize;
 use serde_json::Value as JsonValue;
 use slug::slugify;
 use sqlx::postgres::PgPoolOptions;
-use sqlx::{PgPool, Row};
-use std::collections::{HashMap, HashSet};
+use sqlx::{Connection, PgConnection, PgPool, Row};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fs;
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 use std::time::{Duration, Instant};
-use tokio::time::sleep;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
 
 // ---------------------------------------------------------------------------
 // CLI
 // ---------------------------------------------------------------------------
 
 #[derive(Parser, Debug)]
-#[command(name = "dmp-sync", about = "Sync local catalogue with MusicBrainz")]
+#[command(name = "dmp-sync", version = dmp_common::version!(), long_version = dmp_common::long_version!(), about = "Sync local catalogue with MusicBrainz")]
 struct Args {
     /// Re-sync all artists (including already synced ones)
     #[arg(long)]
@@ -50,9 +60,109 @@ struct Args {
     #[arg(long)]
     resume: bool,
 
+    /// Sync even if the indexer has a checkpoint (an index run is in progress or was interrupted)
+    #[arg(long)]
+    force: bool,
+
+    /// Wait for another DMP script's run to finish instead of exiting
+    #[arg(long, conflicts_with = "steal_lock")]
+    wait: bool,
+
+    /// Take the run lock from another DMP script (ends its lock session; for hung runs)
+    #[arg(long)]
+    steal_lock: bool,
+
     /// Show skipped releases (singles, bootlegs, etc.) in output
     #[arg(long)]
     verbose: bool,
+
+    /// Sync at most N releases per artist, Albums first, then EPs (0 = no limit); the rest are skipped on every run that uses the cap
+    #[arg(long, default_value = "0")]
+    max_releases_per_artist: usize,
+
+    /// Flag matched tracks whose length differs from MusicBrainz by more than N seconds (0 = off)
+    #[arg(long, default_value = "10")]
+    duration_tolerance: u32,
+
+    /// Pair track and release titles without an exact match with the most similar leftover title scoring at least SCORE (0-1, normalized Levenshtein; flag alone = 0.85)
+    #[arg(long, value_name = "SCORE", num_args = 0..=1, default_missing_value = "0.85")]
+    fuzzy_titles: Option<f64>,
+
+    /// Don't seed artist MB IDs and release links from the MusicBrainz IDs in file tags (index metadata JSON)
+    #[arg(long)]
+    no_tag_ids: bool,
+
+    /// Sync the artist folders found in this music directory (created in the DB if missing) instead of DB artists
+    #[arg(long)]
+    folder: Option<String>,
+
+    /// Check stored artist URLs (HEAD) and flag dead links instead of syncing
+    #[arg(long)]
+    check_urls: bool,
+
+    /// With --check-urls: delete dead links instead of flagging them
+    #[arg(long)]
+    remove_dead_urls: bool,
+
+    /// Link each artist's genres to all of its MusicBrainz releases
+    #[arg(long)]
+    propagate_genres: bool,
+
+    /// With --propagate-genres: also fill the artist's top genre into local tracks without a genre tag
+    #[arg(long, requires = "propagate_genres")]
+    propagate_genres_to_tracks: bool,
+
+    /// Preferred release countries for track lists, in order (e.g. US,GB,DE)
+    #[arg(long, value_delimiter = ',')]
+    prefer_country: Vec<String>,
+
+    /// Preferred release formats for track lists, in order (e.g. CD,Digital)
+    #[arg(long, value_delimiter = ',')]
+    prefer_format: Vec<String>,
+
+    /// Only fetch the release groups queued by earlier runs that failed (rate limiting, MB outages)
+    #[arg(long, conflicts_with_all = ["overwrite", "resume", "folder", "check_urls"])]
+    retry_failed: bool,
+
+    /// Share the artist backlog with other --worker runs (any machine, same DB): claim each artist before syncing it
+    #[arg(long, conflicts_with_all = ["resume", "folder", "retry_failed", "check_urls"])]
+    worker: bool,
+
+    /// With --worker: take over claims older than N minutes (their worker is presumed dead)
+    #[arg(long, default_value = "60", requires = "worker")]
+    claim_timeout: u32,
+
+    /// Skip artist images: no Wikipedia/Wikidata/Fanart.tv lookups, downloads or S3 uploads
+    #[arg(long)]
+    skip_images: bool,
+
+    /// Only fetch images for artists without one (or with the indexer's placeholder), from their stored MB ID and Wikipedia/Wikidata links: no discography work and no MusicBrainz requests
+    #[arg(long, conflicts_with_all = ["skip_images", "overwrite", "resume", "folder", "retry_failed", "check_urls", "worker"])]
+    images_only: bool,
+
+    /// Wikipedia languages to take artist images from, in order (e.g. en,de,fr)
+    #[arg(long, value_delimiter = ',', default_value = "en")]
+    wiki_languages: Vec<String>,
+
+    /// Reuse an artist's Wikipedia/Wikidata image lookup for N days (0 = look up every run)
+    #[arg(long, default_value = "30")]
+    wiki_cache_days: u32,
+
+    /// After syncing, write the MISSING releases of the selected artists as an HTML + JSON want-list under DIR
+    #[arg(long, value_name = "DIR", num_args = 0..=1, default_missing_value = "reports")]
+    gaps_report: Option<String>,
+
+    /// MusicBrainz web service to query instead of musicbrainz.org, e.g. a musicbrainz-docker mirror (overrides MB_URL)
+    #[arg(long, value_name = "URL")]
+    mb_url: Option<String>,
+
+    /// Also sync artists archived because they have no local releases or track credits left
+    #[arg(long)]
+    include_archived: bool,
+
+    /// Nightly mode: sync the artists created or changed since the last --auto run plus those due their 30-day refresh, record the run in SyncRun and end with a JSON summary line
+    #[arg(long, conflicts_with_all = ["overwrite", "resume", "folder", "retry_failed", "check_urls", "worker", "images_only", "only", "from", "to", "limit"])]
+    auto: bool,
 }
 
 // ---------------------------------------------------------------------------
@@ -93,6 +203,20 @@ struct MbReleaseGroup {
     secondary_types: Option<Vec<String>>,
     #[serde(rename = "first-release-date")]
     first_release_date: Option<String>,
+    /// With `inc=genres`; not stored in the retry queue
+    genres: Option<Vec<MbGenre>>,
+    /// With `inc=ratings`; not stored in the retry queue
+    rating: Option<MbRating>,
+    /// With `inc=tags`; not stored in the retry queue
+    tags: Option<Vec<MbTag>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MbRating {
+    /// 0–5, `None` until someone rates it
+    value: Option<f64>,
+    #[serde(rename = "votes-count")]
+    votes_count: Option<u32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -101,6 +225,7 @@ struct MbRelease {
     id: String,
     title: String,
     date: Option<String>,
+    country: Option<String>,
     media: Option<Vec<MbMedia>>,
 }
 
@@ -113,6 +238,7 @@ struct MbReleaseList {
 struct MbMedia {
     #[allow(dead_code)]
     position: Option<u32>,
+    format: Option<String>,
     tracks: Option<Vec<MbTrack>>,
 }
 
@@ -163,7 +289,6 @@ struct MbTag {
 struct FanartArtistResponse {
     artistthumb: Option<Vec<FanartImage>>,
     artistbackground: Option<Vec<FanartImage>>,
-    #[allow(dead_code)]
     hdmusiclogo: Option<Vec<FanartImage>>,
 }
 
@@ -172,120 +297,6 @@ struct FanartImage {
     url: String,
 }
 
-// Wikipedia types (used via dynamic JSON parsing in get_wikipedia_image)
-
-// ---------------------------------------------------------------------------
-// Adaptive rate limiter
-// ---------------------------------------------------------------------------
-
-struct RateLimiter {
-    delay_ms: u64,
-    min_delay: u64,
-    max_delay: u64,
-    last_request: Instant,
-}
-
-impl RateLimiter {
-    fn new() -> Self {
-        Self {
-            delay_ms: 1000,      // Start at 1 second (conservative)
-            min_delay: 1000,     // Never go below 1 second
-            max_delay: 10000,    // Max 10 seconds between requests
-            last_request: Instant::now(),
-        }
-    }
-
-    async fn wait(&mut self) {
-        let elapsed = self.last_request.elapsed().as_millis() as u64;
-        if elapsed < self.delay_ms {
-            sleep(Duration::from_millis(self.delay_ms - elapsed)).await;
-        }
-        self.last_request = Instant::now();
-    }
-
-    fn on_success(&mut self) {
-        // Reduce delay by 15% on success (recovery from 10s→1s in ~14 requests vs ~46)
-        if self.delay_ms > self.min_delay {
-            self.delay_ms = (self.delay_ms * 85 / 100).max(self.min_delay);
-        }
-    }
-
-    fn on_rate_limit(&mut self) {
-        // Double the delay on rate limit
-        self.delay_ms = (self.delay_ms * 2).min(self.max_delay);
-    }
-}
-
-// ---------------------------------------------------------------------------
-// MusicBrainz API client
-// ---------------------------------------------------------------------------
-
-const MB_BASE: &str = "https://musicbrainz.org/ws/2";
-const USER_AGENT: &str = "DMPv6/0.1.0 ( https://github.com/dmp )";
-
-async fn mb_get(
-    client: &Client,
-    url: &str,
-    limiter: &mut RateLimiter,
-) -> Result<String, String> {
-    let max_attempts = 10;
-    let mut wait_time = limiter.delay_ms; // Start with current rate limit delay
-    
-    for attempt in 0..max_attempts {
-        limiter.wait().await;
-
-        let resp = client
-            .get(url)
-            .header("User-Agent", USER_AGENT)
-            .header("Accept", "application/json")
-            .send()
-            .await
-            .map_err(|e| format!("Request failed: {}", e))?;
-
-        let status = resp.status().as_u16();
-
-        if status == 200 {
-            limiter.on_success();
-            return resp.text().await.map_err(|e| format!("Read body failed: {}", e));
-        }
-
-        if status == 503 || status == 429 {
-            limiter.on_rate_limit();
-            
-            if attempt < max_attempts - 1 {
-                // Exponential backoff: double the wait time each retry
-                wait_time = (wait_time * 2).min(60000); // Cap at 60 seconds per retry
-                
-                let reason = if status == 503 {
-                    "MB server busy"
-                } else {
-                    "Rate limited"
-                };
-                
-                eprint!(
-                    "\r  {} - waiting {:.1}s before retry {}/{}...          ",
-                    reason, wait_time as f64 / 1000.0, attempt + 1, max_attempts - 1
-                );
-                
-                sleep(Duration::from_millis(wait_time)).await;
-                continue;
-            } else {
-                eprintln!();
-                return Err(format!(
-                    "MusicBrainz API still unavailable after {} retries (waited up to {}s). Will retry this release next time.",
-                    max_attempts,
-                    wait_time / 1000
-                ));
-            }
-        }
-
-        // Other errors (404, 400, etc) fail immediately
-        return Err(format!("HTTP {} for {}", status, url));
-    }
-
-    Err("Max retries exceeded".to_string())
-}
-
 /// Normalize an artist name for similarity comparison:
 /// lowercase, strip leading "the ", collapse punctuation/whitespace.
 fn normalize_name(name: &str) -> String {
@@ -338,8 +349,8 @@ async fn mb_search_artist(
     // e.g. artist:"12 Stones" instead of artist:12 Stones
     let phrase = format!("\"{}\"", name);
     let quoted = urlencoding::encode(&phrase);
-    let url = format!("{}/artist/?query=artist:{}&limit=5&fmt=json", MB_BASE, quoted);
-    let body = mb_get(client, &url, limiter).await?;
+    let url = format!("{}/artist/?query=artist:{}&limit=5&fmt=json", mb::base(), quoted);
+    let body = mb::get(client, &url, limiter).await?;
     let result: MbArtistSearchResult =
         serde_json::from_str(&body).map_err(|e| format!("Parse error: {}", e))?;
 
@@ -436,9 +447,9 @@ async fn mb_get_artist_detail(
 ) -> Result<MbArtistDetail, String> {
     let url = format!(
         "{}/artist/{}?inc=url-rels+genres+tags&fmt=json",
-        MB_BASE, mb_id
+        mb::base(), mb_id
     );
-    let body = mb_get(client, &url, limiter).await?;
+    let body = mb::get(client, &url, limiter).await?;
     serde_json::from_str(&body).map_err(|e| format!("Parse error: {}", e))
 }
 
@@ -447,21 +458,24 @@ async fn mb_get_release_groups(
     mb_id: &str,
     limiter: &mut RateLimiter,
 ) -> Result<Vec<MbReleaseGroup>, String> {
-    let mut all_groups = Vec::new();
+    let mut all_groups: Vec<MbReleaseGroup> = Vec::new();
+    let mut seen: HashSet<String> = HashSet::new();
     let mut offset = 0u32;
     let limit = 100u32;
 
     loop {
         let url = format!(
-            "{}/release-group?artist={}&limit={}&offset={}&fmt=json",
-            MB_BASE, mb_id, limit, offset
+            "{}/release-group?artist={}&inc=genres+ratings+tags&limit={}&offset={}&fmt=json",
+            mb::base(), mb_id, limit, offset
         );
-        let body = mb_get(client, &url, limiter).await?;
+        let body = mb::get(client, &url, limiter).await?;
         let result: MbReleaseGroupList =
             serde_json::from_str(&body).map_err(|e| format!("Parse error: {}", e))?;
 
+        // A group deleted or merged while paging shifts the offsets, so the next
+        // page can repeat one already listed
         let count = result.release_groups.len() as u32;
-        all_groups.extend(result.release_groups);
+        all_groups.extend(result.release_groups.into_iter().filter(|rg| seen.insert(rg.id.clone())));
 
         let total = result.release_group_count.unwrap_or(0);
         offset += count;
@@ -480,9 +494,9 @@ async fn mb_get_release_tracks(
 ) -> Result<Vec<(MbRelease, Vec<MbTrack>)>, String> {
     let url = format!(
         "{}/release?release-group={}&inc=recordings&limit=10&fmt=json",
-        MB_BASE, release_group_id
+        mb::base(), release_group_id
     );
-    let body = mb_get(client, &url, limiter).await?;
+    let body = mb::get(client, &url, limiter).await?;
     let result: MbReleaseList =
         serde_json::from_str(&body).map_err(|e| format!("Parse error: {}", e))?;
 
@@ -555,6 +569,214 @@ fn should_skip_release(rg: &MbReleaseGroup) -> Option<String> {
     None
 }
 
+/// Sort order used when capping an artist's discography: Albums, then EPs,
+/// then everything else (including release groups without a primary type).
+fn release_type_priority(rg: &MbReleaseGroup) -> u8 {
+    match rg.primary_type.as_deref() {
+        Some(pt) if pt.eq_ignore_ascii_case("Album") => 0,
+        Some(pt) if pt.eq_ignore_ascii_case("EP") => 1,
+        _ => 2,
+    }
+}
+
+/// Keep at most `max` syncable release groups, preferring Albums then EPs.
+/// Skipped groups (singles, bootlegs, ...) are kept so they still show up in the
+/// per-artist summary. Returns the remaining groups and how many were capped.
+fn cap_release_groups(release_groups: Vec<MbReleaseGroup>, max: usize) -> (Vec<MbReleaseGroup>, usize) {
+    let (mut syncable, skipped): (Vec<_>, Vec<_>) = release_groups
+        .into_iter()
+        .partition(|rg| should_skip_release(rg).is_none());
+
+    if max == 0 || syncable.len() <= max {
+        syncable.extend(skipped);
+        return (syncable, 0);
+    }
+
+    // Stable sort keeps MusicBrainz ordering within each type
+    syncable.sort_by_key(release_type_priority);
+    let capped = syncable.len() - max;
+    syncable.truncate(max);
+    syncable.extend(skipped);
+    (syncable, capped)
+}
+
+// ---------------------------------------------------------------------------
+// Release group popularity (ratings and tag votes)
+// ---------------------------------------------------------------------------
+
+/// Rating votes a release group is assumed to have at the middle of the
+/// scale, so one 5-star vote doesn't outrank forty 4.5-star ones.
+const RATING_PRIOR_VOTES: f64 = 2.0;
+const RATING_PRIOR_VALUE: f64 = 2.5;
+
+/// A release group's MusicBrainz rating and tag votes, and the popularity
+/// score the web app sorts discographies by.
+#[derive(Debug, Clone, PartialEq)]
+struct Popularity {
+    rating: Option<f64>,
+    votes: i32,
+    /// Sum of the vote counts of the release group's tags
+    tag_count: i32,
+    score: f64,
+}
+
+impl Popularity {
+    /// `None` when the release group came without ratings and tags (the retry
+    /// queue), so the stored values are kept.
+    fn of(rg: &MbReleaseGroup) -> Option<Self> {
+        if rg.rating.is_none() && rg.tags.is_none() {
+            return None;
+        }
+        let rating = rg.rating.as_ref().and_then(|r| r.value);
+        let votes = rg.rating.as_ref().and_then(|r| r.votes_count).unwrap_or(0) as i32;
+        let tag_count = rg.tags.iter().flatten().map(|t| t.count.unwrap_or(0).max(0)).sum();
+        Some(Self { rating, votes, tag_count, score: popularity_score(rating, votes, tag_count) })
+    }
+}
+
+/// `rating × ln(1 + votes + tag votes)`, with the rating pulled towards the
+/// middle of the scale when it has few votes and scaled to 0–1. Unrated,
+/// untagged release groups score 0; the more people rated or tagged one,
+/// the higher it goes, and a better rating scales that up.
+fn popularity_score(rating: Option<f64>, votes: i32, tag_count: i32) -> f64 {
+    let votes_f = votes.max(0) as f64;
+    let weighted = match rating {
+        Some(value) if votes > 0 => {
+            (value * votes_f + RATING_PRIOR_VALUE * RATING_PRIOR_VOTES) / (votes_f + RATING_PRIOR_VOTES)
+        }
+        _ => RATING_PRIOR_VALUE,
+    };
+    let engagement = (votes.max(0) + tag_count.max(0)) as f64;
+    let score = weighted / 5.0 * engagement.ln_1p();
+    (score * 1000.0).round() / 1000.0
+}
+
+// ---------------------------------------------------------------------------
+// Edition selection (--prefer-country / --prefer-format)
+// ---------------------------------------------------------------------------
+
+/// Position of the first preference matching `value`, or `prefs.len()` when
+/// none does. Formats match on whole words so "Vinyl" matches `12" Vinyl`
+/// and "Digital" matches "Digital Media", but "CD" doesn't match "CD-R".
+fn preference_rank(prefs: &[String], value: Option<&str>) -> usize {
+    let Some(value) = value else { return prefs.len() };
+    prefs
+        .iter()
+        .position(|p| {
+            value.eq_ignore_ascii_case(p)
+                || value.split_whitespace().any(|word| word.eq_ignore_ascii_case(p))
+        })
+        .unwrap_or(prefs.len())
+}
+
+fn release_formats(release: &MbRelease) -> Vec<&str> {
+    release
+        .media
+        .iter()
+        .flatten()
+        .filter_map(|m| m.format.as_deref())
+        .collect()
+}
+
+/// Pick the edition whose track list is used for a release group: best
+/// country match first, then best format match, MusicBrainz order on ties.
+/// The edition stored by a previous sync wins any tie, so re-syncs don't flip
+/// between equally ranked editions.
+fn select_edition(
+    releases: &[(MbRelease, Vec<MbTrack>)],
+    countries: &[String],
+    formats: &[String],
+    stored: Option<&str>,
+) -> Option<usize> {
+    let key = |release: &MbRelease| {
+        let country = preference_rank(countries, release.country.as_deref());
+        let format = release_formats(release)
+            .into_iter()
+            .map(|f| preference_rank(formats, Some(f)))
+            .min()
+            .unwrap_or(formats.len());
+        (country, format)
+    };
+    let best = releases.iter().map(|(r, _)| key(r)).min()?;
+    releases
+        .iter()
+        .position(|(r, _)| Some(r.id.as_str()) == stored && key(r) == best)
+        .or_else(|| releases.iter().position(|(r, _)| key(r) == best))
+}
+
+// ---------------------------------------------------------------------------
+// Channel links (YouTube / SoundCloud / Bandcamp)
+// ---------------------------------------------------------------------------
+
+/// `ArtistUrl.type` for streaming/video channels, with the summary label.
+/// MusicBrainz files these under several relation types ("youtube",
+/// "video channel", "free streaming", "social network", ...), so they are
+/// recognized by host instead.
+const CHANNEL_TYPES: &[(&str, &str)] = &[
+    ("youtube", "YouTube"),
+    ("soundcloud", "SoundCloud"),
+    ("bandcamp", "Bandcamp"),
+];
+
+/// Lowercased host of an http(s) URL, without "www." / "m.".
+fn url_host(url: &str) -> Option<String> {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?.split(':').next()?.to_lowercase();
+    let host = host.strip_prefix("www.").or_else(|| host.strip_prefix("m.")).unwrap_or(&host);
+    (!host.is_empty()).then(|| host.to_string())
+}
+
+/// The `CHANNEL_TYPES` entry a URL belongs to, by host.
+fn channel_type(url: &str) -> Option<&'static str> {
+    let host = url_host(url)?;
+    match host.as_str() {
+        "youtube.com" | "music.youtube.com" | "youtu.be" => Some("youtube"),
+        "soundcloud.com" => Some("soundcloud"),
+        h if h == "bandcamp.com" || h.ends_with(".bandcamp.com") => Some("bandcamp"),
+        _ => None,
+    }
+}
+
+/// One spelling per channel: https, lowercase host without "www." / "m.",
+/// no fragment, no trailing slash, and no query except on YouTube video and
+/// playlist links.
+fn normalize_channel_url(url: &str) -> String {
+    let Some(host) = url_host(url) else { return url.to_string() };
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let path_and_query = rest.find(['/', '?', '#']).map_or("", |i| &rest[i..]);
+    let without_fragment = path_and_query.split('#').next().unwrap_or("");
+    let (path, query) = without_fragment.split_once('?').unwrap_or((without_fragment, ""));
+    let path = path.trim_end_matches('/');
+    let keep_query = !query.is_empty() && (path == "/watch" || path == "/playlist");
+    if keep_query {
+        format!("https://{}{}?{}", host, path, query)
+    } else {
+        format!("https://{}{}", host, path)
+    }
+}
+
+/// Artists (of those synced with MusicBrainz) with at least one link of each channel type.
+async fn channel_coverage(pool: &PgPool) -> Result<(Vec<(String, i64)>, i64), sqlx::Error> {
+    let types: Vec<&str> = CHANNEL_TYPES.iter().map(|(t, _)| *t).collect();
+    let rows: Vec<(String, i64)> = sqlx::query_as(
+        r#"SELECT u.type, COUNT(DISTINCT u."artistId")::bigint
+           FROM "ArtistUrl" u
+           JOIN "Artist" a ON a.id = u."artistId"
+           WHERE u.type = ANY($1) AND a."musicbrainzId" IS NOT NULL
+           GROUP BY u.type"#,
+    )
+    .bind(&types)
+    .fetch_all(pool)
+    .await?;
+    let (artists,): (i64,) = sqlx::query_as(
+        r#"SELECT COUNT(*)::bigint FROM "Artist" WHERE "musicbrainzId" IS NOT NULL"#,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok((rows, artists))
+}
+
 // ---------------------------------------------------------------------------
 // Database operations
 // ---------------------------------------------------------------------------
@@ -593,32 +815,37 @@ async fn ensure_release_type(pool: &PgPool, name: &str) -> Result<String, sqlx::
     Ok(row.0)
 }
 
-async fn ensure_genre(pool: &PgPool, name: &str) -> Result<String, sqlx::Error> {
-    let existing: Option<(String,)> =
-        sqlx::query_as(r#"SELECT id FROM "Genre" WHERE name = $1"#)
-            .bind(name)
-            .fetch_optional(pool)
-            .await?;
-
-    if let Some((id,)) = existing {
-        return Ok(id);
-    }
-
-    let id = cuid2::create_id();
-    sqlx::query(
-        r#"INSERT INTO "Genre" (id, name) VALUES ($1, $2) ON CONFLICT (name) DO NOTHING"#,
-    )
-    .bind(&id)
-    .bind(name)
-    .execute(pool)
-    .await?;
-
-    let row: (String,) = sqlx::query_as(r#"SELECT id FROM "Genre" WHERE name = $1"#)
-        .bind(name)
-        .fetch_one(pool)
+/// Genre ids for `names` (in order), creating the missing genres in one
+/// multi-row insert. Ids are cached across artists.
+async fn ensure_genres_cached(
+    pool: &PgPool,
+    names: &[String],
+    cache: &mut HashMap<String, String>,
+) -> Result<Vec<String>, sqlx::Error> {
+    let mut missing: Vec<String> = names.iter().filter(|n| !cache.contains_key(*n)).cloned().collect();
+    missing.sort();
+    missing.dedup();
+    if !missing.is_empty() {
+        let ids: Vec<String> = missing.iter().map(|_| cuid2::create_id()).collect();
+        sqlx::query(
+            r#"INSERT INTO "Genre" (id, name)
+               SELECT * FROM UNNEST($1::text[], $2::text[])
+               ON CONFLICT (name) DO NOTHING"#,
+        )
+        .bind(&ids)
+        .bind(&missing)
+        .execute(pool)
         .await?;
 
-    Ok(row.0)
+        let rows: Vec<(String, String)> = sqlx::query_as(r#"SELECT id, name FROM "Genre" WHERE name = ANY($1)"#)
+            .bind(&missing)
+            .fetch_all(pool)
+            .await?;
+        for (id, name) in rows {
+            cache.insert(name, id);
+        }
+    }
+    Ok(names.iter().filter_map(|n| cache.get(n).cloned()).collect())
 }
 
 /// Cached version of ensure_release_type
@@ -636,36 +863,28 @@ async fn ensure_release_type_cached(
     Ok(id)
 }
 
-/// Cached version of ensure_genre
-async fn ensure_genre_cached(
-    pool: &PgPool,
-    name: &str,
-    cache: &mut HashMap<String, String>,
-) -> Result<String, sqlx::Error> {
-    if let Some(id) = cache.get(name) {
-        return Ok(id.clone());
-    }
-    let id = ensure_genre(pool, name).await?;
-    cache.insert(name.to_string(), id.clone());
-    Ok(id)
-}
-
-async fn upsert_artist_url(
+/// Insert the artist's (type, url) pairs in one multi-row statement; pairs
+/// already stored are left alone.
+async fn upsert_artist_urls(
     pool: &PgPool,
     artist_id: &str,
-    url_type: &str,
-    url: &str,
+    urls: &[(String, String)],
 ) -> Result<(), sqlx::Error> {
-    let id = cuid2::create_id();
+    if urls.is_empty() {
+        return Ok(());
+    }
+    let ids: Vec<String> = urls.iter().map(|_| cuid2::create_id()).collect();
+    let (types, links): (Vec<&str>, Vec<&str>) = urls.iter().map(|(t, u)| (t.as_str(), u.as_str())).unzip();
     let now = Utc::now().naive_utc();
     sqlx::query(
         r#"INSERT INTO "ArtistUrl" (id, type, url, "artistId", "createdAt", "updatedAt")
-           VALUES ($1, $2, $3, $4, $5, $5)
+           SELECT u.id, u.type, u.url, $4, $5, $5
+           FROM UNNEST($1::text[], $2::text[], $3::text[]) AS u(id, type, url)
            ON CONFLICT ("artistId", type, url) DO NOTHING"#,
     )
-    .bind(&id)
-    .bind(url_type)
-    .bind(url)
+    .bind(&ids)
+    .bind(&types)
+    .bind(&links)
     .bind(artist_id)
     .bind(now)
     .execute(pool)
@@ -674,7 +893,7 @@ async fn upsert_artist_url(
 }
 
 async fn upsert_mb_release(
-    pool: &PgPool,
+    conn: &mut PgConnection,
     artist_id: &str,
     title: &str,
     type_id: &str,
@@ -686,7 +905,7 @@ async fn upsert_mb_release(
     )
     .bind(artist_id)
     .bind(title)
-    .fetch_optional(pool)
+    .fetch_optional(&mut *conn)
     .await?;
 
     if let Some((id,)) = existing {
@@ -701,7 +920,7 @@ async fn upsert_mb_release(
         .bind(mb_id)
         .bind(now)
         .bind(&id)
-        .execute(pool)
+        .execute(&mut *conn)
         .await?;
         return Ok(id);
     }
@@ -723,14 +942,35 @@ async fn upsert_mb_release(
     .bind(year)
     .bind(mb_id)
     .bind(now)
-    .fetch_one(pool)
+    .fetch_one(&mut *conn)
     .await
     .map(|row| row.get::<String, _>("id"))
 }
 
+/// Write a release group's rating, tag votes and popularity score.
+async fn store_popularity(
+    conn: &mut PgConnection,
+    mb_release_id: &str,
+    popularity: &Popularity,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"UPDATE "MusicBrainzRelease" SET
+             rating = $1, "ratingVotes" = $2, "tagCount" = $3, popularity = $4
+           WHERE id = $5"#,
+    )
+    .bind(popularity.rating)
+    .bind(popularity.votes)
+    .bind(popularity.tag_count)
+    .bind(popularity.score)
+    .bind(mb_release_id)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
 /// Batch insert MB tracks using UNNEST arrays
 async fn batch_insert_mb_tracks(
-    pool: &PgPool,
+    conn: &mut PgConnection,
     release_id: &str,
     tracks: &[MbTrack],
     disc_number: i32,
@@ -775,42 +1015,106 @@ async fn batch_insert_mb_tracks(
     .bind(&release_ids)
     .bind(&timestamps)
     .bind(&timestamps)
-    .execute(pool)
+    .execute(&mut *conn)
     .await?;
 
     Ok(())
 }
 
 async fn delete_mb_tracks_for_release(
-    pool: &PgPool,
+    conn: &mut PgConnection,
     release_id: &str,
 ) -> Result<u64, sqlx::Error> {
+    // Local tracks point at the old MB tracks; the status check re-links them
+    sqlx::query(
+        r#"UPDATE "LocalReleaseTrack" SET "mbTrackId" = NULL
+           WHERE "mbTrackId" IN (SELECT id FROM "MusicBrainzReleaseTrack" WHERE "releaseId" = $1)"#,
+    )
+    .bind(release_id)
+    .execute(&mut *conn)
+    .await?;
     let result = sqlx::query(
         r#"DELETE FROM "MusicBrainzReleaseTrack" WHERE "releaseId" = $1"#,
     )
     .bind(release_id)
-    .execute(pool)
+    .execute(&mut *conn)
     .await?;
     Ok(result.rows_affected())
 }
 
-async fn link_artist_genre(
+async fn link_artist_genres(
     pool: &PgPool,
     artist_id: &str,
-    genre_id: &str,
+    genre_ids: &[String],
 ) -> Result<(), sqlx::Error> {
+    if genre_ids.is_empty() {
+        return Ok(());
+    }
     sqlx::query(
         r#"INSERT INTO "_ArtistGenres" ("A", "B")
-           VALUES ($1, $2)
+           SELECT $1, g FROM UNNEST($2::text[]) AS g
            ON CONFLICT DO NOTHING"#,
     )
     .bind(artist_id)
-    .bind(genre_id)
+    .bind(genre_ids)
     .execute(pool)
     .await?;
     Ok(())
 }
 
+/// Link the release group's own MB genres to its MB release.
+async fn link_release_genres(
+    conn: &mut PgConnection,
+    release_id: &str,
+    genre_ids: &[String],
+) -> Result<(), sqlx::Error> {
+    if genre_ids.is_empty() {
+        return Ok(());
+    }
+    sqlx::query(
+        r#"INSERT INTO "_ReleaseGenres" ("A", "B")
+           SELECT g, $1 FROM UNNEST($2::text[]) AS g
+           ON CONFLICT DO NOTHING"#,
+    )
+    .bind(release_id)
+    .bind(genre_ids)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Link every genre of the artist to each of its MB releases.
+async fn propagate_release_genres(conn: &mut PgConnection, artist_id: &str) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        r#"INSERT INTO "_ReleaseGenres" ("A", "B")
+           SELECT ag."B", r.id
+           FROM "_ArtistGenres" ag
+           JOIN "MusicBrainzRelease" r ON r."artistId" = ag."A"
+           WHERE ag."A" = $1
+           ON CONFLICT DO NOTHING"#,
+    )
+    .bind(artist_id)
+    .execute(&mut *conn)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Set `genre` on the artist's local tracks that have no genre tag.
+async fn propagate_track_genres(conn: &mut PgConnection, artist_id: &str, genre: &str) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        r#"UPDATE "LocalReleaseTrack" t SET genre = $2, "updatedAt" = NOW()
+           FROM "LocalRelease" lr
+           WHERE t."localReleaseId" = lr.id
+             AND lr."artistId" = $1
+             AND (t.genre IS NULL OR btrim(t.genre) = '')"#,
+    )
+    .bind(artist_id)
+    .bind(genre)
+    .execute(&mut *conn)
+    .await?;
+    Ok(result.rows_affected())
+}
+
 async fn update_statistics(pool: &PgPool) -> Result<(), sqlx::Error> {
     use chrono::Utc;
     let now = Utc::now().naive_utc();
@@ -829,9 +1133,9 @@ async fn update_statistics(pool: &PgPool) -> Result<(), sqlx::Error> {
     .fetch_one(pool)
     .await?;
     
-    // Count artists with images
+    // Count artists with images (the indexer's generated placeholders aren't cover art)
     let artists_with_art: (i64,) = sqlx::query_as(
-        r#"SELECT COUNT(*)::bigint FROM "Artist" WHERE image IS NOT NULL"#
+        r#"SELECT COUNT(*)::bigint FROM "Artist" WHERE image IS NOT NULL AND NOT "imagePlaceholder""#
     )
     .fetch_one(pool)
     .await?;
@@ -901,38 +1205,265 @@ impl MatchStatus {
 
 }
 
+/// One spelling for the punctuation variants NFKC leaves apart (curly
+/// quotes, dashes, CJK brackets and stops).
+fn fold_punctuation(c: char) -> char {
+    match c {
+        '‘' | '’' | '‚' | '‛' | '′' | '`' | '´' | 'ʼ' => '\'',
+        '“' | '”' | '„' | '‟' | '″' | '「' | '」' | '『' | '』' => '"',
+        '‐' | '‑' | '‒' | '–' | '—' | '―' | '−' => '-',
+        '〜' | '～' => '~',
+        '。' => '.',
+        '、' => ',',
+        _ => c,
+    }
+}
+
+/// Title key for matching local tracks and releases with MusicBrainz. NFKC
+/// folds full- and half-width forms and compatibility characters ("Ｔｏｋｙｏ"
+/// is "tokyo", "…" is "..."), apostrophes are dropped ("Don’t" = "Dont") and
+/// other punctuation separates words. Letters of every script, digits and
+/// combining marks are kept. A title of nothing but punctuation ("...", "!!!")
+/// keeps it, so such titles don't all match each other.
 fn normalize_title(title: &str) -> String {
-    title
-        .to_lowercase()
+    let folded: String = title.chars().map(fold_punctuation).nfkc().flat_map(char::to_lowercase).collect();
+    let words: String = folded
         .chars()
-        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
-        .collect::<String>()
-        .split_whitespace()
-        .collect::<Vec<&str>>()
-        .join(" ")
+        .filter(|c| *c != '\'')
+        .map(|c| if c.is_alphanumeric() || is_combining_mark(c) { c } else { ' ' })
+        .collect();
+    let key = words.split_whitespace().collect::<Vec<&str>>().join(" ");
+    if key.is_empty() {
+        return folded.split_whitespace().collect();
+    }
+    key
 }
 
-async fn check_release_status(
-    pool: &PgPool,
-    artist_id: &str,
-    mb_release_id: &str,
-    mb_release_title: &str,
-    mb_tracks: &[(String, Option<i32>)], // (title, position)
-) -> Result<(MatchStatus, Option<JsonValue>, Option<JsonValue>, f64), sqlx::Error> {
-    // Find matching local release
-    let local_release: Option<(String,)> = sqlx::query_as(
-        r#"SELECT id FROM "LocalRelease" WHERE "artistId" = $1 AND LOWER(title) = LOWER($2)"#,
-    )
-    .bind(artist_id)
-    .bind(mb_release_title)
-    .fetch_optional(pool)
-    .await?;
+/// The digit runs of a title key ("part 2 of 3" -> ["2", "3"]).
+fn title_numbers(key: &str) -> Vec<&str> {
+    key.split(|c: char| !c.is_numeric()).filter(|n| !n.is_empty()).collect()
+}
 
-    let local_release_id = match local_release {
-        Some((id,)) => id,
-        None => {
-            return Ok((MatchStatus::Missing, None, None, 0.0));
+/// One-to-one pairs (left index, right index) between title keys that had no
+/// exact match, best score first, each scoring at least `threshold`. Keys
+/// whose numbers differ ("part 1" / "part 2") never pair.
+fn fuzzy_title_pairs(left: &[String], right: &[String], threshold: f64) -> Vec<(usize, usize)> {
+    let mut scored: Vec<(f64, usize, usize)> = Vec::new();
+    for (i, l) in left.iter().enumerate() {
+        for (j, r) in right.iter().enumerate() {
+            if title_numbers(l) != title_numbers(r) {
+                continue;
+            }
+            let score = strsim::normalized_levenshtein(l, r);
+            if score >= threshold {
+                scored.push((score, i, j));
+            }
+        }
+    }
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let (mut used_left, mut used_right) = (HashSet::new(), HashSet::new());
+    let mut pairs = Vec::new();
+    for (_, i, j) in scored {
+        if used_left.contains(&i) || used_right.contains(&j) {
+            continue;
+        }
+        used_left.insert(i);
+        used_right.insert(j);
+        pairs.push((i, j));
+    }
+    pairs
+}
+
+/// Outcome of comparing a local release with its MB release.
+struct ReleaseCheck {
+    status: MatchStatus,
+    #[allow(dead_code)]
+    missing: Option<JsonValue>,
+    #[allow(dead_code)]
+    extra: Option<JsonValue>,
+    score: f64,
+    /// Matched tracks whose length is off by more than --duration-tolerance
+    duration_mismatches: u32,
+}
+
+impl ReleaseCheck {
+    fn new(status: MatchStatus, missing: Option<JsonValue>, extra: Option<JsonValue>, score: f64) -> Self {
+        ReleaseCheck { status, missing, extra, score, duration_mismatches: 0 }
+    }
+}
+
+/// (id, title, position, durationMs) of a MusicBrainzReleaseTrack row.
+type MbTrackRow = (String, String, Option<i32>, Option<i32>);
+/// (id, title, trackNumber, duration) of a LocalReleaseTrack row.
+type LocalTrackRow = (String, Option<String>, Option<i32>, Option<i32>);
+
+/// Local track length minus MB length, in whole seconds (None if either is unknown).
+fn duration_delta(local_secs: Option<i32>, mb_ms: Option<i32>) -> Option<i32> {
+    let mb_secs = (mb_ms? as f64 / 1000.0).round() as i32;
+    Some(local_secs? - mb_secs)
+}
+
+/// Point each local track at the MB track with the same normalized title (the
+/// one at the same position when a title repeats), or with `fuzzy` at the
+/// closest unused one, and record how far their lengths are apart. Returns
+/// how many differ by more than `tolerance` seconds.
+async fn link_release_tracks(
+    conn: &mut PgConnection,
+    local_release_id: &str,
+    mb_release_id: &str,
+    tolerance: u32,
+    fuzzy: Option<f64>,
+) -> Result<u32, sqlx::Error> {
+    let mb_tracks: Vec<MbTrackRow> = sqlx::query_as(
+        r#"SELECT id, title, position, "durationMs" FROM "MusicBrainzReleaseTrack" WHERE "releaseId" = $1"#,
+    )
+    .bind(mb_release_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    let local_tracks: Vec<LocalTrackRow> = sqlx::query_as(
+        r#"SELECT id, title, "trackNumber", duration FROM "LocalReleaseTrack" WHERE "localReleaseId" = $1"#,
+    )
+    .bind(local_release_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut by_title: HashMap<String, Vec<&MbTrackRow>> = HashMap::new();
+    for track in &mb_tracks {
+        by_title.entry(normalize_title(&track.1)).or_default().push(track);
+    }
+
+    let mut linked: Vec<Option<&MbTrackRow>> = local_tracks.iter()
+        .map(|(_, title, track_number, _)| {
+            let candidates = by_title
+                .get(&normalize_title(title.as_deref().unwrap_or("")))
+                .map(Vec::as_slice)
+                .unwrap_or(&[]);
+            candidates.iter()
+                .find(|t| t.2.is_some() && t.2 == *track_number)
+                .or_else(|| candidates.first())
+                .copied()
+        })
+        .collect();
+    if let Some(threshold) = fuzzy {
+        let used: HashSet<&str> = linked.iter().flatten().map(|t| t.0.as_str()).collect();
+        let unlinked: Vec<usize> = (0..local_tracks.len()).filter(|i| linked[*i].is_none()).collect();
+        let unused: Vec<&MbTrackRow> = mb_tracks.iter().filter(|t| !used.contains(t.0.as_str())).collect();
+        let left: Vec<String> = unlinked.iter()
+            .map(|i| normalize_title(local_tracks[*i].1.as_deref().unwrap_or("")))
+            .collect();
+        let right: Vec<String> = unused.iter().map(|t| normalize_title(&t.1)).collect();
+        for (i, j) in fuzzy_title_pairs(&left, &right, threshold) {
+            linked[unlinked[i]] = Some(unused[j]);
         }
+    }
+
+    // One UPDATE for the whole release
+    let mut ids: Vec<&str> = Vec::with_capacity(local_tracks.len());
+    let mut mb_track_ids: Vec<Option<&str>> = Vec::with_capacity(local_tracks.len());
+    let mut deltas: Vec<Option<i32>> = Vec::with_capacity(local_tracks.len());
+    let mut flags: Vec<bool> = Vec::with_capacity(local_tracks.len());
+    for ((local_id, _, _, duration), mb_track) in local_tracks.iter().zip(linked) {
+        let (mb_track_id, delta) = match mb_track {
+            Some(t) => (Some(t.0.as_str()), duration_delta(*duration, t.3)),
+            None => (None, None),
+        };
+        ids.push(local_id);
+        mb_track_ids.push(mb_track_id);
+        deltas.push(delta);
+        flags.push(tolerance > 0 && delta.is_some_and(|d| d.unsigned_abs() > tolerance));
+    }
+    if !ids.is_empty() {
+        sqlx::query(
+            r#"UPDATE "LocalReleaseTrack" t SET
+                 "mbTrackId" = u.mb, "durationDelta" = u.delta, "durationMismatch" = u.mismatch
+               FROM UNNEST($1::text[], $2::text[], $3::int[], $4::bool[]) AS u(id, mb, delta, mismatch)
+               WHERE t.id = u.id"#,
+        )
+        .bind(&ids)
+        .bind(&mb_track_ids)
+        .bind(&deltas)
+        .bind(&flags)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(flags.iter().filter(|f| **f).count() as u32)
+}
+
+/// The artist's local release whose title only differs from the MB release's
+/// by width, punctuation or (with `fuzzy`) spelling. Releases already linked
+/// to another MB release, or tagged with another release group, aren't considered.
+async fn find_release_by_title(
+    conn: &mut PgConnection,
+    artist_id: &str,
+    mb_release_id: &str,
+    mb_release_title: &str,
+    fuzzy: Option<f64>,
+) -> Result<Option<String>, sqlx::Error> {
+    let releases: Vec<(String, String)> = sqlx::query_as(
+        r#"SELECT id, title FROM "LocalRelease"
+           WHERE "artistId" = $1 AND ("releaseId" IS NULL OR "releaseId" = $2)
+             AND ("musicbrainzReleaseGroupId" IS NULL
+                  OR "musicbrainzReleaseGroupId" = (SELECT "musicbrainzId" FROM "MusicBrainzRelease" WHERE id = $2))
+           ORDER BY id"#,
+    )
+    .bind(artist_id)
+    .bind(mb_release_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let key = normalize_title(mb_release_title);
+    let keys: Vec<String> = releases.iter().map(|(_, title)| normalize_title(title)).collect();
+    if let Some(i) = keys.iter().position(|k| *k == key) {
+        return Ok(Some(releases[i].0.clone()));
+    }
+    let Some(threshold) = fuzzy else {
+        return Ok(None);
+    };
+    Ok(fuzzy_title_pairs(&[key], &keys, threshold).first().map(|(_, j)| releases[*j].0.clone()))
+}
+
+async fn check_release_status(
+    conn: &mut PgConnection,
+    artist_id: &str,
+    mb_release_id: &str,
+    mb_release_title: &str,
+    mb_tracks: &[(String, Option<i32>)], // (title, position)
+    duration_tolerance: u32,
+    fuzzy: Option<f64>,
+) -> Result<ReleaseCheck, sqlx::Error> {
+    // Find matching local release: by the release group ID in its tags, then by title
+    // (skipping releases tagged with another release group)
+    let tagged: Option<(String,)> = sqlx::query_as(
+        r#"SELECT lr.id FROM "LocalRelease" lr
+           JOIN "MusicBrainzRelease" r ON r.id = $2
+           WHERE lr."artistId" = $1 AND lr."musicbrainzReleaseGroupId" = r."musicbrainzId"
+           ORDER BY lr.id LIMIT 1"#,
+    )
+    .bind(artist_id)
+    .bind(mb_release_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let local_release = match tagged {
+        Some(found) => Some(found),
+        None => sqlx::query_as(
+            r#"SELECT id FROM "LocalRelease" WHERE "artistId" = $1 AND LOWER(title) = LOWER($2)
+                 AND ("musicbrainzReleaseGroupId" IS NULL
+                      OR "musicbrainzReleaseGroupId" = (SELECT "musicbrainzId" FROM "MusicBrainzRelease" WHERE id = $3))"#,
+        )
+        .bind(artist_id)
+        .bind(mb_release_title)
+        .bind(mb_release_id)
+        .fetch_optional(&mut *conn)
+        .await?,
+    };
+
+    let local_release_id = match local_release {
+        Some((id,)) => id,
+        None => match find_release_by_title(&mut *conn, artist_id, mb_release_id, mb_release_title, fuzzy).await? {
+            Some(id) => id,
+            None => return Ok(ReleaseCheck::new(MatchStatus::Missing, None, None, 0.0)),
+        },
     };
 
     // Link MB release to local release
@@ -941,15 +1472,19 @@ async fn check_release_status(
     )
     .bind(mb_release_id)
     .bind(&local_release_id)
-    .execute(pool)
+    .execute(&mut *conn)
     .await?;
 
+    // Per-track links and length check
+    let duration_mismatches =
+        link_release_tracks(&mut *conn, &local_release_id, mb_release_id, duration_tolerance, fuzzy).await?;
+
     // Get local tracks
     let local_tracks: Vec<(String,)> = sqlx::query_as(
         r#"SELECT COALESCE(title, '') FROM "LocalReleaseTrack" WHERE "localReleaseId" = $1"#,
     )
     .bind(&local_release_id)
-    .fetch_all(pool)
+    .fetch_all(&mut *conn)
     .await?;
 
     let local_titles: HashSet<String> = local_tracks
@@ -963,26 +1498,36 @@ async fn check_release_status(
         .collect();
 
     // Find missing and extra (using HashSet O(1) lookups instead of Vec O(n))
-    let missing: Vec<String> = mb_tracks
+    let mut missing: Vec<String> = mb_tracks
         .iter()
         .filter(|(t, _)| !local_titles.contains(&normalize_title(t)))
         .map(|(t, _)| t.clone())
         .collect();
 
-    let extra: Vec<String> = local_tracks
+    let mut extra: Vec<String> = local_tracks
         .iter()
         .filter(|(t,)| !mb_titles.contains(&normalize_title(t)))
         .map(|(t,)| t.clone())
         .collect();
 
+    // Close spellings left on both sides are the same track
+    if let Some(threshold) = fuzzy {
+        let keys = |titles: &[String]| titles.iter().map(|t| normalize_title(t)).collect::<Vec<_>>();
+        let pairs = fuzzy_title_pairs(&keys(&missing), &keys(&extra), threshold);
+        let paired_missing: HashSet<usize> = pairs.iter().map(|(i, _)| *i).collect();
+        let paired_extra: HashSet<usize> = pairs.iter().map(|(_, j)| *j).collect();
+        missing = missing.into_iter().enumerate().filter(|(i, _)| !paired_missing.contains(i)).map(|(_, t)| t).collect();
+        extra = extra.into_iter().enumerate().filter(|(j, _)| !paired_extra.contains(j)).map(|(_, t)| t).collect();
+    }
+
     let mb_count = mb_tracks.len() as f64;
     let matched_count = mb_count - missing.len() as f64;
 
-    if missing.is_empty() && extra.is_empty() {
-        Ok((MatchStatus::Complete, None, None, 1.0))
+    let check = if missing.is_empty() && extra.is_empty() {
+        ReleaseCheck::new(MatchStatus::Complete, None, None, 1.0)
     } else if missing.is_empty() && !extra.is_empty() {
         let extra_json = serde_json::to_value(&extra).ok();
-        Ok((MatchStatus::ExtraTracks, None, extra_json, 1.0))
+        ReleaseCheck::new(MatchStatus::ExtraTracks, None, extra_json, 1.0)
     } else if !missing.is_empty() {
         let missing_json = serde_json::to_value(&missing).ok();
         let extra_json = if extra.is_empty() {
@@ -995,10 +1540,129 @@ async fn check_release_status(
         } else {
             0.0
         };
-        Ok((MatchStatus::Incomplete, missing_json, extra_json, score))
+        ReleaseCheck::new(MatchStatus::Incomplete, missing_json, extra_json, score)
     } else {
-        Ok((MatchStatus::Unknown, None, None, 0.0))
+        ReleaseCheck::new(MatchStatus::Unknown, None, None, 0.0)
+    };
+    Ok(ReleaseCheck { duration_mismatches, ..check })
+}
+
+/// A release group whose editions have been fetched from MusicBrainz, waiting
+/// to be written in its artist's transaction.
+struct StagedRelease<'a> {
+    rg: &'a MbReleaseGroup,
+    type_id: String,
+    year: Option<i32>,
+    editions: Vec<(MbRelease, Vec<MbTrack>)>,
+    /// Genre ids of the release group's MB genres
+    genre_ids: Vec<String>,
+}
+
+/// Write one staged release: the MB release, the chosen edition's tracks and the
+/// match status. Returns the match score and the number of tracks whose length
+/// is off, or `None` if MB lists no edition.
+async fn store_release(
+    conn: &mut PgConnection,
+    artist_id: &str,
+    staged: &StagedRelease<'_>,
+    args: &Args,
+) -> Result<Option<(f64, u32)>, sqlx::Error> {
+    let rg = staged.rg;
+    // Fetched after all: no longer needs a retry (restored if this release rolls back)
+    dequeue_retry(&mut *conn, artist_id, &rg.id).await?;
+    let mb_release_id =
+        upsert_mb_release(&mut *conn, artist_id, &rg.title, &staged.type_id, staged.year, &rg.id).await?;
+    link_release_genres(&mut *conn, &mb_release_id, &staged.genre_ids).await?;
+    if let Some(popularity) = Popularity::of(rg) {
+        store_popularity(&mut *conn, &mb_release_id, &popularity).await?;
     }
+
+    // Use the preferred edition's tracks (first, most canonical, by default)
+    let stored_edition: Option<String> = sqlx::query_scalar(
+        r#"SELECT "editionId" FROM "MusicBrainzRelease" WHERE id = $1"#,
+    )
+    .bind(&mb_release_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .flatten();
+    // The edition in the local files' tags, when MB returned it, beats any preference
+    let tagged_edition: Option<String> = sqlx::query_scalar(
+        r#"SELECT "musicbrainzReleaseId" FROM "LocalRelease"
+           WHERE "artistId" = $1 AND "musicbrainzReleaseGroupId" = $2 AND "musicbrainzReleaseId" IS NOT NULL
+           ORDER BY id LIMIT 1"#,
+    )
+    .bind(artist_id)
+    .bind(&rg.id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let edition = tagged_edition
+        .and_then(|id| staged.editions.iter().position(|(r, _)| r.id == id))
+        .or_else(|| {
+            select_edition(&staged.editions, &args.prefer_country, &args.prefer_format, stored_edition.as_deref())
+        })
+        .map(|i| &staged.editions[i]);
+
+    let Some((edition, tracks)) = edition else {
+        return Ok(None);
+    };
+
+    // Replace the release's tracks (single batch insert instead of N individual inserts)
+    delete_mb_tracks_for_release(&mut *conn, &mb_release_id).await?;
+    batch_insert_mb_tracks(&mut *conn, &mb_release_id, tracks, 1).await?;
+
+    let mb_track_pairs: Vec<(String, Option<i32>)> = tracks
+        .iter()
+        .map(|track| (track.title.clone(), track.position.map(|p| p as i32)))
+        .collect();
+
+    let check = check_release_status(
+        &mut *conn,
+        artist_id,
+        &mb_release_id,
+        &rg.title,
+        &mb_track_pairs,
+        args.duration_tolerance,
+        args.fuzzy_titles,
+    )
+    .await?;
+    let status = check.status;
+
+    // Update MB release status and the edition its tracks came from
+    let now = Utc::now().naive_utc();
+    let edition_format = release_formats(edition).first().map(|f| f.to_string());
+    sqlx::query(
+        r#"UPDATE "MusicBrainzRelease" SET
+             status = $1::"ReleaseStatus",
+             "editionId" = $4,
+             "editionCountry" = $5,
+             "editionFormat" = $6,
+             "updatedAt" = $2
+           WHERE id = $3"#,
+    )
+    .bind(status.as_str())
+    .bind(now)
+    .bind(&mb_release_id)
+    .bind(&edition.id)
+    .bind(&edition.country)
+    .bind(&edition_format)
+    .execute(&mut *conn)
+    .await?;
+
+    // Also update LocalRelease matchStatus / matchScore if linked
+    sqlx::query(
+        r#"UPDATE "LocalRelease" SET
+             "matchStatus" = $1::"ReleaseStatus",
+             "matchScore" = $3,
+             "updatedAt" = NOW()
+           WHERE "releaseId" = $2"#,
+    )
+    .bind(status.as_str())
+    .bind(&mb_release_id)
+    .bind(check.score)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(Some((check.score, check.duration_mismatches)))
 }
 
 // ---------------------------------------------------------------------------
@@ -1032,248 +1696,1552 @@ async fn load_sync_checkpoint(pool: &PgPool) -> Result<Option<(String, i32)>, sq
     .fetch_optional(pool)
     .await?;
 
-    Ok(row.map(|(slug, count)| (slug.unwrap_or_default(), count)))
+    Ok(row.map(|(slug, count)| (slug.unwrap_or_default(), count)))
+}
+
+async fn clear_sync_checkpoint(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(r#"DELETE FROM "SyncCheckpoint" WHERE id = 'main'"#)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// The indexer's checkpoint (last folder, files processed, last update), which
+/// exists while an index run is in progress or after one was interrupted.
+async fn load_index_checkpoint(
+    pool: &PgPool,
+) -> Result<Option<(String, i32, chrono::NaiveDateTime)>, sqlx::Error> {
+    let row: Option<(Option<String>, i32, chrono::NaiveDateTime)> = sqlx::query_as(
+        r#"SELECT "lastFolder", "filesProcessed", "updatedAt" FROM "IndexCheckpoint" WHERE id = 'main'"#,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|(folder, count, updated_at)| (folder.unwrap_or_default(), count, updated_at)))
+}
+
+// ---------------------------------------------------------------------------
+// Retry queue (--retry-failed)
+// ---------------------------------------------------------------------------
+
+/// Remember a release group whose editions could not be fetched, so it isn't
+/// lost until the artist's next 30-day refresh. Re-queuing bumps `attempts`.
+async fn queue_retry(
+    pool: &PgPool,
+    artist_id: &str,
+    rg: &MbReleaseGroup,
+    error: &str,
+) -> Result<(), sqlx::Error> {
+    let now = Utc::now().naive_utc();
+    sqlx::query(
+        r#"INSERT INTO "SyncRetryQueue"
+             (id, "artistId", "releaseGroupId", title, "primaryType", "firstReleaseDate", "lastError", "createdAt", "updatedAt")
+           VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $8)
+           ON CONFLICT ("artistId", "releaseGroupId") DO UPDATE SET
+             attempts = "SyncRetryQueue".attempts + 1,
+             title = $4, "primaryType" = $5, "firstReleaseDate" = $6,
+             "lastError" = $7, "updatedAt" = $8"#,
+    )
+    .bind(cuid2::create_id())
+    .bind(artist_id)
+    .bind(&rg.id)
+    .bind(&rg.title)
+    .bind(&rg.primary_type)
+    .bind(&rg.first_release_date)
+    .bind(error)
+    .bind(now)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Drop a release group from the queue once it has been stored.
+async fn dequeue_retry(conn: &mut PgConnection, artist_id: &str, release_group_id: &str) -> Result<(), sqlx::Error> {
+    sqlx::query(r#"DELETE FROM "SyncRetryQueue" WHERE "artistId" = $1 AND "releaseGroupId" = $2"#)
+        .bind(artist_id)
+        .bind(release_group_id)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+async fn retry_queue_size(pool: &PgPool) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar(r#"SELECT COUNT(*) FROM "SyncRetryQueue""#)
+        .fetch_one(pool)
+        .await
+}
+
+/// artistId, artist name, release group id, title, primary type, first release date, attempts
+type RetryRow = (String, String, String, String, Option<String>, Option<String>, i32);
+
+/// One artist's queued release groups with their failed attempts so far.
+type RetryArtist = (String, String, Vec<(MbReleaseGroup, i32)>);
+
+/// Outcome of a `--retry-failed` run.
+#[derive(Default)]
+struct RetrySummary {
+    queued: u32,
+    recovered: u32,
+    still_failing: u32,
+    /// Gone from MusicBrainz (deleted / merged), dropped from the queue
+    gone: u32,
+    duration_mismatches: u32,
+}
+
+/// Fetch and store only the queued release groups (honouring --only / --from /
+/// --to on the artist name). Each artist's recovered releases are written in one
+/// transaction, like a normal sync; the artist's other releases are left alone.
+async fn retry_failed_releases(
+    pool: &PgPool,
+    client: &Client,
+    limiter: &mut RateLimiter,
+    args: &Args,
+    error_log: &Mutex<fs::File>,
+) -> Result<RetrySummary, sqlx::Error> {
+    let (conditions, binds) = artist_filter(args).sql_conditions("a.name", 1);
+    let query = format!(
+        r#"SELECT q."artistId", a.name, q."releaseGroupId", q.title, q."primaryType", q."firstReleaseDate", q.attempts
+           FROM "SyncRetryQueue" q
+           JOIN "Artist" a ON a.id = q."artistId"
+           {}
+           ORDER BY a.slug, a.id, q."firstReleaseDate" NULLS LAST, q.title"#,
+        if conditions.is_empty() { String::new() } else { format!("WHERE {}", conditions.join(" AND ")) }
+    );
+    let mut q = sqlx::query_as(&query);
+    for b in &binds {
+        q = q.bind(b);
+    }
+    let rows: Vec<RetryRow> = q.fetch_all(pool).await?;
+
+    let mut summary = RetrySummary { queued: rows.len() as u32, ..Default::default() };
+    println!("Queued release groups: {}", rows.len());
+
+    let mut release_type_cache: HashMap<String, String> = HashMap::new();
+    let mut by_artist: Vec<RetryArtist> = Vec::new();
+    for (artist_id, artist_name, rg_id, title, primary_type, first_release_date, attempts) in rows {
+        let rg = MbReleaseGroup {
+            id: rg_id, title, primary_type, secondary_types: None, first_release_date,
+            genres: None, rating: None, tags: None,
+        };
+        match by_artist.last_mut() {
+            Some((id, _, groups)) if *id == artist_id => groups.push((rg, attempts)),
+            _ => by_artist.push((artist_id, artist_name, vec![(rg, attempts)])),
+        }
+    }
+
+    let total = by_artist.len();
+    for (idx, (artist_id, artist_name, groups)) in by_artist.iter().enumerate() {
+        println!("\n{} {} {}",
+            format!("[{}/{}]", idx + 1, total).bright_blue().bold(),
+            "Retrying:".white(),
+            artist_name.bright_cyan().bold()
+        );
+
+        let mut staged_releases: Vec<StagedRelease> = Vec::new();
+        let mut rate_limited = false;
+        for (rg, attempts) in groups {
+            print!("    {} {} ({}, attempt {})... ",
+                "→".bright_black(),
+                rg.title.bright_white(),
+                rg.primary_type.as_deref().unwrap_or("Album").bright_black(),
+                attempts + 1
+            );
+            std::io::Write::flush(&mut std::io::stdout()).ok();
+
+            let release_type = rg.primary_type.as_deref().unwrap_or("Album");
+            let year = rg
+                .first_release_date
+                .as_ref()
+                .and_then(|d| d.split('-').next())
+                .and_then(|y| y.parse::<i32>().ok());
+            let type_id = ensure_release_type_cached(pool, release_type, &mut release_type_cache).await?;
+
+            match mb_get_release_tracks(client, &rg.id, limiter).await {
+                Ok(editions) => {
+                    println!("{}", "✓".green());
+                    staged_releases.push(StagedRelease { rg, type_id, year, editions, genre_ids: Vec::new() });
+                }
+                Err(e) if mb::is_gone(&e) => {
+                    println!("{}", "gone from MusicBrainz".yellow());
+                    summary.gone += 1;
+                    if let Ok(mut conn) = pool.acquire().await {
+                        dequeue_retry(&mut conn, artist_id, &rg.id).await.ok();
+                    }
+                    if let Ok(mut f) = error_log.lock() {
+                        writeln!(f, "[SYNC] Release group '{}' ({}) by '{}' no longer exists on MusicBrainz (deleted or merged), dropped from the retry queue", rg.title, rg.id, artist_name).ok();
+                    }
+                }
+                Err(e) => {
+                    println!("{} {}", "✗".red(), e.yellow());
+                    summary.still_failing += 1;
+                    queue_retry(pool, artist_id, rg, &e).await.ok();
+                    if let Ok(mut f) = error_log.lock() {
+                        writeln!(f, "[SYNC] Retry failed for release '{}' by '{}': {}", rg.title, artist_name, e).ok();
+                    }
+                    // MusicBrainz is still throttling: keep the rest queued for later
+                    if e.contains("still unavailable after") {
+                        rate_limited = true;
+                        break;
+                    }
+                }
+            }
+        }
+
+        // Store what was fetched even if the run stops here
+        let mut tx = pool.begin().await?;
+        for staged in &staged_releases {
+            let stored = match Connection::begin(&mut *tx).await {
+                Ok(mut savepoint) => match store_release(&mut savepoint, artist_id, staged, args).await {
+                    Ok(score) => savepoint.commit().await.map(|_| score),
+                    Err(e) => {
+                        savepoint.rollback().await.ok();
+                        Err(e)
+                    }
+                },
+                Err(e) => Err(e),
+            };
+            match stored {
+                Ok(result) => {
+                    summary.recovered += 1;
+                    summary.duration_mismatches += result.map_or(0, |(_, mismatches)| mismatches);
+                }
+                Err(e) => {
+                    summary.still_failing += 1;
+                    eprintln!("  ⚠ Release '{}' by '{}': DB error - {}", staged.rg.title, artist_name, e);
+                    if let Ok(mut f) = error_log.lock() {
+                        writeln!(f, "[SYNC] DB error storing release '{}' for artist '{}': {}", staged.rg.title, artist_name, e).ok();
+                    }
+                }
+            }
+        }
+        tx.commit().await?;
+
+        if rate_limited {
+            println!("  {} Stopping: MusicBrainz is still rate limiting, the rest stays queued", "⚠".yellow());
+            break;
+        }
+    }
+
+    Ok(summary)
+}
+
+// ---------------------------------------------------------------------------
+// Artist image download
+// ---------------------------------------------------------------------------
+
+/// Before an artist image column (`image`, `backgroundImage`, `logoImage`, and
+/// its `<column>Url`) is overwritten: queue what it pointed at for `clean` when
+/// that was another object than `new_key` (e.g. the artist's slug changed).
+async fn queue_replaced_artist_image(pool: &PgPool, artist_id: &str, column: &str, new_key: &str) {
+    let query = format!(r#"SELECT "{0}", "{0}Url" FROM "Artist" WHERE id = $1"#, column);
+    let Ok(Some((local, url))) = sqlx::query_as::<_, (Option<String>, Option<String>)>(&query)
+        .bind(artist_id)
+        .fetch_optional(pool)
+        .await
+    else {
+        return;
+    };
+    let old_keys: BTreeSet<String> = [local, url].iter()
+        .flatten()
+        .filter_map(|r| image_object_key(r))
+        .filter(|key| key != new_key)
+        .collect();
+    for key in &old_keys {
+        queue_s3_deletion(pool, key).await.ok();
+    }
+}
+
+async fn download_artist_image(
+    client: &Client,
+    artist: &MbArtistDetail,
+    artist_slug: &str,
+    img_dir: &PathBuf,
+    s3_client: &Option<S3Client>,
+    config: &SyncConfig,
+    pool: &PgPool,
+    artist_id: &str,
+    fanart: Option<&FanartArtistResponse>,
+    wiki: &WikiPrefs,
+) -> Option<String> {
+    let out_path = img_dir.join(format!("{}.jpg", artist_slug));
+    let use_s3 = config.image_storage == "s3" || config.image_storage == "both";
+    let use_local = config.image_storage == "local" || config.image_storage == "both";
+
+    // Try to obtain a source image URL: Wikipedia first, then Fanart.tv
+    let img_url = cached_wiki_image(client, pool, artist_id, artist, wiki)
+        .await
+        .or_else(|| fanart.and_then(fanart_thumb_url))?;
+
+    // Download and resize to local temp file
+    if !download_and_resize(client, &img_url, &out_path).await {
+        return None;
+    }
+
+    queue_replaced_artist_image(pool, artist_id, "image", &format!("artists/{}.jpg", artist_slug)).await;
+
+    // Track whether at least one storage path wrote to the DB
+    let mut stored = false;
+
+    if use_s3 {
+        if let (Some(ref s3), Some(ref bucket), Some(ref public_url)) =
+            (s3_client, &config.s3_bucket, &config.s3_public_url)
+        {
+            let s3_key = format!("artists/{}.jpg", artist_slug);
+            if upload_to_s3(s3, bucket, &s3_key, &out_path, "image/jpeg").await.is_ok() {
+                let image_url = format!("{}/{}", public_url.trim_end_matches('/'), s3_key);
+                sqlx::query(
+                    r#"UPDATE "Artist" SET "imageUrl" = $1, "imagePlaceholder" = false, "updatedAt" = NOW() WHERE id = $2"#,
+                )
+                .bind(&image_url)
+                .bind(artist_id)
+                .execute(pool)
+                .await
+                .ok();
+                stored = true;
+            }
+        }
+    }
+
+    if use_local {
+        let local_filename = format!("{}.jpg", artist_slug);
+        sqlx::query(r#"UPDATE "Artist" SET image = $1, "imagePlaceholder" = false, "updatedAt" = NOW() WHERE id = $2"#)
+            .bind(&local_filename)
+            .bind(artist_id)
+            .execute(pool)
+            .await
+            .ok();
+        stored = true;
+    }
+
+    // Remove temp file when only using S3
+    if !use_local && use_s3 {
+        fs::remove_file(&out_path).ok();
+    }
+
+    if stored {
+        Some(format!("/img/artists/{}.jpg", artist_slug))
+    } else {
+        None
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Wikipedia / Wikidata artist images
+// ---------------------------------------------------------------------------
+
+/// --wiki-languages / --wiki-cache-days
+struct WikiPrefs {
+    /// Lowercased language codes, in order of preference
+    languages: Vec<String>,
+    cache_days: u32,
+}
+
+impl WikiPrefs {
+    fn from_args(args: &Args) -> Self {
+        let languages = args.wiki_languages.iter()
+            .map(|l| l.trim().to_lowercase())
+            .filter(|l| !l.is_empty() && l.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+            .collect();
+        WikiPrefs { languages, cache_days: args.wiki_cache_days }
+    }
+}
+
+/// The artist's Wikipedia/Wikidata image, from `Artist.wikiImageUrl` while the
+/// last lookup is younger than --wiki-cache-days. A lookup that found nothing
+/// is cached too, so entities without an image aren't queried every run.
+async fn cached_wiki_image(
+    client: &Client,
+    pool: &PgPool,
+    artist_id: &str,
+    artist: &MbArtistDetail,
+    wiki: &WikiPrefs,
+) -> Option<String> {
+    if wiki.cache_days > 0 {
+        let cached: Option<(Option<String>, Option<chrono::NaiveDateTime>)> = sqlx::query_as(
+            r#"SELECT "wikiImageUrl", "wikiCheckedAt" FROM "Artist" WHERE id = $1"#,
+        )
+        .bind(artist_id)
+        .fetch_optional(pool)
+        .await
+        .ok()
+        .flatten();
+        if let Some((url, Some(checked_at))) = cached {
+            if (Utc::now().naive_utc() - checked_at).num_days() < wiki.cache_days as i64 {
+                return url;
+            }
+        }
+    }
+
+    let url = get_wikipedia_image(client, artist.relations.as_deref().unwrap_or(&[]), &wiki.languages).await;
+    sqlx::query(
+        r#"UPDATE "Artist" SET "wikiImageUrl" = $1, "wikiCheckedAt" = NOW() WHERE id = $2"#,
+    )
+    .bind(&url)
+    .bind(artist_id)
+    .execute(pool)
+    .await
+    .ok();
+    url
+}
+
+/// ("de", "Kraftwerk") from "https://de.wikipedia.org/wiki/Kraftwerk". The title
+/// is returned as it appears in the URL (already percent-encoded).
+fn wikipedia_page(url: &str) -> Option<(String, String)> {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let (host, path) = rest.split_once('/')?;
+    let lang = host.strip_suffix(".wikipedia.org")?.trim_start_matches("m.");
+    let title = path.strip_prefix("wiki/")?;
+    if lang.is_empty() || title.is_empty() {
+        return None;
+    }
+    Some((lang.split('.').next()?.to_lowercase(), title.to_string()))
+}
+
+/// Pages to try, best first: each preferred language (MB's own link, else the
+/// Wikidata sitelink), then MB's links in other languages.
+fn wiki_candidates(
+    languages: &[String],
+    mb_pages: &[(String, String)],
+    sitelinks: &[(String, String)],
+) -> Vec<(String, String)> {
+    let mut candidates: Vec<(String, String)> = Vec::new();
+    for lang in languages {
+        let page = mb_pages.iter().find(|(l, _)| l == lang)
+            .or_else(|| sitelinks.iter().find(|(l, _)| l == lang));
+        if let Some(page) = page {
+            candidates.push(page.clone());
+        }
+    }
+    for page in mb_pages {
+        if !candidates.iter().any(|(l, _)| *l == page.0) {
+            candidates.push(page.clone());
+        }
+    }
+    candidates
+}
+
+/// Commons image (P18) of a Wikidata entity and its Wikipedia sitelinks as
+/// (language, percent-encoded title).
+async fn fetch_wikidata(client: &Client, entity_id: &str) -> Option<(Option<String>, Vec<(String, String)>)> {
+    let api_url = format!(
+        "https://www.wikidata.org/w/api.php?action=wbgetentities&ids={}&props=claims|sitelinks&format=json",
+        entity_id
+    );
+    let resp = client
+        .get(&api_url)
+        .header("User-Agent", USER_AGENT)
+        .send()
+        .await
+        .ok()?;
+    let body: JsonValue = resp.json().await.ok()?;
+    let entity = body.get("entities")?.get(entity_id)?;
+
+    // P18 (image) is a Commons filename
+    let image = entity.pointer("/claims/P18/0/mainsnak/datavalue/value")
+        .and_then(|v| v.as_str())
+        .map(|filename| format!(
+            "https://commons.wikimedia.org/wiki/Special:FilePath/{}?width=500",
+            urlencoding::encode(filename)
+        ));
+
+    // "dewiki" -> de; skips wikiquote, commonswiki, etc.
+    let sitelinks = entity.get("sitelinks")
+        .and_then(|s| s.as_object())
+        .map(|links| {
+            links.iter()
+                .filter_map(|(site, link)| {
+                    let lang = site.strip_suffix("wiki")?;
+                    if lang.is_empty() || lang == "commons" || lang == "species" || lang.contains("quote") {
+                        return None;
+                    }
+                    let title = link.get("title")?.as_str()?.replace(' ', "_");
+                    Some((lang.replace('_', "-"), urlencoding::encode(&title).into_owned()))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    Some((image, sitelinks))
+}
+
+/// Lead image of a Wikipedia page (`title` percent-encoded).
+async fn fetch_page_image(client: &Client, lang: &str, title: &str) -> Option<String> {
+    let api_url = format!(
+        "https://{}.wikipedia.org/w/api.php?action=query&titles={}&prop=pageimages&format=json&pithumbsize=500&redirects=1",
+        lang, title
+    );
+
+    let resp = client
+        .get(&api_url)
+        .header("User-Agent", USER_AGENT)
+        .send()
+        .await
+        .ok()?;
+
+    let body: JsonValue = resp.json().await.ok()?;
+    let pages = body.get("query")?.get("pages")?;
+
+    if let JsonValue::Object(map) = pages {
+        for (_, page) in map {
+            if let Some(thumb) = page.get("thumbnail") {
+                return thumb.get("source")?.as_str().map(|s| s.to_string());
+            }
+        }
+    }
+
+    None
+}
+
+/// Artist image from the MB artist's Wikidata/Wikipedia relations: the Wikidata
+/// P18 image if there is one, else the lead image of the artist's page in the
+/// first of `languages` that has one.
+async fn get_wikipedia_image(client: &Client, relations: &[MbRelation], languages: &[String]) -> Option<String> {
+    let urls = relations.iter()
+        .filter_map(|rel| rel.url.as_ref().map(|u| (rel.relation_type.as_str(), u.resource.as_str())));
+
+    let mut entity_id: Option<&str> = None;
+    let mut mb_pages: Vec<(String, String)> = Vec::new();
+    for (kind, url) in urls {
+        match kind {
+            "wikidata" => entity_id = entity_id.or_else(|| url.rsplit('/').next().filter(|id| id.starts_with('Q'))),
+            "wikipedia" => mb_pages.extend(wikipedia_page(url)),
+            _ => {}
+        }
+    }
+
+    let mut sitelinks: Vec<(String, String)> = Vec::new();
+    if let Some(id) = entity_id {
+        if let Some((image, links)) = fetch_wikidata(client, id).await {
+            if image.is_some() {
+                return image;
+            }
+            sitelinks = links;
+        }
+    }
+
+    for (lang, title) in wiki_candidates(languages, &mb_pages, &sitelinks) {
+        if let Some(image) = fetch_page_image(client, &lang, &title).await {
+            return Some(image);
+        }
+    }
+    None
+}
+
+async fn fetch_fanart(client: &Client, mb_id: &str) -> Option<FanartArtistResponse> {
+    // Fanart.tv API - no key needed for basic access
+    let url = format!(
+        "https://webservice.fanart.tv/v3/music/{}?api_key={}",
+        mb_id, "NO_KEY"
+    );
+
+    let resp = client
+        .get(&url)
+        .header("User-Agent", USER_AGENT)
+        .send()
+        .await
+        .ok()?;
+
+    if resp.status() != 200 {
+        return None;
+    }
+
+    resp.json().await.ok()
+}
+
+fn fanart_thumb_url(data: &FanartArtistResponse) -> Option<String> {
+    // Prefer artistthumb > artistbackground (logos are stored separately)
+    if let Some(ref thumbs) = data.artistthumb {
+        if let Some(first) = thumbs.first() {
+            return Some(first.url.clone());
+        }
+    }
+    if let Some(ref bgs) = data.artistbackground {
+        if let Some(first) = bgs.first() {
+            return Some(first.url.clone());
+        }
+    }
+
+    None
+}
+
+async fn download_and_resize(client: &Client, url: &str, out_path: &PathBuf) -> bool {
+    let resp = match client
+        .get(url)
+        .header("User-Agent", USER_AGENT)
+        .send()
+        .await
+    {
+        Ok(r) => r,
+        Err(_) => return false,
+    };
+
+    if resp.status() != 200 {
+        return false;
+    }
+
+    let bytes = match resp.bytes().await {
+        Ok(b) => b,
+        Err(_) => return false,
+    };
+
+    match image::load_from_memory(&bytes) {
+        Ok(img) => {
+            let resized =
+                img.resize_to_fill(200, 200, image::imageops::FilterType::Lanczos3);
+            if let Some(parent) = out_path.parent() {
+                fs::create_dir_all(parent).ok();
+            }
+            resized.save(out_path).is_ok()
+        }
+        Err(_) => false,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Fanart.tv background and logo
+// ---------------------------------------------------------------------------
+
+/// Upper bound on a single fanart download; HD backgrounds are usually 1–3 MB.
+const FANART_MAX_BYTES: usize = 8 * 1024 * 1024;
+
+/// Download an image and shrink it to fit within `max_w` x `max_h`, keeping its aspect ratio.
+/// Payloads larger than FANART_MAX_BYTES are rejected before decoding.
+async fn download_and_limit(
+    client: &Client,
+    url: &str,
+    out_path: &Path,
+    max_w: u32,
+    max_h: u32,
+) -> bool {
+    let resp = match client
+        .get(url)
+        .header("User-Agent", USER_AGENT)
+        .send()
+        .await
+    {
+        Ok(r) => r,
+        Err(_) => return false,
+    };
+
+    if resp.status() != 200 {
+        return false;
+    }
+    if resp.content_length().is_some_and(|len| len as usize > FANART_MAX_BYTES) {
+        return false;
+    }
+
+    let bytes = match resp.bytes().await {
+        Ok(b) if b.len() <= FANART_MAX_BYTES => b,
+        _ => return false,
+    };
+
+    match image::load_from_memory(&bytes) {
+        Ok(img) => {
+            let img = if img.width() > max_w || img.height() > max_h {
+                img.resize(max_w, max_h, image::imageops::FilterType::Lanczos3)
+            } else {
+                img
+            };
+            if let Some(parent) = out_path.parent() {
+                fs::create_dir_all(parent).ok();
+            }
+            img.save(out_path).is_ok()
+        }
+        Err(_) => false,
+    }
+}
+
+/// Store a downloaded artist asset according to IMAGE_STORAGE.
+/// `column` is the local-filename column; the S3 URL goes to `<column>Url`.
+/// `relative` is the path under img/artists/ (also used as the S3 key suffix).
+#[allow(clippy::too_many_arguments)]
+async fn store_artist_asset(
+    s3_client: &Option<S3Client>,
+    config: &SyncConfig,
+    pool: &PgPool,
+    artist_id: &str,
+    column: &str,
+    relative: &str,
+    out_path: &Path,
+    content_type: &str,
+) -> bool {
+    let use_s3 = config.image_storage == "s3" || config.image_storage == "both";
+    let use_local = config.image_storage == "local" || config.image_storage == "both";
+    let mut stored = false;
+    queue_replaced_artist_image(pool, artist_id, column, &format!("artists/{}", relative)).await;
+
+    if use_s3 {
+        if let (Some(ref s3), Some(ref bucket), Some(ref public_url)) =
+            (s3_client, &config.s3_bucket, &config.s3_public_url)
+        {
+            let s3_key = format!("artists/{}", relative);
+            if upload_to_s3(s3, bucket, &s3_key, out_path, content_type).await.is_ok() {
+                let image_url = format!("{}/{}", public_url.trim_end_matches('/'), s3_key);
+                let query = format!(
+                    r#"UPDATE "Artist" SET "{}Url" = $1, "updatedAt" = NOW() WHERE id = $2"#,
+                    column
+                );
+                sqlx::query(&query)
+                    .bind(&image_url)
+                    .bind(artist_id)
+                    .execute(pool)
+                    .await
+                    .ok();
+                stored = true;
+            }
+        }
+    }
+
+    if use_local {
+        let query = format!(
+            r#"UPDATE "Artist" SET "{}" = $1, "updatedAt" = NOW() WHERE id = $2"#,
+            column
+        );
+        sqlx::query(&query)
+            .bind(relative)
+            .bind(artist_id)
+            .execute(pool)
+            .await
+            .ok();
+        stored = true;
+    }
+
+    // Remove temp file when only using S3
+    if !use_local && use_s3 {
+        fs::remove_file(out_path).ok();
+    }
+
+    stored
+}
+
+/// Download the fanart.tv background (JPEG, max 1920x1080) and HD logo (PNG, max 800x310)
+/// into img/artists/backgrounds/ and img/artists/logos/.
+/// Returns (background_stored, logo_stored).
+#[allow(clippy::too_many_arguments)]
+async fn download_artist_fanart(
+    client: &Client,
+    fanart: &FanartArtistResponse,
+    artist_slug: &str,
+    img_dir: &Path,
+    s3_client: &Option<S3Client>,
+    config: &SyncConfig,
+    pool: &PgPool,
+    artist_id: &str,
+) -> (bool, bool) {
+    let mut background = false;
+    let mut logo = false;
+
+    if let Some(first) = fanart.artistbackground.as_ref().and_then(|v| v.first()) {
+        let relative = format!("backgrounds/{}.jpg", artist_slug);
+        let out_path = img_dir.join(&relative);
+        if download_and_limit(client, &first.url, &out_path, 1920, 1080).await {
+            background = store_artist_asset(
+                s3_client, config, pool, artist_id, "backgroundImage", &relative, &out_path, "image/jpeg",
+            )
+            .await;
+        }
+    }
+
+    // PNG keeps the logo's transparency
+    if let Some(first) = fanart.hdmusiclogo.as_ref().and_then(|v| v.first()) {
+        let relative = format!("logos/{}.png", artist_slug);
+        let out_path = img_dir.join(&relative);
+        if download_and_limit(client, &first.url, &out_path, 800, 310).await {
+            logo = store_artist_asset(
+                s3_client, config, pool, artist_id, "logoImage", &relative, &out_path, "image/png",
+            )
+            .await;
+        }
+    }
+
+    (background, logo)
+}
+
+/// Where artist images go, shared by the sync loop and --images-only.
+struct ImageStore<'a> {
+    dir: &'a PathBuf,
+    s3_client: &'a Option<S3Client>,
+    config: &'a SyncConfig,
+    wiki: &'a WikiPrefs,
+}
+
+/// The artist image (Wikipedia/Wikidata, else Fanart.tv) plus the Fanart.tv
+/// background and HD logo. Returns whether an artist image was stored.
+async fn sync_artist_images(
+    client: &Client,
+    pool: &PgPool,
+    store: &ImageStore<'_>,
+    detail: &MbArtistDetail,
+    artist_id: &str,
+    artist_slug: &str,
+) -> bool {
+    // Fanart.tv data is shared by the thumb fallback and the background/logo pass
+    let fanart = fetch_fanart(client, &detail.id).await;
+
+    // Artist image
+    print!("  {} Downloading artist image... ", "→".bright_black());
+    std::io::Write::flush(&mut std::io::stdout()).ok();
+    let img_result = download_artist_image(
+        client, detail, artist_slug, store.dir, store.s3_client, store.config, pool, artist_id, fanart.as_ref(), store.wiki,
+    )
+    .await;
+    if img_result.is_some() {
+        println!("{}", "✓".green());
+    } else {
+        println!("{} (not found)", "✗".yellow());
+    }
+
+    // Fanart background + HD logo
+    if let Some(ref fa) = fanart {
+        let (bg, logo) =
+            download_artist_fanart(client, fa, artist_slug, store.dir, store.s3_client, store.config, pool, artist_id).await;
+        if bg || logo {
+            let mut parts = Vec::new();
+            if bg { parts.push("background"); }
+            if logo { parts.push("logo"); }
+            println!("    {} Saved fanart {}", "✓".green(), parts.join(" + "));
+        }
+    }
+    img_result.is_some()
+}
+
+/// --images-only: images for the artists that have none yet (or only the
+/// indexer's placeholder). The MB ID and the Wikipedia/Wikidata links stored
+/// by earlier syncs stand in for the MB artist lookup, so MusicBrainz isn't
+/// queried and its rate limit stays free for release syncing. Artists without
+/// a stored MB ID are left to a normal sync.
+async fn backfill_artist_images(pool: &PgPool, client: &Client, args: &Args, store: &ImageStore<'_>) {
+    let (mut conditions, binds) = artist_filter(args).sql_conditions("name", 1);
+    conditions.push(r#"((image IS NULL AND "imageUrl" IS NULL) OR "imagePlaceholder")"#.to_string());
+    if !args.include_archived {
+        conditions.push(r#""archivedAt" IS NULL"#.to_string());
+    }
+    let query = format!(
+        r#"SELECT id, name, slug, "musicbrainzId" FROM "Artist" WHERE {} ORDER BY slug"#,
+        conditions.join(" AND ")
+    );
+    let mut q = sqlx::query_as::<_, (String, String, String, Option<String>)>(&query);
+    for b in &binds {
+        q = q.bind(b);
+    }
+    let artists: Vec<_> = q.fetch_all(pool).await.expect("Failed to fetch artists")
+        .into_iter()
+        .filter(|(_, name, slug, _)| !is_various_artists(name, slug))
+        .collect();
+    let without_mb_id = artists.iter().filter(|(_, _, _, mb_id)| mb_id.is_none()).count();
+    let mut artists: Vec<_> = artists.into_iter()
+        .filter_map(|(id, name, slug, mb_id)| Some((id, name, slug, mb_id?)))
+        .collect();
+    if args.limit > 0 {
+        artists.truncate(args.limit);
+    }
+
+    println!("Artists without an image: {}", artists.len());
+    if without_mb_id > 0 {
+        println!("  {} {} more without a MusicBrainz ID (a normal sync looks them up first)", "↷".yellow(), without_mb_id);
+    }
+    println!();
+
+    let start = Instant::now();
+    let (mut found, mut missing) = (0u32, 0u32);
+    for (i, (artist_id, name, slug, mb_id)) in artists.iter().enumerate() {
+        println!("[{}/{}] {}", i + 1, artists.len(), name.white().bold());
+        // Earlier syncs stored the artist's MB URL relations; the image lookups only need these
+        let relations: Vec<MbRelation> = sqlx::query_as::<_, (String, String)>(
+            r#"SELECT type, url FROM "ArtistUrl" WHERE "artistId" = $1 AND type IN ('wikidata', 'wikipedia') AND status <> 'DEAD'"#,
+        )
+        .bind(artist_id)
+        .fetch_all(pool)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(relation_type, resource)| MbRelation { relation_type, url: Some(MbUrl { resource }) })
+        .collect();
+        let detail = MbArtistDetail { id: mb_id.clone(), name: name.clone(), relations: Some(relations), genres: None, tags: None };
+        if sync_artist_images(client, pool, store, &detail, artist_id, slug).await {
+            found += 1;
+        } else {
+            missing += 1;
+        }
+    }
+
+    println!();
+    println!("{}", "═".repeat(60).bright_black());
+    println!();
+    println!("{} {:.1}s", "Completed in:".white().bold(), start.elapsed().as_secs_f64());
+    println!("  {} {}", "Images found:".green(), found);
+    if missing > 0 {
+        println!("  {} {}", "Not found:".yellow(), missing);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Artist selection
+// ---------------------------------------------------------------------------
+
+/// The --only / --from / --to artist filter, applied to `Artist.name`.
+fn artist_filter(args: &Args) -> FolderFilter {
+    FolderFilter::from_options(args.only.as_deref(), args.from.as_deref(), args.to.as_deref())
+}
+
+/// "Various Artists" and the like: a compilation marker, not an artist to sync.
+fn is_various_artists(name: &str, slug: &str) -> bool {
+    let name_lower = name.to_lowercase();
+    name_lower == "various artists" || name_lower == "various" || slug == "various-artists" || slug == "various"
+}
+
+/// WHERE clause for artists that are due a sync (everything with --overwrite).
+fn needs_sync_condition(overwrite: bool) -> &'static str {
+    if overwrite {
+        "1=1"
+    } else {
+        r#""musicbrainzId" IS NULL
+               OR "lastSyncedAt" IS NULL
+               OR "lastSyncedAt" < NOW() - INTERVAL '30 days'"#
+    }
+}
+
+/// SQL for artists left with nothing in the local library: no local release
+/// and no track credit (featured, composer, ...). `a` is the artist.
+const NOT_IN_LIBRARY: &str = r#"NOT EXISTS (SELECT 1 FROM "LocalRelease" lr WHERE lr."artistId" = a.id)
+             AND NOT EXISTS (SELECT 1 FROM "TrackArtist" ta WHERE ta."artistId" = a.id)"#;
+
+/// Archive the artists matching the filter that are no longer in the local
+/// library (pruned by the indexer, nuked selectively), so they stop being
+/// synced, and un-archive those that are back. Returns (archived, restored).
+async fn reconcile_archived_artists(pool: &PgPool, filter: &FolderFilter) -> Result<(u64, u64), sqlx::Error> {
+    let (conditions, binds) = filter.sql_conditions("a.name", 1);
+    let filter_sql: String = conditions.iter().map(|c| format!(" AND {}", c)).collect();
+
+    let archive_query = format!(
+        r#"UPDATE "Artist" a SET "archivedAt" = NOW(), "updatedAt" = NOW()
+           WHERE a."archivedAt" IS NULL
+             AND {}{}"#,
+        NOT_IN_LIBRARY, filter_sql
+    );
+    let mut q = sqlx::query(&archive_query);
+    for b in &binds {
+        q = q.bind(b);
+    }
+    let archived = q.execute(pool).await?.rows_affected();
+
+    let restore_query = format!(
+        r#"UPDATE "Artist" a SET "archivedAt" = NULL, "updatedAt" = NOW()
+           WHERE a."archivedAt" IS NOT NULL
+             AND NOT ({}){}"#,
+        NOT_IN_LIBRARY, filter_sql
+    );
+    let mut q = sqlx::query(&restore_query);
+    for b in &binds {
+        q = q.bind(b);
+    }
+    let restored = q.execute(pool).await?.rows_affected();
+
+    Ok((archived, restored))
+}
+
+// ---------------------------------------------------------------------------
+// Nightly runs (--auto)
+// ---------------------------------------------------------------------------
+
+/// The artists an --auto run picked, and its `SyncRun` row.
+struct AutoRun {
+    id: String,
+    /// When the last completed --auto run picked its artists (None on the first one)
+    since: Option<chrono::NaiveDateTime>,
+    /// Artists picked because they are new or changed since `since`; the
+    /// rest are due their 30-day refresh
+    changed: usize,
+    selected: usize,
+}
+
+/// SQL for artists touched after `$param` (by the indexer or the web app):
+/// created or updated, or with a local release added or re-indexed since.
+/// Only what came after the artist's own last sync counts, so the writes of
+/// the run that synced it don't pick it again. NULL (no earlier run) matches
+/// nothing.
+fn changed_since_condition(param: usize) -> String {
+    format!(
+        r#"${0} IS NOT NULL AND (
+                 "createdAt" > GREATEST(${0}, "lastSyncedAt")
+                 OR "updatedAt" > GREATEST(${0}, "lastSyncedAt")
+                 OR EXISTS (SELECT 1 FROM "LocalRelease" lr
+                            WHERE lr."artistId" = "Artist".id AND lr."updatedAt" > GREATEST(${0}, "Artist"."lastSyncedAt")))"#,
+        param
+    )
+}
+
+/// SQL for the 30-day refresh cohort: never synced, or last synced more than 30 days ago.
+const REFRESH_DUE: &str = r#""lastSyncedAt" IS NULL OR "lastSyncedAt" < NOW() - INTERVAL '30 days'"#;
+
+/// Pick the artists of an --auto run (changed since the last completed one
+/// picked its own, plus the refresh cohort, by slug) and open its `SyncRun`
+/// row. `startedAt` is taken before the pick, so what changes while the run
+/// goes is the next run's. The row stays 'running' until `finish_auto_run`,
+/// so a run that dies doesn't move the next one's starting point.
+async fn begin_auto_run(
+    pool: &PgPool,
+    include_archived: bool,
+) -> Result<(AutoRun, Vec<(String, String, String, Option<String>)>), sqlx::Error> {
+    let since: Option<chrono::NaiveDateTime> = sqlx::query_scalar(
+        r#"SELECT MAX("startedAt") FROM "SyncRun" WHERE status = 'completed'"#,
+    )
+    .fetch_one(pool)
+    .await?;
+    let started_at: chrono::NaiveDateTime = sqlx::query_scalar("SELECT NOW()::timestamp(3)")
+        .fetch_one(pool)
+        .await?;
+
+    let changed = changed_since_condition(1);
+    let query = format!(
+        r#"SELECT id, name, slug, "musicbrainzId", COALESCE(({}), false)
+           FROM "Artist"
+           WHERE (({}) OR {}){}
+           ORDER BY slug"#,
+        changed,
+        changed,
+        REFRESH_DUE,
+        if include_archived { "" } else { r#" AND "archivedAt" IS NULL"# }
+    );
+    let rows: Vec<(String, String, String, Option<String>, bool)> = sqlx::query_as(&query)
+        .bind(since)
+        .fetch_all(pool)
+        .await?;
+    let rows: Vec<_> = rows.into_iter()
+        .filter(|(_, name, slug, _, _)| !is_various_artists(name, slug))
+        .collect();
+    let changed = rows.iter().filter(|row| row.4).count();
+
+    let id = cuid2::create_id();
+    sqlx::query(
+        r#"INSERT INTO "SyncRun" (id, status, since, "artistsChanged", "artistsRefreshed", "startedAt")
+           VALUES ($1, 'running', $2, $3, $4, $5)"#,
+    )
+    .bind(&id)
+    .bind(since)
+    .bind(changed as i32)
+    .bind((rows.len() - changed) as i32)
+    .bind(started_at)
+    .execute(pool)
+    .await?;
+
+    let run = AutoRun { id, since, changed, selected: rows.len() };
+    let artists = rows.into_iter().map(|(id, name, slug, mb_id, _)| (id, name, slug, mb_id)).collect();
+    Ok((run, artists))
+}
+
+/// Close the run's `SyncRun` row with its counts; its `startedAt` is where
+/// the next --auto run starts looking for changes.
+async fn finish_auto_run(pool: &PgPool, id: &str, synced: u32, partial: u32, failed: u32) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"UPDATE "SyncRun" SET status = 'completed', "artistsSynced" = $2, "artistsPartial" = $3,
+             "artistsFailed" = $4, "finishedAt" = NOW()
+           WHERE id = $1"#,
+    )
+    .bind(id)
+    .bind(synced as i32)
+    .bind(partial as i32)
+    .bind(failed as i32)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// MusicBrainz IDs from file tags
+// ---------------------------------------------------------------------------
+
+/// SQL for the first of the metadata JSON `keys` a track has, when it is a
+/// single MusicBrainz ID (NULL for anything else, like joined multi-values).
+/// lofty names the keys it knows (`MusicBrainzReleaseId`); the raw tag names
+/// cover files it doesn't map.
+fn tagged_mbid(keys: &[&str]) -> String {
+    let values: Vec<String> = keys.iter().map(|k| format!("t.metadata->>'{}'", k)).collect();
+    format!(
+        "substring(LOWER(COALESCE({})) from '^[0-9a-f]{{8}}-[0-9a-f]{{4}}-[0-9a-f]{{4}}-[0-9a-f]{{4}}-[0-9a-f]{{12}}$')",
+        values.join(", ")
+    )
+}
+
+/// Harvest the MusicBrainz IDs Picard and friends write into files, from the
+/// indexer's metadata JSON, for the artists matching the filter:
+///
+/// - `LocalRelease.musicbrainzReleaseGroupId` / `musicbrainzReleaseId` are set
+///   to the IDs all of the release's tagged tracks agree on (NULL otherwise),
+///   so retagging is picked up. Step 8 links by them before trying titles
+/// - `Artist.musicbrainzId`, when still unset, gets the release artist ID
+///   (track artist ID as fallback) all of its tagged tracks agree on, unless
+///   another artist has or would get the same ID. The name search is skipped
+///
+/// Returns (artists seeded, releases updated).
+async fn seed_tagged_mb_ids(pool: &PgPool, filter: &FolderFilter) -> Result<(u64, u64), sqlx::Error> {
+    let (conditions, binds) = filter.sql_conditions("a.name", 1);
+    let filter_sql: String = conditions.iter().map(|c| format!(" AND {}", c)).collect();
+
+    let release_query = format!(
+        r#"WITH tagged AS (
+             SELECT t."localReleaseId" AS id, {} AS rg, {} AS rel
+             FROM "LocalReleaseTrack" t
+             WHERE t."localReleaseId" IS NOT NULL AND t.metadata IS NOT NULL
+           ), agreed AS (
+             SELECT id,
+                    CASE WHEN COUNT(DISTINCT rg) = 1 THEN MIN(rg) END AS rg,
+                    CASE WHEN COUNT(DISTINCT rel) = 1 THEN MIN(rel) END AS rel
+             FROM tagged GROUP BY id
+           )
+           UPDATE "LocalRelease" lr SET
+             "musicbrainzReleaseGroupId" = g.rg, "musicbrainzReleaseId" = g.rel, "updatedAt" = NOW()
+           FROM agreed g, "Artist" a
+           WHERE lr.id = g.id AND a.id = lr."artistId"
+             AND (lr."musicbrainzReleaseGroupId" IS DISTINCT FROM g.rg OR lr."musicbrainzReleaseId" IS DISTINCT FROM g.rel){}"#,
+        tagged_mbid(&["MusicBrainzReleaseGroupId", "MUSICBRAINZ_RELEASEGROUPID"]),
+        tagged_mbid(&["MusicBrainzReleaseId", "MUSICBRAINZ_ALBUMID"]),
+        filter_sql
+    );
+    let mut q = sqlx::query(&release_query);
+    for b in &binds {
+        q = q.bind(b);
+    }
+    let releases = q.execute(pool).await?.rows_affected();
+
+    let artist_query = format!(
+        r#"WITH tagged AS (
+             SELECT lr."artistId" AS artist_id, {} AS mbid
+             FROM "LocalReleaseTrack" t
+             JOIN "LocalRelease" lr ON lr.id = t."localReleaseId"
+             WHERE t.metadata IS NOT NULL
+           ), agreed AS (
+             SELECT artist_id, MIN(mbid) AS mbid FROM tagged
+             WHERE mbid IS NOT NULL
+             GROUP BY artist_id HAVING COUNT(DISTINCT mbid) = 1
+           )
+           UPDATE "Artist" a SET "musicbrainzId" = g.mbid, "updatedAt" = NOW()
+           FROM agreed g
+           WHERE a.id = g.artist_id AND a."musicbrainzId" IS NULL
+             AND g.mbid IN (SELECT mbid FROM agreed GROUP BY mbid HAVING COUNT(*) = 1)
+             AND NOT EXISTS (SELECT 1 FROM "Artist" o WHERE o."musicbrainzId" = g.mbid){}"#,
+        tagged_mbid(&[
+            "MusicBrainzReleaseArtistId",
+            "MUSICBRAINZ_ALBUMARTISTID",
+            "MusicBrainzArtistId",
+            "MUSICBRAINZ_ARTISTID",
+        ]),
+        filter_sql
+    );
+    let mut q = sqlx::query(&artist_query);
+    for b in &binds {
+        q = q.bind(b);
+    }
+    let artists = q.execute(pool).await?.rows_affected();
+
+    Ok((artists, releases))
+}
+
+// ---------------------------------------------------------------------------
+// Sync workers (--worker)
+// ---------------------------------------------------------------------------
+
+/// `host:pid`, stored in `Artist.syncClaimedBy` while this worker syncs the artist.
+fn worker_id() -> String {
+    let host = std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .map(|h| h.trim().to_string())
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "unknown".to_string());
+    format!("{}:{}", host, std::process::id())
 }
 
-async fn clear_sync_checkpoint(pool: &PgPool) -> Result<(), sqlx::Error> {
-    sqlx::query(r#"DELETE FROM "SyncCheckpoint" WHERE id = 'main'"#)
+/// Claim an artist for this worker in one statement, so two workers can't both
+/// win. Fails when another worker's claim is younger than `timeout_min`, or the
+/// artist no longer needs a sync (another worker finished it since `started`).
+async fn claim_artist(
+    pool: &PgPool,
+    artist_id: &str,
+    worker: &str,
+    timeout_min: u32,
+    overwrite: bool,
+    started: chrono::NaiveDateTime,
+) -> Result<bool, sqlx::Error> {
+    let query = format!(
+        r#"UPDATE "Artist" SET "syncClaimedBy" = $1, "syncClaimedAt" = NOW()
+           WHERE id = $2
+             AND ("syncClaimedBy" IS NULL OR "syncClaimedBy" = $1
+                  OR "syncClaimedAt" < NOW() - make_interval(mins => $3))
+             AND ("lastSyncedAt" IS NULL OR "lastSyncedAt" < $4)
+             AND ({})"#,
+        needs_sync_condition(overwrite)
+    );
+    let result = sqlx::query(&query)
+        .bind(worker)
+        .bind(artist_id)
+        .bind(timeout_min as i32)
+        .bind(started)
         .execute(pool)
         .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+async fn release_claim(pool: &PgPool, artist_id: &str, worker: &str) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"UPDATE "Artist" SET "syncClaimedBy" = NULL, "syncClaimedAt" = NULL
+           WHERE id = $1 AND "syncClaimedBy" = $2"#,
+    )
+    .bind(artist_id)
+    .bind(worker)
+    .execute(pool)
+    .await?;
     Ok(())
 }
 
 // ---------------------------------------------------------------------------
-// Artist image download
+// Folder mode (--folder)
 // ---------------------------------------------------------------------------
 
-async fn download_artist_image(
-    client: &Client,
-    artist: &MbArtistDetail,
-    artist_slug: &str,
-    img_dir: &PathBuf,
-    s3_client: &Option<S3Client>,
-    config: &SyncConfig,
-    pool: &PgPool,
-    artist_id: &str,
-) -> Option<String> {
-    let out_path = img_dir.join(format!("{}.jpg", artist_slug));
-    let use_s3 = config.image_storage == "s3" || config.image_storage == "both";
-    let use_local = config.image_storage == "local" || config.image_storage == "both";
-
-    // Try to obtain a source image URL: Wikipedia first, then Fanart.tv
-    let img_url = {
-        let mut found = None;
-        if let Some(ref relations) = artist.relations {
-            for rel in relations {
-                if rel.relation_type == "wikipedia" || rel.relation_type == "wikidata" {
-                    if let Some(ref url) = rel.url {
-                        if let Some(u) = get_wikipedia_image(client, &url.resource).await {
-                            found = Some(u);
-                            break;
-                        }
-                    }
-                }
-            }
-        }
-        if found.is_none() {
-            found = get_fanart_image(client, &artist.id).await;
-        }
-        found
-    }?;
+/// Top-level folder names of `music_dir`, i.e. artist folders, honouring
+/// --only / --from / --to. Hidden folders, `__` staging folders and
+/// "Various Artists" are skipped.
+fn list_artist_folders(music_dir: &str, args: &Args) -> std::io::Result<Vec<String>> {
+    let filter = artist_filter(args);
+
+    let mut folders: Vec<String> = fs::read_dir(music_dir)?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().map(|t| t.is_dir()).unwrap_or(false))
+        .map(|e| e.file_name().to_string_lossy().trim().to_string())
+        .filter(|name| !name.is_empty() && !name.starts_with('.') && !name.starts_with("__"))
+        .filter(|name| {
+            let lower = name.to_lowercase();
+            !matches!(lower.as_str(), "various artists" | "various" | "va") && filter.matches(name)
+        })
+        .collect();
+    folders.sort_by_key(|f| f.to_lowercase());
+    Ok(folders)
+}
 
-    // Download and resize to local temp file
-    if !download_and_resize(client, &img_url, &out_path).await {
-        return None;
+/// Same as the indexer's `ensure_artist`: look up by slug, insert (as NFC)
+/// if missing. Returns the artist id and whether this call created it.
+async fn ensure_artist(pool: &PgPool, name: &str) -> Result<Option<(String, bool)>, sqlx::Error> {
+    let artist_slug = artist_slug(name);
+    if artist_slug.is_empty() {
+        return Ok(None);
     }
 
-    // Track whether at least one storage path wrote to the DB
-    let mut stored = false;
+    let existing: Option<(String,)> = sqlx::query_as(
+        r#"SELECT id FROM "Artist" WHERE slug = $1"#,
+    )
+    .bind(&artist_slug)
+    .fetch_optional(pool)
+    .await?;
 
-    if use_s3 {
-        if let (Some(ref s3), Some(ref bucket), Some(ref public_url)) =
-            (s3_client, &config.s3_bucket, &config.s3_public_url)
-        {
-            let s3_key = format!("artists/{}.jpg", artist_slug);
-            if upload_to_s3(s3, bucket, &s3_key, &out_path).await.is_ok() {
-                let image_url = format!("{}/{}", public_url.trim_end_matches('/'), s3_key);
-                sqlx::query(
-                    r#"UPDATE "Artist" SET "imageUrl" = $1, "updatedAt" = NOW() WHERE id = $2"#,
-                )
-                .bind(&image_url)
-                .bind(artist_id)
-                .execute(pool)
-                .await
-                .ok();
-                stored = true;
-            }
-        }
+    if let Some((id,)) = existing {
+        return Ok(Some((id, false)));
     }
 
-    if use_local {
-        let local_filename = format!("{}.jpg", artist_slug);
-        sqlx::query(r#"UPDATE "Artist" SET image = $1, "updatedAt" = NOW() WHERE id = $2"#)
-            .bind(&local_filename)
-            .bind(artist_id)
-            .execute(pool)
-            .await
-            .ok();
-        stored = true;
-    }
+    let id = cuid2::create_id();
+    let now = Utc::now().naive_utc();
+    let inserted = sqlx::query(
+        r#"INSERT INTO "Artist" (id, name, slug, "totalPlayCount", "totalTracks", "totalFileSize", "createdAt", "updatedAt")
+           VALUES ($1, $2, $3, 0, 0, 0, $4, $4)
+           ON CONFLICT (slug) DO NOTHING"#,
+    )
+    .bind(&id)
+    .bind(nfc(name))
+    .bind(&artist_slug)
+    .bind(now)
+    .execute(pool)
+    .await?
+    .rows_affected() > 0;
 
-    // Remove temp file when only using S3
-    if !use_local && use_s3 {
-        fs::remove_file(&out_path).ok();
-    }
+    // Return the actual ID (might be different if ON CONFLICT hit)
+    let row: (String,) = sqlx::query_as(
+        r#"SELECT id FROM "Artist" WHERE slug = $1"#,
+    )
+    .bind(&artist_slug)
+    .fetch_one(pool)
+    .await?;
 
-    if stored {
-        Some(format!("/img/artists/{}.jpg", artist_slug))
-    } else {
-        None
-    }
+    Ok(Some((row.0, inserted)))
 }
 
-async fn get_wikipedia_image(client: &Client, wiki_url: &str) -> Option<String> {
-    // Extract page title from URL
-    let title = wiki_url.rsplit('/').next()?;
+/// Artists for --folder: one per artist folder, created in the DB if the
+/// indexer hasn't seen them yet, then narrowed to those due a sync.
+async fn fetch_folder_artists(
+    pool: &PgPool,
+    music_dir: &str,
+    args: &Args,
+    error_log: &Mutex<fs::File>,
+) -> Vec<(String, String, String, Option<String>)> {
+    let folders = match list_artist_folders(music_dir, args) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("{} Cannot read folder {}: {}", "✗".red(), music_dir, e);
+            std::process::exit(1);
+        }
+    };
 
-    // Handle wikidata URLs - fetch from Wikidata API
-    if wiki_url.contains("wikidata.org") {
-        // Get Wikidata entity ID (e.g., Q175097 from https://www.wikidata.org/wiki/Q175097)
-        let wikidata_id = title;
-        let api_url = format!(
-            "https://www.wikidata.org/w/api.php?action=wbgetentities&ids={}&props=claims&format=json",
-            wikidata_id
-        );
-        
-        let resp = client
-            .get(&api_url)
-            .header("User-Agent", USER_AGENT)
-            .send()
-            .await
-            .ok()?;
-        
-        let body: JsonValue = resp.json().await.ok()?;
-        
-        // Get P18 (image) property from claims
-        if let Some(entities) = body.get("entities") {
-            if let Some(entity) = entities.get(wikidata_id) {
-                if let Some(claims) = entity.get("claims") {
-                    if let Some(images) = claims.get("P18") {
-                        if let Some(first_image) = images.get(0) {
-                            if let Some(mainsnak) = first_image.get("mainsnak") {
-                                if let Some(datavalue) = mainsnak.get("datavalue") {
-                                    if let Some(value) = datavalue.get("value") {
-                                        if let Some(filename) = value.as_str() {
-                                            // Convert Wikimedia filename to direct URL
-                                            let filename_encoded = urlencoding::encode(filename);
-                                            return Some(format!(
-                                                "https://commons.wikimedia.org/wiki/Special:FilePath/{}?width=500",
-                                                filename_encoded
-                                            ));
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
+    let mut slugs: Vec<String> = Vec::with_capacity(folders.len());
+    let mut created = 0;
+    for name in &folders {
+        match ensure_artist(pool, name).await {
+            Ok(Some((_, is_new))) => {
+                if is_new {
+                    created += 1;
+                }
+                slugs.push(artist_slug(name));
+            }
+            Ok(None) => {}
+            Err(e) => {
+                if let Ok(mut f) = error_log.lock() {
+                    writeln!(f, "[SYNC] DB error creating artist for folder '{}': {}", name, e).ok();
                 }
             }
         }
-        return None;
     }
-
-    let api_url = format!(
-        "https://en.wikipedia.org/w/api.php?action=query&titles={}&prop=pageimages&format=json&pithumbsize=500",
-        title
+    println!(
+        "{} {} artist folders in {} ({} new artists created)",
+        "→".bright_black(),
+        folders.len(),
+        music_dir,
+        created
     );
 
-    let resp = client
-        .get(&api_url)
-        .header("User-Agent", USER_AGENT)
-        .send()
+    let query = format!(
+        r#"SELECT id, name, slug, "musicbrainzId" FROM "Artist" WHERE ({}) AND slug = ANY($1) ORDER BY slug{}"#,
+        needs_sync_condition(args.overwrite),
+        if args.limit > 0 { format!(" LIMIT {}", args.limit) } else { String::new() }
+    );
+    sqlx::query_as(&query)
+        .bind(&slugs)
+        .fetch_all(pool)
         .await
-        .ok()?;
+        .expect("Failed to fetch artists")
+}
 
-    let body: JsonValue = resp.json().await.ok()?;
-    let pages = body.get("query")?.get("pages")?;
+// ---------------------------------------------------------------------------
+// URL health check (--check-urls)
+// ---------------------------------------------------------------------------
 
-    if let JsonValue::Object(map) = pages {
-        for (_, page) in map {
-            if let Some(thumb) = page.get("thumbnail") {
-                return thumb.get("source")?.as_str().map(|s| s.to_string());
-            }
+const URL_CHECK_CONCURRENCY: usize = 8;
+const URL_CHECK_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum UrlHealth {
+    Ok,
+    /// 404 / 410: the link is gone
+    Dead,
+    /// Timeout or connection failure: flagged DEAD, but may be a passing
+    /// network or DNS outage, so never deleted
+    Unreachable,
+    /// Blocked, rate limited or server error: says nothing about the link itself
+    Inconclusive,
+}
+
+async fn check_url(client: &Client, url: &str) -> (UrlHealth, String) {
+    // Some hosts reject HEAD but serve GET fine
+    let mut result = client.head(url).timeout(URL_CHECK_TIMEOUT).send().await;
+    if let Ok(ref resp) = result {
+        if matches!(resp.status().as_u16(), 403 | 405 | 501) {
+            result = client.get(url).timeout(URL_CHECK_TIMEOUT).send().await;
         }
     }
 
-    None
+    match result {
+        Ok(resp) => {
+            let status = resp.status();
+            match status.as_u16() {
+                404 | 410 => (UrlHealth::Dead, status.to_string()),
+                _ if status.is_success() => (UrlHealth::Ok, status.to_string()),
+                _ => (UrlHealth::Inconclusive, status.to_string()),
+            }
+        }
+        Err(e) if e.is_timeout() => (UrlHealth::Unreachable, "timeout".to_string()),
+        // Behind a filtering proxy without DMP_CA_BUNDLE every https link fails like this
+        Err(e) if e.is_connect() && s3::error_chain(&e).contains("certificate") => {
+            (UrlHealth::Inconclusive, s3::error_chain(&e))
+        }
+        Err(e) if e.is_connect() => (UrlHealth::Unreachable, "connection failed".to_string()),
+        Err(e) => (UrlHealth::Inconclusive, s3::error_chain(&e)),
+    }
 }
 
-async fn get_fanart_image(client: &Client, mb_id: &str) -> Option<String> {
-    // Fanart.tv API - no key needed for basic access
-    let url = format!(
-        "https://webservice.fanart.tv/v3/music/{}?api_key={}",
-        mb_id, "NO_KEY"
+/// HEAD every stored ArtistUrl (respecting --only/--from/--to) and record the
+/// result in "ArtistUrl".status. Links answering 404 / 410 are deleted with
+/// --remove-dead-urls; unreachable ones are only flagged.
+async fn check_artist_urls(pool: &PgPool, client: &Client, args: &Args, error_log: &Mutex<fs::File>) {
+    let (conditions, binds) = artist_filter(args).sql_conditions("a.name", 1);
+    let where_clause = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    };
+    let query = format!(
+        r#"SELECT u.id, u.type, u.url, a.name
+           FROM "ArtistUrl" u
+           JOIN "Artist" a ON a.id = u."artistId"
+           {}
+           ORDER BY a.slug, u.type"#,
+        where_clause
     );
-
-    let resp = client
-        .get(&url)
-        .header("User-Agent", USER_AGENT)
-        .send()
-        .await
-        .ok()?;
-
-    if resp.status() != 200 {
-        return None;
+    let mut q = sqlx::query_as::<_, (String, String, String, String)>(&query);
+    for b in &binds {
+        q = q.bind(b);
     }
+    let urls = q.fetch_all(pool).await.expect("Failed to fetch artist URLs");
 
-    let data: FanartArtistResponse = resp.json().await.ok()?;
+    println!("URLs to check: {}", urls.len());
+    println!();
 
-    // Prefer artistthumb > artistbackground > hdmusiclogo
-    if let Some(ref thumbs) = data.artistthumb {
-        if let Some(first) = thumbs.first() {
-            return Some(first.url.clone());
+    let (mut ok, mut dead, mut unreachable, mut inconclusive, mut failed) = (0u32, 0u32, 0u32, 0u32, 0u32);
+    for chunk in urls.chunks(URL_CHECK_CONCURRENCY) {
+        let mut set = tokio::task::JoinSet::new();
+        for (id, url_type, url, artist_name) in chunk.iter().cloned() {
+            let client = client.clone();
+            set.spawn(async move {
+                let (health, detail) = check_url(&client, &url).await;
+                (id, url_type, url, artist_name, health, detail)
+            });
         }
-    }
-    if let Some(ref bgs) = data.artistbackground {
-        if let Some(first) = bgs.first() {
-            return Some(first.url.clone());
+
+        while let Some(res) = set.join_next().await {
+            let (id, url_type, url, artist_name, health, detail) = match res {
+                Ok(checked) => checked,
+                Err(e) => {
+                    failed += 1;
+                    println!("  {} URL check failed: {}", "✗".red(), e);
+                    if let Ok(mut f) = error_log.lock() {
+                        writeln!(f, "[SYNC] URL check failed: {}", e).ok();
+                    }
+                    continue;
+                }
+            };
+            let now = Utc::now().naive_utc();
+            match health {
+                UrlHealth::Ok => {
+                    ok += 1;
+                    sqlx::query(r#"UPDATE "ArtistUrl" SET status = 'OK', "lastCheckedAt" = $1 WHERE id = $2"#)
+                        .bind(now)
+                        .bind(&id)
+                        .execute(pool)
+                        .await
+                        .ok();
+                }
+                UrlHealth::Dead | UrlHealth::Unreachable => {
+                    let remove = args.remove_dead_urls && health == UrlHealth::Dead;
+                    if health == UrlHealth::Dead { dead += 1 } else { unreachable += 1 }
+                    let action = if remove { "removed" } else { "flagged" };
+                    println!("  {} {} ({}) {} - {} [{}]",
+                        "✗".red(), artist_name, url_type, url.bright_black(), detail.yellow(), action);
+                    if let Ok(mut f) = error_log.lock() {
+                        writeln!(f, "[SYNC] Dead URL for artist '{}' ({}): {} - {}", artist_name, url_type, url, detail).ok();
+                    }
+                    if remove {
+                        sqlx::query(r#"DELETE FROM "ArtistUrl" WHERE id = $1"#)
+                            .bind(&id)
+                            .execute(pool)
+                            .await
+                            .ok();
+                    } else {
+                        sqlx::query(r#"UPDATE "ArtistUrl" SET status = 'DEAD', "lastCheckedAt" = $1 WHERE id = $2"#)
+                            .bind(now)
+                            .bind(&id)
+                            .execute(pool)
+                            .await
+                            .ok();
+                    }
+                }
+                UrlHealth::Inconclusive => {
+                    inconclusive += 1;
+                    println!("  {} {} ({}) {} - {}",
+                        "?".yellow(), artist_name, url_type, url.bright_black(), detail.bright_black());
+                    sqlx::query(r#"UPDATE "ArtistUrl" SET "lastCheckedAt" = $1 WHERE id = $2"#)
+                        .bind(now)
+                        .bind(&id)
+                        .execute(pool)
+                        .await
+                        .ok();
+                }
+            }
         }
     }
 
-    None
+    println!();
+    println!("{}", "═".repeat(60).bright_black());
+    println!("  {} {}", "OK:".green(), ok);
+    println!("  {} {}{}", "Dead:".red(), dead, if args.remove_dead_urls && dead > 0 { " (removed)" } else { "" });
+    println!("  {} {}{}", "Unreachable:".red(), unreachable, if unreachable > 0 { " (flagged, kept)" } else { "" });
+    println!("  {} {}", "Inconclusive:".yellow(), inconclusive);
+    if failed > 0 {
+        println!("  {} {}", "Check failed:".red(), failed);
+    }
 }
 
-async fn download_and_resize(client: &Client, url: &str, out_path: &PathBuf) -> bool {
-    let resp = match client
-        .get(url)
-        .header("User-Agent", USER_AGENT)
-        .send()
-        .await
-    {
-        Ok(r) => r,
-        Err(_) => return false,
-    };
+// ---------------------------------------------------------------------------
+// Discography gaps (--gaps-report)
+// ---------------------------------------------------------------------------
 
-    if resp.status() != 200 {
-        return false;
+/// One MISSING release: (artist name, title, year, type, MB release group id).
+type GapRow = (String, String, Option<i32>, String, Option<String>);
+
+/// Releases the status check marked MISSING, for the artists matching
+/// --only / --from / --to, by artist then year (undated last).
+async fn fetch_gaps(pool: &PgPool, args: &Args) -> Result<Vec<GapRow>, sqlx::Error> {
+    let (mut conditions, binds) = artist_filter(args).sql_conditions("a.name", 1);
+    conditions.insert(0, r#"r.status = 'MISSING'"#.to_string());
+    let query = format!(
+        r#"SELECT a.name, r.title, r.year, t.name, r."musicbrainzId"
+           FROM "MusicBrainzRelease" r
+           JOIN "Artist" a ON a.id = r."artistId"
+           JOIN "ReleaseType" t ON t.id = r."typeId"
+           WHERE {}
+           ORDER BY a.slug, a.id, r.year NULLS LAST, r.title"#,
+        conditions.join(" AND ")
+    );
+    let mut q = sqlx::query_as(&query);
+    for b in &binds {
+        q = q.bind(b);
     }
+    q.fetch_all(pool).await
+}
 
-    let bytes = match resp.bytes().await {
-        Ok(b) => b,
-        Err(_) => return false,
-    };
+/// Write `gaps_<timestamp>/` under `dir`: a report in the analysis style (one
+/// panel of releases per artist, with "Mark as handled" boxes) plus
+/// `gaps.json` for scripts. Returns the report directory.
+fn write_gaps_report(dir: &str, gaps: &[GapRow]) -> std::io::Result<PathBuf> {
+    use dmp_common::report::{Entry, Groups, NavItem, Panel, Report, StatCard};
 
-    match image::load_from_memory(&bytes) {
-        Ok(img) => {
-            let resized =
-                img.resize_to_fill(200, 200, image::imageops::FilterType::Lanczos3);
-            if let Some(parent) = out_path.parent() {
-                fs::create_dir_all(parent).ok();
-            }
-            resized.save(out_path).is_ok()
-        }
-        Err(_) => false,
+    let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+    let report_dir = PathBuf::from(dir).join(format!("gaps_{}", timestamp));
+
+    let mut groups = Groups::new();
+    for (artist, title, year, release_type, _) in gaps {
+        let year = year.map_or("no date".to_string(), |y| y.to_string());
+        groups.entry(artist.clone()).or_default()
+            .push(Entry::new(format!("{} — {}", artist, title)).annotated(Some(format!("({}, {})", year, release_type))));
     }
+    let artists = groups.len();
+
+    let nav = vec![NavItem::overview(), NavItem::page("missing", "Missing", "missing_1.html", gaps.len())];
+    let report = Report::create(&report_dir, "DMP Discography Gaps", nav)?;
+    let mut index = report.index_page("Overview", None)?;
+    index.write_stats(&[vec![
+        StatCard::new("Missing Releases", gaps.len(), "fail"),
+        StatCard::new("Artists", artists, "info"),
+    ]])?;
+    report.write_breakdown(&mut index, "Breakdown", "Releases")?;
+    index.finish()?;
+    report.write_grouped_pages("missing", "Missing", &[Panel::new("releases", "Releases", groups)])?;
+
+    let json: Vec<JsonValue> = gaps.iter()
+        .map(|(artist, title, year, release_type, mb_id)| serde_json::json!({
+            "artist": artist,
+            "title": title,
+            "year": year,
+            "type": release_type,
+            "musicbrainzId": mb_id,
+        }))
+        .collect();
+    let json = serde_json::to_string_pretty(&json).map_err(std::io::Error::other)?;
+    fs::write(report_dir.join("gaps.json"), json + "\n")?;
+    Ok(report_dir)
 }
 
 // ---------------------------------------------------------------------------
@@ -1290,6 +3258,10 @@ struct SyncConfig {
     s3_secret_key: Option<String>,
     s3_endpoint: Option<String>,
     s3_public_url: Option<String>,
+    /// MB_URL: MusicBrainz mirror to query instead of musicbrainz.org
+    mb_url: Option<String>,
+    /// DMP_CA_BUNDLE: extra root certificates for MusicBrainz, Wikipedia, Fanart.tv and S3
+    ca_bundle: Option<PathBuf>,
 }
 
 fn load_config() -> SyncConfig {
@@ -1344,6 +3316,8 @@ fn load_config() -> SyncConfig {
     let s3_secret_key = std::env::var("S3_SECRET_ACCESS_KEY").ok();
     let s3_endpoint = std::env::var("S3_ENDPOINT").ok().filter(|s| !s.is_empty());
     let s3_public_url = std::env::var("S3_PUBLIC_URL").ok();
+    let mb_url = std::env::var("MB_URL").ok().filter(|s| !s.trim().is_empty());
+    let ca_bundle = s3::ca_bundle_path();
 
     SyncConfig {
         database_url,
@@ -1355,6 +3329,8 @@ fn load_config() -> SyncConfig {
         s3_secret_key,
         s3_endpoint,
         s3_public_url,
+        mb_url,
+        ca_bundle,
     }
 }
 
@@ -1362,12 +3338,16 @@ fn load_config() -> SyncConfig {
 // S3 Upload
 // ---------------------------------------------------------------------------
 
-async fn create_s3_client(config: &SyncConfig) -> Option<S3Client> {
+async fn create_s3_client(config: &SyncConfig, ca_bundle: Option<&CaBundle>) -> Option<S3Client> {
     if config.s3_bucket.is_none() || config.s3_region.is_none() {
         return None;
     }
     
     let mut aws_config = aws_config::defaults(BehaviorVersion::latest());
+
+    if let Some(bundle) = ca_bundle {
+        aws_config = aws_config.http_client(bundle.http_client());
+    }
     
     if let Some(ref region) = config.s3_region {
         aws_config = aws_config.region(aws_sdk_s3::config::Region::new(region.clone()));
@@ -1400,6 +3380,7 @@ async fn upload_to_s3(
     bucket: &str,
     key: &str,
     file_path: &Path,
+    content_type: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let body = ByteStream::from_path(file_path).await?;
     
@@ -1408,19 +3389,158 @@ async fn upload_to_s3(
         .bucket(bucket)
         .key(key)
         .body(body)
-        .content_type("image/jpeg")
+        .content_type(content_type)
         .send()
         .await?;
     
     Ok(())
 }
 
+// ---------------------------------------------------------------------------
+// Artist merge
+// ---------------------------------------------------------------------------
+
+/// `dmp-sync merge`: fold duplicate artists (tag variants such as "The Beatles"
+/// / "Beatles, The") into the canonical one.
+#[derive(Parser, Debug)]
+#[command(name = "dmp-sync merge", about = "Merge duplicate artists into one")]
+struct MergeArgs {
+    /// Slug of the artist to keep
+    #[arg(long, value_name = "SLUG")]
+    into: String,
+
+    /// Slug of a duplicate to merge into it and delete (repeatable)
+    #[arg(long = "from", value_name = "SLUG", required = true)]
+    from: Vec<String>,
+
+    /// Show what would be merged, then roll everything back
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Merge even if the artists have different MusicBrainz IDs
+    #[arg(long)]
+    force: bool,
+
+    /// If another DMP script holds the run lock, wait for it to finish instead of exiting
+    #[arg(long)]
+    wait: bool,
+
+    /// Take the run lock from another DMP script (terminates its lock session)
+    #[arg(long)]
+    steal_lock: bool,
+}
+
+struct MergeArtist {
+    id: String,
+    name: String,
+    musicbrainz_id: Option<String>,
+}
+
+
+async fn find_artist_by_slug(conn: &mut PgConnection, slug: &str) -> Result<Option<MergeArtist>, sqlx::Error> {
+    let row = sqlx::query_as::<_, (String, String, Option<String>)>(
+        r#"SELECT id, name, "musicbrainzId" FROM "Artist" WHERE slug = $1"#,
+    )
+    .bind(slug)
+    .fetch_optional(&mut *conn)
+    .await?;
+    Ok(row.map(|(id, name, musicbrainz_id)| MergeArtist { id, name, musicbrainz_id }))
+}
+
+
+async fn run_merge(args: MergeArgs) {
+    println!("DMP Artist Merge");
+    println!("================");
+    println!("Into      : {}", args.into);
+    println!("From      : {}", args.from.join(", "));
+    if args.dry_run {
+        println!("Mode      : dry run (nothing is written)");
+    }
+    println!();
+
+    let config = load_config();
+    let _run_lock = match RunLock::acquire(&config.database_url, "sync", LockMode::from_flags(args.wait, args.steal_lock)).await {
+        Ok(lock) => lock,
+        Err(e) => {
+            println!("{} {}", "✗".red(), e);
+            std::process::exit(1);
+        }
+    };
+    let mut conn = PgConnection::connect(&config.database_url)
+        .await
+        .expect("Failed to connect to database. Is PostgreSQL running?");
+
+    let result: Result<Vec<(String, MergeCounts)>, String> = async {
+        let mut tx = conn.begin().await.map_err(|e| e.to_string())?;
+        let keep = find_artist_by_slug(&mut tx, &args.into)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("No artist with slug '{}'", args.into))?;
+        let mut merged = Vec::new();
+        for slug in &args.from {
+            let dup = find_artist_by_slug(&mut tx, slug)
+                .await
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("No artist with slug '{}'", slug))?;
+            if dup.id == keep.id {
+                return Err(format!("'{}' is the artist to merge into", slug));
+            }
+            if let (Some(a), Some(b)) = (&keep.musicbrainz_id, &dup.musicbrainz_id) {
+                if a != b && !args.force {
+                    return Err(format!(
+                        "'{}' ({}) and '{}' ({}) have different MusicBrainz IDs; pass --force to merge anyway",
+                        args.into, a, slug, b
+                    ));
+                }
+            }
+            let counts = merge_artist(&mut tx, &keep.id, &dup.id).await.map_err(|e| e.to_string())?;
+            merged.push((format!("{} ({})", dup.name, slug), counts));
+        }
+        if args.dry_run {
+            tx.rollback().await.map_err(|e| e.to_string())?;
+        } else {
+            tx.commit().await.map_err(|e| e.to_string())?;
+        }
+        Ok(merged)
+    }
+    .await;
+
+    let merged = match result {
+        Ok(merged) => merged,
+        Err(e) => {
+            println!("{} {}", "✗".red(), e);
+            std::process::exit(1);
+        }
+    };
+    for (dup, c) in &merged {
+        println!("{} {} → {}", "✓".green(), dup, args.into);
+        println!(
+            "  releases: {} moved, {} merged | MB releases: {} moved, {} merged",
+            c.releases_moved, c.releases_merged, c.mb_releases_moved, c.mb_releases_merged
+        );
+        println!(
+            "  credits: {} | URLs: {} | genres: {} | images queued for clean: {}",
+            c.credits_moved, c.urls_moved, c.genres_added, c.images_queued
+        );
+    }
+    println!();
+    if args.dry_run {
+        println!("{} Dry run: rolled back", "→".bright_black());
+    } else {
+        println!("The next `sync` revisits {} to match the merged releases; `clean` deletes the queued images.", args.into);
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Main
 // ---------------------------------------------------------------------------
 
 #[tokio::main]
 async fn main() {
+    if std::env::args().nth(1).as_deref() == Some("merge") {
+        run_merge(MergeArgs::parse_from(std::env::args().skip(1))).await;
+        return;
+    }
     let args = Args::parse();
 
     println!("DMP MusicBrainz Sync");
@@ -1431,6 +3551,55 @@ async fn main() {
     if args.resume {
         println!("Mode      : resume from checkpoint");
     }
+    if args.auto {
+        println!("Mode      : auto (changed since the last --auto run + 30-day refresh)");
+    }
+    if !args.prefer_country.is_empty() || !args.prefer_format.is_empty() {
+        println!(
+            "Editions  : country {} / format {}",
+            if args.prefer_country.is_empty() { "any".to_string() } else { args.prefer_country.join(",") },
+            if args.prefer_format.is_empty() { "any".to_string() } else { args.prefer_format.join(",") }
+        );
+    }
+    let wiki = WikiPrefs::from_args(&args);
+    if args.skip_images {
+        println!("Images    : skipped");
+    } else if wiki.languages != ["en"] || wiki.cache_days != 30 {
+        println!(
+            "Wikipedia : {} ({})",
+            if wiki.languages.is_empty() { "Wikidata only".to_string() } else { wiki.languages.join(",") },
+            if wiki.cache_days == 0 { "no cache".to_string() } else { format!("cached {} days", wiki.cache_days) }
+        );
+    }
+    if args.propagate_genres {
+        println!("Genres    : propagate to releases{}", if args.propagate_genres_to_tracks { " and untagged tracks" } else { "" });
+    }
+    if let Some(ref folder) = args.folder {
+        println!("Mode      : folder-first ({})", folder);
+    }
+    if args.retry_failed {
+        println!("Mode      : retry failed releases (SyncRetryQueue)");
+    }
+    let worker = args.worker.then(worker_id);
+    if let Some(ref w) = worker {
+        println!("Mode      : worker {} (claims expire after {} min)", w, args.claim_timeout);
+    }
+    if args.images_only {
+        println!("Mode      : images only (artists without an image, from stored MB IDs)");
+    }
+    if args.check_urls {
+        println!("Mode      : check artist URLs{}", if args.remove_dead_urls { " (remove dead)" } else { "" });
+    }
+    if args.no_tag_ids {
+        println!("Tag IDs   : ignored");
+    }
+    if let Some(score) = args.fuzzy_titles {
+        if !(score > 0.0 && score <= 1.0) {
+            eprintln!("--fuzzy-titles needs a score above 0 and at most 1 (got {})", score);
+            std::process::exit(1);
+        }
+        println!("Titles    : fuzzy fallback at {:.2}", score);
+    }
     println!();
 
     // Initialize error log
@@ -1443,7 +3612,28 @@ async fn main() {
     );
 
     let config = load_config();
-    println!("Image storage: {}", config.image_storage);
+    if !args.skip_images {
+        println!("Image storage: {}", config.image_storage);
+    }
+    if let Some(url) = args.mb_url.clone().or_else(|| config.mb_url.clone()) {
+        let url = mb::service_url(&url);
+        println!("MusicBrainz  : {}", url);
+        mb::set_base(url);
+    }
+    // reqwest and the S3 client pick the proxy up from the environment themselves
+    if let Some((var, url)) = s3::proxy_from_env() {
+        println!("Proxy        : {} ({})", url, var);
+    }
+    let ca_bundle = match config.ca_bundle.as_deref().map(CaBundle::read).transpose() {
+        Ok(bundle) => bundle,
+        Err(e) => {
+            println!("{} {}", "✗".red(), e);
+            std::process::exit(1);
+        }
+    };
+    if let Some(ref bundle) = ca_bundle {
+        println!("CA bundle    : {} ({} certificate(s))", bundle.path.display(), bundle.certificates);
+    }
     println!();
 
     let pool = PgPoolOptions::new()
@@ -1452,114 +3642,179 @@ async fn main() {
         .await
         .expect("Failed to connect to database. Is PostgreSQL running?");
 
-    let client = Client::builder()
-        .timeout(Duration::from_secs(30))
-        .build()
-        .expect("Failed to create HTTP client");
+    // Only one DMP script (index, sync, clean, nuke) writes at a time
+    let mode = LockMode::from_flags(args.wait, args.steal_lock);
+    // Workers share the lock with each other, still keeping index / clean / nuke out
+    let locked = if args.worker {
+        RunLock::acquire_shared(&config.database_url, "sync", mode).await
+    } else {
+        RunLock::acquire(&config.database_url, "sync", mode).await
+    };
+    let _run_lock = match locked {
+        Ok(lock) => lock,
+        Err(e) => {
+            println!("{} {}", "✗".red(), e);
+            std::process::exit(1);
+        }
+    };
+
+    let client = mb::http_client(ca_bundle.as_ref());
 
     // Initialize S3 client if needed
     let use_s3 = config.image_storage == "s3" || config.image_storage == "both";
-    let s3_client = if use_s3 {
-        create_s3_client(&config).await
+    let s3_client = if use_s3 && !args.skip_images {
+        create_s3_client(&config, ca_bundle.as_ref()).await
     } else {
         None
     };
 
+    if args.check_urls {
+        check_artist_urls(&pool, &client, &args, &error_log).await;
+        return;
+    }
+
+    // Safeguard: don't compute match statuses against a half-finished index (--images-only computes none)
+    if let Ok(Some((folder, files, updated_at))) = load_index_checkpoint(&pool).await.map(|c| c.filter(|_| !args.images_only)) {
+        println!("{} The indexer has a checkpoint: folder '{}', {} files, updated {}",
+            "⚠".yellow(), folder, files, checkpoint_age(updated_at));
+        if !args.force {
+            println!("  Syncing now would compute match statuses against half-indexed releases.");
+            println!("  Let the indexer finish (or `index --resume` an interrupted run), or pass --force.");
+            std::process::exit(1);
+        }
+        println!("  {} --force: syncing anyway, match statuses may be misleading", "→".bright_black());
+        println!();
+    }
+
+    if !args.no_tag_ids {
+        match seed_tagged_mb_ids(&pool, &artist_filter(&args)).await {
+            Ok((0, 0)) => {}
+            Ok((artists, releases)) => {
+                println!(
+                    "{} MusicBrainz IDs from file tags: {} artists seeded, {} release links updated",
+                    "✓".green(),
+                    artists,
+                    releases
+                );
+                println!();
+            }
+            Err(e) => {
+                println!("{} Could not read MusicBrainz IDs from file tags: {}", "⚠".yellow(), e);
+                println!();
+            }
+        }
+    }
+
+    if args.folder.is_none() {
+        match reconcile_archived_artists(&pool, &artist_filter(&args)).await {
+            Ok((0, 0)) => {}
+            Ok((archived, restored)) => {
+                println!(
+                    "{} Artists no longer in the library: {} archived, {} back and restored{}",
+                    "✓".green(),
+                    archived,
+                    restored,
+                    if args.include_archived { " (--include-archived: syncing them anyway)" } else { "" }
+                );
+                println!();
+            }
+            Err(e) => {
+                println!("{} Could not reconcile removed artists: {}", "⚠".yellow(), e);
+                println!();
+            }
+        }
+    }
+
     let mut limiter = RateLimiter::new();
     let start = Instant::now();
 
+    if args.retry_failed {
+        match retry_failed_releases(&pool, &client, &mut limiter, &args, &error_log).await {
+            Ok(summary) => {
+                update_statistics(&pool).await.ok();
+                record_statistics_history(&pool, "sync", 0).await.ok();
+                println!();
+                println!("{}", "═".repeat(60).bright_black());
+                println!();
+                println!("{} {:.1}s", "Completed in:".white().bold(), start.elapsed().as_secs_f64());
+                println!("  {} {}", "Recovered:".green(), summary.recovered);
+                if summary.still_failing > 0 {
+                    println!("  {} {}", "Still failing:".red(), summary.still_failing);
+                }
+                if summary.gone > 0 {
+                    println!("  {} {} (deleted or merged on MusicBrainz, dropped from the queue)", "Gone:".yellow(), summary.gone);
+                }
+                if summary.duration_mismatches > 0 {
+                    println!("  {} {} tracks off by more than {}s", "Durations:".yellow(), summary.duration_mismatches, args.duration_tolerance);
+                }
+                println!("  {} {}", "Queued:".white(), summary.queued);
+                if let Ok(left) = retry_queue_size(&pool).await {
+                    println!("  {} {}", "Left in queue:".white(), left);
+                }
+            }
+            Err(e) => {
+                println!("{} Retry failed: {}", "✗".red(), e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
     // Image directories
     let artist_img_dir = PathBuf::from(&config.project_root)
         .join("web/public/img/artists");
-    fs::create_dir_all(&artist_img_dir).ok();
+    if !args.skip_images {
+        fs::create_dir_all(&artist_img_dir).ok();
+    }
+    let images = ImageStore { dir: &artist_img_dir, s3_client: &s3_client, config: &config, wiki: &wiki };
 
-    // Build artist query with parameterized filters
-    let artists: Vec<(String, String, String, Option<String>)> = {
-        let base_condition = if args.overwrite {
-            "1=1".to_string()
-        } else {
-            r#""musicbrainzId" IS NULL
-               OR "lastSyncedAt" IS NULL
-               OR "lastSyncedAt" < NOW() - INTERVAL '30 days'"#.to_string()
-        };
+    if args.images_only {
+        backfill_artist_images(&pool, &client, &args, &images).await;
+        return;
+    }
 
-        if let Some(ref prefix) = args.only {
-            let pattern = format!("{}%", prefix.to_lowercase());
-            let query = format!(
-                r#"SELECT id, name, slug, "musicbrainzId" FROM "Artist" WHERE ({}) AND LOWER(slug) LIKE $1 ORDER BY slug{}"#,
-                base_condition,
-                if args.limit > 0 { format!(" LIMIT {}", args.limit) } else { String::new() }
-            );
-            sqlx::query_as(&query)
-                .bind(&pattern)
-                .fetch_all(&pool)
-                .await
-                .expect("Failed to fetch artists")
-        } else if args.from.is_some() || args.to.is_some() {
-            match (&args.from, &args.to) {
-                (Some(from), Some(to)) => {
-                    let query = format!(
-                        r#"SELECT id, name, slug, "musicbrainzId" FROM "Artist" WHERE ({}) AND LOWER(slug) >= $1 AND LOWER(slug) <= $2 ORDER BY slug{}"#,
-                        base_condition,
-                        if args.limit > 0 { format!(" LIMIT {}", args.limit) } else { String::new() }
-                    );
-                    sqlx::query_as(&query)
-                        .bind(&from.to_lowercase())
-                        .bind(&to.to_lowercase())
-                        .fetch_all(&pool)
-                        .await
-                        .expect("Failed to fetch artists")
-                }
-                (Some(from), None) => {
-                    let query = format!(
-                        r#"SELECT id, name, slug, "musicbrainzId" FROM "Artist" WHERE ({}) AND LOWER(slug) >= $1 ORDER BY slug{}"#,
-                        base_condition,
-                        if args.limit > 0 { format!(" LIMIT {}", args.limit) } else { String::new() }
-                    );
-                    sqlx::query_as(&query)
-                        .bind(&from.to_lowercase())
-                        .fetch_all(&pool)
-                        .await
-                        .expect("Failed to fetch artists")
-                }
-                (None, Some(to)) => {
-                    let query = format!(
-                        r#"SELECT id, name, slug, "musicbrainzId" FROM "Artist" WHERE ({}) AND LOWER(slug) <= $1 ORDER BY slug{}"#,
-                        base_condition,
-                        if args.limit > 0 { format!(" LIMIT {}", args.limit) } else { String::new() }
-                    );
-                    sqlx::query_as(&query)
-                        .bind(&to.to_lowercase())
-                        .fetch_all(&pool)
-                        .await
-                        .expect("Failed to fetch artists")
+    // Build artist query with parameterized filters
+    let mut auto_run: Option<AutoRun> = None;
+    let artists: Vec<(String, String, String, Option<String>)> = if let Some(ref music_dir) = args.folder {
+        fetch_folder_artists(&pool, music_dir.trim_end_matches('/'), &args, &error_log).await
+    } else if args.auto {
+        match begin_auto_run(&pool, args.include_archived).await {
+            Ok((run, artists)) => {
+                match run.since {
+                    Some(since) => println!("Changed since the last --auto run ({}): {}", since.format("%Y-%m-%d %H:%M"), run.changed),
+                    None => println!("First --auto run: only artists due their 30-day refresh"),
                 }
-                (None, None) => unreachable!(),
+                println!("Due their 30-day refresh: {}", run.selected - run.changed);
+                auto_run = Some(run);
+                artists
             }
-        } else {
-            let query = format!(
-                r#"SELECT id, name, slug, "musicbrainzId" FROM "Artist" WHERE ({}) ORDER BY slug{}"#,
-                base_condition,
-                if args.limit > 0 { format!(" LIMIT {}", args.limit) } else { String::new() }
-            );
-            sqlx::query_as(&query)
-                .fetch_all(&pool)
-                .await
-                .expect("Failed to fetch artists")
+            Err(e) => {
+                println!("{} Could not start the --auto run: {}", "✗".red(), e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        let (mut conditions, binds) = artist_filter(&args).sql_conditions("name", 1);
+        conditions.insert(0, format!("({})", needs_sync_condition(args.overwrite)));
+        if !args.include_archived {
+            conditions.push(r#""archivedAt" IS NULL"#.to_string());
+        }
+        let query = format!(
+            r#"SELECT id, name, slug, "musicbrainzId" FROM "Artist" WHERE {} ORDER BY slug{}"#,
+            conditions.join(" AND "),
+            if args.limit > 0 { format!(" LIMIT {}", args.limit) } else { String::new() }
+        );
+        let mut q = sqlx::query_as(&query);
+        for b in &binds {
+            q = q.bind(b);
         }
+        q.fetch_all(&pool).await.expect("Failed to fetch artists")
     };
 
     // Filter out "Various Artists" (compilation marker)
     let filtered_artists: Vec<_> = artists
         .into_iter()
-        .filter(|(_, name, slug, _)| {
-            // Skip "Various Artists" and similar compilation markers
-            let name_lower = name.to_lowercase();
-            !(name_lower == "various artists" 
-                || name_lower == "various" 
-                || slug == "various-artists"
-                || slug == "various")
-        })
+        .filter(|(_, name, slug, _)| !is_various_artists(name, slug))
         .collect();
 
     // --- Resume: load checkpoint and skip already-processed artists ---
@@ -1578,7 +3833,10 @@ async fn main() {
             }
         }
     } else {
-        clear_sync_checkpoint(&pool).await.ok();
+        // A worker never saves one, so a checkpoint is an interrupted normal sync's to resume
+        if worker.is_none() {
+            clear_sync_checkpoint(&pool).await.ok();
+        }
         None
     };
 
@@ -1605,18 +3863,49 @@ async fn main() {
     let mut failed = 0u32;
     let mut partial = 0u32; // Artists synced but with some release failures
     let mut skipped_compound = 0u32;
+    // Artists that got a YouTube / SoundCloud / Bandcamp link this run, by type
+    let mut channel_counts: HashMap<&'static str, u32> = HashMap::new();
+    // Local tracks flagged by --duration-tolerance across all artists
+    let mut total_duration_mismatches = 0u32;
     // Maps mb_id → primary artist DB id, so compound artists can link releases
     let mut synced_mb_ids: HashMap<String, String> = HashMap::new();
     let total = filtered_artists.len() as u32;
 
+    // --worker: artists another worker claimed first, and the one this worker holds
+    let mut claimed_elsewhere = 0u32;
+    let mut claimed: Option<String> = None;
+    let worker_started = Utc::now().naive_utc();
+
     // Track failed artists with reasons for final report
     let mut failed_artists: Vec<(String, String)> = Vec::new();
+    // (artist, title, id) of release groups deleted or merged on MusicBrainz mid-sync
+    let mut gone_release_groups: Vec<(String, String, String)> = Vec::new();
 
     // In-memory caches for genre and release type lookups
     let mut genre_cache: HashMap<String, String> = HashMap::new();
     let mut release_type_cache: HashMap<String, String> = HashMap::new();
 
     for (idx, (artist_id, artist_name, artist_slug, existing_mb_id)) in filtered_artists.iter().enumerate() {
+        if let Some(ref w) = worker {
+            if let Some(previous) = claimed.take() {
+                release_claim(&pool, &previous, w).await.ok();
+            }
+            match claim_artist(&pool, artist_id, w, args.claim_timeout, args.overwrite, worker_started).await {
+                Ok(true) => claimed = Some(artist_id.clone()),
+                Ok(false) => {
+                    claimed_elsewhere += 1;
+                    continue;
+                }
+                Err(e) => {
+                    if let Ok(mut f) = error_log.lock() {
+                        writeln!(f, "[SYNC] Could not claim artist '{}': {}", artist_name, e).ok();
+                    }
+                    claimed_elsewhere += 1;
+                    continue;
+                }
+            }
+        }
+
         let progress_num = idx + 1;
         println!("\n{} {} {}",
             format!("[{}/{}]", progress_num, total).bright_blue().bold(),
@@ -1720,6 +4009,10 @@ async fn main() {
             .await
             .unwrap_or_default();
 
+            let mut conn = match pool.acquire().await {
+                Ok(conn) => conn,
+                Err(_) => continue,
+            };
             let mut linked = 0u32;
             for (mb_release_id, mb_release_title) in &mb_releases {
                 let mb_tracks: Vec<(String, Option<i32>)> = sqlx::query_as(
@@ -1730,12 +4023,14 @@ async fn main() {
                 .await
                 .unwrap_or_default();
 
-                if let Ok((status, _, _, _)) = check_release_status(
-                    &pool,
+                if let Ok(ReleaseCheck { status, score, .. }) = check_release_status(
+                    &mut conn,
                     &artist_id,
                     mb_release_id,
                     mb_release_title,
                     &mb_tracks,
+                    args.duration_tolerance,
+                    args.fuzzy_titles,
                 )
                 .await
                 {
@@ -1744,11 +4039,13 @@ async fn main() {
                         sqlx::query(
                             r#"UPDATE "LocalRelease" SET
                                  "matchStatus" = $1::"ReleaseStatus",
+                                 "matchScore" = $3,
                                  "updatedAt" = NOW()
                                WHERE "releaseId" = $2"#,
                         )
                         .bind(status.as_str())
                         .bind(mb_release_id)
+                        .bind(score)
                         .execute(&pool)
                         .await
                         .ok();
@@ -1764,59 +4061,59 @@ async fn main() {
         }
 
         // 2. Get artist detail (URLs, genres, tags)
+        let mut top_genre: Option<String> = None;
         println!("  {} Fetching artist details...", "→".bright_black());
         match mb_get_artist_detail(&client, &mb_id, &mut limiter).await {
             Ok(detail) => {
                 let mut details_count = 0;
                 
-                // URLs
-                if let Some(ref rels) = detail.relations {
-                    for rel in rels {
-                        if let Some(ref url) = rel.url {
-                            upsert_artist_url(&pool, artist_id, &rel.relation_type, &url.resource)
-                                .await
-                                .ok();
-                            details_count += 1;
+                // URLs (channels are stored under their own type, whatever the MB relation), one insert
+                let mut artist_channels: HashSet<&'static str> = HashSet::new();
+                let mut urls: Vec<(String, String)> = Vec::new();
+                for rel in detail.relations.iter().flatten() {
+                    let Some(ref url) = rel.url else { continue };
+                    match channel_type(&url.resource) {
+                        Some(kind) => {
+                            artist_channels.insert(kind);
+                            urls.push((kind.to_string(), normalize_channel_url(&url.resource)));
                         }
+                        None => urls.push((rel.relation_type.clone(), url.resource.clone())),
                     }
+                    details_count += 1;
                 }
-
-                // Genres from MB (cached)
-                let mut genre_count = 0;
-                if let Some(ref genres) = detail.genres {
-                    for g in genres {
-                        if g.count.unwrap_or(0) > 0 {
-                            if let Ok(genre_id) = ensure_genre_cached(&pool, &g.name, &mut genre_cache).await {
-                                link_artist_genre(&pool, artist_id, &genre_id).await.ok();
-                                genre_count += 1;
-                            }
-                        }
+                if upsert_artist_urls(&pool, artist_id, &urls).await.is_ok() {
+                    for kind in artist_channels {
+                        *channel_counts.entry(kind).or_insert(0) += 1;
                     }
                 }
 
-                // Tags as genres (fallback, cached)
-                if let Some(ref tags) = detail.tags {
-                    for t in tags {
-                        if t.count.unwrap_or(0) > 0 {
-                            if let Ok(genre_id) = ensure_genre_cached(&pool, &t.name, &mut genre_cache).await {
-                                link_artist_genre(&pool, artist_id, &genre_id).await.ok();
-                                genre_count += 1;
-                            }
-                        }
-                    }
+                // Genres from MB, then tags as genres (fallback); created and linked in one go each
+                let genre_names: Vec<String> = detail.genres.iter().flatten()
+                    .filter(|g| g.count.unwrap_or(0) > 0)
+                    .map(|g| g.name.clone())
+                    .chain(detail.tags.iter().flatten().filter(|t| t.count.unwrap_or(0) > 0).map(|t| t.name.clone()))
+                    .collect();
+                let mut genre_count = 0;
+                if let Ok(genre_ids) = ensure_genres_cached(&pool, &genre_names, &mut genre_cache).await {
+                    genre_count = genre_ids.len();
+                    link_artist_genres(&pool, artist_id, &genre_ids).await.ok();
                 }
 
                 println!("    {} Saved {} URLs, {} genres", "✓".green(), details_count, genre_count);
 
-                // Artist image
-                print!("  {} Downloading artist image... ", "→".bright_black());
-                std::io::Write::flush(&mut std::io::stdout()).ok();
-                let img_result =
-                    download_artist_image(&client, &detail, artist_slug, &artist_img_dir, &s3_client, &config, &pool, artist_id).await;
-                if img_result.is_some() {
-                    println!("{}", "✓".green());
-                } else {
-                    println!("{} (not found)", "✗".yellow());
+                // Most-voted genre (tags as fallback), used by --propagate-genres-to-tracks
+                top_genre = detail.genres.iter().flatten()
+                    .filter(|g| g.count.unwrap_or(0) > 0)
+                    .max_by_key(|g| g.count.unwrap_or(0))
+                    .map(|g| g.name.clone())
+                    .or_else(|| detail.tags.iter().flatten()
+                        .filter(|t| t.count.unwrap_or(0) > 0)
+                        .max_by_key(|t| t.count.unwrap_or(0))
+                        .map(|t| t.name.clone()));
+
+                // --skip-images leaves the stored images as they are
+                if !args.skip_images {
+                    sync_artist_images(&client, &pool, &images, &detail, artist_id, artist_slug).await;
                 }
             }
             Err(e) => {
@@ -1842,13 +4139,28 @@ async fn main() {
             }
         };
 
+        let (release_groups, capped_releases) =
+            cap_release_groups(release_groups, args.max_releases_per_artist);
+        if capped_releases > 0 {
+            println!("    {} Capped at {} releases ({} lower-priority releases skipped)",
+                "↷".yellow(),
+                args.max_releases_per_artist,
+                capped_releases
+            );
+        }
+
         let mut release_scores: Vec<f64> = Vec::new();
         let mut release_failures = 0u32;
+        let mut gone_releases = 0u32;
+        let mut duration_mismatches = 0u32;
         let mut skipped_singles = 0u32;
         let mut processed_releases = 0u32;
         let total_to_process = release_groups.iter().filter(|rg| should_skip_release(rg).is_none()).count();
 
-        for rg in &release_groups {
+        // Phase 1: fetch every release's editions. Nothing is written yet, so an
+        // interrupted sync leaves the artist's previous discography untouched.
+        let mut staged_releases: Vec<StagedRelease> = Vec::new();
+        for (rg_idx, rg) in release_groups.iter().enumerate() {
             if let Some(skip_reason) = should_skip_release(rg) {
                 if args.verbose {
                     println!("    {} {} ({}) - Skipping ({})",
@@ -1886,36 +4198,36 @@ async fn main() {
                 .and_then(|d| d.split('-').next())
                 .and_then(|y| y.parse::<i32>().ok());
 
+            // Release types are shared lookup rows, so they are created outside the
+            // artist's transaction (a rollback would leave stale ids in the cache)
             let type_id = match ensure_release_type_cached(&pool, release_type, &mut release_type_cache).await {
                 Ok(id) => id,
                 Err(_) => continue,
             };
 
-            // Upsert MB release
-            let mb_release_id =
-                match upsert_mb_release(&pool, artist_id, &rg.title, &type_id, year, &rg.id).await
-                {
-                    Ok(id) => id,
-                    Err(e) => {
-                        eprintln!(
-                            "\n  ⚠ Release '{}' by '{}': DB error - {}",
-                            rg.title, artist_name, e
-                        );
-                        if let Ok(mut f) = error_log.lock() {
-                            writeln!(f, "[SYNC] DB error inserting release '{}' for artist '{}': {}", rg.title, artist_name, e).ok();
-                        }
-                        release_failures += 1;
-                        continue;
-                    }
-                };
-
             // Get tracks for this release group
-            let release_tracks =
+            let editions =
                 match mb_get_release_tracks(&client, &rg.id, &mut limiter).await {
                     Ok(rt) => {
                         if args.verbose { println!("{}", "✓".green()); }
                         rt
                     }
+                    Err(e) if mb::is_gone(&e) => {
+                        // Deleted or merged since the release group list was fetched:
+                        // nothing to retry, and not the artist's fault
+                        if args.verbose {
+                            println!("{}", "gone from MusicBrainz".yellow());
+                        }
+                        gone_releases += 1;
+                        gone_release_groups.push((artist_name.clone(), rg.title.clone(), rg.id.clone()));
+                        if let Ok(mut f) = error_log.lock() {
+                            writeln!(f, "[SYNC] Release group '{}' ({}) by '{}' no longer exists on MusicBrainz (deleted or merged), skipped", rg.title, rg.id, artist_name).ok();
+                        }
+                        if let Ok(mut conn) = pool.acquire().await {
+                            dequeue_retry(&mut conn, artist_id, &rg.id).await.ok();
+                        }
+                        continue;
+                    }
                     Err(e) => {
                         // mb_get already retried 10 times with exponential backoff
                         // If we still failed, log it and move on
@@ -1923,6 +4235,7 @@ async fn main() {
                             println!("{} {}", "✗".red(), e.yellow());
                         }
                         release_failures += 1;
+                        queue_retry(&pool, artist_id, rg, &e).await.ok();
 
                         if let Ok(mut f) = error_log.lock() {
                             writeln!(f, "[SYNC] Failed to fetch tracks for release '{}' by '{}': {}", rg.title, artist_name, e).ok();
@@ -1934,6 +4247,10 @@ async fn main() {
                                 println!("    {} Stopping sync for '{}' due to persistent rate limiting",
                                     "⚠".yellow(), artist_name.yellow());
                             }
+                            // The release groups not reached yet are queued too
+                            for rest in release_groups[rg_idx + 1..].iter().filter(|rg| should_skip_release(rg).is_none()) {
+                                queue_retry(&pool, artist_id, rest, "Not fetched: persistent rate limiting").await.ok();
+                            }
                             failed_artists.push((artist_name.clone(), "Persistent rate limiting".to_string()));
                             break;
                         }
@@ -1941,120 +4258,147 @@ async fn main() {
                     }
                 };
 
-            // Use the first (most canonical) release's tracks
-            if let Some((_, tracks)) = release_tracks.first() {
-                // Delete existing tracks for this MB release, then batch insert fresh
-                delete_mb_tracks_for_release(&pool, &mb_release_id).await.ok();
+            let genre_names: Vec<String> = rg.genres.iter().flatten()
+                .filter(|g| g.count.unwrap_or(0) > 0)
+                .map(|g| g.name.clone())
+                .collect();
+            let genre_ids = ensure_genres_cached(&pool, &genre_names, &mut genre_cache).await.unwrap_or_default();
+            staged_releases.push(StagedRelease { rg, type_id, year, editions, genre_ids });
+        }
+
+        // Clear the progress line in non-verbose mode
+        if !args.verbose && total_to_process > 0 {
+            eprint!("\r{}\r", " ".repeat(60));
+        }
+
+        // Phase 2: write the whole discography in one transaction, so the web app
+        // never sees half of an artist's releases, or releases without tracks
+        let mut tx = match pool.begin().await {
+            Ok(tx) => tx,
+            Err(e) => {
+                println!("  {} DB error: {}", "✗".red().bold(), e.to_string().bright_red());
+                failed_artists.push((artist_name.clone(), format!("DB error: {}", e)));
+                if let Ok(mut f) = error_log.lock() {
+                    writeln!(f, "[SYNC] DB error starting transaction for artist '{}': {}", artist_name, e).ok();
+                }
+                failed += 1;
+                continue;
+            }
+        };
+
+        for staged in &staged_releases {
+            // Each release gets a savepoint: a failed release is rolled back on its
+            // own and the rest of the discography is still committed
+            let stored = match Connection::begin(&mut *tx).await {
+                Ok(mut savepoint) => match store_release(&mut savepoint, artist_id, staged, &args).await {
+                    Ok(score) => savepoint.commit().await.map(|_| score),
+                    Err(e) => {
+                        savepoint.rollback().await.ok();
+                        Err(e)
+                    }
+                },
+                Err(e) => Err(e),
+            };
+
+            match stored {
+                Ok(Some((score, mismatches))) => {
+                    release_scores.push(score);
+                    duration_mismatches += mismatches;
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    eprintln!(
+                        "  ⚠ Release '{}' by '{}': DB error - {}",
+                        staged.rg.title, artist_name, e
+                    );
+                    if let Ok(mut f) = error_log.lock() {
+                        writeln!(f, "[SYNC] DB error storing release '{}' for artist '{}': {}", staged.rg.title, artist_name, e).ok();
+                    }
+                    release_failures += 1;
+                }
+            }
+        }
 
-                let disc_num = 1i32;
+        // Summary for this artist
+        println!("  {} Processed {} releases ({} skipped, {} failed{})",
+            "→".bright_black(),
+            processed_releases, 
+            skipped_singles,
+            release_failures,
+            if gone_releases > 0 { format!(", {} gone from MusicBrainz", gone_releases) } else { String::new() }
+        );
+        if duration_mismatches > 0 {
+            println!("  {} {} local tracks differ from MusicBrainz by more than {}s (possible wrong version)",
+                "⚠".yellow(), duration_mismatches, args.duration_tolerance);
+            total_duration_mismatches += duration_mismatches;
+        }
 
-                // Batch insert all tracks at once (single query instead of N individual inserts)
-                batch_insert_mb_tracks(&pool, &mb_release_id, tracks, disc_num).await.ok();
+        // If we got ANY scores OR just had skipped / vanished releases (no failures), mark as synced
+        let all_processed = !release_scores.is_empty() || (processed_releases == gone_releases && release_failures == 0);
 
-                let mb_track_pairs: Vec<(String, Option<i32>)> = tracks
-                    .iter()
-                    .map(|track| (track.title.clone(), track.position.map(|p| p as i32)))
-                    .collect();
+        let committed: Result<(), sqlx::Error> = async {
+            // Propagate artist genres down to releases (and optionally tracks)
+            if args.propagate_genres {
+                let releases_linked = propagate_release_genres(&mut tx, artist_id).await?;
+                let tracks_filled = match (&top_genre, args.propagate_genres_to_tracks) {
+                    (Some(genre), true) => propagate_track_genres(&mut tx, artist_id, genre).await?,
+                    _ => 0,
+                };
+                println!("  {} Propagated genres: {} release links, {} tracks",
+                    "→".bright_black(), releases_linked, tracks_filled);
+            }
 
-                // Status check
-                let (status, _missing, _extra, score) = match check_release_status(
-                    &pool,
-                    artist_id,
-                    &mb_release_id,
-                    &rg.title,
-                    &mb_track_pairs,
-                )
-                .await
-                {
-                    Ok(result) => result,
-                    Err(_) => (MatchStatus::Unknown, None, None, 0.0),
+            // Update artist - mark as synced even if all releases were skipped
+            let now = Utc::now().naive_utc();
+
+            if all_processed {
+                let avg_score = if release_scores.is_empty() {
+                    None // No releases processed = no score
+                } else {
+                    Some(release_scores.iter().sum::<f64>() / release_scores.len() as f64)
                 };
 
-                // Update MB release status (just the status, not the track arrays)
-                let now = Utc::now().naive_utc();
                 sqlx::query(
-                    r#"UPDATE "MusicBrainzRelease" SET
-                         status = $1::"ReleaseStatus",
+                    r#"UPDATE "Artist" SET
+                         "averageMatchScore" = $1,
+                         "lastSyncedAt" = $2,
                          "updatedAt" = $2
                        WHERE id = $3"#,
                 )
-                .bind(status.as_str())
+                .bind(avg_score)
                 .bind(now)
-                .bind(&mb_release_id)
-                .execute(&pool)
-                .await
-                .ok();
-
-                // Also update LocalRelease matchStatus if linked
+                .bind(artist_id)
+                .execute(&mut *tx)
+                .await?;
+            } else {
+                // Just update timestamp (will be retried in 30 days)
                 sqlx::query(
-                    r#"UPDATE "LocalRelease" SET
-                         "matchStatus" = $1::"ReleaseStatus",
-                         "updatedAt" = NOW()
-                       WHERE "releaseId" = $2"#,
+                    r#"UPDATE "Artist" SET
+                         "lastSyncedAt" = $1,
+                         "updatedAt" = $1
+                       WHERE id = $2"#,
                 )
-                .bind(status.as_str())
-                .bind(&mb_release_id)
-                .execute(&pool)
-                .await
-                .ok();
-
-                release_scores.push(score);
+                .bind(now)
+                .bind(artist_id)
+                .execute(&mut *tx)
+                .await?;
             }
-        }
 
-        // Clear the progress line in non-verbose mode
-        if !args.verbose && total_to_process > 0 {
-            eprint!("\r{}\r", " ".repeat(60));
+            tx.commit().await
         }
+        .await;
 
-        // Summary for this artist
-        println!("  {} Processed {} releases ({} skipped, {} failed)",
-            "→".bright_black(),
-            processed_releases, 
-            skipped_singles,
-            release_failures
-        );
+        if let Err(e) = committed {
+            println!("  {} DB error, releases rolled back: {}", "✗".red().bold(), e.to_string().bright_red());
+            failed_artists.push((artist_name.clone(), format!("DB error, releases rolled back: {}", e)));
+            if let Ok(mut f) = error_log.lock() {
+                writeln!(f, "[SYNC] DB error committing artist '{}', releases rolled back: {}", artist_name, e).ok();
+            }
+            failed += 1;
+            continue;
+        }
 
-        // Update artist - mark as synced even if all releases were skipped
-        let now = Utc::now().naive_utc();
-        
-        // If we got ANY scores OR just had skipped releases (no failures), mark as synced
-        let all_processed = release_scores.len() > 0 || (processed_releases == 0 && release_failures == 0);
-        
-        if all_processed {
-            let avg_score = if release_scores.is_empty() {
-                None // No releases processed = no score
-            } else {
-                Some(release_scores.iter().sum::<f64>() / release_scores.len() as f64)
-            };
-            
-            sqlx::query(
-                r#"UPDATE "Artist" SET
-                     "averageMatchScore" = $1,
-                     "lastSyncedAt" = $2,
-                     "updatedAt" = $2
-                   WHERE id = $3"#,
-            )
-            .bind(avg_score)
-            .bind(now)
-            .bind(artist_id)
-            .execute(&pool)
-            .await
-            .ok();
-        } else {
-            // Just update timestamp (will be retried in 30 days)
-            sqlx::query(
-                r#"UPDATE "Artist" SET
-                     "lastSyncedAt" = $1,
-                     "updatedAt" = $1
-                   WHERE id = $2"#,
-            )
-            .bind(now)
-            .bind(artist_id)
-            .execute(&pool)
-            .await
-            .ok();
-            
+        if !all_processed {
             failed += 1;
             failed_artists.push((artist_name.clone(), "Could not process any releases (errors occurred)".to_string()));
             if let Ok(mut f) = error_log.lock() {
@@ -2079,17 +4423,24 @@ async fn main() {
             println!("  {} Failed to sync", "✗".red().bold());
         }
 
-        // Save checkpoint every 10 artists
-        if (idx + 1) % 10 == 0 {
+        // Save checkpoint every 10 artists (the singleton checkpoint can't describe several workers)
+        if worker.is_none() && (idx + 1) % 10 == 0 {
             save_sync_checkpoint(&pool, artist_slug, (idx + 1) as i32).await.ok();
         }
     }
 
-    // Clear checkpoint on successful completion
-    clear_sync_checkpoint(&pool).await.ok();
+    if let (Some(w), Some(last)) = (&worker, claimed.take()) {
+        release_claim(&pool, &last, w).await.ok();
+    }
+
+    // Clear checkpoint on successful completion (workers leave it to the run that saved it)
+    if worker.is_none() {
+        clear_sync_checkpoint(&pool).await.ok();
+    }
 
-    // Update statistics
+    // Update statistics and append this run to the history
     update_statistics(&pool).await.ok();
+    record_statistics_history(&pool, "sync", (synced + partial) as i32).await.ok();
 
     let elapsed = start.elapsed();
     println!();
@@ -2106,7 +4457,35 @@ async fn main() {
     if failed > 0 {
         println!("  {} {}", "Failed:".red(), failed);
     }
+    if claimed_elsewhere > 0 {
+        println!("  {} {} (claimed by other workers or already synced by them)", "Elsewhere:".bright_black(), claimed_elsewhere);
+    }
     println!("  {} {}", "Total:".white(), total);
+    let channels_found: Vec<String> = CHANNEL_TYPES.iter()
+        .map(|(kind, label)| format!("{} {}", label, channel_counts.get(kind).copied().unwrap_or(0)))
+        .collect();
+    println!("  {} {} (artists this run)", "Channels:".white(), channels_found.join(", "));
+    if total_duration_mismatches > 0 {
+        println!("  {} {} tracks off by more than {}s", "Durations:".yellow(), total_duration_mismatches, args.duration_tolerance);
+    }
+    if let Ok((coverage, artists)) = channel_coverage(&pool).await {
+        let covered: Vec<String> = CHANNEL_TYPES.iter()
+            .map(|(kind, label)| {
+                let n = coverage.iter().find(|(t, _)| t == kind).map_or(0, |(_, n)| *n);
+                format!("{} {}", label, n)
+            })
+            .collect();
+        println!("  {} {} of {} MB artists", "Coverage:".white(), covered.join(", "), artists);
+    }
+    if let Some(ref dir) = args.gaps_report {
+        match fetch_gaps(&pool, &args).await {
+            Ok(gaps) => match write_gaps_report(dir, &gaps) {
+                Ok(report_dir) => println!("  {} {} missing releases (see {})", "Gaps:".white(), gaps.len(), report_dir.display()),
+                Err(e) => eprintln!("  {} Failed to write gaps report: {}", "✗".red(), e),
+            },
+            Err(e) => eprintln!("  {} Failed to load missing releases: {}", "✗".red(), e),
+        }
+    }
     
     // Show detailed failure list
     if !failed_artists.is_empty() {
@@ -2117,11 +4496,53 @@ async fn main() {
         }
     }
     
-    if partial > 0 || failed > 0 {
+    if !gone_release_groups.is_empty() {
+        println!();
+        println!("{}", "Gone from MusicBrainz (deleted or merged during the sync, skipped):".yellow().bold());
+        for (artist, title, id) in &gone_release_groups {
+            println!("  {} {} - {} {}", "↷".yellow(), artist.bright_white(), title, id.bright_black());
+        }
+    }
+
+    let queued = retry_queue_size(&pool).await.unwrap_or(0);
+    if queued > 0 {
+        println!();
+        println!("{} {} release groups are queued after failed fetches; run {} to fetch just those.",
+            "Tip:".yellow().bold(),
+            queued,
+            "./sync --retry-failed".bright_cyan()
+        );
+    } else if partial > 0 || failed > 0 {
         println!();
         println!("{} Run {} again to retry.", 
             "Tip:".yellow().bold(), 
             "./sync".bright_cyan()
         );
     }
+
+    // --auto: the last line is the run's summary as JSON, for the timer's logs and alerting
+    if let Some(run) = auto_run {
+        if let Err(e) = finish_auto_run(&pool, &run.id, synced, partial, failed).await {
+            println!("{} Could not record the --auto run: {}", "✗".red(), e);
+            std::process::exit(1);
+        }
+        let summary = serde_json::json!({
+            "runId": run.id,
+            "since": run.since.map(|t| t.format("%Y-%m-%dT%H:%M:%S").to_string()),
+            "selected": run.selected,
+            "changed": run.changed,
+            "refresh": run.selected - run.changed,
+            "synced": synced,
+            "partial": partial,
+            "failed": failed,
+            "skippedCompound": skipped_compound,
+            "retryQueue": queued,
+            "elapsedSeconds": (elapsed.as_secs_f64() * 10.0).round() / 10.0,
+            "failedArtists": failed_artists.iter()
+                .map(|(name, reason)| serde_json::json!({ "artist": name, "reason": reason }))
+                .collect::<Vec<_>>(),
+        });
+        println!();
+        println!("{}", summary);
+    }
 }
@@ -0,0 +1,4 @@
+-- Migration: Archive artists that are no longer in the local library
+-- Set by sync for artists with no local release and no track credit left; archived artists aren't synced
+
+ALTER TABLE "Artist" ADD COLUMN IF NOT EXISTS "archivedAt" TIMESTAMP(3);
@@ -0,0 +1,30 @@
+-- Migration: Add fanart.tv background and HD logo columns to Artist
+-- Local values are paths relative to img/artists/ (e.g. backgrounds/radiohead.jpg)
+
+ALTER TABLE "Artist" ADD COLUMN IF NOT EXISTS "backgroundImage" TEXT;
+ALTER TABLE "Artist" ADD COLUMN IF NOT EXISTS "backgroundImageUrl" TEXT;
+ALTER TABLE "Artist" ADD COLUMN IF NOT EXISTS "logoImage" TEXT;
+ALTER TABLE "Artist" ADD COLUMN IF NOT EXISTS "logoImageUrl" TEXT;
+
+-- Queue the fanart objects alongside the thumb when an artist is deleted
+CREATE OR REPLACE FUNCTION queue_artist_image_deletion()
+RETURNS TRIGGER AS $$
+BEGIN
+  IF OLD."imageUrl" IS NOT NULL AND OLD."imageUrl" != '' THEN
+    INSERT INTO "S3DeletionQueue" (id, "objectKey")
+    VALUES (gen_random_uuid()::text, 'artists/' || OLD.slug || '.jpg')
+    ON CONFLICT DO NOTHING;
+  END IF;
+  IF OLD."backgroundImageUrl" IS NOT NULL AND OLD."backgroundImageUrl" != '' THEN
+    INSERT INTO "S3DeletionQueue" (id, "objectKey")
+    VALUES (gen_random_uuid()::text, 'artists/backgrounds/' || OLD.slug || '.jpg')
+    ON CONFLICT DO NOTHING;
+  END IF;
+  IF OLD."logoImageUrl" IS NOT NULL AND OLD."logoImageUrl" != '' THEN
+    INSERT INTO "S3DeletionQueue" (id, "objectKey")
+    VALUES (gen_random_uuid()::text, 'artists/logos/' || OLD.slug || '.png')
+    ON CONFLICT DO NOTHING;
+  END IF;
+  RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,4 @@
+-- Migration: Flag artist images that are generated placeholders
+-- Set by `index --placeholders`, cleared by `sync` when it stores a real image
+
+ALTER TABLE "Artist" ADD COLUMN IF NOT EXISTS "imagePlaceholder" BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,5 @@
+-- Migration: Artist sync claims (for concurrent `sync --worker` runs)
+-- The worker syncing an artist writes its host:pid and the time it claimed it
+
+ALTER TABLE "Artist" ADD COLUMN IF NOT EXISTS "syncClaimedBy" TEXT;
+ALTER TABLE "Artist" ADD COLUMN IF NOT EXISTS "syncClaimedAt" TIMESTAMP(3);
@@ -0,0 +1,11 @@
+-- Migration: Track ArtistUrl health (set by `sync --check-urls`)
+-- DEAD = 404/410, timeout or connection failure on the last check
+
+DO $$ BEGIN
+  CREATE TYPE "ArtistUrlStatus" AS ENUM ('UNCHECKED', 'OK', 'DEAD');
+EXCEPTION
+  WHEN duplicate_object THEN NULL;
+END $$;
+
+ALTER TABLE "ArtistUrl" ADD COLUMN IF NOT EXISTS status "ArtistUrlStatus" NOT NULL DEFAULT 'UNCHECKED';
+ALTER TABLE "ArtistUrl" ADD COLUMN IF NOT EXISTS "lastCheckedAt" TIMESTAMP(3);
@@ -0,0 +1,5 @@
+-- Migration: cache of the Wikidata/Wikipedia artist image lookup (written by sync)
+-- wikiCheckedAt without wikiImageUrl means the last lookup found no image
+
+ALTER TABLE "Artist" ADD COLUMN IF NOT EXISTS "wikiImageUrl" TEXT;
+ALTER TABLE "Artist" ADD COLUMN IF NOT EXISTS "wikiCheckedAt" TIMESTAMP(3);
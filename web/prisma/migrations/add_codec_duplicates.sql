@@ -0,0 +1,13 @@
+-- Migration: CodecDuplicate table (filled by `index --find-codec-dupes`)
+-- One row per track that also exists in another codec; dropTrack is the suggested deletion
+
+CREATE TABLE IF NOT EXISTS "CodecDuplicate" (
+  id TEXT PRIMARY KEY,
+  "keepTrackId" TEXT NOT NULL REFERENCES "LocalReleaseTrack"(id) ON DELETE CASCADE ON UPDATE CASCADE,
+  "dropTrackId" TEXT NOT NULL REFERENCES "LocalReleaseTrack"(id) ON DELETE CASCADE ON UPDATE CASCADE,
+  "durationDelta" INTEGER NOT NULL,
+  "createdAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS "CodecDuplicate_keepTrackId_dropTrackId_key" ON "CodecDuplicate"("keepTrackId", "dropTrackId");
+CREATE INDEX IF NOT EXISTS "CodecDuplicate_dropTrackId_idx" ON "CodecDuplicate"("dropTrackId");
@@ -0,0 +1,11 @@
+-- Migration: LibraryRoot table (written by every index run)
+-- The music dir's artist folder count and a sample of their names, to catch a wrong drive mounted in its place
+
+CREATE TABLE IF NOT EXISTS "LibraryRoot" (
+  id TEXT PRIMARY KEY DEFAULT 'main',
+  "musicDir" TEXT NOT NULL,
+  "folderCount" INTEGER NOT NULL DEFAULT 0,
+  "sampleFolders" TEXT[] NOT NULL DEFAULT '{}',
+  "createdAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  "updatedAt" TIMESTAMP(3) NOT NULL
+);
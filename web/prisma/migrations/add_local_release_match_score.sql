@@ -0,0 +1,4 @@
+-- Migration: Add the numeric match score to LocalRelease
+-- Share of the linked MusicBrainz release's tracks found locally (0.0–1.0), written by sync next to matchStatus
+
+ALTER TABLE "LocalRelease" ADD COLUMN IF NOT EXISTS "matchScore" DOUBLE PRECISION;
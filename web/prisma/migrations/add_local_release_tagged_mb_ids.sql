@@ -0,0 +1,6 @@
+-- Migration: Add the MusicBrainz IDs from file tags to LocalRelease
+-- Harvested by sync from the indexer's metadata JSON (MUSICBRAINZ_RELEASEGROUPID / MUSICBRAINZ_ALBUMID) to link releases without matching titles
+
+ALTER TABLE "LocalRelease" ADD COLUMN IF NOT EXISTS "musicbrainzReleaseGroupId" TEXT;
+ALTER TABLE "LocalRelease" ADD COLUMN IF NOT EXISTS "musicbrainzReleaseId" TEXT;
+CREATE INDEX IF NOT EXISTS "LocalRelease_musicbrainzReleaseGroupId_idx" ON "LocalRelease"("musicbrainzReleaseGroupId");
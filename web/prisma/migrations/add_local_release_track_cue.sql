@@ -0,0 +1,5 @@
+-- Migration: Add CUE sheet offsets to LocalReleaseTrack
+-- Set by index for tracks split out of a single-file album rip (filePath "<rip>#NN"), in milliseconds; cueEnd is NULL for the last track
+
+ALTER TABLE "LocalReleaseTrack" ADD COLUMN IF NOT EXISTS "cueStart" INTEGER;
+ALTER TABLE "LocalReleaseTrack" ADD COLUMN IF NOT EXISTS "cueEnd" INTEGER;
@@ -0,0 +1,4 @@
+-- Migration: Flag LocalRelease years copied from the linked MusicBrainz release
+-- Set by `index --backfill-years` when no track has a year tag
+
+ALTER TABLE "LocalRelease" ADD COLUMN IF NOT EXISTS "yearBackfilled" BOOLEAN NOT NULL DEFAULT false;
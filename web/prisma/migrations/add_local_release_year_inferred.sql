@@ -0,0 +1,4 @@
+-- Migration: Flag LocalRelease years approximated from file mtime
+-- Set by `index --infer-year-from-mtime` when no track has a year tag
+
+ALTER TABLE "LocalRelease" ADD COLUMN IF NOT EXISTS "yearInferred" BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,5 @@
+-- Migration: per-track length check against MusicBrainz (filled by sync)
+-- durationDelta is local minus MB length in seconds; durationMismatch marks |delta| > --duration-tolerance
+
+ALTER TABLE "LocalReleaseTrack" ADD COLUMN IF NOT EXISTS "durationDelta" INTEGER;
+ALTER TABLE "LocalReleaseTrack" ADD COLUMN IF NOT EXISTS "durationMismatch" BOOLEAN NOT NULL DEFAULT false;
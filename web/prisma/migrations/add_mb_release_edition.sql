@@ -0,0 +1,7 @@
+-- Migration: Record which MusicBrainz edition (release) a MusicBrainzRelease's
+-- track list came from, so re-syncs keep the same edition
+-- (chosen by `sync --prefer-country` / `--prefer-format`)
+
+ALTER TABLE "MusicBrainzRelease" ADD COLUMN IF NOT EXISTS "editionId" TEXT;
+ALTER TABLE "MusicBrainzRelease" ADD COLUMN IF NOT EXISTS "editionCountry" TEXT;
+ALTER TABLE "MusicBrainzRelease" ADD COLUMN IF NOT EXISTS "editionFormat" TEXT;
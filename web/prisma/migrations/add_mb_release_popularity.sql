@@ -0,0 +1,9 @@
+-- Migration: Store each MusicBrainz release group's rating and tag votes, and
+-- the popularity score sync derives from them (default sort of discographies)
+
+ALTER TABLE "MusicBrainzRelease" ADD COLUMN IF NOT EXISTS "rating" DOUBLE PRECISION;
+ALTER TABLE "MusicBrainzRelease" ADD COLUMN IF NOT EXISTS "ratingVotes" INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE "MusicBrainzRelease" ADD COLUMN IF NOT EXISTS "tagCount" INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE "MusicBrainzRelease" ADD COLUMN IF NOT EXISTS "popularity" DOUBLE PRECISION NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS "MusicBrainzRelease_artistId_popularity_idx" ON "MusicBrainzRelease"("artistId", "popularity");
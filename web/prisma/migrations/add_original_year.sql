@@ -0,0 +1,5 @@
+-- Migration: Original release year next to the edition year
+-- Set by index from ORIGINALYEAR / ORIGINALDATE / TDOR; LocalRelease gets the earliest of its tracks'. Sorting prefers it over year
+
+ALTER TABLE "LocalReleaseTrack" ADD COLUMN IF NOT EXISTS "originalYear" INTEGER;
+ALTER TABLE "LocalRelease" ADD COLUMN IF NOT EXISTS "originalYear" INTEGER;
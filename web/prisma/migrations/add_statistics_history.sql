@@ -0,0 +1,24 @@
+-- Migration: StatisticsHistory table (one row appended per index / sync run)
+-- Snapshots the Statistics counters plus coverage percentages for dashboard charts
+
+CREATE TABLE IF NOT EXISTS "StatisticsHistory" (
+  id TEXT PRIMARY KEY,
+  source VARCHAR(10) NOT NULL,
+  "artistsSynced" INTEGER NOT NULL DEFAULT 0,
+  artists INTEGER NOT NULL DEFAULT 0,
+  tracks INTEGER NOT NULL DEFAULT 0,
+  releases INTEGER NOT NULL DEFAULT 0,
+  genres INTEGER NOT NULL DEFAULT 0,
+  playtime BIGINT NOT NULL DEFAULT 0,
+  "artistsSyncedWithMusicbrainz" INTEGER NOT NULL DEFAULT 0,
+  "releasesSyncedWithMusicbrainz" INTEGER NOT NULL DEFAULT 0,
+  "artistsWithCoverArt" INTEGER NOT NULL DEFAULT 0,
+  "releasesWithCoverArt" INTEGER NOT NULL DEFAULT 0,
+  "artistMusicbrainzCoverage" DOUBLE PRECISION,
+  "releaseMusicbrainzCoverage" DOUBLE PRECISION,
+  "artistCoverArtCoverage" DOUBLE PRECISION,
+  "releaseCoverArtCoverage" DOUBLE PRECISION,
+  "createdAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS "StatisticsHistory_createdAt_idx" ON "StatisticsHistory"("createdAt");
@@ -0,0 +1,17 @@
+-- Migration: SyncRetryQueue table (filled by sync, drained by `sync --retry-failed`)
+-- One row per (artist, release group) whose editions could not be fetched from MusicBrainz
+
+CREATE TABLE IF NOT EXISTS "SyncRetryQueue" (
+  id TEXT PRIMARY KEY,
+  "artistId" TEXT NOT NULL REFERENCES "Artist"(id) ON DELETE CASCADE ON UPDATE CASCADE,
+  "releaseGroupId" TEXT NOT NULL,
+  title TEXT NOT NULL,
+  "primaryType" TEXT,
+  "firstReleaseDate" TEXT,
+  attempts INTEGER NOT NULL DEFAULT 1,
+  "lastError" TEXT,
+  "createdAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  "updatedAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS "SyncRetryQueue_artistId_releaseGroupId_key" ON "SyncRetryQueue"("artistId", "releaseGroupId");
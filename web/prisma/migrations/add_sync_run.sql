@@ -0,0 +1,17 @@
+-- Migration: SyncRun table (written by `sync --auto`)
+-- One row per nightly run; the last completed one's startedAt (taken before it picked its artists) is where the next run looks for changed artists
+
+CREATE TABLE IF NOT EXISTS "SyncRun" (
+  id TEXT PRIMARY KEY,
+  status TEXT NOT NULL DEFAULT 'running',
+  since TIMESTAMP(3),
+  "artistsChanged" INTEGER NOT NULL DEFAULT 0,
+  "artistsRefreshed" INTEGER NOT NULL DEFAULT 0,
+  "artistsSynced" INTEGER NOT NULL DEFAULT 0,
+  "artistsPartial" INTEGER NOT NULL DEFAULT 0,
+  "artistsFailed" INTEGER NOT NULL DEFAULT 0,
+  "startedAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  "finishedAt" TIMESTAMP(3)
+);
+
+CREATE INDEX IF NOT EXISTS "SyncRun_finishedAt_idx" ON "SyncRun"("finishedAt");
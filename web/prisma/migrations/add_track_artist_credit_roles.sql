@@ -0,0 +1,6 @@
+-- Migration: TrackArtist roles for credit tags (set by the indexer)
+-- COMPOSER / REMIXER / CONDUCTOR come from the COMPOSER, REMIXER (MIXARTIST) and CONDUCTOR tags
+
+ALTER TYPE "TrackArtistRole" ADD VALUE IF NOT EXISTS 'COMPOSER';
+ALTER TYPE "TrackArtistRole" ADD VALUE IF NOT EXISTS 'REMIXER';
+ALTER TYPE "TrackArtistRole" ADD VALUE IF NOT EXISTS 'CONDUCTOR';
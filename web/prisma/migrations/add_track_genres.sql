@@ -0,0 +1,14 @@
+-- Migration: TrackGenre table (written by every index run)
+-- Every genre of a track's GENRE tag, split on --genre-separators; position 0 is the one in "LocalReleaseTrack".genre
+
+CREATE TABLE IF NOT EXISTS "TrackGenre" (
+  id TEXT PRIMARY KEY,
+  "trackId" TEXT NOT NULL REFERENCES "LocalReleaseTrack"(id) ON DELETE CASCADE ON UPDATE CASCADE,
+  "genreId" TEXT NOT NULL REFERENCES "Genre"(id) ON DELETE CASCADE ON UPDATE CASCADE,
+  position INTEGER NOT NULL DEFAULT 0,
+  "createdAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS "TrackGenre_trackId_genreId_key" ON "TrackGenre"("trackId", "genreId");
+CREATE INDEX IF NOT EXISTS "TrackGenre_trackId_idx" ON "TrackGenre"("trackId");
+CREATE INDEX IF NOT EXISTS "TrackGenre_genreId_idx" ON "TrackGenre"("genreId");
@@ -0,0 +1,14 @@
+-- Migration: TrackLyrics table (filled by `index --extract-lyrics`)
+-- One row per track and language; "und" when the tag doesn't say (Vorbis LYRICS, MP4 ©lyr)
+
+CREATE TABLE IF NOT EXISTS "TrackLyrics" (
+  id TEXT PRIMARY KEY,
+  "trackId" TEXT NOT NULL REFERENCES "LocalReleaseTrack"(id) ON DELETE CASCADE ON UPDATE CASCADE,
+  language VARCHAR(3) NOT NULL DEFAULT 'und',
+  lyrics TEXT NOT NULL,
+  "createdAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  "updatedAt" TIMESTAMP(3) NOT NULL
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS "TrackLyrics_trackId_language_key" ON "TrackLyrics"("trackId", language);
+CREATE INDEX IF NOT EXISTS "TrackLyrics_trackId_idx" ON "TrackLyrics"("trackId");
@@ -0,0 +1,36 @@
+-- Migration: Queue images stored only locally on delete, and skip keys already queued
+-- Sync also queues replaced artist images itself (dmp_common::deletion_queue), so one key can be queued twice
+
+CREATE OR REPLACE FUNCTION queue_artist_image_deletion()
+RETURNS TRIGGER AS $$
+BEGIN
+  IF COALESCE(OLD.image, '') != '' OR COALESCE(OLD."imageUrl", '') != '' THEN
+    INSERT INTO "S3DeletionQueue" (id, "objectKey")
+    SELECT gen_random_uuid()::text, 'artists/' || OLD.slug || '.jpg'
+    WHERE NOT EXISTS (SELECT 1 FROM "S3DeletionQueue" WHERE "objectKey" = 'artists/' || OLD.slug || '.jpg');
+  END IF;
+  IF COALESCE(OLD."backgroundImage", '') != '' OR COALESCE(OLD."backgroundImageUrl", '') != '' THEN
+    INSERT INTO "S3DeletionQueue" (id, "objectKey")
+    SELECT gen_random_uuid()::text, 'artists/backgrounds/' || OLD.slug || '.jpg'
+    WHERE NOT EXISTS (SELECT 1 FROM "S3DeletionQueue" WHERE "objectKey" = 'artists/backgrounds/' || OLD.slug || '.jpg');
+  END IF;
+  IF COALESCE(OLD."logoImage", '') != '' OR COALESCE(OLD."logoImageUrl", '') != '' THEN
+    INSERT INTO "S3DeletionQueue" (id, "objectKey")
+    SELECT gen_random_uuid()::text, 'artists/logos/' || OLD.slug || '.png'
+    WHERE NOT EXISTS (SELECT 1 FROM "S3DeletionQueue" WHERE "objectKey" = 'artists/logos/' || OLD.slug || '.png');
+  END IF;
+  RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION queue_release_image_deletion()
+RETURNS TRIGGER AS $$
+BEGIN
+  IF COALESCE(OLD.image, '') != '' OR COALESCE(OLD."imageUrl", '') != '' THEN
+    INSERT INTO "S3DeletionQueue" (id, "objectKey")
+    SELECT gen_random_uuid()::text, 'releases/' || OLD.id || '.jpg'
+    WHERE NOT EXISTS (SELECT 1 FROM "S3DeletionQueue" WHERE "objectKey" = 'releases/' || OLD.id || '.jpg');
+  END IF;
+  RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,28 @@
+-- Migration: Store YouTube / SoundCloud / Bandcamp links under their own ArtistUrl type
+-- Sync now recognizes these by host; rows saved earlier under generic MB relation types are retyped here
+
+-- The same link under two generic types would collide once retyped: keep one
+DELETE FROM "ArtistUrl" u
+USING "ArtistUrl" o
+WHERE o."artistId" = u."artistId" AND o.url = u.url AND o.id < u.id
+  AND u.type NOT IN ('youtube', 'soundcloud', 'bandcamp')
+  AND o.type NOT IN ('youtube', 'soundcloud', 'bandcamp')
+  AND u.url ~* '^https?://([a-z0-9-]+\.)*(youtube\.com|youtu\.be|soundcloud\.com|bandcamp\.com)(/|$)';
+
+-- Links already stored under a channel type (e.g. MB's own "youtube" relation) are dropped as duplicates
+DELETE FROM "ArtistUrl" u
+USING "ArtistUrl" o
+WHERE o."artistId" = u."artistId" AND o.url = u.url
+  AND o.type IN ('youtube', 'soundcloud', 'bandcamp')
+  AND u.type NOT IN ('youtube', 'soundcloud', 'bandcamp')
+  AND u.url ~* '^https?://([a-z0-9-]+\.)*(youtube\.com|youtu\.be|soundcloud\.com|bandcamp\.com)(/|$)';
+
+UPDATE "ArtistUrl"
+SET type = CASE
+      WHEN url ~* '^https?://([a-z0-9-]+\.)*(youtube\.com|youtu\.be)(/|$)' THEN 'youtube'
+      WHEN url ~* '^https?://([a-z0-9-]+\.)*soundcloud\.com(/|$)' THEN 'soundcloud'
+      ELSE 'bandcamp'
+    END,
+    "updatedAt" = NOW()
+WHERE type NOT IN ('youtube', 'soundcloud', 'bandcamp')
+  AND url ~* '^https?://([a-z0-9-]+\.)*(youtube\.com|youtu\.be|soundcloud\.com|bandcamp\.com)(/|$)';
@@ -12,6 +12,10 @@ export default defineEventHandler(async (event) => {
       slug: true,
       image: true,
       imageUrl: true,
+      backgroundImage: true,
+      backgroundImageUrl: true,
+      logoImage: true,
+      logoImageUrl: true,
       musicbrainzId: true,
       averageMatchScore: true,
       totalPlayCount: true,
@@ -19,7 +23,10 @@ export default defineEventHandler(async (event) => {
       totalFileSize: true,
       lastSyncedAt: true,
       genres: { select: { id: true, name: true } },
-      urls: { select: { id: true, type: true, url: true } },
+      urls: {
+        where: { status: { not: 'DEAD' } },
+        select: { id: true, type: true, url: true },
+      },
       mbReleases: {
         select: {
           id: true,
@@ -27,6 +34,7 @@ export default defineEventHandler(async (event) => {
           year: true,
           musicbrainzId: true,
           status: true,
+          popularity: true,
           type: { select: { name: true, slug: true } },
           tracks: { select: { id: true } },
           localReleases: {
@@ -35,6 +43,7 @@ export default defineEventHandler(async (event) => {
               title: true,
               image: true,
               imageUrl: true,
+              matchScore: true,
               tracks: { select: { id: true } },
             },
           },
@@ -62,14 +71,20 @@ export default defineEventHandler(async (event) => {
       id: true,
       title: true,
       year: true,
+      originalYear: true,
+      yearInferred: true,
       image: true,
       imageUrl: true,
       matchStatus: true,
+      matchScore: true,
       releaseId: true,
       tracks: { select: { id: true } },
     },
-    orderBy: [{ year: 'asc' }, { title: 'asc' }],
   })
+  // By year; a remaster by its original release year, not the edition's
+  localReleases.sort((a, b) =>
+    (a.originalYear ?? a.year ?? Infinity) - (b.originalYear ?? b.year ?? Infinity)
+    || a.title.localeCompare(b.title))
 
   // Index local releases by their MB releaseId for quick lookup
   const localByMbId = new Map<string, typeof localReleases[number]>()
@@ -90,10 +105,13 @@ export default defineEventHandler(async (event) => {
       id: mbr.id,
       title: mbr.title,
       year: mbr.year,
+      yearInferred: false,
       type: mbr.type.name,
       typeSlug: mbr.type.slug,
       musicbrainzId: mbr.musicbrainzId,
       status: mbr.status,
+      popularity: mbr.popularity,
+      matchScore: localRelease?.matchScore ?? null,
       image: localRelease?.image || null,
       imageUrl: localRelease?.imageUrl || null,
       trackCount: mbr.tracks.length,
@@ -115,11 +133,14 @@ export default defineEventHandler(async (event) => {
     releases.push({
       id: lr.id,
       title: lr.title,
-      year: lr.year,
+      year: lr.originalYear ?? lr.year,
+      yearInferred: !lr.originalYear && lr.yearInferred,
       type: 'Unmatched',
       typeSlug: 'unmatched',
       musicbrainzId: null,
       status: lr.matchStatus,
+      popularity: null,
+      matchScore: lr.matchScore,
       image: lr.image,
       imageUrl: lr.imageUrl,
       trackCount: 0,
@@ -139,11 +160,14 @@ export default defineEventHandler(async (event) => {
     releases.push({
       id: lr.id,
       title: lr.title,
-      year: lr.year,
+      year: lr.originalYear ?? lr.year,
+      yearInferred: !lr.originalYear && lr.yearInferred,
       type: 'Appears On',
       typeSlug: 'appears-on',
       musicbrainzId: null,
       status: lr.matchStatus,
+      popularity: null,
+      matchScore: lr.matchScore,
       image: lr.image,
       imageUrl: lr.imageUrl,
       trackCount: 0,
@@ -8,7 +8,7 @@ export default defineEventHandler(async (event) => {
 
   const track = await prisma.localReleaseTrack.findUnique({
     where: { id },
-    select: { filePath: true },
+    select: { filePath: true, cueStart: true },
   })
 
   if (!track) throw createError({ statusCode: 404, statusMessage: 'Track not found' })
@@ -16,7 +16,9 @@ export default defineEventHandler(async (event) => {
   const musicDir = useRuntimeConfig().musicDir
   if (!musicDir) throw createError({ statusCode: 500, statusMessage: 'MUSIC_DIR not configured' })
 
-  const filePath = join(musicDir, track.filePath)
+  // Cue tracks ("<rip>#NN") stream their whole rip
+  const relativePath = track.cueStart === null ? track.filePath : track.filePath.replace(/#\d+$/, '')
+  const filePath = join(musicDir, relativePath)
 
   let stat
   try {
@@ -5,7 +5,7 @@ export default defineEventHandler(async () => {
     select: {
       id: true,
       name: true,
-      _count: { select: { artists: true } },
+      _count: { select: { artists: true, releases: true } },
     },
     orderBy: { name: 'asc' },
   })
@@ -14,5 +14,6 @@ export default defineEventHandler(async () => {
     id: g.id,
     name: g.name,
     artistCount: g._count.artists,
+    releaseCount: g._count.releases,
   }))
 })
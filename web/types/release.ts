@@ -2,10 +2,15 @@ export interface UnifiedRelease {
   id: string
   title: string
   year: number | null
+  yearInferred: boolean
   type: string
   typeSlug: string
   musicbrainzId: string | null
   status: ReleaseStatus
+  /** MusicBrainz rating and tag votes combined by sync (higher = more popular); null for releases not on MusicBrainz */
+  popularity: number | null
+  /** Share of the MB release's tracks found locally (0–1), null until sync has matched it */
+  matchScore: number | null
   image: string | null
   imageUrl: string | null
   trackCount: number